@@ -0,0 +1,192 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use colored::Colorize;
+use pgx_utils::{exit_with_error, handle_result, run_pg_config};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Sanity check a freshly-built extension library against the Postgres it's about to be installed
+/// into:  it must export `Pg_magic_func`, have been compiled for the same major version, and not
+/// need any Postgres symbols that version doesn't provide.
+///
+/// These checks rely on binutils (`nm`, `objdump`) and are only performed on Linux
+pub(crate) fn check_abi(shlib: &PathBuf, pg_config: &Option<String>, major_version: u16) {
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+
+    status!(
+        "{} ABI of `{}`",
+        "    Checking".bold().green(),
+        shlib.display()
+    );
+
+    let defined = match nm(shlib, "--defined-only") {
+        Some(defined) => defined,
+        None => {
+            status!(
+                "{} `nm` is unavailable, skipping ABI checks",
+                "     Warning".bold().yellow()
+            );
+            return;
+        }
+    };
+    let defined = defined
+        .into_iter()
+        .map(|(name, address, _)| (name, address))
+        .collect::<Vec<_>>();
+
+    if !defined.iter().any(|(name, _)| name == "Pg_magic_func") {
+        exit_with_error!(
+            "`{}` does not export `Pg_magic_func`.  Did you forget `pg_module_magic!()`?",
+            shlib.display()
+        )
+    }
+
+    if let Some((_, address)) = defined
+        .iter()
+        .find(|(name, _)| name == "PGX_PG_VERSION_NUM")
+    {
+        if let Some(version_num) = read_u32(shlib, address) {
+            let built_for = (version_num / 10000) as u16;
+            if built_for != major_version {
+                exit_with_error!(
+                    "`{}` was built for Postgres {} but is being installed into Postgres {}",
+                    shlib.display(),
+                    built_for,
+                    major_version
+                )
+            }
+        }
+    }
+
+    check_undefined_symbols(shlib, pg_config, major_version);
+}
+
+/// Every symbol the library needs must come from either the libraries it links against or
+/// the `postgres` executable itself
+fn check_undefined_symbols(shlib: &PathBuf, pg_config: &Option<String>, major_version: u16) {
+    let undefined = match nm(shlib, "--undefined-only") {
+        Some(undefined) => undefined,
+        None => return,
+    };
+
+    let mut provided = HashSet::new();
+    let mut postgres = PathBuf::from(run_pg_config(pg_config, "--bindir"));
+    postgres.push("postgres");
+    for library in std::iter::once(postgres).chain(linked_libraries(shlib)) {
+        if let Some(symbols) = nm(&library, "--defined-only") {
+            provided.extend(symbols.into_iter().map(|(name, _, _)| name));
+        }
+    }
+
+    let mut missing = undefined
+        .into_iter()
+        // weak symbols are allowed to be missing
+        .filter(|(_, _, kind)| kind != "w" && kind != "v")
+        .map(|(name, _, _)| name)
+        .filter(|name| !provided.contains(name))
+        .collect::<Vec<_>>();
+    missing.sort();
+    missing.dedup();
+
+    if !missing.is_empty() {
+        exit_with_error!(
+            "`{}` uses symbols that don't exist in Postgres {}:\n    {}",
+            shlib.display(),
+            major_version,
+            missing.join("\n    ")
+        )
+    }
+}
+
+/// Returns `(name, address, kind)` for each dynamic symbol matching `filter`, or `None` if `nm`
+/// couldn't be run
+fn nm(file: &PathBuf, filter: &str) -> Option<Vec<(String, String, String)>> {
+    let output = Command::new("nm")
+        .arg("-D")
+        .arg(filter)
+        .arg(file)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                // defined symbols are `address kind name`, undefined ones are just `kind name`
+                let parts = line.split_whitespace().collect::<Vec<_>>();
+                match parts.as_slice() {
+                    [address, kind, name] => {
+                        Some((strip_version(name), address.to_string(), kind.to_string()))
+                    }
+                    [kind, name] => Some((strip_version(name), String::new(), kind.to_string())),
+                    _ => None,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// `nm` reports versioned symbols as `name@VERSION`, but we only care about the name
+fn strip_version(name: &str) -> String {
+    name.split('@').next().unwrap_or(name).to_string()
+}
+
+fn linked_libraries(shlib: &PathBuf) -> Vec<PathBuf> {
+    let output = handle_result!(
+        format!("failed to run ldd on {}", shlib.display()),
+        Command::new("ldd").arg(shlib).output()
+    );
+
+    // lines look like `libc.so.6 => /lib/x86_64-linux-gnu/libc.so.6 (0x00007f...)`
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            line.split_whitespace()
+                .find(|part| part.starts_with('/'))
+                .map(PathBuf::from)
+        })
+        .collect()
+}
+
+/// Read the (little-endian) 4-byte word at `address` in `shlib`
+fn read_u32(shlib: &PathBuf, address: &str) -> Option<u32> {
+    let start = u64::from_str_radix(address, 16).ok()?;
+    let output = Command::new("objdump")
+        .arg("-s")
+        .arg(format!("--start-address=0x{:x}", start))
+        .arg(format!("--stop-address=0x{:x}", start + 4))
+        .arg(shlib)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // the dump line looks like ` 1f2e0 0cd40100                             ....`
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|line| {
+        line.split_whitespace()
+            .next()
+            .and_then(|addr| u64::from_str_radix(addr, 16).ok())
+            == Some(start)
+    })?;
+    let hex = line.split_whitespace().nth(1)?;
+    if hex.len() != 8 {
+        return None;
+    }
+
+    let mut bytes = [0u8; 4];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(u32::from_le_bytes(bytes))
+}
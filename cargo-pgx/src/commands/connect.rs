@@ -0,0 +1,28 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use crate::commands::run::exec_psql;
+use crate::commands::status::status_postgres;
+use pgx_utils::exit_with_error;
+
+/// Replace ourselves with `psql` connected to `dbname` on the pgx-managed Postgres
+/// `major_version`, as `user` if given, passing it `psql_args` as-is.  Nothing is built, installed,
+/// or started, so the only output is psql's own, which makes this usable from scripts
+pub(crate) fn connect_psql(
+    major_version: u16,
+    dbname: &str,
+    user: Option<&str>,
+    psql_args: &[&str],
+) {
+    if !status_postgres(major_version) {
+        exit_with_error!(
+            "Postgres v{} isn't running.  Start it with `{}`",
+            major_version,
+            format!("cargo pgx start pg{}", major_version)
+                .bold()
+                .yellow()
+        )
+    }
+
+    exec_psql(major_version, dbname, user, psql_args);
+}
@@ -1,7 +1,10 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
+use crate::commands::workspace::member_hint;
 use pgx_utils::{exit_with_error, handle_result};
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
@@ -13,11 +16,45 @@ pub fn get_property(name: &str) -> Option<String> {
         return Some(extname);
     }
 
+    read_properties(&control_file)
+        .into_iter()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v)
+}
+
+/// Every property in the control file, in the order they appear, followed by the values
+/// `cargo pgx install` derives from them:  `extname`, the `sql_filename` of the schema it
+/// installs, and the `library_filename` it installs the shared library as
+pub(crate) fn get_properties() -> Vec<(String, String)> {
+    let (control_file, extname) = find_control_file();
+    let mut properties = read_properties(&control_file);
+
+    let default_version = properties
+        .iter()
+        .find(|(k, _)| k == "default_version")
+        .map(|(_, v)| v.clone());
+    properties.push(("extname".to_string(), extname.clone()));
+    if let Some(version) = default_version {
+        properties.push((
+            "sql_filename".to_string(),
+            format!("{}--{}.sql", extname, version),
+        ));
+    }
+    properties.push(("library_filename".to_string(), format!("{}.so", extname)));
+    properties
+}
+
+fn read_properties(control_file: &PathBuf) -> Vec<(String, String)> {
     let control_file = File::open(control_file).unwrap();
     let reader = BufReader::new(control_file);
+    let mut properties = Vec::new();
 
     for line in reader.lines() {
         let line = line.unwrap();
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
         let parts: Vec<&str> = line.split('=').collect();
 
         if parts.len() != 2 {
@@ -25,15 +62,12 @@ pub fn get_property(name: &str) -> Option<String> {
         }
 
         let (k, v) = (parts.get(0).unwrap().trim(), parts.get(1).unwrap().trim());
-
-        if k == name {
-            let v = v.trim_start_matches('\'');
-            let v = v.trim_end_matches('\'');
-            return Some(v.trim().to_string());
-        }
+        let v = v.trim_start_matches('\'');
+        let v = v.trim_end_matches('\'');
+        properties.push((k.to_string(), v.trim().to_string()));
     }
 
-    None
+    properties
 }
 
 pub(crate) fn find_control_file() -> (PathBuf, String) {
@@ -54,5 +88,104 @@ pub(crate) fn find_control_file() -> (PathBuf, String) {
         }
     }
 
-    exit_with_error!("control file not found in current directory")
+    match member_hint() {
+        Some(hint) => exit_with_error!("control file not found in current directory: {}", hint),
+        None => exit_with_error!("control file not found in current directory"),
+    }
+}
+
+/// The `[package.metadata.pgx]` table from the extension's `Cargo.toml`
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PgxMetadata {
+    /// Additional shared libraries to install into `pg_config --pkglibdir`
+    #[serde(default)]
+    pub libraries: Vec<PathBuf>,
+
+    /// Data files (or directories of them) to install into `pg_config --sharedir`/extname/
+    #[serde(default)]
+    pub data: Vec<PathBuf>,
+
+    /// Documentation files (or directories of them) to install into `pg_config --docdir`/extension/
+    #[serde(default)]
+    pub docs: Vec<PathBuf>,
+
+    /// Shared libraries, besides the usual system libraries, that a `--static` package may link
+    #[serde(default)]
+    pub allowed_libraries: Vec<String>,
+
+    /// Settings for the `postgresql.conf` of the Postgres that `cargo pgx run` and `cargo pgx test`
+    /// use, such as `shared_preload_libraries`
+    #[serde(default)]
+    pub postgresql_conf: BTreeMap<String, toml::Value>,
+
+    /// Where the generated `*.generated.sql` files go, instead of `./sql/`.  Hand-written SQL
+    /// files always live in `./sql/`
+    #[serde(default)]
+    pub schema_dir: Option<PathBuf>,
+
+    /// Generate a file for each Rust module, rather than for each source file, so the `mod`s
+    /// within a file get files of their own
+    #[serde(default)]
+    pub split_schema: bool,
+
+    /// Roles granted `EXECUTE` on every function the generated schema creates.  They must exist
+    /// before `CREATE EXTENSION`
+    #[serde(default)]
+    pub grant_execute_to: Vec<String>,
+
+    /// Revoke `PUBLIC`'s default `EXECUTE` on every function the generated schema creates
+    #[serde(default)]
+    pub revoke_public: bool,
+
+    /// The `search_path` every `#[pg_extern]` function is created with, such as
+    /// `"pg_catalog, pg_temp"`, unless it has a `set_search_path` of its own
+    #[serde(default)]
+    pub function_search_path: Option<String>,
+
+    /// Roles the extension's schema creates, if they don't already exist, by name
+    #[serde(default)]
+    pub roles: BTreeMap<String, PgxRole>,
+}
+
+/// A role in `[package.metadata.pgx.roles]`
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PgxRole {
+    /// Create it with `LOGIN`, rather than as a group role
+    #[serde(default)]
+    pub login: bool,
+
+    /// The roles it's made a member of, which must already exist or be declared as well
+    #[serde(default)]
+    pub member_of: Vec<String>,
+
+    /// Grant it `EXECUTE` on every function the generated schema creates, as if it were listed
+    /// in `grant_execute_to`
+    #[serde(default)]
+    pub grant_execute: bool,
+}
+
+/// The extension's `Cargo.toml`
+pub(crate) fn get_cargo_toml() -> toml::Value {
+    let cargo_toml = handle_result!(
+        "unable to read Cargo.toml",
+        std::fs::read_to_string("Cargo.toml")
+    );
+    handle_result!(
+        "Cargo.toml is invalid",
+        toml::from_str::<toml::Value>(&cargo_toml)
+    )
+}
+
+pub(crate) fn get_pgx_metadata() -> PgxMetadata {
+    match get_cargo_toml()
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("pgx"))
+    {
+        Some(pgx) => handle_result!(
+            "[package.metadata.pgx] in Cargo.toml is invalid",
+            pgx.clone().try_into::<PgxMetadata>()
+        ),
+        None => PgxMetadata::default(),
+    }
 }
@@ -4,17 +4,19 @@
 
 use colored::Colorize;
 use pgx_utils::{
-    exit_with_error, get_pgx_config_path, get_pgx_home, handle_result, prefix_path,
-    BASE_POSTGRES_PORT_NO,
+    exit_with_error, get_pg_config_major_version, get_pgx_config_path, get_pgx_home, handle_result,
+    parse_pg_release, prefix_path, BASE_POSTGRES_PORT_NO,
 };
 use rayon::prelude::*;
 use rttp_client::{ HttpClient, types::Proxy };
+use sha2::{Digest, Sha256};
 use std::fmt::Display;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use syn::export::Formatter;
 
 static PROCESS_ENV_DENYLIST: &'static [&'static str] = &[
@@ -32,16 +34,33 @@ static PROCESS_ENV_DENYLIST: &'static [&'static str] = &[
     "LIBRARY_PATH", // see https://github.com/zombodb/pgx/issues/16
 ];
 
-#[derive(Debug)]
+/// Flags every Postgres we build is configured with, besides its install prefix and port
+static CONFIGURE_FLAGS: &'static [&'static str] = &["--enable-debug", "--enable-cassert"];
+
+/// Written into a cached Postgres tree once it's completely built and installed
+static CACHE_COMPLETE_MARKER: &'static str = ".pgx-complete";
+
+/// How long a cache lock can be held before we assume the job holding it died.  Building a
+/// single Postgres, even with contrib, takes a fraction of this
+const CACHE_LOCK_STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone)]
 struct PgVersion {
     major: u16,
     minor: u16,
-    url: &'static str,
 }
 
 impl PgVersion {
-    pub const fn new(major: u16, minor: u16, url: &'static str) -> Self {
-        PgVersion { major, minor, url }
+    pub const fn new(major: u16, minor: u16) -> Self {
+        PgVersion { major, minor }
+    }
+
+    /// Where postgresql.org publishes this release's source tarball
+    fn url(&self) -> String {
+        format!(
+            "https://ftp.postgresql.org/pub/source/v{0}.{1}/postgresql-{0}.{1}.tar.bz2",
+            self.major, self.minor
+        )
     }
 
     fn port(&self) -> u16 {
@@ -59,83 +78,348 @@ impl Display for PgVersion {
     }
 }
 
-const PG10_VERSION: PgVersion = PgVersion::new(
-    10,
-    13,
-    "https://ftp.postgresql.org/pub/source/v10.13/postgresql-10.13.tar.bz2",
-);
-const PG11_VERSION: PgVersion = PgVersion::new(
-    11,
-    8,
-    "https://ftp.postgresql.org/pub/source/v11.8/postgresql-11.8.tar.bz2",
-);
-const PG12_VERSION: PgVersion = PgVersion::new(
-    12,
-    3,
-    "https://ftp.postgresql.org/pub/source/v12.3/postgresql-12.3.tar.bz2",
-);
+const PG10_VERSION: PgVersion = PgVersion::new(10, 13);
+const PG11_VERSION: PgVersion = PgVersion::new(11, 8);
+const PG12_VERSION: PgVersion = PgVersion::new(12, 3);
+const PG13_VERSION: PgVersion = PgVersion::new(13, 9);
+const PG14_VERSION: PgVersion = PgVersion::new(14, 6);
+const PG15_VERSION: PgVersion = PgVersion::new(15, 1);
 
 pub(crate) fn init_pgx(
     pg10_config: Option<&str>,
     pg11_config: Option<&str>,
     pg12_config: Option<&str>,
+    pg13_config: Option<&str>,
+    pg14_config: Option<&str>,
+    pg15_config: Option<&str>,
+    with_contrib: bool,
+    cache_dir: Option<PathBuf>,
+    jobs: Option<usize>,
 ) -> std::result::Result<(), std::io::Error> {
     let dir = get_pgx_home();
+    if let Some(cache_dir) = &cache_dir {
+        handle_result!(
+            format!("creating {}", cache_dir.display()),
+            std::fs::create_dir_all(cache_dir)
+        );
+    }
 
     let input_configs = vec![
         (pg10_config, &PG10_VERSION),
         (pg11_config, &PG11_VERSION),
         (pg12_config, &PG12_VERSION),
+        (pg13_config, &PG13_VERSION),
+        (pg14_config, &PG14_VERSION),
+        (pg15_config, &PG15_VERSION),
     ];
     let output_configs = Arc::new(Mutex::new(Vec::new()));
 
-    input_configs
-        .into_par_iter()
-        .for_each(|(pg_config, version)| {
-            let pg_config = pg_config.map_or_else(
-                || download_postgres(version, &dir),
-                |v| PathBuf::from_str(v).unwrap(),
-            );
-
-            let mut mutex = output_configs.lock();
-            let output_configs = mutex.as_mut().expect("failed to get output_configs lock");
+    // by default every version is built at once, each `make` with a third of the CPUs, as much
+    // of each build is spent downloading and configuring rather than compiling
+    let (concurrent, make_jobs) = match jobs {
+        Some(jobs) => (jobs, 1.max(num_cpus::get() / jobs)),
+        None => (input_configs.len(), 1.max(num_cpus::get() / 3)),
+    };
+    let pool = handle_result!(
+        "failed to start the build threads",
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(concurrent)
+            .build()
+    );
 
-            output_configs.push((pg_config, version));
-        });
+    pool.install(|| {
+        input_configs
+            .into_par_iter()
+            .for_each(|(pg_config, default_version)| {
+                let fetch = |version: &PgVersion| match &cache_dir {
+                    Some(cache_dir) => cached_postgres(version, cache_dir, with_contrib, make_jobs),
+                    None => download_postgres(version, &dir, with_contrib, make_jobs),
+                };
+
+                // `--pg12=12.6` pins an exact release, while anything else is a path to `pg_config`
+                let (pg_config, version, pinned) = match pg_config {
+                    Some(value) => match parse_pinned_version(value, default_version) {
+                        Some(version) => (fetch(&version), version, true),
+                        None => (existing_pg_config(value), default_version.clone(), false),
+                    },
+                    None => (fetch(default_version), default_version.clone(), false),
+                };
+
+                let mut mutex = output_configs.lock();
+                let output_configs = mutex.as_mut().expect("failed to get output_configs lock");
+
+                output_configs.push((pg_config, version, pinned));
+            });
+    });
 
     let mut mutex = output_configs.lock();
     let output_configs = mutex.as_mut().unwrap();
 
-    output_configs.sort_by(|(_, a), (_, b)| a.major.cmp(&b.major));
-    for (pg_config, version) in output_configs.iter() {
+    output_configs.sort_by(|(_, a, _), (_, b, _)| a.major.cmp(&b.major));
+    for (pg_config, version, _) in output_configs.iter() {
         validate_pg_config(pg_config, version);
     }
 
     write_config(output_configs)
 }
 
-fn download_postgres(version: &PgVersion, pgxdir: &PathBuf) -> PathBuf {
-    println!(
-        "{} {} from {}",
-        " Downloading".bold().green(),
-        version,
-        version.url
+/// The exact release `value` names, like `12.6` for `--pg12=12.6`, or `None` if it's a path to
+/// `pg_config` instead
+fn parse_pinned_version(value: &str, default_version: &PgVersion) -> Option<PgVersion> {
+    let (major, minor) = parse_pg_release(value)?;
+    if major != default_version.major {
+        exit_with_error!(
+            "--{}={} pins a Postgres {} release, not Postgres {}",
+            default_version.label(),
+            value,
+            major,
+            default_version.major
+        )
+    }
+    Some(PgVersion::new(major, minor))
+}
+
+/// The `pg_config` of a Postgres that's already installed, such as from the operating system's
+/// packages, made absolute so `config.toml` works from any directory
+fn existing_pg_config(value: &str) -> PathBuf {
+    let pg_config = PathBuf::from_str(value).unwrap();
+    if !pg_config.is_file() {
+        exit_with_error!("`{}` does not exist", pg_config.display())
+    }
+
+    // not canonicalized, as Debian's /usr/bin/pg_config is a symlink to a wrapper script that
+    // behaves differently depending on the name it's run as
+    if pg_config.is_relative() {
+        let mut absolute = handle_result!(
+            "unable to determine the current directory",
+            std::env::current_dir()
+        );
+        absolute.push(pg_config);
+        absolute
+    } else {
+        pg_config
+    }
+}
+
+fn download_postgres(
+    version: &PgVersion,
+    pgxdir: &PathBuf,
+    with_contrib: bool,
+    make_jobs: usize,
+) -> PathBuf {
+    let mut pgdir = pgxdir.clone();
+    pgdir.push(format!("{}.{}", version.major, version.minor));
+    build_postgres(version, &pgdir, with_contrib, make_jobs)
+}
+
+/// Reuse the Postgres tree an earlier `cargo pgx init --cache-dir` built in `cache_dir` from the
+/// same inputs, or build it there for next time.  Postgres can't be moved once it's installed,
+/// so `config.toml` points straight into the cache, which must therefore be restored to the same
+/// path on every run
+fn cached_postgres(
+    version: &PgVersion,
+    cache_dir: &PathBuf,
+    with_contrib: bool,
+    make_jobs: usize,
+) -> PathBuf {
+    let key = format!(
+        "{}.{}-{:016x}",
+        version.major,
+        version.minor,
+        cache_key(version, with_contrib)
     );
+    let mut pgdir = cache_dir.clone();
+    pgdir.push(&key);
+    let mut complete = pgdir.clone();
+    complete.push(CACHE_COMPLETE_MARKER);
+
+    if complete.exists() {
+        println!(
+            "{} {} from {}",
+            "      Cached".bold().green(),
+            version,
+            pgdir.display()
+        );
+    } else {
+        let _lock = CacheLock::acquire(cache_dir, &key);
+
+        // another job may have built it while we waited for the lock
+        if complete.exists() {
+            println!(
+                "{} {} from {}",
+                "      Cached".bold().green(),
+                version,
+                pgdir.display()
+            );
+        } else {
+            build_postgres(version, &pgdir, with_contrib, make_jobs);
+            handle_result!(
+                format!("creating {}", complete.display()),
+                File::create(&complete)
+            );
+        }
+    }
+
+    let mut pg_config = get_pg_installdir(&pgdir);
+    pg_config.push("bin");
+    pg_config.push("pg_config");
+    pg_config
+}
+
+/// A hash of everything that goes into building `version`, which names its tree in the cache.
+/// This is FNV-1a, rather than `std`'s `DefaultHasher`, because it must not change from one
+/// Rust release to the next
+fn cache_key(version: &PgVersion, with_contrib: bool) -> u64 {
+    let mut inputs = vec![
+        version.url(),
+        version.port().to_string(),
+        with_contrib.to_string(),
+        std::env::consts::OS.to_string(),
+        std::env::consts::ARCH.to_string(),
+    ];
+    inputs.extend(CONFIGURE_FLAGS.iter().map(|flag| flag.to_string()));
+
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in inputs.join("\0").bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Stops concurrent CI jobs that share a cache directory from building the same Postgres into it
+/// at once.  The lock is a file created with `create_new`, and it's removed when dropped
+struct CacheLock {
+    path: PathBuf,
+}
+
+impl CacheLock {
+    fn acquire(cache_dir: &PathBuf, key: &str) -> Self {
+        let mut path = cache_dir.clone();
+        path.push(format!("{}.lock", key));
+
+        let mut waiting = false;
+        loop {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    // only for the benefit of whoever finds a stale lock
+                    let _ = write!(file, "{}", std::process::id());
+                    return CacheLock { path };
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let age = std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok());
+                    match age {
+                        Some(age) if age > CACHE_LOCK_STALE_AFTER => {
+                            println!(
+                                "{} stale lock {}",
+                                "    Removing".bold().yellow(),
+                                path.display()
+                            );
+                            let _ = std::fs::remove_file(&path);
+                        }
+                        _ => {
+                            if !waiting {
+                                println!(
+                                    "{} for another job to finish building into {}",
+                                    "     Waiting".bold().yellow(),
+                                    cache_dir.display()
+                                );
+                                waiting = true;
+                            }
+                            std::thread::sleep(Duration::from_secs(5));
+                        }
+                    }
+                }
+                Err(e) => exit_with_error!("failed to create {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn build_postgres(
+    version: &PgVersion,
+    pgdir: &PathBuf,
+    with_contrib: bool,
+    make_jobs: usize,
+) -> PathBuf {
+    let url = version.url();
+    println!("{} {} from {}", " Downloading".bold().green(), version, url);
+    let tarball = download(&url);
+    verify_checksum(version, &url, &tarball);
+    untar(&tarball, pgdir, version);
+    configure_postgres(version, pgdir);
+    make_postgres(version, pgdir, make_jobs);
+    let pg_config = make_install_postgres(version, pgdir, make_jobs); // returns the path to pg_config
+    if with_contrib {
+        make_install_contrib(version, pgdir, make_jobs);
+    }
+    pg_config
+}
+
+fn download(url: &str) -> Vec<u8> {
     let mut http_client = HttpClient::new();
-    http_client.get().url(version.url);
-    if let Some((host, port)) = env_proxy::for_url_str(version.url).host_port() {
+    http_client.get().url(url);
+    if let Some((host, port)) = env_proxy::for_url_str(url).host_port() {
         http_client.proxy(Proxy::https(host, port as u32));
     }
-    let result = handle_result!("", http_client.emit());
-    let pgdir = untar(result.body().binary(), pgxdir, version);
-    configure_postgres(version, &pgdir);
-    make_postgres(version, &pgdir);
-    make_install_postgres(version, &pgdir) // returns the path to pg_config
+    let result = handle_result!(format!("downloading {}", url), http_client.emit());
+    result.body().binary().to_vec()
 }
 
-fn untar(bytes: &[u8], pgxdir: &PathBuf, version: &PgVersion) -> PathBuf {
-    let mut pgdir = pgxdir.clone();
-    pgdir.push(format!("{}.{}", version.major, version.minor));
+/// Check `tarball` against the sha256 checksum postgresql.org publishes next to it, so we never
+/// build from a truncated download or a tarball that isn't the release we asked for
+fn verify_checksum(version: &PgVersion, url: &str, tarball: &[u8]) {
+    let checksum_url = format!("{}.sha256", url);
+    let checksums = download(&checksum_url);
+
+    // the file looks like `sha256sum` output: `<checksum>  postgresql-12.6.tar.bz2`
+    let expected = String::from_utf8_lossy(&checksums)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        exit_with_error!(
+            "couldn't find a sha256 checksum for {} at {}.  Is {}.{} a real Postgres release?",
+            version,
+            checksum_url,
+            version.major,
+            version.minor
+        )
+    }
+
+    let actual = format!("{:x}", Sha256::digest(tarball));
+    if actual != expected {
+        exit_with_error!(
+            "{} failed checksum verification: {} should have sha256 {}, but it's {}",
+            version,
+            url,
+            expected,
+            actual
+        )
+    }
+    println!(
+        "{} {} sha256 {}",
+        "    Verified".bold().green(),
+        version,
+        actual
+    );
+}
+
+fn untar(bytes: &[u8], pgdir: &PathBuf, version: &PgVersion) {
     if pgdir.exists() {
         // delete everything at this path if it already exists
         println!("{} {}", "    Removing".bold().green(), pgdir.display());
@@ -175,8 +459,6 @@ fn untar(bytes: &[u8], pgxdir: &PathBuf, version: &PgVersion) -> PathBuf {
     if !output.status.success() {
         exit_with_error!(String::from_utf8(output.stderr).unwrap())
     }
-
-    pgdir
 }
 
 fn configure_postgres(version: &PgVersion, pgdir: &PathBuf) {
@@ -186,8 +468,7 @@ fn configure_postgres(version: &PgVersion, pgdir: &PathBuf) {
     command
         .arg(format!("--prefix={}", get_pg_installdir(pgdir).display()))
         .arg(format!("--with-pgport={}", version.port()))
-        .arg("--enable-debug")
-        .arg("--enable-cassert")
+        .args(CONFIGURE_FLAGS)
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .stdin(std::process::Stdio::null())
@@ -216,14 +497,13 @@ fn configure_postgres(version: &PgVersion, pgdir: &PathBuf) {
     }
 }
 
-fn make_postgres(version: &PgVersion, pgdir: &PathBuf) {
-    let num_cpus = 1.max(num_cpus::get() / 3);
+fn make_postgres(version: &PgVersion, pgdir: &PathBuf, make_jobs: usize) {
     println!("{} {}", "   Compiling".bold().green(), version);
     let mut command = std::process::Command::new("make");
 
     command
         .arg("-j")
-        .arg(num_cpus.to_string())
+        .arg(make_jobs.to_string())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .stdin(std::process::Stdio::null())
@@ -252,7 +532,7 @@ fn make_postgres(version: &PgVersion, pgdir: &PathBuf) {
     }
 }
 
-fn make_install_postgres(version: &PgVersion, pgdir: &PathBuf) -> PathBuf {
+fn make_install_postgres(version: &PgVersion, pgdir: &PathBuf, make_jobs: usize) -> PathBuf {
     println!(
         "{} {} to {}",
         "  Installing".bold().green(),
@@ -262,6 +542,8 @@ fn make_install_postgres(version: &PgVersion, pgdir: &PathBuf) -> PathBuf {
     let mut command = std::process::Command::new("make");
 
     command
+        .arg("-j")
+        .arg(make_jobs.to_string())
         .arg("install")
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
@@ -295,8 +577,61 @@ fn make_install_postgres(version: &PgVersion, pgdir: &PathBuf) -> PathBuf {
     pg_config
 }
 
+fn make_install_contrib(version: &PgVersion, pgdir: &PathBuf, make_jobs: usize) {
+    println!(
+        "{} {} contrib extensions to {}",
+        "  Installing".bold().green(),
+        version,
+        get_pg_installdir(pgdir).display()
+    );
+    let mut command = std::process::Command::new("make");
+
+    command
+        .arg("-j")
+        .arg(make_jobs.to_string())
+        .arg("-C")
+        .arg("contrib")
+        .arg("install")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null())
+        .current_dir(pgdir.display().to_string());
+    for var in PROCESS_ENV_DENYLIST {
+        command.env_remove(var);
+    }
+
+    let command_str = format!("{:?}", command);
+
+    let child = handle_result!(format!("Failed: {:?}", command_str), command.spawn());
+
+    let output = handle_result!(
+        format!("could not receive make's output: {}", command_str),
+        child.wait_with_output()
+    );
+
+    if !output.status.success() {
+        exit_with_error!(format!(
+            "{}\n{}{}",
+            command_str,
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap()
+        ))
+    }
+}
+
 fn validate_pg_config(pg_config: &PathBuf, version: &PgVersion) {
     println!("{} {}", "  Validating".bold().green(), pg_config.display());
+
+    let actual_version = get_pg_config_major_version(&Some(pg_config.display().to_string()));
+    if actual_version != version.major {
+        exit_with_error!(
+            "--{} was given `{}`, but that's Postgres {}",
+            version.label(),
+            pg_config.display(),
+            actual_version
+        )
+    }
+
     let mut command = std::process::Command::new(pg_config);
 
     command
@@ -332,19 +667,46 @@ fn validate_pg_config(pg_config: &PathBuf, version: &PgVersion) {
             includedir.display().to_string().bold().yellow()
         );
     }
+
+    // the directory can exist without the headers in it, depending on how Postgres was packaged
+    let mut postgres_h = includedir.clone();
+    postgres_h.push("postgres.h");
+    if !postgres_h.exists() {
+        exit_with_error!(
+            "`{}` does not exist.  Install the Postgres server development headers, such as the \
+             `postgresql-server-dev-{}` (Debian/Ubuntu) or `postgresql{}-devel` (Red Hat) package",
+            postgres_h.display().to_string().bold().yellow(),
+            version.major,
+            version.major
+        );
+    }
 }
 
-fn write_config(pg_configs: &Vec<(PathBuf, &PgVersion)>) -> Result<(), std::io::Error> {
+fn write_config(pg_configs: &Vec<(PathBuf, PgVersion, bool)>) -> Result<(), std::io::Error> {
     let config_path = get_pgx_config_path();
     let mut file = handle_result!(
         format!("Unable to create {}", config_path.display()),
         File::create(&config_path)
     );
     file.write_all(b"[configs]\n")?;
-    for (pg_config, version) in pg_configs {
+    for (pg_config, version, _) in pg_configs {
         file.write_all(format!("{}=\"{}\"\n", version.label(), pg_config.display()).as_bytes())?;
     }
 
+    // `cargo pgx install` and `cargo pgx test` refuse to use any other release of these
+    file.write_all(b"\n[pins]\n")?;
+    for (_, version, _) in pg_configs.iter().filter(|(_, _, pinned)| *pinned) {
+        file.write_all(
+            format!(
+                "{}=\"{}.{}\"\n",
+                version.label(),
+                version.major,
+                version.minor
+            )
+            .as_bytes(),
+        )?;
+    }
+
     Ok(())
 }
 
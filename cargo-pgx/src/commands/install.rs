@@ -1,90 +1,805 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
-use crate::commands::get::{find_control_file, get_property};
-use crate::commands::schema::read_load_order;
+use crate::commands::abi_check::check_abi;
+use crate::commands::get::{find_control_file, get_pgx_metadata, get_property};
+use crate::commands::output::{event, is_json};
+use crate::commands::schema::{resolve_load_order, SchemaLayout};
+use crate::commands::sql_check::check_sql_syntax;
+use crate::commands::watch::find_files;
 use colored::Colorize;
 use pgx_utils::{
-    exit_with_error, get_pg_config_major_version, get_target_dir, handle_result, run_pg_config,
+    check_pinned_version, exit_with_error, get_pg_config_major_version, get_target_dir,
+    handle_result, run_pg_config,
 };
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::str::FromStr;
+
+/// Optional adjustments made to the extension while it's being installed
+#[derive(Debug, Default, Clone)]
+pub(crate) struct InstallOptions {
+    /// rpath entries to set on the shared library.  `@PKGLIBDIR@` is replaced with Postgres'
+    /// `pg_config --pkglibdir`
+    pub rpaths: Vec<String>,
+
+    /// `(old, new)` pairs of dependent library install names to change (macOS only)
+    pub install_names: Vec<(String, String)>,
+
+    /// Overwrite an existing `extname--version.sql` that has different contents without asking
+    pub force: bool,
+
+    /// Install the schema files already in `./sql/` instead of regenerating them first
+    pub no_schema: bool,
+
+    /// Statically link as much as possible and verify the shared library only links against
+    /// the expected system libraries
+    pub static_link: bool,
+
+    /// If the current user can't write to Postgres' directories, copy files into them with
+    /// `sudo`.  The extension itself is still built as the current user
+    pub sudo: bool,
+
+    /// The target triple to cross-compile the extension for, passed to `cargo build --target`
+    pub target: Option<String>,
+
+    /// Install the shared library here rather than where `pg_config --pkglibdir` says, such as
+    /// when the Postgres on the target host is laid out differently than the local one
+    pub pkglibdir: Option<PathBuf>,
+
+    /// Install the control file, schema, and data files here rather than where
+    /// `pg_config --sharedir` says
+    pub sharedir: Option<PathBuf>,
+
+    /// Install the control file and schema here rather than in the `extension/` directory of
+    /// the sharedir
+    pub extensiondir: Option<PathBuf>,
+
+    /// Install the documentation files, into its `extension/` directory, here rather than where
+    /// `pg_config --docdir` says
+    pub docdir: Option<PathBuf>,
+
+    /// Build into this directory instead of the usual `target/`, so that builds for different
+    /// Postgres versions don't keep invalidating each other
+    pub target_dir: Option<PathBuf>,
+
+    /// Build the extension and generate its schema, but only print the files that would be
+    /// copied, written, patched, or removed, instead of touching them
+    pub dry_run: bool,
+
+    /// Don't check the syntax of the extension's SQL files before installing them
+    pub no_sql_check: bool,
+
+    /// Install the library a previous `cargo build` left in the target directory instead of
+    /// building it again, refusing to if it's missing or older than the sources
+    pub no_build: bool,
+}
+
+/// How `install_extension` changes files under its base directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WriteMode {
+    /// As the current user
+    Direct,
+
+    /// Through `sudo`, because the current user can't write to Postgres' directories
+    Sudo,
+
+    /// Not at all, just say what would be done (`--dry-run`)
+    DryRun,
+}
+
+impl WriteMode {
+    /// The status label for an action:  `label`, or `would` for a dry run
+    fn label(self, label: &'static str, would: &'static str) -> colored::ColoredString {
+        match self {
+            WriteMode::DryRun => would.bold().cyan(),
+            _ => label.bold().green(),
+        }
+    }
+
+    /// The `--json` event for an action:  `name`, or `would` for a dry run
+    fn event_name(self, name: &'static str, would: &'static str) -> &'static str {
+        match self {
+            WriteMode::DryRun => would,
+            _ => name,
+        }
+    }
+}
+
+/// System libraries a statically-linked extension is still expected to link against
+static STATIC_ALLOWED_LIBRARIES: &[&str] = &[
+    "linux-vdso",
+    "linux-gate",
+    "ld-linux",
+    "libc",
+    "libm",
+    "libdl",
+    "libpthread",
+    "librt",
+];
 
 pub(crate) fn install_extension(
     pg_config: &Option<String>,
     is_release: bool,
     base_directory: Option<PathBuf>,
+    options: &InstallOptions,
 ) {
-    let base_directory = base_directory.unwrap_or("/".into());
+    // packagers stage an install with `DESTDIR`, the same as for `make install`
+    let base_directory = base_directory
+        .or_else(|| {
+            std::env::var_os("DESTDIR")
+                .filter(|destdir| !destdir.is_empty())
+                .map(PathBuf::from)
+        })
+        .unwrap_or("/".into());
     let (control_file, extname) = find_control_file();
     let major_version = get_pg_config_major_version(pg_config);
+    status!(
+        "{} Postgres {} (`{}`)",
+        "   Targeting".bold().green(),
+        major_version,
+        pg_config.as_deref().unwrap_or("pg_config")
+    );
+    check_pinned_version(pg_config);
+    let pkglibdir = options
+        .pkglibdir
+        .clone()
+        .unwrap_or_else(|| get_pkglibdir(pg_config));
+    let pkgdir = make_relative(pkglibdir.clone());
+    let sharedir = options
+        .sharedir
+        .clone()
+        .unwrap_or_else(|| get_sharedir(pg_config));
+    let extdir = make_relative(
+        options
+            .extensiondir
+            .clone()
+            .unwrap_or_else(|| get_extensiondir(&sharedir)),
+    );
+    let sharedir = make_relative(sharedir);
+    let metadata = get_pgx_metadata();
+
+    // only ask `pg_config` where documentation goes if there's some to install
+    let docdir = if metadata.docs.is_empty() {
+        None
+    } else {
+        Some(make_relative(get_extensiondir(
+            &options
+                .docdir
+                .clone()
+                .unwrap_or_else(|| get_docdir(pg_config)),
+        )))
+    };
+
+    // decide this before building, so a missing `--sudo` doesn't cost a full compile
+    let mode = if options.dry_run {
+        WriteMode::DryRun
+    } else if install_needs_sudo(
+        &base_directory,
+        &[&pkgdir, &extdir, &sharedir]
+            .iter()
+            .cloned()
+            .chain(docdir.as_ref())
+            .collect::<Vec<_>>(),
+        options.sudo,
+    ) {
+        WriteMode::Sudo
+    } else {
+        WriteMode::Direct
+    };
+
+    if options.no_build {
+        status!(
+            "{} build, using the existing library",
+            "    Skipping".bold().green()
+        );
+    } else {
+        build_extension(pg_config, major_version, is_release, &extname, options);
+    }
+
+    status!();
+    status!("installing extension");
+    let shlibpath = find_library_file(&extname, is_release, options);
+    if options.no_build {
+        check_library_is_current(&shlibpath, &extname);
+    }
+    let mut manifest = Vec::new();
 
-    build_extension(major_version, is_release);
+    // the local Postgres and binutils can't vouch for a library built for another platform
+    match &options.target {
+        Some(target) => status!(
+            "{} `{}` was cross-compiled for `{}`, skipping ABI checks",
+            "     Warning".bold().yellow(),
+            format_display_path(&shlibpath),
+            target
+        ),
+        None => check_abi(&shlibpath, pg_config, major_version),
+    }
+
+    if options.static_link {
+        verify_static_linking(
+            &shlibpath,
+            &metadata.allowed_libraries,
+            options.target.is_some(),
+        );
+    }
+
+    if !options.no_schema {
+        handle_result!("failed to generate SQL schema", crate::generate_schema());
+    }
 
-    println!();
-    println!("installing extension");
-    let pkgdir = make_relative(get_pkglibdir(pg_config));
-    let extdir = make_relative(get_extensiondir(pg_config));
-    let shlibpath = find_library_file(&extname, is_release);
+    // a typo is much cheaper to find now, before anything is installed, than when `CREATE
+    // EXTENSION` runs on someone's server
+    if !options.no_sql_check {
+        match &options.target {
+            Some(_) => status!(
+                "{} cross-compiling, skipping the SQL syntax check",
+                "     Warning".bold().yellow()
+            ),
+            None => check_sql_files(pg_config, &extname),
+        }
+    }
+
+    let schema = prepare_schema(&extdir, &extname, &base_directory, options.force, mode);
 
     {
         let mut dest = base_directory.clone();
         dest.push(&extdir);
         dest.push(&control_file);
-        copy_file(control_file, dest, "control file");
+        copy_file(control_file, dest, "control file", &mut manifest, mode);
     }
 
     {
         let mut dest = base_directory.clone();
         dest.push(&pkgdir);
         dest.push(format!("{}.so", extname));
-        copy_file(shlibpath, dest, "shared library");
+        copy_and_patch_file(
+            shlibpath,
+            dest,
+            "shared library",
+            &mut manifest,
+            mode,
+            |shlib| patch_shared_library(shlib, &pkglibdir, options, mode),
+        );
+    }
+
+    // auxiliary libraries named in [package.metadata.pgx]
+    for library in &metadata.libraries {
+        let mut dest = base_directory.clone();
+        dest.push(&pkgdir);
+        dest.push(library.file_name().unwrap_or_else(|| {
+            exit_with_error!("`{}` is not a library file", library.display())
+        }));
+        copy_file(
+            library.clone(),
+            dest,
+            "auxiliary library",
+            &mut manifest,
+            mode,
+        );
+    }
+
+    // and data files, which go into a directory named after the extension
+    for data in &metadata.data {
+        let mut dest = base_directory.clone();
+        dest.push(&sharedir);
+        dest.push(&extname);
+        copy_data(data, dest, "data file", &mut manifest, mode);
+    }
+
+    // and documentation, which goes where `make install` puts an extension's `DOCS`
+    if let Some(docdir) = &docdir {
+        for doc in &metadata.docs {
+            let mut dest = base_directory.clone();
+            dest.push(docdir);
+            copy_data(doc, dest, "documentation", &mut manifest, mode);
+        }
+    }
+
+    copy_sql_files(
+        schema,
+        &extdir,
+        &extname,
+        &base_directory,
+        &mut manifest,
+        mode,
+    );
+
+    write_manifest(&extdir, &extname, &base_directory, manifest, mode);
+
+    if mode == WriteMode::DryRun {
+        status!(
+            "{} dry run of installing {}, nothing was installed",
+            "    Finished".bold().green(),
+            extname
+        );
+    } else {
+        status!("{} installing {}", "    Finished".bold().green(), extname);
+    }
+    event(
+        "finished",
+        json!({
+            "extname": extname,
+            "version": get_version(),
+            "pg_version": major_version,
+            "dry_run": mode == WriteMode::DryRun,
+        }),
+    );
+}
+
+fn copy_data(
+    src: &PathBuf,
+    dest_dir: PathBuf,
+    msg: &str,
+    manifest: &mut Vec<PathBuf>,
+    mode: WriteMode,
+) {
+    let mut dest = dest_dir;
+    dest.push(src.file_name().unwrap_or_else(|| {
+        exit_with_error!("`{}` is not a data file or directory", src.display())
+    }));
+
+    if src.is_dir() {
+        for entry in handle_result!(
+            format!("failed to read {}", src.display()),
+            std::fs::read_dir(src)
+        ) {
+            if let Ok(entry) = entry {
+                copy_data(&entry.path(), dest.clone(), msg, manifest, mode);
+            }
+        }
+    } else {
+        copy_file(src.clone(), dest, msg, manifest, mode);
+    }
+}
+
+/// Record every file we just installed in `extname.manifest`, and remove any files the previous
+/// install's manifest lists that we no longer install
+fn write_manifest(
+    extdir: &PathBuf,
+    extname: &str,
+    base_directory: &PathBuf,
+    manifest: Vec<PathBuf>,
+    mode: WriteMode,
+) {
+    let mut filename = base_directory.clone();
+    filename.push(extdir);
+    filename.push(format!("{}.manifest", extname));
+
+    let installed = manifest
+        .iter()
+        .map(|path| format!("/{}", path.strip_prefix(base_directory).unwrap_or(path).display()))
+        .collect::<Vec<_>>();
+
+    if let Ok(previous) = std::fs::read_to_string(&filename) {
+        for stale in previous.lines().filter(|line| !installed.iter().any(|i| i == line)) {
+            let mut path = base_directory.clone();
+            path.push(stale.trim_start_matches('/'));
+            if path.is_file() {
+                status!(
+                    "{} stale file `{}`",
+                    mode.label("    Removing", "Would remove"),
+                    path.display()
+                );
+                remove_file(&path, mode);
+                event(
+                    mode.event_name("removed", "would_remove"),
+                    json!({ "path": path }),
+                );
+            }
+        }
+    }
+
+    let contents = installed
+        .iter()
+        .map(|path| format!("{}\n", path))
+        .collect::<String>();
+    if mode == WriteMode::DryRun {
+        status!(
+            "{} manifest to `{}`",
+            mode.label("     Writing", " Would write"),
+            filename.display()
+        );
+    }
+    write_file(&filename, &contents, mode);
+    event(
+        mode.event_name("installed", "would_install"),
+        json!({ "kind": "manifest", "path": filename }),
+    );
+}
+
+fn copy_file(src: PathBuf, dest: PathBuf, msg: &str, manifest: &mut Vec<PathBuf>, mode: WriteMode) {
+    copy_and_patch_file(src, dest, msg, manifest, mode, |_| ())
+}
+
+/// Copy `src` to `dest` like `copy_file()`, calling `patch` on the copy before it's renamed into
+/// place, so nothing ever sees it half-patched.  In a dry run, `patch` is given `dest`
+fn copy_and_patch_file(
+    src: PathBuf,
+    dest: PathBuf,
+    msg: &str,
+    manifest: &mut Vec<PathBuf>,
+    mode: WriteMode,
+    patch: impl FnOnce(&PathBuf),
+) {
+    if mode == WriteMode::DryRun {
+        // the exact paths, so a packager can audit where everything lands
+        status!(
+            "{} {} `{}` to `{}`",
+            mode.label("     Copying", "  Would copy"),
+            msg,
+            src.display(),
+            dest.display()
+        );
+    } else {
+        if !dest.parent().unwrap().exists() {
+            create_dir(dest.parent().unwrap(), mode);
+        }
+
+        status!(
+            "{} {} to `{}`",
+            "     Copying".bold().green(),
+            msg,
+            format_display_path(&dest)
+        );
+    }
+
+    // copy next to `dest` and rename it into place, rather than overwriting it.  Backends that
+    // already loaded the old shared library keep the old file mapped, and rewriting it under them
+    // crashes them, while the new file is only loaded by those that look it up afterwards
+    let tmp = dest.with_file_name(format!(
+        ".{}.pgx-tmp",
+        dest.file_name().unwrap().to_string_lossy()
+    ));
+    match mode {
+        WriteMode::Direct => {
+            handle_result!(
+                format!("failed copying `{}` to `{}`", src.display(), dest.display()),
+                std::fs::copy(&src, &tmp)
+            );
+            patch(&tmp);
+            handle_result!(
+                format!("failed to move `{}` into place", dest.display()),
+                std::fs::rename(&tmp, &dest)
+            );
+        }
+        WriteMode::Sudo => {
+            let mut command = sudo_command("cp");
+            command.arg(&src).arg(&tmp);
+            run_sudo(command, &format!("copy `{}`", src.display()));
+            patch(&tmp);
+
+            let mut command = sudo_command("mv");
+            command.arg("-f").arg(&tmp).arg(&dest);
+            run_sudo(command, &format!("move `{}` into place", src.display()));
+        }
+        WriteMode::DryRun => patch(&dest),
     }
+    event(
+        mode.event_name("installed", "would_install"),
+        json!({ "kind": msg, "source": src, "path": dest }),
+    );
+    manifest.push(dest);
+}
+
+/// Decide whether installing into `dirs`, relative to `base_directory`, has to be done with
+/// `sudo`.  That's only when the current user can't write to one of them, and then only when
+/// `--sudo` allows it
+fn install_needs_sudo(base_directory: &PathBuf, dirs: &[&PathBuf], allow_sudo: bool) -> bool {
+    let unwritable = dirs
+        .iter()
+        .map(|dir| base_directory.join(dir))
+        .find(|dir| !is_writable(dir));
 
+    match unwritable {
+        None => false,
+        Some(dir) if allow_sudo => {
+            status!(
+                "{} `{}` is not writable by the current user, so installing with sudo",
+                "   Elevating".bold().green(),
+                dir.display()
+            );
+
+            // ask for a password now, rather than partway through copying files
+            let mut command = Command::new("sudo");
+            command.arg("-v");
+            run_sudo(command, "authenticate with sudo");
+            true
+        }
+        Some(dir) => exit_with_error!(
+            "`{}` is not writable by the current user.  Use `--sudo` to copy files into it with elevated privileges",
+            dir.display()
+        ),
+    }
+}
+
+/// Can the current user create files in `dir`, or in its nearest existing parent if `dir`
+/// doesn't exist yet?
+fn is_writable(dir: &PathBuf) -> bool {
+    let mut dir = dir.clone();
+    while !dir.exists() {
+        if !dir.pop() {
+            return false;
+        }
+    }
+
+    let mut probe = dir;
+    probe.push(format!(".pgx-install-{}", std::process::id()));
+    match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
     {
-        handle_result!("failed to generate SQL schema", crate::generate_schema());
+        Ok(_) => {
+            std::fs::remove_file(&probe).ok();
+            true
+        }
+        Err(_) => false,
     }
+}
 
-    copy_sql_files(&extdir, &extname, &base_directory);
+/// A `Command` that runs `program` through `sudo`
+fn sudo_command(program: &str) -> Command {
+    let mut command = Command::new("sudo");
+    command.arg(program);
+    command
+}
+
+fn run_sudo(mut command: Command, msg: &str) {
+    let command_str = format!("{:?}", command);
 
-    println!("{} installing {}", "    Finished".bold().green(), extname);
+    // sudo may need the terminal to prompt for a password
+    let status = handle_result!(
+        format!("failed to run: {}", command_str),
+        command
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()
+    );
+    if !status.success() {
+        exit_with_error!("failed to {}: {}", msg, command_str)
+    }
 }
 
-fn copy_file(src: PathBuf, dest: PathBuf, msg: &str) {
-    if !dest.parent().unwrap().exists() {
+fn create_dir(dir: &Path, mode: WriteMode) {
+    match mode {
+        WriteMode::Direct => {
+            handle_result!(
+                format!("failed to create destination directory {}", dir.display()),
+                std::fs::create_dir_all(dir)
+            );
+        }
+        WriteMode::Sudo => {
+            let mut command = sudo_command("mkdir");
+            command.arg("-p").arg(dir);
+            run_sudo(command, &format!("create directory `{}`", dir.display()));
+        }
+        WriteMode::DryRun => (),
+    }
+}
+
+fn write_file(dest: &PathBuf, contents: &str, mode: WriteMode) {
+    if mode == WriteMode::DryRun {
+        return;
+    }
+
+    if mode == WriteMode::Sudo {
+        // write the contents out as ourselves, then have sudo copy them into place
+        let mut staged = get_target_dir();
+        staged.push(dest.file_name().unwrap());
         handle_result!(
-            format!(
-                "failed to create destination directory {}",
-                dest.parent().unwrap().display()
-            ),
-            std::fs::create_dir_all(dest.parent().unwrap())
+            format!("couldn't write {}", staged.display()),
+            std::fs::write(&staged, contents)
+        );
+
+        let mut command = sudo_command("cp");
+        command.arg(&staged).arg(dest);
+        run_sudo(command, &format!("write `{}`", dest.display()));
+        std::fs::remove_file(&staged).ok();
+    } else {
+        handle_result!(
+            format!("couldn't write {}", dest.display()),
+            std::fs::write(dest, contents)
+        );
+    }
+}
+
+fn remove_file(path: &PathBuf, mode: WriteMode) {
+    if mode == WriteMode::DryRun {
+        return;
+    }
+
+    if mode == WriteMode::Sudo {
+        let mut command = sudo_command("rm");
+        command.arg("-f").arg(path);
+        run_sudo(command, &format!("remove `{}`", path.display()));
+    } else {
+        handle_result!(
+            format!("failed to remove {}", path.display()),
+            std::fs::remove_file(path)
         );
     }
+}
+
+fn patch_shared_library(
+    shlib: &PathBuf,
+    pkglibdir: &PathBuf,
+    options: &InstallOptions,
+    mode: WriteMode,
+) {
+    // the patch tools rewrite the copy in place, so need the same privileges we made it with
+    let patch_command = |program: &str| {
+        if mode == WriteMode::Sudo {
+            sudo_command(program)
+        } else {
+            Command::new(program)
+        }
+    };
+
+    let rpaths = options
+        .rpaths
+        .iter()
+        .map(|rpath| rpath.replace("@PKGLIBDIR@", &pkglibdir.display().to_string()))
+        .collect::<Vec<_>>();
+
+    if cfg!(target_os = "macos") {
+        for rpath in rpaths {
+            let mut command = patch_command("install_name_tool");
+            command.arg("-add_rpath").arg(&rpath).arg(shlib);
+            run_patch_command(command, mode, &format!("rpath `{}`", rpath));
+        }
+
+        for (old, new) in &options.install_names {
+            let mut command = patch_command("install_name_tool");
+            command.arg("-change").arg(old).arg(new).arg(shlib);
+            run_patch_command(
+                command,
+                mode,
+                &format!("install name `{}` to `{}`", old, new),
+            );
+        }
+    } else {
+        if !options.install_names.is_empty() {
+            exit_with_error!("changing install names is only supported on macOS")
+        }
 
-    println!(
-        "{} {} to `{}`",
-        "     Copying".bold().green(),
-        msg,
-        format_display_path(&dest)
+        if !rpaths.is_empty() {
+            let rpath = rpaths.join(":");
+            let mut command = patch_command("patchelf");
+            command.arg("--set-rpath").arg(&rpath).arg(shlib);
+            run_patch_command(command, mode, &format!("rpath `{}`", rpath));
+        }
+    }
+}
+
+fn run_patch_command(mut command: Command, mode: WriteMode, msg: &str) {
+    let command_str = format!("{:?}", command);
+    if mode == WriteMode::DryRun {
+        status!(
+            "{} {} with {}",
+            mode.label("    Patching", " Would patch"),
+            msg,
+            command_str
+        );
+        return;
+    }
+    status!("{} {}", "    Patching".bold().green(), msg);
+
+    let output = handle_result!(
+        format!("failed to run: {}", command_str),
+        command.output()
     );
+    if !output.status.success() {
+        exit_with_error!(
+            "failed to patch shared library: {}\n\n{}{}",
+            command_str,
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap()
+        )
+    }
+}
 
-    handle_result!(
-        format!("failed copying `{}` to `{}`", src.display(), dest.display()),
-        std::fs::copy(&src, &dest)
+fn verify_static_linking(shlib: &PathBuf, allowed_libraries: &[String], cross_compiled: bool) {
+    if cfg!(target_os = "macos") {
+        exit_with_error!("static linking is only supported on Linux")
+    }
+
+    // ldd has to run the target's dynamic loader, so for a cross-compiled library we settle for
+    // the libraries it names directly, which readelf can list for any architecture
+    let mut command = Command::new(if cross_compiled { "readelf" } else { "ldd" });
+    if cross_compiled {
+        command.arg("--dynamic");
+    }
+    command.arg(shlib);
+    let command_str = format!("{:?}", command);
+    status!(
+        "{} linked libraries of `{}`",
+        "   Verifying".bold().green(),
+        format_display_path(shlib)
     );
+
+    let output = handle_result!(
+        format!("failed to run: {}", command_str),
+        command.output()
+    );
+    if !output.status.success() {
+        exit_with_error!(
+            "problem listing linked libraries: {}\n\n{}",
+            command_str,
+            String::from_utf8(output.stderr).unwrap()
+        )
+    }
+
+    // each ldd line looks like `libc.so.6 => /lib/x86_64-linux-gnu/libc.so.6 (0x00007f...)`, and
+    // each readelf one like `0x0000000000000001 (NEEDED)  Shared library: [libc.so.6]`
+    let unexpected = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter(|line| line.trim() != "statically linked")
+        .filter_map(|line| {
+            if cross_compiled {
+                if !line.contains("(NEEDED)") {
+                    return None;
+                }
+                let start = line.find('[')? + 1;
+                let end = line.rfind(']')?;
+                line.get(start..end)
+            } else {
+                line.split_whitespace().next()
+            }
+        })
+        .map(|library| library.rsplit('/').next().unwrap_or(library).to_string())
+        .filter(|library| {
+            let name = library.split('.').next().unwrap_or(library);
+            !STATIC_ALLOWED_LIBRARIES.contains(&name)
+                && !name.starts_with("ld-linux")
+                && !allowed_libraries.iter().any(|allowed| allowed == name || allowed == library)
+        })
+        .collect::<Vec<_>>();
+
+    if !unexpected.is_empty() {
+        exit_with_error!(
+            "`{}` is dynamically linked against unexpected libraries: {}\n\nIf these are expected, list them in `allowed_libraries` under [package.metadata.pgx] in Cargo.toml",
+            shlib.display(),
+            unexpected.join(", ")
+        )
+    }
 }
 
-fn build_extension(major_version: u16, is_release: bool) {
+fn build_extension(
+    pg_config: &Option<String>,
+    major_version: u16,
+    is_release: bool,
+    extname: &str,
+    options: &InstallOptions,
+) {
     let features = std::env::var("PGX_BUILD_FEATURES").unwrap_or(format!("pg{}", major_version));
     let flags = std::env::var("PGX_BUILD_FLAGS").unwrap_or_default();
+    validate_version_features(&features, major_version);
+
     let mut command = Command::new("cargo");
     command.arg("build");
     if is_release {
         command.arg("--release");
     }
 
+    if let Some(target) = &options.target {
+        command.arg("--target");
+        command.arg(target);
+    }
+
+    if let Some(target_dir) = &options.target_dir {
+        command.env("CARGO_TARGET_DIR", target_dir);
+    }
+
+    // the local Postgres' flags are no help building for another platform
+    if options.target.is_none() {
+        set_pg_build_flags(&mut command, pg_config);
+    }
+
     if !features.trim().is_empty() {
         command.arg("--features");
         command.arg(&features);
@@ -95,11 +810,46 @@ fn build_extension(major_version: u16, is_release: bool) {
         command.arg(arg);
     }
 
+    // baked into the library by `pg_module_magic!()` so it can verify itself against the
+    // installed extension schema at load time
+    command.env("PGX_EXTENSION_NAME", extname);
+    command.env("PGX_EXTENSION_VERSION", get_version());
+
+    // and these so a production library can be traced back to its sources
+    if let Some(revision) = get_git_revision() {
+        command.env("PGX_BUILD_GIT_REVISION", revision);
+    }
+    command.env(
+        "PGX_BUILD_PROFILE",
+        if is_release { "release" } else { "debug" },
+    );
+
+    if options.static_link {
+        // link the C runtime support library statically, and ask build scripts that use
+        // pkg-config to find static versions of whatever native libraries they need
+        let rustflags = std::env::var("RUSTFLAGS").unwrap_or_default();
+        command.env(
+            "RUSTFLAGS",
+            format!("{} -C link-arg=-static-libgcc", rustflags).trim(),
+        );
+        command.env("PKG_CONFIG_ALL_STATIC", "1");
+    }
+
     let command = command.stdout(Stdio::inherit()).stderr(Stdio::inherit());
     let command_str = format!("{:?}", command);
-    println!(
+    status!(
         "building extension with features `{}`\n{}",
-        features, command_str
+        features,
+        command_str
+    );
+    event(
+        "build",
+        json!({
+            "features": features,
+            "profile": if is_release { "release" } else { "debug" },
+            "pg_version": major_version,
+            "target": options.target,
+        }),
     );
     let status = handle_result!(
         format!("failed to spawn cargo: {}", command_str),
@@ -110,22 +860,150 @@ fn build_extension(major_version: u16, is_release: bool) {
     }
 }
 
-fn copy_sql_files(extdir: &PathBuf, extname: &str, base_directory: &PathBuf) {
-    let load_order = read_load_order(&PathBuf::from_str("./sql/load-order.txt").unwrap());
+/// Tell the build the flags Postgres itself was built with, so native code compiled for the
+/// extension, with the `cc` crate or bindgen, can find the same ICU, OpenSSL, LLVM, etc. that
+/// Postgres did, even when they're somewhere non-standard.
+///
+/// They're passed as they are in `PGX_PG_CPPFLAGS`, `PGX_PG_CFLAGS_SL`, and `PGX_PG_LDFLAGS`, for
+/// `pgx-pg-sys` and any other build script that wants them.  `CFLAGS` and the like are left
+/// alone, as they'd apply to every crate being built, not just those that compile against Postgres
+pub(crate) fn set_pg_build_flags(command: &mut Command, pg_config: &Option<String>) {
+    command.env("PGX_PG_CPPFLAGS", run_pg_config(pg_config, "--cppflags"));
+    command.env("PGX_PG_CFLAGS_SL", run_pg_config(pg_config, "--cflags_sl"));
+    command.env("PGX_PG_LDFLAGS", run_pg_config(pg_config, "--ldflags"));
+}
+
+/// Make sure the Postgres version features we're building with, such as `pg13` or `pgx/pg13`,
+/// agree with the `pg_config` we're installing with.  A library compiled against another
+/// version's headers only shows up as a crash once it's loaded
+fn validate_version_features(features: &str, major_version: u16) {
+    let expected = format!("pg{}", major_version);
+    let versions = features
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|feature| feature.rsplit('/').next().unwrap_or(feature))
+        .filter(|feature| {
+            feature.len() > 2
+                && feature.starts_with("pg")
+                && feature[2..].chars().all(|c| c.is_ascii_digit())
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(wrong) = versions.iter().find(|feature| **feature != expected) {
+        exit_with_error!(
+            "building with feature `{}`, but `pg_config` is for Postgres {}.  Use `--pg-version` to pick the `pg_config` for the version you want",
+            wrong,
+            major_version
+        )
+    }
+}
+
+/// The extension's `extname--version.sql`, assembled and ready to be written
+struct ExtensionSchema {
+    target_filename: PathBuf,
+    sql: String,
+    load_order_files: Vec<String>,
+}
+
+/// Assemble the extension's schema and, if a different one is already installed for this version,
+/// show what changed and ask before going on.  Done before anything is copied, so saying no leaves
+/// the installation as it was
+fn prepare_schema(
+    extdir: &PathBuf,
+    extname: &str,
+    base_directory: &PathBuf,
+    force: bool,
+    mode: WriteMode,
+) -> ExtensionSchema {
+    let layout = SchemaLayout::from_metadata();
+    let load_order = resolve_load_order(&layout);
+    let load_order_files = load_order
+        .iter()
+        .map(|(file, _)| file.clone())
+        .collect::<Vec<_>>();
     let mut target_filename = base_directory.clone();
     target_filename.push(extdir);
     target_filename.push(format!("{}--{}.sql", extname, get_version()));
+    let sql = assemble_schema(&layout, load_order);
+
+    // don't silently change the schema of a version that's already installed
+    if let Ok(existing) = std::fs::read_to_string(&target_filename) {
+        if existing != sql {
+            print_diff(&existing, &sql, &format_display_path(&target_filename));
+            warn_removed_functions(&existing, &sql);
+            if mode != WriteMode::DryRun {
+                confirm_overwrite(&target_filename, force);
+            }
+        }
+    }
+
+    ExtensionSchema {
+        target_filename,
+        sql,
+        load_order_files,
+    }
+}
+
+fn copy_sql_files(
+    schema: ExtensionSchema,
+    extdir: &PathBuf,
+    extname: &str,
+    base_directory: &PathBuf,
+    manifest: &mut Vec<PathBuf>,
+    mode: WriteMode,
+) {
+    let ExtensionSchema {
+        target_filename,
+        sql,
+        load_order_files,
+    } = schema;
 
-    let mut sql = std::fs::File::create(&target_filename).unwrap();
-    println!(
+    status!(
         "{} extension schema to `{}`",
-        "     Writing".bold().green(),
-        format_display_path(&target_filename)
+        mode.label("     Writing", " Would write"),
+        if mode == WriteMode::DryRun {
+            target_filename.display().to_string()
+        } else {
+            format_display_path(&target_filename)
+        }
+    );
+    write_file(&target_filename, &sql, mode);
+    event(
+        mode.event_name("installed", "would_install"),
+        json!({
+            "kind": "extension schema",
+            "path": target_filename,
+            "version": get_version(),
+            "files": load_order_files,
+        }),
     );
+    manifest.push(target_filename.clone());
+
+    // now copy all the version upgrade files too
+    for sql in handle_result!("failed to read ./sql/ directory", std::fs::read_dir("sql/")) {
+        if let Ok(sql) = sql {
+            let filename = sql.file_name().into_string().unwrap();
 
-    // write each sql file from load-order.txt to the version.sql file
-    for file in load_order {
-        let file = PathBuf::from_str(&format!("sql/{}", file)).unwrap();
+            if filename.starts_with(&format!("{}--", extname)) && filename.ends_with(".sql") {
+                let mut dest = base_directory.clone();
+                dest.push(&extdir);
+                dest.push(filename);
+
+                copy_file(sql.path(), dest, "extension schema file", manifest, mode);
+            }
+        }
+    }
+}
+
+/// Concatenate the SQL files in `load_order`, each under its `search_path`, into the contents of
+/// the extension's `extname--version.sql`
+pub(crate) fn assemble_schema(
+    layout: &SchemaLayout,
+    load_order: Vec<(String, Option<String>)>,
+) -> String {
+    let mut sql = String::new();
+    let mut saved_search_path = false;
+    for (file, annotated_search_path) in load_order {
+        let file = layout.path_of(&file);
         let pwd = std::env::current_dir().expect("no current directory");
         let contents = std::fs::read_to_string(&file).expect(&format!(
             "could not open {}/{}",
@@ -133,40 +1011,234 @@ fn copy_sql_files(extdir: &PathBuf, extname: &str, base_directory: &PathBuf) {
             file.display()
         ));
 
-        sql.write_all(b"--\n")
-            .expect("couldn't write version SQL file");
-        sql.write_all(format!("-- {}\n", file.display()).as_bytes())
-            .expect("couldn't write version SQL file");
-        sql.write_all(b"--\n")
-            .expect("couldn't write version SQL file");
-        sql.write_all(contents.as_bytes())
-            .expect("couldn't write version SQL file");
-        sql.write_all(b"\n\n\n")
-            .expect("couldn't write version SQL file");
+        sql.push_str("--\n");
+        sql.push_str(&format!("-- {}\n", file.display()));
+        sql.push_str("--\n");
+
+        // a search_path from load-order.txt takes precedence over one in the file itself
+        let search_path = annotated_search_path.or_else(|| search_path_frontmatter(&contents));
+        if let Some(search_path) = &search_path {
+            if !saved_search_path {
+                // remember the search_path CREATE/ALTER EXTENSION gave us so we can restore it
+                sql.push_str("SELECT pg_catalog.set_config('pgx.saved_search_path', pg_catalog.current_setting('search_path'), true);\n");
+                saved_search_path = true;
+            }
+            sql.push_str(&format!("SET LOCAL search_path TO {};\n", search_path));
+        }
+
+        sql.push_str(&contents);
+
+        if search_path.is_some() {
+            // so the next file doesn't silently create its objects in this file's schema
+            sql.push_str("\nSELECT pg_catalog.set_config('search_path', pg_catalog.current_setting('pgx.saved_search_path'), true);");
+        }
+        sql.push_str("\n\n\n");
     }
 
-    // now copy all the version upgrade files too
+    sql
+}
+
+/// Check the syntax of the schema files, in load order, and of every upgrade script with the parser
+/// of the Postgres `pg_config` describes, and exit with every problem found
+fn check_sql_files(pg_config: &Option<String>, extname: &str) {
+    let layout = SchemaLayout::from_metadata();
+    let mut files = resolve_load_order(&layout)
+        .iter()
+        .map(|(file, _)| layout.path_of(file))
+        .collect::<Vec<_>>();
     for sql in handle_result!("failed to read ./sql/ directory", std::fs::read_dir("sql/")) {
         if let Ok(sql) = sql {
             let filename = sql.file_name().into_string().unwrap();
-
             if filename.starts_with(&format!("{}--", extname)) && filename.ends_with(".sql") {
-                let mut dest = PathBuf::new();
-                dest.push(&extdir);
-                dest.push(filename);
+                files.push(sql.path());
+            }
+        }
+    }
 
-                copy_file(sql.path(), dest, "extension schema file");
+    check_sql_syntax(pg_config, &files);
+}
+
+/// Find a `-- pgx: search_path = ...` line in the leading comments of a SQL file
+fn search_path_frontmatter(contents: &str) -> Option<String> {
+    for line in contents.lines() {
+        let line = line.trim();
+        if !line.starts_with("--") {
+            break;
+        }
+
+        let comment = line.trim_start_matches('-').trim();
+        if comment.starts_with("pgx:") {
+            let directive = comment.trim_start_matches("pgx:").trim();
+            if directive.starts_with("search_path") {
+                let value = directive.trim_start_matches("search_path").trim_start();
+                if value.starts_with('=') {
+                    return Some(value.trim_start_matches('=').trim().to_string());
+                }
             }
         }
     }
+
+    None
+}
+
+/// Warn about functions `old` created that `new` no longer does, as anything calling them will
+/// break.  A renamed function can keep answering to its old name with
+/// `#[pg_extern(deprecated_alias = "old_name")]`
+fn warn_removed_functions(old: &str, new: &str) {
+    let remaining = created_functions(new);
+    for name in created_functions(old) {
+        if !remaining.contains(&name) {
+            status!(
+                "{} function `{}` is no longer created, which breaks existing callers.  If it was renamed, add #[pg_extern(deprecated_alias = \"{}\")] to its replacement",
+                "     Warning".bold().yellow(),
+                name,
+                name.rsplit('.').next().unwrap().trim_matches('"')
+            );
+        }
+    }
+}
+
+/// The names of the functions `sql` creates
+fn created_functions(sql: &str) -> BTreeSet<String> {
+    sql.lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            if line.starts_with("CREATE OR REPLACE FUNCTION ") {
+                let name = line.trim_start_matches("CREATE OR REPLACE FUNCTION ");
+                Some(name[..name.find('(').unwrap_or_else(|| name.len())].trim().to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
 }
 
-fn find_library_file(extname: &str, is_release: bool) -> PathBuf {
-    let mut target_dir = get_target_dir();
+/// Print a colored unified diff between `old` and `new`
+fn print_diff(old: &str, new: &str, filename: &str) {
+    const CONTEXT: usize = 3;
+
+    let lines = diff::lines(old, new);
+    let changed = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, diff::Result::Both(_, _)))
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+
+    // group changed lines, along with their surrounding context, into hunks
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for idx in changed {
+        let start = idx.saturating_sub(CONTEXT);
+        let end = (idx + CONTEXT + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    status!("{}", format!("--- {}", filename).bold());
+    status!("{}", format!("+++ {} (regenerated)", filename).bold());
+
+    // line numbers, in the old and new text, of each diff line
+    let mut positions = Vec::with_capacity(lines.len());
+    let (mut old_line, mut new_line) = (1, 1);
+    for line in &lines {
+        positions.push((old_line, new_line));
+        match line {
+            diff::Result::Left(_) => old_line += 1,
+            diff::Result::Right(_) => new_line += 1,
+            diff::Result::Both(_, _) => {
+                old_line += 1;
+                new_line += 1;
+            }
+        }
+    }
+
+    for (start, end) in hunks {
+        let hunk = &lines[start..end];
+        let old_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, diff::Result::Right(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, diff::Result::Left(_)))
+            .count();
+        let (old_start, new_start) = positions[start];
+
+        status!(
+            "{}",
+            format!(
+                "@@ -{},{} +{},{} @@",
+                old_start, old_count, new_start, new_count
+            )
+            .cyan()
+        );
+        for line in hunk {
+            match line {
+                diff::Result::Left(l) => status!("{}", format!("-{}", l).red()),
+                diff::Result::Right(r) => status!("{}", format!("+{}", r).green()),
+                diff::Result::Both(l, _) => status!(" {}", l),
+            }
+        }
+    }
+}
+
+fn confirm_overwrite(filename: &PathBuf, force: bool) {
+    if force {
+        return;
+    }
+
+    // there's nobody to ask when a tool is reading our --json output, or we're not interactive,
+    // and silently overwriting a released version's schema is what we're asking about
+    if is_json() || !atty::is(atty::Stream::Stdin) {
+        exit_with_error!(
+            "`{}` already exists with different contents.  Bump `default_version` in your control file, or use `--force` to overwrite it",
+            filename.display()
+        )
+    }
+
+    print!(
+        "{} `{}` already exists with different contents.  Overwrite? [y/N] ",
+        "    Question".bold().yellow(),
+        format_display_path(filename)
+    );
+    handle_result!("failed to flush stdout", std::io::stdout().flush());
+
+    let mut answer = String::new();
+    handle_result!(
+        "failed to read answer",
+        std::io::stdin().read_line(&mut answer)
+    );
+    if !answer.trim().eq_ignore_ascii_case("y") && !answer.trim().eq_ignore_ascii_case("yes") {
+        exit_with_error!(
+            "not overwriting `{}`.  Bump `default_version` in your control file, or use `--force`",
+            filename.display()
+        )
+    }
+}
+
+fn find_library_file(extname: &str, is_release: bool, options: &InstallOptions) -> PathBuf {
+    // cargo puts cross-compiled artifacts in a directory named after the target triple
+    let mut target_dir = options.target_dir.clone().unwrap_or_else(get_target_dir);
+    if let Some(target) = &options.target {
+        target_dir.push(target);
+    }
     target_dir.push(if is_release { "release" } else { "debug" });
 
+    // with `--no-build`, whatever built the library had to use the same profile and target
+    let hint = if options.no_build {
+        " (--no-build installs what `cargo build` already built, so build it first)"
+    } else {
+        ""
+    };
+
     if !target_dir.exists() {
-        exit_with_error!("target directory does not exist: {}", target_dir.display());
+        exit_with_error!(
+            "target directory does not exist: {}{}",
+            target_dir.display(),
+            hint
+        );
     }
 
     for f in handle_result!(
@@ -187,22 +1259,93 @@ fn find_library_file(extname: &str, is_release: bool) -> PathBuf {
         }
     }
 
-    exit_with_error!("library file not found in: `{}`", target_dir.display())
+    exit_with_error!(
+        "library file not found in: `{}`{}",
+        target_dir.display(),
+        hint
+    )
 }
 
-fn get_version() -> String {
+/// With `--no-build`, make sure the library we found isn't older than the sources it's built
+/// from, which would mean installing something other than what's checked out
+fn check_library_is_current(shlibpath: &PathBuf, extname: &str) {
+    let built = handle_result!(
+        format!("failed to read `{}`", shlibpath.display()),
+        shlibpath
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+    );
+
+    let mut sources = BTreeMap::new();
+    find_files(&PathBuf::from("src"), &mut sources);
+    for file in &[
+        PathBuf::from("build.rs"),
+        PathBuf::from("Cargo.toml"),
+        PathBuf::from(format!("{}.control", extname)),
+    ] {
+        if let Ok(modified) = file.metadata().and_then(|metadata| metadata.modified()) {
+            sources.insert(file.clone(), modified);
+        }
+    }
+
+    if let Some((source, _)) = sources
+        .iter()
+        .filter(|(_, modified)| **modified > built)
+        .max_by_key(|(_, modified)| **modified)
+    {
+        exit_with_error!(
+            "`{}` is older than `{}`.  Rebuild it, or install without --no-build",
+            format_display_path(shlibpath),
+            source.display()
+        )
+    }
+}
+
+pub(crate) fn get_version() -> String {
     match get_property("default_version") {
         Some(v) => v,
         None => exit_with_error!("cannot determine extension version number.  Is the `default_version` property declared in the control file?"),
     }
 }
 
-fn get_pkglibdir(pg_config: &Option<String>) -> PathBuf {
+/// The git commit the crate is at, with `-dirty` appended if it has uncommitted changes, or
+/// `$PGX_BUILD_GIT_REVISION` for builds from a source tree that isn't a git checkout
+pub(crate) fn get_git_revision() -> Option<String> {
+    if let Ok(revision) = std::env::var("PGX_BUILD_GIT_REVISION") {
+        return Some(revision);
+    }
+
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let revision = git(&["rev-parse", "HEAD"])?;
+    match git(&["status", "--porcelain", "--untracked-files=no"]) {
+        Some(changes) if !changes.is_empty() => Some(format!("{}-dirty", revision)),
+        _ => Some(revision),
+    }
+}
+
+pub(crate) fn get_pkglibdir(pg_config: &Option<String>) -> PathBuf {
     run_pg_config(pg_config, "--pkglibdir").into()
 }
 
-fn get_extensiondir(pg_config: &Option<String>) -> PathBuf {
-    let mut dir: PathBuf = run_pg_config(pg_config, "--sharedir").into();
+pub(crate) fn get_sharedir(pg_config: &Option<String>) -> PathBuf {
+    run_pg_config(pg_config, "--sharedir").into()
+}
+
+pub(crate) fn get_docdir(pg_config: &Option<String>) -> PathBuf {
+    run_pg_config(pg_config, "--docdir").into()
+}
+
+pub(crate) fn get_extensiondir(sharedir: &PathBuf) -> PathBuf {
+    let mut dir = sharedir.clone();
 
     dir.push("extension");
     dir
@@ -221,7 +1364,7 @@ fn make_relative(path: PathBuf) -> PathBuf {
     relative
 }
 
-fn format_display_path(path: &PathBuf) -> String {
+pub(crate) fn format_display_path(path: &PathBuf) -> String {
     path.strip_prefix(get_target_dir().parent().unwrap())
         .unwrap_or(&path)
         .display()
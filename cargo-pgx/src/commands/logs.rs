@@ -0,0 +1,123 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx_utils::{exit_with_error, get_pglog_file, handle_result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How often `--follow` checks the log file for more output
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Print the log file of the pgx-managed Postgres `major_version`, or just its last `lines` lines.
+/// With `follow`, keep printing whatever Postgres writes to it until interrupted, starting over
+/// from the top of the file when it's rotated
+pub(crate) fn show_logs(
+    major_version: u16,
+    lines: Option<usize>,
+    follow: bool,
+) -> std::result::Result<(), std::io::Error> {
+    let logfile = get_pglog_file(major_version);
+    let mut file = match File::open(&logfile) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => exit_with_error!(
+            "Postgres v{} has no log file at {} yet.  Start it with `{}`",
+            major_version,
+            logfile.display(),
+            format!("cargo pgx start pg{}", major_version)
+                .bold()
+                .yellow()
+        ),
+        Err(e) => exit_with_error!("failed to open {}: {}", logfile.display(), e),
+    };
+
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+
+    let mut contents = Vec::new();
+    handle_result!(
+        format!("failed to read {}", logfile.display()),
+        file.read_to_end(&mut contents)
+    );
+    let start = match lines {
+        Some(lines) => start_of_last_lines(&contents, lines),
+        None => 0,
+    };
+    if !write_or_stop(&mut stdout, &contents[start..]) || !follow {
+        return Ok(());
+    }
+
+    let mut inode = handle_result!(
+        format!("failed to stat {}", logfile.display()),
+        file.metadata()
+    )
+    .ino();
+    let mut position = contents.len() as u64;
+    loop {
+        std::thread::sleep(FOLLOW_INTERVAL);
+
+        // `cargo pgx start` rotates the log by renaming it, so a new file at the same path, or
+        // the same one cut short, means everything in it is new
+        if let Some((reopened, reopened_inode)) = reopen_if_rotated(&logfile, inode, position) {
+            file = reopened;
+            inode = reopened_inode;
+            position = 0;
+        }
+
+        handle_result!(
+            format!("failed to read {}", logfile.display()),
+            file.seek(SeekFrom::Start(position))
+        );
+        contents.clear();
+        handle_result!(
+            format!("failed to read {}", logfile.display()),
+            file.read_to_end(&mut contents)
+        );
+        position += contents.len() as u64;
+        if !write_or_stop(&mut stdout, &contents) {
+            return Ok(());
+        }
+    }
+}
+
+/// Where the last `lines` lines of `contents` begin.  A final line without a newline counts as one
+fn start_of_last_lines(contents: &[u8], lines: usize) -> usize {
+    if lines == 0 {
+        return contents.len();
+    }
+    let body = match contents.last() {
+        Some(b'\n') => &contents[..contents.len() - 1],
+        _ => contents,
+    };
+
+    body.iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, byte)| **byte == b'\n')
+        .nth(lines - 1)
+        .map_or(0, |(i, _)| i + 1)
+}
+
+/// The log file at `logfile`, opened afresh, if it's no longer the file with `inode` or it's
+/// shorter than what we've already printed
+fn reopen_if_rotated(logfile: &PathBuf, inode: u64, position: u64) -> Option<(File, u64)> {
+    // in between the old file being renamed and Postgres creating the new one, there is none
+    let metadata = std::fs::metadata(logfile).ok()?;
+    if metadata.ino() == inode && metadata.len() >= position {
+        return None;
+    }
+
+    let file = File::open(logfile).ok()?;
+    Some((file, metadata.ino()))
+}
+
+/// Write `bytes` to stdout, returning false if whatever is reading it, like `head`, has gone away
+fn write_or_stop(stdout: &mut std::io::StdoutLock, bytes: &[u8]) -> bool {
+    match stdout.write_all(bytes).and_then(|_| stdout.flush()) {
+        Ok(()) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => false,
+        Err(e) => exit_with_error!("failed to write the log: {}", e),
+    }
+}
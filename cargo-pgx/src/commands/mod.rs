@@ -2,14 +2,27 @@
 // governed by the MIT license that can be found in the LICENSE file.
 
 
+#[macro_use]
+pub(crate) mod output;
+
+pub(crate) mod abi_check;
+pub(crate) mod connect;
 pub(crate) mod get;
 pub(crate) mod init;
 pub(crate) mod install;
+pub(crate) mod logs;
 pub(crate) mod new;
 pub(crate) mod package;
+pub(crate) mod pgxn;
 pub(crate) mod run;
 pub(crate) mod schema;
+pub(crate) mod schema_graph;
+pub(crate) mod sql_check;
 pub(crate) mod start;
 pub(crate) mod status;
 pub(crate) mod stop;
 pub(crate) mod test;
+pub(crate) mod test_report;
+pub(crate) mod upgrade;
+pub(crate) mod watch;
+pub(crate) mod workspace;
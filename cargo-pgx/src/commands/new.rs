@@ -1,20 +1,50 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
+use pgx_utils::exit_with_error;
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
 
+/// The kinds of extension crate `cargo pgx new --template` can create
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CrateTemplate {
+    /// A `#[pg_extern]` "hello world" function
+    Default,
+
+    /// A background worker started from `_PG_init()`
+    BackgroundWorker,
+
+    /// A custom Postgres type with its own text input/output functions
+    CustomType,
+
+    /// A trigger function, and the hand-written SQL that creates it
+    Trigger,
+}
+
+impl CrateTemplate {
+    pub(crate) fn from_name(name: &str) -> Self {
+        match name {
+            "default" => CrateTemplate::Default,
+            "bgworker" => CrateTemplate::BackgroundWorker,
+            "type" => CrateTemplate::CustomType,
+            "trigger" => CrateTemplate::Trigger,
+            _ => exit_with_error!("unrecognized template: {}", name),
+        }
+    }
+}
+
 pub(crate) fn create_crate_template(
     path: PathBuf,
     name: &str,
-    is_bgworker: bool,
+    template: CrateTemplate,
 ) -> Result<(), std::io::Error> {
     create_directory_structure(&path)?;
-    create_control_file(&path, name)?;
+    create_control_file(&path, name, template)?;
     create_cargo_toml(&path, name)?;
     create_dotcargo_config(&path, name)?;
-    create_lib_rs(&path, name, is_bgworker)?;
+    create_lib_rs(&path, name, template)?;
+    create_sql_files(&path, name, template)?;
     create_git_ignore(&path, name)?;
 
     let cwd = std::env::current_dir().unwrap();
@@ -41,13 +71,23 @@ fn create_directory_structure(path: &PathBuf) -> Result<(), std::io::Error> {
     std::fs::create_dir_all(&src_dir)
 }
 
-fn create_control_file(path: &PathBuf, name: &str) -> Result<(), std::io::Error> {
+fn create_control_file(
+    path: &PathBuf,
+    name: &str,
+    template: CrateTemplate,
+) -> Result<(), std::io::Error> {
     let mut filename = path.clone();
 
     filename.push(format!("{}.control", name));
     let mut file = std::fs::File::create(filename)?;
 
-    file.write_all(&format!(include_str!("../templates/control"), name = name).as_bytes())?;
+    if template == CrateTemplate::BackgroundWorker {
+        file.write_all(
+            &format!(include_str!("../templates/bgworker_control"), name = name).as_bytes(),
+        )?;
+    } else {
+        file.write_all(&format!(include_str!("../templates/control"), name = name).as_bytes())?;
+    }
 
     Ok(())
 }
@@ -75,19 +115,50 @@ fn create_dotcargo_config(path: &PathBuf, _name: &str) -> Result<(), std::io::Er
     Ok(())
 }
 
-fn create_lib_rs(path: &PathBuf, name: &str, is_bgworker: bool) -> Result<(), std::io::Error> {
+fn create_lib_rs(
+    path: &PathBuf,
+    name: &str,
+    template: CrateTemplate,
+) -> Result<(), std::io::Error> {
     let mut filename = path.clone();
 
     filename.push("src");
     filename.push("lib.rs");
     let mut file = std::fs::File::create(filename)?;
 
-    if is_bgworker {
-        file.write_all(
-            &format!(include_str!("../templates/bgworker_lib_rs"), name = name).as_bytes(),
-        )?;
-    } else {
-        file.write_all(&format!(include_str!("../templates/lib_rs"), name = name).as_bytes())?;
+    let contents = match template {
+        CrateTemplate::Default => format!(include_str!("../templates/lib_rs"), name = name),
+        CrateTemplate::BackgroundWorker => {
+            format!(include_str!("../templates/bgworker_lib_rs"), name = name)
+        }
+        CrateTemplate::CustomType => format!(include_str!("../templates/type_lib_rs")),
+        CrateTemplate::Trigger => format!(include_str!("../templates/trigger_lib_rs"), name = name),
+    };
+    file.write_all(contents.as_bytes())?;
+
+    Ok(())
+}
+
+/// Write the hand-written SQL files a template needs.  Each declares what it needs loaded
+/// before it with a `-- pgx: requires` comment, so there's no `sql/load-order.txt` to maintain
+fn create_sql_files(
+    path: &PathBuf,
+    name: &str,
+    template: CrateTemplate,
+) -> Result<(), std::io::Error> {
+    let files = match template {
+        CrateTemplate::Trigger => vec![(
+            "triggers.sql",
+            format!(include_str!("../templates/trigger_sql"), name = name),
+        )],
+        _ => vec![],
+    };
+
+    for (sql_file, contents) in files {
+        let mut filename = path.clone();
+        filename.push("sql");
+        filename.push(sql_file);
+        std::fs::write(filename, contents)?;
     }
 
     Ok(())
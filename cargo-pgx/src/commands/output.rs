@@ -0,0 +1,51 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Print a progress message for people.  With `--json`, stdout is reserved for events, so these
+/// go to stderr instead
+macro_rules! status {
+    () => ({
+        if crate::commands::output::is_json() {
+            eprintln!()
+        } else {
+            println!()
+        }
+    });
+    ($($arg:tt)+) => ({
+        if crate::commands::output::is_json() {
+            eprintln!($($arg)+)
+        } else {
+            println!($($arg)+)
+        }
+    });
+}
+
+/// Switch to `--json` output: one JSON object per line on stdout for each event, and everything
+/// else, uncolored, on stderr
+pub(crate) fn enable_json() {
+    JSON_OUTPUT.store(true, Ordering::SeqCst);
+    colored::control::set_override(false);
+}
+
+pub(crate) fn is_json() -> bool {
+    JSON_OUTPUT.load(Ordering::SeqCst)
+}
+
+/// Emit an event, such as `{"event": "installed", "path": ...}`, if `--json` was given.  `fields`
+/// must be a JSON object
+pub(crate) fn event(name: &str, fields: serde_json::Value) {
+    if !is_json() {
+        return;
+    }
+
+    let mut event = json!({ "event": name });
+    if let (Some(event), serde_json::Value::Object(fields)) = (event.as_object_mut(), fields) {
+        event.extend(fields);
+    }
+    println!("{}", event);
+}
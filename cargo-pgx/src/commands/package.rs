@@ -1,14 +1,111 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
-
-use crate::commands::get::get_property;
-use crate::commands::install::install_extension;
-use pgx_utils::{get_pg_config_major_version, get_target_dir, handle_result};
+use crate::commands::get::{get_cargo_toml, get_property};
+use crate::commands::install::{
+    format_display_path, get_git_revision, get_pkglibdir, get_sharedir, get_version,
+    install_extension, InstallOptions,
+};
+use crate::commands::output::event;
+use colored::Colorize;
+use pgx_utils::{exit_with_error, get_pg_config_major_version, get_target_dir, handle_result};
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use std::path::PathBuf;
+use std::process::Command;
+
+/// The distributable package formats `cargo pgx package --format` can build
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PackageFormat {
+    Deb,
+    Rpm,
+}
+
+impl PackageFormat {
+    pub(crate) fn from_name(name: &str) -> Self {
+        match name {
+            "deb" => PackageFormat::Deb,
+            "rpm" => PackageFormat::Rpm,
+            _ => exit_with_error!("unrecognized package format: {}", name),
+        }
+    }
+}
+
+/// The metadata `cargo pgx package --repo-metadata` can write for publishing the packages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RepoMetadata {
+    Apt,
+    Homebrew,
+}
+
+impl RepoMetadata {
+    pub(crate) fn from_name(name: &str) -> Self {
+        match name {
+            "apt" => RepoMetadata::Apt,
+            "homebrew" => RepoMetadata::Homebrew,
+            _ => exit_with_error!("unrecognized repository metadata: {}", name),
+        }
+    }
+}
+
+/// What `cargo pgx package --docker-image` should build
+#[derive(Debug)]
+pub(crate) struct DockerOptions {
+    /// The tag for the image, which is `extname:version-pgXX`, like `my_extension:1.0-pg13`, if
+    /// not given
+    pub tag: Option<String>,
+
+    /// Also create the extension in `template1`, and so in every database, when a container
+    /// initializes its data directory
+    pub create_extension: bool,
+}
+
+pub(crate) fn package_extension(
+    pg_config: &Option<String>,
+    is_debug: bool,
+    options: &InstallOptions,
+    formats: &[PackageFormat],
+    docker: Option<&DockerOptions>,
+    metadata: &[RepoMetadata],
+    download_url: Option<&str>,
+    stamp: bool,
+) {
+    // catch these before spending a build on them
+    if metadata.contains(&RepoMetadata::Apt) && !formats.contains(&PackageFormat::Deb) {
+        exit_with_error!("--repo-metadata apt requires --format deb")
+    }
+    if metadata.contains(&RepoMetadata::Homebrew) && download_url.is_none() {
+        exit_with_error!("--repo-metadata homebrew requires --download-url")
+    }
+    if stamp && get_git_revision().is_none() {
+        exit_with_error!(
+            "--stamp requires a git checkout, or the revision in $PGX_BUILD_GIT_REVISION"
+        )
+    }
 
-pub(crate) fn package_extension(pg_config: &Option<String>, is_debug: bool) {
-    let base_path = build_base_path(pg_config, is_debug);
+    // the official images lay Postgres out like Debian's packages do, which is unlikely to be
+    // where the local `pg_config` says
+    let docker_options;
+    let options = match docker {
+        Some(_) => {
+            let pgver = get_pg_config_major_version(pg_config);
+            docker_options = InstallOptions {
+                pkglibdir: options
+                    .pkglibdir
+                    .clone()
+                    .or_else(|| Some(format!("/usr/lib/postgresql/{}/lib", pgver).into())),
+                sharedir: options
+                    .sharedir
+                    .clone()
+                    .or_else(|| Some(format!("/usr/share/postgresql/{}", pgver).into())),
+                ..options.clone()
+            };
+            &docker_options
+        }
+        None => options,
+    };
+
+    let base_path = build_base_path(pg_config, is_debug, &options.target);
 
     if base_path.exists() {
         handle_result!(
@@ -26,14 +123,638 @@ pub(crate) fn package_extension(pg_config: &Option<String>, is_debug: bool) {
             std::fs::create_dir_all(&base_path)
         )
     }
-    install_extension(pg_config, !is_debug, Some(base_path));
+    install_extension(pg_config, !is_debug, Some(base_path.clone()), options);
+
+    // the package directory already mirrors the paths `pg_config` told us to install to, so
+    // each package just needs to carry its contents as-is
+    let info = PackageInfo::new(
+        get_pg_config_major_version(pg_config),
+        &options.target,
+        is_debug,
+        stamp,
+    );
+    event(
+        "package",
+        json!({
+            "format": "directory",
+            "path": base_path,
+            "extname": info.extname,
+            "version": info.version,
+            "pg_version": info.pgver,
+            "arch": info.arch,
+            "git_revision": info.git_revision,
+        }),
+    );
+    let mut debs = Vec::new();
+    for format in formats {
+        match format {
+            PackageFormat::Deb => debs.push(build_deb(&base_path, &info)),
+            PackageFormat::Rpm => build_rpm(&base_path, &info),
+        }
+    }
+    for kind in metadata {
+        match kind {
+            RepoMetadata::Apt => write_apt_packages(&debs),
+            RepoMetadata::Homebrew => build_homebrew_formula(
+                &base_path,
+                &info,
+                &options
+                    .pkglibdir
+                    .clone()
+                    .unwrap_or_else(|| get_pkglibdir(pg_config)),
+                &options
+                    .sharedir
+                    .clone()
+                    .unwrap_or_else(|| get_sharedir(pg_config)),
+                download_url.unwrap(),
+            ),
+        }
+    }
+    if let Some(docker) = docker {
+        build_docker_image(&base_path, &info, docker);
+    }
 }
 
-fn build_base_path(pg_config: &Option<String>, is_debug: bool) -> PathBuf {
-    let mut target_dir = get_target_dir();
+fn build_base_path(pg_config: &Option<String>, is_debug: bool, target: &Option<String>) -> PathBuf {
     let pgver = get_pg_config_major_version(pg_config);
     let extname = get_property("extname").expect("could not determine extension name");
-    target_dir.push(if is_debug { "debug" } else { "release" });
+    let mut target_dir = get_profile_dir(is_debug, target);
     target_dir.push(format!("{}-pg{}", extname, pgver));
     target_dir
 }
+
+/// The directory cargo builds into, like `target/release/` or, when cross-compiling,
+/// `target/aarch64-unknown-linux-gnu/release/`
+fn get_profile_dir(is_debug: bool, target: &Option<String>) -> PathBuf {
+    let mut target_dir = get_target_dir();
+    if let Some(target) = target {
+        target_dir.push(target);
+    }
+    target_dir.push(if is_debug { "debug" } else { "release" });
+    target_dir
+}
+
+/// What we know about the extension for describing it in a package
+struct PackageInfo {
+    extname: String,
+    version: String,
+    pgver: u16,
+    arch: String,
+    description: String,
+    maintainer: String,
+    license: String,
+    homepage: Option<String>,
+    git_revision: Option<String>,
+}
+
+impl PackageInfo {
+    /// With `stamp`, the packages' `version` is the extension's with the git revision, and the
+    /// profile for a debug build, appended, like `1.0.0+git1a2b3c4` or `1.0.0+git1a2b3c4.debug`
+    fn new(pgver: u16, target: &Option<String>, is_debug: bool, stamp: bool) -> Self {
+        let extname = get_property("extname").expect("could not determine extension name");
+        let cargo_toml = get_cargo_toml();
+        let package = cargo_toml.get("package");
+        let cargo_property = |name: &str| {
+            package
+                .and_then(|package| package.get(name))
+                .and_then(|value| match value {
+                    toml::Value::Array(values) => values.first().cloned(),
+                    value => Some(value.clone()),
+                })
+                .and_then(|value| value.as_str().map(|s| s.to_string()))
+        };
+
+        let git_revision = get_git_revision();
+        let mut version = get_version();
+        if let (true, Some(revision)) = (stamp, &git_revision) {
+            let (commit, dirty) = match revision.strip_suffix("-dirty") {
+                Some(commit) => (commit, true),
+                None => (revision.as_str(), false),
+            };
+            version.push_str(&format!("+git{}", commit.get(..7).unwrap_or(commit)));
+            if dirty {
+                version.push_str(".dirty");
+            }
+            if is_debug {
+                version.push_str(".debug");
+            }
+        }
+
+        PackageInfo {
+            description: get_property("comment")
+                .or_else(|| cargo_property("description"))
+                .unwrap_or_else(|| format!("{} extension for PostgreSQL", extname)),
+            maintainer: cargo_property("authors").unwrap_or_else(|| "unknown".to_string()),
+            license: cargo_property("license").unwrap_or_else(|| "unknown".to_string()),
+            homepage: cargo_property("homepage").or_else(|| cargo_property("repository")),
+            version,
+            git_revision,
+            extname,
+            pgver,
+            // the first component of a target triple, like `aarch64-unknown-linux-gnu`, is its
+            // architecture
+            arch: target
+                .as_ref()
+                .and_then(|target| target.split('-').next())
+                .unwrap_or(std::env::consts::ARCH)
+                .to_string(),
+        }
+    }
+}
+
+/// Every file in `dir`, recursively
+fn find_files(dir: &PathBuf, mut files: Vec<PathBuf>) -> Vec<PathBuf> {
+    for entry in handle_result!(
+        format!("failed to read directory `{}`", dir.display()),
+        std::fs::read_dir(dir)
+    ) {
+        let path = handle_result!("failed to read directory entry", entry).path();
+        if path.is_dir() {
+            files = find_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn run_package_command(mut command: Command, what: &str) {
+    let command_str = format!("{:?}", command);
+    let output = handle_result!(format!("failed to run: {}", command_str), command.output());
+    if !output.status.success() {
+        exit_with_error!(
+            "failed to build {}: {}\n\n{}{}",
+            what,
+            command_str,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    }
+}
+
+/// Build a Debian package, named like the `postgresql-NN-extname` packages from
+/// apt.postgresql.org, from the contents of `base_path`, and return its path
+fn build_deb(base_path: &PathBuf, info: &PackageInfo) -> PathBuf {
+    let name = format!(
+        "postgresql-{}-{}",
+        info.pgver,
+        info.extname.replace('_', "-")
+    );
+    let arch = match info.arch.as_str() {
+        "x86_64" => "amd64",
+        "x86" | "i586" | "i686" => "i386",
+        "aarch64" => "arm64",
+        "powerpc64" | "powerpc64le" => "ppc64el",
+        other => other,
+    };
+    let installed_size = find_files(base_path, Vec::new())
+        .iter()
+        .filter_map(|file| file.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum::<u64>()
+        / 1024;
+
+    // dpkg-deb wants the control file inside the tree it packages, so it only lives there
+    // while we build
+    let mut debian_dir = base_path.clone();
+    debian_dir.push("DEBIAN");
+    handle_result!(
+        "failed to create DEBIAN directory",
+        std::fs::create_dir_all(&debian_dir)
+    );
+    let mut control = debian_dir.clone();
+    control.push("control");
+    handle_result!(
+        format!("failed to write {}", control.display()),
+        std::fs::write(
+            &control,
+            format!(
+                "Package: {}\nVersion: {}\nArchitecture: {}\nMaintainer: {}\nInstalled-Size: {}\nSection: database\nPriority: optional\nDescription: {}\n",
+                name, info.version, arch, info.maintainer, installed_size, info.description
+            )
+        )
+    );
+
+    let mut deb = base_path.parent().unwrap().to_path_buf();
+    deb.push(format!("{}_{}_{}.deb", name, info.version, arch));
+    status!(
+        "{} `{}`",
+        "    Building".bold().green(),
+        format_display_path(&deb)
+    );
+
+    let mut command = Command::new("dpkg-deb");
+    command
+        .arg("--root-owner-group")
+        .arg("--build")
+        .arg(base_path)
+        .arg(&deb);
+    run_package_command(command, "Debian package");
+    event("package", json!({ "format": "deb", "path": deb }));
+
+    handle_result!(
+        "failed to remove DEBIAN directory",
+        std::fs::remove_dir_all(&debian_dir)
+    );
+    deb
+}
+
+/// Build an RPM, named like the `extname_NN` packages from yum.postgresql.org, from the
+/// contents of `base_path`
+fn build_rpm(base_path: &PathBuf, info: &PackageInfo) {
+    let name = format!("{}_{}", info.extname, info.pgver);
+    let output_dir = base_path.parent().unwrap().to_path_buf();
+    let mut topdir = output_dir.clone();
+    topdir.push("rpmbuild");
+    if topdir.exists() {
+        handle_result!(
+            format!(
+                "failed to remove existing directory: `{}`",
+                topdir.display()
+            ),
+            std::fs::remove_dir_all(&topdir)
+        );
+    }
+
+    let files = find_files(base_path, Vec::new())
+        .iter()
+        .map(|file| format!("\"/{}\"", file.strip_prefix(base_path).unwrap().display()))
+        .collect::<Vec<_>>();
+
+    let mut spec = topdir.clone();
+    spec.push("SPECS");
+    handle_result!(
+        "failed to create rpmbuild directory",
+        std::fs::create_dir_all(&spec)
+    );
+    spec.push(format!("{}.spec", name));
+    handle_result!(
+        format!("failed to write {}", spec.display()),
+        std::fs::write(
+            &spec,
+            format!(
+                "%global debug_package %{{nil}}\n%global __strip /bin/true\n%global _build_id_links none\n\n\
+                 Name: {name}\nVersion: {version}\nRelease: 1\nSummary: {description}\nLicense: {license}\nPackager: {maintainer}\nAutoReqProv: no\n\n\
+                 %description\n{description}\n\n\
+                 %install\nmkdir -p %{{buildroot}}\ncp -a \"{base_path}/.\" %{{buildroot}}/\n\n\
+                 %files\n{files}\n",
+                name = name,
+                version = info.version.replace('-', "_"),
+                description = info.description,
+                license = info.license,
+                maintainer = info.maintainer,
+                base_path = base_path.display(),
+                files = files.join("\n")
+            )
+        )
+    );
+
+    status!("{} RPM for `{}`", "    Building".bold().green(), name);
+    let mut command = Command::new("rpmbuild");
+    command
+        .arg("-bb")
+        .arg("--target")
+        .arg(&info.arch)
+        .arg("--define")
+        .arg(format!("_topdir {}", topdir.display()))
+        .arg(&spec);
+    run_package_command(command, "RPM");
+
+    let mut rpms = topdir.clone();
+    rpms.push("RPMS");
+    for rpm in find_files(&rpms, Vec::new()) {
+        let mut dest = output_dir.clone();
+        dest.push(rpm.file_name().unwrap());
+        handle_result!(
+            format!("failed to copy {}", rpm.display()),
+            std::fs::copy(&rpm, &dest)
+        );
+        event("package", json!({ "format": "rpm", "path": dest }));
+        status!(
+            "{} `{}`",
+            "     Writing".bold().green(),
+            format_display_path(&dest)
+        );
+    }
+}
+
+/// Add `debs` to the `Packages` index, and its `Packages.gz`, of the flat apt repository that's
+/// the directory they're in.  Entries for other packages, like those for other Postgres versions
+/// packaged earlier, are kept, so one directory can serve them all with a `sources.list` line like
+/// `deb https://example.com/repo ./`
+fn write_apt_packages(debs: &[PathBuf]) {
+    let repo_dir = match debs.first() {
+        Some(deb) => deb.parent().unwrap().to_path_buf(),
+        None => return,
+    };
+    let mut packages = repo_dir.clone();
+    packages.push("Packages");
+
+    let filenames = debs
+        .iter()
+        .map(|deb| format!("Filename: ./{}", deb.file_name().unwrap().to_string_lossy()))
+        .collect::<Vec<_>>();
+    let mut stanzas = match std::fs::read_to_string(&packages) {
+        Ok(existing) => existing
+            .split("\n\n")
+            .map(|stanza| stanza.trim().to_string())
+            .filter(|stanza| {
+                !stanza.is_empty()
+                    && !stanza
+                        .lines()
+                        .any(|line| filenames.iter().any(|filename| line == filename))
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => Vec::new(),
+    };
+
+    for (deb, filename) in debs.iter().zip(filenames) {
+        // the control file's fields, plus where to find the package and how to check it
+        let output = handle_result!(
+            format!("failed to run dpkg-deb --field {}", deb.display()),
+            Command::new("dpkg-deb").arg("--field").arg(deb).output()
+        );
+        if !output.status.success() {
+            exit_with_error!(
+                "failed to read the control fields of {}:\n\n{}",
+                deb.display(),
+                String::from_utf8_lossy(&output.stderr)
+            )
+        }
+        let contents = handle_result!(
+            format!("failed to read {}", deb.display()),
+            std::fs::read(deb)
+        );
+        stanzas.push(format!(
+            "{}\n{}\nSize: {}\nSHA256: {:x}",
+            String::from_utf8_lossy(&output.stdout).trim(),
+            filename,
+            contents.len(),
+            Sha256::digest(&contents)
+        ));
+    }
+    stanzas.sort();
+
+    status!(
+        "{} `{}`",
+        "     Writing".bold().green(),
+        format_display_path(&packages)
+    );
+    let index = format!("{}\n", stanzas.join("\n\n"));
+    handle_result!(
+        format!("failed to write {}", packages.display()),
+        std::fs::write(&packages, &index)
+    );
+
+    let mut command = Command::new("gzip");
+    command
+        .arg("-9")
+        .arg("--no-name")
+        .arg("--force")
+        .arg("--keep")
+        .arg(&packages);
+    run_package_command(command, "Packages.gz");
+    event("package", json!({ "format": "apt", "path": packages }));
+}
+
+/// Build a tarball of the extension's files, laid out under `lib/` and `share/` rather than the
+/// full `pg_config` paths, and a Homebrew formula that downloads it from `download_url` and
+/// installs it where Homebrew's `postgresql@NN` looks for extensions
+fn build_homebrew_formula(
+    base_path: &PathBuf,
+    info: &PackageInfo,
+    pkglibdir: &PathBuf,
+    sharedir: &PathBuf,
+    download_url: &str,
+) {
+    let output_dir = base_path.parent().unwrap().to_path_buf();
+    let mut staging = output_dir.clone();
+    staging.push(format!("{}-pg{}-homebrew", info.extname, info.pgver));
+    if staging.exists() {
+        handle_result!(
+            format!(
+                "failed to remove existing directory: `{}`",
+                staging.display()
+            ),
+            std::fs::remove_dir_all(&staging)
+        );
+    }
+
+    for (dir, name) in &[(pkglibdir, "lib"), (sharedir, "share")] {
+        let mut from = base_path.clone();
+        from.push(dir.strip_prefix("/").unwrap_or(dir.as_path()));
+        if !from.exists() {
+            continue;
+        }
+        for file in find_files(&from, Vec::new()) {
+            let mut dest = staging.clone();
+            dest.push(name);
+            dest.push(file.strip_prefix(&from).unwrap());
+            handle_result!(
+                format!("failed to create {}", dest.parent().unwrap().display()),
+                std::fs::create_dir_all(dest.parent().unwrap())
+            );
+            handle_result!(
+                format!("failed to copy {}", file.display()),
+                std::fs::copy(&file, &dest)
+            );
+        }
+    }
+
+    let mut tarball = output_dir.clone();
+    tarball.push(format!(
+        "{}-{}-pg{}.tar.gz",
+        info.extname, info.version, info.pgver
+    ));
+    status!(
+        "{} `{}`",
+        "    Building".bold().green(),
+        format_display_path(&tarball)
+    );
+    let mut command = Command::new("tar");
+    command
+        .arg("-czf")
+        .arg(&tarball)
+        .arg("-C")
+        .arg(&staging)
+        .arg("lib")
+        .arg("share");
+    run_package_command(command, "tarball");
+    let contents = handle_result!(
+        format!("failed to read {}", tarball.display()),
+        std::fs::read(&tarball)
+    );
+
+    // Homebrew derives the class name from the file name:  `my-ext-pg13.rb` holds `MyExtPg13`
+    let name = format!("{}-pg{}", info.extname.replace('_', "-"), info.pgver);
+    let mut class = String::new();
+    let mut upper = true;
+    for c in name.to_lowercase().chars() {
+        if c == '-' {
+            upper = true;
+        } else if upper {
+            class.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            class.push(c);
+        }
+    }
+    let url = format!(
+        "{}/{}",
+        download_url.trim_end_matches('/'),
+        tarball.file_name().unwrap().to_string_lossy()
+    );
+    let postgresql = format!("postgresql@{}", info.pgver);
+    let mut formula = format!(
+        "class {class} < Formula\n  \
+         desc \"{description}\"\n  \
+         homepage \"{homepage}\"\n  \
+         url \"{url}\"\n  \
+         version \"{version}\"\n  \
+         sha256 \"{sha256:x}\"\n",
+        class = class,
+        description = info.description.replace('"', "\\\""),
+        homepage = info.homepage.as_deref().unwrap_or(download_url),
+        url = url,
+        version = info.version,
+        sha256 = Sha256::digest(&contents),
+    );
+    if info.license != "unknown" {
+        formula.push_str(&format!("  license \"{}\"\n", info.license));
+    }
+    formula.push_str(&format!(
+        "\n  depends_on \"{postgresql}\"\n\n  \
+         def install\n    \
+         (lib/\"{postgresql}\").install Dir[\"lib/*\"]\n    \
+         (share/\"{postgresql}\").install Dir[\"share/*\"]\n  \
+         end\n\
+         end\n",
+        postgresql = postgresql
+    ));
+
+    let mut path = output_dir.clone();
+    path.push(format!("{}.rb", name));
+    status!(
+        "{} `{}`",
+        "     Writing".bold().green(),
+        format_display_path(&path)
+    );
+    handle_result!(
+        format!("failed to write {}", path.display()),
+        std::fs::write(&path, formula)
+    );
+    event(
+        "package",
+        json!({ "format": "homebrew", "path": path, "tarball": tarball, "url": url }),
+    );
+}
+
+/// Build a Docker image that layers the contents of `base_path` onto the official `postgres`
+/// image for the extension's Postgres version.  Its build context, with the `Dockerfile`, is
+/// left in `extname-pgXX-docker/` next to `base_path`
+fn build_docker_image(base_path: &PathBuf, info: &PackageInfo, docker: &DockerOptions) {
+    let tag = docker.tag.clone().unwrap_or_else(|| {
+        // tags can't have the `+` of a `--stamp`ed version
+        let version = info
+            .version
+            .chars()
+            .map(|c| match c {
+                'A'..='Z' | 'a'..='z' | '0'..='9' | '_' | '.' | '-' => c,
+                _ => '-',
+            })
+            .collect::<String>();
+        format!(
+            "{}:{}-pg{}",
+            info.extname.to_lowercase(),
+            version,
+            info.pgver
+        )
+    });
+    let mut context = base_path.clone();
+    context.set_file_name(format!("{}-pg{}-docker", info.extname, info.pgver));
+    if context.exists() {
+        handle_result!(
+            format!(
+                "failed to remove existing directory: `{}`",
+                context.display()
+            ),
+            std::fs::remove_dir_all(&context)
+        );
+    }
+
+    // everything we installed goes in `rootfs/`, to be copied into the image's `/` as-is
+    let mut rootfs = context.clone();
+    rootfs.push("rootfs");
+    for file in find_files(base_path, Vec::new()) {
+        let mut dest = rootfs.clone();
+        dest.push(file.strip_prefix(base_path).unwrap());
+        handle_result!(
+            format!("failed to create {}", dest.parent().unwrap().display()),
+            std::fs::create_dir_all(dest.parent().unwrap())
+        );
+        handle_result!(
+            format!("failed to copy {}", file.display()),
+            std::fs::copy(&file, &dest)
+        );
+    }
+
+    let mut dockerfile = format!(
+        "FROM postgres:{pgver}\n\
+         LABEL org.opencontainers.image.title=\"{extname}\" \\\n      \
+         org.opencontainers.image.version=\"{version}\" \\\n      \
+         org.opencontainers.image.description=\"{description}\" \\\n      \
+         org.opencontainers.image.licenses=\"{license}\" \\\n      \
+         org.opencontainers.image.revision=\"{revision}\"\n\
+         COPY rootfs/ /\n",
+        pgver = info.pgver,
+        extname = info.extname,
+        version = info.version,
+        description = info.description.replace('"', "\\\""),
+        license = info.license,
+        revision = info.git_revision.as_deref().unwrap_or("unknown"),
+    );
+    if docker.create_extension {
+        // the entrypoint runs these once, after it creates `$POSTGRES_DB`, which is too late for
+        // that database to get the extension from `template1`
+        let script = format!(
+            "#!/bin/sh\n\
+             set -e\n\
+             for db in template1 \"$POSTGRES_DB\"; do\n    \
+             psql -v ON_ERROR_STOP=1 --username \"$POSTGRES_USER\" --dbname \"$db\" \\\n        \
+             -c 'CREATE EXTENSION IF NOT EXISTS \"{}\"'\n\
+             done\n",
+            info.extname
+        );
+        let mut path = context.clone();
+        path.push("create-extension.sh");
+        handle_result!(
+            format!("failed to write {}", path.display()),
+            std::fs::write(&path, script)
+        );
+        dockerfile.push_str(&format!(
+            "COPY create-extension.sh /docker-entrypoint-initdb.d/50-create-{}.sh\n",
+            info.extname
+        ));
+    }
+
+    let mut path = context.clone();
+    path.push("Dockerfile");
+    handle_result!(
+        format!("failed to write {}", path.display()),
+        std::fs::write(&path, dockerfile)
+    );
+
+    status!(
+        "{} image `{}` from `{}`",
+        "    Building".bold().green(),
+        tag,
+        format_display_path(&path)
+    );
+    let mut command = Command::new("docker");
+    command.arg("build").arg("--tag").arg(&tag).arg(&context);
+    run_package_command(command, "Docker image");
+    event(
+        "package",
+        json!({ "format": "docker", "path": context, "tag": tag }),
+    );
+}
@@ -0,0 +1,602 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use crate::commands::get::{find_control_file, get_cargo_toml, get_pgx_metadata, get_property};
+use crate::commands::install::{format_display_path, get_version};
+use crate::commands::output::event;
+use crate::commands::schema::SchemaLayout;
+use colored::Colorize;
+use pgx_utils::{exit_with_error, get_target_dir, handle_result};
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The license names the PGXN Meta Spec knows, for the `license` of `META.json`
+const PGXN_LICENSES: &[&str] = &[
+    "agpl_3",
+    "apache_1_1",
+    "apache_2_0",
+    "artistic_1",
+    "artistic_2",
+    "bsd",
+    "freebsd",
+    "gfdl_1_2",
+    "gfdl_1_3",
+    "gpl_1",
+    "gpl_2",
+    "gpl_3",
+    "lgpl_2_1",
+    "lgpl_3_0",
+    "mit",
+    "mozilla_1_0",
+    "mozilla_1_1",
+    "openssl",
+    "perl_5",
+    "postgresql",
+    "qpl_1_0",
+    "ssleay",
+    "sun",
+    "zlib",
+    "open_source",
+    "restricted",
+    "unrestricted",
+    "unknown",
+];
+
+/// Build a source distribution for PGXN in `./target/pgxn/`:  the crate's sources, its `./sql/`
+/// directory with the freshly generated schema, and a `META.json` describing it, which is checked
+/// against the PGXN Meta Spec before anything is archived
+pub(crate) fn package_pgxn(no_schema: bool) {
+    let meta = make_meta();
+    let problems = validate_meta(&meta);
+    if !problems.is_empty() {
+        exit_with_error!(
+            "META.json doesn't conform to the PGXN Meta Spec:\n  {}",
+            problems.join("\n  ")
+        )
+    }
+
+    if !no_schema {
+        handle_result!("failed to generate SQL schema", crate::generate_schema());
+    }
+
+    let name = meta["name"].as_str().unwrap();
+    let version = meta["version"].as_str().unwrap();
+    let dist_name = format!("{}-{}", name, version);
+    let mut output_dir = get_target_dir();
+    output_dir.push("pgxn");
+    let mut staging = output_dir.clone();
+    staging.push(&dist_name);
+    if staging.exists() {
+        handle_result!(
+            format!(
+                "failed to remove existing directory: `{}`",
+                staging.display()
+            ),
+            std::fs::remove_dir_all(&staging)
+        );
+    }
+
+    let mut files = Vec::new();
+    for path in dist_paths() {
+        if path.is_dir() {
+            files = find_files(&path, files);
+        } else if path.exists() {
+            files.push(path);
+        }
+    }
+
+    for file in &files {
+        let mut dest = staging.clone();
+        dest.push(file);
+        handle_result!(
+            format!("failed to create {}", dest.parent().unwrap().display()),
+            std::fs::create_dir_all(dest.parent().unwrap())
+        );
+        handle_result!(
+            format!("failed to copy {}", file.display()),
+            std::fs::copy(file, &dest)
+        );
+    }
+
+    let mut meta_json = staging.clone();
+    meta_json.push("META.json");
+    handle_result!(
+        format!("failed to write {}", meta_json.display()),
+        std::fs::write(
+            &meta_json,
+            format!("{}\n", serde_json::to_string_pretty(&meta).unwrap())
+        )
+    );
+
+    let mut tarball = output_dir.clone();
+    tarball.push(format!("{}.tar.gz", dist_name));
+    status!(
+        "{} `{}`",
+        "    Building".bold().green(),
+        format_display_path(&tarball)
+    );
+    let mut command = Command::new("tar");
+    command
+        .arg("-czf")
+        .arg(&tarball)
+        .arg("-C")
+        .arg(&output_dir)
+        .arg(&dist_name);
+    let command_str = format!("{:?}", command);
+    let output = handle_result!(format!("failed to run: {}", command_str), command.output());
+    if !output.status.success() {
+        exit_with_error!(
+            "failed to build PGXN distribution: {}\n\n{}",
+            command_str,
+            String::from_utf8_lossy(&output.stderr)
+        )
+    }
+
+    event(
+        "package",
+        json!({ "format": "pgxn", "path": tarball, "meta": meta }),
+    );
+}
+
+/// Describe the extension as PGXN's `META.json` does, from `Cargo.toml` and the control file.  A
+/// `META.json` in the crate takes precedence, key by key, for whatever can't be worked out, such
+/// as `tags` beyond the crate's `keywords` or extra `prereqs`
+fn make_meta() -> Value {
+    let extname = get_property("extname").expect("could not determine extension name");
+    let (control_file, _) = find_control_file();
+    let cargo_toml = get_cargo_toml();
+    let package = cargo_toml.get("package");
+    let cargo_property = |name: &str| package.and_then(|package| package.get(name));
+    let cargo_string = |name: &str| {
+        cargo_property(name)
+            .and_then(|value| value.as_str())
+            .map(|s| s.to_string())
+    };
+    let cargo_strings = |name: &str| {
+        cargo_property(name)
+            .and_then(|value| value.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_str().map(|s| s.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+
+    let version = normalize_version(&get_version());
+    let description = cargo_string("description");
+    let abstract_ = get_property("comment").or_else(|| description.clone());
+    let mut meta = json!({
+        "name": extname,
+        "abstract": abstract_.clone().unwrap_or_default(),
+        "version": version,
+        "maintainer": cargo_strings("authors"),
+        "license": cargo_string("license").map_or_else(|| json!("unknown"), |l| pgxn_license(&l)),
+        "provides": {
+            extname.clone(): {
+                "file": control_file,
+                "version": version,
+                "abstract": abstract_,
+            }
+        },
+        "release_status": if version.contains(|c: char| c.is_ascii_alphabetic()) {
+            "testing"
+        } else {
+            "stable"
+        },
+        "generated_by": format!("cargo-pgx {}", env!("CARGO_PKG_VERSION")),
+        "meta-spec": {
+            "version": "1.0.0",
+            "url": "https://pgxn.org/meta/spec.txt",
+        },
+    });
+
+    let meta_object = meta.as_object_mut().unwrap();
+    if description.is_some() && description != abstract_ {
+        meta_object.insert("description".into(), json!(description));
+    }
+
+    let keywords = cargo_strings("keywords");
+    if !keywords.is_empty() {
+        meta_object.insert("tags".into(), json!(keywords));
+    }
+
+    let mut resources = Map::new();
+    if let Some(homepage) = cargo_string("homepage") {
+        resources.insert("homepage".into(), json!(homepage));
+    }
+    if let Some(repository) = cargo_string("repository") {
+        resources.insert(
+            "repository".into(),
+            json!({ "url": repository, "web": repository, "type": "git" }),
+        );
+    }
+    if !resources.is_empty() {
+        meta_object.insert("resources".into(), Value::Object(resources));
+    }
+
+    // the oldest Postgres the crate has a `pgXX` feature for is the oldest it can be built for
+    let oldest_pg = cargo_toml
+        .get("features")
+        .and_then(|features| features.as_table())
+        .and_then(|features| {
+            features
+                .keys()
+                .filter_map(|feature| feature.strip_prefix("pg")?.parse::<u16>().ok())
+                .min()
+        });
+    if let Some(oldest_pg) = oldest_pg {
+        meta_object.insert(
+            "prereqs".into(),
+            json!({ "runtime": { "requires": { "PostgreSQL": format!("{}.0.0", oldest_pg) } } }),
+        );
+    }
+
+    if let Ok(contents) = std::fs::read_to_string("META.json") {
+        let overrides = handle_result!(
+            "META.json is invalid",
+            serde_json::from_str::<Map<String, Value>>(&contents)
+        );
+        meta_object.extend(overrides);
+    }
+
+    meta
+}
+
+/// The files and directories that make up the distribution, relative to the crate
+fn dist_paths() -> Vec<PathBuf> {
+    let (control_file, _) = find_control_file();
+    let mut paths = vec![
+        PathBuf::from("Cargo.toml"),
+        PathBuf::from("Cargo.lock"),
+        PathBuf::from("build.rs"),
+        PathBuf::from(".cargo"),
+        PathBuf::from("src"),
+        PathBuf::from("sql"),
+        control_file,
+    ];
+
+    // and the README, license, and changelog, whatever they're called
+    for entry in handle_result!(
+        "cannot open current directory for reading",
+        std::fs::read_dir(".")
+    ) {
+        let entry = handle_result!("cannot open current directory for reading", entry);
+        let filename = entry.file_name().to_string_lossy().to_uppercase();
+        if [
+            "README",
+            "LICENSE",
+            "LICENCE",
+            "COPYING",
+            "CHANGELOG",
+            "CHANGES",
+        ]
+        .iter()
+        .any(|prefix| filename.starts_with(prefix))
+        {
+            paths.push(PathBuf::from(entry.file_name()));
+        }
+    }
+
+    let layout = SchemaLayout::from_metadata();
+    if !layout.dir.starts_with("sql") && !layout.dir.starts_with("./sql") {
+        paths.push(layout.dir);
+    }
+
+    let metadata = get_pgx_metadata();
+    paths.extend(metadata.libraries);
+    paths.extend(metadata.data);
+
+    // anything outside the crate isn't part of its source
+    paths.retain(|path| path.is_relative() && !path.starts_with(".."));
+    paths
+}
+
+/// Every file in `dir`, recursively
+fn find_files(dir: &PathBuf, mut files: Vec<PathBuf>) -> Vec<PathBuf> {
+    for entry in handle_result!(
+        format!("failed to read directory `{}`", dir.display()),
+        std::fs::read_dir(dir)
+    ) {
+        let path = handle_result!("failed to read directory entry", entry).path();
+        if path.is_dir() {
+            files = find_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// PGXN versions are Semantic Versions, which need all three numbers, so a control file's
+/// `default_version = '1.0'` becomes `1.0.0`
+fn normalize_version(version: &str) -> String {
+    let numbers_end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or_else(|| version.len());
+    let (numbers, rest) = version.split_at(numbers_end);
+    let mut numbers = numbers
+        .split('.')
+        .filter(|n| !n.is_empty())
+        .collect::<Vec<_>>();
+    while numbers.len() < 3 {
+        numbers.push("0");
+    }
+    format!("{}{}", numbers.join("."), rest)
+}
+
+/// Translate a Cargo (SPDX) license expression, such as `MIT OR Apache-2.0`, into PGXN's names
+/// for the licenses.  Those PGXN doesn't have a name for are given by their SPDX URL instead
+fn pgxn_license(expression: &str) -> Value {
+    let licenses = expression
+        .split(|c| c == '/' || c == '(' || c == ')')
+        .flat_map(|part| part.split(" OR "))
+        .flat_map(|part| part.split(" AND "))
+        .map(|license| license.trim())
+        .filter(|license| !license.is_empty())
+        .collect::<Vec<_>>();
+
+    let names = licenses
+        .iter()
+        .map(|license| match *license {
+            "AGPL-3.0" | "AGPL-3.0-only" | "AGPL-3.0-or-later" => Some("agpl_3"),
+            "Apache-1.1" => Some("apache_1_1"),
+            "Apache-2.0" => Some("apache_2_0"),
+            "Artistic-1.0" => Some("artistic_1"),
+            "Artistic-2.0" => Some("artistic_2"),
+            "BSD-3-Clause" | "BSD-4-Clause" => Some("bsd"),
+            "BSD-2-Clause" | "BSD-2-Clause-FreeBSD" => Some("freebsd"),
+            "GPL-1.0" | "GPL-1.0-only" | "GPL-1.0-or-later" => Some("gpl_1"),
+            "GPL-2.0" | "GPL-2.0-only" | "GPL-2.0-or-later" => Some("gpl_2"),
+            "GPL-3.0" | "GPL-3.0-only" | "GPL-3.0-or-later" => Some("gpl_3"),
+            "LGPL-2.1" | "LGPL-2.1-only" | "LGPL-2.1-or-later" => Some("lgpl_2_1"),
+            "LGPL-3.0" | "LGPL-3.0-only" | "LGPL-3.0-or-later" => Some("lgpl_3_0"),
+            "MIT" => Some("mit"),
+            "MPL-1.0" => Some("mozilla_1_0"),
+            "MPL-1.1" => Some("mozilla_1_1"),
+            "OpenSSL" => Some("openssl"),
+            "PostgreSQL" => Some("postgresql"),
+            "Zlib" => Some("zlib"),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+
+    match names {
+        Some(mut names) => {
+            names.dedup();
+            match names.len() {
+                0 => json!("unknown"),
+                1 => json!(names[0]),
+                _ => json!(names),
+            }
+        }
+
+        // the `{ "name": "url" }` form can't be mixed with PGXN's names, so every license needs it
+        None => Value::Object(
+            licenses
+                .iter()
+                .map(|license| {
+                    (
+                        license.to_string(),
+                        json!(format!("https://spdx.org/licenses/{}.html", license)),
+                    )
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Check `meta` against the parts of the PGXN Meta Spec that PGXN rejects uploads for, returning
+/// a description of each problem
+fn validate_meta(meta: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+    let meta = match meta.as_object() {
+        Some(meta) => meta,
+        None => return vec!["META.json must be a JSON object".to_string()],
+    };
+
+    for key in &[
+        "name",
+        "abstract",
+        "version",
+        "maintainer",
+        "license",
+        "provides",
+        "meta-spec",
+    ] {
+        if meta.get(*key).map_or(true, Value::is_null) {
+            problems.push(format!("`{}` is required", key));
+        }
+    }
+
+    if let Some(name) = meta.get("name").and_then(Value::as_str) {
+        if !is_valid_term(name) || name.len() < 2 {
+            problems.push(format!(
+                "`name` must be at least two characters, without whitespace, slashes, or control characters: {}",
+                name
+            ));
+        }
+    }
+
+    if let Some(abstract_) = meta.get("abstract") {
+        if abstract_.as_str().map_or(true, |s| s.trim().is_empty()) {
+            problems.push(
+                "`abstract` must be a non-empty string.  Set `comment` in the control file, or `description` in Cargo.toml".to_string(),
+            );
+        }
+    }
+
+    if let Some(version) = meta.get("version") {
+        check_version("version", version, &mut problems);
+    }
+
+    if let Some(maintainer) = meta.get("maintainer") {
+        let valid = match maintainer {
+            Value::String(s) => !s.trim().is_empty(),
+            Value::Array(values) => {
+                !values.is_empty() && values.iter().all(|value| value.as_str().is_some())
+            }
+            _ => false,
+        };
+        if !valid {
+            problems.push(
+                "`maintainer` must name at least one maintainer.  Set `authors` in Cargo.toml"
+                    .to_string(),
+            );
+        }
+    }
+
+    if let Some(license) = meta.get("license") {
+        let is_known = |value: &Value| {
+            value
+                .as_str()
+                .map_or(false, |name| PGXN_LICENSES.contains(&name))
+        };
+        let valid = match license {
+            Value::String(_) => is_known(license),
+            Value::Array(values) => !values.is_empty() && values.iter().all(is_known),
+            Value::Object(urls) => {
+                !urls.is_empty() && urls.values().all(|url| url.as_str().is_some())
+            }
+            _ => false,
+        };
+        if !valid {
+            problems.push(format!(
+                "`license` must be one of PGXN's license names ({}), a list of them, or an object of names and URLs: {}",
+                PGXN_LICENSES.join(", "),
+                license
+            ));
+        }
+    }
+
+    match meta.get("provides") {
+        Some(Value::Object(provides)) if !provides.is_empty() => {
+            for (extension, provided) in provides {
+                match provided.as_object() {
+                    Some(provided) => {
+                        if provided.get("file").and_then(Value::as_str).is_none() {
+                            problems.push(format!("`provides.{}.file` is required", extension));
+                        }
+                        match provided.get("version") {
+                            Some(version) => check_version(
+                                &format!("provides.{}.version", extension),
+                                version,
+                                &mut problems,
+                            ),
+                            None => problems
+                                .push(format!("`provides.{}.version` is required", extension)),
+                        }
+                    }
+                    None => problems.push(format!("`provides.{}` must be an object", extension)),
+                }
+            }
+        }
+        Some(_) => problems.push("`provides` must name at least one extension".to_string()),
+        None => {}
+    }
+
+    if let Some(meta_spec) = meta.get("meta-spec") {
+        if meta_spec.get("version").and_then(Value::as_str) != Some("1.0.0") {
+            problems.push("`meta-spec.version` must be \"1.0.0\"".to_string());
+        }
+    }
+
+    if let Some(status) = meta.get("release_status") {
+        if !["stable", "testing", "unstable"].contains(&status.as_str().unwrap_or_default()) {
+            problems.push(format!(
+                "`release_status` must be \"stable\", \"testing\", or \"unstable\": {}",
+                status
+            ));
+        }
+    }
+
+    if let Some(tags) = meta.get("tags") {
+        let valid = tags.as_array().map_or(false, |tags| {
+            tags.iter().all(|tag| {
+                tag.as_str().map_or(false, |tag| {
+                    !tag.is_empty()
+                        && tag.len() <= 255
+                        && !tag.chars().any(|c| c.is_control() || c == '/' || c == '\\')
+                })
+            })
+        });
+        if !valid {
+            problems.push(format!(
+                "`tags` must be a list of strings of up to 255 characters, without slashes or control characters: {}",
+                tags
+            ));
+        }
+    }
+
+    if let Some(prereqs) = meta.get("prereqs") {
+        for (phase, relationships) in prereqs.as_object().into_iter().flatten() {
+            if !["configure", "build", "test", "runtime", "develop"].contains(&phase.as_str()) {
+                problems.push(format!(
+                    "`prereqs.{}` isn't a PGXN prerequisite phase",
+                    phase
+                ));
+            }
+            for (relationship, requirements) in relationships.as_object().into_iter().flatten() {
+                if !["requires", "recommends", "suggests", "conflicts"]
+                    .contains(&relationship.as_str())
+                {
+                    problems.push(format!(
+                        "`prereqs.{}.{}` isn't a PGXN prerequisite relationship",
+                        phase, relationship
+                    ));
+                }
+                for (prereq, version) in requirements.as_object().into_iter().flatten() {
+                    // `0` means any version will do
+                    if version != &json!(0) && version != &json!("0") {
+                        check_version(
+                            &format!("prereqs.{}.{}.{}", phase, relationship, prereq),
+                            version,
+                            &mut problems,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Names can't contain whitespace, slashes, backslashes, or control characters
+fn is_valid_term(term: &str) -> bool {
+    !term.is_empty()
+        && !term
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '/' || c == '\\')
+}
+
+/// PGXN versions follow Semantic Versioning 1.0.0:  `MAJOR.MINOR.PATCH`, optionally followed by
+/// an alphanumeric pre-release, like `1.0.0-beta1`
+fn check_version(key: &str, version: &Value, problems: &mut Vec<String>) {
+    let is_semver = version.as_str().map_or(false, |version| {
+        let numbers_end = version
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or_else(|| version.len());
+        let (numbers, prerelease) = version.split_at(numbers_end);
+        let prerelease = prerelease.strip_prefix('-').unwrap_or(prerelease);
+        let numbers = numbers.split('.').collect::<Vec<_>>();
+
+        numbers.len() == 3
+            && numbers
+                .iter()
+                .all(|n| !n.is_empty() && (*n == "0" || !n.starts_with('0')))
+            && prerelease
+                .chars()
+                .enumerate()
+                .all(|(i, c)| c.is_ascii_alphanumeric() && (i > 0 || c.is_ascii_alphabetic()))
+    });
+
+    if !is_semver {
+        problems.push(format!(
+            "`{}` must be a semantic version, like 1.0.0 or 1.0.0-beta1: {}",
+            key, version
+        ));
+    }
+}
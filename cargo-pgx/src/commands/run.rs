@@ -1,25 +1,69 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
-use crate::commands::install::install_extension;
+use crate::commands::get::get_pgx_metadata;
+use crate::commands::install::{install_extension, InstallOptions};
 use crate::commands::start::start_postgres;
 use crate::commands::stop::stop_postgres;
 use colored::Colorize;
-use pgx_utils::{createdb, get_pg_config, get_psql_path, BASE_POSTGRES_PORT_NO};
+use pgx_utils::{
+    createdb, createextension, createrole, get_pg_config, get_pglog_file, get_psql_path,
+    grant_database, BASE_POSTGRES_PORT_NO,
+};
+use std::collections::BTreeMap;
 use std::process::Command;
 use std::os::unix::process::CommandExt;
 
-pub(crate) fn run_psql(major_version: u16, dbname: &str, is_release: bool) {
+/// The role `psql` should connect as, if not the user that ran `initdb`
+pub(crate) enum RunAs<'a> {
+    User(&'a str),
+    Superuser(&'a str),
+}
+
+/// contrib extensions that only work when loaded via `shared_preload_libraries`
+static PRELOAD_CONTRIB: &[&str] = &["pg_stat_statements"];
+
+pub(crate) fn run_psql(
+    major_version: u16,
+    dbname: &str,
+    is_release: bool,
+    run_as: Option<RunAs>,
+    contrib: &[&str],
+    mut settings: BTreeMap<String, String>,
+    detach: bool,
+) {
     let pg_config = get_pg_config(major_version);
 
     // stop postgres
     stop_postgres(major_version);
 
     // install the extension
-    install_extension(&pg_config, is_release, None);
+    install_extension(&pg_config, is_release, None, &InstallOptions::default());
 
-    // restart postgres
-    start_postgres(major_version);
+    // restart postgres, preloading any contrib extensions that require it along with whatever
+    // libraries the settings already preload
+    let preload = contrib
+        .iter()
+        .filter(|extname| PRELOAD_CONTRIB.contains(extname))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !preload.is_empty() {
+        let libraries = settings
+            .entry("shared_preload_libraries".to_string())
+            .or_default();
+        let mut names = libraries
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        for extname in preload {
+            if !names.iter().any(|name| name == extname) {
+                names.push(extname.to_string());
+            }
+        }
+        *libraries = names.join(",");
+    }
+    start_postgres(major_version, Some(&settings));
 
     // create the named database
     if !createdb(
@@ -36,18 +80,86 @@ pub(crate) fn run_psql(major_version: u16, dbname: &str, is_release: bool) {
         );
     }
 
+    // the extension's functions are granted to these, so they need to exist before it's created
+    create_grant_roles(major_version);
+
+    // create the requested contrib extensions
+    let port = BASE_POSTGRES_PORT_NO + major_version;
+    for extname in contrib {
+        createextension(major_version, "localhost", port, dbname, extname);
+    }
+
+    // create the role we'll be connecting as
+    let user = match run_as {
+        Some(RunAs::User(rolename)) => {
+            createrole(major_version, "localhost", port, rolename, false);
+            grant_database(major_version, "localhost", port, dbname, rolename);
+            Some(rolename)
+        }
+        Some(RunAs::Superuser(rolename)) => {
+            createrole(major_version, "localhost", port, rolename, true);
+            Some(rolename)
+        }
+        None => None,
+    };
+
+    if detach {
+        // leave Postgres running for whatever connects next, and say how to find it
+        println!(
+            "{} Postgres v{} on port {}, logging to {}",
+            "    Detached".bold().green(),
+            major_version,
+            port.to_string().bold().cyan(),
+            get_pglog_file(major_version).display()
+        );
+        println!(
+            "{} `{}` to connect, `{}` to watch its log",
+            "         Run".bold().green(),
+            format!("cargo pgx connect pg{} {}", major_version, dbname)
+                .bold()
+                .yellow(),
+            format!("cargo pgx logs pg{} --follow", major_version)
+                .bold()
+                .yellow()
+        );
+        return;
+    }
+
     // run psql
-    exec_psql(major_version, dbname);
+    exec_psql(major_version, dbname, user, &[]);
+}
+
+/// Create the roles `grant_execute_to` in `[package.metadata.pgx]` names, in the pgx-managed
+/// Postgres `major_version`, if they don't already exist
+pub(crate) fn create_grant_roles(major_version: u16) {
+    for rolename in get_pgx_metadata().grant_execute_to {
+        if !rolename.eq_ignore_ascii_case("public") {
+            createrole(
+                major_version,
+                "localhost",
+                BASE_POSTGRES_PORT_NO + major_version,
+                &rolename,
+                false,
+            );
+        }
+    }
 }
 
-fn exec_psql(major_version: u16, dbname: &str) {
+/// Become `psql`, connected to `dbname` on the pgx-managed Postgres `major_version`.  `args` come
+/// after our own, so any `-d`, `-U`, etc. among them win
+pub(crate) fn exec_psql(major_version: u16, dbname: &str, user: Option<&str>, args: &[&str]) {
     let mut command = Command::new(get_psql_path(major_version));
     command
         .arg("-h")
         .arg("localhost")
         .arg("-p")
-        .arg((BASE_POSTGRES_PORT_NO + major_version).to_string())
-        .arg(dbname);
+        .arg((BASE_POSTGRES_PORT_NO + major_version).to_string());
+
+    if let Some(user) = user {
+        command.arg("-U").arg(user);
+    }
+
+    command.arg("-d").arg(dbname).args(args);
 
     // we'll never return from here as we've now become psql
     panic!("{}", command.exec());
@@ -1,14 +1,21 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
-use crate::commands::get::get_property;
+use crate::commands::get::{get_pgx_metadata, get_property, PgxRole};
+use crate::commands::install::assemble_schema;
+use crate::commands::output::event;
+use crate::commands::schema_graph::{EntityKind, SchemaGraph};
+use colored::Colorize;
 use pgx_utils::{
-    categorize_type, exit_with_error, get_named_capture, handle_result, CategorizedType, ExternArgs,
+    byval_type_layout, categorize_type, exit_with_error, get_named_capture, get_target_dir,
+    handle_result, CategorizedType, ExternArgs, BUILD_INFO_FUNCTION, FUNCTION_REGISTRY_FUNCTION,
+    FUNCTION_STATS_FUNCTIONS, SUPERVISED_WORKERS_FUNCTION,
 };
 use proc_macro2::{Ident, Span, TokenTree};
 use quote::quote;
+use serde_json::json;
 use std::borrow::BorrowMut;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::DirEntry;
 use std::io::{BufRead, Write};
 use std::ops::Deref;
@@ -17,8 +24,13 @@ use std::result::Result;
 use std::str::FromStr;
 use syn::export::ToTokens;
 use syn::export::TokenStream2;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{Attribute, FnArg, Item, ItemFn, Pat, ReturnType, Type};
+use syn::{
+    Attribute, FnArg, Item, ItemFn, Lit, Meta, MetaNameValue, NestedMeta, Pat, ReturnType, Token,
+    Type,
+};
 
 #[derive(Debug)]
 enum OperatorOptions {
@@ -44,44 +56,287 @@ enum CategorizedAttribute {
     Other(Vec<(Span, String)>),
 }
 
+/// Where the generated schema files are written, and how finely they're split up
+#[derive(Debug, Clone)]
+pub(crate) struct SchemaLayout {
+    /// The directory the `*.generated.sql` files go in
+    pub(crate) dir: PathBuf,
+
+    /// Generate a file for each Rust module, rather than for each source file
+    pub(crate) split: bool,
+
+    /// Only generate the SQL for this Postgres major version, leaving out whatever is
+    /// `#[cfg(feature = "pgXX")]`'d for the others.  `None` generates everything
+    pub(crate) pg_version: Option<u16>,
+}
+
+impl SchemaLayout {
+    /// The layout `[package.metadata.pgx]` asks for, which is a file per source file in `./sql/`
+    /// unless it says otherwise
+    pub(crate) fn from_metadata() -> Self {
+        let metadata = get_pgx_metadata();
+        SchemaLayout {
+            dir: metadata.schema_dir.unwrap_or_else(|| PathBuf::from("sql")),
+            split: metadata.split_schema,
+            pg_version: None,
+        }
+    }
+
+    /// The path of the SQL file named `file`.  Generated files are in `dir`, and hand-written ones
+    /// are always in `./sql/`
+    pub(crate) fn path_of(&self, file: &str) -> PathBuf {
+        let mut path = if file.ends_with(".generated.sql") {
+            self.dir.clone()
+        } else {
+            PathBuf::from("sql")
+        };
+        path.push(file);
+        path
+    }
+}
+
+/// What `[package.metadata.pgx]` asks of every function the schema creates, other than
+/// `#[pg_test]` functions
+#[derive(Debug, Default)]
+struct FunctionDefaults {
+    /// Revoke `PUBLIC`'s default `EXECUTE`, so only the roles below (and superusers and the
+    /// extension's owner) can call them
+    revoke_public: bool,
+
+    /// The roles granted `EXECUTE`
+    grant_execute_to: Vec<String>,
+
+    /// The `search_path` to pin `#[pg_extern]` and `#[pg_operator]` functions to, unless they
+    /// pick their own with `set_search_path`
+    search_path: Option<String>,
+}
+
+impl FunctionDefaults {
+    fn from_metadata() -> Self {
+        let metadata = get_pgx_metadata();
+        let mut grant_execute_to = metadata.grant_execute_to;
+        for (rolename, role) in &metadata.roles {
+            if role.grant_execute && !grant_execute_to.contains(rolename) {
+                grant_execute_to.push(rolename.clone());
+            }
+        }
+
+        FunctionDefaults {
+            revoke_public: metadata.revoke_public,
+            grant_execute_to,
+            search_path: metadata.function_search_path,
+        }
+    }
+
+    /// Add the default `search_path` to a function's `args`, if it doesn't set its own
+    fn apply(&self, mut args: HashSet<ExternArgs>) -> HashSet<ExternArgs> {
+        if let Some(search_path) = &self.search_path {
+            if !args
+                .iter()
+                .any(|arg| matches!(arg, ExternArgs::SetSearchPath(_)))
+            {
+                args.insert(ExternArgs::SetSearchPath(search_path.clone()));
+            }
+        }
+        args
+    }
+}
+
 pub(crate) fn generate_schema() -> Result<(), std::io::Error> {
+    generate_schema_with_graph(&SchemaLayout::from_metadata(), None)
+}
+
+/// Generate the extension's schema files, laid out as `layout` says, and, if `graph_file` is given,
+/// also write a graph of the SQL entities they create and how those depend on each other
+pub(crate) fn generate_schema_with_graph(
+    layout: &SchemaLayout,
+    graph_file: Option<PathBuf>,
+) -> Result<(), std::io::Error> {
+    // load-order.txt lists what's generated for every version, so a single version's files
+    // mustn't prune it
+    generate_schema_files(layout, graph_file, layout.pg_version.is_none())
+}
+
+/// Generate the schema for each of the Postgres major `versions` into a `pgXX/` directory within
+/// `layout.dir`, next to the usual files for all of them.  If no SQL is version-gated, every
+/// version's schema is the same and only the usual files are kept
+pub(crate) fn generate_schema_per_version(
+    layout: &SchemaLayout,
+    versions: &[u16],
+    graph_file: Option<PathBuf>,
+) -> Result<(), std::io::Error> {
+    generate_schema_files(layout, graph_file, true)?;
+
+    let mut schemas = Vec::new();
+    for version in versions {
+        let mut version_layout = layout.clone();
+        version_layout.dir = layout.dir.join(format!("pg{}", version));
+        version_layout.pg_version = Some(*version);
+        generate_schema_files(&version_layout, None, false)?;
+        schemas.push((
+            version_layout.dir.clone(),
+            assemble_schema(&version_layout, resolve_load_order(&version_layout)),
+        ));
+    }
+
+    if schemas.windows(2).all(|pair| pair[0].1 == pair[1].1) {
+        for (dir, _) in &schemas {
+            std::fs::remove_dir_all(dir)?;
+        }
+        status!(
+            "{} version-gated SQL, the schema is the same for every Postgres version",
+            "          No".bold().green()
+        );
+    }
+
+    Ok(())
+}
+
+/// Print the extension's whole schema script, its SQL files concatenated in load order as
+/// `cargo pgx install` does, to stdout.  It's generated in the target directory, so nothing in
+/// `./sql/` is touched
+pub(crate) fn print_schema(layout: &SchemaLayout) -> Result<(), std::io::Error> {
+    let mut scratch = layout.clone();
+    scratch.dir = get_target_dir().join("pgx-schema");
+    generate_schema_files(&scratch, None, false)?;
+
+    let schema = assemble_schema(&scratch, resolve_load_order(&scratch));
+    std::fs::remove_dir_all(&scratch.dir)?;
+    print!("{}", schema);
+    Ok(())
+}
+
+fn generate_schema_files(
+    layout: &SchemaLayout,
+    graph_file: Option<PathBuf>,
+    update_load_order: bool,
+) -> Result<(), std::io::Error> {
     let path = PathBuf::from_str("./src").unwrap();
     let files = find_rs_files(&path, Vec::new());
     let default_schema = get_property("schema").unwrap_or_else(|| "public".to_string());
-
-    delete_generated_sql();
+    let defaults = FunctionDefaults::from_metadata();
+    let mut graph = SchemaGraph::default();
+
+    delete_generated_sql(layout);
+
+    // generate everything before writing anything, so each file can say which of the others it
+    // needs loaded first
+    let generated = files
+        .iter()
+        .flat_map(|f| {
+            generate_sql(
+                f,
+                default_schema.clone(),
+                layout.split,
+                layout.pg_version,
+                &defaults,
+                &mut graph,
+            )
+        })
+        .collect::<Vec<_>>();
+    let file_dependencies = graph.file_dependencies();
+
+    // the roles go in a file of their own, which everything generated is loaded after, as any of
+    // it might grant them something
+    let roles = make_role_statements(&get_pgx_metadata().roles);
+    for (file, purpose) in &[
+        (ROLES_SQL_FILE, "the roles in [package.metadata.pgx.roles]"),
+        (INIT_SQL_FILE, "calling #[pg_extern(on_create)] functions"),
+    ] {
+        if generated.iter().any(|(generated, _)| generated == file) {
+            exit_with_error!(
+                "`{}` is generated for {}, so it can't be generated for a source file too",
+                file,
+                purpose
+            );
+        }
+    }
 
     let mut created = Vec::new();
-    files.iter().for_each(|f: &DirEntry| {
-        let statemets = generate_sql(f, default_schema.clone());
-        let (did_write, filename) = write_sql_file(f, statemets);
-
-        // strip the leading ./sql/ from the filenames we generated
-        let mut filename = filename.display().to_string();
-        filename = filename.trim_start_matches("./sql/").to_string();
+    for (sql_file, mut statements) in generated {
+        if !statements.is_empty() {
+            let mut requires = file_dependencies
+                .get(&sql_file)
+                .map_or_else(Vec::new, |requires| requires.iter().cloned().collect());
+            if !roles.is_empty() {
+                requires.insert(0, ROLES_SQL_FILE.to_string());
+            }
+            if !requires.is_empty() {
+                statements.insert(0, format!("-- pgx: requires = {}", requires.join(", ")));
+            }
+        }
 
-        if did_write {
-            created.push(filename);
+        if write_sql_file(layout, &sql_file, statements) {
+            event(
+                "generated",
+                json!({ "file": layout.path_of(&sql_file).display().to_string() }),
+            );
+            created.push(sql_file);
         }
-    });
+    }
 
-    process_schema_load_order(created);
+    // and with none declared, this removes a file left over from when there were
+    if write_sql_file(layout, ROLES_SQL_FILE, roles) {
+        event(
+            "generated",
+            json!({ "file": layout.path_of(ROLES_SQL_FILE).display().to_string() }),
+        );
+        created.push(ROLES_SQL_FILE.to_string());
+    }
+
+    let init = graph
+        .on_create()
+        .iter()
+        .map(|function| format!("SELECT {}();", function))
+        .collect::<Vec<_>>();
+    if write_sql_file(layout, INIT_SQL_FILE, init) {
+        event(
+            "generated",
+            json!({ "file": layout.path_of(INIT_SQL_FILE).display().to_string() }),
+        );
+        created.push(INIT_SQL_FILE.to_string());
+    }
+
+    if update_load_order {
+        process_schema_load_order(created);
+    }
+
+    // resolving the load order now reports cycles and stray files before anything is installed
+    let load_order = resolve_load_order(layout)
+        .into_iter()
+        .map(|(file, _)| file)
+        .collect::<Vec<_>>();
+    event("load_order", json!({ "files": load_order }));
+
+    if let Some(graph_file) = graph_file {
+        graph.write(&graph_file, &load_order)?;
+    }
 
     Ok(())
 }
 
+/// Keep an existing `load-order.txt` in step with the generated files.  Extensions without one
+/// rely entirely on `-- pgx: requires` for their ordering, so we don't create it
 fn process_schema_load_order(mut created: Vec<String>) {
     let filename = PathBuf::from_str("./sql/load-order.txt").unwrap();
+    if !filename.exists() {
+        return;
+    }
     let mut load_order = read_load_order(&filename);
 
     // keep in load oder only those files that a) aren't generated or b) are generated that we just created
     // ie, remove those that are flagged as generated but aren't valid anymore
-    load_order.retain(|v| {
-        !v.ends_with(".generated.sql") || (v.ends_with(".generated.sql") && created.contains(v))
+    load_order.retain(|line| {
+        let (v, _) = parse_load_order_line(line);
+        !v.ends_with(".generated.sql") || (v.ends_with(".generated.sql") && created.contains(&v))
     });
 
     // remove everything from created that is already in load order
-    created.retain(|v| !load_order.contains(v));
+    created.retain(|v| {
+        !load_order
+            .iter()
+            .any(|line| &parse_load_order_line(line).0 == v)
+    });
 
     // append whatever is left in created to load_order as they're new files
     created.sort();
@@ -106,7 +361,34 @@ fn process_schema_load_order(mut created: Vec<String>) {
     });
 }
 
-pub(crate) fn read_load_order(filename: &PathBuf) -> Vec<String> {
+/// Split a line from `load-order.txt` into the SQL file it names and, if it's annotated with one,
+/// the `search_path` that file should be loaded with, ie:
+///
+/// ```text
+/// types.generated.sql search_path=myschema, public
+/// ```
+fn parse_load_order_line(line: &str) -> (String, Option<String>) {
+    let line = line.trim();
+    let (filename, annotation) = match line.find(char::is_whitespace) {
+        Some(idx) => (&line[..idx], line[idx..].trim()),
+        None => (line, ""),
+    };
+
+    if annotation.is_empty() {
+        (filename.to_string(), None)
+    } else if annotation.starts_with("search_path=") {
+        let search_path = annotation.trim_start_matches("search_path=").trim();
+        (filename.to_string(), Some(search_path.to_string()))
+    } else {
+        exit_with_error!(
+            "unrecognized annotation in load-order.txt for `{}`: {}",
+            filename,
+            annotation
+        )
+    }
+}
+
+fn read_load_order(filename: &PathBuf) -> Vec<String> {
     let mut load_order = Vec::new();
 
     if let Ok(file) = std::fs::File::open(&filename) {
@@ -119,8 +401,169 @@ pub(crate) fn read_load_order(filename: &PathBuf) -> Vec<String> {
     load_order
 }
 
-fn write_sql_file(f: &DirEntry, statements: Vec<String>) -> (bool, PathBuf) {
-    let filename = make_sql_filename(f);
+/// Work out the order the extension's SQL files are loaded in, along with the `search_path`
+/// `load-order.txt` annotates each with, if any.
+///
+/// Every `.sql` file in `./sql/`, other than the `extname--*.sql` upgrade scripts, is loaded, as
+/// is every generated file in `layout.dir`.  A file that needs others loaded before it says so
+/// with a `-- pgx: requires = a.sql, b.sql` comment, and the generated files get one naming the
+/// generated files they use things from.  Beyond that, files are loaded in the order
+/// `load-order.txt` lists them, if there is one, and then by name.
+///
+/// It's an error for the requirements to form a cycle, or for a hand-written file to be neither
+/// listed in `load-order.txt` nor part of any `requires`, as that's usually a file someone forgot
+/// to hook up.  A file that needs nothing else can say so with an empty `-- pgx: requires =`
+pub(crate) fn resolve_load_order(layout: &SchemaLayout) -> Vec<(String, Option<String>)> {
+    let extname = get_property("extname").expect("could not determine extension name");
+    let listed = read_load_order(&PathBuf::from_str("./sql/load-order.txt").unwrap())
+        .iter()
+        .map(|line| parse_load_order_line(line))
+        .filter(|(file, _)| !file.is_empty())
+        .collect::<Vec<_>>();
+
+    // hand-written files are in ./sql/ and generated ones are in the layout's directory, which is
+    // usually the same one
+    let mut files = BTreeMap::new();
+    for (dir, generated) in &[(PathBuf::from("sql"), false), (layout.dir.clone(), true)] {
+        for entry in handle_result!(
+            format!("failed to read {}/ directory", dir.display()),
+            std::fs::read_dir(dir)
+        ) {
+            let entry = handle_result!(
+                format!("failed to read {}/ directory", dir.display()),
+                entry
+            );
+            let filename = entry.file_name().into_string().unwrap();
+            if !filename.ends_with(".sql")
+                || filename.starts_with(&format!("{}--", extname))
+                || filename.ends_with(".generated.sql") != *generated
+            {
+                continue;
+            }
+
+            let contents = handle_result!(
+                format!("failed to read {}", entry.path().display()),
+                std::fs::read_to_string(entry.path())
+            );
+            files.insert(filename, requires_directives(&contents));
+        }
+    }
+
+    for (file, _) in &listed {
+        if !files.contains_key(file) {
+            exit_with_error!(
+                "load-order.txt lists `{}`, but there's no {}",
+                file,
+                layout.path_of(file).display()
+            )
+        }
+    }
+
+    let is_required = |file: &str| files.values().flatten().flatten().any(|r| r == file);
+    for (file, requires) in &files {
+        if let Some(required) = requires.iter().flatten().find(|r| !files.contains_key(*r)) {
+            exit_with_error!(
+                "{} requires `{}`, but there's no {}",
+                layout.path_of(file).display(),
+                required,
+                layout.path_of(required).display()
+            )
+        }
+
+        if !file.ends_with(".generated.sql")
+            && requires.is_none()
+            && !is_required(file)
+            && !listed.iter().any(|(f, _)| f == file)
+        {
+            exit_with_error!(
+                "sql/{} is never loaded.  Declare what it needs with a `-- pgx: requires = ...` comment, or list it in load-order.txt",
+                file
+            )
+        }
+    }
+
+    // take the first file, in load-order.txt's order and then by name, whose requirements are
+    // already loaded until there are none left
+    let position = |file: &String| {
+        listed
+            .iter()
+            .position(|(f, _)| f == file)
+            .unwrap_or_else(|| listed.len())
+    };
+    let mut pending = files.keys().cloned().collect::<Vec<_>>();
+    pending.sort_by_key(position);
+
+    let mut load_order: Vec<(String, Option<String>)> = Vec::new();
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|file| {
+            files[file]
+                .iter()
+                .flatten()
+                .all(|required| load_order.iter().any(|(f, _)| f == required))
+        });
+
+        match ready {
+            Some(idx) => {
+                let file = pending.remove(idx);
+                let search_path = listed
+                    .iter()
+                    .find(|(f, _)| f == &file)
+                    .and_then(|(_, search_path)| search_path.clone());
+                load_order.push((file, search_path));
+            }
+            None => exit_with_error!(
+                "the `-- pgx: requires` comments of these SQL files form a cycle: {}",
+                pending.join(", ")
+            ),
+        }
+    }
+
+    // the `#[pg_extern(on_create)]` functions run once everything else is created, wherever
+    // load-order.txt put them
+    if let Some(idx) = load_order.iter().position(|(f, _)| f == INIT_SQL_FILE) {
+        let init = load_order.remove(idx);
+        load_order.push(init);
+    }
+
+    load_order
+}
+
+/// Collect the files named by every `-- pgx: requires = ...` comment in a SQL file.  They're
+/// looked for throughout the file, rather than only at its top, so `extension_sql!()` blocks can
+/// carry them into generated files.  `None` means the file has no such comment at all
+fn requires_directives(contents: &str) -> Option<Vec<String>> {
+    let mut requires: Option<Vec<String>> = None;
+    for line in contents.lines() {
+        let line = line.trim();
+        if !line.starts_with("--") {
+            continue;
+        }
+
+        let comment = line.trim_start_matches('-').trim();
+        if comment.starts_with("pgx:") {
+            let directive = comment.trim_start_matches("pgx:").trim();
+            if directive.starts_with("requires") {
+                let value = directive.trim_start_matches("requires").trim_start();
+                if value.starts_with('=') {
+                    requires.get_or_insert_with(Vec::new).extend(
+                        value
+                            .trim_start_matches('=')
+                            .split(',')
+                            .map(|file| file.trim().to_string())
+                            .filter(|file| !file.is_empty()),
+                    );
+                }
+            }
+        }
+    }
+
+    requires
+}
+
+/// Write the statements generated into `sql_file`, or delete it if there aren't any.  Returns
+/// whether the file was written
+fn write_sql_file(layout: &SchemaLayout, sql_file: &str, statements: Vec<String>) -> bool {
+    let filename = layout.path_of(sql_file);
 
     if statements.is_empty() {
         // delete existing sql file if it exists
@@ -131,7 +574,7 @@ fn write_sql_file(f: &DirEntry, statements: Vec<String>) -> (bool, PathBuf) {
             );
         }
 
-        (false, filename)
+        false
     } else {
         // write the statements out to the sql file
         let mut file = handle_result!(
@@ -149,21 +592,21 @@ fn write_sql_file(f: &DirEntry, statements: Vec<String>) -> (bool, PathBuf) {
             );
         }
 
-        (true, filename)
+        true
     }
 }
 
-fn make_sql_filename(f: &DirEntry) -> PathBuf {
-    PathBuf::from_str(make_sql_filename_from_string(f.path().display().to_string()).as_str())
-        .unwrap()
-}
-
-fn make_sql_filename_from_string(mut sql_filename: String) -> String {
+/// The name of the file generated for `f` or, when the schema is split, for the `mod` nested
+/// within it that `modules` names, ie `lib.tests.generated.sql` for `mod tests` in `src/lib.rs`
+fn make_sql_filename(f: &DirEntry, modules: &[String]) -> String {
+    let mut sql_filename = f.path().display().to_string();
     sql_filename = sql_filename.trim_start_matches("./src/").to_string();
-    sql_filename = sql_filename.trim_start_matches("./sql/").to_string();
     sql_filename = sql_filename.trim_end_matches(".rs").to_string();
     sql_filename = sql_filename.replace("/", "_");
-    sql_filename.insert_str(0, "./sql/");
+    for module in modules {
+        sql_filename.push('.');
+        sql_filename.push_str(module);
+    }
     sql_filename.push_str(".generated.sql");
 
     sql_filename
@@ -191,9 +634,12 @@ fn find_rs_files(path: &PathBuf, mut files: Vec<DirEntry>) -> Vec<DirEntry> {
     files
 }
 
-fn delete_generated_sql() {
-    let path = PathBuf::from_str("./sql").unwrap();
-    for f in std::fs::read_dir(&path).unwrap() {
+fn delete_generated_sql(layout: &SchemaLayout) {
+    handle_result!(
+        format!("failed to create {}/ directory", layout.dir.display()),
+        std::fs::create_dir_all(&layout.dir)
+    );
+    for f in std::fs::read_dir(&layout.dir).unwrap() {
         if let Ok(f) = f {
             let filename = f.file_name().into_string().unwrap();
 
@@ -207,12 +653,94 @@ fn delete_generated_sql() {
     }
 }
 
+/// The attributes of the kinds of items the schema is generated from
+fn item_attrs(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Mod(module) => &module.attrs,
+        Item::Struct(strct) => &strct.attrs,
+        Item::Enum(enm) => &enm.attrs,
+        Item::Macro(makro) => &makro.attrs,
+        Item::Fn(func) => &func.attrs,
+        _ => &[],
+    }
+}
+
+/// Would an item with these attributes be compiled for Postgres `pg_version`?  Only its
+/// `#[cfg(...)]`s naming `pgXX` features are considered, as whatever else they test for could go
+/// either way
+fn cfg_enabled(attrs: &[Attribute], pg_version: u16) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("cfg"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => list.nested.first().cloned(),
+            _ => None,
+        })
+        .all(|predicate| eval_cfg(&predicate, pg_version) != Some(false))
+}
+
+/// Evaluate a `#[cfg(...)]` predicate for Postgres `pg_version`, or `None` if it depends on
+/// something other than the `pgXX` features
+fn eval_cfg(predicate: &NestedMeta, pg_version: u16) -> Option<bool> {
+    match predicate {
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+            path,
+            lit: Lit::Str(feature),
+            ..
+        })) if path.is_ident("feature") => {
+            let feature = feature.value();
+            if !feature.starts_with("pg") {
+                return None;
+            }
+            feature
+                .trim_start_matches("pg")
+                .parse::<u16>()
+                .ok()
+                .map(|version| version == pg_version)
+        }
+        NestedMeta::Meta(Meta::List(list)) => {
+            let mut values = list
+                .nested
+                .iter()
+                .map(|predicate| eval_cfg(predicate, pg_version));
+            if list.path.is_ident("not") {
+                values.next().flatten().map(|value| !value)
+            } else if list.path.is_ident("any") {
+                values.fold(Some(false), |any, value| match (any, value) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                })
+            } else if list.path.is_ident("all") {
+                values.fold(Some(true), |all, value| match (all, value) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                })
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
 fn parse_extern_args(att: &Attribute) -> HashSet<ExternArgs> {
     pgx_utils::parse_extern_attributes(att.tokens.clone())
 }
 
-fn generate_sql(rs_file: &DirEntry, default_schema: String) -> Vec<String> {
+/// Generate the statements for `rs_file`, along with the name of the file they go in.  When the
+/// schema is `split`, each `mod` within it gets a file of its own too
+fn generate_sql(
+    rs_file: &DirEntry,
+    default_schema: String,
+    split: bool,
+    pg_version: Option<u16>,
+    defaults: &FunctionDefaults,
+    graph: &mut SchemaGraph,
+) -> Vec<(String, Vec<String>)> {
     let mut sql = Vec::new();
+    let mut modules = Vec::new();
     let file = std::fs::read_to_string(rs_file.path()).unwrap();
     let ast = syn::parse_file(file.as_str()).unwrap();
 
@@ -222,23 +750,40 @@ fn generate_sql(rs_file: &DirEntry, default_schema: String) -> Vec<String> {
     walk_items(
         rs_file,
         &mut sql,
+        if split { Some(&mut modules) } else { None },
         ast.items,
         &mut schema_stack,
         &default_schema,
+        pg_version,
+        defaults,
+        graph,
     );
 
-    sql
+    let mut files = vec![(make_sql_filename(rs_file, &[]), sql)];
+    files.append(&mut modules);
+    files
 }
 
-#[allow(clippy::cognitive_complexity)]
+/// Generate the statements for `items` into `sql`.  When the schema is split, `modules` collects
+/// the files, and their statements, of the `mod`s nested within them
+#[allow(clippy::cognitive_complexity, clippy::too_many_arguments)]
 fn walk_items(
     rs_file: &DirEntry,
     sql: &mut Vec<String>,
+    mut modules: Option<&mut Vec<(String, Vec<String>)>>,
     items: Vec<Item>,
     schema_stack: &mut Vec<String>,
     default_schema: &str,
+    pg_version: Option<u16>,
+    defaults: &FunctionDefaults,
+    graph: &mut SchemaGraph,
 ) {
     let statement_cnt = sql.len();
+    let sql_file = match modules {
+        // the bottom of the stack is the default schema rather than a module
+        Some(_) => make_sql_filename(rs_file, &schema_stack[1..]),
+        None => make_sql_filename(rs_file, &[]),
+    };
     let mut postgres_types = Vec::new();
     let mut operator_sql = Vec::new();
     let current_schema = schema_stack
@@ -246,37 +791,125 @@ fn walk_items(
         .expect("couldn't determine the current schema")
         .clone();
     for item in items {
+        if let Some(pg_version) = pg_version {
+            if !cfg_enabled(item_attrs(&item), pg_version) {
+                continue;
+            }
+        }
+
         if let Item::Mod(module) = item {
             if let Some((_, items)) = module.content {
                 schema_stack.push(module.ident.to_string());
-                walk_items(rs_file, sql, items, schema_stack, default_schema);
+                match modules.as_deref_mut() {
+                    Some(modules) => {
+                        let mut module_sql = Vec::new();
+                        walk_items(
+                            rs_file,
+                            &mut module_sql,
+                            Some(&mut *modules),
+                            items,
+                            schema_stack,
+                            default_schema,
+                            pg_version,
+                            defaults,
+                            graph,
+                        );
+                        modules.push((make_sql_filename(rs_file, &schema_stack[1..]), module_sql));
+                    }
+                    None => walk_items(
+                        rs_file,
+                        sql,
+                        None,
+                        items,
+                        schema_stack,
+                        default_schema,
+                        pg_version,
+                        defaults,
+                        graph,
+                    ),
+                }
                 schema_stack.pop();
             }
         } else if let Item::Struct(strct) = item {
             let mut found_postgres_type = false;
+            let mut passed_by_value = false;
+            let mut archived = false;
+            let mut packed = false;
+            let mut postgres_eq = false;
             for a in strct.attrs {
                 let string = a.to_token_stream().to_string();
 
+                if string.contains("PostgresEq") {
+                    postgres_eq = true;
+                }
+
                 if string.contains("PostgresType") {
                     found_postgres_type = true;
+                } else if string.contains("passedbyvalue") {
+                    passed_by_value = true;
+                } else if string.contains("archived_inoutfuncs") {
+                    archived = true;
+                } else if string.contains("repr") && string.contains("packed") {
+                    packed = true;
                 }
             }
 
             if found_postgres_type {
                 let name = strct.ident.to_string().to_lowercase();
+                graph.add_entity(EntityKind::Type, name.clone(), sql_file.clone());
                 sql.push(format!("CREATE TYPE {}.{};", current_schema, name));
 
                 postgres_types.push(format!("CREATE OR REPLACE FUNCTION {qualified_name}_in(cstring) RETURNS {qualified_name} IMMUTABLE STRICT LANGUAGE C AS 'MODULE_PATHNAME', '{name}_in_wrapper';", qualified_name = qualify_name(&current_schema, &name), name = name));
                 postgres_types.push(format!("CREATE OR REPLACE FUNCTION {qualified_name}_out({qualified_name}) RETURNS cstring IMMUTABLE STRICT LANGUAGE C AS 'MODULE_PATHNAME', '{name}_out_wrapper';", qualified_name = qualify_name(&current_schema, &name), name = name));
-                postgres_types.push(format!(
-                    "CREATE TYPE {qualified_name} (
+                if passed_by_value {
+                    let (size, align) = match byval_type_layout(&strct.fields, packed) {
+                        Ok(layout) => layout,
+                        Err(e) => exit_with_error!("{}: {}", strct.ident, e),
+                    };
+                    let alignment = match align {
+                        1 => "char",
+                        2 => "int2",
+                        4 => "int4",
+                        _ => "double",
+                    };
+                    postgres_types.push(format!(
+                        "CREATE TYPE {qualified_name} (
+                        INTERNALLENGTH = {size},
+                        INPUT = {qualified_name}_in,
+                        OUTPUT = {qualified_name}_out,
+                        PASSEDBYVALUE,
+                        ALIGNMENT = {alignment},
+                        STORAGE = plain
+                    );",
+                        qualified_name = qualify_name(&current_schema, &name),
+                        size = size,
+                        alignment = alignment
+                    ));
+                } else {
+                    // archived values are read in place, so need to be as aligned as anything
+                    // inside them
+                    let alignment = if archived { "double" } else { "int4" };
+                    postgres_types.push(format!(
+                        "CREATE TYPE {qualified_name} (
                         INTERNALLENGTH = variable,
                         INPUT = {qualified_name}_in,
                         OUTPUT = {qualified_name}_out,
+                        ALIGNMENT = {alignment},
                         STORAGE = extended
                     );",
-                    qualified_name = qualify_name(&current_schema, &name)
-                ));
+                        qualified_name = qualify_name(&current_schema, &name),
+                        alignment = alignment
+                    ));
+                }
+
+                if postgres_eq {
+                    operator_sql.append(&mut make_postgres_eq_statements(
+                        graph,
+                        &sql_file,
+                        &current_schema,
+                        &name,
+                    ));
+                }
             }
         } else if let Item::Enum(enm) = item {
             let mut found_postgres_enum = false;
@@ -290,6 +923,7 @@ fn walk_items(
 
             if found_postgres_enum {
                 let name = enm.ident.to_string().to_lowercase();
+                graph.add_entity(EntityKind::Enum, name.clone(), sql_file.clone());
                 sql.push(format!(
                     "CREATE TYPE {qualified_name} AS ENUM (",
                     qualified_name = qualify_name(&current_schema, &name)
@@ -330,6 +964,50 @@ fn walk_items(
 
                 // and remember this sql block
                 sql.push(string.to_string());
+            } else if name.ends_with("extension_config_table") {
+                sql.push(location_comment(rs_file, &makro.span()));
+                sql.append(&mut make_config_table_statements(
+                    makro.mac.tokens,
+                    &current_schema,
+                ));
+            } else if name.ends_with("pg_function_stats")
+                || name.ends_with("pg_function_registry")
+                || name.ends_with("pg_build_info")
+                || name.ends_with("pg_supervised_workers")
+            {
+                let functions = if name.ends_with("pg_function_stats") {
+                    function_stats_functions()
+                } else if name.ends_with("pg_function_registry") {
+                    function_registry_functions()
+                } else if name.ends_with("pg_supervised_workers") {
+                    supervised_workers_functions()
+                } else {
+                    build_info_functions()
+                };
+
+                sql.push(location_comment(rs_file, &makro.span()));
+                for func in functions {
+                    if let (Some(statement), Some(func_name), Some(type_names)) =
+                        make_create_function_statement(
+                            &func,
+                            Some(HashSet::new()),
+                            rs_file,
+                            None,
+                            &current_schema,
+                        )
+                    {
+                        let function_entity = add_function_to_graph(
+                            graph,
+                            &func,
+                            rs_file,
+                            &sql_file,
+                            &qualify_name(&current_schema, &func_name),
+                            &type_names,
+                        );
+                        sql.push(statement);
+                        sql.append(&mut make_privilege_statements(&function_entity, defaults));
+                    }
+                }
             }
         } else if let Item::Fn(func) = item {
             let attributes = collect_attributes(rs_file, &func.sig.ident, &func.attrs);
@@ -343,13 +1021,23 @@ fn walk_items(
                     // if we're in test mode, which is controlled by the PGX_TEST_MODE
                     // environment variable
                     CategorizedAttribute::PgTest((span, _)) if is_test_mode => {
-                        if let (Some(statement), _, _) = make_create_function_statement(
-                            &func,
-                            None,
-                            rs_file,
-                            None,
-                            &current_schema,
-                        ) {
+                        if let (Some(statement), Some(func_name), Some(type_names)) =
+                            make_create_function_statement(
+                                &func,
+                                None,
+                                rs_file,
+                                None,
+                                &current_schema,
+                            )
+                        {
+                            add_function_to_graph(
+                                graph,
+                                &func,
+                                rs_file,
+                                &sql_file,
+                                &qualify_name(&current_schema, &func_name),
+                                &type_names,
+                            );
                             function_sql.push(location_comment(rs_file, &span));
                             function_sql.push(statement);
                         }
@@ -358,15 +1046,77 @@ fn walk_items(
                     // for #[pg_extern] attributes, we only want to programatically generate
                     // a CREATE FUNCTION statement if we don't already have some
                     CategorizedAttribute::PgExtern((span, args)) if function_sql.is_empty() => {
-                        if let (Some(statement), _, _) = make_create_function_statement(
-                            &func,
-                            Some(args),
-                            rs_file,
-                            sql_func_args.clone(),
-                            &current_schema,
-                        ) {
+                        let args = defaults.apply(args);
+                        if let (Some(statement), Some(func_name), Some(type_names)) =
+                            make_create_function_statement(
+                                &func,
+                                Some(args.clone()),
+                                rs_file,
+                                sql_func_args.clone(),
+                                &current_schema,
+                            )
+                        {
+                            let function_entity = add_function_to_graph(
+                                graph,
+                                &func,
+                                rs_file,
+                                &sql_file,
+                                &qualify_name(&current_schema, &func_name),
+                                &type_names,
+                            );
+                            let mut functions = vec![privilege_signature(
+                                &function_entity,
+                                &current_schema,
+                                &func_name,
+                                &sql_func_args,
+                            )];
+                            for extern_arg in &args {
+                                if let ExternArgs::DeprecatedAlias(alias) = extern_arg {
+                                    let alias_entity = format!(
+                                        "{}({})",
+                                        qualify_name(
+                                            &current_schema,
+                                            &quote_ident_string(alias.clone())
+                                        ),
+                                        type_names.join(", ")
+                                    );
+                                    graph.add_entity(
+                                        EntityKind::Function,
+                                        alias_entity.clone(),
+                                        sql_file.clone(),
+                                    );
+                                    graph.add_dependency(&alias_entity, &function_entity);
+                                    functions.push(privilege_signature(
+                                        &alias_entity,
+                                        &current_schema,
+                                        &quote_ident_string(alias.clone()),
+                                        &sql_func_args,
+                                    ));
+                                }
+                            }
+                            if args.contains(&ExternArgs::OnCreate) {
+                                if !type_names.is_empty() {
+                                    exit_with_error!(
+                                        "#[pg_extern(on_create)] function `{}` can't take any arguments",
+                                        func_name
+                                    )
+                                }
+                                graph.add_on_create(qualify_name(&current_schema, &func_name));
+                            }
+
                             function_sql.push(location_comment(rs_file, &span));
                             function_sql.push(statement);
+                            function_sql.append(&mut make_security_statements(
+                                &func,
+                                &args,
+                                rs_file,
+                                &current_schema,
+                                &func_name,
+                            ));
+                            for function in &functions {
+                                function_sql
+                                    .append(&mut make_privilege_statements(function, defaults));
+                            }
                         }
                     }
 
@@ -378,7 +1128,7 @@ fn walk_items(
                         if let (Some(statement), Some(func_name), Some(type_names)) =
                             make_create_function_statement(
                                 &func,
-                                Some(args),
+                                Some(defaults.apply(args)),
                                 rs_file,
                                 sql_func_args.clone(),
                                 &current_schema,
@@ -390,8 +1140,25 @@ fn walk_items(
                                 )
                             }
 
+                            let function_entity = add_function_to_graph(
+                                graph,
+                                &func,
+                                rs_file,
+                                &sql_file,
+                                &qualify_name(&current_schema, &func_name),
+                                &type_names,
+                            );
                             function_sql.push(location_comment(rs_file, &span));
                             function_sql.push(statement);
+                            function_sql.append(&mut make_privilege_statements(
+                                &privilege_signature(
+                                    &function_entity,
+                                    &current_schema,
+                                    &func_name,
+                                    &sql_func_args,
+                                ),
+                                defaults,
+                            ));
 
                             let mut name = None;
 
@@ -408,6 +1175,21 @@ fn walk_items(
                                 )
                             }
 
+                            let operator_entity = format!(
+                                "OPERATOR {}({})",
+                                qualify_name(&current_schema, name.unwrap()),
+                                type_names.join(", ")
+                            );
+                            graph.add_entity(
+                                EntityKind::Operator,
+                                operator_entity.clone(),
+                                sql_file.clone(),
+                            );
+                            graph.add_dependency(&operator_entity, &function_entity);
+                            for type_name in &type_names {
+                                graph.add_type_dependency(&operator_entity, type_name);
+                            }
+
                             let mut sql = String::new();
                             sql.push_str("CREATE OPERATOR ");
                             sql.push_str(&qualify_name(&current_schema, &name.unwrap()));
@@ -487,6 +1269,224 @@ fn walk_items(
     }
 }
 
+/// The generated file that creates the roles `[package.metadata.pgx.roles]` declares
+const ROLES_SQL_FILE: &str = "extension_roles.generated.sql";
+
+/// The generated file that calls the `#[pg_extern(on_create)]` functions.  It's always loaded
+/// last, so they can use anything the extension's script creates
+const INIT_SQL_FILE: &str = "extension_init.generated.sql";
+
+/// Statements that create each of `roles` that doesn't exist yet, and grant them membership in
+/// the roles they're declared a `member_of`.  Roles belong to the cluster rather than the
+/// extension, so they're left in place by `DROP EXTENSION` and may already exist when it's
+/// created again, or in another database
+fn make_role_statements(roles: &BTreeMap<String, PgxRole>) -> Vec<String> {
+    let mut statements = Vec::new();
+    for (rolename, role) in roles {
+        statements.push(format!(
+            "DO $$\n\
+             BEGIN\n    \
+                 IF NOT EXISTS (SELECT 1 FROM pg_catalog.pg_roles WHERE rolname = '{}') THEN\n        \
+                     CREATE ROLE {} {};\n    \
+                 END IF;\n\
+             END\n\
+             $$;",
+            rolename.replace("'", "''"),
+            quote_ident_string(rolename.clone()),
+            if role.login { "LOGIN" } else { "NOLOGIN" }
+        ));
+    }
+
+    // after creating them all, so they can be members of each other in any order.  Granting a
+    // membership that's already been granted only raises a NOTICE
+    for (rolename, role) in roles {
+        for group in &role.member_of {
+            statements.push(format!(
+                "GRANT {} TO {};",
+                quote_ident_string(group.clone()),
+                quote_ident_string(rolename.clone())
+            ));
+        }
+    }
+    statements
+}
+
+/// The `REVOKE` and `GRANT` statements `defaults` asks for on `function`, a signature such as
+/// `schema.func(int4, text)`
+fn make_privilege_statements(function: &str, defaults: &FunctionDefaults) -> Vec<String> {
+    let mut statements = Vec::new();
+    if defaults.revoke_public {
+        statements.push(format!("REVOKE ALL ON FUNCTION {} FROM PUBLIC;", function));
+    }
+    for role in &defaults.grant_execute_to {
+        let role = if role.eq_ignore_ascii_case("public") {
+            "PUBLIC".to_string()
+        } else {
+            quote_ident_string(role.clone())
+        };
+        statements.push(format!(
+            "GRANT EXECUTE ON FUNCTION {} TO {};",
+            function, role
+        ));
+    }
+    statements
+}
+
+/// The signature to `GRANT` on for a function whose graph entity is `function_entity`.  That's
+/// the entity itself unless the function's arguments come from `#[sql_funcargs]`, which the
+/// entity doesn't know the types of, in which case the name alone has to do
+fn privilege_signature(
+    function_entity: &str,
+    schema: &str,
+    func_name: &str,
+    sql_func_args: &Option<String>,
+) -> String {
+    match sql_func_args {
+        Some(_) => qualify_name(schema, func_name),
+        None => function_entity.to_string(),
+    }
+}
+
+/// Record the function in the schema graph, along with the types it takes and returns.  Returns the
+/// name of its graph entity
+fn add_function_to_graph(
+    graph: &mut SchemaGraph,
+    func: &ItemFn,
+    rs_file: &DirEntry,
+    sql_file: &str,
+    qualified_name: &str,
+    type_names: &[String],
+) -> String {
+    let entity = format!("{}({})", qualified_name, type_names.join(", "));
+    graph.add_entity(EntityKind::Function, entity.clone(), sql_file.to_string());
+
+    for type_name in type_names {
+        graph.add_type_dependency(&entity, type_name);
+    }
+
+    if let ReturnType::Type(_, ty) = &func.sig.output {
+        if let Some((return_type, _, _, _)) = translate_type(rs_file, ty) {
+            graph.add_type_dependency(&entity, &return_type);
+        }
+    }
+
+    entity
+}
+
+/// The functions `pgx::pg_function_stats!()` expands to
+fn function_stats_functions() -> Vec<ItemFn> {
+    FUNCTION_STATS_FUNCTIONS
+        .iter()
+        .map(|signature| builtin_function(signature, None))
+        .collect()
+}
+
+/// The function `pgx::pg_function_registry!()` expands to, which is named after the extension in
+/// SQL
+fn function_registry_functions() -> Vec<ItemFn> {
+    vec![builtin_function(
+        FUNCTION_REGISTRY_FUNCTION,
+        Some("functions"),
+    )]
+}
+
+/// The function `pgx::pg_supervised_workers!()` expands to, which is named after the extension in
+/// SQL
+fn supervised_workers_functions() -> Vec<ItemFn> {
+    vec![builtin_function(
+        SUPERVISED_WORKERS_FUNCTION,
+        Some("workers"),
+    )]
+}
+
+/// The function `pgx::pg_build_info!()` expands to, which is named after the extension in SQL
+fn build_info_functions() -> Vec<ItemFn> {
+    vec![builtin_function(BUILD_INFO_FUNCTION, Some("build_info"))]
+}
+
+/// A function with one of the built-in `signature`s from `pgx_utils`, which the macros that
+/// expand to it share.  Only the signature matters here, so its body is empty.  With a
+/// `suffix`, it's named `<extname>_<suffix>` in SQL
+fn builtin_function(signature: &str, suffix: Option<&str>) -> ItemFn {
+    let funcname = match suffix {
+        Some(suffix) => {
+            let extname = get_property("extname").expect("could not determine extension name");
+            format!("/// ```funcname\n/// {}_{}\n/// ```\n", extname, suffix)
+        }
+        None => String::new(),
+    };
+
+    handle_result!(
+        "failed to parse a built-in function",
+        syn::parse_str::<ItemFn>(&format!("{}{} {{}}", funcname, signature))
+    )
+}
+
+/// The functions, operators, and hash operator class `#[derive(PostgresEq)]` needs, which are what
+/// let Postgres find values of the type in arrays of it
+fn make_postgres_eq_statements(
+    graph: &mut SchemaGraph,
+    sql_file: &str,
+    schema: &str,
+    name: &str,
+) -> Vec<String> {
+    let qualified_name = qualify_name(schema, name);
+    let eq = qualify_name(schema, "=");
+    let ne = qualify_name(schema, "<>");
+
+    for opname in &[&eq, &ne] {
+        let operator_entity = format!("OPERATOR {}({}, {})", opname, name, name);
+        graph.add_entity(
+            EntityKind::Operator,
+            operator_entity.clone(),
+            sql_file.to_string(),
+        );
+        graph.add_type_dependency(&operator_entity, name);
+    }
+
+    vec![
+        format!("CREATE OR REPLACE FUNCTION {qualified_name}_eq({qualified_name}, {qualified_name}) RETURNS bool IMMUTABLE STRICT PARALLEL SAFE LANGUAGE C AS 'MODULE_PATHNAME', '{name}_eq_wrapper';", qualified_name = qualified_name, name = name),
+        format!("CREATE OR REPLACE FUNCTION {qualified_name}_ne({qualified_name}, {qualified_name}) RETURNS bool IMMUTABLE STRICT PARALLEL SAFE LANGUAGE C AS 'MODULE_PATHNAME', '{name}_ne_wrapper';", qualified_name = qualified_name, name = name),
+        format!("CREATE OR REPLACE FUNCTION {qualified_name}_hash({qualified_name}) RETURNS integer IMMUTABLE STRICT PARALLEL SAFE LANGUAGE C AS 'MODULE_PATHNAME', '{name}_hash_wrapper';", qualified_name = qualified_name, name = name),
+        format!(
+            "CREATE OPERATOR {eq} (
+   PROCEDURE={qualified_name}_eq,
+   LEFTARG={qualified_name},
+   RIGHTARG={qualified_name},
+   COMMUTATOR=OPERATOR({eq}),
+   NEGATOR=OPERATOR({ne}),
+   RESTRICT=eqsel,
+   JOIN=eqjoinsel,
+   HASHES
+);",
+            eq = eq,
+            ne = ne,
+            qualified_name = qualified_name
+        ),
+        format!(
+            "CREATE OPERATOR {ne} (
+   PROCEDURE={qualified_name}_ne,
+   LEFTARG={qualified_name},
+   RIGHTARG={qualified_name},
+   COMMUTATOR=OPERATOR({ne}),
+   NEGATOR=OPERATOR({eq}),
+   RESTRICT=neqsel,
+   JOIN=neqjoinsel
+);",
+            eq = eq,
+            ne = ne,
+            qualified_name = qualified_name
+        ),
+        format!(
+            "CREATE OPERATOR CLASS {qualified_name}_hash_ops DEFAULT FOR TYPE {qualified_name} USING hash AS
+   OPERATOR 1 {eq} ({qualified_name}, {qualified_name}),
+   FUNCTION 1 {qualified_name}_hash({qualified_name});",
+            eq = eq,
+            qualified_name = qualified_name
+        ),
+    ]
+}
+
 fn qualify_name(schema: &str, name: &str) -> String {
     if "public" == schema {
         name.to_owned()
@@ -509,13 +1509,37 @@ fn make_create_function_statement(
     let sql_func_name =
         extract_funcname_attribute(&attributes).unwrap_or_else(|| quote_ident(&func.sig.ident));
     let mut sql_argument_type_names = Vec::new();
+    let mut sql_type_overrides = extern_args
+        .iter()
+        .flatten()
+        .filter_map(|extern_arg| match extern_arg {
+            ExternArgs::SqlType(name, sql_type) => Some((name.clone(), sql_type.clone())),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let deprecated_aliases = extern_args
+        .iter()
+        .flatten()
+        .filter_map(|extern_arg| match extern_arg {
+            ExternArgs::DeprecatedAlias(alias) => Some(alias.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut forwarded_args = Vec::new();
 
     statement.push_str(&format!(
         "CREATE OR REPLACE FUNCTION {}",
         qualify_name(schema, &sql_func_name)
     ));
+    let signature_start = statement.len();
 
     if let Some(sql_func_arg) = sql_func_arg {
+        if !deprecated_aliases.is_empty() {
+            exit_with_error!(
+                "#[pg_extern(deprecated_alias)] can't be used with #[sql_funcargs] on function {}",
+                func.sig.ident
+            )
+        }
         statement.push_str(sql_func_arg.as_str());
     } else {
         // function arguments
@@ -528,7 +1552,14 @@ fn make_create_function_statement(
                     exit_with_error!("functions that take 'self' are not supported")
                 }
                 FnArg::Typed(ty) => match translate_type(rs_file, &ty.ty) {
-                    Some((type_name, _, default_value, variadic)) => {
+                    Some((mut type_name, _, default_value, variadic)) => {
+                        if let Pat::Ident(ident) = ty.pat.deref() {
+                            if let Some(sql_type) =
+                                sql_type_overrides.remove(&ident.ident.to_string())
+                            {
+                                type_name = sql_type;
+                            }
+                        }
                         sql_argument_type_names.push(type_name.to_string());
 
                         if i > 0 {
@@ -539,6 +1570,9 @@ fn make_create_function_statement(
                         statement.push(' ');
                         if variadic {
                             statement.push_str("VARIADIC ");
+                            forwarded_args.push(format!("VARIADIC ${}", i + 1));
+                        } else {
+                            forwarded_args.push(format!("${}", i + 1));
                         }
                         statement.push_str(&type_name);
 
@@ -589,21 +1623,35 @@ fn make_create_function_statement(
     }
 
     // append RETURNS clause
-    match match &func.sig.output {
+    let returns_start = statement.len();
+    let returns_void = match match &func.sig.output {
         ReturnType::Default => Some(("void".to_string(), false, None, false)),
         ReturnType::Type(_, ty) => translate_type(rs_file, ty),
     } {
         Some((return_type, _is_option, _, _)) => {
-            statement.push_str(&format!(" RETURNS {}", return_type))
+            let return_type = sql_type_overrides.remove("return").unwrap_or(return_type);
+            statement.push_str(&format!(" RETURNS {}", return_type));
+            return_type == "void"
         }
         None => exit_with_error!(
             "could not determine return type for function: {}",
             func.sig.ident
         ),
+    };
+
+    if let Some(name) = sql_type_overrides.keys().next() {
+        exit_with_error!(
+            "#[pg_extern(sql_type)] on function {} names an unknown argument: {}",
+            func.sig.ident,
+            name
+        )
     }
 
-    // modifiers
+    // modifiers, in a fixed order so an unchanged function always generates the same statement
+    let modifiers_start = statement.len();
     if let Some(extern_args) = extern_args {
+        let mut extern_args = extern_args.into_iter().collect::<Vec<_>>();
+        extern_args.sort();
         for extern_arg in extern_args {
             match extern_arg {
                 ExternArgs::Immutable => statement.push_str(" IMMUTABLE"),
@@ -616,15 +1664,71 @@ fn make_create_function_statement(
                 ExternArgs::ParallelRestricted => statement.push_str(" PARALLEL RESTRICTED"),
                 ExternArgs::Error(_) => { /* noop */ }
                 ExternArgs::NoGuard => {}
+                ExternArgs::RequiresPrimary => { /* enforced at runtime by the function wrapper */ }
+                ExternArgs::TrackStats => { /* recorded at runtime by the function wrapper */ }
+                ExternArgs::SqlType(..) => { /* applied to the argument and return types above */ }
+                ExternArgs::DeprecatedAlias(_) => { /* forwarding functions are generated below */ }
+                ExternArgs::SecurityDefiner => statement.push_str(" SECURITY DEFINER"),
+                ExternArgs::OnCreate => { /* called from the generated init file */ }
+                ExternArgs::SetSearchPath(search_path) if search_path.trim().is_empty() => {
+                    statement.push_str(" SET search_path TO ''")
+                }
+                ExternArgs::SetSearchPath(search_path) => {
+                    statement.push_str(&format!(" SET search_path TO {}", search_path))
+                }
+
+                // these generate their own statements in make_security_statements()
+                ExternArgs::SecurityBarrierView(_)
+                | ExternArgs::RlsPolicy(_)
+                | ExternArgs::RlsTable(_)
+                | ExternArgs::RlsCommand(_) => {}
             }
         }
     }
 
+    let signature = statement[signature_start..returns_start].to_string();
+    let returns = statement[returns_start..modifiers_start].to_string();
+    let modifiers = statement[modifiers_start..].to_string();
+
     statement.push_str(&format!(
         " LANGUAGE c AS 'MODULE_PATHNAME', '{}';",
         exported_func_name
     ));
 
+    // a plpgsql function under each old name that warns, then calls through to this one
+    let qualified_func_name = qualify_name(schema, &sql_func_name);
+    let call = format!("{}({})", qualified_func_name, forwarded_args.join(", "));
+    let body = if returns_void {
+        format!("PERFORM {};", call)
+    } else if returns.starts_with(" RETURNS SETOF") || returns.starts_with(" RETURNS TABLE") {
+        format!("RETURN QUERY SELECT * FROM {};", call)
+    } else {
+        format!("RETURN {};", call)
+    };
+    for alias in deprecated_aliases {
+        let message = format!(
+            "function {}() is deprecated, use {}() instead",
+            alias,
+            qualified_func_name.replace('"', "")
+        )
+        .replace('\'', "''");
+        statement.push_str(&format!(
+            "\nCREATE OR REPLACE FUNCTION {alias}{signature}{returns}{modifiers} LANGUAGE plpgsql AS $$ BEGIN RAISE WARNING USING MESSAGE = '{message}', ERRCODE = '01P01'; {body} END; $$;",
+            alias = qualify_name(schema, &quote_ident_string(alias.clone())),
+            signature = signature,
+            returns = returns,
+            modifiers = modifiers,
+            message = message,
+            body = body,
+        ));
+        statement.push_str(&format!(
+            "\nCOMMENT ON FUNCTION {}({}) IS '{}';",
+            qualify_name(schema, &quote_ident_string(alias)),
+            sql_argument_type_names.join(", "),
+            message
+        ));
+    }
+
     (
         Some(statement),
         Some(sql_func_name),
@@ -632,6 +1736,150 @@ fn make_create_function_statement(
     )
 }
 
+/// Generate the `CREATE TABLE` for an `extension_config_table!()`, along with the
+/// `pg_extension_config_dump()` calls that tell `pg_dump` to include its data (and that of any
+/// sequences), rather than assuming `CREATE EXTENSION` will recreate it
+fn make_config_table_statements(tokens: TokenStream2, schema: &str) -> Vec<String> {
+    let args = match Punctuated::<MetaNameValue, Token![,]>::parse_terminated.parse2(tokens) {
+        Ok(args) => args,
+        Err(e) => exit_with_error!("failed to parse extension_config_table!(): {}", e),
+    };
+
+    let (mut name, mut columns, mut filter, mut sequences) = (None, None, None, Vec::new());
+    for arg in args {
+        let key = arg.path.to_token_stream().to_string();
+        let value = match arg.lit {
+            Lit::Str(s) => s.value(),
+            _ => exit_with_error!("extension_config_table!() `{}` must be a string", key),
+        };
+
+        match key.as_str() {
+            "name" => name = Some(value),
+            "columns" => columns = Some(value),
+            "filter" => filter = Some(value),
+            "sequences" => sequences.extend(
+                value
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty()),
+            ),
+            other => exit_with_error!("unrecognized extension_config_table!() argument: {}", other),
+        }
+    }
+
+    let name = qualify_name(
+        schema,
+        &name.unwrap_or_else(|| exit_with_error!("extension_config_table!() requires a `name`")),
+    );
+    let columns =
+        columns.unwrap_or_else(|| exit_with_error!("extension_config_table!() requires `columns`"));
+    let columns = columns.trim_matches('\n');
+
+    let mut statements = vec![
+        format!("CREATE TABLE {} (\n{}\n);", name, columns),
+        format!(
+            "SELECT pg_catalog.pg_extension_config_dump('{}', '{}');",
+            name.replace("'", "''"),
+            filter.unwrap_or_default().replace("'", "''")
+        ),
+    ];
+    for sequence in sequences {
+        statements.push(format!(
+            "SELECT pg_catalog.pg_extension_config_dump('{}', '');",
+            qualify_name(schema, &sequence).replace("'", "''")
+        ));
+    }
+    statements
+}
+
+/// Generate the `CREATE VIEW ... WITH (security_barrier)` and/or `CREATE POLICY` statements
+/// requested by a `#[pg_extern]`'s `security_barrier_view` and `rls_*` arguments
+///
+/// A security barrier view selects everything from the (argument-less) function.  A row level
+/// security policy calls the function, which must return `bool`, with the columns of `rls_table`
+/// named by the function's arguments
+fn make_security_statements(
+    func: &ItemFn,
+    extern_args: &HashSet<ExternArgs>,
+    rs_file: &DirEntry,
+    schema: &str,
+    sql_func_name: &str,
+) -> Vec<String> {
+    let mut statements = Vec::new();
+    let qualified_func_name = qualify_name(schema, sql_func_name);
+    let column_names = func
+        .sig
+        .inputs
+        .iter()
+        .filter(|arg| match arg {
+            FnArg::Typed(ty) => translate_type(rs_file, &ty.ty).is_some(),
+            FnArg::Receiver(_) => false,
+        })
+        .map(arg_name)
+        .collect::<Vec<_>>();
+
+    let (mut policy, mut table, mut command) = (None, None, None);
+    for extern_arg in extern_args {
+        match extern_arg {
+            ExternArgs::SecurityBarrierView(view) => {
+                if !column_names.is_empty() {
+                    exit_with_error!(
+                        "#[pg_extern(security_barrier_view)] function {} must not take any arguments",
+                        func.sig.ident
+                    )
+                }
+
+                statements.push(format!(
+                    "CREATE OR REPLACE VIEW {} WITH (security_barrier) AS SELECT * FROM {}();",
+                    qualify_name(schema, view),
+                    qualified_func_name
+                ));
+            }
+            ExternArgs::RlsPolicy(name) => policy = Some(name),
+            ExternArgs::RlsTable(name) => table = Some(name),
+            ExternArgs::RlsCommand(name) => command = Some(name.to_uppercase()),
+            _ => {}
+        }
+    }
+
+    match (policy, table) {
+        (Some(policy), Some(table)) => {
+            let predicate = format!("{}({})", qualified_func_name, column_names.join(", "));
+            let command = command.unwrap_or_else(|| "ALL".to_string());
+            let clauses = match command.as_str() {
+                "SELECT" | "DELETE" => format!("USING ({})", predicate),
+                "INSERT" => format!("WITH CHECK ({})", predicate),
+                "ALL" | "UPDATE" => format!("USING ({}) WITH CHECK ({})", predicate, predicate),
+                other => exit_with_error!(
+                    "#[pg_extern(rls_command)] of {} must be one of ALL, SELECT, INSERT, UPDATE, or DELETE, not {}",
+                    func.sig.ident,
+                    other
+                ),
+            };
+
+            statements.push(format!("ALTER TABLE {} ENABLE ROW LEVEL SECURITY;", table));
+            statements.push(format!(
+                "CREATE POLICY {} ON {} FOR {} {};",
+                policy, table, command, clauses
+            ));
+        }
+        (None, None) => {
+            if command.is_some() {
+                exit_with_error!(
+                    "#[pg_extern(rls_command)] on {} requires rls_policy and rls_table",
+                    func.sig.ident
+                )
+            }
+        }
+        _ => exit_with_error!(
+            "#[pg_extern] {} must specify both rls_policy and rls_table",
+            func.sig.ident
+        ),
+    }
+
+    statements
+}
+
 fn func_args_have_option(func: &ItemFn, rs_file: &DirEntry) -> bool {
     for arg in &func.sig.inputs {
         if let FnArg::Typed(ty) = arg {
@@ -1006,7 +2254,9 @@ fn translate_type_string(
         }
         _pgvarlena
             if rust_type.starts_with("PgVarlena <")
-                || rust_type.starts_with("pgx :: PgVarlena <") =>
+                || rust_type.starts_with("pgx :: PgVarlena <")
+                || rust_type.starts_with("PgArchived <")
+                || rust_type.starts_with("pgx :: PgArchived <") =>
         {
             let mut extraced_type = extract_type(&rust_type);
             if let Some((rt, dv, v)) = deconstruct_macro(&extraced_type) {
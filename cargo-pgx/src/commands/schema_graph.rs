@@ -0,0 +1,241 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use colored::Colorize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum EntityKind {
+    Type,
+    Enum,
+    Function,
+    Operator,
+}
+
+impl EntityKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            EntityKind::Type => "type",
+            EntityKind::Enum => "enum",
+            EntityKind::Function => "function",
+            EntityKind::Operator => "operator",
+        }
+    }
+
+    fn dot_shape(&self) -> &'static str {
+        match self {
+            EntityKind::Type | EntityKind::Enum => "box",
+            EntityKind::Function => "ellipse",
+            EntityKind::Operator => "diamond",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entity {
+    kind: EntityKind,
+    sql_file: String,
+}
+
+/// The SQL entities `cargo pgx schema` generates, and what each of them depends on
+#[derive(Debug, Default)]
+pub(crate) struct SchemaGraph {
+    entities: BTreeMap<String, Entity>,
+    dependencies: Vec<(String, String)>,
+
+    /// The `#[pg_extern(on_create)]` functions, in the order they were found
+    on_create: Vec<String>,
+}
+
+impl SchemaGraph {
+    pub(crate) fn add_entity(&mut self, kind: EntityKind, name: String, sql_file: String) {
+        self.entities.insert(name, Entity { kind, sql_file });
+    }
+
+    /// Record that `from` depends on the SQL type named `type_name`.  Types that aren't defined by
+    /// this extension (ie, Postgres builtins) are ignored when the graph is written
+    pub(crate) fn add_type_dependency(&mut self, from: &str, type_name: &str) {
+        if let Some(type_name) = normalize_type_name(type_name) {
+            self.add_dependency(from, &type_name);
+        }
+    }
+
+    pub(crate) fn add_dependency(&mut self, from: &str, to: &str) {
+        let edge = (from.to_string(), to.to_string());
+        if !self.dependencies.contains(&edge) {
+            self.dependencies.push(edge);
+        }
+    }
+
+    /// Record that the function named `name` is called once the rest of the schema is created
+    pub(crate) fn add_on_create(&mut self, name: String) {
+        self.on_create.push(name);
+    }
+
+    pub(crate) fn on_create(&self) -> &[String] {
+        &self.on_create
+    }
+
+    /// Dependencies between entities this extension defines
+    fn edges(&self) -> impl Iterator<Item = &(String, String)> {
+        self.dependencies.iter().filter(move |(from, to)| {
+            from != to && self.entities.contains_key(from) && self.entities.contains_key(to)
+        })
+    }
+
+    /// The other generated files each generated file needs loaded before it, because it uses
+    /// something they create
+    pub(crate) fn file_dependencies(&self) -> BTreeMap<String, BTreeSet<String>> {
+        let mut files = BTreeMap::<String, BTreeSet<String>>::new();
+        for (from, to) in self.edges() {
+            let from = &self.entities[from.as_str()].sql_file;
+            let to = &self.entities[to.as_str()].sql_file;
+            if from != to {
+                files.entry(from.clone()).or_default().insert(to.clone());
+            }
+        }
+        files
+    }
+
+    /// An edge is out of order if its dependency is generated into a file that's loaded after the
+    /// file of the entity that needs it
+    fn is_out_of_order(&self, from: &str, to: &str, load_order: &[String]) -> bool {
+        let position = |name: &str| {
+            let file = &self.entities[name].sql_file;
+            load_order.iter().position(|f| f == file)
+        };
+
+        match (position(from), position(to)) {
+            (Some(from), Some(to)) => to > from,
+            _ => false,
+        }
+    }
+
+    /// Write the graph to `path`, as JSON if it ends in `.json`, otherwise as Graphviz dot
+    pub(crate) fn write(&self, path: &PathBuf, load_order: &[String]) -> Result<(), std::io::Error> {
+        let output = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::to_string_pretty(&self.to_json(load_order))?,
+            _ => self.to_dot(load_order),
+        };
+
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(output.as_bytes())?;
+
+        status!(
+            "{} schema graph to {}",
+            "     Writing".bold().green(),
+            path.display()
+        );
+
+        for (from, to) in self.edges() {
+            if self.is_out_of_order(from, to, load_order) {
+                status!(
+                    "{} {} depends on {}, but {} is loaded after {}",
+                    "     Warning".bold().yellow(),
+                    from.bold(),
+                    to.bold(),
+                    self.entities[to.as_str()].sql_file,
+                    self.entities[from.as_str()].sql_file
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn to_json(&self, load_order: &[String]) -> serde_json::Value {
+        let entities = self
+            .entities
+            .iter()
+            .map(|(name, entity)| {
+                json!({
+                    "name": name,
+                    "kind": entity.kind.as_str(),
+                    "file": entity.sql_file,
+                })
+            })
+            .collect::<Vec<_>>();
+        let dependencies = self
+            .edges()
+            .map(|(from, to)| {
+                json!({
+                    "from": from,
+                    "to": to,
+                    "out_of_order": self.is_out_of_order(from, to, load_order),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({ "entities": entities, "dependencies": dependencies })
+    }
+
+    fn to_dot(&self, load_order: &[String]) -> String {
+        let mut dot = String::new();
+        dot.push_str("digraph schema {\n    rankdir=LR;\n");
+
+        // group entities by the file they're generated into
+        let mut files = BTreeMap::<&str, Vec<(&String, &Entity)>>::new();
+        for (name, entity) in &self.entities {
+            files
+                .entry(entity.sql_file.as_str())
+                .or_default()
+                .push((name, entity));
+        }
+
+        for (idx, (file, entities)) in files.iter().enumerate() {
+            dot.push_str(&format!(
+                "    subgraph cluster_{} {{\n        label={};\n",
+                idx,
+                dot_quote(file)
+            ));
+            for (name, entity) in entities {
+                dot.push_str(&format!(
+                    "        {} [shape={}];\n",
+                    dot_quote(name),
+                    entity.kind.dot_shape()
+                ));
+            }
+            dot.push_str("    }\n");
+        }
+
+        for (from, to) in self.edges() {
+            let style = if self.is_out_of_order(from, to, load_order) {
+                " [color=red]"
+            } else {
+                ""
+            };
+            dot.push_str(&format!(
+                "    {} -> {}{};\n",
+                dot_quote(from),
+                dot_quote(to),
+                style
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Reduce an SQL type, such as `SETOF schema.mytype[]`, to the name of the type it's made of
+fn normalize_type_name(type_name: &str) -> Option<String> {
+    let type_name = type_name.trim();
+    if type_name.starts_with("TABLE (") {
+        // composite return types don't depend on anything we can name here
+        return None;
+    }
+
+    let type_name = type_name.trim_start_matches("SETOF ");
+    let type_name = type_name.trim_start_matches("VARIADIC ");
+    let type_name = type_name.trim_end_matches("[]");
+    let type_name = type_name.rsplit('.').next().unwrap_or(type_name);
+
+    Some(type_name.trim_matches('"').to_lowercase())
+}
+
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
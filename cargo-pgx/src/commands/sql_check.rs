@@ -0,0 +1,196 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use colored::Colorize;
+use pgx_utils::{exit_with_error, run_pg_config};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// The SQLSTATE of a syntax error
+const SYNTAX_ERROR: &str = "42601";
+
+/// Check the syntax of `files` with the parser of the Postgres that `pg_config` describes, and exit
+/// with every problem found.  They're run, in order, in a throwaway database of a scratch cluster
+/// started from that installation's own binaries.  As the extension isn't installed there, errors
+/// such as a missing library or type are expected, and only syntax errors count
+pub(crate) fn check_sql_syntax(pg_config: &Option<String>, files: &[PathBuf]) {
+    status!(
+        "{} the syntax of {} SQL files",
+        "    Checking".bold().green(),
+        files.len()
+    );
+
+    let bindir = PathBuf::from(run_pg_config(pg_config, "--bindir"));
+    let cluster = ScratchCluster {
+        dir: std::env::temp_dir().join(format!("pgx-sql-check-{}", std::process::id())),
+        bindir,
+    };
+
+    // the cluster is stopped and removed when it's dropped, so nothing in here may exit
+    let result = cluster.start().and_then(|_| {
+        let mut problems = Vec::new();
+        for file in files {
+            let contents = std::fs::read_to_string(file)
+                .map_err(|e| format!("failed to read {}: {}", file.display(), e))?;
+            let (script, output) = cluster.psql(&script_for(&contents))?;
+            problems.extend(syntax_errors(&file.display().to_string(), &script, &output));
+        }
+        Ok(problems)
+    });
+    drop(cluster);
+
+    match result {
+        Ok(problems) if problems.is_empty() => {}
+        Ok(problems) => exit_with_error!(
+            "the extension's SQL has syntax errors:\n  {}\n\nUse --no-sql-check to install it anyway",
+            problems.join("\n  ")
+        ),
+        Err(e) => exit_with_error!(
+            "unable to check the syntax of the extension's SQL: {}\n\nUse --no-sql-check to install it without checking",
+            e
+        ),
+    }
+}
+
+/// `sql` as CREATE/ALTER EXTENSION would run it:  without its `\echo` lines, and with its
+/// placeholders substituted, keeping everything else on the same line for the line numbers
+fn script_for(sql: &str) -> String {
+    sql.split('\n')
+        .map(|line| {
+            if line.starts_with("\\echo") {
+                String::new()
+            } else {
+                line.replace("@extschema@", "\"_extschema_\"")
+                    .replace("@extowner@", "\"_extowner_\"")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A `file:line: error` for each syntax error in `psql`'s verbose output from running `script`,
+/// which reports them as `psql:SCRIPT:LINE: ERROR:  42601: MESSAGE`.  The line is where the
+/// statement ends
+fn syntax_errors(file: &str, script: &PathBuf, output: &str) -> Vec<String> {
+    let prefix = format!("psql:{}:", script.display());
+    output
+        .lines()
+        .filter_map(|line| {
+            let rest = line.strip_prefix(&prefix)?;
+            let mut parts = rest.splitn(2, ": ERROR:  ");
+            let (lineno, error) = (parts.next()?, parts.next()?);
+            let message = error.strip_prefix(SYNTAX_ERROR)?.trim_start_matches(": ");
+            Some(format!("{}:{}: {}", file, lineno, message))
+        })
+        .collect()
+}
+
+/// A Postgres cluster that lives in `dir` for as long as the check does, and only listens on a
+/// socket there, so it can't get in the way of any other
+struct ScratchCluster {
+    dir: PathBuf,
+    bindir: PathBuf,
+}
+
+impl ScratchCluster {
+    fn start(&self) -> Result<(), String> {
+        let datadir = self.dir.join("data");
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("failed to create {}: {}", self.dir.display(), e))?;
+
+        run(Command::new(self.bindir.join("initdb"))
+            .arg("-D")
+            .arg(&datadir)
+            .arg("-U")
+            .arg("postgres")
+            .arg("-A")
+            .arg("trust")
+            .arg("--no-sync"))?;
+        run(Command::new(self.bindir.join("pg_ctl"))
+            .arg("start")
+            .arg("-w")
+            .arg("-D")
+            .arg(&datadir)
+            .arg("-l")
+            .arg(self.dir.join("postgres.log"))
+            .arg("-o")
+            .arg(format!(
+                "-c listen_addresses='' -k '{}' -c fsync=off",
+                self.dir.display()
+            )))?;
+        run(self
+            .psql_command("postgres")
+            .arg("-c")
+            .arg("CREATE DATABASE pgx_sql_check"))?;
+        Ok(())
+    }
+
+    /// Run `sql` in the throwaway database, carrying on past any errors, and return the script
+    /// `psql` ran it from and what it printed about them
+    fn psql(&self, sql: &str) -> Result<(PathBuf, String), String> {
+        let script = self.dir.join("script.sql");
+        std::fs::write(&script, sql)
+            .map_err(|e| format!("failed to write {}: {}", script.display(), e))?;
+
+        let mut command = self.psql_command("pgx_sql_check");
+        command
+            .arg("-q")
+            .arg("-v")
+            .arg("VERBOSITY=verbose")
+            .arg("-f")
+            .arg(&script)
+            .stdin(Stdio::null());
+        let command_str = format!("{:?}", command);
+
+        let output = command
+            .output()
+            .map_err(|e| format!("failed to run {}: {}", command_str, e))?;
+        Ok((script, String::from_utf8_lossy(&output.stderr).into_owned()))
+    }
+
+    fn psql_command(&self, dbname: &str) -> Command {
+        let mut command = Command::new(self.bindir.join("psql"));
+        command
+            .arg("-X")
+            .arg("-h")
+            .arg(&self.dir)
+            .arg("-U")
+            .arg("postgres")
+            .arg("-d")
+            .arg(dbname);
+        command
+    }
+}
+
+impl Drop for ScratchCluster {
+    fn drop(&mut self) {
+        let datadir = self.dir.join("data");
+        if datadir.join("postmaster.pid").exists() {
+            let _ = run(Command::new(self.bindir.join("pg_ctl"))
+                .arg("stop")
+                .arg("-m")
+                .arg("immediate")
+                .arg("-D")
+                .arg(&datadir));
+        }
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Run `command` to completion, returning everything it printed if it fails
+fn run(command: &mut Command) -> Result<(), String> {
+    let command_str = format!("{:?}", command);
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", command_str, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{}\n\n{}{}",
+            command_str,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
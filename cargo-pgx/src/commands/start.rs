@@ -1,17 +1,31 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
+use crate::commands::get::get_pgx_metadata;
 use crate::commands::status::status_postgres;
+use crate::commands::stop::stop_postgres;
 use colored::Colorize;
 use pgx_utils::{
     exit_with_error, get_pgbin_dir, get_pgdata_dir, get_pglog_file, get_pgx_home, handle_result,
     BASE_POSTGRES_PORT_NO,
 };
+use std::collections::BTreeMap;
 use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process::Stdio;
 
-pub(crate) fn start_postgres(major_version: u16) {
+/// The file in the data directory, included by its `postgresql.conf`, that holds the settings
+/// from `[package.metadata.pgx.postgresql_conf]` and `--set`
+const SETTINGS_FILE: &str = "pgx.conf";
+
+/// Once its log file grows past this, Postgres starts a new one the next time it's started, and
+/// the old one is kept as `{major}.log.1`
+const LOG_ROTATE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Start the pgx-managed Postgres `major_version`, if it isn't already running.  With `settings`,
+/// those become the cluster's custom `postgresql.conf` settings, and a running Postgres is
+/// restarted if they've changed.  Without, whatever settings it last had are left alone
+pub(crate) fn start_postgres(major_version: u16, settings: Option<&BTreeMap<String, String>>) {
     let datadir = get_pgdata_dir(major_version);
     let logfile = get_pglog_file(major_version);
     let bindir = get_pgbin_dir(major_version);
@@ -21,8 +35,22 @@ pub(crate) fn start_postgres(major_version: u16) {
         initdb(&bindir, &datadir);
     }
 
+    let changed = match settings {
+        Some(settings) => write_settings(&datadir, settings),
+        None => false,
+    };
+
     if status_postgres(major_version) {
-        return;
+        if !changed {
+            return;
+        }
+
+        println!(
+            "{} Postgres v{} to apply changed settings",
+            "  Restarting".bold().green(),
+            major_version
+        );
+        stop_postgres(major_version);
     }
 
     println!(
@@ -31,6 +59,13 @@ pub(crate) fn start_postgres(major_version: u16) {
         major_version,
         port.to_string().bold().cyan()
     );
+    rotate_log(&logfile);
+    let options = format!(
+        "-i -p {} -c unix_socket_directories={}",
+        port,
+        get_pgx_home().display()
+    );
+
     let mut command = std::process::Command::new(format!("{}/pg_ctl", bindir.display()));
     // Unsafe block is for the pre_exec setsid call below
     //
@@ -43,11 +78,7 @@ pub(crate) fn start_postgres(major_version: u16) {
             .stderr(Stdio::piped())
             .arg("start")
             .arg("--options")
-            .arg(format!(
-                "-o -i -p {} -c unix_socket_directories={}",
-                port,
-                get_pgx_home().display()
-            ))
+            .arg(format!("-o {}", options))
             .arg("-D")
             .arg(datadir.display().to_string())
             .arg("-l")
@@ -73,6 +104,21 @@ pub(crate) fn start_postgres(major_version: u16) {
     }
 }
 
+/// Move `logfile` aside to `{logfile}.1`, replacing any older one, if it's grown past
+/// `LOG_ROTATE_SIZE`.  Postgres isn't running, so nothing has it open
+fn rotate_log(logfile: &PathBuf) {
+    match std::fs::metadata(logfile) {
+        Ok(metadata) if metadata.len() > LOG_ROTATE_SIZE => {
+            let rotated = PathBuf::from(format!("{}.1", logfile.display()));
+            handle_result!(
+                format!("failed to rotate {}", logfile.display()),
+                std::fs::rename(logfile, &rotated)
+            );
+        }
+        _ => {}
+    }
+}
+
 fn initdb(bindir: &PathBuf, datadir: &PathBuf) {
     println!(
         " {} data directory at {}",
@@ -100,3 +146,73 @@ fn initdb(bindir: &PathBuf, datadir: &PathBuf) {
         )
     }
 }
+
+/// The settings for a pgx-managed Postgres: those in `[package.metadata.pgx.postgresql_conf]`,
+/// overridden by any `--set NAME=VALUE`s
+pub(crate) fn cluster_settings(overrides: &[&str]) -> BTreeMap<String, String> {
+    let mut settings = BTreeMap::new();
+    for (name, value) in get_pgx_metadata().postgresql_conf {
+        let value = match value {
+            toml::Value::String(s) => s,
+            toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                value.to_string()
+            }
+            _ => exit_with_error!(
+                "[package.metadata.pgx.postgresql_conf] setting `{}` must be a string, number, or boolean",
+                name
+            ),
+        };
+        settings.insert(name, value);
+    }
+
+    for setting in overrides {
+        let mut parts = setting.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) if !name.trim().is_empty() => {
+                settings.insert(name.trim().to_string(), value.trim().to_string())
+            }
+            _ => exit_with_error!("--set must be of the form NAME=VALUE: {}", setting),
+        };
+    }
+    settings
+}
+
+/// Write `settings` to the data directory's `SETTINGS_FILE`, making sure `postgresql.conf`
+/// includes it, and return whether they differ from what was there before
+fn write_settings(datadir: &PathBuf, settings: &BTreeMap<String, String>) -> bool {
+    let mut contents =
+        "# written by cargo pgx from [package.metadata.pgx.postgresql_conf] and --set, don't edit\n"
+            .to_string();
+    for (name, value) in settings {
+        contents.push_str(&format!("{} = '{}'\n", name, value.replace('\'', "''")));
+    }
+
+    let mut postgresql_conf = datadir.clone();
+    postgresql_conf.push("postgresql.conf");
+    let conf = handle_result!(
+        format!("failed to read {}", postgresql_conf.display()),
+        std::fs::read_to_string(&postgresql_conf)
+    );
+    let include = format!("include_if_exists = '{}'", SETTINGS_FILE);
+    if !conf.lines().any(|line| line.trim() == include) {
+        handle_result!(
+            format!("failed to write {}", postgresql_conf.display()),
+            std::fs::write(&postgresql_conf, format!("{}\n{}\n", conf, include))
+        );
+    }
+
+    let mut settings_file = datadir.clone();
+    settings_file.push(SETTINGS_FILE);
+    match std::fs::read_to_string(&settings_file) {
+        Ok(existing) if existing == contents => false,
+        // a cluster from before we managed its settings has none of its own
+        Err(_) if settings.is_empty() => false,
+        _ => {
+            handle_result!(
+                format!("failed to write {}", settings_file.display()),
+                std::fs::write(&settings_file, &contents)
+            );
+            true
+        }
+    }
+}
@@ -2,9 +2,77 @@
 // governed by the MIT license that can be found in the LICENSE file.
 
 
-use pgx_utils::{exit_with_error, get_pgbin_dir, get_pgdata_dir, handle_result};
+use colored::Colorize;
+use pgx_utils::{
+    does_db_exist, exit_with_error, get_pgbin_dir, get_pgdata_dir, get_pglog_file, handle_result,
+    installed_extensions, BASE_POSTGRES_PORT_NO,
+};
 use std::process::Stdio;
 
+/// Print whether the pgx-managed Postgres `major_version` is running, where it keeps its data
+/// directory and log file, and, if it's running, which extensions are created in `dbname`
+pub(crate) fn report_status(major_version: u16, dbname: &str) {
+    let datadir = get_pgdata_dir(major_version);
+    if !datadir.exists() {
+        println!(
+            "Postgres v{} is {} (run `{}` to initialize it)",
+            major_version,
+            "not initialized".bold().yellow(),
+            format!("cargo pgx start pg{}", major_version)
+                .bold()
+                .yellow()
+        );
+        return;
+    }
+
+    let is_running = status_postgres(major_version);
+    let port = BASE_POSTGRES_PORT_NO + major_version;
+    if is_running {
+        println!(
+            "Postgres v{} is {}",
+            major_version,
+            "running".bold().green()
+        );
+    } else {
+        println!("Postgres v{} is {}", major_version, "stopped".bold().red());
+    }
+    println!(
+        "{} {}",
+        "        port".bold(),
+        port.to_string().bold().cyan()
+    );
+    println!("{} {}", "    data dir".bold(), datadir.display());
+    println!(
+        "{} {}",
+        "    log file".bold(),
+        get_pglog_file(major_version).display()
+    );
+
+    if !is_running {
+        return;
+    }
+
+    if !does_db_exist(major_version, "localhost", port, dbname) {
+        println!(
+            "{} none (there's no database {})",
+            "  extensions".bold(),
+            dbname
+        );
+        return;
+    }
+
+    let extensions = installed_extensions(major_version, "localhost", port, dbname)
+        .into_iter()
+        .map(|(name, version)| format!("{} {}", name, version))
+        .collect::<Vec<_>>();
+    println!(
+        "{} {} (in database {})",
+        "  extensions".bold(),
+        extensions.join(", "),
+        dbname
+    );
+}
+
 pub(crate) fn status_postgres(major_version: u16) -> bool {
     let datadir = get_pgdata_dir(major_version);
     let bindir = get_pgbin_dir(major_version);
@@ -1,27 +1,370 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
-use pgx_utils::{exit_with_error, get_target_dir, handle_result};
+use crate::commands::get::get_pgx_metadata;
+use crate::commands::install::set_pg_build_flags;
+use crate::commands::output::event;
+use crate::commands::run::RunAs;
+use crate::commands::test_report::{
+    is_binary_start, parse_binary_line, parse_test_line, TestId, TestMatrix,
+};
+use colored::Colorize;
+use pgx_utils::{
+    check_pinned_version, exit_with_error, find_pg_config, get_target_dir, handle_result,
+};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader};
 use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
 
-pub fn test_extension(major_version: u16) {
-    let target_dir = get_target_dir();
-
-    let status = handle_result!(
-        "failed to run cargo test",
-        Command::new("cargo")
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .arg("test")
-            .arg("--all")
-            .arg("--features")
-            .arg(format!("pg{}", major_version))
-            .arg("--no-default-features")
-            .env("CARGO_TARGET_DIR", target_dir.display().to_string())
-            .status()
+/// How `cargo pgx test` should run the suite, beyond which Postgres version it's for
+#[derive(Default)]
+pub(crate) struct TestOptions<'a> {
+    /// The role to run the tests as, instead of the bootstrap superuser
+    pub run_as: Option<RunAs<'a>>,
+
+    /// Only run the tests whose names contain this
+    pub testname: Option<&'a str>,
+
+    /// Install the schema files already in `./sql/` instead of regenerating them
+    pub no_schema: bool,
+
+    /// Test the extension that's already installed instead of building and installing it again
+    pub no_install: bool,
+
+    /// Leave the test Postgres running, with its database set up, when the tests finish
+    pub keep_running: bool,
+
+    /// Skip all setup and run the tests against the Postgres a previous `keep_running` run left
+    /// behind
+    pub existing_cluster: bool,
+
+    /// Only run this machine's share of the tests
+    pub shard: Option<TestShard>,
+
+    /// Custom `postgresql.conf` settings for the test Postgres
+    pub settings: BTreeMap<String, String>,
+
+    /// Only run the test named exactly `testname`
+    pub exact: bool,
+
+    /// Show everything the tests print as they run, not just the output of those that fail
+    pub nocapture: bool,
+
+    /// How many tests to run at once, instead of one per CPU
+    pub test_threads: Option<usize>,
+
+    /// Anything else to pass to the test harness, as given after `--`
+    pub harness_args: Vec<&'a str>,
+}
+
+/// One of `total` deterministic slices of the test suite, as given by `--shard 2/8`, so CI can
+/// split the tests across machines
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TestShard {
+    /// Which slice this is, from 1 to `total`
+    pub index: usize,
+    pub total: usize,
+}
+
+impl TestShard {
+    pub(crate) fn parse(value: &str) -> Self {
+        let mut parts = value.splitn(2, '/');
+        let shard = match (parts.next(), parts.next()) {
+            (Some(index), Some(total)) => match (index.parse(), total.parse()) {
+                (Ok(index), Ok(total)) => Some(TestShard { index, total }),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match shard {
+            Some(shard) if shard.index >= 1 && shard.index <= shard.total => shard,
+            _ => exit_with_error!(
+                "--shard must be of the form INDEX/TOTAL, with INDEX from 1 to TOTAL: {}",
+                value
+            ),
+        }
+    }
+
+    /// This shard's share of `tests`: sorted by name, every `total`th one starting with the
+    /// `index`th.  Every machine lists the same tests, so together the shards cover each test
+    /// exactly once
+    fn select(&self, mut tests: Vec<String>) -> Vec<String> {
+        tests.sort();
+        tests.dedup();
+        tests
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| i % self.total == self.index - 1)
+            .map(|(_, name)| name)
+            .collect()
+    }
+}
+
+/// Run the test suite against `major_version`, recording each test's outcome in `matrix`.  Returns
+/// `false` if `cargo test` failed
+pub(crate) fn test_extension(
+    major_version: u16,
+    options: &TestOptions,
+    matrix: &mut TestMatrix,
+) -> bool {
+    let pg_config = Some(find_pg_config(major_version));
+    check_pinned_version(&pg_config);
+
+    // the name filter and harness arguments for each time `cargo test` is to be run
+    let runs = match &options.shard {
+        Some(shard) => {
+            let tests = list_tests(major_version, options);
+            let count = tests.len();
+            let tests = shard.select(tests);
+            let selected = tests.len();
+            let batches = batch_test_names(tests);
+            status!(
+                "{} {} of {} tests for shard {}/{}{}",
+                "     Running".bold().green(),
+                selected,
+                count,
+                shard.index,
+                shard.total,
+                if batches.len() > 1 {
+                    format!(", in {} batches", batches.len())
+                } else {
+                    String::new()
+                }
+            );
+
+            // without any names, `cargo test` would run everything, so an empty shard has no
+            // batches and isn't run at all
+            batches
+                .into_iter()
+                .map(|batch| {
+                    let mut filter = vec!["--exact".to_string()];
+                    filter.extend(batch);
+                    (None, filter)
+                })
+                .collect::<Vec<_>>()
+        }
+        None => {
+            let mut filter = Vec::new();
+            if options.exact {
+                filter.push("--exact".to_string());
+            }
+            vec![(options.testname, filter)]
+        }
+    };
+
+    let mut success = true;
+    for (testname, filter) in runs {
+        success &= run_cargo_test(major_version, options, &pg_config, testname, filter, matrix);
+    }
+
+    event(
+        "test_run",
+        json!({ "pg_version": major_version, "success": success }),
     );
+    success
+}
+
+/// The most bytes of test names to give a single `cargo test`.  It's well under the smallest
+/// `ARG_MAX` we're likely to run into, macOS' 256KiB, which the environment counts against too
+const MAX_TEST_NAMES_LEN: usize = 64 * 1024;
 
-    if !status.success() {
-        exit_with_error!("cargo pgx test failed with status = {:?}", status.code())
+/// Split `tests` into batches whose names fit on one command line
+fn batch_test_names(tests: Vec<String>) -> Vec<Vec<String>> {
+    let mut batches = Vec::new();
+    let mut batch = Vec::new();
+    let mut len = 0;
+    for test in tests {
+        // each name is also a pointer and a nul terminator in the argument vector
+        let test_len = test.len() + 1 + std::mem::size_of::<usize>();
+        if !batch.is_empty() && len + test_len > MAX_TEST_NAMES_LEN {
+            batches.push(std::mem::take(&mut batch));
+            len = 0;
+        }
+        len += test_len;
+        batch.push(test);
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
     }
+    batches
+}
+
+/// Run `cargo test` once against `major_version`, with `testname` as its name filter and `filter`
+/// ahead of the other harness arguments, and record each test's outcome in `matrix`.  Returns
+/// `false` if it failed
+fn run_cargo_test(
+    major_version: u16,
+    options: &TestOptions,
+    pg_config: &Option<String>,
+    testname: Option<&str>,
+    filter: Vec<String>,
+    matrix: &mut TestMatrix,
+) -> bool {
+    let mut command = Command::new("cargo");
+    command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .arg("test")
+        .arg("--all")
+        .arg("--features")
+        .arg(format!("pg{}", major_version))
+        .arg("--no-default-features")
+        .env("CARGO_TARGET_DIR", get_target_dir().display().to_string());
+    set_pg_build_flags(&mut command, pg_config);
+    if let Some(testname) = testname {
+        command.arg(testname);
+    }
+    let mut harness_args = filter;
+    if options.nocapture {
+        harness_args.push("--nocapture".to_string());
+    }
+    if let Some(test_threads) = options.test_threads {
+        harness_args.push(format!("--test-threads={}", test_threads));
+    }
+    harness_args.extend(options.harness_args.iter().map(|arg| arg.to_string()));
+    if !harness_args.is_empty() {
+        command.arg("--").args(&harness_args);
+    }
+
+    // the test framework picks these up and connects as the requested role
+    match &options.run_as {
+        Some(RunAs::User(rolename)) => {
+            command.env("PGX_TEST_USER", rolename);
+        }
+        Some(RunAs::Superuser(rolename)) => {
+            command.env("PGX_TEST_USER", rolename);
+            command.env("PGX_TEST_USER_IS_SUPERUSER", "true");
+        }
+        None => {}
+    }
+
+    // and these tell it which of its setup steps to skip
+    for (enabled, name) in &[
+        (options.no_schema, "PGX_TEST_NO_SCHEMA"),
+        (options.no_install, "PGX_TEST_NO_INSTALL"),
+        (options.keep_running, "PGX_TEST_KEEP_RUNNING"),
+        (options.existing_cluster, "PGX_TEST_EXISTING_CLUSTER"),
+    ] {
+        if *enabled {
+            command.env(name, "true");
+        }
+    }
+
+    // the roles the extension's functions are granted to, which must exist before it's created
+    let grant_roles = get_pgx_metadata()
+        .grant_execute_to
+        .into_iter()
+        .filter(|rolename| !rolename.eq_ignore_ascii_case("public"))
+        .collect::<Vec<_>>();
+    if !grant_roles.is_empty() {
+        command.env("PGX_TEST_GRANT_ROLES", grant_roles.join(","));
+    }
+
+    // one `name = 'value'` per line, added to the test Postgres' configuration
+    if !options.settings.is_empty() {
+        let settings = options
+            .settings
+            .iter()
+            .map(|(name, value)| format!("{} = '{}'", name, value.replace('\'', "''")))
+            .collect::<Vec<_>>();
+        command.env("PGX_TEST_POSTGRESQL_CONF", settings.join("\n"));
+    }
+
+    let mut child = handle_result!("failed to run cargo test", command.spawn());
+
+    // cargo names each test binary on stderr before running it, so pass stderr through, keeping
+    // the names for the binaries' results as they come in on stdout
+    let stderr = child
+        .stderr
+        .take()
+        .expect("couldn't take cargo test's stderr");
+    let (sender, binaries) = channel();
+    let stderr_thread = std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if let Some(binary) = parse_binary_line(&line) {
+                let _ = sender.send(binary);
+            }
+            eprintln!("{}", line);
+        }
+    });
+
+    // echo the test harness' output as we go, remembering the result of each test
+    let mut results = Vec::new();
+    let mut binary = String::new();
+    let stdout = child.stdout.take().expect("couldn't take cargo test's stdout");
+    for line in BufReader::new(stdout).lines() {
+        let line = handle_result!("failed to read cargo test's output", line);
+        if is_binary_start(&line) {
+            binary = next_binary(&binaries);
+        } else if let Some((name, outcome)) = parse_test_line(&line) {
+            event(
+                "test",
+                json!({ "pg_version": major_version, "binary": binary, "name": name, "outcome": outcome.as_str() }),
+            );
+            let id = TestId {
+                binary: binary.clone(),
+                name,
+            };
+            results.push((id, outcome));
+        }
+        status!("{}", line);
+    }
+    matrix.record(major_version, results);
+    let _ = stderr_thread.join();
+
+    let status = handle_result!("failed to wait for cargo test", child.wait());
+    status.success()
+}
+
+/// The test binary that's started printing its results.  cargo named it on stderr before running
+/// it, but that's read on another thread, which may not have gotten to it yet
+fn next_binary(binaries: &Receiver<String>) -> String {
+    binaries
+        .recv_timeout(Duration::from_secs(5))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// The name of every test `cargo test` would run against `major_version`, or only those that
+/// `options`' `testname` and harness arguments select
+fn list_tests(major_version: u16, options: &TestOptions) -> Vec<String> {
+    let mut command = Command::new("cargo");
+    command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .arg("test")
+        .arg("--all")
+        .arg("--features")
+        .arg(format!("pg{}", major_version))
+        .arg("--no-default-features")
+        .env("CARGO_TARGET_DIR", get_target_dir().display().to_string());
+    set_pg_build_flags(&mut command, &Some(find_pg_config(major_version)));
+    command.arg("--");
+    if let Some(testname) = options.testname {
+        command.arg(testname);
+    }
+    if options.exact {
+        command.arg("--exact");
+    }
+    command.args(&options.harness_args).arg("--list");
+
+    let command_str = format!("{:?}", command);
+    let output = handle_result!(format!("failed to run: {}", command_str), command.output());
+    if !output.status.success() {
+        exit_with_error!("failed to list tests: {}", command_str)
+    }
+
+    // each test binary lists its tests as `name: test`, followed by a summary line
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_suffix(": test"))
+        .map(|name| name.to_string())
+        .collect()
 }
@@ -0,0 +1,316 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use colored::Colorize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TestOutcome {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+impl TestOutcome {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            TestOutcome::Passed => "passed",
+            TestOutcome::Failed => "failed",
+            TestOutcome::Ignored => "ignored",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "passed" => Some(TestOutcome::Passed),
+            "failed" => Some(TestOutcome::Failed),
+            "ignored" => Some(TestOutcome::Ignored),
+            _ => None,
+        }
+    }
+}
+
+/// A test, as named by the test binary that ran it.  `cargo test --all` runs a binary for each
+/// crate (and each of their integration tests and doctests), and names are only unique within one
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct TestId {
+    /// The binary, such as `pgx_tests` for `target/debug/deps/pgx_tests-0123abcd`
+    pub binary: String,
+    pub name: String,
+}
+
+/// The test binary announced by one of the header lines `cargo test` prints to stderr, such as
+/// `pgx_tests` for `     Running unittests (target/debug/deps/pgx_tests-0123abcd)`, or
+/// `pgx_tests (doctests)` for `   Doc-tests pgx_tests`
+pub(crate) fn parse_binary_line(line: &str) -> Option<String> {
+    let line = line.trim();
+    if let Some(krate) = line.strip_prefix("Doc-tests ") {
+        return Some(format!("{} (doctests)", krate.trim()));
+    }
+
+    // newer cargos give the source file first, and the binary's path in parentheses
+    let running = line.strip_prefix("Running ")?;
+    let path = match (running.rfind('('), running.ends_with(')')) {
+        (Some(open), true) => &running[open + 1..running.len() - 1],
+        _ => running,
+    };
+    let file = Path::new(path).file_stem()?.to_string_lossy();
+
+    // without the hash cargo adds, so the name is the same from one build to the next
+    let binary = match file.rfind('-') {
+        Some(dash) if file[dash + 1..].chars().all(|c| c.is_ascii_hexdigit()) => &file[..dash],
+        _ => &file,
+    };
+    Some(binary.to_string())
+}
+
+/// Is `line` the first a test binary prints, such as `running 12 tests`?
+pub(crate) fn is_binary_start(line: &str) -> bool {
+    line.starts_with("running ") && (line.ends_with(" tests") || line.ends_with(" test"))
+}
+
+/// Parse a libtest result line, such as `test tests::foo ... ok`
+pub(crate) fn parse_test_line(line: &str) -> Option<(String, TestOutcome)> {
+    let line = line.trim();
+    if !line.starts_with("test ") {
+        return None;
+    }
+
+    let mut parts = line["test ".len()..].rsplitn(2, " ... ");
+    let outcome = match parts.next()? {
+        "ok" => TestOutcome::Passed,
+        "FAILED" => TestOutcome::Failed,
+        "ignored" => TestOutcome::Ignored,
+        _ => return None,
+    };
+    let name = parts.next()?.trim();
+
+    Some((name.to_string(), outcome))
+}
+
+/// The results of every test, for every Postgres version it was run against
+#[derive(Debug, Default)]
+pub(crate) struct TestMatrix {
+    versions: Vec<u16>,
+    results: BTreeMap<TestId, BTreeMap<u16, TestOutcome>>,
+}
+
+impl TestMatrix {
+    pub(crate) fn record(&mut self, major_version: u16, results: Vec<(TestId, TestOutcome)>) {
+        if !self.versions.contains(&major_version) {
+            self.versions.push(major_version);
+        }
+
+        for (test, outcome) in results {
+            self.results
+                .entry(test)
+                .or_default()
+                .insert(major_version, outcome);
+        }
+    }
+
+    /// Add the results from a JSON report written by `write_report()`, such as one from each
+    /// `cargo pgx test --shard` run
+    pub(crate) fn merge_report(&mut self, path: &PathBuf) -> Result<(), std::io::Error> {
+        let invalid = |what: &str| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} is not a valid test report: {}", path.display(), what),
+            )
+        };
+
+        let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let tests = report["tests"]
+            .as_array()
+            .ok_or_else(|| invalid("no tests"))?;
+
+        let mut results = BTreeMap::<u16, Vec<(TestId, TestOutcome)>>::new();
+        for version in report["versions"]
+            .as_array()
+            .ok_or_else(|| invalid("no versions"))?
+        {
+            let version = version
+                .as_str()
+                .and_then(|version| version.trim_start_matches("pg").parse().ok())
+                .ok_or_else(|| invalid("bad version"))?;
+            results.entry(version).or_default();
+        }
+
+        for test in tests {
+            let id = TestId {
+                binary: test["binary"]
+                    .as_str()
+                    .ok_or_else(|| invalid("test without a binary"))?
+                    .to_string(),
+                name: test["name"]
+                    .as_str()
+                    .ok_or_else(|| invalid("test without a name"))?
+                    .to_string(),
+            };
+            for (version, version_results) in results.iter_mut() {
+                // a test that didn't run against this version is `null`
+                if let Some(outcome) = test["versions"][format!("pg{}", version)].as_str() {
+                    let outcome =
+                        TestOutcome::from_name(outcome).ok_or_else(|| invalid("bad outcome"))?;
+                    version_results.push((id.clone(), outcome));
+                }
+            }
+        }
+
+        for (version, outcomes) in results {
+            self.record(version, outcomes);
+        }
+        Ok(())
+    }
+
+    /// Tests that failed on some, but not all, of the versions they were run against
+    fn version_specific_failures(&self) -> Vec<(&TestId, Vec<u16>)> {
+        self.results
+            .iter()
+            .filter_map(|(test, outcomes)| {
+                let failed = outcomes
+                    .iter()
+                    .filter(|(_, outcome)| **outcome == TestOutcome::Failed)
+                    .map(|(version, _)| *version)
+                    .collect::<Vec<_>>();
+
+                if !failed.is_empty() && failed.len() < outcomes.len() {
+                    Some((test, failed))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn print_summary(&self) {
+        if self.versions.len() < 2 {
+            return;
+        }
+
+        let failures = self.version_specific_failures();
+        if failures.is_empty() {
+            return;
+        }
+
+        status!();
+        status!("{}", "version-specific failures:".bold().red());
+        for (test, versions) in failures {
+            let versions = versions
+                .iter()
+                .map(|v| format!("pg{}", v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            status!(
+                "    {} ({}) failed on {}",
+                test.name.bold(),
+                test.binary,
+                versions.yellow()
+            );
+        }
+    }
+
+    /// Write the matrix to `path`, as JUnit XML if it ends in `.xml`, otherwise as JSON
+    pub(crate) fn write_report(&self, path: &PathBuf) -> Result<(), std::io::Error> {
+        let report = match path.extension().and_then(|e| e.to_str()) {
+            Some("xml") => self.to_junit(),
+            _ => serde_json::to_string_pretty(&self.to_json())?,
+        };
+
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(report.as_bytes())?;
+
+        status!(
+            "{} test report to {}",
+            "     Writing".bold().green(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let tests = self
+            .results
+            .iter()
+            .map(|(test, outcomes)| {
+                let versions = self
+                    .versions
+                    .iter()
+                    .map(|version| {
+                        let outcome = outcomes.get(version).map(|o| o.as_str());
+                        (format!("pg{}", version), json!(outcome))
+                    })
+                    .collect::<serde_json::Map<_, _>>();
+
+                json!({ "binary": test.binary, "name": test.name, "versions": versions })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "versions": self.versions.iter().map(|v| format!("pg{}", v)).collect::<Vec<_>>(),
+            "tests": tests,
+        })
+    }
+
+    fn to_junit(&self) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+
+        for version in &self.versions {
+            let cases = self
+                .results
+                .iter()
+                .filter_map(|(test, outcomes)| outcomes.get(version).map(|o| (test, *o)))
+                .collect::<Vec<_>>();
+            let failures = cases
+                .iter()
+                .filter(|(_, o)| *o == TestOutcome::Failed)
+                .count();
+            let skipped = cases
+                .iter()
+                .filter(|(_, o)| *o == TestOutcome::Ignored)
+                .count();
+
+            xml.push_str(&format!(
+                "  <testsuite name=\"pg{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n",
+                version,
+                cases.len(),
+                failures,
+                skipped
+            ));
+            for (test, outcome) in cases {
+                // tests from different binaries can have the same name, so the class says which
+                let name = xml_escape(&test.name);
+                let classname = xml_escape(&format!("pg{}.{}", version, test.binary));
+                match outcome {
+                    TestOutcome::Passed => xml.push_str(&format!(
+                        "    <testcase name=\"{}\" classname=\"{}\"/>\n",
+                        name, classname
+                    )),
+                    TestOutcome::Failed => xml.push_str(&format!(
+                        "    <testcase name=\"{}\" classname=\"{}\"><failure/></testcase>\n",
+                        name, classname
+                    )),
+                    TestOutcome::Ignored => xml.push_str(&format!(
+                        "    <testcase name=\"{}\" classname=\"{}\"><skipped/></testcase>\n",
+                        name, classname
+                    )),
+                }
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
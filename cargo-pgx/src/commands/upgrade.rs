@@ -0,0 +1,614 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use crate::commands::get::get_property;
+use crate::commands::install::{
+    assemble_schema, format_display_path, get_extensiondir, get_sharedir, get_version,
+};
+use crate::commands::output::event;
+use crate::commands::schema::{resolve_load_order, SchemaLayout};
+use colored::Colorize;
+use pgx_utils::exit_with_error;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// What a statement in an extension's schema creates, as far as upgrading it is concerned
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+    /// A function, by its name and its arguments without their defaults, ie `s.f("a" integer)`
+    Function(String),
+
+    /// The placeholder for a base type, created ahead of its input and output functions
+    ShellType(String),
+
+    /// An enum, and its labels (quoted, as they're written), in order
+    Enum(String, Vec<String>),
+
+    /// Any other type
+    Type(String),
+
+    /// An operator, as `name(leftarg, rightarg)`
+    Operator(String),
+
+    /// Anything else, known only by its text
+    Other(String),
+}
+
+impl Object {
+    /// Statements that create the same object have the same key
+    fn key(&self) -> String {
+        match self {
+            Object::Function(signature) => format!("function {}", signature),
+            Object::ShellType(name) => format!("shell type {}", name),
+            Object::Enum(name, _) | Object::Type(name) => format!("type {}", name),
+            Object::Operator(signature) => format!("operator {}", signature),
+            Object::Other(text) => text.clone(),
+        }
+    }
+}
+
+/// One statement from a schema
+struct Statement {
+    /// As it was written, including any comments leading up to it
+    text: String,
+
+    /// Without the leading comments or trailing `;`, and with each run of whitespace made a
+    /// single space, for comparing
+    normalized: String,
+
+    object: Object,
+}
+
+/// Write `./sql/extname--FROM--VERSION.sql`, an upgrade script from the previously released
+/// version `from` of the extension to the current one.  It's the difference between the two
+/// versions' schemas:  the new functions, types, and operators are created, changed functions
+/// replaced, new enum labels added, and removed functions and operators dropped.  What can't be
+/// done automatically, such as changing a type, is left in comments
+pub(crate) fn generate_upgrade_script(
+    layout: &SchemaLayout,
+    from: &str,
+) -> Result<(), std::io::Error> {
+    let extname = get_property("extname").expect("could not determine extension name");
+    let version = get_version();
+    if from == version {
+        exit_with_error!(
+            "--upgrade-from {} is the current version.  Change `default_version` in the control file to the new version first",
+            from
+        )
+    }
+
+    let filename = PathBuf::from("sql").join(format!("{}--{}--{}.sql", extname, from, version));
+    if filename.exists() {
+        exit_with_error!(
+            "{} already exists.  Delete it to generate it again",
+            filename.display()
+        )
+    }
+
+    let previous = find_previous_schema(&extname, from);
+    let old = parse_statements(
+        &std::fs::read_to_string(&previous)?,
+        &previous.display().to_string(),
+    );
+    let new = parse_statements(
+        &assemble_schema(layout, resolve_load_order(layout)),
+        "the generated schema",
+    );
+    let statements = upgrade_statements(&old, &new);
+
+    let mut script = format!(
+        "-- {extname}--{from}--{version}.sql\n\
+         --\n\
+         -- Generated by `cargo pgx schema --upgrade-from {from}` from the differences between the\n\
+         -- {from} and {version} schemas.  It's a starting point:  review it, and add any data migrations\n\
+         -- the new version needs, before releasing it.\n\
+         \n\
+         \\echo Use \"ALTER EXTENSION {extname} UPDATE TO '{version}'\" to load this file. \\quit\n",
+        extname = extname,
+        from = from,
+        version = version
+    );
+    for statement in &statements {
+        script.push('\n');
+        script.push_str(statement);
+        script.push('\n');
+    }
+    std::fs::write(&filename, script)?;
+
+    status!(
+        "{} upgrade script from {} to `{}`, with {} statement{} to review",
+        "     Writing".bold().green(),
+        format_display_path(&previous),
+        filename.display(),
+        statements.len(),
+        if statements.len() == 1 { "" } else { "s" }
+    );
+    event(
+        "upgrade_script",
+        json!({
+            "path": filename,
+            "from": from,
+            "to": version,
+            "previous_schema": previous,
+            "statements": statements.len(),
+        }),
+    );
+    Ok(())
+}
+
+/// The schema of version `version`, either a copy kept in `./sql/`, or the one `cargo pgx
+/// install` put in the extension directory of the `pg_config` on the `$PATH`
+fn find_previous_schema(extname: &str, version: &str) -> PathBuf {
+    let filename = format!("{}--{}.sql", extname, version);
+    let kept = PathBuf::from("sql").join(&filename);
+    if kept.exists() {
+        return kept;
+    }
+
+    let extensiondir = get_extensiondir(&get_sharedir(&None));
+    let installed = extensiondir.join(&filename);
+    if installed.exists() {
+        return installed;
+    }
+
+    exit_with_error!(
+        "the schema of version {}, {}, is in neither ./sql/ nor {}.  Keep a copy of each released version's schema in ./sql/",
+        version,
+        filename,
+        extensiondir.display()
+    )
+}
+
+/// Split `sql`, the contents of `file`, into its statements
+fn parse_statements(sql: &str, file: &str) -> Vec<Statement> {
+    // an extension script's `\echo` lines are dropped before it runs, and aren't SQL
+    let sql = sql
+        .lines()
+        .filter(|line| !line.starts_with("\\echo"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let statements = match split_statements(&sql) {
+        Ok(statements) => statements,
+        Err(e) => exit_with_error!("unable to split {} into statements: {}", file, e),
+    };
+
+    statements
+        .iter()
+        .filter_map(|text| {
+            let text = text.trim().trim_end_matches(';').trim_end();
+            let normalized = normalize(text);
+            if normalized.is_empty() {
+                // nothing but comments
+                return None;
+            }
+
+            Some(Statement {
+                text: text.to_string(),
+                object: classify(&normalized),
+                normalized,
+            })
+        })
+        .collect()
+}
+
+/// `text` without its leading comments, and with each run of whitespace made a single space
+fn normalize(text: &str) -> String {
+    text.lines()
+        .skip_while(|line| {
+            let line = line.trim();
+            line.is_empty() || line.starts_with("--")
+        })
+        .flat_map(|line| line.split_whitespace())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Work out what the `normalized` statement creates
+fn classify(normalized: &str) -> Object {
+    let function = strip_prefix_ignore_case(normalized, "CREATE OR REPLACE FUNCTION ")
+        .or_else(|| strip_prefix_ignore_case(normalized, "CREATE FUNCTION "));
+    if let Some(rest) = function {
+        if let Some((name, args, _)) = split_call(rest) {
+            return Object::Function(format!("{}({})", name.trim(), without_defaults(args)));
+        }
+    } else if let Some(rest) = strip_prefix_ignore_case(normalized, "CREATE TYPE ") {
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or_else(|| rest.len());
+        let (name, definition) = (rest[..end].to_string(), rest[end..].trim());
+        return if definition.is_empty() {
+            Object::ShellType(name)
+        } else if let Some(labels) = strip_prefix_ignore_case(definition, "AS ENUM") {
+            let labels = labels.trim().trim_start_matches('(').trim_end_matches(')');
+            Object::Enum(
+                name,
+                split_top_level(labels)
+                    .iter()
+                    .map(|label| label.trim().to_string())
+                    .filter(|label| !label.is_empty())
+                    .collect(),
+            )
+        } else {
+            Object::Type(name)
+        };
+    } else if let Some(rest) = strip_prefix_ignore_case(normalized, "CREATE OPERATOR ") {
+        if let Some((name, options, _)) = split_call(rest) {
+            let mut leftarg = "NONE".to_string();
+            let mut rightarg = "NONE".to_string();
+            for option in split_top_level(options) {
+                let mut parts = option.splitn(2, '=');
+                if let (Some(option), Some(value)) = (parts.next(), parts.next()) {
+                    match option.trim().to_ascii_uppercase().as_str() {
+                        "LEFTARG" => leftarg = value.trim().to_string(),
+                        "RIGHTARG" => rightarg = value.trim().to_string(),
+                        _ => {}
+                    }
+                }
+            }
+            return Object::Operator(format!("{} ({}, {})", name.trim(), leftarg, rightarg));
+        }
+    }
+
+    Object::Other(normalized.to_string())
+}
+
+/// The statements that take a database from the `old` schema to the `new` one
+fn upgrade_statements(old: &[Statement], new: &[Statement]) -> Vec<String> {
+    let new_keys = new
+        .iter()
+        .map(|statement| statement.object.key())
+        .collect::<HashSet<_>>();
+    let old_by_key = old
+        .iter()
+        .map(|statement| (statement.object.key(), statement))
+        .collect::<HashMap<_, _>>();
+    let mut statements = Vec::new();
+
+    // what's gone goes first, latest first so things are dropped before what they depend on, and
+    // so a function whose arguments were renamed can be created again
+    for statement in old.iter().rev() {
+        if new_keys.contains(&statement.object.key()) {
+            continue;
+        }
+
+        match &statement.object {
+            Object::Function(signature) => {
+                statements.push(format!("DROP FUNCTION {};", signature));
+            }
+            Object::Operator(signature) => {
+                statements.push(format!("DROP OPERATOR {};", signature));
+            }
+            Object::ShellType(_) => { /* dropped along with the type itself */ }
+            Object::Enum(name, _) | Object::Type(name) => statements.push(commented(
+                &format!(
+                    "type {} is no longer created.  Dropping it drops every column of that type, so that's left to you:",
+                    name
+                ),
+                &format!("DROP TYPE {}", name),
+            )),
+            Object::Other(_) => statements.push(commented(
+                "no longer in the schema, undo it by hand if needed:",
+                &statement.text,
+            )),
+        }
+    }
+
+    for statement in new {
+        let previous = match old_by_key.get(&statement.object.key()) {
+            Some(previous) => previous,
+            None => {
+                statements.push(format!("{};", statement.text));
+                continue;
+            }
+        };
+        if previous.normalized == statement.normalized {
+            continue;
+        }
+
+        match (&previous.object, &statement.object) {
+            (Object::Function(signature), Object::Function(_)) => {
+                // CREATE OR REPLACE can't change the return type, or argument names or defaults
+                if function_head(&previous.normalized) != function_head(&statement.normalized) {
+                    statements.push(format!("DROP FUNCTION {};", signature));
+                }
+                statements.push(format!("{};", or_replace(&statement.text)));
+            }
+            (Object::Enum(name, old_labels), Object::Enum(_, new_labels)) => {
+                statements.append(&mut alter_enum(name, old_labels, new_labels))
+            }
+            (_, object) => statements.push(commented(
+                &format!(
+                    "{} changed, and has to be migrated by hand.  It's now created with:",
+                    object.key()
+                ),
+                &statement.text,
+            )),
+        }
+    }
+
+    statements
+}
+
+/// Add the labels `new` has that `old` doesn't.  Labels can't be removed or reordered, so those
+/// changes are only pointed out
+fn alter_enum(name: &str, old: &[String], new: &[String]) -> Vec<String> {
+    let mut statements = Vec::new();
+
+    let removed = old
+        .iter()
+        .filter(|label| !new.contains(label))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !removed.is_empty() {
+        statements.push(format!(
+            "-- enum {} no longer has {}, but labels can't be removed from an existing enum",
+            name,
+            removed.join(", ")
+        ));
+    }
+
+    let kept_old = old.iter().filter(|label| new.contains(label));
+    let kept_new = new.iter().filter(|label| old.contains(label));
+    if !kept_old.eq(kept_new) {
+        statements.push(format!(
+            "-- enum {}'s labels were reordered, but an existing enum's labels can't be",
+            name
+        ));
+    }
+
+    let mut added = false;
+    for (i, label) in new.iter().enumerate() {
+        if old.contains(label) {
+            continue;
+        }
+
+        // the labels before this one have all been added by now
+        let position = if i > 0 {
+            format!(" AFTER {}", new[i - 1])
+        } else {
+            match new.iter().find(|label| old.contains(label)) {
+                Some(next) => format!(" BEFORE {}", next),
+                None => String::new(),
+            }
+        };
+        if !added {
+            statements.push(
+                "-- ALTER TYPE ... ADD VALUE needs Postgres 12 or later to run within ALTER EXTENSION UPDATE".to_string(),
+            );
+            added = true;
+        }
+        statements.push(format!(
+            "ALTER TYPE {} ADD VALUE IF NOT EXISTS {}{};",
+            name, label, position
+        ));
+    }
+
+    statements
+}
+
+/// The name, arguments, and return type of the `normalized` `CREATE FUNCTION`, which is what
+/// `CREATE OR REPLACE FUNCTION` can't change
+fn function_head(normalized: &str) -> String {
+    const CLAUSES: &[&str] = &[
+        " LANGUAGE ",
+        " AS ",
+        " IMMUTABLE",
+        " STABLE",
+        " VOLATILE",
+        " STRICT",
+        " CALLED ON",
+        " RETURNS NULL ON",
+        " PARALLEL ",
+        " SECURITY ",
+        " LEAKPROOF",
+        " NOT LEAKPROOF",
+        " COST ",
+        " ROWS ",
+        " SET ",
+        " WINDOW",
+    ];
+
+    // just past the `)` closing the arguments
+    let end = match split_call(normalized) {
+        Some((name, args, _)) => name.len() + args.len() + 2,
+        None => return normalized.to_string(),
+    };
+    let upper = normalized.to_ascii_uppercase();
+    let head_end = CLAUSES
+        .iter()
+        .filter_map(|clause| upper[end..].find(clause).map(|i| end + i))
+        .min()
+        .unwrap_or_else(|| normalized.len());
+    normalized[..head_end].to_string()
+}
+
+/// `text`, which is a `CREATE FUNCTION` statement, as a `CREATE OR REPLACE FUNCTION`
+fn or_replace(text: &str) -> String {
+    let upper = text.to_ascii_uppercase();
+    if upper.contains("CREATE OR REPLACE FUNCTION") {
+        return text.to_string();
+    }
+
+    match upper.find("CREATE FUNCTION") {
+        Some(i) => format!(
+            "{}CREATE OR REPLACE FUNCTION{}",
+            &text[..i],
+            &text[i + "CREATE FUNCTION".len()..]
+        ),
+        None => text.to_string(),
+    }
+}
+
+/// `statement`, commented out, after the comment `note`
+fn commented(note: &str, statement: &str) -> String {
+    let mut commented = format!("-- {}", note);
+    for line in format!("{};", statement).lines() {
+        commented.push_str("\n-- ");
+        commented.push_str(line);
+    }
+    commented
+}
+
+/// A function's arguments, without their `DEFAULT`s, as `DROP FUNCTION` wants them
+fn without_defaults(args: &str) -> String {
+    split_top_level(args)
+        .iter()
+        .map(|arg| {
+            let upper = arg.to_ascii_uppercase();
+            let end = [upper.find(" DEFAULT "), upper.find(" = ")]
+                .iter()
+                .filter_map(|i| *i)
+                .min()
+                .unwrap_or_else(|| arg.len());
+            arg[..end].trim().to_string()
+        })
+        .filter(|arg| !arg.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Split `name(args) rest` into its three parts, finding the `)` that closes the `(`
+fn split_call(text: &str) -> Option<(&str, &str, &str)> {
+    let open = text.find('(')?;
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in text.char_indices().skip_while(|(i, _)| *i < open) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&text[..open], &text[open + 1..i], &text[i + 1..]));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split `sql` at the semicolons that aren't within a comment, a quoted string or identifier, or a
+/// dollar-quoted body
+fn split_statements(sql: &str) -> Result<Vec<&str>, String> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                // block comments nest
+                let mut depth = 0;
+                loop {
+                    if i >= bytes.len() {
+                        return Err("unterminated /* comment".to_string());
+                    } else if bytes[i..].starts_with(b"/*") {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i..].starts_with(b"*/") {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            quote @ b'\'' | quote @ b'"' => {
+                // only E'...' strings escape with a backslash.  A doubled quote ends the string
+                // and starts another, which comes to the same thing
+                let escapes = quote == b'\''
+                    && i > 0
+                    && bytes[i - 1].eq_ignore_ascii_case(&b'e')
+                    && (i < 2 || !is_ident_byte(bytes[i - 2]));
+                i += 1;
+                loop {
+                    match bytes.get(i) {
+                        None => return Err("unterminated quoted string".to_string()),
+                        Some(b'\\') if escapes => i += 2,
+                        Some(&c) if c == quote => break,
+                        Some(_) => i += 1,
+                    }
+                }
+            }
+            b'$' if i == 0 || !is_ident_byte(bytes[i - 1]) => {
+                if let Some(len) = dollar_tag(&sql[i..]) {
+                    let tag = &sql[i..i + len];
+                    match sql[i + len..].find(tag) {
+                        Some(end) => i += len + end + len - 1,
+                        None => return Err(format!("unterminated {} string", tag)),
+                    }
+                }
+            }
+            b';' => {
+                statements.push(&sql[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    statements.push(&sql[start..]);
+    Ok(statements)
+}
+
+/// The length of the `$tag$` that opens a dollar-quoted string at the start of `text`, if one does.
+/// A `$` followed by a number is a parameter
+fn dollar_tag(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    if bytes.get(1).map_or(false, u8::is_ascii_digit) {
+        return None;
+    }
+    let end = 1 + bytes[1..].iter().position(|byte| !is_ident_byte(*byte))?;
+    if bytes[end] == b'$' {
+        Some(end + 1)
+    } else {
+        None
+    }
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+/// Split `text` at the commas that aren't quoted or within parentheses
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    match text.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&text[prefix.len()..]),
+        _ => None,
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use crate::commands::get::get_property;
+use crate::commands::run::create_grant_roles;
+use crate::commands::start::{cluster_settings, start_postgres};
+use crate::commands::stop::stop_postgres;
+use colored::Colorize;
+use pgx_utils::{createdb, exit_with_error, get_psql_path, handle_result, BASE_POSTGRES_PORT_NO};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+/// How often we look for changed files
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Rebuild and reinstall the extension into the pgx-managed Postgres `major_version` every time
+/// its sources change, restarting Postgres so new connections load the new library.  With
+/// `recreate`, the extension is also dropped and created again in `dbname`.
+///
+/// This never returns.  Build failures are reported and then we wait for the next change.
+pub(crate) fn watch_extension(major_version: u16, dbname: &str, is_release: bool, recreate: bool) {
+    let extname = get_property("extname").expect("could not determine extension name");
+
+    start_postgres(major_version, Some(&cluster_settings(&[])));
+    createdb(
+        major_version,
+        "localhost",
+        BASE_POSTGRES_PORT_NO + major_version,
+        dbname,
+        true,
+    );
+    create_grant_roles(major_version);
+
+    reinstall(major_version, dbname, &extname, is_release, recreate);
+    let mut snapshot = snapshot_sources(&extname);
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let mut current = snapshot_sources(&extname);
+        if current == snapshot {
+            continue;
+        }
+
+        // editors tend to save in several steps, and `git checkout` touches many files at once,
+        // so wait for things to settle before rebuilding
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let next = snapshot_sources(&extname);
+            if next == current {
+                break;
+            }
+            current = next;
+        }
+
+        for path in changed_files(&snapshot, &current) {
+            println!("{} {}", "     Changed".bold().cyan(), path.display());
+        }
+
+        reinstall(major_version, dbname, &extname, is_release, recreate);
+
+        // installing regenerates the schema files, which isn't a change worth reacting to
+        snapshot = snapshot_sources(&extname);
+    }
+}
+
+fn reinstall(major_version: u16, dbname: &str, extname: &str, is_release: bool, recreate: bool) {
+    // backends that already loaded the old library would crash if it were overwritten
+    // underneath them
+    stop_postgres(major_version);
+    let installed = install(major_version, is_release);
+    start_postgres(major_version, Some(&cluster_settings(&[])));
+
+    if !installed {
+        println!(
+            "{} to install {}, waiting for changes",
+            "      Failed".bold().red(),
+            extname
+        );
+        return;
+    }
+
+    if recreate && !recreate_extension(major_version, dbname, extname) {
+        return;
+    }
+
+    println!(
+        "{} {} is up to date, waiting for changes",
+        "    Finished".bold().green(),
+        extname
+    );
+}
+
+/// Run `cargo pgx install` for `major_version` in a child process, so a compile error is
+/// reported instead of ending the watch.  The schema is expected to change as we go, so
+/// overwriting the installed one doesn't ask first
+fn install(major_version: u16, is_release: bool) -> bool {
+    let exe = handle_result!(
+        "unable to determine the path to cargo-pgx",
+        std::env::current_exe()
+    );
+    let mut command = Command::new(exe);
+    command
+        .arg("pgx")
+        .arg("install")
+        .arg("--pg-version")
+        .arg(major_version.to_string())
+        .arg("--force");
+    if is_release {
+        command.arg("--release");
+    }
+
+    let command_str = format!("{:?}", command);
+    let status = handle_result!(format!("failed to run: {}", command_str), command.status());
+    status.success()
+}
+
+/// `DROP EXTENSION ... CASCADE` and `CREATE EXTENSION` in `dbname`, reporting, but otherwise
+/// ignoring, any error
+fn recreate_extension(major_version: u16, dbname: &str, extname: &str) -> bool {
+    println!("{} extension {}", "  Recreating".bold().green(), extname);
+    let quoted = extname.replace("\"", "\"\"");
+    let mut command = Command::new(get_psql_path(major_version));
+    command
+        .arg("-XqAt")
+        .arg("-v")
+        .arg("ON_ERROR_STOP=1")
+        .arg("-h")
+        .arg("localhost")
+        .arg("-p")
+        .arg((BASE_POSTGRES_PORT_NO + major_version).to_string())
+        .arg(dbname)
+        .arg("-c")
+        .arg(format!("DROP EXTENSION IF EXISTS \"{}\" CASCADE;", quoted))
+        .arg("-c")
+        .arg(format!("CREATE EXTENSION \"{}\";", quoted))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let command_str = format!("{:?}", command);
+
+    let output = handle_result!(
+        format!("failed to run psql: {}", command_str),
+        command.output()
+    );
+    if !output.status.success() {
+        println!(
+            "{} to recreate extension {}, waiting for changes\n\n{}",
+            "      Failed".bold().red(),
+            extname,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return false;
+    }
+
+    true
+}
+
+/// The modification time of every file we rebuild for: everything in `src/` and `sql/`, besides
+/// generated schema files, plus `Cargo.toml` and the control file
+fn snapshot_sources(extname: &str) -> BTreeMap<PathBuf, SystemTime> {
+    let mut files = BTreeMap::new();
+    find_files(&PathBuf::from("src"), &mut files);
+    find_files(&PathBuf::from("sql"), &mut files);
+    for file in &[
+        PathBuf::from("Cargo.toml"),
+        PathBuf::from(format!("{}.control", extname)),
+    ] {
+        if let Ok(modified) = file.metadata().and_then(|metadata| metadata.modified()) {
+            files.insert(file.clone(), modified);
+        }
+    }
+    files
+}
+
+pub(crate) fn find_files(dir: &PathBuf, files: &mut BTreeMap<PathBuf, SystemTime>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => exit_with_error!("failed to read directory `{}`: {}", dir.display(), e),
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let filename = entry.file_name().to_string_lossy().to_string();
+        if filename.starts_with('.') || filename.ends_with(".generated.sql") {
+            // editor swap files, and what we generate ourselves
+            continue;
+        }
+
+        match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => find_files(&path, files),
+            Ok(metadata) => {
+                if let Ok(modified) = metadata.modified() {
+                    files.insert(path, modified);
+                }
+            }
+            // it was removed while we were looking
+            Err(_) => {}
+        }
+    }
+}
+
+/// Files that were added, removed, or modified between two snapshots
+fn changed_files<'a>(
+    before: &'a BTreeMap<PathBuf, SystemTime>,
+    after: &'a BTreeMap<PathBuf, SystemTime>,
+) -> Vec<&'a PathBuf> {
+    let mut changed = after
+        .iter()
+        .filter(|(path, modified)| before.get(*path) != Some(*modified))
+        .map(|(path, _)| path)
+        .collect::<Vec<_>>();
+    changed.extend(before.keys().filter(|path| !after.contains_key(*path)));
+    changed.sort();
+    changed
+}
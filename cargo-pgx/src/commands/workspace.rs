@@ -0,0 +1,217 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use colored::Colorize;
+use pgx_utils::{exit_with_error, find_workspace_root, handle_result};
+use std::path::{Path, PathBuf};
+
+/// A crate in the workspace
+pub(crate) struct Member {
+    /// Its `package.name`
+    pub(crate) name: String,
+
+    /// The directory of its `Cargo.toml`
+    pub(crate) dir: PathBuf,
+}
+
+impl Member {
+    /// A member is a pgx extension if it has a control file
+    pub(crate) fn is_extension(&self) -> bool {
+        std::fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .any(|entry| entry.file_name().to_string_lossy().ends_with(".control"))
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// The members of the workspace the current directory is in, in the order `[workspace] members`
+/// lists them, with the root package (if there is one) first.  Outside of a workspace, that's
+/// just the current crate
+pub(crate) fn workspace_members() -> Vec<Member> {
+    let root = match find_workspace_root() {
+        Some(root) => root,
+        None => {
+            let dir = handle_result!(
+                "unable to determine the current directory",
+                std::env::current_dir()
+            );
+            return read_member(&dir).into_iter().collect();
+        }
+    };
+
+    let manifest = read_manifest(&root)
+        .unwrap_or_else(|| exit_with_error!("{} is invalid", root.join("Cargo.toml").display()));
+    let workspace = manifest.get("workspace").unwrap();
+    let patterns = |key: &str| {
+        workspace
+            .get(key)
+            .and_then(|value| value.as_array())
+            .map_or_else(Vec::new, |values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_str())
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+            })
+    };
+    let excluded = patterns("exclude")
+        .iter()
+        .flat_map(|pattern| expand_member_pattern(&root, pattern))
+        .collect::<Vec<_>>();
+
+    let mut dirs = Vec::new();
+    if manifest.get("package").is_some() {
+        dirs.push(root.clone());
+    }
+    for pattern in patterns("members") {
+        for dir in expand_member_pattern(&root, &pattern) {
+            if !dirs.contains(&dir) && !excluded.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+
+    dirs.iter().filter_map(|dir| read_member(dir)).collect()
+}
+
+/// The workspace's members that are pgx extensions
+pub(crate) fn extension_members() -> Vec<Member> {
+    workspace_members()
+        .into_iter()
+        .filter(Member::is_extension)
+        .collect()
+}
+
+/// Change into the directory of the workspace member named `name`, so the rest of `cargo pgx`
+/// finds its control file, `sql/` directory, and `Cargo.toml` the same as if it had been run there
+pub(crate) fn enter_member(name: &str) {
+    let members = workspace_members();
+    let member = match members.iter().find(|member| member.name == name) {
+        Some(member) => member,
+        None => exit_with_error!(
+            "package `{}` is not a member of this workspace (members: {})",
+            name,
+            members
+                .iter()
+                .map(|member| member.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    if !member.is_extension() {
+        exit_with_error!(
+            "package `{}` is not a pgx extension: there's no control file in {}",
+            name,
+            member.dir.display()
+        );
+    }
+
+    enter_dir(&member.dir);
+}
+
+/// Change into `dir`, where the rest of `cargo pgx` expects to find the extension
+pub(crate) fn enter_dir(dir: &Path) {
+    handle_result!(
+        format!("unable to change into {}", dir.display()),
+        std::env::set_current_dir(dir)
+    );
+}
+
+/// Outside of a workspace's members, say which ones the user could have meant
+pub(crate) fn member_hint() -> Option<String> {
+    find_workspace_root()?;
+
+    let names = extension_members()
+        .into_iter()
+        .map(|member| member.name)
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "this is a workspace, use `-p NAME` to choose one of its extensions: {}",
+            names.join(", ")
+        ))
+    }
+}
+
+/// Print which extension is up next when working through the whole workspace
+pub(crate) fn announce_member(member: &Member) {
+    status!(
+        "{} {} ({})",
+        "   Workspace".bold().green(),
+        member.name,
+        member.dir.display()
+    );
+}
+
+fn read_manifest(dir: &Path) -> Option<toml::Value> {
+    std::fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|contents| toml::from_str::<toml::Value>(&contents).ok())
+}
+
+fn read_member(dir: &Path) -> Option<Member> {
+    let name = read_manifest(dir)?
+        .get("package")?
+        .get("name")?
+        .as_str()?
+        .to_string();
+    Some(Member {
+        name,
+        dir: dir.to_path_buf(),
+    })
+}
+
+/// The directories a `members` (or `exclude`) entry names.  Like `cargo`, a path component may
+/// contain `*` wildcards, as in `extensions/*`
+fn expand_member_pattern(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![root.to_path_buf()];
+    for component in Path::new(pattern).components() {
+        let component = component.as_os_str().to_string_lossy();
+        if !component.contains('*') {
+            dirs = dirs.into_iter().map(|dir| dir.join(&*component)).collect();
+            continue;
+        }
+
+        let mut expanded = Vec::new();
+        for dir in dirs {
+            if let Ok(entries) = std::fs::read_dir(&dir) {
+                for entry in entries.filter_map(Result::ok) {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if entry.path().is_dir() && matches_wildcard(&component, &name) {
+                        expanded.push(entry.path());
+                    }
+                }
+            }
+        }
+        expanded.sort();
+        dirs = expanded;
+    }
+
+    dirs.into_iter()
+        .filter(|dir| dir.join("Cargo.toml").exists())
+        .collect()
+}
+
+/// Does `name` match `pattern`, in which `*` matches any run of characters?
+fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    let parts = pattern.split('*').collect::<Vec<_>>();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !name.starts_with(first) || name.len() < first.len() + last.len() || !name.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
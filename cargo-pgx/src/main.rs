@@ -6,20 +6,33 @@ extern crate clap;
 
 mod commands;
 
-use crate::commands::get::get_property;
+use crate::commands::connect::connect_psql;
+use crate::commands::get::{get_properties, get_property};
 use crate::commands::init::init_pgx;
-use crate::commands::install::install_extension;
-use crate::commands::new::create_crate_template;
-use crate::commands::package::package_extension;
-use crate::commands::run::run_psql;
-use crate::commands::schema::generate_schema;
-use crate::commands::start::start_postgres;
-use crate::commands::status::status_postgres;
+use crate::commands::install::{install_extension, InstallOptions};
+use crate::commands::logs::show_logs;
+use crate::commands::new::{create_crate_template, CrateTemplate};
+use crate::commands::output::{enable_json, event, is_json};
+use crate::commands::package::{package_extension, DockerOptions, PackageFormat, RepoMetadata};
+use crate::commands::pgxn::package_pgxn;
+use crate::commands::run::{run_psql, RunAs};
+use crate::commands::schema::{
+    generate_schema, generate_schema_per_version, generate_schema_with_graph, print_schema,
+    SchemaLayout,
+};
+use crate::commands::start::{cluster_settings, start_postgres};
+use crate::commands::status::report_status;
 use crate::commands::stop::stop_postgres;
-use crate::commands::test::test_extension;
-use clap::App;
-use colored::Colorize;
-use pgx_utils::{exit, exit_with_error, get_pg_config};
+use crate::commands::test::{test_extension, TestOptions, TestShard};
+use crate::commands::test_report::TestMatrix;
+use crate::commands::upgrade::generate_upgrade_script;
+use crate::commands::watch::watch_extension;
+use crate::commands::workspace::{announce_member, enter_dir, enter_member, extension_members};
+use clap::{App, ArgMatches};
+use pgx_utils::{
+    exit, exit_with_error, find_pg_config, get_pg_config, get_target_dir, handle_result,
+};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::str::FromStr;
 
@@ -30,27 +43,66 @@ fn main() -> std::result::Result<(), std::io::Error> {
     let matches = app.get_matches();
 
     if let Some(extension) = matches.subcommand_matches("pgx") {
+        if let (_, Some(subcommand)) = extension.subcommand() {
+            if subcommand.is_present("json") {
+                enable_json();
+            }
+
+            // `-p NAME` works on that member of the workspace, wherever in it we were run from
+            if let Some(name) = subcommand.value_of("package") {
+                enter_member(name);
+            }
+        }
+
         let result = match extension.subcommand() {
             ("init", Some(init)) => {
                 let pg10_path = init.value_of("pg10");
                 let pg11_path = init.value_of("pg11");
                 let pg12_path = init.value_of("pg12");
+                let pg13_path = init.value_of("pg13");
+                let pg14_path = init.value_of("pg14");
+                let pg15_path = init.value_of("pg15");
+                let with_contrib = init.is_present("contrib");
+                let cache_dir = init
+                    .value_of("cache_dir")
+                    .map(PathBuf::from)
+                    .or_else(|| std::env::var("PGX_CACHE_DIR").ok().map(PathBuf::from));
+                let jobs = init
+                    .value_of("jobs")
+                    .map(|jobs| match usize::from_str(jobs) {
+                        Ok(jobs) if jobs > 0 => jobs,
+                        _ => exit_with_error!("--jobs must be a positive number: {}", jobs),
+                    });
 
-                init_pgx(pg10_path, pg11_path, pg12_path)
+                init_pgx(
+                    pg10_path,
+                    pg11_path,
+                    pg12_path,
+                    pg13_path,
+                    pg14_path,
+                    pg15_path,
+                    with_contrib,
+                    cache_dir,
+                    jobs,
+                )
             }
             ("new", Some(new)) => {
-                let is_bgworker = new.is_present("bgworker");
+                let template = if new.is_present("bgworker") {
+                    CrateTemplate::BackgroundWorker
+                } else {
+                    CrateTemplate::from_name(new.value_of("template").unwrap_or("default"))
+                };
                 let extname = new
                     .value_of("name")
                     .expect("<NAME> argument to create is required");
                 validate_extension_name(extname);
                 let path = PathBuf::from_str(&format!("{}/", extname)).unwrap();
-                create_crate_template(path, extname, is_bgworker)
+                create_crate_template(path, extname, template)
             }
             ("start", Some(start)) => {
                 let pgver = start.value_of("pg_version").unwrap_or("all");
                 for major_version in make_pg_major_version(pgver) {
-                    start_postgres(*major_version);
+                    start_postgres(*major_version, None);
                 }
 
                 Ok(())
@@ -65,39 +117,124 @@ fn main() -> std::result::Result<(), std::io::Error> {
             }
             ("status", Some(status)) => {
                 let pgver = status.value_of("pg_version").unwrap_or("all");
-                for major_version in make_pg_major_version(pgver) {
-                    if status_postgres(*major_version) {
-                        println!(
-                            "Postgres v{} is {}",
-                            major_version,
-                            "running".bold().green()
-                        )
-                    } else {
-                        println!("Postgres v{} is {}", major_version, "stopped".bold().red())
+                let dbname = status.value_of("dbname").unwrap_or("postgres");
+                for (i, major_version) in make_pg_major_version(pgver).iter().enumerate() {
+                    if i > 0 {
+                        println!();
                     }
+                    report_status(*major_version, dbname);
                 }
                 Ok(())
             }
+            ("logs", Some(logs)) => {
+                let pgver = logs
+                    .value_of("pg_version")
+                    .expect("<PG_VERSION> is required");
+                let lines = logs
+                    .value_of("lines")
+                    .map(|lines| match usize::from_str(lines) {
+                        Ok(lines) => lines,
+                        _ => exit_with_error!("--lines must be a number: {}", lines),
+                    });
+                show_logs(
+                    make_pg_major_version(pgver)[0],
+                    lines,
+                    logs.is_present("follow"),
+                )
+            }
             ("install", Some(install)) => {
                 let is_release = install.is_present("release");
                 let pg_config = match std::env::var("PGX_TEST_MODE_VERSION") {
                     // for test mode, we want the pg_config specified in PGX_TEST_MODE_VERSION
-                    Ok(pgver) => get_pg_config(u16::from_str(&pgver).expect(
+                    Ok(pgver) => Some(find_pg_config(u16::from_str(&pgver).expect(
                         "PGX_TEST_MODE_VERSION does not contain a valid postgres version number",
-                    )),
+                    ))),
+
+                    // otherwise, the user either asked for a specific version, or just ran
+                    // "cargo pgx install", and we use whatever "pg_config" is on the path
+                    Err(_) => make_pg_config(install),
+                };
+
+                let install_here = || {
+                    if install.is_present("all") {
+                        // each version gets its own target directory, so switching between their
+                        // feature flags doesn't rebuild everything every time
+                        for major_version in make_pg_major_version("all") {
+                            let mut target_dir = get_target_dir();
+                            target_dir.push(format!("pg{}", major_version));
 
-                    // otherwise, the user just ran "cargo pgx install", and we use whatever "pg_config" is on the path
-                    Err(_) => Some("pg_config".to_string()),
+                            install_extension(
+                                &get_pg_config(*major_version),
+                                is_release,
+                                None,
+                                &InstallOptions {
+                                    target_dir: Some(target_dir),
+                                    ..make_install_options(install)
+                                },
+                            );
+                        }
+                    } else {
+                        install_extension(
+                            &pg_config,
+                            is_release,
+                            None,
+                            &make_install_options(install),
+                        );
+                    }
                 };
 
-                install_extension(&pg_config, is_release, None);
+                if install.is_present("workspace") {
+                    let members = extension_members();
+                    if members.is_empty() {
+                        exit_with_error!("no member of this workspace is a pgx extension");
+                    }
+                    for member in members {
+                        announce_member(&member);
+                        enter_dir(&member.dir);
+                        install_here();
+                    }
+                } else {
+                    install_here();
+                }
                 Ok(())
             }
             ("package", Some(package)) => {
                 let is_debug = package.is_present("debug");
-                let pg_config = Some("pg_config".to_string()); // use whatever "pg_config" is on the path
+                let pg_config = make_pg_config(package);
 
-                package_extension(&pg_config, is_debug);
+                let formats = package.values_of("format").map_or_else(Vec::new, |values| {
+                    values.map(PackageFormat::from_name).collect()
+                });
+
+                let docker = if package.is_present("docker_image") {
+                    Some(DockerOptions {
+                        tag: package.value_of("docker_image").map(|tag| tag.to_string()),
+                        create_extension: package.is_present("docker_create_extension"),
+                    })
+                } else {
+                    None
+                };
+
+                let metadata = package
+                    .values_of("repo_metadata")
+                    .map_or_else(Vec::new, |values| {
+                        values.map(RepoMetadata::from_name).collect()
+                    });
+
+                package_extension(
+                    &pg_config,
+                    is_debug,
+                    &make_install_options(package),
+                    &formats,
+                    docker.as_ref(),
+                    &metadata,
+                    package.value_of("download_url"),
+                    package.is_present("stamp"),
+                );
+                Ok(())
+            }
+            ("pgxn", Some(pgxn)) => {
+                package_pgxn(pgxn.is_present("no_schema"));
                 Ok(())
             }
             ("run", Some(run)) => {
@@ -109,21 +246,166 @@ fn main() -> std::result::Result<(), std::io::Error> {
                     |v| v.to_string(),
                 );
                 let is_release = run.is_present("release");
-                run_psql(make_pg_major_version(pgver)[0], &dbname, is_release);
+                let run_as = match (run.value_of("user"), run.value_of("superuser")) {
+                    (Some(user), _) => Some(RunAs::User(user)),
+                    (_, Some(superuser)) => Some(RunAs::Superuser(superuser)),
+                    _ => None,
+                };
+                let contrib = run
+                    .values_of("contrib")
+                    .map_or_else(Vec::new, |values| values.collect());
+                run_psql(
+                    make_pg_major_version(pgver)[0],
+                    &dbname,
+                    is_release,
+                    run_as,
+                    &contrib,
+                    make_cluster_settings(run),
+                    run.is_present("detach"),
+                );
+                Ok(())
+            }
+            ("connect", Some(connect)) => {
+                let pgver = connect
+                    .value_of("pg_version")
+                    .expect("<PG_VERSION> is required");
+                let dbname = connect.value_of("dbname").map_or_else(
+                    || get_property("extname").expect("could not determine extension name"),
+                    |v| v.to_string(),
+                );
+                let psql_args = connect
+                    .values_of("psql_args")
+                    .map_or_else(Vec::new, |values| values.collect());
+                connect_psql(
+                    make_pg_major_version(pgver)[0],
+                    &dbname,
+                    connect.value_of("user"),
+                    &psql_args,
+                );
+                Ok(())
+            }
+            ("watch", Some(watch)) => {
+                let pgver = watch
+                    .value_of("pg_version")
+                    .expect("<PG_VERSION> is required");
+                let dbname = watch.value_of("dbname").map_or_else(
+                    || get_property("extname").expect("could not determine extension name"),
+                    |v| v.to_string(),
+                );
+                watch_extension(
+                    make_pg_major_version(pgver)[0],
+                    &dbname,
+                    watch.is_present("release"),
+                    watch.is_present("recreate"),
+                );
                 Ok(())
             }
             ("test", Some(test)) => {
                 let pgver = test.value_of("pg_version").unwrap_or("all");
+                let options = TestOptions {
+                    run_as: match (test.value_of("user"), test.value_of("superuser")) {
+                        (Some(user), _) => Some(RunAs::User(user)),
+                        (_, Some(superuser)) => Some(RunAs::Superuser(superuser)),
+                        _ => None,
+                    },
+                    testname: test.value_of("testname"),
+                    no_schema: test.is_present("no_schema"),
+                    no_install: test.is_present("no_install"),
+                    keep_running: test.is_present("keep_running"),
+                    existing_cluster: test.is_present("existing_cluster"),
+                    shard: test.value_of("shard").map(TestShard::parse),
+                    settings: make_cluster_settings(test),
+                    exact: test.is_present("exact"),
+                    nocapture: test.is_present("nocapture"),
+                    test_threads: make_test_threads(test),
+                    harness_args: test
+                        .values_of("harness_args")
+                        .map_or_else(Vec::new, |values| values.collect()),
+                };
+                let mut matrix = TestMatrix::default();
+                let mut failed = Vec::new();
                 for major_version in make_pg_major_version(pgver) {
-                    test_extension(*major_version);
+                    if !test_extension(*major_version, &options, &mut matrix) {
+                        failed.push(format!("pg{}", major_version));
+                    }
+                }
+
+                matrix.print_summary();
+                if let Some(report) = test.value_of("report") {
+                    matrix.write_report(&PathBuf::from(report))?;
+                }
+
+                if !failed.is_empty() {
+                    exit_with_error!("cargo pgx test failed for {}", failed.join(", "))
                 }
                 Ok(())
             }
-            ("schema", Some(_schema)) => generate_schema(),
+            ("merge-reports", Some(merge)) => {
+                let mut matrix = TestMatrix::default();
+                for report in merge.values_of("reports").unwrap() {
+                    let report = PathBuf::from(report);
+                    handle_result!(
+                        format!("failed to read {}", report.display()),
+                        matrix.merge_report(&report)
+                    );
+                }
+
+                matrix.print_summary();
+                matrix.write_report(&PathBuf::from(merge.value_of("report").unwrap()))
+            }
+            ("schema", Some(schema)) => {
+                let mut layout = SchemaLayout::from_metadata();
+                if let Some(out) = schema.value_of("out") {
+                    layout.dir = PathBuf::from(out);
+                }
+                if schema.is_present("split") {
+                    layout.split = true;
+                }
+                let versions = schema.value_of("pg_version").map(make_pg_major_version);
+                if let Some(&[version]) = versions {
+                    layout.pg_version = Some(version);
+                }
+                let dot = schema.value_of("dot").map(PathBuf::from);
+
+                if schema.is_present("stdout") {
+                    if layout.pg_version.is_none() && versions.is_some() {
+                        exit_with_error!(
+                            "--stdout prints the schema of one Postgres version at a time"
+                        )
+                    }
+                    print_schema(&layout)
+                } else {
+                    match versions {
+                        Some(versions) if versions.len() > 1 => {
+                            generate_schema_per_version(&layout, versions, dot)
+                        }
+                        _ => generate_schema_with_graph(&layout, dot),
+                    }
+                    .and_then(|()| match schema.value_of("upgrade_from") {
+                        Some(from) => generate_upgrade_script(&layout, from),
+                        None => Ok(()),
+                    })
+                }
+            }
             ("get", Some(get)) => {
-                let name = get.value_of("name").expect("no property name specified");
-                if let Some(value) = get_property(name) {
-                    println!("{}", value);
+                let properties = get_properties();
+                if get.is_present("all") {
+                    if is_json() {
+                        let properties = properties
+                            .into_iter()
+                            .map(|(k, v)| (k, serde_json::Value::String(v)))
+                            .collect::<serde_json::Map<_, _>>();
+                        event("properties", serde_json::Value::Object(properties));
+                    } else {
+                        for (k, v) in properties {
+                            println!("{} = {}", k, v);
+                        }
+                    }
+                } else {
+                    let name = get.value_of("name").expect("no property name specified");
+                    if let Some((_, value)) = properties.into_iter().find(|(k, _)| k == name) {
+                        println!("{}", value);
+                    }
                 }
                 Ok(())
             }
@@ -148,12 +430,80 @@ fn validate_extension_name(extname: &str) {
     }
 }
 
+fn make_install_options(matches: &ArgMatches) -> InstallOptions {
+    let rpaths = matches
+        .values_of("rpath")
+        .map_or_else(Vec::new, |values| values.map(|v| v.to_string()).collect());
+    let install_names = matches.values_of("install_name").map_or_else(Vec::new, |values| {
+        values
+            .map(|v| {
+                let mut parts = v.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(old), Some(new)) => (old.to_string(), new.to_string()),
+                    _ => exit_with_error!("--install-name must be of the form OLD=NEW: {}", v),
+                }
+            })
+            .collect()
+    });
+
+    InstallOptions {
+        rpaths,
+        install_names,
+        force: matches.is_present("force"),
+        no_schema: matches.is_present("no_schema"),
+        static_link: matches.is_present("static"),
+        sudo: matches.is_present("sudo"),
+        target: matches.value_of("target").map(|v| v.to_string()),
+        pkglibdir: matches.value_of("pkglibdir").map(PathBuf::from),
+        sharedir: matches.value_of("sharedir").map(PathBuf::from),
+        extensiondir: matches.value_of("extensiondir").map(PathBuf::from),
+        docdir: matches.value_of("docdir").map(PathBuf::from),
+        target_dir: None,
+        dry_run: matches.is_present("dry_run"),
+        no_sql_check: matches.is_present("no_sql_check"),
+        no_build: matches.is_present("no_build"),
+    }
+}
+
+/// The `pg_config` for the `--pg-version` the user asked for, otherwise whatever "pg_config" is
+/// on the path
+fn make_pg_config(matches: &ArgMatches) -> Option<String> {
+    match matches.value_of("pg_version") {
+        Some(pgver) => match make_pg_major_version(pgver) {
+            [major_version] => Some(find_pg_config(*major_version)),
+            _ => exit_with_error!("--pg-version must be a single Postgres version: {}", pgver),
+        },
+        None => Some("pg_config".to_string()),
+    }
+}
+
+/// The pgx-managed Postgres' settings, with the `--set`s given on the command line
+fn make_cluster_settings(matches: &ArgMatches) -> BTreeMap<String, String> {
+    let overrides = matches
+        .values_of("set")
+        .map_or_else(Vec::new, |values| values.collect());
+    cluster_settings(&overrides)
+}
+
+/// The number of `--test-threads`, if given
+fn make_test_threads(matches: &ArgMatches) -> Option<usize> {
+    matches
+        .value_of("test_threads")
+        .map(|threads| match usize::from_str(threads) {
+            Ok(threads) if threads > 0 => threads,
+            _ => exit_with_error!("--test-threads must be a positive number: {}", threads),
+        })
+}
+
 fn make_pg_major_version(version_string: &str) -> &'static [u16] {
     match version_string {
-        "all" => &[10, 11, 12],
-        "pg10" => &[10],
-        "pg11" => &[11],
-        "pg12" => &[12],
+        "all" => &[10, 11, 12, 13, 14, 15],
+        "pg10" | "10" => &[10],
+        "pg11" | "11" => &[11],
+        "pg12" | "12" => &[12],
+        "pg13" | "13" => &[13],
+        "pg14" | "14" => &[14],
+        "pg15" | "15" => &[15],
         _ => exit_with_error!("unrecognized Postgres version: {}", version_string),
     }
 }
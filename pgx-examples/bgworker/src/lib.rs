@@ -19,6 +19,9 @@ use std::time::Duration;
 
 pg_module_magic!();
 
+// monitor this extension's background workers from SQL with `SELECT * FROM bgworker_workers();`
+pg_supervised_workers!();
+
 #[pg_guard]
 pub extern "C" fn _PG_init() {
     BackgroundWorkerBuilder::new("Background Worker Example")
@@ -26,6 +29,7 @@ pub extern "C" fn _PG_init() {
         .set_library("bgworker")
         .set_argument(42i32.into_datum())
         .enable_spi_access()
+        .enable_supervision()
         .load();
 }
 
@@ -161,9 +161,39 @@ pub fn pg_extern(attr: TokenStream, item: TokenStream) -> TokenStream {
     let is_raw = args.contains(&ExternArgs::Raw);
     let no_guard = args.contains(&ExternArgs::NoGuard);
 
+    let requires_primary = args.contains(&ExternArgs::RequiresPrimary);
+    let track_stats = args.contains(&ExternArgs::TrackStats);
+    let on_create = args.contains(&ExternArgs::OnCreate);
+
     let ast = parse_macro_input!(item as syn::Item);
     match ast {
-        Item::Fn(func) => rewrite_item_fn(func, is_raw, no_guard).into(),
+        Item::Fn(mut func) => {
+            if track_stats {
+                let name = func.sig.ident.to_string();
+                func.block.stmts.insert(
+                    0,
+                    syn::parse_quote! {
+                        let _pgx_function_stats_guard =
+                            pgx::function_stats::PgFunctionStatsGuard::enter(#name);
+                    },
+                );
+            }
+            if requires_primary {
+                let name = func.sig.ident.to_string();
+                func.block.stmts.insert(
+                    0,
+                    syn::parse_quote! { pgx::recovery::check_not_in_recovery(#name); },
+                );
+            }
+            if on_create {
+                let name = func.sig.ident.to_string();
+                func.block.stmts.insert(
+                    0,
+                    syn::parse_quote! { pgx::extension::check_creating_extension(#name); },
+                );
+            }
+            rewrite_item_fn(func, is_raw, no_guard).into()
+        }
         _ => panic!("#[pg_extern] can only be applied to top-level functions"),
     }
 }
@@ -173,6 +203,11 @@ fn rewrite_item_fn(mut func: ItemFn, is_raw: bool, no_guard: bool) -> proc_macro
         &format!("pg_finfo_{}_wrapper", func.sig.ident),
         Span::call_site(),
     );
+    let meta_name = syn::Ident::new(
+        &format!("pgx_meta_{}_wrapper", func.sig.ident),
+        Span::call_site(),
+    );
+    let func_name = func.sig.ident.to_string();
 
     // use the PgGuardRewriter to go ahead and wrap the function here, rather than applying
     // a #[pg_guard] macro to the original function.  This is necessary so that compiler
@@ -192,6 +227,18 @@ fn rewrite_item_fn(mut func: ItemFn, is_raw: bool, no_guard: bool) -> proc_macro
                 &V1_API
             }
 
+            #[no_mangle]
+            #[doc(hidden)]
+            pub extern "C" fn #meta_name() -> &'static pgx::PgExternMetadata {
+                const METADATA: pgx::PgExternMetadata = pgx::PgExternMetadata {
+                    name: #func_name,
+                    module_path: module_path!(),
+                    file: file!(),
+                    line: line!(),
+                };
+                &METADATA
+            }
+
             #rewritten_func
         }
     } else {
@@ -265,7 +312,16 @@ fn impl_postgres_enum(ast: DeriveInput) -> proc_macro2::TokenStream {
     stream
 }
 
-#[proc_macro_derive(PostgresType, attributes(inoutfuncs, pgvarlena_inoutfuncs))]
+#[proc_macro_derive(
+    PostgresType,
+    attributes(
+        inoutfuncs,
+        pgvarlena_inoutfuncs,
+        text_inoutfuncs,
+        archived_inoutfuncs,
+        passedbyvalue
+    )
+)]
 pub fn postgres_type(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as syn::DeriveInput);
 
@@ -282,12 +338,17 @@ fn impl_postgres_type(ast: DeriveInput) -> proc_macro2::TokenStream {
     let mut stream = proc_macro2::TokenStream::new();
 
     // validate that we're only operating on a struct
-    match ast.data {
-        Data::Struct(_) => { /* this is okay */ }
+    let fields = match &ast.data {
+        Data::Struct(data) => &data.fields,
         _ => panic!("#[derive(PostgresType)] can only be applied to structs"),
-    }
+    };
 
+    let passed_by_value = args.remove(&PostgresTypeAttribute::PassedByValue);
     if args.is_empty() {
+        if passed_by_value {
+            panic!("#[passedbyvalue] types must also be #[inoutfuncs] or #[text_inoutfuncs]");
+        }
+
         // assume the user wants us to implement the InOutFuncs
         args.insert(PostgresTypeAttribute::Default);
     }
@@ -302,6 +363,54 @@ fn impl_postgres_type(ast: DeriveInput) -> proc_macro2::TokenStream {
         impl #generics pgx::PostgresType for #name #generics { }
     });
 
+    // and #[passedbyvalue] types are converted to and from a Datum directly, rather than via CBOR
+    if passed_by_value {
+        if !generics.params.is_empty() {
+            panic!("#[passedbyvalue] types can't be generic");
+        }
+        if args.contains(&PostgresTypeAttribute::PgVarlenaInOutFuncs)
+            || args.contains(&PostgresTypeAttribute::ArchivedInOutFuncs)
+        {
+            panic!("#[passedbyvalue] types can't also be stored as a varlena");
+        }
+
+        let (size, align) =
+            byval_type_layout(fields, is_packed(&ast.attrs)).unwrap_or_else(|e| panic!("{}", e));
+        let type_name = name.to_string();
+        stream.extend(quote! {
+            // `cargo pgx schema` declares the type's INTERNALLENGTH and ALIGNMENT from the same
+            // layout, so the struct must really be laid out that way.  Use #[repr(C)] if it isn't
+            const _: [(); #size] = [(); std::mem::size_of::<#name>()];
+            const _: [(); #align] = [(); std::mem::align_of::<#name>()];
+
+            impl pgx::IntoDatum for #name {
+                fn into_datum(self) -> Option<pgx::pg_sys::Datum> {
+                    Some(unsafe { pgx::byval_into_datum(self) })
+                }
+
+                fn type_oid() -> pgx::pg_sys::Oid {
+                    unsafe {
+                        pgx::direct_function_call::<pgx::pg_sys::Oid>(
+                            pgx::pg_sys::regtypein,
+                            vec![pgx::IntoDatum::into_datum(#type_name)],
+                        )
+                        .expect("failed to lookup typeoid")
+                    }
+                }
+            }
+
+            impl pgx::FromDatum for #name {
+                unsafe fn from_datum(datum: pgx::pg_sys::Datum, is_null: bool, _typoid: pgx::pg_sys::Oid) -> Option<Self> {
+                    if is_null {
+                        None
+                    } else {
+                        Some(pgx::byval_from_datum(datum))
+                    }
+                }
+            }
+        });
+    }
+
     // and if we don't have custom inout/funcs, we use the JsonInOutFuncs trait
     // which implements _in and _out #[pg_extern] functions that just return the type itself
     if args.contains(&PostgresTypeAttribute::Default) {
@@ -342,6 +451,36 @@ fn impl_postgres_type(ast: DeriveInput) -> proc_macro2::TokenStream {
                 buffer.into()
             }
         });
+    } else if args.contains(&PostgresTypeAttribute::TextInOutFuncs) {
+        // or if it's TextInOutFuncs, our _in/_out functions convert to and from plain Rust strings
+        stream.extend(quote! {
+            #[pg_extern(immutable,parallel_safe)]
+            pub fn #funcname_in #generics(input: &#lifetime std::ffi::CStr) -> #name #generics {
+                <#name #generics as pgx::TextInOutFuncs>::input(input.to_str().expect("text input is not valid UTF8"))
+            }
+
+            #[pg_extern(immutable,parallel_safe)]
+            pub fn #funcname_out #generics(input: #name #generics) -> &#lifetime std::ffi::CStr {
+                let mut buffer = StringInfo::new();
+                buffer.push_str(&pgx::TextInOutFuncs::output(&input));
+                buffer.into()
+            }
+        });
+    } else if args.contains(&PostgresTypeAttribute::ArchivedInOutFuncs) {
+        // or if it's ArchivedInOutFuncs, our _in/_out functions use a PgArchived
+        stream.extend(quote! {
+            #[pg_extern(immutable,parallel_safe)]
+            pub fn #funcname_in #generics(input: &#lifetime std::ffi::CStr) -> pgx::PgArchived<#name #generics> {
+                pgx::PgArchived::new(&<#name #generics as pgx::ArchivedInOutFuncs>::input(input))
+            }
+
+            #[pg_extern(immutable,parallel_safe)]
+            pub fn #funcname_out #generics(input: pgx::PgArchived<#name #generics>) -> &#lifetime std::ffi::CStr {
+                let mut buffer = StringInfo::new();
+                <#name #generics as pgx::ArchivedInOutFuncs>::output(&input, &mut buffer);
+                buffer.into()
+            }
+        });
     } else if args.contains(&PostgresTypeAttribute::PgVarlenaInOutFuncs) {
         // otherwise if it's PgVarlenaInOutFuncs our _in/_out functions use a PgVarlena
         stream.extend(quote! {
@@ -362,6 +501,55 @@ fn impl_postgres_type(ast: DeriveInput) -> proc_macro2::TokenStream {
     stream
 }
 
+/// Generate `=` and `<>` operators, and a default hash operator class, for a
+/// `#[derive(PostgresType)]` type that's also `Eq` and `Hash`.
+///
+/// These are what Postgres needs to find elements in arrays of the type, so `= ANY()`, `<> ALL()`,
+/// `@>`, `<@`, `&&`, `array_position()` and `array_remove()` all work with them, as do
+/// `SELECT DISTINCT`, `GROUP BY`, and hash joins.
+#[proc_macro_derive(PostgresEq)]
+pub fn postgres_eq(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+
+    impl_postgres_eq(ast).into()
+}
+
+fn impl_postgres_eq(ast: DeriveInput) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let generics = &ast.generics;
+    let funcname_eq = Ident::new(&format!("{}_eq", name).to_lowercase(), name.span());
+    let funcname_ne = Ident::new(&format!("{}_ne", name).to_lowercase(), name.span());
+    let funcname_hash = Ident::new(&format!("{}_hash", name).to_lowercase(), name.span());
+    let args = parse_postgres_type_args(&ast.attrs);
+
+    // compare values the same way their _out function receives them, dereferencing the ones that
+    // are wrapped up in a varlena
+    let (arg_type, deref) = if args.contains(&PostgresTypeAttribute::PgVarlenaInOutFuncs) {
+        (quote! { pgx::PgVarlena<#name #generics> }, quote! { * })
+    } else if args.contains(&PostgresTypeAttribute::ArchivedInOutFuncs) {
+        (quote! { pgx::PgArchived<#name #generics> }, quote! { * })
+    } else {
+        (quote! { #name #generics }, quote! {})
+    };
+
+    quote! {
+        #[pg_extern(immutable,parallel_safe)]
+        pub fn #funcname_eq #generics(left: #arg_type, right: #arg_type) -> bool {
+            #deref left == #deref right
+        }
+
+        #[pg_extern(immutable,parallel_safe)]
+        pub fn #funcname_ne #generics(left: #arg_type, right: #arg_type) -> bool {
+            #deref left != #deref right
+        }
+
+        #[pg_extern(immutable,parallel_safe)]
+        pub fn #funcname_hash #generics(value: #arg_type) -> i32 {
+            pgx::hash_value(&#deref value)
+        }
+    }
+}
+
 #[proc_macro_derive(PostgresGucEnum, attributes(hidden))]
 pub fn postgres_guc_enum(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as syn::DeriveInput);
@@ -447,6 +635,9 @@ fn impl_guc_enum(ast: DeriveInput) -> proc_macro2::TokenStream {
 enum PostgresTypeAttribute {
     InOutFuncs,
     PgVarlenaInOutFuncs,
+    TextInOutFuncs,
+    ArchivedInOutFuncs,
+    PassedByValue,
     Default,
 }
 
@@ -459,6 +650,17 @@ fn parse_postgres_type_args(attributes: &[Attribute]) -> HashSet<PostgresTypeAtt
             "pgvarlena_inoutfuncs" => {
                 categorized_attributes.insert(PostgresTypeAttribute::PgVarlenaInOutFuncs)
             }
+            "text_inoutfuncs" => {
+                categorized_attributes.insert(PostgresTypeAttribute::TextInOutFuncs)
+            }
+            "archived_inoutfuncs" => {
+                categorized_attributes.insert(PostgresTypeAttribute::ArchivedInOutFuncs)
+            }
+            "passedbyvalue" => categorized_attributes.insert(PostgresTypeAttribute::PassedByValue),
+
+            // other attributes, like #[repr(C)], doc comments, and those of rkyv's derives, aren't
+            // ours
+            "repr" | "doc" | "archive" | "archive_attr" => false,
             _ => panic!(
                 "unrecognized PostgresType attribute: {}",
                 a.path.to_token_stream().to_string()
@@ -469,8 +671,149 @@ fn parse_postgres_type_args(attributes: &[Attribute]) -> HashSet<PostgresTypeAtt
     categorized_attributes
 }
 
+/// Is the struct declared `#[repr(packed)]`?
+fn is_packed(attributes: &[Attribute]) -> bool {
+    attributes.iter().any(|a| {
+        a.path.to_token_stream().to_string() == "repr" && a.tokens.to_string().contains("packed")
+    })
+}
+
 #[proc_macro]
 pub fn extension_sql(_: TokenStream) -> TokenStream {
     // we don't want to output anything here
     TokenStream::new()
 }
+
+/// Declare an extension configuration table.  `cargo pgx schema` generates its `CREATE TABLE`
+/// statement and registers it with `pg_extension_config_dump()` so that its contents survive
+/// `pg_dump`/`pg_restore`
+///
+/// ```rust,ignore
+/// extension_config_table!(
+///     name = "my_config",
+///     columns = r#"
+///         id serial8 NOT NULL PRIMARY KEY,
+///         key text NOT NULL UNIQUE,
+///         value text,
+///         is_default bool NOT NULL DEFAULT false
+///     "#,
+///     // only dump rows the user has added, not the ones `CREATE EXTENSION` inserts
+///     filter = "WHERE NOT is_default",
+///     sequences = "my_config_id_seq",
+/// );
+/// ```
+#[proc_macro]
+pub fn extension_config_table(_: TokenStream) -> TokenStream {
+    // we don't want to output anything here
+    TokenStream::new()
+}
+
+/// Create a SQL function named `<extname>_build_info()` that returns a single row describing how
+/// the extension's library was built:  its `(extension_version, git_revision, profile,
+/// pgx_version, pg_version)`.  See the `magic` module documentation
+#[proc_macro]
+pub fn pg_build_info(_: TokenStream) -> TokenStream {
+    builtin_function(
+        BUILD_INFO_FUNCTION,
+        quote! {
+            pgx::get_magic_block().into_iter().map(|block| {
+                (
+                    block.extversion.map(|version| version.to_string()),
+                    block.git_revision.map(|revision| revision.to_string()),
+                    block.profile.to_string(),
+                    block.pgx_version.to_string(),
+                    block.pg_version(),
+                )
+            })
+        },
+    )
+}
+
+/// Create the `pgx_function_stats()` and `pgx_function_stats_reset()` SQL functions for
+/// inspecting the statistics of `#[pg_extern(track_stats)]` functions.  Use it once, at the top
+/// level of the extension's `lib.rs`
+#[proc_macro]
+pub fn pg_function_stats(_: TokenStream) -> TokenStream {
+    let [stats, reset] = FUNCTION_STATS_FUNCTIONS;
+    let mut functions = builtin_function(
+        stats,
+        quote! {
+            pgx::function_stats::function_stats()
+                .into_iter()
+                .map(|stats| {
+                    (
+                        stats.name,
+                        stats.calls as i64,
+                        stats.total_time.as_secs_f64() * 1000.0,
+                        stats.self_time.as_secs_f64() * 1000.0,
+                    )
+                })
+        },
+    );
+    functions.extend(builtin_function(
+        reset,
+        quote! {
+            pgx::function_stats::reset_function_stats()
+        },
+    ));
+    functions
+}
+
+/// Create a SQL function named `<extname>_functions()` that lists the functions the extension
+/// exports as `(name, signature, rust_path, volatility)`.  See the `function_registry` module
+/// documentation
+#[proc_macro]
+pub fn pg_function_registry(_: TokenStream) -> TokenStream {
+    builtin_function(
+        FUNCTION_REGISTRY_FUNCTION,
+        quote! {
+            pgx::function_registry::exported_functions()
+                .into_iter()
+                .map(|function| {
+                    (
+                        function.name.clone(),
+                        function.signature.clone(),
+                        function.rust_path(),
+                        function.volatility.as_str().to_string(),
+                    )
+                })
+        },
+    )
+}
+
+/// Create a SQL function named `<extname>_workers()` that returns the `(name, pid, state,
+/// last_heartbeat, restart_count)` of each of the extension's supervised background workers.  See
+/// `BackgroundWorker::supervised_workers()`
+#[proc_macro]
+pub fn pg_supervised_workers(_: TokenStream) -> TokenStream {
+    builtin_function(
+        SUPERVISED_WORKERS_FUNCTION,
+        quote! {
+            pgx::bgworkers::BackgroundWorker::supervised_workers()
+                .into_iter()
+                .map(|worker| {
+                    (
+                        worker.name,
+                        worker.pid,
+                        worker.state.as_str().to_string(),
+                        worker.last_heartbeat,
+                        worker.restart_count,
+                    )
+                })
+        },
+    )
+}
+
+/// A `#[pg_extern]` function with one of `pgx_utils`' built-in `signature`s, which `cargo pgx
+/// schema` generates the `CREATE FUNCTION` for, and `body`
+fn builtin_function(signature: &str, body: TokenStream2) -> TokenStream {
+    let signature =
+        syn::parse_str::<syn::Signature>(signature).expect("invalid built-in function signature");
+    (quote! {
+        #[pg_extern]
+        #signature {
+            #body
+        }
+    })
+    .into()
+}
@@ -64,7 +64,7 @@ fn main() -> Result<(), std::io::Error> {
     eprintln!("shim_dir={}", shim_dir.display());
     eprintln!("common_rs={}", common_rs.display());
 
-    let major_versions = vec![10, 11, 12];
+    let major_versions = vec![10, 11, 12, 13, 14, 15];
 
     if std::env::var("DOCS_RS").unwrap_or("false".into()) == "1" {
         return Ok(());
@@ -74,6 +74,9 @@ fn main() -> Result<(), std::io::Error> {
     build_deps::rerun_if_changed_paths("include/*").unwrap();
     build_deps::rerun_if_changed_paths("cshim/pgx-cshim.c").unwrap();
     build_deps::rerun_if_changed_paths("cshim/Makefile").unwrap();
+    for name in &["PGX_PG_CPPFLAGS", "PGX_PG_CFLAGS_SL", "PGX_PG_LDFLAGS"] {
+        println!("cargo:rerun-if-env-changed={}", name);
+    }
 
     let shim_mutex = Mutex::new(());
 
@@ -103,7 +106,7 @@ fn main() -> Result<(), std::io::Error> {
         build_shim(&shim_dir, &shim_mutex, major_version, &pg_config);
     });
 
-    generate_common_rs(manifest_dir, &out_dir);
+    generate_common_rs(manifest_dir, &out_dir, &major_versions);
 
     Ok(())
 }
@@ -123,6 +126,7 @@ fn run_bindgen(
     let bindings = bindgen::Builder::default()
         .header(include_h.display().to_string())
         .clang_arg(&format!("-I{}", includedir_server))
+        .clang_args(pg_build_flags(major_version, "PGX_PG_CPPFLAGS"))
         .parse_callbacks(Box::new(IgnoredMacros::default()))
         .blacklist_function("varsize_any") // pgx converts the VARSIZE_ANY macro, so we don't want to also have this function, which is in heaptuple.c
         .blacklist_function("query_tree_walker")
@@ -179,18 +183,34 @@ fn build_shim(
     }
 
     // no matter what, tell rustc to link to the library that was built for the feature we're currently building
-    if std::env::var("CARGO_FEATURE_PG10").is_ok() {
-        println!("cargo:rustc-link-search={}", shim_dir.display());
-        println!("cargo:rustc-link-lib=static=pgx-cshim-10");
-    } else if std::env::var("CARGO_FEATURE_PG11").is_ok() {
-        println!("cargo:rustc-link-search={}", shim_dir.display());
-        println!("cargo:rustc-link-lib=static=pgx-cshim-11");
-    } else if std::env::var("CARGO_FEATURE_PG12").is_ok() {
+    if std::env::var(format!("CARGO_FEATURE_PG{}", major_version)).is_ok() {
         println!("cargo:rustc-link-search={}", shim_dir.display());
-        println!("cargo:rustc-link-lib=static=pgx-cshim-12");
+        println!("cargo:rustc-link-lib=static=pgx-cshim-{}", major_version);
+
+        // so the extension links against the same libraries Postgres did
+        for flag in pg_build_flags(major_version, "PGX_PG_LDFLAGS") {
+            if flag.starts_with("-L") && flag.len() > 2 {
+                println!("cargo:rustc-link-search=native={}", &flag[2..]);
+            }
+        }
     }
 }
 
+/// The flags `cargo pgx` found Postgres was built with, from the `PGX_PG_*` environment variable
+/// `name`.  They come from the `pg_config` for the version being built, so they're not applied to
+/// any other version's bindings
+fn pg_build_flags(major_version: u16, name: &str) -> Vec<String> {
+    if std::env::var(format!("CARGO_FEATURE_PG{}", major_version)).is_err() {
+        return Vec::new();
+    }
+
+    std::env::var(name)
+        .unwrap_or_default()
+        .split_ascii_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
 fn build_shim_for_version(
     shim_dir: &PathBuf,
     major_version: u16,
@@ -218,12 +238,12 @@ fn build_shim_for_version(
     Ok(())
 }
 
-fn generate_common_rs(working_dir: PathBuf, out_dir: &PathBuf) {
+fn generate_common_rs(working_dir: PathBuf, out_dir: &PathBuf, major_versions: &[u16]) {
     eprintln!("[all branches] Regenerating common.rs and XX_specific.rs files...");
     let cwd = std::env::current_dir().unwrap();
 
     std::env::set_current_dir(&working_dir).unwrap();
-    let result = bindings_diff::main(out_dir);
+    let result = bindings_diff::main(out_dir, major_versions);
     std::env::set_current_dir(cwd).unwrap();
 
     if result.is_err() {
@@ -309,46 +329,60 @@ fn rust_fmt(path: &str) -> Result<(), std::io::Error> {
 
 pub(crate) mod bindings_diff {
     use crate::rust_fmt;
-    use quote::{quote, ToTokens};
+    use quote::{format_ident, quote, ToTokens};
     use std::collections::BTreeSet;
     use std::io::{Read, Write};
     use std::path::PathBuf;
     use std::str::FromStr;
 
-    pub(crate) fn main(out_dir: &PathBuf) -> Result<(), std::io::Error> {
-        let mut v10 = read_source_file(&format!("{}pg10_bindings.rs", out_dir.display()));
-        let mut v11 = read_source_file(&format!("{}pg11_bindings.rs", out_dir.display()));
-        let mut v12 = read_source_file(&format!("{}pg12_bindings.rs", out_dir.display()));
-
-        let mut versions = vec![&mut v10, &mut v11, &mut v12];
-        let common = build_common_set(&mut versions);
+    pub(crate) fn main(out_dir: &PathBuf, major_versions: &[u16]) -> Result<(), std::io::Error> {
+        let mut versions = major_versions
+            .iter()
+            .map(|major_version| {
+                read_source_file(&format!(
+                    "{}pg{}_bindings.rs",
+                    out_dir.display(),
+                    major_version
+                ))
+            })
+            .collect::<Vec<_>>();
+        let common = build_common_set(&mut versions.iter_mut().collect());
 
         eprintln!(
-            "[all branches]: common={}, v10={}, v11={}, v12={}",
+            "[all branches]: common={}, {}",
             common.len(),
-            v10.len(),
-            v11.len(),
-            v12.len(),
+            major_versions
+                .iter()
+                .zip(versions.iter())
+                .map(|(major_version, items)| format!("v{}={}", major_version, items.len()))
+                .collect::<Vec<_>>()
+                .join(", ")
         );
 
-        write_common_file(&format!("{}common.rs", out_dir.display()), &common);
-        write_source_file(&format!("{}pg10_specific.rs", out_dir.display()), &v10);
-        write_source_file(&format!("{}pg11_specific.rs", out_dir.display()), &v11);
-        write_source_file(&format!("{}pg12_specific.rs", out_dir.display()), &v12);
+        write_common_file(
+            &format!("{}common.rs", out_dir.display()),
+            &common,
+            major_versions,
+        );
+        for (major_version, items) in major_versions.iter().zip(versions.iter()) {
+            write_source_file(
+                &format!("{}pg{}_specific.rs", out_dir.display(), major_version),
+                items,
+            );
+        }
 
         // delete the bindings files when we're done with them
-        std::fs::remove_file(
-            PathBuf::from_str(&format!("{}pg10_bindings.rs", out_dir.display())).unwrap(),
-        )
-        .expect("couldn't delete v10 bindings");
-        std::fs::remove_file(
-            PathBuf::from_str(&format!("{}pg11_bindings.rs", out_dir.display())).unwrap(),
-        )
-        .expect("couldn't delete v11 bindings");
-        std::fs::remove_file(
-            PathBuf::from_str(&format!("{}pg12_bindings.rs", out_dir.display())).unwrap(),
-        )
-        .expect("couldn't delete v12 bindings");
+        for major_version in major_versions {
+            std::fs::remove_file(
+                PathBuf::from_str(&format!(
+                    "{}pg{}_bindings.rs",
+                    out_dir.display(),
+                    major_version
+                ))
+                .unwrap(),
+            )
+            .unwrap_or_else(|_| panic!("couldn't delete v{} bindings", major_version));
+        }
 
         Ok(())
     }
@@ -421,19 +455,22 @@ pub(crate) mod bindings_diff {
             .unwrap_or_else(|e| panic!("unable to run rustfmt for {}: {:?}", filename, e));
     }
 
-    fn write_common_file(filename: &str, items: &BTreeSet<String>) {
+    fn write_common_file(filename: &str, items: &BTreeSet<String>, major_versions: &[u16]) {
         let mut file = std::fs::File::create(filename).expect("failed to create common.rs");
+        let specific_uses = major_versions.iter().map(|major_version| {
+            let feature = format!("pg{}", major_version);
+            let module = format_ident!("pg{}_specific", major_version);
+            quote! {
+                #[cfg(feature = #feature)]
+                use crate::#module::*;
+            }
+        });
         file.write_all(
             quote! {
                 use crate as pg_sys;
                 use pgx_macros::*;
 
-                #[cfg(feature = "pg10")]
-                use crate::pg10_specific::*;
-                #[cfg(feature = "pg11")]
-                use crate::pg11_specific::*;
-                #[cfg(feature = "pg12")]
-                use crate::pg12_specific::*;
+                #(#specific_uses)*
             }
             .to_string()
             .as_bytes(),
@@ -8,6 +8,9 @@
 #define IS_PG_10 (PG_VERSION_NUM >= 100000 && PG_VERSION_NUM < 110000)
 #define IS_PG_11 (PG_VERSION_NUM >= 110000 && PG_VERSION_NUM < 120000)
 #define IS_PG_12 (PG_VERSION_NUM >= 120000 && PG_VERSION_NUM < 130000)
+#define IS_PG_13 (PG_VERSION_NUM >= 130000 && PG_VERSION_NUM < 140000)
+#define IS_PG_14 (PG_VERSION_NUM >= 140000 && PG_VERSION_NUM < 150000)
+#define IS_PG_15 (PG_VERSION_NUM >= 150000 && PG_VERSION_NUM < 160000)
 
 #include "access/htup.h"
 #include "access/htup_details.h"
@@ -17,6 +20,7 @@
 #else
 #include "nodes/pathnodes.h"
 #endif
+#include "nodes/pg_list.h"
 #include "parser/parsetree.h"
 #include "utils/memutils.h"
 #include "utils/builtins.h"
@@ -44,6 +48,13 @@ void pgx_ereport(int level, int code, char *message, char *file, int lineno, int
                     errmsg("%s", message), errcontext_msg("%s:%d:%d", file, lineno, colno)));
 }
 
+PGDLLEXPORT void pgx_ereport_detail(int level, int code, char *message, char *detail, char *file, int lineno, int colno);
+void pgx_ereport_detail(int level, int code, char *message, char *detail, char *file, int lineno, int colno) {
+    ereport(level,
+            (errcode(code),
+                    errmsg("%s", message), errdetail("%s", detail), errcontext_msg("%s:%d:%d", file, lineno, colno)));
+}
+
 PGDLLEXPORT void pgx_SET_VARSIZE(struct varlena *ptr, int size);
 void pgx_SET_VARSIZE(struct varlena *ptr, int size) {
     SET_VARSIZE(ptr, size);
@@ -86,6 +97,32 @@ Oid pgx_HeapTupleHeaderGetOid(HeapTupleHeader htup_header) {
 }
 #endif
 
+#if IS_PG_13 || IS_PG_14 || IS_PG_15
+/*
+ * As of Postgres 13, Lists are arrays and these are all inline functions, so they're not
+ * otherwise callable from Rust
+ */
+PGDLLEXPORT void *pgx_list_nth(List *list, int nth);
+void *pgx_list_nth(List *list, int nth) {
+    return list_nth(list, nth);
+}
+
+PGDLLEXPORT int pgx_list_nth_int(List *list, int nth);
+int pgx_list_nth_int(List *list, int nth) {
+    return list_nth_int(list, nth);
+}
+
+PGDLLEXPORT Oid pgx_list_nth_oid(List *list, int nth);
+Oid pgx_list_nth_oid(List *list, int nth) {
+    return list_nth_oid(list, nth);
+}
+
+PGDLLEXPORT ListCell *pgx_list_nth_cell(List *list, int nth);
+ListCell *pgx_list_nth_cell(List *list, int nth) {
+    return list_nth_cell(list, nth);
+}
+#endif
+
 PGDLLEXPORT char *pgx_GETSTRUCT(HeapTuple tuple);
 char *pgx_GETSTRUCT(HeapTuple tuple) {
     return GETSTRUCT(tuple);
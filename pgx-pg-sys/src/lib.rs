@@ -27,6 +27,15 @@ pub mod pg10_specific;
 pub mod pg11_specific;
 pub mod pg12_specific;
 
+// these aren't checked into `generated-bindings/`, so unlike the above, they're only compiled
+// when their version is selected
+#[cfg(feature = "pg13")]
+pub mod pg13_specific;
+#[cfg(feature = "pg14")]
+pub mod pg14_specific;
+#[cfg(feature = "pg15")]
+pub mod pg15_specific;
+
 // expose things we want available for all versions
 pub use all_versions::*;
 
@@ -198,6 +207,453 @@ mod all_versions {
             context: *mut ::std::os::raw::c_void,
         ) -> bool;
     }
+
+    // from commands/extension.h, which we don't generate bindings for
+    extern "C" {
+        pub static mut creating_extension: bool;
+        pub static mut CurrentExtensionObject: super::Oid;
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn get_extension_oid(
+            extname: *const ::std::os::raw::c_char,
+            missing_ok: bool,
+        ) -> super::Oid;
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn get_extension_name(ext_oid: super::Oid) -> *mut ::std::os::raw::c_char;
+    }
+
+    /// this comes from `catalog/pg_extension.h`
+    pub const ExtensionRelationId: super::Oid = 3079;
+
+    /// this comes from `catalog/pg_proc.h`
+    pub const ProcedureRelationId: super::Oid = 1255;
+
+    // from postmaster/syslogger.h, which we don't generate bindings for
+    extern "C" {
+        pub static mut Log_directory: *mut ::std::os::raw::c_char;
+    }
+
+    /// this comes from `catalog/pg_authid.h` (Postgres 11+), where Postgres 14 renamed it to
+    /// `ROLE_PG_READ_SERVER_FILES`
+    #[cfg(any(
+        feature = "pg11",
+        feature = "pg12",
+        feature = "pg13",
+        feature = "pg14",
+        feature = "pg15"
+    ))]
+    pub const DEFAULT_ROLE_READ_SERVER_FILES: super::Oid = 4569;
+
+    // from utils/inval.h, which we don't generate bindings for
+    pub type SyscacheCallbackFunction = ::std::option::Option<
+        unsafe extern "C" fn(arg: super::Datum, cacheid: ::std::os::raw::c_int, hashvalue: u32),
+    >;
+    pub type RelcacheCallbackFunction =
+        ::std::option::Option<unsafe extern "C" fn(arg: super::Datum, relid: super::Oid)>;
+
+    #[pg_guard]
+    extern "C" {
+        pub fn CacheRegisterSyscacheCallback(
+            cacheid: ::std::os::raw::c_int,
+            func: SyscacheCallbackFunction,
+            arg: super::Datum,
+        );
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn CacheRegisterRelcacheCallback(func: RelcacheCallbackFunction, arg: super::Datum);
+    }
+
+    // from storage/lmgr.h, which we don't generate bindings for
+    #[pg_guard]
+    extern "C" {
+        pub fn LockRelationOid(relid: super::Oid, lockmode: super::LOCKMODE);
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn UnlockRelationOid(relid: super::Oid, lockmode: super::LOCKMODE);
+    }
+
+    /// this comes from `catalog/pg_replication_origin.h`
+    pub const ReplicationOriginRelationId: super::Oid = 6000;
+
+    /// this comes from `replication/origin.h`
+    pub const InvalidRepOriginId: super::RepOriginId = 0;
+
+    /// this comes from `replication/origin.h`
+    pub const DoNotReplicateId: super::RepOriginId = u16::max_value();
+
+    // from replication/origin.h, which we don't generate bindings for
+    extern "C" {
+        pub static mut replorigin_session_origin: super::RepOriginId;
+        pub static mut replorigin_session_origin_lsn: super::XLogRecPtr;
+        pub static mut replorigin_session_origin_timestamp: super::TimestampTz;
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn replorigin_by_name(
+            name: *mut ::std::os::raw::c_char,
+            missing_ok: bool,
+        ) -> super::RepOriginId;
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn replorigin_create(name: *mut ::std::os::raw::c_char) -> super::RepOriginId;
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn replorigin_drop(roident: super::RepOriginId, nowait: bool);
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn replorigin_by_oid(
+            roident: super::RepOriginId,
+            missing_ok: bool,
+            roname: *mut *mut ::std::os::raw::c_char,
+        ) -> bool;
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn replorigin_advance(
+            node: super::RepOriginId,
+            remote_commit: super::XLogRecPtr,
+            local_commit: super::XLogRecPtr,
+            go_backward: bool,
+            wal_log: bool,
+        );
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn replorigin_get_progress(node: super::RepOriginId, flush: bool) -> super::XLogRecPtr;
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn replorigin_session_setup(node: super::RepOriginId);
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn replorigin_session_reset();
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn replorigin_session_get_progress(flush: bool) -> super::XLogRecPtr;
+    }
+
+    // from utils/datum.h, which we don't generate bindings for
+    #[pg_guard]
+    extern "C" {
+        pub fn datumCopy(
+            value: super::Datum,
+            typByVal: bool,
+            typLen: ::std::os::raw::c_int,
+        ) -> super::Datum;
+    }
+
+    // from parser/parser.h, which we don't generate bindings for
+    #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
+    #[pg_guard]
+    extern "C" {
+        pub fn raw_parser(str: *const ::std::os::raw::c_char) -> *mut super::List;
+    }
+
+    /// this comes from `parser/parser.h` (Postgres 14+)
+    #[cfg(any(feature = "pg14", feature = "pg15"))]
+    pub type RawParseMode = u32;
+    #[cfg(any(feature = "pg14", feature = "pg15"))]
+    pub const RawParseMode_RAW_PARSE_DEFAULT: RawParseMode = 0;
+
+    /// Postgres 14 added a `RawParseMode` argument to `raw_parser()`.  This parses `str` as
+    /// complete SQL statements, as `raw_parser()` did before then
+    #[cfg(any(feature = "pg14", feature = "pg15"))]
+    #[inline]
+    pub unsafe fn raw_parser(str: *const ::std::os::raw::c_char) -> *mut super::List {
+        mod parser {
+            use pgx_macros::*;
+
+            #[pg_guard]
+            extern "C" {
+                pub fn raw_parser(
+                    str: *const ::std::os::raw::c_char,
+                    mode: super::RawParseMode,
+                ) -> *mut crate::List;
+            }
+        }
+
+        parser::raw_parser(str, RawParseMode_RAW_PARSE_DEFAULT)
+    }
+
+    // from utils/ruleutils.h, which we don't generate bindings for
+    #[pg_guard]
+    extern "C" {
+        pub fn deparse_expression(
+            expr: *mut super::Node,
+            dpcontext: *mut super::List,
+            forceprefix: bool,
+            showimplicit: bool,
+        ) -> *mut ::std::os::raw::c_char;
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn deparse_context_for(
+            aliasname: *const ::std::os::raw::c_char,
+            relid: super::Oid,
+        ) -> *mut super::List;
+    }
+
+    // from commands/prepare.h, which we don't generate bindings for
+    #[repr(C)]
+    #[derive(Debug, Copy, Clone)]
+    pub struct PreparedStatement {
+        pub stmt_name: [::std::os::raw::c_char; 64usize],
+        pub plansource: *mut super::CachedPlanSource,
+        pub from_sql: bool,
+        pub prepare_time: super::TimestampTz,
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn FetchPreparedStatement(
+            stmt_name: *const ::std::os::raw::c_char,
+            throwError: bool,
+        ) -> *mut PreparedStatement;
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn DropPreparedStatement(stmt_name: *const ::std::os::raw::c_char, showError: bool);
+    }
+
+    #[pg_guard]
+    extern "C" {
+        pub fn DropAllPreparedStatements();
+    }
+
+    // from commands/vacuum.h, which we don't generate bindings for
+    #[cfg(any(feature = "pg10", feature = "pg11"))]
+    #[pg_guard]
+    extern "C" {
+        pub fn ExecVacuum(vacstmt: *mut super::VacuumStmt, isTopLevel: bool);
+    }
+
+    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+    #[pg_guard]
+    extern "C" {
+        pub fn ExecVacuum(
+            pstate: *mut super::ParseState,
+            vacstmt: *mut super::VacuumStmt,
+            isTopLevel: bool,
+        );
+    }
+
+    // from executor/tuptable.h (Postgres 12+), which we don't generate bindings for
+    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+    extern "C" {
+        pub static TTSOpsMinimalTuple: super::TupleTableSlotOps;
+    }
+
+    // from nodes/pg_list.h, where Postgres 13 turned these into inline functions
+    #[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+    pub use list::*;
+
+    #[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+    mod list {
+        extern "C" {
+            fn pgx_list_nth(
+                list: *const crate::List,
+                n: ::std::os::raw::c_int,
+            ) -> *mut ::std::os::raw::c_void;
+            fn pgx_list_nth_int(
+                list: *const crate::List,
+                n: ::std::os::raw::c_int,
+            ) -> ::std::os::raw::c_int;
+            fn pgx_list_nth_oid(list: *const crate::List, n: ::std::os::raw::c_int) -> crate::Oid;
+            fn pgx_list_nth_cell(
+                list: *const crate::List,
+                n: ::std::os::raw::c_int,
+            ) -> *mut crate::ListCell;
+        }
+
+        #[inline]
+        pub unsafe fn list_nth(
+            list: *const crate::List,
+            n: ::std::os::raw::c_int,
+        ) -> *mut ::std::os::raw::c_void {
+            pgx_list_nth(list, n)
+        }
+
+        #[inline]
+        pub unsafe fn list_nth_int(
+            list: *const crate::List,
+            n: ::std::os::raw::c_int,
+        ) -> ::std::os::raw::c_int {
+            pgx_list_nth_int(list, n)
+        }
+
+        #[inline]
+        pub unsafe fn list_nth_oid(
+            list: *const crate::List,
+            n: ::std::os::raw::c_int,
+        ) -> crate::Oid {
+            pgx_list_nth_oid(list, n)
+        }
+
+        #[inline]
+        pub unsafe fn list_nth_cell(
+            list: *const crate::List,
+            n: ::std::os::raw::c_int,
+        ) -> *mut crate::ListCell {
+            pgx_list_nth_cell(list, n)
+        }
+    }
+
+    /// this comes from `utils/dsa.h`
+    pub const InvalidDsaPointer: super::dsa_pointer = 0;
+
+    // from lib/dshash.h (Postgres 11+), which we don't generate bindings for
+    #[cfg(any(
+        feature = "pg11",
+        feature = "pg12",
+        feature = "pg13",
+        feature = "pg14",
+        feature = "pg15"
+    ))]
+    pub use dshash::*;
+
+    #[cfg(any(
+        feature = "pg11",
+        feature = "pg12",
+        feature = "pg13",
+        feature = "pg14",
+        feature = "pg15"
+    ))]
+    mod dshash {
+        use pgx_macros::*;
+
+        #[repr(C)]
+        pub struct dshash_table {
+            _unused: [u8; 0],
+        }
+        pub type dshash_table_handle = crate::dsa_pointer;
+        pub type dshash_hash = u32;
+        pub type dshash_compare_function = ::std::option::Option<
+            unsafe extern "C" fn(
+                a: *const ::std::os::raw::c_void,
+                b: *const ::std::os::raw::c_void,
+                size: usize,
+                arg: *mut ::std::os::raw::c_void,
+            ) -> ::std::os::raw::c_int,
+        >;
+        pub type dshash_hash_function = ::std::option::Option<
+            unsafe extern "C" fn(
+                v: *const ::std::os::raw::c_void,
+                size: usize,
+                arg: *mut ::std::os::raw::c_void,
+            ) -> dshash_hash,
+        >;
+
+        #[repr(C)]
+        pub struct dshash_parameters {
+            pub key_size: usize,
+            pub entry_size: usize,
+            pub compare_function: dshash_compare_function,
+            pub hash_function: dshash_hash_function,
+            pub tranche_id: ::std::os::raw::c_int,
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_create(
+                area: *mut crate::dsa_area,
+                params: *const dshash_parameters,
+                arg: *mut ::std::os::raw::c_void,
+            ) -> *mut dshash_table;
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_attach(
+                area: *mut crate::dsa_area,
+                params: *const dshash_parameters,
+                handle: dshash_table_handle,
+                arg: *mut ::std::os::raw::c_void,
+            ) -> *mut dshash_table;
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_detach(hash_table: *mut dshash_table);
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_get_hash_table_handle(hash_table: *mut dshash_table)
+                -> dshash_table_handle;
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_destroy(hash_table: *mut dshash_table);
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_find(
+                hash_table: *mut dshash_table,
+                key: *const ::std::os::raw::c_void,
+                exclusive: bool,
+            ) -> *mut ::std::os::raw::c_void;
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_find_or_insert(
+                hash_table: *mut dshash_table,
+                key: *const ::std::os::raw::c_void,
+                found: *mut bool,
+            ) -> *mut ::std::os::raw::c_void;
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_delete_key(
+                hash_table: *mut dshash_table,
+                key: *const ::std::os::raw::c_void,
+            ) -> bool;
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_delete_entry(
+                hash_table: *mut dshash_table,
+                entry: *mut ::std::os::raw::c_void,
+            );
+        }
+
+        #[pg_guard]
+        extern "C" {
+            pub fn dshash_release_lock(
+                hash_table: *mut dshash_table,
+                entry: *mut ::std::os::raw::c_void,
+            );
+        }
+    }
 }
 
 mod internal {
@@ -210,6 +666,15 @@ mod internal {
     #[cfg(feature = "pg12")]
     pub use pg12::*;
 
+    #[cfg(feature = "pg13")]
+    pub use pg13::*;
+
+    #[cfg(feature = "pg14")]
+    pub use pg14::*;
+
+    #[cfg(feature = "pg15")]
+    pub use pg15::*;
+
     //
     // for specific versions
     //
@@ -380,4 +845,139 @@ mod internal {
             );
         }
     }
+
+    #[cfg(feature = "pg13")]
+    mod pg13 {
+        pub use crate::common::*;
+        pub use crate::pg13_specific::*;
+
+        pub use crate::pg13_specific::AllocSetContextCreateInternal as AllocSetContextCreateExtended;
+
+        pub const QTW_EXAMINE_RTES: u32 = crate::pg13_specific::QTW_EXAMINE_RTES_BEFORE;
+
+        /// # Safety
+        ///
+        /// This function wraps Postgres' internal `IndexBuildHeapScan` method, and therfore, is
+        /// inherently unsafe
+        pub unsafe fn IndexBuildHeapScan<T>(
+            heap_relation: crate::Relation,
+            index_relation: crate::Relation,
+            index_info: *mut crate::pg13_specific::IndexInfo,
+            build_callback: crate::IndexBuildCallback,
+            build_callback_state: *mut T,
+        ) {
+            let heap_relation_ref = heap_relation.as_ref().unwrap();
+            let table_am = heap_relation_ref.rd_tableam.as_ref().unwrap();
+
+            table_am.index_build_range_scan.unwrap()(
+                heap_relation,
+                index_relation,
+                index_info,
+                true,
+                false,
+                true,
+                0,
+                crate::InvalidBlockNumber,
+                build_callback,
+                build_callback_state as *mut std::os::raw::c_void,
+                std::ptr::null_mut(),
+            );
+        }
+    }
+
+    #[cfg(feature = "pg14")]
+    mod pg14 {
+        pub use crate::common::*;
+        pub use crate::pg14_specific::*;
+
+        pub use crate::pg14_specific::AllocSetContextCreateInternal as AllocSetContextCreateExtended;
+
+        pub const QTW_EXAMINE_RTES: u32 = crate::pg14_specific::QTW_EXAMINE_RTES_BEFORE;
+
+        /// # Safety
+        ///
+        /// This function wraps Postgres' internal `IndexBuildHeapScan` method, and therfore, is
+        /// inherently unsafe
+        pub unsafe fn IndexBuildHeapScan<T>(
+            heap_relation: crate::Relation,
+            index_relation: crate::Relation,
+            index_info: *mut crate::pg14_specific::IndexInfo,
+            build_callback: crate::IndexBuildCallback,
+            build_callback_state: *mut T,
+        ) {
+            let heap_relation_ref = heap_relation.as_ref().unwrap();
+            let table_am = heap_relation_ref.rd_tableam.as_ref().unwrap();
+
+            table_am.index_build_range_scan.unwrap()(
+                heap_relation,
+                index_relation,
+                index_info,
+                true,
+                false,
+                true,
+                0,
+                crate::InvalidBlockNumber,
+                build_callback,
+                build_callback_state as *mut std::os::raw::c_void,
+                std::ptr::null_mut(),
+            );
+        }
+    }
+
+    #[cfg(feature = "pg15")]
+    mod pg15 {
+        pub use crate::common::*;
+        pub use crate::pg15_specific::*;
+
+        pub use crate::pg15_specific::AllocSetContextCreateInternal as AllocSetContextCreateExtended;
+
+        pub const QTW_EXAMINE_RTES: u32 = crate::pg15_specific::QTW_EXAMINE_RTES_BEFORE;
+
+        /// # Safety
+        ///
+        /// This function wraps Postgres' internal `IndexBuildHeapScan` method, and therfore, is
+        /// inherently unsafe
+        pub unsafe fn IndexBuildHeapScan<T>(
+            heap_relation: crate::Relation,
+            index_relation: crate::Relation,
+            index_info: *mut crate::pg15_specific::IndexInfo,
+            build_callback: crate::IndexBuildCallback,
+            build_callback_state: *mut T,
+        ) {
+            let heap_relation_ref = heap_relation.as_ref().unwrap();
+            let table_am = heap_relation_ref.rd_tableam.as_ref().unwrap();
+
+            table_am.index_build_range_scan.unwrap()(
+                heap_relation,
+                index_relation,
+                index_info,
+                true,
+                false,
+                true,
+                0,
+                crate::InvalidBlockNumber,
+                build_callback,
+                build_callback_state as *mut std::os::raw::c_void,
+                std::ptr::null_mut(),
+            );
+        }
+
+        /// Postgres 15 renamed `pg_analyze_and_rewrite()` to
+        /// `pg_analyze_and_rewrite_fixedparams()`
+        pub unsafe fn pg_analyze_and_rewrite(
+            parsetree: *mut crate::RawStmt,
+            query_string: *const ::std::os::raw::c_char,
+            param_types: *mut crate::Oid,
+            num_params: ::std::os::raw::c_int,
+            query_env: *mut crate::QueryEnvironment,
+        ) -> *mut crate::List {
+            crate::pg15_specific::pg_analyze_and_rewrite_fixedparams(
+                parsetree,
+                query_string,
+                param_types,
+                num_params,
+                query_env,
+            )
+        }
+    }
 }
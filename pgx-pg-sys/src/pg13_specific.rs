@@ -0,0 +1 @@
+include!("../generated-bindings/pg13_specific.rs");
@@ -0,0 +1 @@
+include!("../generated-bindings/pg14_specific.rs");
@@ -0,0 +1 @@
+include!("../generated-bindings/pg15_specific.rs");
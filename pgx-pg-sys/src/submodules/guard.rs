@@ -6,7 +6,9 @@
 use crate::FlushErrorState;
 use once_cell::sync::OnceCell;
 use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
 use std::cell::Cell;
+use std::ffi::CStr;
 use std::panic::catch_unwind;
 
 extern "C" {
@@ -19,7 +21,15 @@ extern "C" {
         lineno: i32,
         colno: i32,
     );
-
+    fn pgx_ereport_detail(
+        level: i32,
+        code: i32,
+        message: *const std::os::raw::c_char,
+        detail: *const std::os::raw::c_char,
+        file: *const std::os::raw::c_char,
+        lineno: i32,
+        colno: i32,
+    );
 }
 
 #[derive(Clone, Debug)]
@@ -48,6 +58,7 @@ struct PanicLocation {
     file: String,
     line: u32,
     col: u32,
+    backtrace: Option<String>,
 }
 
 thread_local! { static PANIC_LOCATION: Cell<Option<PanicLocation>> = Cell::new(None) }
@@ -61,6 +72,7 @@ fn take_panic_location() -> PanicLocation {
             file: "<unknown>".to_string(),
             line: 0,
             col: 0,
+            backtrace: None,
         },
     })
 }
@@ -69,6 +81,16 @@ fn take_panic_location() -> PanicLocation {
 // for all other threads.
 thread_local! { pub(crate) static IS_MAIN_THREAD: OnceCell<()> = OnceCell::new() }
 
+/// Allow the current thread to call `#[pg_guard]` functions outside of Postgres, for
+/// `pgx::mock`'s unit tests.  Unlike `register_pg_guard_panic_handler()`, this leaves the panic
+/// hook alone and may be called any number of times
+#[doc(hidden)]
+pub fn register_mock_main_thread() {
+    IS_MAIN_THREAD.with(|v| {
+        let _ = v.set(());
+    });
+}
+
 pub fn register_pg_guard_panic_handler() {
     // first, lets ensure we're not calling ourselves twice
     if IS_MAIN_THREAD.with(|v| v.get().is_some()) {
@@ -99,6 +121,11 @@ pub fn register_pg_guard_panic_handler() {
                             file: location.file().to_string(),
                             line: location.line(),
                             col: location.column(),
+                            backtrace: if is_rust_panic(info.payload()) {
+                                capture_backtrace()
+                            } else {
+                                None
+                            },
                         }),
                         None => None,
                     }
@@ -110,10 +137,113 @@ pub fn register_pg_guard_panic_handler() {
     }))
 }
 
+/// Was the panic with `payload` a Rust `panic!()`, rather than the `JumpContext` a Postgres ERROR
+/// is carried across Rust frames in?  Only those are worth a backtrace
+fn is_rust_panic(payload: &(dyn Any + Send)) -> bool {
+    payload.is::<String>() || payload.is::<&str>()
+}
+
+/// Capture a backtrace of the `panic!()` currently being handled, if one is wanted.
+///
+/// The `pgx.panic_backtrace` setting decides, when it's been set (ie, `SET pgx.panic_backtrace =
+/// on`).  Otherwise the `RUST_BACKTRACE` environment variable of the Postgres server does, as it
+/// would for any Rust program.  A backtrace is only useful if the extension was built with debug
+/// info, which release builds have to ask for with `debug = true` in their Cargo profile
+fn capture_backtrace() -> Option<String> {
+    // it's not a real GUC, so reading it as a placeholder works no matter which pgx extensions
+    // are loaded
+    let setting = unsafe {
+        let value = crate::GetConfigOption(
+            b"pgx.panic_backtrace\0".as_ptr() as *const std::os::raw::c_char,
+            true,
+            false,
+        );
+        if value.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(value).to_string_lossy().to_lowercase())
+        }
+    };
+
+    let backtrace = match setting.as_deref() {
+        Some("on") | Some("true") | Some("yes") | Some("1") => Backtrace::force_capture(),
+        Some("off") | Some("false") | Some("no") | Some("0") => return None,
+        _ => Backtrace::capture(),
+    };
+
+    match backtrace.status() {
+        BacktraceStatus::Captured => Some(backtrace.to_string()),
+        _ => None,
+    }
+}
+
+/// The `SQLSTATE` a Rust `panic!()` is reported with, `XX000`
+const ERRCODE_INTERNAL_ERROR: i32 = 2600;
+
+/// A Postgres ERROR (or Rust panic) caught by `pg_try()`, as handed to `PgTryResult::catch_error()`
+#[derive(Clone, Debug)]
+pub struct CaughtError {
+    /// The error's `SQLSTATE`, packed into an integer as Postgres does.  `pgx::PgSqlErrorCode`
+    /// has a variant for each one.  A Rust panic is `ERRCODE_INTERNAL_ERROR`
+    pub sqlerrcode: i32,
+
+    /// The primary error message
+    pub message: String,
+
+    /// The error's `DETAIL`, if it has one
+    pub detail: Option<String>,
+
+    /// The error's `HINT`, if it has one
+    pub hint: Option<String>,
+}
+
+impl CaughtError {
+    /// The five-character `SQLSTATE` of the error, such as `23505`
+    pub fn sqlstate(&self) -> String {
+        (0..5)
+            .map(|i| (((self.sqlerrcode >> (6 * i)) & 0x3F) as u8 + b'0') as char)
+            .collect()
+    }
+
+    /// Raise this error again, as an ERROR with the same `SQLSTATE`, message, and `DETAIL`
+    #[track_caller]
+    pub fn rethrow(self) -> ! {
+        let location = std::panic::Location::caller();
+        let c_message = std::ffi::CString::new(self.message.replace('\0', "")).unwrap();
+        let c_file = std::ffi::CString::new(location.file()).unwrap_or_default();
+
+        unsafe {
+            match self.detail {
+                Some(detail) => {
+                    let c_detail = std::ffi::CString::new(detail).unwrap_or_default();
+                    pgx_ereport_detail(
+                        crate::ERROR as i32,
+                        self.sqlerrcode,
+                        c_message.as_ptr(),
+                        c_detail.as_ptr(),
+                        c_file.as_ptr(),
+                        location.line() as i32,
+                        location.column() as i32,
+                    );
+                }
+                None => pgx_ereport(
+                    crate::ERROR as i32,
+                    self.sqlerrcode,
+                    c_message.as_ptr(),
+                    c_file.as_ptr(),
+                    location.line() as i32,
+                    location.column() as i32,
+                ),
+            }
+        }
+        unreachable!("ereport() failed to rethrow: {}", self.message);
+    }
+}
+
 /// A `std::result::Result`-type value returned from `pg_try()` that allows for performing cleanup
 /// work after a closure raised an error and before it is possibly rethrown
 #[must_use = "this `PgTryResult` may be be holding a Postgres ERROR.  It must be consumed or rethrown"]
-pub struct PgTryResult<T>(std::thread::Result<T>);
+pub struct PgTryResult<T>(std::thread::Result<T>, crate::MemoryContext);
 
 impl<T> PgTryResult<T> {
     /// Retrieve the returned value or panic if the try block raised an error
@@ -160,6 +290,30 @@ impl<T> PgTryResult<T> {
         }
     }
 
+    /// Handle an error thrown by the try block with `catch`, which is given the caught error to
+    /// decide what to return instead.  Use the error's `sqlerrcode` to tell, say, a unique
+    /// violation from a serialization failure.
+    ///
+    /// ## Safety
+    ///
+    /// This function does not rethrow a caught ERROR, so it has the same caveats as
+    /// `unwrap_or_else()`.  Unless the try block ran in a subtransaction that's rolled back after
+    /// the error, Postgres may be left in an undefined state.  `catch` may rethrow the error with
+    /// `ereport()` (or `panic!()`) if it's not one it knows how to handle
+    pub unsafe fn catch_error<F>(self, catch: F) -> T
+    where
+        F: FnOnce(CaughtError) -> T,
+    {
+        match self.0 {
+            Ok(result) => result,
+            Err(e) => {
+                let error = copy_error(e, self.1);
+                FlushErrorState();
+                catch(error)
+            }
+        }
+    }
+
     /// Perform some operation cleanup operation after the try block if an error was thrown.
     ///
     /// In the event an error was caught, it is rethrown.
@@ -228,10 +382,54 @@ where
     // run try_func() in a catch_unwind, as we never want a Rust panic! to leak
     // from this function.  It's imperative that we nevery try to panic! across
     // FFI (extern "C") function boundaries
+    let context = unsafe { crate::CurrentMemoryContext };
     let result = catch_unwind(try_func);
 
     // return our result -- it could be Ok(), or it could be an Err()
-    PgTryResult(result)
+    PgTryResult(result, context)
+}
+
+/// Make a `CaughtError` of `error`, before the caller flushes Postgres' error state.  `context` is
+/// the memory context the try block started in
+unsafe fn copy_error(
+    error: Box<dyn Any + std::marker::Send>,
+    context: crate::MemoryContext,
+) -> CaughtError {
+    match downcast_err(error) {
+        Ok(message) => {
+            // forget where the panic!() happened, so the next one records its own location
+            take_panic_location();
+            CaughtError {
+                sqlerrcode: ERRCODE_INTERNAL_ERROR,
+                message,
+                detail: None,
+                hint: None,
+            }
+        }
+
+        Err(_) => {
+            // Postgres longjmp()s out of ereport() still in ErrorContext, which CopyErrorData()
+            // can't copy into, so go back to where the try block started, as PG_CATCH() would
+            crate::CurrentMemoryContext = context;
+
+            let edata = crate::CopyErrorData();
+            let string = |s: *const std::os::raw::c_char| {
+                if s.is_null() {
+                    None
+                } else {
+                    Some(CStr::from_ptr(s).to_string_lossy().into_owned())
+                }
+            };
+            let error = CaughtError {
+                sqlerrcode: (*edata).sqlerrcode,
+                message: string((*edata).message).unwrap_or_default(),
+                detail: string((*edata).detail),
+                hint: string((*edata).hint),
+            };
+            crate::FreeErrorData(edata);
+            error
+        }
+    }
 }
 
 fn catch_guard<Catch>(error: Box<dyn Any + std::marker::Send>, catch_func: Catch)
@@ -246,21 +444,36 @@ where
     match downcast_err(error) {
         // the error is a String, which means it was originally a Rust panic!(), so
         // translate it into an elog(ERROR), including the code location that caused
-        // the panic!()
+        // the panic!(), and its backtrace as the DETAIL if we captured one
         Ok(message) => {
             let location = take_panic_location();
             let c_message = std::ffi::CString::new(message.clone()).unwrap();
             let c_file = std::ffi::CString::new(location.file).unwrap();
 
             unsafe {
-                pgx_ereport(
-                    crate::ERROR as i32,
-                    2600, // ERRCODE_INTERNAL_ERROR
-                    c_message.as_ptr(),
-                    c_file.as_ptr(),
-                    location.line as i32,
-                    location.col as i32,
-                );
+                match location.backtrace {
+                    Some(backtrace) => {
+                        let c_detail = std::ffi::CString::new(format!("backtrace:\n{}", backtrace))
+                            .unwrap_or_default();
+                        pgx_ereport_detail(
+                            crate::ERROR as i32,
+                            ERRCODE_INTERNAL_ERROR,
+                            c_message.as_ptr(),
+                            c_detail.as_ptr(),
+                            c_file.as_ptr(),
+                            location.line as i32,
+                            location.col as i32,
+                        );
+                    }
+                    None => pgx_ereport(
+                        crate::ERROR as i32,
+                        ERRCODE_INTERNAL_ERROR,
+                        c_message.as_ptr(),
+                        c_file.as_ptr(),
+                        location.line as i32,
+                        location.col as i32,
+                    ),
+                }
             }
             unreachable!("ereport() failed at depth==0 with message: {}", message);
         }
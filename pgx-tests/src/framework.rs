@@ -4,19 +4,21 @@
 use std::process::{Command, Stdio};
 
 use lazy_static::*;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
 
 use colored::*;
 use pgx::*;
 use pgx_utils::{
-    createdb, get_dropdb_path, get_initdb_path, get_named_capture, get_postmaster_path,
-    get_target_dir, BASE_POSTGRES_TESTING_PORT_NO,
+    createdb, createrole, get_dropdb_path, get_initdb_path, get_named_capture, get_pgbin_dir,
+    get_postmaster_path, get_target_dir, grant_database, BASE_POSTGRES_TESTING_PORT_NO,
 };
 use postgres::error::DbError;
 use postgres::Client;
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 type LogLines = Arc<Mutex<HashMap<String, Vec<String>>>>;
 
@@ -33,8 +35,20 @@ lazy_static! {
         system_session_id: "NONE".to_string(),
     });
     static ref SHUTDOWN_HOOKS: Mutex<Vec<Box<dyn Fn() + Send>>> = Mutex::new(Vec::new());
+
+    /// `#[pg_test]`s share the test Postgres, while a `CrashTestCluster` needs it to itself
+    static ref CLUSTER_LOCK: RwLock<()> = RwLock::new(());
 }
 
+/// The pid of the postmaster we started, which changes if a `CrashTestCluster` restarts it
+static POSTMASTER_PID: AtomicU32 = AtomicU32::new(0);
+
+/// Where a test Postgres left running after the tests finish logs to, in its data directory
+const POSTMASTER_LOG: &str = "postmaster.log";
+
+/// How long we wait for a crashed Postgres' processes to be gone
+const CRASH_TIMEOUT: Duration = Duration::from_secs(30);
+
 fn register_shutdown_hook() {
     extern "C" fn run_shutdown_hooks() {
         for func in SHUTDOWN_HOOKS.lock().unwrap().iter() {
@@ -58,6 +72,8 @@ pub fn run_test(
 ) {
     let (loglines, system_session_id) = initialize_test_framework(postgresql_conf);
 
+    // a `CrashTestCluster` can't be crashing Postgres while we're using it
+    let _shared = CLUSTER_LOCK.read().unwrap_or_else(|e| e.into_inner());
     let (mut client, session_id) = client();
 
     let schema = "tests"; // get_extension_schema();
@@ -165,22 +181,40 @@ fn initialize_test_framework(postgresql_conf: Vec<&'static str>) -> (LogLines, S
     if !state.installed {
         register_shutdown_hook();
 
-        install_extension();
-        initdb(postgresql_conf);
-
-        let system_session_id = start_pg(state.loglines.clone());
-        dropdb();
-        createdb(
-            pg_sys::get_pg_major_version_num(),
-            &get_pg_host(),
-            get_pg_port(),
-            get_pg_dbname(),
-            false,
-        );
-        create_extension();
+        if is_set("PGX_TEST_EXISTING_CLUSTER") {
+            // a previous `cargo pgx test --keep-running` already did all the setup, so all
+            // we need is for it to still be there
+            if !get_pgdata_path().join("postmaster.pid").exists() {
+                panic!(
+                    "no test Postgres is running in {}, run `cargo pgx test --keep-running` first",
+                    get_pgdata_path().display()
+                );
+            }
+            eprintln!(
+                "using the test Postgres already running on port {}",
+                get_pg_port()
+            );
+        } else {
+            if !is_set("PGX_TEST_NO_INSTALL") {
+                install_extension();
+            }
+            stop_leftover_pg();
+            initdb(postgresql_conf);
+
+            state.system_session_id = start_pg(state.loglines.clone());
+            dropdb();
+            createdb(
+                pg_sys::get_pg_major_version_num(),
+                &get_pg_host(),
+                get_pg_port(),
+                get_pg_dbname(),
+                false,
+            );
+            create_extension();
+            create_test_role();
+        }
 
         state.installed = true;
-        state.system_session_id = system_session_id;
     }
 
     (state.loglines.clone(), state.system_session_id.clone())
@@ -196,13 +230,7 @@ pub fn client() -> (postgres::Client, String) {
         }
     }
 
-    let mut client = postgres::Config::new()
-        .host(&get_pg_host())
-        .port(get_pg_port())
-        .user(&get_pg_user())
-        .dbname(&get_pg_dbname())
-        .connect(postgres::NoTls)
-        .unwrap();
+    let mut client = connect(&get_pg_user());
 
     let session_id = determine_session_id(&mut client);
     client
@@ -220,11 +248,26 @@ pub fn client() -> (postgres::Client, String) {
     (client, session_id)
 }
 
+fn connect(user: &str) -> postgres::Client {
+    postgres::Config::new()
+        .host(&get_pg_host())
+        .port(get_pg_port())
+        .user(user)
+        .dbname(&get_pg_dbname())
+        .connect(postgres::NoTls)
+        .unwrap()
+}
+
 fn install_extension() {
     eprintln!("installing extension");
-    let mut command = Command::new("cargo-pgx")
-        .arg("pgx")
-        .arg("install")
+    let mut command = Command::new("cargo-pgx");
+    // the schema changes along with the code under test, and there's nobody to ask about it
+    command.arg("pgx").arg("install").arg("--force");
+    if is_set("PGX_TEST_NO_SCHEMA") {
+        command.arg("--no-schema");
+    }
+
+    let mut child = command
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .env(
@@ -242,7 +285,7 @@ fn install_extension() {
         .spawn()
         .unwrap();
 
-    let status = command.wait().unwrap();
+    let status = child.wait().unwrap();
     if !status.success() {
         panic!("failed to install extension");
     }
@@ -283,9 +326,43 @@ fn modify_postgresql_conf(pgdata: PathBuf, postgresql_conf: Vec<&'static str>) {
             .write_all(format!("{}\n", setting).as_bytes())
             .expect("couldn't append custom setting to postgresql.conf");
     }
+
+    // settings from `cargo pgx test --set` and `[package.metadata.pgx.postgresql_conf]`
+    if let Ok(settings) = std::env::var("PGX_TEST_POSTGRESQL_CONF") {
+        postgresql_conf_file
+            .write_all(format!("{}\n", settings).as_bytes())
+            .expect("couldn't append cargo pgx settings to postgresql.conf");
+    }
 }
 
 fn start_pg(loglines: LogLines) -> String {
+    let session_id = spawn_pg(loglines);
+
+    if is_set("PGX_TEST_KEEP_RUNNING") {
+        eprintln!(
+            "leaving Postgres running on port {} for `cargo pgx test --existing-cluster`, logging to {}",
+            get_pg_port(),
+            get_pgdata_path().join(POSTMASTER_LOG).display()
+        );
+        return session_id;
+    }
+
+    // add a shutdown hook so we can terminate it when the test framework exits
+    add_shutdown_hook(move || unsafe {
+        let message_string =
+            std::ffi::CString::new("Stopping Postgres\n\n".bold().blue().to_string()).unwrap();
+        libc::printf(message_string.as_ptr());
+        libc::kill(
+            POSTMASTER_PID.load(Ordering::SeqCst) as libc::pid_t,
+            libc::SIGTERM,
+        );
+    });
+
+    session_id
+}
+
+/// Start the postmaster and wait for it to accept connections
+fn spawn_pg(loglines: LogLines) -> String {
     let mut command = Command::new(get_postmaster_path(pg_sys::get_pg_major_version_num()));
     command
         .arg("-D")
@@ -294,27 +371,41 @@ fn start_pg(loglines: LogLines) -> String {
         .arg(get_pg_host())
         .arg("-p")
         .arg(get_pg_port().to_string())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::piped());
+        .stdout(Stdio::inherit());
+
+    // a Postgres that's to outlive us can't log to a pipe that closes when we exit, so it logs to
+    // a file in its data directory instead, which we follow from where it ends now
+    let logfile = if is_set("PGX_TEST_KEEP_RUNNING") {
+        let path = get_pgdata_path().join(POSTMASTER_LOG);
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .unwrap_or_else(|e| panic!("couldn't open {}: {}", path.display(), e));
+        let offset = file.metadata().unwrap().len();
+        command.stderr(file);
+        Some((path, offset))
+    } else {
+        command.stderr(Stdio::piped());
+        None
+    };
 
     let command_str = format!("{:?}", command);
 
     // start Postgres and monitor its stderr in the background
     // also notify the main thread when it's ready to accept connections
-    let (pgpid, session_id) = monitor_pg(command, command_str, loglines);
-
-    // add a shutdown hook so we can terminate it when the test framework exits
-    add_shutdown_hook(move || unsafe {
-        let message_string =
-            std::ffi::CString::new("Stopping Postgres\n\n".bold().blue().to_string()).unwrap();
-        libc::printf(message_string.as_ptr());
-        libc::kill(pgpid as libc::pid_t, libc::SIGTERM);
-    });
+    let (pgpid, session_id) = monitor_pg(command, command_str, loglines, logfile);
+    POSTMASTER_PID.store(pgpid, Ordering::SeqCst);
 
     session_id
 }
 
-fn monitor_pg(mut command: Command, cmd_string: String, loglines: LogLines) -> (u32, String) {
+fn monitor_pg(
+    mut command: Command,
+    cmd_string: String,
+    loglines: LogLines,
+    logfile: Option<(PathBuf, u64)>,
+) -> (u32, String) {
     let (sender, receiver) = std::sync::mpsc::channel();
 
     std::thread::spawn(move || {
@@ -330,12 +421,17 @@ fn monitor_pg(mut command: Command, cmd_string: String, loglines: LogLines) -> (
         eprintln!("{}", pg_sys::get_pg_version_string().bold().purple());
 
         // wait for the database to say its ready to start up
-        let reader = BufReader::new(
-            child
-                .stderr
-                .take()
-                .expect("couldn't take postmaster stderr"),
-        );
+        let reader: Box<dyn BufRead> = match &logfile {
+            Some((path, offset)) => {
+                Box::new(BufReader::new(LogFollower::new(path, *offset, &mut child)))
+            }
+            None => Box::new(BufReader::new(
+                child
+                    .stderr
+                    .take()
+                    .expect("couldn't take postmaster stderr"),
+            )),
+        };
 
         let regex = regex::Regex::new(r#"\[.*?\] \[.*?\] \[(?P<session_id>.*?)\]"#).unwrap();
         let mut is_started_yet = false;
@@ -379,14 +475,10 @@ fn monitor_pg(mut command: Command, cmd_string: String, loglines: LogLines) -> (
             }
         }
 
-        // wait for Postgres to really finish
-        match child.try_wait() {
-            Ok(status) => {
-                if let Some(_status) = status {
-                    // we exited normally
-                }
-            }
-            Err(e) => panic!(e),
+        // wait for Postgres to really finish, which also means a crashed postmaster doesn't
+        // linger as a zombie
+        if let Err(e) = child.wait() {
+            panic!(e)
         }
     });
 
@@ -395,6 +487,187 @@ fn monitor_pg(mut command: Command, cmd_string: String, loglines: LogLines) -> (
     receiver.recv().expect("Postgres failed to start")
 }
 
+/// Reads the log file a postmaster's stderr goes to as it's written, until the postmaster exits
+struct LogFollower<'a> {
+    file: std::fs::File,
+    postmaster: &'a mut std::process::Child,
+}
+
+impl<'a> LogFollower<'a> {
+    fn new(path: &Path, offset: u64, postmaster: &'a mut std::process::Child) -> Self {
+        let mut file = std::fs::File::open(path)
+            .unwrap_or_else(|e| panic!("couldn't open {}: {}", path.display(), e));
+        file.seek(SeekFrom::Start(offset))
+            .unwrap_or_else(|e| panic!("couldn't seek in {}: {}", path.display(), e));
+        LogFollower { file, postmaster }
+    }
+}
+
+impl<'a> Read for LogFollower<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            // check first, so whatever it wrote before exiting is still read
+            let exited = self.postmaster.try_wait()?.is_some();
+            let len = self.file.read(buf)?;
+            if len > 0 || exited {
+                return Ok(len);
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+/// Exclusive use of the test Postgres, for plain `#[test]`s that crash it and check what it
+/// recovers.  `#[pg_test]`s wait until it's dropped.
+///
+/// Data that's to survive a crash must be committed, unlike the data `#[pg_test]`s create, so
+/// crash tests should clean up after themselves.
+///
+/// ```rust,no_run
+/// #[test]
+/// fn test_counter_survives_crash() {
+///     let cluster = pgx_tests::CrashTestCluster::new(crate::pg_test::postgresql_conf_options());
+///     let mut client = cluster.client();
+///     client.simple_query("CREATE TABLE counter AS SELECT 42 AS n").unwrap();
+///
+///     cluster.crash_and_restart();
+///
+///     let mut client = cluster.client();
+///     let row = client.query_one("SELECT n FROM counter", &[]).unwrap();
+///     assert_eq!(row.get::<_, i32>(0), 42);
+///     client.simple_query("DROP TABLE counter").unwrap();
+/// }
+/// ```
+pub struct CrashTestCluster {
+    _exclusive: RwLockWriteGuard<'static, ()>,
+}
+
+impl CrashTestCluster {
+    /// Set up the test Postgres, if no test has yet, with the crate's `postgresql_conf` options
+    /// and wait for the `#[pg_test]`s using it to finish
+    pub fn new(postgresql_conf: Vec<&'static str>) -> Self {
+        initialize_test_framework(postgresql_conf);
+        CrashTestCluster {
+            _exclusive: CLUSTER_LOCK.write().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+
+    /// A new connection to the test database
+    pub fn client(&self) -> postgres::Client {
+        client().0
+    }
+
+    /// `SIGKILL` the postmaster and every process it started, as if the machine lost power, and
+    /// start it again.  Returns once it's replayed its WAL and is accepting connections.
+    ///
+    /// Any connections made before the crash are broken.
+    pub fn crash_and_restart(&self) {
+        let pids = postmaster_processes();
+        eprintln!("{} pids={:?}", "Crashing Postgres".bold().red(), pids);
+        for pid in &pids {
+            unsafe {
+                libc::kill(*pid, libc::SIGKILL);
+            }
+        }
+
+        // a new postmaster won't start while any of them are still attached to the old one's
+        // shared memory
+        let start = Instant::now();
+        while pids.iter().any(|pid| unsafe { libc::kill(*pid, 0) } == 0) {
+            if start.elapsed() > CRASH_TIMEOUT {
+                panic!("crashed Postgres processes are still running: {:?}", pids);
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        let state = TEST_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        spawn_pg(state.loglines.clone());
+    }
+
+    /// Shut Postgres down cleanly, call `while_stopped` with its data directory, and start it
+    /// again.  Returns once it's accepting connections.
+    ///
+    /// Any connections made before the shutdown are broken.
+    pub fn shutdown_and_restart<F: FnOnce(&Path)>(&self, while_stopped: F) {
+        eprintln!("{}", "Restarting Postgres".bold().blue());
+        let pgdata = get_pgdata_path();
+        let mut pg_ctl = get_pgbin_dir(pg_sys::get_pg_major_version_num());
+        pg_ctl.push("pg_ctl");
+        let status = Command::new(pg_ctl)
+            .arg("stop")
+            .arg("-D")
+            .arg(pgdata.to_str().unwrap())
+            .arg("-m")
+            .arg("fast")
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()
+            .unwrap();
+        if !status.success() {
+            panic!("failed to stop the test Postgres in {}", pgdata.display());
+        }
+
+        while_stopped(&pgdata);
+
+        let state = TEST_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
+        spawn_pg(state.loglines.clone());
+    }
+}
+
+/// The running postmaster, from its `postmaster.pid`, followed by its child processes
+fn postmaster_processes() -> Vec<libc::pid_t> {
+    let pidfile = get_pgdata_path().join("postmaster.pid");
+    let contents = std::fs::read_to_string(&pidfile)
+        .unwrap_or_else(|e| panic!("couldn't read {}: {}", pidfile.display(), e));
+    let postmaster = contents
+        .lines()
+        .next()
+        .and_then(|line| line.trim().parse::<libc::pid_t>().ok())
+        .unwrap_or_else(|| panic!("{} doesn't start with a pid", pidfile.display()));
+
+    let output = Command::new("pgrep")
+        .arg("-P")
+        .arg(postmaster.to_string())
+        .output()
+        .expect("failed to run pgrep");
+    let mut pids = vec![postmaster];
+    pids.extend(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse::<libc::pid_t>().ok()),
+    );
+    pids
+}
+
+/// Stop the Postgres a previous `--keep-running` run left behind, so we can start our own
+fn stop_leftover_pg() {
+    let pgdata = get_pgdata_path();
+    if !pgdata.join("postmaster.pid").exists() {
+        return;
+    }
+
+    let mut pg_ctl = get_pgbin_dir(pg_sys::get_pg_major_version_num());
+    pg_ctl.push("pg_ctl");
+    let status = Command::new(pg_ctl)
+        .arg("stop")
+        .arg("-D")
+        .arg(pgdata.to_str().unwrap())
+        .arg("-m")
+        .arg("fast")
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+
+    // a stale pid file, from a Postgres that's no longer running, is fine too
+    if !status.success() && pgdata.join("postmaster.pid").exists() {
+        panic!(
+            "failed to stop the test Postgres left running in {}",
+            pgdata.display()
+        );
+    }
+}
+
 fn dropdb() {
     let output = Command::new(get_dropdb_path(pg_sys::get_pg_major_version_num()))
         .arg("--if-exists")
@@ -422,13 +695,60 @@ fn dropdb() {
 }
 
 fn create_extension() {
-    let (mut client, _) = client();
+    // the roles its functions are granted to have to exist first
+    if let Ok(rolenames) = std::env::var("PGX_TEST_GRANT_ROLES") {
+        for rolename in rolenames.split(',') {
+            createrole(
+                pg_sys::get_pg_major_version_num(),
+                &get_pg_host(),
+                get_pg_port(),
+                rolename,
+                false,
+            );
+        }
+    }
+
+    // always as the bootstrap superuser, regardless of who the tests run as
+    let mut client = connect(&get_bootstrap_user());
 
     client
         .simple_query(&format!("CREATE EXTENSION {};", get_extension_name()))
         .unwrap();
 }
 
+fn create_test_role() {
+    if let Some(rolename) = get_test_user() {
+        let is_superuser = std::env::var("PGX_TEST_USER_IS_SUPERUSER").is_ok();
+        let major_version = pg_sys::get_pg_major_version_num();
+
+        createrole(
+            major_version,
+            &get_pg_host(),
+            get_pg_port(),
+            &rolename,
+            is_superuser,
+        );
+
+        if !is_superuser {
+            grant_database(
+                major_version,
+                &get_pg_host(),
+                get_pg_port(),
+                get_pg_dbname(),
+                &rolename,
+            );
+
+            // so the role can call the test functions
+            connect(&get_bootstrap_user())
+                .simple_query(&format!(
+                    "GRANT USAGE ON SCHEMA tests TO \"{}\";",
+                    rolename.replace("\"", "\"\"")
+                ))
+                .expect("failed to grant USAGE on the tests schema");
+        }
+    }
+}
+
 fn get_extension_name() -> String {
     std::env::var("CARGO_PKG_NAME")
         .unwrap_or_else(|_| panic!("CARGO_PKG_NAME is not an envvar"))
@@ -457,5 +777,18 @@ fn get_pg_dbname() -> &'static str {
 }
 
 fn get_pg_user() -> String {
+    get_test_user().unwrap_or_else(get_bootstrap_user)
+}
+
+/// Whether `cargo pgx test` set one of its `PGX_TEST_*` switches
+fn is_set(envvar: &str) -> bool {
+    std::env::var(envvar).is_ok()
+}
+
+fn get_test_user() -> Option<String> {
+    std::env::var("PGX_TEST_USER").ok()
+}
+
+fn get_bootstrap_user() -> String {
     std::env::var("USER").unwrap_or_else(|_| panic!("USER is not an envvar"))
 }
@@ -14,6 +14,7 @@ pub mod pg_test {
     }
 
     pub fn postgresql_conf_options() -> Vec<&'static str> {
-        vec![]
+        // so the tests can use shared memory
+        vec!["shared_preload_libraries = 'pgx_tests'"]
     }
 }
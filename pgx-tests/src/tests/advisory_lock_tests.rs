@@ -0,0 +1,61 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+    use std::time::Duration;
+
+    fn advisory_locks_held() -> i64 {
+        Spi::get_one(
+            "SELECT count(*) FROM pg_locks WHERE locktype = 'advisory' AND pid = pg_backend_pid();",
+        )
+        .expect("count was null")
+    }
+
+    #[pg_test]
+    fn test_session_lock_released_on_drop() {
+        let guard = PgAdvisoryLock::session(42).lock();
+        assert_eq!(guard.key(), PgAdvisoryLockKey::BigInt(42));
+        assert_eq!(advisory_locks_held(), 1);
+
+        drop(guard);
+        assert_eq!(advisory_locks_held(), 0);
+    }
+
+    #[pg_test]
+    fn test_lock_interoperates_with_sql() {
+        let _guard = PgAdvisoryLock::session((1, 2)).lock();
+        let found = Spi::get_one::<bool>(
+            "SELECT EXISTS (SELECT 1 FROM pg_locks WHERE locktype = 'advisory' AND classid = 1 AND objid = 2 AND objsubid = 2);",
+        )
+        .expect("exists was null");
+        assert!(found);
+    }
+
+    #[pg_test]
+    fn test_try_lock() {
+        let guard = PgAdvisoryLock::session(7).try_lock();
+        assert!(guard.is_some());
+        guard.unwrap().unlock();
+        assert_eq!(advisory_locks_held(), 0);
+    }
+
+    #[pg_test]
+    fn test_shared_locks_are_compatible() {
+        let _first = PgAdvisoryLock::session(8).shared().lock();
+        let second = PgAdvisoryLock::session(8)
+            .shared()
+            .lock_timeout(Duration::from_millis(10));
+        assert!(second.is_some());
+    }
+
+    #[pg_test]
+    fn test_transaction_lock_outlives_guard() {
+        drop(PgAdvisoryLock::transaction(9).lock());
+        assert_eq!(advisory_locks_held(), 1);
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#![cfg(feature = "rkyv")]
+
+use pgx::*;
+use rkyv::{Archive, Deserialize, Serialize};
+use std::ffi::CStr;
+
+#[derive(Archive, Serialize, Deserialize, PostgresType, Debug, PartialEq)]
+#[archive(check_bytes)]
+#[archived_inoutfuncs]
+pub struct Tags {
+    tags: Vec<String>,
+}
+
+impl ArchivedInOutFuncs for Tags {
+    fn input(input: &CStr) -> Self {
+        let tags = input
+            .to_str()
+            .unwrap()
+            .split(',')
+            .map(|tag| tag.to_string());
+        Tags {
+            tags: tags.collect(),
+        }
+    }
+
+    fn output(archived: &ArchivedTags, buffer: &mut StringInfo) {
+        let tags = archived
+            .tags
+            .iter()
+            .map(|tag| tag.as_str())
+            .collect::<Vec<_>>();
+        buffer.push_str(&tags.join(","));
+    }
+}
+
+#[pg_extern]
+fn tags_contain(tags: PgArchived<Tags>, tag: &str) -> bool {
+    tags.tags.iter().any(|t| t.as_str() == tag)
+}
+
+#[pg_extern]
+fn tags_add(tags: PgArchived<Tags>, tag: &str) -> PgArchived<Tags> {
+    let mut tags = tags.deserialize();
+    tags.tags.push(tag.to_string());
+    PgArchived::new(&tags)
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use crate::tests::archived_tests::Tags;
+    use pgx::*;
+
+    #[pg_test]
+    fn test_archived_round_trip() {
+        Spi::run("CREATE TABLE archived_tags (id int, tags Tags)");
+        Spi::run("INSERT INTO archived_tags VALUES (1, 'a,b'), (2, 'c')");
+        Spi::run("UPDATE archived_tags SET tags = tags_add(tags, 'z') WHERE id = 1");
+
+        let tags = Spi::get_one::<PgArchived<Tags>>("SELECT tags FROM archived_tags WHERE id = 1")
+            .expect("SPI returned NULL");
+        let names = tags.tags.iter().map(|tag| tag.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b", "z"]);
+        assert_eq!(
+            tags.deserialize(),
+            Tags {
+                tags: vec!["a".to_string(), "b".to_string(), "z".to_string()]
+            }
+        );
+
+        assert_eq!(
+            Spi::get_one::<String>("SELECT tags::text FROM archived_tags WHERE id = 1"),
+            Some("a,b,z".to_string())
+        );
+        assert_eq!(
+            Spi::get_one::<i32>("SELECT id FROM archived_tags WHERE tags_contain(tags, 'c')"),
+            Some(2)
+        );
+    }
+
+    #[pg_test]
+    fn test_archived_invalid() {
+        let garbage = vec![0xffu8; 16].into_datum().unwrap();
+        let error = subtransaction(|| unsafe {
+            PgArchived::<Tags>::from_datum(garbage);
+        })
+        .err()
+        .expect("an invalid archive was read");
+
+        assert_eq!(
+            PgSqlErrorCode::from_sqlerrcode(error.sqlerrcode),
+            Some(PgSqlErrorCode::ERRCODE_DATA_CORRUPTED)
+        );
+        assert!(
+            error.message.starts_with("invalid archived"),
+            "{}",
+            error.message
+        );
+    }
+}
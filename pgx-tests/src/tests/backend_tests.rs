@@ -0,0 +1,90 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_pid() {
+        assert_eq!(
+            Some(backend::pid()),
+            Spi::get_one::<i32>("SELECT pg_backend_pid()")
+        );
+    }
+
+    #[pg_test]
+    fn test_database() {
+        assert_eq!(
+            Some(backend::database_name()),
+            Spi::get_one::<String>("SELECT current_database()::text")
+        );
+        assert_eq!(
+            Some(backend::database_oid()),
+            Spi::get_one::<pg_sys::Oid>(
+                "SELECT oid FROM pg_database WHERE datname = current_database()"
+            )
+        );
+    }
+
+    #[pg_test]
+    fn test_user() {
+        assert_eq!(
+            Some(backend::user_name()),
+            Spi::get_one::<String>("SELECT current_user::text")
+        );
+        assert_eq!(
+            Some(backend::session_user_name()),
+            Spi::get_one::<String>("SELECT session_user::text")
+        );
+        assert_eq!(
+            Some(backend::user_oid()),
+            Spi::get_one::<pg_sys::Oid>("SELECT oid FROM pg_roles WHERE rolname = current_user")
+        );
+    }
+
+    #[pg_test]
+    fn test_application_name() {
+        Spi::run("SET application_name TO 'backend_tests'");
+        assert_eq!(
+            backend::application_name(),
+            Some("backend_tests".to_string())
+        );
+
+        Spi::run("SET application_name TO ''");
+        assert_eq!(backend::application_name(), None);
+    }
+
+    #[pg_test]
+    fn test_client_addr() {
+        assert_eq!(
+            backend::client_addr(),
+            Spi::get_one::<Inet>("SELECT inet_client_addr()").map(|inet| inet.0)
+        );
+        assert_eq!(
+            backend::client_port(),
+            Spi::get_one::<i32>("SELECT inet_client_port()")
+        );
+    }
+
+    #[pg_test]
+    fn test_transaction_state() {
+        // tests run inside of a transaction that's rolled back afterwards
+        assert!(backend::in_transaction());
+        assert!(backend::in_transaction_block());
+        assert!(!backend::is_read_only());
+        assert_eq!(
+            backend::isolation_level().as_str(),
+            Spi::get_one::<String>("SELECT current_setting('transaction_isolation')").unwrap()
+        );
+    }
+
+    #[pg_test]
+    fn test_read_only() {
+        Spi::run("SET TRANSACTION READ ONLY");
+        assert!(backend::is_read_only());
+    }
+}
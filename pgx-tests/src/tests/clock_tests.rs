@@ -0,0 +1,63 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+    use std::time::{Duration, SystemTime};
+
+    #[pg_test]
+    fn test_clock_set_and_advance() {
+        assert!(!clock::is_overridden());
+        {
+            let _clock = clock::set(SystemTime::UNIX_EPOCH);
+            assert!(clock::is_overridden());
+            assert_eq!(clock::now(), SystemTime::UNIX_EPOCH);
+
+            clock::advance(Duration::from_secs(90));
+            assert_eq!(
+                clock::now(),
+                SystemTime::UNIX_EPOCH + Duration::from_secs(90)
+            );
+        }
+
+        assert!(!clock::is_overridden());
+        assert!(clock::now() > SystemTime::UNIX_EPOCH + Duration::from_secs(90));
+    }
+
+    #[pg_test]
+    fn test_clock_nested_overrides() {
+        let _outer = clock::set(SystemTime::UNIX_EPOCH);
+        {
+            let _inner = clock::set(SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+            assert_eq!(
+                clock::now(),
+                SystemTime::UNIX_EPOCH + Duration::from_secs(1)
+            );
+        }
+        assert_eq!(clock::now(), SystemTime::UNIX_EPOCH);
+    }
+
+    #[pg_test]
+    fn test_clock_current_timestamp() {
+        let _clock = clock::set(SystemTime::UNIX_EPOCH + Duration::from_secs(946_684_800 + 1));
+        assert_eq!(clock::current_timestamp(), 1_000_000);
+
+        let matches = Spi::get_one_with_args::<bool>(
+            "SELECT '2000-01-01 00:00:00+00'::timestamptz + $1 * interval '1 microsecond' = '2000-01-01 00:00:01+00'",
+            vec![(
+                PgBuiltInOids::INT8OID.oid(),
+                clock::current_timestamp().into_datum(),
+            )],
+        );
+        assert_eq!(matches, Some(true));
+    }
+
+    #[pg_test(error = "clock::advance() requires a clock pinned with clock::set()")]
+    fn test_clock_advance_unpinned() {
+        clock::advance(Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[test]
+    fn test_column_from_options() {
+        let column = vec![Some(1), None, Some(3)]
+            .into_iter()
+            .collect::<PgColumn<i32>>();
+        assert_eq!(column.values, vec![1, 0, 3]);
+        assert_eq!(column.nulls, Some(vec![false, true, false]));
+        assert_eq!(column.null_count(), 1);
+        assert_eq!(column.get(1), None);
+        assert_eq!(column.get(2), Some(&3));
+        assert_eq!(column.into_options(), vec![Some(1), None, Some(3)]);
+    }
+
+    #[test]
+    fn test_column_without_nulls() {
+        let column = vec![Some(1i64), Some(2)]
+            .into_iter()
+            .collect::<PgColumn<i64>>();
+        assert_eq!(column, PgColumn::new(vec![1, 2]));
+        assert_eq!(column.null_count(), 0);
+    }
+
+    #[pg_test]
+    fn test_spi_get_column() {
+        let column = Spi::connect(|client| {
+            Ok(Some(
+                client
+                    .select("SELECT * FROM (VALUES (1), (NULL), (3)) t(x)", None, None)
+                    .get_column::<i32>(1)
+                    .expect("no column 1"),
+            ))
+        })
+        .unwrap();
+
+        assert_eq!(column.values, vec![1, 0, 3]);
+        assert_eq!(column.nulls, Some(vec![false, true, false]));
+    }
+
+    #[pg_test]
+    fn test_spi_get_text_column() {
+        let column = Spi::connect(|client| {
+            Ok(Some(
+                client
+                    .select(
+                        "SELECT 'row ' || i FROM generate_series(1, 3) i",
+                        None,
+                        None,
+                    )
+                    .get_column::<String>(1)
+                    .expect("no column 1"),
+            ))
+        })
+        .unwrap();
+
+        assert_eq!(column.nulls, None);
+        assert_eq!(column.values, vec!["row 1", "row 2", "row 3"]);
+    }
+
+    #[pg_test]
+    fn test_spi_get_missing_column() {
+        let column = Spi::connect(|client| {
+            Ok(Some(
+                client
+                    .select("SELECT 1", None, None)
+                    .get_column::<i32>(2)
+                    .is_none(),
+            ))
+        });
+        assert_eq!(column, Some(true));
+    }
+
+    #[pg_test]
+    fn test_tuplestore_columns() {
+        Spi::run("CREATE TYPE column_test_row AS (id bigint, label text);");
+        let typid = Spi::get_one::<i64>("SELECT 'column_test_row'::regtype::oid::bigint;")
+            .expect("type oid is NULL");
+        let tupdesc = unsafe {
+            PgTupleDesc::from_pg_is_copy(pg_sys::lookup_rowtype_tupdesc_copy(
+                typid as pg_sys::Oid,
+                -1,
+            ))
+        };
+
+        let ids = (0..100i64).map(Some).collect::<PgColumn<i64>>();
+        let labels = (0..100i64)
+            .map(|i| {
+                if i % 10 == 0 {
+                    None
+                } else {
+                    Some(format!("row {}", i))
+                }
+            })
+            .collect::<PgColumn<String>>();
+
+        let mut store = PgTuplestore::new(&tupdesc, true);
+        store.push_columns(&[
+            i64::into_datum_column(ids.clone()),
+            String::into_datum_column(labels.clone()),
+        ]);
+        assert_eq!(store.len(), 100);
+
+        let id_column = store.column(0);
+        let label_column = store.column(1);
+        assert_eq!(unsafe { id_column.to_column::<i64>() }, ids);
+        assert_eq!(unsafe { label_column.to_column::<String>() }, labels);
+    }
+}
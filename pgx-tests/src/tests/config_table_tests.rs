@@ -0,0 +1,36 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+extension_config_table!(
+    name = "test_config",
+    columns = r#"
+    id serial8 NOT NULL PRIMARY KEY,
+    key text NOT NULL UNIQUE,
+    is_default bool NOT NULL DEFAULT false
+"#,
+    filter = "WHERE NOT is_default",
+    sequences = "test_config_id_seq",
+);
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_config_table_registered() {
+        let registered = Spi::get_one::<bool>(
+            "SELECT extconfig @> ARRAY['test_config'::regclass::oid, 'test_config_id_seq'::regclass::oid] FROM pg_extension WHERE extname = 'pgx_tests'",
+        );
+        assert_eq!(registered, Some(true));
+
+        let filter = Spi::get_one::<String>(
+            "SELECT extcondition[array_position(extconfig, 'test_config'::regclass::oid)] FROM pg_extension WHERE extname = 'pgx_tests'",
+        );
+        assert_eq!(filter, Some("WHERE NOT is_default".to_string()));
+    }
+}
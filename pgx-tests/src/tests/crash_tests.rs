@@ -0,0 +1,39 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+// these are plain `#[test]`s, as crashing Postgres from inside it would take the test down too
+#[cfg(test)]
+mod tests {
+    use crate::CrashTestCluster;
+
+    #[test]
+    fn test_committed_data_survives_crash() {
+        let cluster = CrashTestCluster::new(crate::pg_test::postgresql_conf_options());
+        let mut client = cluster.client();
+        client
+            .batch_execute(
+                "DROP TABLE IF EXISTS crash_test_data;
+                 CREATE TABLE crash_test_data AS SELECT generate_series(1, 1000) AS id;",
+            )
+            .expect("failed to create crash_test_data");
+
+        // never committed, so it shouldn't come back
+        let mut tx = client.transaction().unwrap();
+        tx.batch_execute("INSERT INTO crash_test_data VALUES (-1)")
+            .unwrap();
+
+        cluster.crash_and_restart();
+        drop(tx);
+
+        let mut client = cluster.client();
+        let row = client
+            .query_one("SELECT count(*), min(id) FROM crash_test_data", &[])
+            .expect("crash_test_data didn't survive the crash");
+        assert_eq!(row.get::<_, i64>(0), 1000);
+        assert_eq!(row.get::<_, i32>(1), 1);
+
+        client
+            .batch_execute("DROP TABLE crash_test_data")
+            .expect("failed to drop crash_test_data");
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#![cfg(any(
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15"
+))]
+
+use pgx::*;
+
+/// Shared by every backend and not rolled back.  `_PG_init()` initializes it
+pub(crate) static TABLE: PgDshash<i64, i64> = PgDshash::new("pgx_tests_dshash");
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use super::TABLE;
+    use pgx::*;
+
+    fn sorted_entries() -> Vec<(i64, i64)> {
+        let mut entries = TABLE.iter().collect::<Vec<_>>();
+        entries.sort();
+        entries
+    }
+
+    #[pg_test]
+    fn test_dshash() {
+        // a cluster kept running keeps what the last run left
+        TABLE.clear();
+        assert!(TABLE.is_empty());
+
+        for i in 1..=5 {
+            assert_eq!(TABLE.insert(i, i * 10), None);
+        }
+        assert_eq!(TABLE.insert(1, 11), Some(10));
+        assert_eq!(TABLE.get(&1), Some(11));
+        assert_eq!(TABLE.get(&6), None);
+        assert_eq!(TABLE.len(), 5);
+
+        // removing from the middle moves the last key into its place, which must still be found
+        assert_eq!(TABLE.remove(&3), Some(30));
+        assert_eq!(TABLE.remove(&3), None);
+        assert!(!TABLE.contains_key(&3));
+        assert_eq!(sorted_entries(), vec![(1, 11), (2, 20), (4, 40), (5, 50)]);
+        assert_eq!(TABLE.remove(&5), Some(50));
+        assert_eq!(TABLE.remove(&1), Some(11));
+        assert_eq!(sorted_entries(), vec![(2, 20), (4, 40)]);
+
+        // past the key array's first allocation, so it has to grow
+        for i in 100..200 {
+            TABLE.insert(i, i);
+        }
+        assert_eq!(TABLE.len(), 102);
+        assert_eq!(TABLE.iter().count(), 102);
+        assert_eq!(TABLE.get(&150), Some(150));
+
+        TABLE.clear();
+        assert!(TABLE.is_empty());
+        assert_eq!(TABLE.iter().count(), 0);
+        assert_eq!(TABLE.get(&2), None);
+
+        // and it's still usable
+        assert_eq!(TABLE.insert(2, 21), None);
+        assert_eq!(sorted_entries(), vec![(2, 21)]);
+    }
+}
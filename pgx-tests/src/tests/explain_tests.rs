@@ -0,0 +1,74 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    fn explain_output(es: &PgExplainState) -> String {
+        unsafe { std::ffi::CStr::from_ptr((*(*es.as_ptr()).str).data) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[pg_test]
+    fn test_explain_text_properties() {
+        let es = PgExplainState::from_pg(unsafe { pg_sys::NewExplainState() });
+        assert_eq!(es.format(), PgExplainFormat::Text);
+
+        es.property_text("Index Name", "my_index");
+        es.property_integer("Memory", Some("kB"), 42);
+        es.property_float("Fetch Time", Some("ms"), 1.5, 3);
+        es.property_bool("Cached", true);
+        es.property_list("Columns", &["a", "b"]);
+
+        assert_eq!(
+            explain_output(&es),
+            "Index Name: my_index\nMemory: 42 kB\nFetch Time: 1.500 ms\nCached: true\nColumns: a, b\n"
+        );
+    }
+
+    #[cfg(any(
+        feature = "pg11",
+        feature = "pg12",
+        feature = "pg13",
+        feature = "pg14",
+        feature = "pg15"
+    ))]
+    #[pg_test]
+    fn test_explain_json_properties() {
+        let es = PgExplainState::from_pg(unsafe { pg_sys::NewExplainState() });
+        unsafe {
+            (*es.as_ptr()).format = pg_sys::ExplainFormat_EXPLAIN_FORMAT_JSON;
+            pg_sys::ExplainBeginOutput(es.as_ptr());
+        }
+        assert_eq!(es.format(), PgExplainFormat::Json);
+
+        es.group("Query", None, true, |es| {
+            es.property_text("Index Name", "my_index");
+            es.property_integer("Memory", Some("kB"), 42);
+            es.property_float("Fetch Time", Some("ms"), 1.5, 3);
+            es.property_bool("Cached", true);
+            es.property_list("Columns", &["a", "b"]);
+        });
+        unsafe {
+            pg_sys::ExplainEndOutput(es.as_ptr());
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&explain_output(&es)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "Index Name": "my_index",
+                "Memory": 42,
+                "Fetch Time": 1.5,
+                "Cached": true,
+                "Columns": ["a", "b"]
+            }])
+        );
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+#[pg_extern(on_create)]
+fn extension_tests_on_create() {
+    Spi::run("CREATE TABLE extension_tests_on_create AS SELECT 1 AS calls");
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    fn create_table() -> pg_sys::ObjectAddress {
+        Spi::run("CREATE TABLE extension_tests (id int)");
+        relation_address(
+            Spi::get_one::<pg_sys::Oid>("SELECT 'extension_tests'::regclass::oid")
+                .expect("table wasn't created"),
+        )
+    }
+
+    fn is_member() -> bool {
+        Spi::get_one::<bool>(
+            "SELECT EXISTS (
+                SELECT 1 FROM pg_depend
+                 WHERE classid = 'pg_class'::regclass
+                   AND objid = 'extension_tests'::regclass
+                   AND refobjid = (SELECT oid FROM pg_extension WHERE extname = 'pgx_tests')
+                   AND deptype = 'e'
+            )",
+        )
+        .unwrap()
+    }
+
+    #[pg_test]
+    fn test_extension_oid() {
+        assert_eq!(
+            extension_oid("pgx_tests"),
+            Spi::get_one::<pg_sys::Oid>("SELECT oid FROM pg_extension WHERE extname = 'pgx_tests'")
+        );
+        assert_eq!(extension_oid("no_such_extension"), None);
+    }
+
+    #[pg_test]
+    fn test_not_creating_extension() {
+        assert!(!creating_extension());
+        assert_eq!(current_extension(), None);
+    }
+
+    #[pg_test]
+    fn test_add_and_remove() {
+        let extension = extension_oid("pgx_tests").unwrap();
+        let table = create_table();
+        assert_eq!(extension_of_object(&table), None);
+
+        add_to_extension(extension, &table);
+        assert!(is_member());
+        assert_eq!(extension_of_object(&table), Some(extension));
+
+        remove_from_extension(extension, &table);
+        assert!(!is_member());
+        assert_eq!(extension_of_object(&table), None);
+    }
+
+    #[pg_test(error = "table extension_tests is already a member of extension \"pgx_tests\"")]
+    fn test_add_twice() {
+        let extension = extension_oid("pgx_tests").unwrap();
+        let table = create_table();
+        add_to_extension(extension, &table);
+        add_to_extension(extension, &table);
+    }
+
+    #[pg_test(error = "table extension_tests is not a member of extension \"pgx_tests\"")]
+    fn test_remove_non_member() {
+        let extension = extension_oid("pgx_tests").unwrap();
+        remove_from_extension(extension, &create_table());
+    }
+
+    #[pg_test]
+    fn test_on_create_was_called() {
+        assert_eq!(
+            Spi::get_one::<i32>("SELECT sum(calls)::int FROM extension_tests_on_create"),
+            Some(1)
+        );
+    }
+
+    #[pg_test(error = "extension_tests_on_create() can only be called by CREATE EXTENSION")]
+    fn test_on_create_outside_create_extension() {
+        Spi::run("SELECT extension_tests_on_create()");
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_pg_qsort_by() {
+        let mut comparisons = 0;
+        let mut values = vec![5, 3, 9, 1, 7];
+        pg_qsort_by(&mut values, |a: &i32, b: &i32| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+        assert_eq!(values, vec![1, 3, 5, 7, 9]);
+        assert!(comparisons > 0);
+    }
+
+    #[pg_test]
+    fn test_pg_qsort_by_strings() {
+        let mut values = vec!["pear".to_string(), "apple".to_string(), "fig".to_string()];
+        pg_qsort_by(&mut values, |a, b| b.cmp(a));
+        assert_eq!(values, vec!["pear", "fig", "apple"]);
+    }
+
+    #[pg_test(error = "panic in comparator")]
+    fn test_pg_qsort_by_panic() {
+        let mut values = vec![2, 1];
+        pg_qsort_by(&mut values, |_: &i32, _: &i32| {
+            panic!("panic in comparator")
+        });
+    }
+
+    fn fire_void<F: FnMut()>(callback: &mut PgCallback<F>) {
+        unsafe { void_trampoline::<F>(callback.as_arg()) }
+    }
+
+    #[pg_test]
+    fn test_void_trampoline() {
+        let mut called = 0;
+        let mut callback = PgCallback::new(|| called += 1);
+        fire_void(&mut callback);
+        fire_void(&mut callback);
+        drop(callback);
+        assert_eq!(called, 2);
+    }
+
+    #[pg_test(error = "panic in callback")]
+    fn test_void_trampoline_panic() {
+        fire_void(&mut PgCallback::new(|| panic!("panic in callback")));
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+    use std::ffi::CString;
+
+    fn fingerprint_of(sql: &str) -> u64 {
+        let sql = CString::new(sql).unwrap();
+        unsafe {
+            let raw = PgList::<pg_sys::RawStmt>::from_pg(pg_sys::pg_parse_query(sql.as_ptr()));
+            let queries = PgList::<pg_sys::Query>::from_pg(pg_sys::pg_analyze_and_rewrite(
+                raw.head().expect("no statements"),
+                sql.as_ptr(),
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+            ));
+            let query = queries.head().expect("no queries");
+            query_fingerprint(&*query)
+        }
+    }
+
+    #[pg_test]
+    fn test_query_fingerprint_ignores_constants() {
+        assert_eq!(
+            fingerprint_of("SELECT 1 + 2 AS x"),
+            fingerprint_of("SELECT 40   +  2 AS x")
+        );
+        assert_ne!(
+            fingerprint_of("SELECT 1 + 2 AS x"),
+            fingerprint_of("SELECT 1 - 2 AS x")
+        );
+    }
+
+    #[test]
+    fn test_normalize_query_string() {
+        assert_eq!(
+            normalize_query_string(
+                "SELECT 'it''s', E'\\'', 1.5e3, $$dollar$$ FROM t WHERE a = $1 -- 99"
+            ),
+            "SELECT $2, $3, $4, $5 FROM t WHERE a = $1 -- 99"
+        );
+        assert_eq!(
+            normalize_query_string("SELECT \"col'1\", t1.x FROM t1"),
+            "SELECT \"col'1\", t1.x FROM t1"
+        );
+    }
+
+    #[test]
+    fn test_query_string_fingerprint() {
+        assert_eq!(
+            query_string_fingerprint("select a from t where b = 1"),
+            query_string_fingerprint("SELECT a\n  FROM t /* comment */\n WHERE b = 'two'")
+        );
+        assert_ne!(
+            query_string_fingerprint("SELECT a FROM t"),
+            query_string_fingerprint("SELECT \"A\" FROM t")
+        );
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+pg_function_registry!();
+
+#[pg_extern(immutable)]
+fn registered_add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_exported_functions() {
+        let function = exported_functions()
+            .into_iter()
+            .find(|function| function.name == "registered_add")
+            .expect("registered_add() is not in the registry");
+
+        assert_eq!(
+            function.signature,
+            "registered_add(integer,integer) RETURNS integer"
+        );
+        assert_eq!(function.volatility, PgVolatility::Immutable);
+        assert_eq!(
+            function.rust_path().as_deref(),
+            Some("pgx_tests::tests::function_registry_tests::registered_add")
+        );
+        assert!(function
+            .metadata
+            .unwrap()
+            .file
+            .ends_with("function_registry_tests.rs"));
+    }
+
+    #[pg_test]
+    fn test_function_registry_srf() {
+        let rust_path = Spi::get_one::<String>(
+            "SELECT rust_path FROM pgx_tests_functions() WHERE name = 'registered_add';",
+        );
+        assert_eq!(
+            rust_path.as_deref(),
+            Some("pgx_tests::tests::function_registry_tests::registered_add")
+        );
+    }
+}
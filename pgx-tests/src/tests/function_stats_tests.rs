@@ -0,0 +1,32 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+pg_function_stats!();
+
+#[pg_extern(track_stats)]
+fn tracked_add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_tracked_function_runs_without_stats() {
+        // `_PG_init()` doesn't call `pg_function_stats_init()`
+        assert!(!function_stats_enabled());
+        let result = Spi::get_one::<i32>("SELECT tracked_add(40, 2);");
+        assert_eq!(result, Some(42));
+    }
+
+    #[pg_test(error = "function statistics are not being collected")]
+    fn test_function_stats_srf_without_stats() {
+        Spi::get_one::<i64>("SELECT count(*) FROM pgx_function_stats();");
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_hash_map_insert_get_remove() {
+        let mut map = PgHashMap::<i64, f64>::new();
+        assert!(map.is_empty());
+
+        for i in 0..10_000i64 {
+            assert_eq!(map.insert(i, i as f64 / 2.0), None);
+        }
+        assert_eq!(map.len(), 10_000);
+        assert_eq!(map.get(&4242), Some(&2121.0));
+        assert_eq!(map.get(&10_000), None);
+
+        assert_eq!(map.insert(4242, 0.5), Some(2121.0));
+        *map.get_mut(&4242).unwrap() += 1.0;
+        assert_eq!(map.get(&4242), Some(&1.5));
+
+        assert_eq!(map.remove(&4242), Some(1.5));
+        assert_eq!(map.remove(&4242), None);
+        assert!(!map.contains_key(&4242));
+        assert_eq!(map.len(), 9_999);
+    }
+
+    #[pg_test]
+    fn test_hash_map_iter() {
+        let mut map = PgHashMap::<(i32, i32), bool>::new();
+        for i in 0..100 {
+            map.insert((i, -i), i % 2 == 0);
+        }
+
+        let mut entries = map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(entries.len(), 100);
+        assert_eq!(entries[0], ((0, 0), true));
+        assert_eq!(entries[99], ((99, -99), false));
+
+        // stopping early must end the scan cleanly
+        assert_eq!(map.iter().take(3).count(), 3);
+    }
+
+    #[pg_test]
+    fn test_hash_map_lives_in_context() {
+        // dynahash creates its own memory context, as a child of the one we asked for
+        let map = PgHashMap::<pg_sys::Oid, i32>::new_in(PgMemoryContexts::TopTransactionContext);
+        let context = PgMemoryContexts::Of(map.as_ptr() as void_ptr).value();
+        assert_eq!(
+            unsafe { (*context).parent },
+            PgMemoryContexts::TopTransactionContext.value()
+        );
+        map.destroy();
+    }
+}
@@ -67,6 +67,7 @@ mod tests {
                 prev_hook(range_table, ereport_on_violation)
             }
 
+            #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
             fn planner(
                 &mut self,
                 parse: PgBox<Query>,
@@ -81,6 +82,24 @@ mod tests {
                 self.events += 1;
                 prev_hook(parse, cursor_options, bound_params)
             }
+
+            #[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+            fn planner(
+                &mut self,
+                parse: PgBox<Query>,
+                query_string: Option<&std::ffi::CStr>,
+                cursor_options: i32,
+                bound_params: PgBox<ParamListInfoData>,
+                prev_hook: fn(
+                    PgBox<Query>,
+                    Option<&std::ffi::CStr>,
+                    i32,
+                    PgBox<ParamListInfoData>,
+                ) -> HookResult<*mut PlannedStmt>,
+            ) -> HookResult<*mut PlannedStmt> {
+                self.events += 1;
+                prev_hook(parse, query_string, cursor_options, bound_params)
+            }
         }
 
         static mut HOOK: TestHook = TestHook { events: 0 };
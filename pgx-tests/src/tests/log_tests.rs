@@ -80,4 +80,136 @@ mod tests {
     fn test_panic() {
         panic!("panic message")
     }
+
+    #[pg_test]
+    fn test_info_with_fields() {
+        let rows = 42;
+        info!("processed {} batches", 3; rows = rows, table = "my table");
+    }
+
+    #[pg_test]
+    fn test_warning_with_fields() {
+        warning!("warn message"; reason = "testing",);
+    }
+
+    #[pg_test(error = "ereport error with detail")]
+    fn test_ereport_with_detail() {
+        ereport_with_detail(
+            PgLogLevel::ERROR,
+            PgSqlErrorCode::ERRCODE_INTERNAL_ERROR,
+            "ereport error with detail",
+            "some detail",
+            file!(),
+            line!(),
+            column!(),
+        )
+    }
+
+    #[pg_test]
+    fn test_log_level_enabled() {
+        Spi::run("SET client_min_messages = notice");
+        Spi::run("SET log_min_messages = warning");
+        assert!(log_level_enabled(PgLogLevel::WARNING));
+        assert!(log_level_enabled(PgLogLevel::NOTICE));
+        assert!(log_level_enabled(PgLogLevel::INFO));
+        assert!(log_level_enabled(PgLogLevel::LOG));
+        assert!(!log_level_enabled(PgLogLevel::DEBUG1));
+        assert!(!log_level_enabled(PgLogLevel::DEBUG5));
+
+        Spi::run("SET client_min_messages = debug5");
+        assert!(log_level_enabled(PgLogLevel::DEBUG5));
+    }
+
+    #[pg_test]
+    fn test_warn_once() {
+        for i in 0..3 {
+            warn_once!("warn once message {}", i);
+        }
+    }
+
+    #[pg_test]
+    fn test_log_every() {
+        for i in 0..10 {
+            log_every!(5, "log every message {}", i);
+        }
+    }
+
+    #[test]
+    fn test_log_sampler() {
+        let sampler = PgLogSampler::new();
+        let logged = (0..10).filter(|_| sampler.sample(3)).count();
+        assert_eq!(logged, 4);
+        assert_eq!(sampler.calls(), 10);
+    }
+
+    #[test]
+    fn test_log_sampler_once() {
+        let sampler = PgLogSampler::new();
+        let logged = (0..10).filter(|_| sampler.sample(0)).count();
+        assert_eq!(logged, 1);
+    }
+
+    #[test]
+    fn test_key_value_field_format() {
+        let fields = [("rows", "42".to_string()), ("table", "my table".to_string())];
+        assert_eq!(
+            PgLogFieldFormat::KeyValue.render(&fields),
+            "rows=42\ntable=\"my table\""
+        );
+    }
+
+    #[test]
+    fn test_json_field_format() {
+        let fields = [("rows", "42".to_string()), ("table", "my table".to_string())];
+        assert_eq!(
+            PgLogFieldFormat::Json.render(&fields),
+            r#"{"rows":"42","table":"my table"}"#
+        );
+    }
+
+    #[test]
+    fn test_sqlstate() {
+        assert_eq!(PgSqlErrorCode::ERRCODE_UNIQUE_VIOLATION.sqlstate(), "23505");
+        assert_eq!(
+            PgSqlErrorCode::ERRCODE_T_R_DEADLOCK_DETECTED.sqlstate(),
+            "40P01"
+        );
+        assert_eq!(
+            PgSqlErrorCode::from_sqlstate("40001"),
+            Some(PgSqlErrorCode::ERRCODE_T_R_SERIALIZATION_FAILURE)
+        );
+        assert_eq!(
+            PgSqlErrorCode::from_sqlstate("40p01"),
+            Some(PgSqlErrorCode::ERRCODE_T_R_DEADLOCK_DETECTED)
+        );
+        assert_eq!(PgSqlErrorCode::from_sqlstate("4000"), None);
+        assert_eq!(PgSqlErrorCode::from_sqlstate("ZZ999"), None);
+
+        for code in PgSqlErrorCode::ALL {
+            assert_eq!(
+                PgSqlErrorCode::from_sqlerrcode(code.sqlerrcode()),
+                Some(*code)
+            );
+        }
+    }
+
+    #[test]
+    fn test_sqlstate_class() {
+        assert_eq!(
+            PgSqlErrorCode::ERRCODE_T_R_SERIALIZATION_FAILURE.class(),
+            SqlStateClass::TransactionRollback
+        );
+        assert_eq!(
+            PgSqlErrorCode::ERRCODE_OUT_OF_MEMORY.class(),
+            SqlStateClass::InsufficientResources
+        );
+        assert_eq!(SqlStateClass::IntegrityConstraintViolation.code(), "23");
+
+        // codes pgx doesn't know about still have a class
+        assert_eq!(
+            SqlStateClass::from_sqlstate("22P99"),
+            Some(SqlStateClass::DataException)
+        );
+        assert_eq!(SqlStateClass::from_sqlstate("Q0000"), None);
+    }
 }
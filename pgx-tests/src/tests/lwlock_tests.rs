@@ -0,0 +1,93 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+/// Shared by every backend, so each test uses a lock of its own.  `_PG_init()` initializes it
+pub(crate) static LOCKS: PgLwLockTranche = PgLwLockTranche::new("pgx_tests_locks", 4);
+
+const POISONED: usize = 0;
+const CLEARED: usize = 1;
+const TRIED: usize = 2;
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use super::{CLEARED, LOCKS, POISONED, TRIED};
+    use pgx::*;
+
+    /// Panic while holding lock `index` exclusively, as a half-finished update would
+    fn poison(index: usize) {
+        let result = subtransaction(|| {
+            let _guard = LOCKS.exclusive(index);
+            panic!("interrupted while holding the lock");
+        });
+        assert!(result.is_err());
+    }
+
+    #[pg_test(
+        error = "LWLock 0 of tranche `pgx_tests_locks` is poisoned: a previous holder panicked while holding it"
+    )]
+    fn test_lwlock_poisoned() {
+        poison(POISONED);
+        assert!(LOCKS.is_poisoned(POISONED));
+
+        // it's been released, but can't be taken again
+        LOCKS.shared(POISONED);
+    }
+
+    #[pg_test]
+    fn test_lwlock_clear_poison() {
+        poison(CLEARED);
+        assert!(LOCKS.is_poisoned(CLEARED));
+        assert!(subtransaction(|| LOCKS.try_exclusive(CLEARED).is_some()).is_err());
+
+        LOCKS.clear_poison(CLEARED);
+        assert!(!LOCKS.is_poisoned(CLEARED));
+        assert!(LOCKS.exclusive(CLEARED).is_exclusive());
+
+        // only exclusive guards poison, as shared ones can't have changed anything
+        let result = subtransaction(|| {
+            let _guard = LOCKS.shared(CLEARED);
+            panic!("interrupted while holding the lock");
+        });
+        assert!(result.is_err());
+        assert!(!LOCKS.is_poisoned(CLEARED));
+    }
+
+    #[pg_test]
+    fn test_lwlock_try() {
+        let guard = LOCKS.exclusive(TRIED);
+        assert_eq!(guard.index(), TRIED);
+        assert!(guard.is_exclusive());
+        assert!(LOCKS.try_exclusive(TRIED).is_none());
+        assert!(LOCKS.try_shared(TRIED).is_none());
+        drop(guard);
+
+        let guard = LOCKS.try_shared(TRIED).expect("the lock wasn't free");
+        assert!(!guard.is_exclusive());
+        assert!(LOCKS.try_exclusive(TRIED).is_none());
+        assert!(LOCKS.try_shared(TRIED).is_some());
+        drop(guard);
+
+        assert!(LOCKS.try_exclusive(TRIED).is_some());
+    }
+
+    #[pg_test(error = "LWLock 4 is out of range for tranche `pgx_tests_locks`, which has 4 locks")]
+    fn test_lwlock_out_of_range() {
+        assert_eq!(LOCKS.len(), 4);
+        LOCKS.exclusive(4);
+    }
+
+    #[pg_test(error = "LWLock 4 is out of range for tranche `pgx_tests_locks`, which has 4 locks")]
+    fn test_lwlock_try_out_of_range() {
+        LOCKS.try_shared(4);
+    }
+
+    #[pg_test(error = "LWLock 4 is out of range for tranche `pgx_tests_locks`, which has 4 locks")]
+    fn test_lwlock_poison_out_of_range() {
+        LOCKS.is_poisoned(4);
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+pg_build_info!();
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_magic_block_is_registered() {
+        let block = get_magic_block().expect("no magic block registered");
+        assert_eq!(block.pgx_version, PGX_VERSION);
+        assert_eq!(block.pg_version_num, pg_sys::PG_VERSION_NUM);
+        assert_eq!(block.extname, Some("pgx_tests"));
+    }
+
+    #[pg_test]
+    fn test_binary_compatibility() {
+        check_binary_compatibility();
+    }
+
+    #[pg_test]
+    fn test_binary_compatibility_mismatch() {
+        Spi::run(
+            "UPDATE pg_catalog.pg_extension SET extversion = 'old' WHERE extname = 'pgx_tests'",
+        );
+
+        let error = subtransaction(check_binary_compatibility)
+            .err()
+            .expect("the version mismatch wasn't detected");
+        assert!(
+            error.message.ends_with(
+                "does not match installed SQL version old.  Run `ALTER EXTENSION pgx_tests UPDATE;`"
+            ),
+            "{}",
+            error.message
+        );
+    }
+
+    #[pg_test]
+    fn test_build_info() {
+        let block = get_magic_block().expect("no magic block registered");
+        assert!(block.profile == "debug" || block.profile == "release");
+        assert!(block
+            .pg_version()
+            .starts_with(&format!("{}.", pg_sys::PG_VERSION_NUM / 10000)));
+
+        let pgx_version = Spi::get_one::<String>("SELECT pgx_version FROM pgx_tests_build_info();");
+        assert_eq!(pgx_version.as_deref(), Some(PGX_VERSION));
+
+        let profile = Spi::get_one::<String>("SELECT profile FROM pgx_tests_build_info();");
+        assert_eq!(profile.as_deref(), Some(block.profile));
+    }
+}
@@ -1,7 +1,6 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
-
 #[cfg(any(test, feature = "pg_test"))]
 mod tests {
     #[allow(unused_imports)]
@@ -41,4 +40,36 @@ mod tests {
 
         assert!(did_drop.load(Ordering::SeqCst))
     }
+
+    #[pg_test]
+    fn test_memory_usage() {
+        PgMemoryContexts::Transient {
+            parent: PgMemoryContexts::CurrentMemoryContext.value(),
+            name: "test",
+            min_context_size: 4096,
+            initial_block_size: 4096,
+            max_block_size: 4096,
+        }
+        .switch_to(|context| {
+            let before = context.usage();
+            context.palloc(64 * 1024);
+            let after = context.usage();
+
+            assert!(after.total_bytes >= before.total_bytes + 64 * 1024);
+            assert!(after.used_bytes() >= 64 * 1024);
+            assert!(after.blocks > before.blocks);
+        });
+    }
+
+    #[pg_test]
+    fn test_memory_usage_includes_children() {
+        let parent = PgMemoryBudget::new("parent", usize::max_value());
+        let mut child = PgMemoryBudget::new_in(parent.context(), "child", usize::max_value());
+        child.switch_to(|context| {
+            context.palloc(64 * 1024);
+        });
+
+        assert!(parent.context().usage().used_bytes() >= 64 * 1024);
+        assert!(parent.context().usage_without_children().used_bytes() < 64 * 1024);
+    }
 }
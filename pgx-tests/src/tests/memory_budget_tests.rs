@@ -0,0 +1,59 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_memory_budget_tracks_usage() {
+        let mut budget = PgMemoryBudget::new("test_budget", 1024 * 1024);
+        assert_eq!(budget.used(), 0);
+
+        let ptr = budget.switch_to(|context| context.palloc(1000) as usize);
+        assert!(budget.used() >= 1000);
+        assert_eq!(budget.remaining(), budget.limit() - budget.used());
+
+        unsafe { pg_sys::pfree(ptr as void_mut_ptr) };
+        assert_eq!(budget.used(), 0);
+
+        budget.switch_to(|context| {
+            context.palloc(1000);
+        });
+        budget.reset();
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[pg_test]
+    fn test_memory_budget_repalloc() {
+        let mut budget = PgMemoryBudget::new("test_budget", 1024 * 1024);
+        let ptr = budget.switch_to(|context| context.palloc(100) as usize);
+        let ptr = unsafe { pg_sys::repalloc(ptr as void_mut_ptr, 10_000) };
+        assert!(budget.used() >= 10_000);
+
+        unsafe { pg_sys::pfree(ptr) };
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[pg_test(error = "memory context \"test_budget\" exceeded its budget of 4096 bytes")]
+    fn test_memory_budget_exceeded() {
+        let mut budget = PgMemoryBudget::new("test_budget", 4096);
+        budget.switch_to(|context| {
+            context.palloc(1024);
+            context.palloc(8192);
+        });
+    }
+
+    #[pg_test]
+    fn test_memory_budget_set_limit() {
+        let mut budget = PgMemoryBudget::new("test_budget", 4096);
+        budget.set_limit(64 * 1024);
+        budget.switch_to(|context| {
+            context.palloc(8192);
+        });
+        assert_eq!(budget.limit(), 64 * 1024);
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+#[pg_extern]
+fn mock_repeat(s: &str, n: i32) -> String {
+    s.repeat(n as usize)
+}
+
+#[pg_extern]
+fn mock_add_one(i: Option<i32>) -> Option<i32> {
+    i.map(|i| i + 1)
+}
+
+// these run as plain `#[test]`s, without Postgres, so they're left out of the extension that
+// `#[pg_test]`s run in
+#[cfg(test)]
+mod tests {
+    use pgx::*;
+
+    pg_mock!();
+
+    #[test]
+    fn test_mock_text_round_trip() {
+        mock::run(|| {
+            let datum = "hello, world".into_datum().unwrap();
+            let s = unsafe { String::from_datum(datum, false, pg_sys::TEXTOID) };
+            assert_eq!(s.as_deref(), Some("hello, world"));
+
+            let s = unsafe { <&str>::from_datum(datum, false, pg_sys::TEXTOID) };
+            assert_eq!(s, Some("hello, world"));
+        })
+    }
+
+    #[test]
+    fn test_mock_call() {
+        mock::run(|| {
+            let result = mock::MockFunctionCall::new()
+                .arg("ab")
+                .arg(3)
+                .call::<String>(super::mock_repeat_wrapper);
+            assert_eq!(result.as_deref(), Some("ababab"));
+        })
+    }
+
+    #[test]
+    fn test_mock_call_null() {
+        mock::run(|| {
+            let result = mock::MockFunctionCall::new()
+                .arg(Some(41))
+                .call::<i32>(super::mock_add_one_wrapper);
+            assert_eq!(result, Some(42));
+
+            let result = mock::MockFunctionCall::new()
+                .arg(None::<i32>)
+                .call::<i32>(super::mock_add_one_wrapper);
+            assert_eq!(result, None);
+        })
+    }
+
+    #[test]
+    fn test_mock_arg_types() {
+        mock::run(|| {
+            let fcinfo = mock::MockFunctionCall::new().arg("a").arg(1).build();
+            assert_eq!(get_getarg_type(fcinfo, 0), pg_sys::TEXTOID);
+            assert_eq!(get_getarg_type(fcinfo, 1), pg_sys::INT4OID);
+            assert_eq!(get_getarg_type(fcinfo, 2), pg_sys::InvalidOid);
+        })
+    }
+}
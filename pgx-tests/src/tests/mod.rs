@@ -1,31 +1,98 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
+mod advisory_lock_tests;
 mod anyarray_tests;
+mod archived_tests;
 mod array_tests;
+mod backend_tests;
 mod bytea_tests;
+mod clock_tests;
+mod column_tests;
+mod config_table_tests;
+mod crash_tests;
 mod datetime_tests;
 mod default_arg_value_tests;
 mod derive_pgtype_lifetimes;
+mod dshash_tests;
 mod enum_type_tests;
+mod explain_tests;
+mod extension_tests;
 mod fcinfo_tests;
+mod ffi_callback_tests;
+mod fingerprint_tests;
+mod function_registry_tests;
+mod function_stats_tests;
 mod guc_tests;
+mod hash_table_tests;
 mod hooks_tests;
 mod inet_tests;
 mod json_tests;
 mod log_tests;
+mod lwlock_tests;
+mod magic_tests;
 mod memcxt_tests;
+mod memory_budget_tests;
+mod mock_tests;
+mod name_tests;
 mod node_tests;
 mod numeric_tests;
+mod parser_tests;
 mod pg_extern_args_tests;
 mod pg_try_tests;
+mod portal_tests;
 mod postgres_type_tests;
+mod prepared_statement_tests;
+mod queue_tests;
+mod random_tests;
+mod recovery_tests;
+mod reload_tests;
+mod replication_origin_tests;
+mod replication_slot_tests;
 mod schema_tests;
+mod security_tests;
+mod server_files_tests;
+mod shmem_cache_tests;
+mod snapshot_tests;
 mod spi_tests;
 mod srf_tests;
+mod stat_counters_tests;
+mod stringinfo_tests;
 mod struct_type_tests;
+mod subxact_tests;
+mod tempfile_tests;
+mod tuplesort_tests;
+mod tuplestore_tests;
+mod vacuum_tests;
 mod variadic_tests;
 mod xact_callback_tests;
 mod xid64_tests;
 
+use pgx::*;
+
 pgx::pg_module_magic!();
+
+/// The test Postgres loads us via `shared_preload_libraries`, so this is where the tests ask for
+/// their shared memory
+#[pg_guard]
+pub extern "C" fn _PG_init() {
+    pg_stat_counters_init(&stat_counters_tests::COUNTED);
+    pg_stat_counters_init(&stat_counters_tests::RESET);
+    pg_stat_counters_init(&stat_counters_tests::SAVED);
+
+    pg_shmem_init(&shmem_cache_tests::VALUES);
+    pg_shmem_init(&shmem_cache_tests::LRU);
+    pg_shmem_init(&shmem_cache_tests::CHAINED);
+    pg_shmem_init(&shmem_cache_tests::CLEARED);
+
+    pg_shmem_init(&lwlock_tests::LOCKS);
+
+    #[cfg(any(
+        feature = "pg11",
+        feature = "pg12",
+        feature = "pg13",
+        feature = "pg14",
+        feature = "pg15"
+    ))]
+    pg_shmem_init(&dshash_tests::TABLE);
+}
@@ -0,0 +1,52 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("users"), "users");
+        assert_eq!(quote_identifier("Users"), "\"Users\"");
+        assert_eq!(quote_identifier("select"), "\"select\"");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+        assert_eq!(
+            quote_qualified_identifier("public", "My Table"),
+            "public.\"My Table\""
+        );
+    }
+
+    #[pg_test]
+    fn test_quote_literal() {
+        assert_eq!(quote_literal("it's"), "'it''s'");
+        assert_eq!(quote_literal("back\\slash"), "E'back\\\\slash'");
+        assert_eq!(quote_nullable(None), "NULL");
+        assert_eq!(quote_nullable(Some("x")), "'x'");
+    }
+
+    #[pg_test]
+    fn test_quoted_sql_roundtrip() {
+        let evil = "'); DROP TABLE users; --";
+        let result = Spi::get_one::<String>(&format!("SELECT {}", quote_literal(evil)));
+        assert_eq!(result, Some(evil.to_string()));
+    }
+
+    #[pg_test]
+    fn test_name_truncation() {
+        let long = "é".repeat(40);
+        let truncated = truncate_identifier(&long);
+        assert_eq!(truncated.len(), 62);
+
+        let name = str_to_name(&long);
+        assert_eq!(name_to_str(&name), truncated);
+    }
+
+    #[pg_test(error = "invalid byte sequence for encoding \"UTF8\": 0x00")]
+    fn test_to_cstring_null_byte() {
+        to_cstring("a\0b");
+    }
+}
@@ -14,4 +14,61 @@ mod tests {
         let node = PgNodeFactory::makeIndexAmRoutine();
         assert_eq!(PgNode::IndexAmRoutine as u32, node.type_)
     }
+
+    #[pg_test]
+    fn test_make_func_expr() {
+        let abs = Spi::get_one::<pg_sys::Oid>("SELECT 'abs(int4)'::regprocedure::oid").unwrap();
+        let expr = make_func_expr(abs, vec![make_const(42i32).into_expr()]);
+        assert_eq!(expr.funcresulttype, pg_sys::INT4OID);
+        assert_eq!(deparse_expression(expr.into_expr(), None), "abs(42)");
+    }
+
+    #[pg_test]
+    fn test_make_const_text() {
+        let text = make_const("hello");
+        assert_eq!(text.consttype, pg_sys::TEXTOID);
+        assert_ne!(text.constcollid, pg_sys::InvalidOid);
+        assert_eq!(deparse_expression(text.into_expr(), None), "'hello'::text");
+
+        let null = make_const(None::<i64>);
+        assert!(null.constisnull);
+        assert_eq!(deparse_expression(null.into_expr(), None), "NULL::bigint");
+    }
+
+    #[pg_test]
+    fn test_make_var_and_bool_expr() {
+        let relname = std::ffi::CString::new("relname").unwrap();
+        let attno = unsafe { pg_sys::get_attnum(pg_sys::RelationRelationId, relname.as_ptr()) };
+        let var = make_var(1, attno, pg_sys::NAMEOID);
+        let not = make_bool_expr(
+            pg_sys::BoolExprType_NOT_EXPR,
+            vec![make_bool_expr(
+                pg_sys::BoolExprType_OR_EXPR,
+                vec![
+                    make_const(true).into_expr(),
+                    make_null_const(pg_sys::BOOLOID).into_expr(),
+                ],
+            )
+            .into_expr()],
+        );
+
+        let context = Some(("pg_class", pg_sys::RelationRelationId));
+        assert_eq!(deparse_expression(var.into_expr(), context), "relname");
+        assert_eq!(
+            deparse_expression(not.into_expr(), context),
+            "(NOT (true OR NULL::boolean))"
+        );
+    }
+
+    #[pg_test]
+    fn test_make_target_entry() {
+        let tle = make_target_entry(make_const(1i32).into_expr(), 1, Some("one"), false);
+        assert_eq!(tle.resno, 1);
+        assert_eq!(
+            unsafe { std::ffi::CStr::from_ptr(tle.resname) }
+                .to_str()
+                .unwrap(),
+            "one"
+        );
+    }
 }
@@ -0,0 +1,86 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    fn count_nodes(stmt: *mut pg_sys::Node, tag: PgNode, raw: bool) -> usize {
+        let tag = tag as u32;
+        let mut count = 0;
+        let mut visitor = |node: *mut pg_sys::Node| {
+            if is_a(node, tag) {
+                count += 1;
+            }
+            PgWalk::Continue
+        };
+        if raw {
+            walk_raw_tree(stmt, &mut visitor);
+        } else {
+            walk_query_tree(stmt, &mut visitor);
+        }
+        count
+    }
+
+    #[pg_test]
+    fn test_raw_parse() {
+        let stmts = raw_parse("SELECT a, b + 1 FROM no_such_table WHERE c = 'x'; SELECT 1");
+        assert_eq!(stmts.len(), 2);
+
+        let first = stmts[0].as_ptr() as *mut pg_sys::Node;
+        assert_eq!(count_nodes(first, PgNode::ColumnRef, true), 3);
+        assert_eq!(count_nodes(first, PgNode::A_Const, true), 2);
+        assert_eq!(count_nodes(first, PgNode::SelectStmt, true), 1);
+    }
+
+    #[pg_test(error = "syntax error at or near \"SELEKT\"")]
+    fn test_raw_parse_syntax_error() {
+        raw_parse("SELEKT 1");
+    }
+
+    #[pg_test]
+    fn test_parse_analyze_walks_subqueries() {
+        let queries = parse_analyze(
+            "SELECT relname FROM pg_class WHERE oid IN (SELECT indexrelid FROM pg_index)",
+        );
+        assert_eq!(queries.len(), 1);
+
+        let query = queries[0].as_ptr() as *mut pg_sys::Node;
+        assert_eq!(count_nodes(query, PgNode::Query, false), 2);
+        assert_eq!(count_nodes(query, PgNode::Var, false), 3);
+    }
+
+    #[pg_test]
+    fn test_walk_stop() {
+        let stmts = raw_parse("SELECT a, b, c");
+        let mut visited = 0;
+        let stopped = walk_raw_tree(
+            stmts[0].as_ptr() as *mut pg_sys::Node,
+            &mut |node: *mut pg_sys::Node| {
+                visited += 1;
+                if is_a(node, PgNode::ColumnRef as u32) {
+                    PgWalk::Stop
+                } else {
+                    PgWalk::Continue
+                }
+            },
+        );
+        assert!(stopped);
+        assert!(visited < 6);
+    }
+
+    #[pg_test]
+    fn test_deparse_expression() {
+        let queries = parse_analyze("SELECT 1 FROM pg_class WHERE relpages > 10 + 1");
+        let query = &queries[0];
+        let jointree = unsafe { &*query.jointree };
+        let sql = deparse_expression(
+            jointree.quals,
+            Some(("pg_class", pg_sys::RelationRelationId)),
+        );
+        assert_eq!(sql, "(relpages > (10 + 1))");
+    }
+}
@@ -20,4 +20,62 @@ mod tests {
         .expect("failed to get SPI result");
         assert!(result)
     }
+
+    #[pg_extern(sql_type(relation = "regclass", return = "regclass"))]
+    fn relation_passthrough(relation: pg_sys::Oid) -> pg_sys::Oid {
+        relation
+    }
+
+    #[pg_test]
+    fn test_sql_type_overrides() {
+        let result = Spi::get_one::<bool>(
+            "SELECT proargtypes[0] = 'regclass'::regtype AND prorettype = 'regclass'::regtype FROM pg_proc WHERE proname = 'relation_passthrough'",
+        )
+        .expect("failed to get SPI result");
+        assert!(result);
+
+        let name = Spi::get_one::<String>("SELECT tests.relation_passthrough('pg_class')::text")
+            .expect("failed to get SPI result");
+        assert_eq!(name, "pg_class");
+    }
+
+    #[pg_extern(deprecated_alias = "old_add_one")]
+    fn add_one(value: i32) -> i32 {
+        value + 1
+    }
+
+    #[pg_extern(deprecated_alias = "old_numbers")]
+    fn numbers(count: i32) -> impl std::iter::Iterator<Item = i32> {
+        1..=count
+    }
+
+    #[pg_test]
+    fn test_deprecated_alias() {
+        let result = Spi::get_one::<i32>("SELECT tests.old_add_one(41)")
+            .expect("failed to get SPI result");
+        assert_eq!(result, 42);
+
+        let result = Spi::get_one::<i64>("SELECT sum(n) FROM tests.old_numbers(4) n")
+            .expect("failed to get SPI result");
+        assert_eq!(result, 10);
+    }
+
+    #[pg_extern(security_definer, set_search_path = "pg_catalog, pg_temp")]
+    fn pinned_search_path() -> String {
+        Spi::get_one::<String>("SELECT current_setting('search_path')")
+            .expect("failed to get SPI result")
+    }
+
+    #[pg_test]
+    fn test_set_search_path() {
+        let result = Spi::get_one::<String>("SELECT tests.pinned_search_path()")
+            .expect("failed to get SPI result");
+        assert_eq!(result, "pg_catalog, pg_temp");
+
+        let result = Spi::get_one::<bool>(
+            "SELECT prosecdef FROM pg_proc WHERE proname = 'pinned_search_path'",
+        )
+        .expect("failed to get SPI result");
+        assert!(result);
+    }
 }
@@ -66,4 +66,49 @@ mod tests {
     fn test_pg_try_unwrap_or_rethrow_with_error_in_rethrow() {
         pg_try(|| panic!("rethrow a panic")).unwrap_or_rethrow(|| panic!("panic in rethrow"));
     }
+
+    #[pg_test(error = "panic with a backtrace")]
+    fn test_panic_with_backtrace() {
+        Spi::run("SET pgx.panic_backtrace = on");
+        pg_try(|| panic!("panic with a backtrace")).unwrap();
+    }
+
+    #[pg_test(error = "panic without a backtrace")]
+    fn test_panic_without_backtrace() {
+        Spi::run("SET pgx.panic_backtrace = off");
+        pg_try(|| panic!("panic without a backtrace")).unwrap();
+    }
+
+    #[pg_test]
+    fn test_pg_try_catch_error_no_error() {
+        let result = unsafe { pg_try(|| 42).catch_error(|_| 99) };
+        assert_eq!(42, result);
+    }
+
+    #[pg_test]
+    fn test_pg_try_catch_error_with_error() {
+        let error = unsafe {
+            pg_try(|| {
+                raw_parse("SELEKT 1");
+                None
+            })
+            .catch_error(Some)
+        }
+        .expect("the syntax error wasn't caught");
+
+        assert_eq!(error.sqlstate(), "42601");
+        assert_eq!(
+            PgSqlErrorCode::from_sqlerrcode(error.sqlerrcode),
+            Some(PgSqlErrorCode::ERRCODE_SYNTAX_ERROR)
+        );
+        assert_eq!(error.message, "syntax error at or near \"SELEKT\"");
+    }
+
+    #[pg_test]
+    fn test_pg_try_catch_error_with_panic() {
+        let error = unsafe { pg_try(|| panic!("caught a panic")).catch_error(|error| error) };
+
+        assert_eq!(error.sqlstate(), "XX000");
+        assert_eq!(error.message, "caught a panic");
+    }
 }
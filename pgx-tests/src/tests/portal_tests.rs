@@ -0,0 +1,68 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_find_portal() {
+        Spi::run("DECLARE portal_test_cursor SCROLL CURSOR FOR SELECT generate_series(1, 10)");
+
+        let portal = PgPortal::find("portal_test_cursor").expect("cursor not found");
+        assert_eq!(portal.name(), "portal_test_cursor");
+        assert_eq!(portal.status(), PgPortalStatus::Ready);
+        assert!(portal.is_scrollable());
+        assert!(!portal.is_holdable());
+        assert!(portal.is_visible());
+        assert!(portal.at_start());
+        assert!(portal
+            .source_text()
+            .unwrap()
+            .contains("generate_series(1, 10)"));
+
+        assert!(PgPortal::find("no_such_cursor").is_none());
+    }
+
+    #[pg_test]
+    fn test_all_portals() {
+        Spi::run("DECLARE portal_test_a CURSOR FOR SELECT 1");
+        Spi::run("DECLARE portal_test_b CURSOR FOR SELECT 2");
+
+        let mut names = PgPortal::all()
+            .iter()
+            .map(|portal| portal.name().to_string())
+            .filter(|name| name.starts_with("portal_test_"))
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["portal_test_a", "portal_test_b"]);
+    }
+
+    #[pg_test]
+    fn test_fetch_cursor() {
+        Spi::run("DECLARE portal_test_fetch CURSOR FOR SELECT generate_series(1, 10)");
+
+        let sum = Spi::connect(|client| {
+            let first = client
+                .fetch_cursor("portal_test_fetch", 3)
+                .map(|row| row.get_datum::<i32>(1).unwrap())
+                .sum::<i32>();
+            let rest = client.fetch_cursor("portal_test_fetch", 100).len() as i32;
+            Ok(Some(first * 100 + rest))
+        });
+        assert_eq!(sum, Some(607));
+
+        let portal = PgPortal::find("portal_test_fetch").unwrap();
+        assert!(portal.at_end());
+    }
+
+    #[pg_test(error = "cursor \"no_such_cursor\" does not exist")]
+    fn test_fetch_missing_cursor() {
+        Spi::execute(|client| {
+            client.fetch_cursor("no_such_cursor", 1);
+        });
+    }
+}
@@ -53,6 +53,103 @@ impl InOutFuncs for CustomTextFormatSerializedType {
     }
 }
 
+#[derive(Serialize, Deserialize, PostgresType)]
+#[text_inoutfuncs]
+pub struct Distance {
+    meters: f64,
+}
+
+impl TextInOutFuncs for Distance {
+    fn input(input: &str) -> Self {
+        let meters = if input.ends_with("km") {
+            f64::from_str(input.trim_end_matches("km")).map(|km| km * 1000.0)
+        } else {
+            f64::from_str(input.trim_end_matches('m'))
+        };
+
+        Distance {
+            meters: meters.unwrap_or_else(|_| panic!("invalid distance: {}", input)),
+        }
+    }
+
+    fn output(&self) -> String {
+        if self.meters >= 1000.0 {
+            format!("{}km", self.meters / 1000.0)
+        } else {
+            format!("{}m", self.meters)
+        }
+    }
+}
+
+#[derive(Copy, Clone, PostgresType)]
+#[passedbyvalue]
+#[text_inoutfuncs]
+#[repr(C)]
+pub struct ByValType {
+    x: i32,
+    y: i32,
+}
+
+impl TextInOutFuncs for ByValType {
+    fn input(input: &str) -> Self {
+        let mut iter = input.trim_matches(|c| c == '(' || c == ')').split(',');
+        let (x, y) = (iter.next(), iter.next());
+
+        ByValType {
+            x: i32::from_str(x.unwrap().trim()).expect("x is not a valid i32"),
+            y: i32::from_str(y.unwrap().trim()).expect("y is not a valid i32"),
+        }
+    }
+
+    fn output(&self) -> String {
+        format!("({},{})", self.x, self.y)
+    }
+}
+
+#[pg_extern]
+fn byvaltype_swap(value: ByValType) -> ByValType {
+    ByValType {
+        x: value.y,
+        y: value.x,
+    }
+}
+
+#[derive(Serialize, Deserialize, PostgresType, PostgresEq, PartialEq, Eq, Hash, Debug)]
+#[text_inoutfuncs]
+pub struct Color {
+    name: String,
+}
+
+impl TextInOutFuncs for Color {
+    fn input(input: &str) -> Self {
+        Color {
+            name: input.to_string(),
+        }
+    }
+
+    fn output(&self) -> String {
+        self.name.clone()
+    }
+}
+
+#[pg_extern]
+fn color_palette() -> Vec<Color> {
+    vec![
+        Color { name: "red".into() },
+        Color {
+            name: "green".into(),
+        },
+    ]
+}
+
+#[pg_extern]
+fn color_names(colors: Vec<Option<Color>>) -> Vec<Option<String>> {
+    colors
+        .into_iter()
+        .map(|color| color.map(|color| color.name))
+        .collect()
+}
+
 #[derive(Serialize, Deserialize, PostgresType)]
 pub struct JsonType {
     a: f32,
@@ -66,7 +163,7 @@ mod tests {
     use crate as pgx_tests;
 
     use crate::tests::postgres_type_tests::{
-        CustomTextFormatSerializedType, JsonType, VarlenaType,
+        ByValType, Color, CustomTextFormatSerializedType, Distance, JsonType, VarlenaType,
     };
     use pgx::*;
 
@@ -98,4 +195,120 @@ mod tests {
         assert_eq!(result.b, 2.0);
         assert_eq!(result.c, 3);
     }
+
+    #[pg_test]
+    fn test_texttype() {
+        let result =
+            Spi::get_one::<Distance>("SELECT '1.5km'::Distance").expect("SPI returned NULL");
+        assert_eq!(result.meters, 1500.0);
+
+        let result = Spi::get_one::<String>("SELECT '300m'::Distance::text");
+        assert_eq!(result, Some("300m".to_string()));
+    }
+
+    #[pg_test(error = "invalid distance: far")]
+    fn test_texttype_invalid() {
+        Spi::get_one::<Distance>("SELECT 'far'::Distance");
+    }
+
+    #[pg_test]
+    fn test_byvaltype() {
+        let result = Spi::get_one::<ByValType>("SELECT byvaltype_swap('(1,2)'::ByValType)")
+            .expect("SPI returned NULL");
+        assert_eq!(result.x, 2);
+        assert_eq!(result.y, 1);
+
+        Spi::run("CREATE TABLE byvaltype_tests (value ByValType)");
+        Spi::run("INSERT INTO byvaltype_tests VALUES ('(-3,4)')");
+        let result = Spi::get_one::<String>("SELECT value::text FROM byvaltype_tests");
+        assert_eq!(result, Some("(-3,4)".to_string()));
+    }
+
+    #[pg_test]
+    fn test_byvaltype_declaration() {
+        let byval =
+            Spi::get_one::<bool>("SELECT typbyval FROM pg_type WHERE typname = 'byvaltype'");
+        let len = Spi::get_one::<i16>("SELECT typlen FROM pg_type WHERE typname = 'byvaltype'");
+        let align = Spi::get_one::<String>(
+            "SELECT typalign::text FROM pg_type WHERE typname = 'byvaltype'",
+        );
+        assert_eq!(byval, Some(true));
+        assert_eq!(len, Some(8));
+        assert_eq!(align, Some("i".to_string()));
+    }
+
+    #[pg_test]
+    fn test_array_type_created() {
+        let array_type = Spi::get_one::<String>(
+            "SELECT typarray::regtype::text FROM pg_type WHERE typname = 'color'",
+        );
+        assert_eq!(array_type, Some("color[]".to_string()));
+    }
+
+    #[pg_test]
+    fn test_vec_of_custom_type() {
+        let colors = Spi::get_one::<Vec<Option<Color>>>("SELECT color_palette()")
+            .expect("SPI returned NULL");
+        assert_eq!(
+            colors,
+            vec![
+                Some(Color { name: "red".into() }),
+                Some(Color {
+                    name: "green".into()
+                })
+            ]
+        );
+
+        let names = Spi::get_one::<Vec<Option<String>>>(
+            "SELECT color_names(ARRAY['blue', NULL, 'red']::Color[])",
+        )
+        .expect("SPI returned NULL");
+        assert_eq!(names, vec![Some("blue".into()), None, Some("red".into())]);
+    }
+
+    #[pg_test]
+    fn test_custom_type_any_all() {
+        let found = Spi::get_one::<bool>("SELECT 'green'::Color = ANY(color_palette())");
+        let missing = Spi::get_one::<bool>("SELECT 'blue'::Color <> ALL(color_palette())");
+        let position = Spi::get_one::<i32>("SELECT array_position(color_palette(), 'green')");
+        assert_eq!(found, Some(true));
+        assert_eq!(missing, Some(true));
+        assert_eq!(position, Some(2));
+    }
+
+    #[pg_test]
+    fn test_custom_type_array_containment() {
+        let contains = Spi::get_one::<bool>("SELECT color_palette() @> ARRAY['red']::Color[]");
+        let contained =
+            Spi::get_one::<bool>("SELECT ARRAY['red', 'blue']::Color[] <@ color_palette()");
+        let overlaps =
+            Spi::get_one::<bool>("SELECT color_palette() && ARRAY['blue', 'green']::Color[]");
+        assert_eq!(contains, Some(true));
+        assert_eq!(contained, Some(false));
+        assert_eq!(overlaps, Some(true));
+    }
+
+    #[pg_test]
+    fn test_custom_type_unnest() {
+        let names = Spi::get_one::<String>(
+            "SELECT string_agg(color::text, ',' ORDER BY ord) FROM unnest(color_palette()) WITH ORDINALITY AS t(color, ord)",
+        );
+        let distinct = Spi::get_one::<i64>(
+            "SELECT count(*) FROM (SELECT DISTINCT color FROM unnest(color_palette() || color_palette()) AS color) colors",
+        );
+        assert_eq!(names, Some("red,green".to_string()));
+        assert_eq!(distinct, Some(2));
+    }
+
+    #[pg_test]
+    fn test_byvaltype_array() {
+        let values =
+            Spi::get_one::<Vec<Option<ByValType>>>("SELECT ARRAY['(1,2)', '(3,4)']::ByValType[]")
+                .expect("SPI returned NULL");
+        let values = values
+            .into_iter()
+            .map(|value| value.map(|value| (value.x, value.y)))
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![Some((1, 2)), Some((3, 4))]);
+    }
 }
@@ -0,0 +1,59 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_find_prepared_statement() {
+        Spi::run("PREPARE prepared_test_find(int, text) AS SELECT $1, $2");
+
+        let stmt = PgPreparedStatement::find("prepared_test_find").expect("statement not found");
+        assert_eq!(stmt.name(), "prepared_test_find");
+        assert!(stmt.query_string().contains("SELECT $1, $2"));
+        assert_eq!(stmt.param_types(), vec![pg_sys::INT4OID, pg_sys::TEXTOID]);
+        assert!(stmt.from_sql());
+        assert!(stmt.is_valid());
+
+        assert!(PgPreparedStatement::find("no_such_statement").is_none());
+        stmt.deallocate();
+    }
+
+    #[pg_test]
+    fn test_deallocate_prepared_statement() {
+        Spi::run("PREPARE prepared_test_a AS SELECT 1");
+        Spi::run("PREPARE prepared_test_b AS SELECT 2");
+
+        let mut names = PgPreparedStatement::all()
+            .iter()
+            .map(|stmt| stmt.name().to_string())
+            .filter(|name| name.starts_with("prepared_test_"))
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["prepared_test_a", "prepared_test_b"]);
+
+        PgPreparedStatement::find("prepared_test_a")
+            .unwrap()
+            .deallocate();
+        assert!(PgPreparedStatement::find("prepared_test_a").is_none());
+
+        PgPreparedStatement::deallocate_all();
+        assert!(PgPreparedStatement::find("prepared_test_b").is_none());
+    }
+
+    #[pg_test]
+    fn test_invalidate_all_plans() {
+        Spi::run("PREPARE prepared_test_invalidate AS SELECT relname FROM pg_class LIMIT 1");
+        PgPreparedStatement::invalidate_all_plans();
+
+        let stmt = PgPreparedStatement::find("prepared_test_invalidate").unwrap();
+        assert!(!stmt.is_valid());
+        Spi::run("EXECUTE prepared_test_invalidate");
+        assert!(stmt.is_valid());
+        stmt.deallocate();
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::queue::PgQueue;
+    use pgx::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn make_queue() -> PgQueue {
+        let queue = PgQueue::new("tests.test_queue");
+        Spi::run(&queue.create_table_sql());
+        queue
+    }
+
+    #[pg_test]
+    fn test_queue_enqueue_dequeue_ack() {
+        let queue = make_queue();
+        let id = queue.enqueue(json!({"task": "one"}));
+
+        let message = queue
+            .dequeue(Duration::from_secs(60))
+            .expect("no message dequeued");
+        assert_eq!(message.id, id);
+        assert_eq!(message.payload, json!({"task": "one"}));
+        assert_eq!(message.attempts, 1);
+
+        assert!(queue.ack(message.id));
+        assert!(queue.is_empty());
+    }
+
+    #[pg_test]
+    fn test_queue_visibility_timeout() {
+        let queue = make_queue();
+        let first = queue.enqueue(json!(1));
+        let second = queue.enqueue(json!(2));
+
+        // the first message is hidden once dequeued, so we should get the second one next
+        assert_eq!(queue.dequeue(Duration::from_secs(60)).unwrap().id, first);
+        assert_eq!(queue.dequeue(Duration::from_secs(60)).unwrap().id, second);
+        assert!(queue.dequeue(Duration::from_secs(60)).is_none());
+
+        // and once released it's visible again, with its attempts counted
+        assert!(queue.release(first));
+        let retried = queue.dequeue(Duration::from_secs(60)).unwrap();
+        assert_eq!(retried.id, first);
+        assert_eq!(retried.attempts, 2);
+        assert_eq!(queue.len(), 2);
+    }
+}
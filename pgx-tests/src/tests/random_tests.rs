@@ -0,0 +1,71 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    fn draw() -> Vec<u64> {
+        (0..8).map(|_| random::next_u64()).collect()
+    }
+
+    #[pg_test]
+    fn test_random_seed_is_repeatable() {
+        let first = {
+            let _random = random::seed(42);
+            draw()
+        };
+        let second = {
+            let _random = random::seed(42);
+            draw()
+        };
+        assert_eq!(first, second);
+
+        let _random = random::seed(43);
+        assert_ne!(draw(), first);
+    }
+
+    #[pg_test]
+    fn test_random_seed_restores_sequence() {
+        let _outer = random::seed(1);
+        let expected = {
+            let _random = random::seed(1);
+            draw()
+        };
+
+        {
+            let _inner = random::seed(2);
+            draw();
+        }
+        assert_eq!(draw(), expected);
+    }
+
+    #[pg_test]
+    fn test_random_ranges() {
+        let _random = random::seed(7);
+        for _ in 0..1000 {
+            assert!(random::below(10) < 10);
+
+            let f = random::next_f64();
+            assert!((0.0..1.0).contains(&f));
+        }
+
+        let mut bytes = [0u8; 13];
+        random::fill_bytes(&mut bytes);
+        assert!(bytes.iter().any(|b| *b != 0));
+
+        let mut values = (0..100).collect::<Vec<_>>();
+        random::shuffle(&mut values);
+        assert_ne!(values, (0..100).collect::<Vec<_>>());
+        values.sort();
+        assert_eq!(values, (0..100).collect::<Vec<_>>());
+    }
+
+    #[pg_test(error = "random::below() requires a non-zero bound")]
+    fn test_random_below_zero() {
+        random::below(0);
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+#[pg_extern(requires_primary)]
+fn primary_only_function() -> bool {
+    true
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+    use std::time::Duration;
+
+    #[pg_test]
+    fn test_not_in_recovery() {
+        assert!(!recovery::in_recovery());
+        recovery::check_not_in_recovery("test");
+    }
+
+    #[pg_test]
+    fn test_requires_primary_function() {
+        let result = Spi::get_one::<bool>("SELECT primary_only_function();");
+        assert_eq!(result, Some(true));
+    }
+
+    #[pg_test]
+    fn test_wait_for_flushed_lsn() {
+        let lsn = recovery::current_lsn();
+        assert!(recovery::wait_for_lsn(lsn, Duration::from_secs(1)));
+    }
+
+    #[pg_test]
+    fn test_lsn_matches_pg_lsn() {
+        let lsn = Spi::get_one::<String>("SELECT pg_current_wal_flush_lsn()::text;")
+            .expect("lsn was null");
+        let parsed = recovery::parse_lsn(&lsn).expect("failed to parse LSN");
+        assert_eq!(recovery::format_lsn(parsed), lsn);
+    }
+
+    #[test]
+    fn test_parse_lsn() {
+        assert_eq!(recovery::parse_lsn("16/B374D848"), Some(0x16_B374_D848));
+        assert_eq!(recovery::parse_lsn("0/0"), Some(0));
+        assert_eq!(recovery::parse_lsn("16B374D848"), None);
+        assert_eq!(recovery::format_lsn(0x16_B374_D848), "16/B374D848");
+    }
+}
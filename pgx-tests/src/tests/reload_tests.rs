@@ -0,0 +1,38 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+    use std::cell::Cell;
+
+    // two libraries loaded into the same backend each have a `static` of their own
+    static FIRST_LIBRARY: PgBackendState<Cell<i64>> =
+        PgBackendState::new("reload_tests counter", 1, || Cell::new(0));
+    static SECOND_LIBRARY: PgBackendState<Cell<i64>> =
+        PgBackendState::new("reload_tests counter", 1, || Cell::new(100));
+    static INCOMPATIBLE_LIBRARY: PgBackendState<Cell<i64>> =
+        PgBackendState::new("reload_tests counter", 2, || Cell::new(0));
+
+    #[pg_test]
+    fn test_backend_state_shared_between_libraries() {
+        let first = FIRST_LIBRARY.get();
+        first.set(first.get() + 1);
+
+        let second = SECOND_LIBRARY.get();
+        assert_eq!(second.get(), first.get());
+        second.set(second.get() + 1);
+        assert_eq!(first.get(), second.get());
+    }
+
+    #[pg_test(
+        error = "backend state `reload_tests counter` has version 1, but this library expects version 2.  Reconnect to use it"
+    )]
+    fn test_backend_state_version_mismatch() {
+        FIRST_LIBRARY.get();
+        INCOMPATIBLE_LIBRARY.get();
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_create_and_find() {
+        assert!(PgReplicationOrigin::find("pgx_test_origin").is_none());
+
+        let origin = PgReplicationOrigin::create("pgx_test_origin");
+        assert_eq!(origin.name(), "pgx_test_origin");
+        assert_eq!(PgReplicationOrigin::find("pgx_test_origin"), Some(origin.clone()));
+        assert_eq!(PgReplicationOrigin::from_id(origin.id()), Some(origin.clone()));
+        assert_eq!(PgReplicationOrigin::find_or_create("pgx_test_origin"), origin);
+
+        let oid = Spi::get_one::<pg_sys::Oid>(
+            "SELECT pg_replication_origin_oid('pgx_test_origin');",
+        )
+        .expect("origin oid was null");
+        assert_eq!(oid, origin.id() as pg_sys::Oid);
+    }
+
+    #[pg_test]
+    fn test_advance() {
+        let origin = PgReplicationOrigin::create("pgx_test_advance");
+        origin.advance(0x1_0000_0000);
+        assert_eq!(origin.progress(false), 0x1_0000_0000);
+        origin.drop_origin(true);
+        assert!(PgReplicationOrigin::find("pgx_test_advance").is_none());
+    }
+
+    #[pg_test]
+    fn test_session() {
+        let origin = PgReplicationOrigin::create("pgx_test_session");
+        {
+            let session = origin.setup_session();
+            assert_eq!(session.origin(), &origin);
+            assert_eq!(
+                Spi::get_one::<bool>("SELECT pg_replication_origin_session_is_setup();"),
+                Some(true)
+            );
+        }
+        assert_eq!(
+            Spi::get_one::<bool>("SELECT pg_replication_origin_session_is_setup();"),
+            Some(false)
+        );
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_physical_slot_lifecycle() {
+        let lsn = create_physical_replication_slot("pgx_test_physical", true, false);
+        assert!(lsn.is_some());
+
+        let slot = replication_slot("pgx_test_physical").expect("slot wasn't created");
+        assert_eq!(slot.kind, ReplicationSlotKind::Physical);
+        assert!(!slot.temporary);
+        assert!(!slot.active);
+        assert_eq!(slot.restart_lsn, lsn);
+        assert!(slot.retained_wal_bytes().is_some());
+
+        drop_replication_slot("pgx_test_physical");
+        assert!(replication_slot("pgx_test_physical").is_none());
+    }
+
+    #[pg_test]
+    fn test_unreserved_slot_has_no_restart_lsn() {
+        let lsn = create_physical_replication_slot("pgx_test_unreserved", false, false);
+        assert!(lsn.is_none());
+
+        let slot = replication_slot("pgx_test_unreserved").expect("slot wasn't created");
+        assert!(slot.restart_lsn.is_none());
+        assert!(slot.retained_wal_bytes().is_none());
+        drop_replication_slot("pgx_test_unreserved");
+    }
+
+    #[pg_test(error = "replication slot \"pgx_test_missing\" does not exist")]
+    fn test_drop_missing_slot() {
+        drop_replication_slot("pgx_test_missing");
+    }
+}
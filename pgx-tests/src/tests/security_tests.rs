@@ -0,0 +1,56 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+extension_sql!(
+    r#"
+CREATE TABLE rls_documents (
+    id serial8 NOT NULL PRIMARY KEY,
+    owner text NOT NULL
+);
+INSERT INTO rls_documents (owner) VALUES ('alice'), ('bob');
+"#
+);
+
+#[pg_extern(rls_policy = "owner_only", rls_table = "rls_documents", rls_command = "select")]
+fn rls_document_is_visible(owner: &str) -> bool {
+    owner == "alice"
+}
+
+#[pg_extern(security_barrier_view = "security_barrier_numbers")]
+fn security_barrier_numbers_srf() -> impl std::iter::Iterator<Item = i32> {
+    1..=3
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_rls_policy_created() {
+        let policies = Spi::get_one::<i64>(
+            "SELECT count(*) FROM pg_policies WHERE tablename = 'rls_documents' AND policyname = 'owner_only' AND cmd = 'SELECT'",
+        );
+        assert_eq!(policies, Some(1));
+
+        let enabled = Spi::get_one::<bool>(
+            "SELECT relrowsecurity FROM pg_class WHERE oid = 'rls_documents'::regclass",
+        );
+        assert_eq!(enabled, Some(true));
+    }
+
+    #[pg_test]
+    fn test_security_barrier_view_created() {
+        let count = Spi::get_one::<i64>("SELECT count(*) FROM security_barrier_numbers");
+        assert_eq!(count, Some(3));
+
+        let is_barrier = Spi::get_one::<bool>(
+            "SELECT 'security_barrier=true' = ANY(reloptions) FROM pg_class WHERE oid = 'security_barrier_numbers'::regclass",
+        );
+        assert_eq!(is_barrier, Some(true));
+    }
+}
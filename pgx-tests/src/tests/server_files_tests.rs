@@ -0,0 +1,37 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_read_server_file() {
+        let version = read_server_file_text("PG_VERSION", 0, None, false).expect("no PG_VERSION");
+        assert_eq!(version.trim(), pg_sys::get_pg_major_version_string());
+
+        let partial = read_server_file("PG_VERSION", 0, Some(1), false).unwrap();
+        assert_eq!(partial.len(), 1);
+    }
+
+    #[pg_test]
+    fn test_read_missing_server_file() {
+        assert!(read_server_file("this_file_does_not_exist", 0, None, true).is_none());
+    }
+
+    #[pg_test]
+    fn test_list_server_dir() {
+        let entries = list_server_dir(".", false, false).unwrap();
+        assert!(entries.contains(&"PG_VERSION".to_string()));
+    }
+
+    #[pg_test(error = "path must be in or below the current directory")]
+    fn test_server_file_path_confinement() {
+        Spi::run("CREATE ROLE pgx_server_files_test");
+        Spi::run("SET ROLE pgx_server_files_test");
+        resolve_server_file_path("../../etc/passwd");
+    }
+}
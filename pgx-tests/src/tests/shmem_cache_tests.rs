@@ -0,0 +1,143 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+use std::hash::{Hash, Hasher};
+
+/// A key that always hashes the same, so every one lands in the same partition and bucket chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Colliding(i64);
+
+impl Hash for Colliding {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+/// Each test has a cache of its own, as they're shared by every backend and not rolled back.
+/// `_PG_init()` initializes all of these
+pub(crate) static VALUES: PgShmemCache<i64, i64> = PgShmemCache::new("pgx_tests_cache", 64);
+pub(crate) static LRU: PgShmemCache<i64, i64> =
+    PgShmemCache::with_partitions("pgx_tests_cache_lru", 3, 1);
+pub(crate) static CHAINED: PgShmemCache<Colliding, i64> =
+    PgShmemCache::with_partitions("pgx_tests_cache_chained", 4, 1);
+pub(crate) static CLEARED: PgShmemCache<i64, i64> =
+    PgShmemCache::new("pgx_tests_cache_cleared", 64);
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use super::{Colliding, CHAINED, CLEARED, LRU, VALUES};
+    use pgx::*;
+
+    #[pg_test]
+    fn test_cache_insert_get_replace() {
+        // it's not rolled back, so a cluster kept running keeps what the last run left
+        VALUES.clear();
+
+        assert_eq!(VALUES.get(&1), None);
+        assert_eq!(VALUES.insert(1, 10), None);
+        assert_eq!(VALUES.insert(2, 20), None);
+        assert_eq!(VALUES.get(&1), Some(10));
+        assert_eq!(VALUES.get(&2), Some(20));
+        assert!(VALUES.contains_key(&1));
+        assert!(!VALUES.contains_key(&3));
+
+        assert_eq!(VALUES.insert(1, 11), Some(10));
+        assert_eq!(VALUES.get(&1), Some(11));
+        assert_eq!(VALUES.len(), 2);
+
+        assert_eq!(VALUES.remove(&1), Some(11));
+        assert_eq!(VALUES.remove(&1), None);
+        assert_eq!(VALUES.get(&1), None);
+        assert_eq!(VALUES.len(), 1);
+    }
+
+    #[pg_test]
+    fn test_cache_evicts_least_recently_used() {
+        LRU.clear();
+        assert_eq!(LRU.capacity(), 3);
+        LRU.insert(1, 10);
+        LRU.insert(2, 20);
+        LRU.insert(3, 30);
+
+        // reading 1 makes 2 the least recently used
+        assert_eq!(LRU.get(&1), Some(10));
+        LRU.insert(4, 40);
+        assert!(!LRU.contains_key(&2));
+        assert_eq!(LRU.len(), 3);
+
+        // but `contains_key()` doesn't count as a use, so 3 goes next
+        assert!(LRU.contains_key(&3));
+        LRU.insert(5, 50);
+        assert!(!LRU.contains_key(&3));
+
+        // while replacing a value does
+        assert_eq!(LRU.insert(1, 11), Some(10));
+        LRU.insert(6, 60);
+        assert!(!LRU.contains_key(&4));
+        assert_eq!(LRU.get(&1), Some(11));
+        assert_eq!(LRU.get(&5), Some(50));
+        assert_eq!(LRU.get(&6), Some(60));
+    }
+
+    #[pg_test]
+    fn test_cache_remove_from_chain() {
+        CHAINED.clear();
+        for i in 1..=4 {
+            CHAINED.insert(Colliding(i), i * 10);
+        }
+
+        // from the middle, the head, and the tail of the bucket's chain
+        assert_eq!(CHAINED.remove(&Colliding(2)), Some(20));
+        assert_eq!(CHAINED.remove(&Colliding(4)), Some(40));
+        assert_eq!(CHAINED.remove(&Colliding(1)), Some(10));
+        assert_eq!(CHAINED.len(), 1);
+        assert_eq!(CHAINED.get(&Colliding(3)), Some(30));
+        assert_eq!(CHAINED.get(&Colliding(2)), None);
+
+        // what's removed goes back on the free list, so filling it again evicts nothing
+        for i in 5..=7 {
+            CHAINED.insert(Colliding(i), i * 10);
+        }
+        assert_eq!(CHAINED.len(), 4);
+        for i in [3, 5, 6, 7].iter() {
+            assert_eq!(CHAINED.get(&Colliding(*i)), Some(i * 10));
+        }
+    }
+
+    #[pg_test]
+    fn test_cache_clear() {
+        for i in 0..32 {
+            CLEARED.insert(i, i);
+        }
+        assert!(!CLEARED.is_empty());
+
+        CLEARED.clear();
+        assert!(CLEARED.is_empty());
+        assert_eq!(CLEARED.get(&1), None);
+
+        // and it's still usable
+        CLEARED.insert(1, 10);
+        assert_eq!(CLEARED.get(&1), Some(10));
+        assert_eq!(CLEARED.len(), 1);
+    }
+
+    #[pg_test]
+    fn test_cache_capacity() {
+        // rounded up to a whole number of entries per partition
+        assert_eq!(VALUES.capacity(), 64);
+        assert_eq!(
+            PgShmemCache::<i64, i64>::new("pgx_tests_capacity", 100).capacity(),
+            112
+        );
+        assert_eq!(
+            PgShmemCache::<i64, i64>::with_partitions("pgx_tests_capacity", 10, 4).capacity(),
+            12
+        );
+        assert_eq!(
+            PgShmemCache::<i64, i64>::with_partitions("pgx_tests_capacity", 1, 1).capacity(),
+            1
+        );
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_spi_render() {
+        let table = Spi::render(
+            "SELECT 1 AS id, 'alice'::text AS name UNION ALL SELECT 2, NULL UNION ALL SELECT 3, E'a\\nb' ORDER BY id",
+        );
+        assert_eq!(
+            table,
+            " id | name\n----+--------\n 1  | alice\n 2  | [NULL]\n 3  | a\\nb\n(3 rows)\n"
+        );
+    }
+
+    #[pg_test]
+    fn test_spi_render_one_row() {
+        assert_eq!(
+            Spi::render("SELECT 42 AS answer"),
+            " answer\n--------\n 42\n(1 row)\n"
+        );
+    }
+
+    #[pg_test]
+    fn test_spi_snapshot() {
+        assert_spi_snapshot!(
+            "spi_snapshot_generate_series",
+            "SELECT i, i * 2 AS doubled FROM generate_series(1, 3) i"
+        );
+    }
+
+    #[pg_test]
+    fn test_snapshot_mismatch() {
+        let dir = std::env::temp_dir().join(format!("pgx-snapshot-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("snapshots")).unwrap();
+        std::fs::write(dir.join("snapshots/mismatch.snap"), "a\nb\nc\n").unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            snapshot::assert_snapshot(dir.to_str().unwrap(), "mismatch", "a\nx\nc\n")
+        });
+        let message = result
+            .expect_err("mismatched snapshot didn't fail")
+            .downcast::<String>()
+            .expect("panic message isn't a String");
+        let new = std::fs::read_to_string(dir.join("snapshots/mismatch.snap.new"));
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(message.contains("  a\n+ x\n- b\n  c\n"), "{}", message);
+        assert_eq!(new.unwrap(), "a\nx\nc\n");
+    }
+}
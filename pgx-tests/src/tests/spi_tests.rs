@@ -1,7 +1,6 @@
 // Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
 // governed by the MIT license that can be found in the LICENSE file.
 
-
 #[cfg(any(test, feature = "pg_test"))]
 mod tests {
     #[allow(unused_imports)]
@@ -150,4 +149,107 @@ mod tests {
     fn test_panic_via_spi() {
         Spi::run("SELECT tests.do_panic();");
     }
+
+    fn sum_rows(table: SpiTupleTable) -> i32 {
+        table.map(|row| row.get_datum::<i32>(1).unwrap()).sum()
+    }
+
+    #[pg_test]
+    fn test_spi_open_cursor() {
+        let sum = Spi::connect(|client| {
+            let mut cursor = client.open_cursor("SELECT generate_series(1, 10)", None);
+            let first = sum_rows(cursor.fetch_forward(3));
+            let rest = sum_rows(cursor.fetch_forward(100));
+            Ok(Some(first * 100 + rest))
+        });
+        assert_eq!(sum, Some(649));
+    }
+
+    #[pg_test]
+    fn test_spi_open_cursor_with_args() {
+        let sum = Spi::connect(|client| {
+            let mut cursor = client.open_cursor(
+                "SELECT generate_series(1, $1)",
+                Some(vec![(PgOid::from(pg_sys::INT4OID), 4.into_datum())]),
+            );
+            Ok(Some(sum_rows(cursor.fetch_forward(100))))
+        });
+        assert_eq!(sum, Some(10));
+    }
+
+    #[pg_test]
+    fn test_spi_scroll_cursor() {
+        Spi::connect(|client| {
+            let mut cursor = client.open_scroll_cursor("SELECT generate_series(1, 10)", None);
+
+            let last = cursor
+                .fetch(SpiFetchDirection::Absolute(-1))
+                .first()
+                .get_one::<i32>();
+            assert_eq!(last, Some(10));
+
+            let backward = cursor
+                .fetch(SpiFetchDirection::Backward(3))
+                .map(|row| row.get_datum::<i32>(1).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(backward, vec![9, 8, 7]);
+
+            let third = cursor
+                .fetch(SpiFetchDirection::Absolute(3))
+                .first()
+                .get_one::<i32>();
+            assert_eq!(third, Some(3));
+
+            let fifth = cursor
+                .fetch(SpiFetchDirection::Relative(2))
+                .first()
+                .get_one::<i32>();
+            assert_eq!(fifth, Some(5));
+
+            assert_eq!(cursor.move_to(SpiFetchDirection::Forward(2)), 2);
+            let eighth = cursor.fetch_forward(1).first().get_one::<i32>();
+            assert_eq!(eighth, Some(8));
+
+            Ok(Some(()))
+        });
+    }
+
+    #[pg_test]
+    fn test_spi_cursor_detach() {
+        let name = Spi::connect(|client| {
+            let mut cursor = client.open_cursor("SELECT generate_series(1, 10)", None);
+            cursor.fetch_forward(5);
+            Ok(Some(cursor.detach_into_name()))
+        })
+        .unwrap();
+
+        let sum = Spi::connect(|client| {
+            let mut cursor = client
+                .find_cursor(&name)
+                .expect("detached cursor was closed");
+            Ok(Some(sum_rows(cursor.fetch_forward(100))))
+        });
+        assert_eq!(sum, Some(40));
+    }
+
+    #[pg_test]
+    fn test_spi_cursor_closed_on_drop() {
+        let name = Spi::connect(|client| {
+            let cursor = client.open_cursor("SELECT 1", None);
+            Ok(Some(cursor.name().to_string()))
+        })
+        .unwrap();
+
+        assert!(PgPortal::find(&name).is_none());
+    }
+
+    #[pg_test(error = "cursor can only scan forward")]
+    fn test_spi_cursor_no_scroll() {
+        Spi::connect(|client| {
+            let mut cursor = client.open_cursor("SELECT generate_series(1, 10)", None);
+            cursor.fetch_forward(5);
+            cursor.fetch(SpiFetchDirection::Backward(1));
+            Ok(Some(()))
+        });
+    }
 }
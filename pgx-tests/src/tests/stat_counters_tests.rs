@@ -0,0 +1,177 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+use pgx::*;
+
+/// Each test has counters of its own, as they're shared by every backend and not rolled back.
+/// `_PG_init()` enables all of these, but not `DISABLED` below
+pub(crate) static COUNTED: PgStatCounters =
+    PgStatCounters::new("pgx_tests_counted", &["calls", "rows"]);
+pub(crate) static RESET: PgStatCounters =
+    PgStatCounters::new("pgx_tests_reset", &["calls", "rows"]);
+pub(crate) static SAVED: PgStatCounters =
+    PgStatCounters::new("pgx_tests_saved", &["calls", "rows"]);
+
+#[pg_extern]
+fn saved_counter_add(index: i32, n: i64) {
+    SAVED.add(index as usize, n as u64);
+}
+
+#[pg_extern]
+fn saved_counter(index: i32) -> i64 {
+    SAVED.get(index as usize) as i64
+}
+
+#[pg_extern]
+fn saved_counters_reset() -> Option<i64> {
+    SAVED.stats_reset()
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use super::{COUNTED, RESET};
+    use pgx::*;
+
+    static DISABLED: PgStatCounters = PgStatCounters::new("pgx_tests_counters", &["calls", "rows"]);
+
+    #[pg_test]
+    fn test_counters_without_shmem() {
+        assert!(!DISABLED.enabled());
+        assert_eq!(DISABLED.counters(), &["calls", "rows"]);
+        DISABLED.increment(0);
+        DISABLED.add(1, 42);
+    }
+
+    #[pg_test(error = "statistics counters `pgx_tests_counters` are not enabled")]
+    fn test_counters_snapshot_without_shmem() {
+        DISABLED.snapshot();
+    }
+
+    #[pg_test(error = "statistics counters `pgx_tests_counters` are not enabled")]
+    fn test_counters_reset_without_shmem() {
+        DISABLED.reset();
+    }
+
+    #[pg_test]
+    fn test_counters_count() {
+        assert!(COUNTED.enabled());
+        let calls = COUNTED.get(0);
+        let rows = COUNTED.get(1);
+
+        COUNTED.increment(0);
+        COUNTED.add(1, 42);
+        assert_eq!(COUNTED.get(0), calls + 1);
+        assert_eq!(COUNTED.get(1), rows + 42);
+        assert_eq!(
+            COUNTED.snapshot(),
+            vec![("calls", calls + 1), ("rows", rows + 42)]
+        );
+    }
+
+    #[pg_test(
+        error = "statistics counters `pgx_tests_counted` have 2 counters, there's no counter 2"
+    )]
+    fn test_counters_index_out_of_range() {
+        COUNTED.increment(2);
+    }
+
+    #[pg_test]
+    fn test_counters_reset() {
+        RESET.add(0, 5);
+        RESET.add(1, 7);
+
+        let before = unsafe { pg_sys::GetCurrentTimestamp() };
+        RESET.reset();
+        let after = unsafe { pg_sys::GetCurrentTimestamp() };
+
+        assert_eq!(RESET.get(0), 0);
+        assert_eq!(RESET.get(1), 0);
+        let stats_reset = RESET
+            .stats_reset()
+            .expect("reset() didn't record when it ran");
+        assert!(before <= stats_reset && stats_reset <= after);
+    }
+
+    // these are plain `#[test]`s, as they restart Postgres
+    #[cfg(test)]
+    mod restart {
+        use crate::CrashTestCluster;
+        use std::path::PathBuf;
+
+        const STATS_FILE: &str = "pg_stat/pgx_tests_saved.stat";
+
+        fn saved(client: &mut postgres::Client) -> (i64, i64, Option<i64>) {
+            let row = client
+                .query_one(
+                    "SELECT saved_counter(0), saved_counter(1), saved_counters_reset()",
+                    &[],
+                )
+                .expect("couldn't read the saved counters");
+            (row.get(0), row.get(1), row.get(2))
+        }
+
+        #[test]
+        fn test_counters_saved_across_restart() {
+            let cluster = CrashTestCluster::new(crate::pg_test::postgresql_conf_options());
+            let mut client = cluster.client();
+            client
+                .simple_query("SELECT saved_counter_add(0, 5)")
+                .expect("couldn't add to the saved counters");
+            let (calls, rows, stats_reset) = saved(&mut client);
+
+            let mut stats_file = PathBuf::new();
+            let mut contents = String::new();
+            cluster.shutdown_and_restart(|pgdata| {
+                stats_file = pgdata.join(STATS_FILE);
+                contents = std::fs::read_to_string(&stats_file)
+                    .expect("the counters weren't saved at shutdown");
+            });
+            assert_eq!(
+                contents,
+                format!(
+                    "calls {}\nrows {}\nstats_reset {}\n",
+                    calls,
+                    rows,
+                    stats_reset.unwrap_or(0)
+                )
+            );
+            assert_eq!(saved(&mut cluster.client()), (calls, rows, stats_reset));
+
+            // so a crash doesn't bring back what's been counted since
+            assert!(!stats_file.exists());
+        }
+
+        #[test]
+        fn test_counters_load() {
+            let cluster = CrashTestCluster::new(crate::pg_test::postgresql_conf_options());
+
+            // in any order, and ignoring counters that have since been removed
+            cluster.shutdown_and_restart(|pgdata| {
+                std::fs::write(
+                    pgdata.join(STATS_FILE),
+                    "removed 3\nrows 9\nstats_reset 12345\ncalls 7\n",
+                )
+                .expect("couldn't write the statistics file");
+            });
+            assert_eq!(saved(&mut cluster.client()), (7, 9, Some(12345)));
+        }
+
+        #[test]
+        fn test_counters_load_corrupt() {
+            let cluster = CrashTestCluster::new(crate::pg_test::postgresql_conf_options());
+
+            // none of it is trusted, not even the lines before the corrupt one
+            let mut stats_file = PathBuf::new();
+            cluster.shutdown_and_restart(|pgdata| {
+                stats_file = pgdata.join(STATS_FILE);
+                std::fs::write(&stats_file, "calls 7\nrows nine\nstats_reset 12345\n")
+                    .expect("couldn't write the statistics file");
+            });
+            assert_eq!(saved(&mut cluster.client()), (0, 0, None));
+            assert!(!stats_file.exists());
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_stringinfo_fmt_write() {
+        use std::fmt::Write;
+
+        let mut buffer = PgStringInfo::new();
+        write!(&mut buffer, "{}-{:03}", "row", 7).unwrap();
+        buffer.push('\u{1F600}');
+        assert_eq!(buffer.to_string(), "row-007\u{1F600}");
+    }
+
+    #[pg_test]
+    fn test_stringinfo_io_write() {
+        use std::io::Write;
+
+        let mut buffer = PgStringInfo::new();
+        for i in 0..3 {
+            writeln!(&mut buffer, "{}\t{}", i, i * i).unwrap();
+        }
+        assert_eq!(buffer.as_bytes(), b"0\t0\n1\t1\n2\t4\n");
+    }
+
+    #[pg_test]
+    fn test_stringinfo_into_char_ptr() {
+        use std::fmt::Write;
+
+        let mut buffer = PgStringInfo::new();
+        write!(&mut buffer, "{:?}", Some(42)).unwrap();
+        let ptr = buffer.into_char_ptr();
+        let copy = unsafe { std::ffi::CStr::from_ptr(ptr) };
+        assert_eq!(copy.to_str().unwrap(), "Some(42)");
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    fn serialization_failure() {
+        Spi::run(
+            "DO $$ BEGIN RAISE EXCEPTION 'conflict' USING ERRCODE = 'serialization_failure'; END $$",
+        );
+    }
+
+    #[pg_test]
+    fn test_subtransaction_commits() {
+        Spi::run("CREATE TABLE subxact_commits (id int)");
+        let result = subtransaction(|| {
+            Spi::run("INSERT INTO subxact_commits VALUES (1)");
+            42
+        });
+
+        assert_eq!(result.ok(), Some(42));
+        assert_eq!(
+            Spi::get_one::<i64>("SELECT count(*) FROM subxact_commits"),
+            Some(1)
+        );
+    }
+
+    #[pg_test]
+    fn test_subtransaction_rolls_back() {
+        Spi::run("CREATE TABLE subxact_rolls_back (id int)");
+        let result = subtransaction(|| {
+            Spi::run("INSERT INTO subxact_rolls_back VALUES (1)");
+            Spi::run("SELECT 1/0");
+        });
+
+        let error = result.expect_err("division by zero wasn't caught");
+        assert_eq!(
+            PgSqlErrorCode::from_sqlerrcode(error.sqlerrcode),
+            Some(PgSqlErrorCode::ERRCODE_DIVISION_BY_ZERO)
+        );
+        assert_eq!(
+            Spi::get_one::<i64>("SELECT count(*) FROM subxact_rolls_back"),
+            Some(0)
+        );
+    }
+
+    #[pg_test]
+    fn test_retry_on_serialization() {
+        let attempts = AtomicU32::new(0);
+        let result = retry_on_serialization(|| {
+            if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                serialization_failure();
+            }
+            42
+        });
+
+        assert_eq!(result, 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[pg_test(error = "conflict")]
+    fn test_retry_on_serialization_gives_up() {
+        let policy = PgRetryPolicy {
+            max_attempts: 2,
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        };
+        retry_on_serialization_with(policy, serialization_failure);
+    }
+
+    #[pg_test(error = "division by zero")]
+    fn test_retry_on_serialization_other_error() {
+        retry_on_serialization(|| Spi::run("SELECT 1/0"));
+    }
+}
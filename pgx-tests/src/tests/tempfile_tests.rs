@@ -0,0 +1,47 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[pg_test]
+    fn test_tempfile_roundtrip() {
+        let mut file = PgTempFile::new();
+        file.write_all(b"hello, world").unwrap();
+        assert_eq!(file.size().unwrap(), 12);
+
+        file.seek(SeekFrom::Start(7)).unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "world");
+    }
+
+    #[pg_test]
+    fn test_tempfile_deleted_on_drop() {
+        let file = PgTempFile::new();
+        let path = file.path();
+        assert!(std::path::Path::new(&path).exists());
+
+        drop(file);
+        assert!(!std::path::Path::new(&path).exists());
+    }
+
+    #[pg_test]
+    fn test_resource_owner_cleans_up_leaked_tempfile() {
+        let owner = PgResourceOwner::new("pgx tempfile test");
+        let file = PgTempFile::new_in(&owner);
+        let path = file.path();
+
+        // simulate losing track of the file, as happens if the work is aborted
+        std::mem::forget(file);
+        assert!(std::path::Path::new(&path).exists());
+
+        drop(owner);
+        assert!(!std::path::Path::new(&path).exists());
+    }
+}
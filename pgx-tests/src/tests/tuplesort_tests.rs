@@ -0,0 +1,83 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    #[pg_test]
+    fn test_datum_sort_integers() {
+        let mut sort = PgDatumSort::by_default_order(pg_sys::INT4OID, false);
+        for value in vec![Some(3), None, Some(1), Some(2)] {
+            sort.push(value.into_datum());
+        }
+
+        let sorted = sort
+            .sort()
+            .map(|datum| unsafe {
+                i32::from_datum(datum.unwrap_or(0), datum.is_none(), pg_sys::INT4OID)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sorted, vec![Some(1), Some(2), Some(3), None]);
+    }
+
+    #[pg_test]
+    fn test_datum_sort_text_descending() {
+        let mut sort = PgDatumSort::by_default_order(pg_sys::TEXTOID, true);
+        for value in vec!["banana", "apple", "cherry"] {
+            sort.push(value.into_datum());
+        }
+        sort.push(None);
+
+        let sorted = sort
+            .sort()
+            .map(|datum| unsafe {
+                String::from_datum(datum.unwrap_or(0), datum.is_none(), pg_sys::TEXTOID)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sorted,
+            vec![
+                None,
+                Some("cherry".to_string()),
+                Some("banana".to_string()),
+                Some("apple".to_string())
+            ]
+        );
+    }
+
+    #[pg_test]
+    fn test_external_sort_spills() {
+        // a simple LCG, so the input is in a scrambled but repeatable order
+        let mut next = 42u64;
+        let mut values = Vec::new();
+        for _ in 0..50_000 {
+            next = next.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            values.push(next >> 33);
+        }
+
+        let mut sort = PgExternalSort::new(|a: &u64, b: &u64| b.cmp(a)).with_work_mem(64);
+        for value in &values {
+            sort.push(*value);
+        }
+        assert_eq!(sort.len(), values.len());
+        assert!(sort.is_spilled());
+
+        values.sort_by(|a, b| b.cmp(a));
+        assert_eq!(sort.sort().collect::<Vec<_>>(), values);
+    }
+
+    #[pg_test]
+    fn test_external_sort_in_memory() {
+        let mut sort = PgExternalSort::new(|a: &String, b: &String| a.cmp(b))
+            .with_item_size(|s| std::mem::size_of::<String>() + s.len());
+        for word in &["pear", "fig", "apple"] {
+            sort.push(word.to_string());
+        }
+        assert!(!sort.is_spilled());
+        assert_eq!(sort.sort().collect::<Vec<_>>(), vec!["apple", "fig", "pear"]);
+    }
+}
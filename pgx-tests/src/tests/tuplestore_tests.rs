@@ -0,0 +1,66 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    fn row_tupdesc() -> PgTupleDesc<'static> {
+        Spi::run("CREATE TYPE tuplestore_test_row AS (id bigint, label text);");
+        let typid = Spi::get_one::<i64>("SELECT 'tuplestore_test_row'::regtype::oid::bigint;")
+            .expect("type oid is NULL");
+        unsafe {
+            PgTupleDesc::from_pg_is_copy(pg_sys::lookup_rowtype_tupdesc_copy(
+                typid as pg_sys::Oid,
+                -1,
+            ))
+        }
+    }
+
+    #[pg_test]
+    fn test_tuplestore_spills_to_disk() {
+        let tupdesc = row_tupdesc();
+        let mut store = PgTuplestore::with_work_mem(&tupdesc, true, 64);
+        for i in 0..10_000i64 {
+            let label = if i % 10 == 0 {
+                None
+            } else {
+                format!("row {}", i).into_datum()
+            };
+            store.push_values(&[i.into_datum(), label]);
+        }
+        assert_eq!(store.len(), 10_000);
+        assert!(!store.is_in_memory());
+
+        // random access tuplestores can be read more than once
+        for _ in 0..2 {
+            let mut count = 0;
+            for (i, row) in store.rows().enumerate() {
+                let id = unsafe {
+                    i64::from_datum(row[0].unwrap_or(0), row[0].is_none(), pg_sys::INT8OID)
+                };
+                let label = unsafe {
+                    String::from_datum(row[1].unwrap_or(0), row[1].is_none(), pg_sys::TEXTOID)
+                };
+                assert_eq!(id, Some(i as i64));
+                if i % 10 == 0 {
+                    assert_eq!(label, None);
+                } else {
+                    assert_eq!(label, Some(format!("row {}", i)));
+                }
+                count += 1;
+            }
+            assert_eq!(count, 10_000);
+        }
+    }
+
+    #[pg_test(error = "tuplestore rows have 2 attributes, but 1 values were provided")]
+    fn test_tuplestore_wrong_number_of_values() {
+        let tupdesc = row_tupdesc();
+        let mut store = PgTuplestore::new(&tupdesc, false);
+        store.push_values(&[1i64.into_datum()]);
+    }
+}
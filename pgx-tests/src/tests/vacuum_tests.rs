@@ -0,0 +1,49 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgx_tests;
+
+    use pgx::*;
+
+    fn create_table() -> pg_sys::Oid {
+        Spi::run("CREATE TABLE vacuum_tests (id int)");
+        Spi::run("INSERT INTO vacuum_tests SELECT generate_series(1, 1000)");
+        Spi::get_one::<pg_sys::Oid>("SELECT 'vacuum_tests'::regclass::oid")
+            .expect("table wasn't created")
+    }
+
+    #[pg_test]
+    fn test_analyze_relation() {
+        let relid = create_table();
+        analyze_relation(relid);
+
+        let reltuples = Spi::get_one::<f32>(
+            "SELECT reltuples FROM pg_class WHERE oid = 'vacuum_tests'::regclass",
+        );
+        assert_eq!(reltuples, Some(1000.0));
+        assert_eq!(
+            Spi::get_one::<i64>("SELECT count(*) FROM pg_stats WHERE tablename = 'vacuum_tests'"),
+            Some(1)
+        );
+    }
+
+    #[pg_test(error = "relation with OID 4294967295 does not exist")]
+    fn test_analyze_missing_relation() {
+        analyze_relation(u32::max_value());
+    }
+
+    #[pg_test(error = "VACUUM cannot run inside a transaction")]
+    fn test_vacuum_in_transaction() {
+        let relid = create_table();
+        vacuum_relation(relid, &PgVacuumOptions::default());
+    }
+
+    #[pg_test]
+    fn test_table_stats() {
+        assert!(table_stats(pg_sys::RelationRelationId).is_some());
+        assert!(table_stats(pg_sys::InvalidOid).is_none());
+    }
+}
@@ -5,7 +5,7 @@ use colored::Colorize;
 use proc_macro2::TokenTree;
 use quote::quote;
 use serde_derive::Deserialize;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::str::FromStr;
@@ -54,14 +54,28 @@ pub struct PgConfigPaths {
     pub pg10: String,
     pub pg11: String,
     pub pg12: String,
+
+    /// Optional, as a `config.toml` written before `cargo-pgx` supported them doesn't have them
+    pub pg13: Option<String>,
+    pub pg14: Option<String>,
+    pub pg15: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Configs {
     configs: PgConfigPaths,
+
+    /// The exact release, such as `pg12 = "12.6"`, of each Postgres `cargo pgx init` was told to
+    /// pin
+    #[serde(default)]
+    pins: HashMap<String, String>,
 }
 
 pub fn load_pgx_config() -> PgConfigPaths {
+    load_configs().configs
+}
+
+fn load_configs() -> Configs {
     let path = get_pgx_config_path();
 
     if !path.exists() {
@@ -75,17 +89,28 @@ pub fn load_pgx_config() -> PgConfigPaths {
     }
 
     handle_result!(
-        "config.toml invalid",
+        format!(
+            "config.toml invalid.  Have you re-run `{}` since upgrading cargo-pgx?",
+            "cargo pgx init".bold().yellow()
+        ),
         toml::from_str::<Configs>(handle_result!(
             "Unable to read config.toml",
             &std::fs::read_to_string(path)
         ))
     )
-    .configs
 }
 
 pub fn get_pgbin_dir(major_version: u16) -> PathBuf {
-    run_pg_config(&get_pg_config(major_version), "--bindir").into()
+    let pg_config = get_pg_config(major_version);
+    if pg_config.is_none() {
+        exit_with_error!(
+            "Postgres {} isn't in {}.  Re-run `{}` to add it",
+            major_version,
+            get_pgx_config_path().display(),
+            "cargo pgx init".bold().yellow()
+        )
+    }
+    run_pg_config(&pg_config, "--bindir").into()
 }
 
 pub fn get_postmaster_path(major_version: u16) -> PathBuf {
@@ -154,30 +179,184 @@ pub fn get_pgx_config_path() -> PathBuf {
 pub fn get_target_dir() -> PathBuf {
     std::env::var("CARGO_TARGET_DIR").map_or_else(
         |_| {
-            let mut cwd = std::env::current_dir().unwrap();
-            cwd.push("target");
-            cwd
+            // the members of a workspace share the target directory at its root
+            let mut dir = find_workspace_root().unwrap_or_else(|| std::env::current_dir().unwrap());
+            dir.push("target");
+            dir
         },
         |v| v.into(),
     )
 }
 
+/// The directory of the nearest `Cargo.toml`, at or above the current directory, with a
+/// `[workspace]` table, the same way `cargo` looks for it
+pub fn find_workspace_root() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml"))
+                .ok()
+                .and_then(|contents| toml::from_str::<toml::Value>(&contents).ok())
+                .map_or(false, |manifest| manifest.get("workspace").is_some())
+        })
+        .map(|dir| dir.to_path_buf())
+}
+
 pub fn get_pg_config(major_version: u16) -> Option<String> {
     let paths = load_pgx_config();
     match major_version {
         10 => Some(paths.pg10),
         11 => Some(paths.pg11),
         12 => Some(paths.pg12),
+        13 => paths.pg13,
+        14 => paths.pg14,
+        15 => paths.pg15,
         _ => None,
     }
 }
 
 pub fn get_pg_config_major_version(pg_config: &Option<String>) -> u16 {
     let version_string = run_pg_config(&pg_config, "--version");
-    let version_parts = version_string.split_whitespace().collect::<Vec<&str>>();
-    let version = version_parts.get(1);
-    let version = f64::from_str(&version.unwrap()).expect("not a valid version number");
-    version.floor() as u16
+    match parse_pg_config_version(&version_string) {
+        Some(version) => version,
+        None => exit_with_error!(
+            "`{} --version` didn't report a valid Postgres version: {}",
+            pg_config.as_deref().unwrap_or("pg_config"),
+            version_string
+        ),
+    }
+}
+
+/// The major version from `pg_config --version` output, such as `PostgreSQL 13.2`, `PostgreSQL
+/// 10.14 (Ubuntu 10.14-1)`, or `PostgreSQL 15beta1`
+pub fn parse_pg_config_version(version_string: &str) -> Option<u16> {
+    let version = version_string.split_whitespace().nth(1)?;
+    let major = version
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+    u16::from_str(&major).ok()
+}
+
+/// The release from `pg_config --version` output, such as `13.2` from `PostgreSQL 13.2` or
+/// `10.14` from `PostgreSQL 10.14 (Ubuntu 10.14-1)`
+pub fn parse_pg_config_release(version_string: &str) -> Option<&str> {
+    version_string.split_whitespace().nth(1)
+}
+
+/// The `(major, minor)` of a release like `12.6`, as given to `cargo pgx init --pg12=12.6`
+pub fn parse_pg_release(release: &str) -> Option<(u16, u16)> {
+    let mut parts = release.splitn(2, '.');
+    match (parts.next(), parts.next()) {
+        (Some(major), Some(minor)) => {
+            Some((u16::from_str(major).ok()?, u16::from_str(minor).ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// Exit if `cargo pgx init` pinned `pg_config`'s major version to a release other than the one
+/// `pg_config` reports, so a build never silently uses a different minor than it was pinned to
+pub fn check_pinned_version(pg_config: &Option<String>) {
+    if !get_pgx_config_path().exists() {
+        return;
+    }
+
+    let version_string = run_pg_config(pg_config, "--version");
+    let major_version = get_pg_config_major_version(pg_config);
+    let release = parse_pg_config_release(&version_string).unwrap_or_default();
+    if let Some(pinned) = load_configs().pins.get(&format!("pg{}", major_version)) {
+        if pinned != release {
+            exit_with_error!(
+                "`{}` is Postgres {}, but {} pins Postgres {} to {}.  Re-run `{}` to change it",
+                pg_config.as_deref().unwrap_or("pg_config"),
+                release,
+                get_pgx_config_path().display(),
+                major_version,
+                pinned,
+                "cargo pgx init".bold().yellow()
+            )
+        }
+    }
+}
+
+/// Find the `pg_config` for Postgres `major_version`, for commands that can be told which
+/// version to use rather than relying on whatever `pg_config` is on the `$PATH`.
+///
+/// The one `cargo pgx init` configured in `~/.pgx/config.toml` is used if there is one.
+/// Otherwise it's the first of `$PG_CONFIG`, every `pg_config` on the `$PATH`, and the places
+/// Debian and Red Hat packages put theirs, that reports being that version.
+pub fn find_pg_config(major_version: u16) -> String {
+    let mut candidates = Vec::new();
+    if get_pgx_config_path().exists() {
+        if let Some(pg_config) = get_pg_config(major_version) {
+            // a misconfigured version is worse than none, so this one must be right
+            let pg_config = Some(pg_config);
+            let configured_version = get_pg_config_major_version(&pg_config);
+            let pg_config = pg_config.unwrap();
+            if configured_version != major_version {
+                exit_with_error!(
+                    "{} says `{}` is Postgres {}, but it's Postgres {}.  Re-run `{}` to fix it",
+                    get_pgx_config_path().display(),
+                    pg_config,
+                    major_version,
+                    configured_version,
+                    "cargo pgx init".bold().yellow()
+                )
+            }
+            return pg_config;
+        }
+    }
+
+    if let Ok(pg_config) = std::env::var("PG_CONFIG") {
+        candidates.push(PathBuf::from(pg_config));
+    }
+    if let Some(path) = std::env::var_os("PATH") {
+        for mut dir in std::env::split_paths(&path) {
+            dir.push("pg_config");
+            candidates.push(dir);
+        }
+    }
+    candidates.push(format!("/usr/lib/postgresql/{}/bin/pg_config", major_version).into());
+    candidates.push(format!("/usr/pgsql-{}/bin/pg_config", major_version).into());
+
+    let mut found = Vec::new();
+    for candidate in candidates {
+        if !candidate.is_file() || found.iter().any(|(path, _)| path == &candidate) {
+            continue;
+        }
+
+        let version = Command::new(&candidate)
+            .arg("--version")
+            .output()
+            .ok()
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .and_then(|version| parse_pg_config_version(&version));
+        match version {
+            Some(version) if version == major_version => {
+                return candidate.display().to_string();
+            }
+            Some(version) => found.push((candidate, version)),
+            None => {}
+        }
+    }
+
+    let found = found
+        .iter()
+        .map(|(path, version)| format!("\n    {} (Postgres {})", path.display(), version))
+        .collect::<String>();
+    exit_with_error!(
+        "couldn't find a `pg_config` for Postgres {}.  Run `{}` or put it on your $PATH.  Found:{}",
+        major_version,
+        format!("cargo pgx init --pg{} <PG_CONFIG>", major_version)
+            .bold()
+            .yellow(),
+        if found.is_empty() {
+            " nothing".to_string()
+        } else {
+            found
+        }
+    )
 }
 
 pub fn get_pg_download_dir() -> PathBuf {
@@ -253,7 +432,7 @@ pub fn createdb(
     true
 }
 
-fn does_db_exist(major_version: u16, host: &str, port: u16, dbname: &str) -> bool {
+pub fn does_db_exist(major_version: u16, host: &str, port: u16, dbname: &str) -> bool {
     let mut command = Command::new(get_psql_path(major_version));
     command
         .arg("-XqAt")
@@ -291,6 +470,146 @@ fn does_db_exist(major_version: u16, host: &str, port: u16, dbname: &str) -> boo
     }
 }
 
+/// Create the extension `extname` in `dbname`, if it isn't already
+pub fn createextension(major_version: u16, host: &str, port: u16, dbname: &str, extname: &str) {
+    println!("{} extension {}", "    Creating".bold().green(), extname);
+    run_psql_command(
+        major_version,
+        host,
+        port,
+        dbname,
+        &format!(
+            "CREATE EXTENSION IF NOT EXISTS \"{}\";",
+            extname.replace("\"", "\"\"")
+        ),
+    );
+}
+
+/// The name and version of each extension created in `dbname`, ordered by name
+pub fn installed_extensions(
+    major_version: u16,
+    host: &str,
+    port: u16,
+    dbname: &str,
+) -> Vec<(String, String)> {
+    run_psql_command(
+        major_version,
+        host,
+        port,
+        dbname,
+        "SELECT extname, extversion FROM pg_extension ORDER BY extname;",
+    )
+    .lines()
+    .filter_map(|line| {
+        let mut parts = line.splitn(2, '|');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(version)) => Some((name.to_string(), version.to_string())),
+            _ => None,
+        }
+    })
+    .collect()
+}
+
+/// Create a LOGIN role named `rolename`, as either a superuser or a regular user, if it doesn't
+/// already exist.  Returns `true` if the role was created
+pub fn createrole(
+    major_version: u16,
+    host: &str,
+    port: u16,
+    rolename: &str,
+    is_superuser: bool,
+) -> bool {
+    if does_role_exist(major_version, host, port, rolename) {
+        return false;
+    }
+
+    println!(
+        "{} {} {}",
+        "    Creating".bold().green(),
+        if is_superuser { "superuser" } else { "role" },
+        rolename
+    );
+    run_psql_command(
+        major_version,
+        host,
+        port,
+        "template1",
+        &format!(
+            "CREATE ROLE \"{}\" LOGIN {};",
+            rolename.replace("\"", "\"\""),
+            if is_superuser {
+                "SUPERUSER"
+            } else {
+                "NOSUPERUSER"
+            }
+        ),
+    );
+
+    true
+}
+
+/// Allow the (non-superuser) `rolename` to connect to, and create objects in, `dbname`
+pub fn grant_database(major_version: u16, host: &str, port: u16, dbname: &str, rolename: &str) {
+    run_psql_command(
+        major_version,
+        host,
+        port,
+        "template1",
+        &format!(
+            "GRANT ALL ON DATABASE \"{}\" TO \"{}\";",
+            dbname.replace("\"", "\"\""),
+            rolename.replace("\"", "\"\"")
+        ),
+    );
+}
+
+fn does_role_exist(major_version: u16, host: &str, port: u16, rolename: &str) -> bool {
+    let stdout = run_psql_command(
+        major_version,
+        host,
+        port,
+        "template1",
+        &format!(
+            "select count(*) from pg_roles where rolname = '{}';",
+            rolename.replace("'", "''")
+        ),
+    );
+    let count = i32::from_str(stdout.trim()).expect("result is not a number");
+    count > 0
+}
+
+fn run_psql_command(major_version: u16, host: &str, port: u16, dbname: &str, sql: &str) -> String {
+    let mut command = Command::new(get_psql_path(major_version));
+    command
+        .arg("-XqAt")
+        .arg("-h")
+        .arg(host)
+        .arg("-p")
+        .arg(port.to_string())
+        .arg(dbname)
+        .arg("-c")
+        .arg(sql)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let command_str = format!("{:?}", command);
+
+    let output = handle_result!(
+        format!("Failed to run psql: {}", command_str),
+        command.output()
+    );
+
+    if !output.status.success() {
+        exit_with_error!(
+            "problem running psql: {}\n\n{}{}",
+            command_str,
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap()
+        )
+    }
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
 pub fn get_named_capture(
     regex: &regex::Regex,
     name: &'static str,
@@ -302,7 +621,7 @@ pub fn get_named_capture(
     }
 }
 
-#[derive(Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
 pub enum ExternArgs {
     Immutable,
     Strict,
@@ -314,6 +633,20 @@ pub enum ExternArgs {
     ParallelUnsafe,
     ParallelRestricted,
     Error(String),
+    SecurityBarrierView(String),
+    RlsPolicy(String),
+    RlsTable(String),
+    RlsCommand(String),
+    RequiresPrimary,
+    TrackStats,
+    /// `sql_type(arg = "type", return = "type")`, as (argument name or "return", SQL type) pairs
+    SqlType(String, String),
+    DeprecatedAlias(String),
+    SecurityDefiner,
+    /// `set_search_path = "pg_catalog, pg_temp"`, pinned with `SET search_path` while it runs
+    SetSearchPath(String),
+    /// `on_create`, called once by `CREATE EXTENSION` after the rest of the schema is created
+    OnCreate,
 }
 
 #[derive(Debug)]
@@ -346,16 +679,40 @@ pub fn parse_extern_attributes(attr: TokenStream2) -> HashSet<ExternArgs> {
                     "parallel_safe" => args.insert(ExternArgs::ParallelSafe),
                     "parallel_unsafe" => args.insert(ExternArgs::ParallelUnsafe),
                     "parallel_restricted" => args.insert(ExternArgs::ParallelRestricted),
-                    "error" => {
-                        let _punc = itr.next().unwrap();
-                        let literal = itr.next().unwrap();
-                        let message = literal.to_string();
-                        let message = unescape::unescape(&message).expect("failed to unescape");
-
-                        // trim leading/trailing quotes around the literal
-                        let message = message[1..message.len() - 1].to_string();
-                        args.insert(ExternArgs::Error(message.to_string()))
+                    "error" => args.insert(ExternArgs::Error(next_string_literal(&mut itr))),
+                    "security_barrier_view" => args.insert(ExternArgs::SecurityBarrierView(
+                        next_string_literal(&mut itr),
+                    )),
+                    "rls_policy" => args.insert(ExternArgs::RlsPolicy(next_string_literal(&mut itr))),
+                    "rls_table" => args.insert(ExternArgs::RlsTable(next_string_literal(&mut itr))),
+                    "rls_command" => {
+                        args.insert(ExternArgs::RlsCommand(next_string_literal(&mut itr)))
+                    }
+                    "requires_primary" => args.insert(ExternArgs::RequiresPrimary),
+                    "track_stats" => args.insert(ExternArgs::TrackStats),
+                    "deprecated_alias" => {
+                        args.insert(ExternArgs::DeprecatedAlias(next_string_literal(&mut itr)))
+                    }
+                    "security_definer" => args.insert(ExternArgs::SecurityDefiner),
+                    "set_search_path" => {
+                        args.insert(ExternArgs::SetSearchPath(next_string_literal(&mut itr)))
                     }
+                    "on_create" => args.insert(ExternArgs::OnCreate),
+                    "sql_type" => match itr.next() {
+                        Some(TokenTree::Group(g)) => {
+                            let mut overrides = g.stream().into_iter();
+                            while let Some(t) = overrides.next() {
+                                if let TokenTree::Ident(name) = t {
+                                    args.insert(ExternArgs::SqlType(
+                                        name.to_string(),
+                                        next_string_literal(&mut overrides),
+                                    ));
+                                }
+                            }
+                            true
+                        }
+                        _ => panic!("#[pg_extern(sql_type)] expects a list like `sql_type(arg = \"regclass\")`"),
+                    },
                     _ => false,
                 };
             }
@@ -366,6 +723,17 @@ pub fn parse_extern_attributes(attr: TokenStream2) -> HashSet<ExternArgs> {
     args
 }
 
+/// consume the `= "value"` following an attribute argument's name, returning the unquoted value
+fn next_string_literal(itr: &mut impl Iterator<Item = TokenTree>) -> String {
+    let _punc = itr.next().unwrap();
+    let literal = itr.next().unwrap();
+    let value = literal.to_string();
+    let value = unescape::unescape(&value).expect("failed to unescape");
+
+    // trim leading/trailing quotes around the literal
+    value[1..value.len() - 1].to_string()
+}
+
 pub fn categorize_return_type(func: &ItemFn) -> CategorizedType {
     let rt = &func.sig.output;
 
@@ -485,9 +853,129 @@ pub fn categorize_type(ty: &Type) -> CategorizedType {
     }
 }
 
+/// The `(size, alignment)` in bytes of a `#[derive(PostgresType)]` struct with the
+/// `#[passedbyvalue]` attribute, laid out like `#[repr(C)]` (or `#[repr(C, packed)]`) does.
+///
+/// Both the derive and `cargo pgx schema` use this, so the struct's `INTERNALLENGTH` and
+/// `ALIGNMENT` always agree with how Rust lays it out.  Its fields must be primitive numbers,
+/// `bool`s, `char`s, or arrays of them, and it must be 1, 2, 4, or 8 bytes
+pub fn byval_type_layout(fields: &syn::Fields, packed: bool) -> Result<(usize, usize), String> {
+    let mut size = 0;
+    let mut align = 1;
+    for field in fields.iter() {
+        let (field_size, field_align) = primitive_layout(&field.ty)?;
+        let field_align = if packed { 1 } else { field_align };
+        size = round_up(size, field_align) + field_size;
+        align = align.max(field_align);
+    }
+    let size = round_up(size, align);
+
+    match size {
+        1 | 2 | 4 | 8 => Ok((size, align)),
+        _ => Err(format!(
+            "#[passedbyvalue] types must be 1, 2, 4, or 8 bytes, not {}",
+            size
+        )),
+    }
+}
+
+fn primitive_layout(ty: &Type) -> Result<(usize, usize), String> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let name = path.path.segments.last().unwrap().ident.to_string();
+            let size = match name.as_str() {
+                "bool" | "i8" | "u8" => 1,
+                "i16" | "u16" => 2,
+                "i32" | "u32" | "f32" | "char" => 4,
+                "i64" | "u64" | "f64" => 8,
+                _ => return Err(format!("#[passedbyvalue] types can't contain a {}", name)),
+            };
+            Ok((size, size))
+        }
+        Type::Array(array) => {
+            let (size, align) = primitive_layout(&array.elem)?;
+            match &array.len {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Int(len),
+                    ..
+                }) => Ok((
+                    size * len.base10_parse::<usize>().map_err(|e| e.to_string())?,
+                    align,
+                )),
+                _ => Err("#[passedbyvalue] array lengths must be integer literals".to_string()),
+            }
+        }
+        _ => Err(format!(
+            "#[passedbyvalue] types can't contain a {}",
+            quote! {#ty}
+        )),
+    }
+}
+
+fn round_up(offset: usize, align: usize) -> usize {
+    (offset + align - 1) / align * align
+}
+
+/// The signature of `pgx_build_info()`, which `pg_build_info!()` creates and `cargo pgx schema`
+/// names `<extname>_build_info` in SQL.
+///
+/// `cargo pgx schema` only reads the extension's source, and never sees what the macro expands
+/// to, so both generate the function from this, and its `CREATE FUNCTION` always matches
+pub const BUILD_INFO_FUNCTION: &str = "fn pgx_build_info() -> impl std::iter::Iterator<
+    Item = (
+        name!(extension_version, Option<String>),
+        name!(git_revision, Option<String>),
+        name!(profile, String),
+        name!(pgx_version, String),
+        name!(pg_version, String),
+    ),
+>";
+
+/// The signatures of `pgx_function_stats()` and `pgx_function_stats_reset()`, which
+/// `pg_function_stats!()` creates.  Shared with `cargo pgx schema` like `BUILD_INFO_FUNCTION`
+pub const FUNCTION_STATS_FUNCTIONS: [&str; 2] = [
+    "fn pgx_function_stats() -> impl std::iter::Iterator<
+        Item = (
+            name!(function_name, String),
+            name!(calls, i64),
+            name!(total_time_ms, f64),
+            name!(self_time_ms, f64),
+        ),
+    >",
+    "fn pgx_function_stats_reset()",
+];
+
+/// The signature of `pgx_supervised_workers()`, which `pg_supervised_workers!()` creates and
+/// `cargo pgx schema` names `<extname>_workers` in SQL.  Shared like `BUILD_INFO_FUNCTION`
+pub const SUPERVISED_WORKERS_FUNCTION: &str =
+    "fn pgx_supervised_workers() -> impl std::iter::Iterator<
+        Item = (
+            name!(name, String),
+            name!(pid, i32),
+            name!(state, String),
+            name!(last_heartbeat, Option<TimestampWithTimeZone>),
+            name!(restart_count, i64),
+        ),
+    >";
+
+/// The signature of `pgx_function_registry()`, which `pg_function_registry!()` creates and
+/// `cargo pgx schema` names `<extname>_functions` in SQL.  Shared like `BUILD_INFO_FUNCTION`
+pub const FUNCTION_REGISTRY_FUNCTION: &str =
+    "fn pgx_function_registry() -> impl std::iter::Iterator<
+        Item = (
+            name!(name, String),
+            name!(signature, String),
+            name!(rust_path, Option<String>),
+            name!(volatility, String),
+        ),
+    >";
+
 #[cfg(test)]
 mod tests {
-    use crate::{parse_extern_attributes, ExternArgs};
+    use crate::{
+        byval_type_layout, parse_extern_attributes, parse_pg_config_release,
+        parse_pg_config_version, parse_pg_release, ExternArgs,
+    };
     use std::str::FromStr;
     use syn::export::TokenStream2;
 
@@ -501,4 +989,109 @@ mod tests {
             "syntax error at or near \"THIS\"".to_string()
         )));
     }
+
+    #[test]
+    fn parse_sql_type_args() {
+        let s = "immutable, sql_type(relation = \"regclass\", return = \"text[]\")";
+        let ts = TokenStream2::from_str(s).unwrap();
+
+        let args = parse_extern_attributes(ts);
+        assert!(args.contains(&ExternArgs::Immutable));
+        assert!(args.contains(&ExternArgs::SqlType(
+            "relation".to_string(),
+            "regclass".to_string()
+        )));
+        assert!(args.contains(&ExternArgs::SqlType(
+            "return".to_string(),
+            "text[]".to_string()
+        )));
+        assert_eq!(args.len(), 3);
+    }
+
+    #[test]
+    fn parse_deprecated_alias_args() {
+        let s = "deprecated_alias = \"old_name\", deprecated_alias = \"older_name\"";
+        let ts = TokenStream2::from_str(s).unwrap();
+
+        let args = parse_extern_attributes(ts);
+        assert!(args.contains(&ExternArgs::DeprecatedAlias("old_name".to_string())));
+        assert!(args.contains(&ExternArgs::DeprecatedAlias("older_name".to_string())));
+    }
+
+    #[test]
+    fn parse_search_path_args() {
+        let s = "security_definer, set_search_path = \"pg_catalog, pg_temp\"";
+        let ts = TokenStream2::from_str(s).unwrap();
+
+        let args = parse_extern_attributes(ts);
+        assert!(args.contains(&ExternArgs::SecurityDefiner));
+        assert!(args.contains(&ExternArgs::SetSearchPath(
+            "pg_catalog, pg_temp".to_string()
+        )));
+        assert_eq!(args.len(), 2);
+    }
+
+    #[test]
+    fn parse_on_create_args() {
+        let s = "on_create, volatile";
+        let ts = TokenStream2::from_str(s).unwrap();
+
+        let args = parse_extern_attributes(ts);
+        assert!(args.contains(&ExternArgs::OnCreate));
+        assert!(args.contains(&ExternArgs::Volatile));
+        assert_eq!(args.len(), 2);
+    }
+
+    #[test]
+    fn pg_config_versions() {
+        assert_eq!(parse_pg_config_version("PostgreSQL 13.2"), Some(13));
+        assert_eq!(
+            parse_pg_config_version("PostgreSQL 10.14 (Ubuntu 10.14-1.pgdg20.04+1)"),
+            Some(10)
+        );
+        assert_eq!(parse_pg_config_version("PostgreSQL 15beta1"), Some(15));
+        assert_eq!(parse_pg_config_version("PostgreSQL 14devel"), Some(14));
+        assert_eq!(parse_pg_config_version("PostgreSQL"), None);
+        assert_eq!(parse_pg_config_version(""), None);
+    }
+
+    #[test]
+    fn pg_releases() {
+        assert_eq!(parse_pg_config_release("PostgreSQL 13.2"), Some("13.2"));
+        assert_eq!(
+            parse_pg_config_release("PostgreSQL 10.14 (Ubuntu 10.14-1.pgdg20.04+1)"),
+            Some("10.14")
+        );
+        assert_eq!(parse_pg_config_release("PostgreSQL"), None);
+
+        assert_eq!(parse_pg_release("12.6"), Some((12, 6)));
+        assert_eq!(parse_pg_release("15.1"), Some((15, 1)));
+        assert_eq!(parse_pg_release("15beta1"), None);
+        assert_eq!(parse_pg_release("12"), None);
+        assert_eq!(parse_pg_release("12.x"), None);
+        assert_eq!(parse_pg_release("/usr/bin/pg_config"), None);
+    }
+
+    #[test]
+    fn byval_layout() {
+        let layout = |source: &str, packed: bool| {
+            let strct: syn::ItemStruct = syn::parse_str(source).unwrap();
+            byval_type_layout(&strct.fields, packed)
+        };
+
+        assert_eq!(layout("struct A { a: i32, b: f32 }", false), Ok((8, 4)));
+        assert_eq!(layout("struct A { a: u8, b: i16 }", false), Ok((4, 2)));
+        assert_eq!(layout("struct A(i64);", false), Ok((8, 8)));
+        assert_eq!(
+            layout("struct A { a: [u8; 3], b: bool }", false),
+            Ok((4, 1))
+        );
+        assert_eq!(
+            layout("struct A { a: u8, b: i16, c: u8 }", true),
+            Ok((4, 1))
+        );
+        assert!(layout("struct A { a: u8, b: i32 }", true).is_err());
+        assert!(layout("struct A { a: String }", false).is_err());
+        assert!(layout("struct A { a: i64, b: i64 }", false).is_err());
+    }
 }
@@ -0,0 +1,219 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Advisory locks, the same ones taken by Postgres' `pg_advisory_lock()` family of functions
+//!
+//! Locks are acquired directly through the lock manager rather than by running SQL through SPI,
+//! and are held by a `PgAdvisoryLockGuard` that releases them when dropped.
+//!
+//! A blocking `PgAdvisoryLock::lock()` waits in the lock manager like any other lock, so it
+//! participates in Postgres' deadlock detection:  if waiting would deadlock, one of the waiting
+//! transactions is aborted with a "deadlock detected" ERROR rather than hanging forever.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use std::time::Duration;
+//!
+//! // only one backend at a time may sync with the external service
+//! match PgAdvisoryLock::session(42).lock_timeout(Duration::from_secs(5)) {
+//!     Some(_guard) => { /* do the work, the lock is released at the end of this scope */ }
+//!     None => warning!("another backend is already syncing"),
+//! }
+//! ```
+use crate::pg_sys;
+use std::time::{Duration, Instant};
+
+/// The key identifying an advisory lock.  Postgres keeps the single-`i64` and pair-of-`i32`
+/// key spaces separate, so `BigInt(1)` and `Pair(0, 1)` are different locks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PgAdvisoryLockKey {
+    /// A key like `pg_advisory_lock(bigint)` takes
+    BigInt(i64),
+
+    /// A key like `pg_advisory_lock(int, int)` takes
+    Pair(i32, i32),
+}
+
+impl From<i64> for PgAdvisoryLockKey {
+    fn from(key: i64) -> Self {
+        PgAdvisoryLockKey::BigInt(key)
+    }
+}
+
+impl From<(i32, i32)> for PgAdvisoryLockKey {
+    fn from(key: (i32, i32)) -> Self {
+        PgAdvisoryLockKey::Pair(key.0, key.1)
+    }
+}
+
+/// An advisory lock that hasn't yet been acquired
+#[derive(Clone, Copy, Debug)]
+pub struct PgAdvisoryLock {
+    key: PgAdvisoryLockKey,
+    shared: bool,
+    session: bool,
+}
+
+/// An acquired advisory lock
+///
+/// Session-level locks are released when the guard is dropped.  Transaction-level locks can't be
+/// released early (just like `pg_advisory_xact_lock()`), so dropping their guard does nothing and
+/// the lock is released when the transaction ends
+#[derive(Debug)]
+pub struct PgAdvisoryLockGuard {
+    lock: PgAdvisoryLock,
+}
+
+impl PgAdvisoryLock {
+    /// A session-level lock, like `pg_advisory_lock()`.  It's held until its guard is dropped,
+    /// regardless of whether the transaction that acquired it commits or aborts
+    pub fn session<K: Into<PgAdvisoryLockKey>>(key: K) -> Self {
+        PgAdvisoryLock {
+            key: key.into(),
+            shared: false,
+            session: true,
+        }
+    }
+
+    /// A transaction-level lock, like `pg_advisory_xact_lock()`.  It's held until the end of the
+    /// current transaction
+    pub fn transaction<K: Into<PgAdvisoryLockKey>>(key: K) -> Self {
+        PgAdvisoryLock {
+            key: key.into(),
+            shared: false,
+            session: false,
+        }
+    }
+
+    /// Acquire the lock in shared mode, like `pg_advisory_lock_shared()`.  Any number of shared
+    /// holders may hold the lock at once, but they conflict with exclusive holders
+    pub fn shared(mut self) -> Self {
+        self.shared = true;
+        self
+    }
+
+    /// The lock's key
+    pub fn key(&self) -> PgAdvisoryLockKey {
+        self.key
+    }
+
+    /// Wait for the lock, however long it takes.  Raises an ERROR if waiting would deadlock, or if
+    /// the wait exceeds `lock_timeout`
+    pub fn lock(self) -> PgAdvisoryLockGuard {
+        self.acquire(false);
+        PgAdvisoryLockGuard { lock: self }
+    }
+
+    /// Acquire the lock only if it's available immediately, like `pg_try_advisory_lock()`
+    pub fn try_lock(self) -> Option<PgAdvisoryLockGuard> {
+        if self.acquire(true) {
+            Some(PgAdvisoryLockGuard { lock: self })
+        } else {
+            None
+        }
+    }
+
+    /// Keep trying to acquire the lock until `timeout` has elapsed, returning `None` if it never
+    /// became available.  Interrupts, such as a query cancel, are honored while waiting
+    pub fn lock_timeout(self, timeout: Duration) -> Option<PgAdvisoryLockGuard> {
+        let deadline = Instant::now() + timeout;
+        let mut delay = Duration::from_millis(1);
+
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return Some(guard);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+
+            // back off, up to 100ms between attempts, but never sleep past the deadline
+            let sleep = std::cmp::min(delay, deadline - now);
+            sleep_on_latch(sleep);
+            delay = std::cmp::min(delay * 2, Duration::from_millis(100));
+        }
+    }
+
+    fn acquire(&self, dont_wait: bool) -> bool {
+        let tag = self.locktag();
+        let result = unsafe { pg_sys::LockAcquire(&tag, self.lockmode(), self.session, dont_wait) };
+        result != pg_sys::LockAcquireResult_LOCKACQUIRE_NOT_AVAIL
+    }
+
+    fn release(&self) {
+        let tag = self.locktag();
+        unsafe {
+            pg_sys::LockRelease(&tag, self.lockmode(), self.session);
+        }
+    }
+
+    fn lockmode(&self) -> pg_sys::LOCKMODE {
+        if self.shared {
+            pg_sys::ShareLock as pg_sys::LOCKMODE
+        } else {
+            pg_sys::ExclusiveLock as pg_sys::LOCKMODE
+        }
+    }
+
+    /// Build the same `LOCKTAG` as Postgres' `SET_LOCKTAG_INT64()` and `SET_LOCKTAG_INT32()`
+    /// macros, so these locks interoperate with the SQL-level functions and show up in `pg_locks`
+    fn locktag(&self) -> pg_sys::LOCKTAG {
+        let (field2, field3, field4) = match self.key {
+            PgAdvisoryLockKey::BigInt(key) => ((key >> 32) as u32, key as u32, 1),
+            PgAdvisoryLockKey::Pair(key1, key2) => (key1 as u32, key2 as u32, 2),
+        };
+
+        pg_sys::LOCKTAG {
+            locktag_field1: unsafe { pg_sys::MyDatabaseId },
+            locktag_field2: field2,
+            locktag_field3: field3,
+            locktag_field4: field4,
+            locktag_type: pg_sys::LockTagType_LOCKTAG_ADVISORY as u8,
+            locktag_lockmethodid: pg_sys::USER_LOCKMETHOD as u8,
+        }
+    }
+}
+
+impl PgAdvisoryLockGuard {
+    /// The key of the lock this guard holds
+    pub fn key(&self) -> PgAdvisoryLockKey {
+        self.lock.key
+    }
+
+    /// Release the lock now.  This is the same as dropping the guard
+    pub fn unlock(self) {
+        // `Drop` does the work
+    }
+}
+
+impl Drop for PgAdvisoryLockGuard {
+    fn drop(&mut self) {
+        if self.lock.session {
+            // if the lock somehow isn't held (perhaps `pg_advisory_unlock_all()` released it),
+            // the lock manager issues its own WARNING
+            self.lock.release();
+        }
+    }
+}
+
+/// Sleep on `MyLatch` for up to `duration`, servicing interrupts and exiting if the postmaster dies
+pub(crate) fn sleep_on_latch(duration: Duration) {
+    unsafe {
+        let rc = pg_sys::WaitLatch(
+            pg_sys::MyLatch,
+            (pg_sys::WL_LATCH_SET | pg_sys::WL_TIMEOUT | pg_sys::WL_POSTMASTER_DEATH) as i32,
+            duration.as_millis() as i64,
+            pg_sys::PG_WAIT_EXTENSION,
+        );
+        pg_sys::ResetLatch(pg_sys::MyLatch);
+
+        if rc & pg_sys::WL_POSTMASTER_DEATH as i32 != 0 {
+            pg_sys::proc_exit(1);
+        }
+    }
+    check_for_interrupts!();
+}
@@ -0,0 +1,161 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Typed accessors for the current backend's session and transaction state
+//!
+//! These read the same values as `pg_backend_pid()`, `current_database()`, `current_user`,
+//! `inet_client_addr()`, and friends, but straight from the backend's globals, so they're cheap
+//! enough to call from hot paths and don't need SPI.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! #[pg_extern]
+//! fn whoami() -> String {
+//!     format!(
+//!         "pid {} connected to {} as {} from {}",
+//!         backend::pid(),
+//!         backend::database_name(),
+//!         backend::user_name(),
+//!         backend::client_addr().unwrap_or_else(|| "the local socket".to_string())
+//!     )
+//! }
+//! ```
+use crate::{direct_function_call, pg_sys, void_mut_ptr, Inet};
+use std::ffi::CStr;
+
+/// A transaction isolation level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PgIsolationLevel {
+    ReadUncommitted,
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl PgIsolationLevel {
+    fn from_pg(level: i32) -> Self {
+        match level as u32 {
+            pg_sys::XACT_READ_UNCOMMITTED => PgIsolationLevel::ReadUncommitted,
+            pg_sys::XACT_READ_COMMITTED => PgIsolationLevel::ReadCommitted,
+            pg_sys::XACT_REPEATABLE_READ => PgIsolationLevel::RepeatableRead,
+            pg_sys::XACT_SERIALIZABLE => PgIsolationLevel::Serializable,
+            _ => panic!("unrecognized transaction isolation level: {}", level),
+        }
+    }
+
+    /// The level's name, as `SHOW transaction_isolation` spells it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PgIsolationLevel::ReadUncommitted => "read uncommitted",
+            PgIsolationLevel::ReadCommitted => "read committed",
+            PgIsolationLevel::RepeatableRead => "repeatable read",
+            PgIsolationLevel::Serializable => "serializable",
+        }
+    }
+}
+
+/// The process ID of this backend, like `pg_backend_pid()`
+pub fn pid() -> i32 {
+    unsafe { pg_sys::MyProcPid }
+}
+
+/// The Oid of the database this backend is connected to
+pub fn database_oid() -> pg_sys::Oid {
+    unsafe { pg_sys::MyDatabaseId }
+}
+
+/// The name of the database this backend is connected to, like `current_database()`
+pub fn database_name() -> String {
+    let name = unsafe { pg_sys::get_database_name(database_oid()) };
+    if name.is_null() {
+        panic!("database {} does not exist", database_oid());
+    }
+    take_pg_string(name)
+}
+
+/// The Oid of the role privileges are currently checked against, like `current_user`.  This
+/// changes inside `SECURITY DEFINER` functions and after `SET ROLE`
+pub fn user_oid() -> pg_sys::Oid {
+    unsafe { pg_sys::GetUserId() }
+}
+
+/// The name of the role privileges are currently checked against, like `current_user`
+pub fn user_name() -> String {
+    take_pg_string(unsafe { pg_sys::GetUserNameFromId(user_oid(), false) })
+}
+
+/// The Oid of the role the session is running as, like `session_user`
+pub fn session_user_oid() -> pg_sys::Oid {
+    unsafe { pg_sys::GetSessionUserId() }
+}
+
+/// The name of the role the session is running as, like `session_user`
+pub fn session_user_name() -> String {
+    take_pg_string(unsafe { pg_sys::GetUserNameFromId(session_user_oid(), false) })
+}
+
+/// The session's `application_name`, if the client has set one
+pub fn application_name() -> Option<String> {
+    unsafe {
+        if pg_sys::application_name.is_null() || *pg_sys::application_name == 0 {
+            None
+        } else {
+            Some(
+                CStr::from_ptr(pg_sys::application_name)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+}
+
+/// The address of the connected client, like `inet_client_addr()`.  `None` for clients connected
+/// through a Unix socket, and for backends without a client, such as background workers
+pub fn client_addr() -> Option<String> {
+    unsafe { direct_function_call::<Inet>(pg_sys::inet_client_addr, vec![]) }.map(|inet| inet.0)
+}
+
+/// The port of the connected client, like `inet_client_port()`.  `None` whenever
+/// `client_addr()` is
+pub fn client_port() -> Option<i32> {
+    unsafe { direct_function_call::<i32>(pg_sys::inet_client_port, vec![]) }
+}
+
+/// Is a transaction in progress?  This is true while any statement is running, and only false
+/// between statements outside of a transaction block, or in a background worker that hasn't
+/// started one
+pub fn in_transaction() -> bool {
+    unsafe { pg_sys::IsTransactionState() }
+}
+
+/// Is an explicit transaction block, started by `BEGIN`, in progress?
+pub fn in_transaction_block() -> bool {
+    unsafe { pg_sys::IsTransactionBlock() }
+}
+
+/// Is the current transaction read-only?  It is by default on a hot standby, or if it was
+/// started with `BEGIN READ ONLY`
+pub fn is_read_only() -> bool {
+    unsafe { pg_sys::XactReadOnly }
+}
+
+/// The isolation level of the current transaction
+pub fn isolation_level() -> PgIsolationLevel {
+    PgIsolationLevel::from_pg(unsafe { pg_sys::XactIsoLevel })
+}
+
+/// The isolation level new transactions start with, from `default_transaction_isolation`
+pub fn default_isolation_level() -> PgIsolationLevel {
+    PgIsolationLevel::from_pg(unsafe { pg_sys::DefaultXactIsoLevel })
+}
+
+fn take_pg_string(ptr: *mut std::os::raw::c_char) -> String {
+    let string = unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned();
+    unsafe { pg_sys::pfree(ptr as void_mut_ptr) };
+    string
+}
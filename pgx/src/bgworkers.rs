@@ -4,17 +4,27 @@
 //! Safely create Postgres Background Workers, including with full SPI support
 //!
 //! See: [https://www.postgresql.org/docs/12/bgworker.html](https://www.postgresql.org/docs/12/bgworker.html)
-use crate::pg_sys;
+use crate::{pg_sys, FromDatum, TimestampWithTimeZone};
 use std::convert::TryInto;
 use std::ffi::CStr;
 use std::ffi::CString;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicI64, AtomicU32, AtomicU8, Ordering};
 use std::time::Duration;
 
 pub static mut PREV_SHMEM_STARTUP_HOOK: Option<unsafe extern "C" fn()> = None;
 static GOT_SIGHUP: AtomicBool = AtomicBool::new(false);
 static GOT_SIGTERM: AtomicBool = AtomicBool::new(false);
 
+/// The maximum number of supervised workers a single extension can register
+pub const MAX_SUPERVISED_WORKERS: usize = 64;
+const SUPERVISED_WORKER_NAME_LEN: usize = 96;
+
+static mut SUPERVISED_WORKERS: Vec<SupervisedWorker> = Vec::new();
+static mut PREV_SUPERVISION_SHMEM_STARTUP_HOOK: Option<unsafe extern "C" fn()> = None;
+static mut SUPERVISION_TABLE: *mut SupervisionTable = std::ptr::null_mut();
+static mut LOOKED_FOR_SUPERVISION_SLOT: bool = false;
+static mut MY_SUPERVISION_SLOT: Option<usize> = None;
+
 bitflags! {
     struct BGWflags: i32 {
         const BGWORKER_SHMEM_ACCESS                = pg_sys::BGWORKER_SHMEM_ACCESS as i32;
@@ -39,7 +49,7 @@ bitflags! {
         const WL_POSTMASTER_DEATH  = pg_sys::WL_POSTMASTER_DEATH as i32;
         const WL_SOCKET_CONNECTED  = pg_sys::WL_SOCKET_WRITEABLE as i32;
         const WL_SOCKET_MASK       = (pg_sys::WL_SOCKET_READABLE | pg_sys::WL_SOCKET_WRITEABLE | pg_sys::WL_SOCKET_CONNECTED) as i32;
-        #[cfg(any(feature = "pg12"))]
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
         const WL_EXIT_ON_PM_DEATH  = pg_sys::WL_EXIT_ON_PM_DEATH  as i32;
 
     }
@@ -62,7 +72,13 @@ impl BackgroundWorker {
     pub fn get_name() -> &'static str {
         #[cfg(feature = "pg10")]
         const LEN: usize = 64;
-        #[cfg(any(feature = "pg11", feature = "pg12"))]
+        #[cfg(any(
+            feature = "pg11",
+            feature = "pg12",
+            feature = "pg13",
+            feature = "pg14",
+            feature = "pg15"
+        ))]
         const LEN: usize = 96;
 
         unsafe {
@@ -103,6 +119,7 @@ impl BackgroundWorker {
     ///
     /// Returns true if we're still supposed to be alive and haven't received a SIGTERM
     pub fn wait_latch(timeout: Option<Duration>) -> bool {
+        set_my_state(BackgroundWorkerState::Waiting);
         match timeout {
             Some(t) => wait_latch(
                 t.as_millis().try_into().unwrap(),
@@ -110,7 +127,72 @@ impl BackgroundWorker {
             ),
             None => wait_latch(0, WLflags::WL_LATCH_SET | WLflags::WL_POSTMASTER_DEATH),
         };
-        !BackgroundWorker::sigterm_received()
+        let keep_running = !BackgroundWorker::sigterm_received();
+        if keep_running {
+            BackgroundWorker::heartbeat();
+        }
+        keep_running
+    }
+
+    /// Record that this worker is alive and making progress.
+    ///
+    /// `wait_latch()` does this automatically each time it wakes up, so workers only need to call
+    /// this directly if they perform long-running work between waits.  It does nothing unless the
+    /// worker was registered with `BackgroundWorkerBuilder::enable_supervision()`
+    pub fn heartbeat() {
+        if let Some(slot) = my_slot() {
+            slot.last_heartbeat
+                .store(unsafe { pg_sys::GetCurrentTimestamp() }, Ordering::SeqCst);
+            slot.state
+                .store(BackgroundWorkerState::Running as u32, Ordering::SeqCst);
+        }
+    }
+
+    /// Report the status of every background worker this extension registered with
+    /// `BackgroundWorkerBuilder::enable_supervision()`, in the order they were registered.  Those
+    /// that haven't started yet are `Stopped`, with a `pid` of zero.
+    ///
+    /// This can be called from any backend.  The `pg_supervised_workers!()` macro creates an
+    /// `<extname>_workers()` SQL function that returns it, so operators can monitor an extension's
+    /// workers from SQL:
+    ///
+    /// ```rust,no_run
+    /// use pgx::*;
+    ///
+    /// pg_module_magic!();
+    /// pg_supervised_workers!();
+    /// ```
+    ///
+    /// ```sql
+    /// SELECT * FROM my_extension_workers();
+    /// ```
+    pub fn supervised_workers() -> Vec<BackgroundWorkerStatus> {
+        let table = match unsafe { SUPERVISION_TABLE.as_ref() } {
+            Some(table) => table,
+            None => return Vec::new(),
+        };
+
+        table
+            .slots
+            .iter()
+            .take(unsafe { SUPERVISED_WORKERS.len() })
+            .map(|slot| {
+                let heartbeat = slot.last_heartbeat.load(Ordering::SeqCst);
+                BackgroundWorkerStatus {
+                    name: slot.name_as_string(),
+                    pid: slot.pid.load(Ordering::SeqCst),
+                    state: BackgroundWorkerState::from(slot.state.load(Ordering::SeqCst)),
+                    last_heartbeat: unsafe {
+                        TimestampWithTimeZone::from_datum(
+                            heartbeat as pg_sys::Datum,
+                            heartbeat == 0,
+                            pg_sys::TIMESTAMPTZOID,
+                        )
+                    },
+                    restart_count: slot.restart_count.load(Ordering::SeqCst),
+                }
+            })
+            .collect()
     }
 
     /// Is this `BackgroundWorker` allowed to continue?
@@ -131,7 +213,13 @@ impl BackgroundWorker {
             #[cfg(feature = "pg10")]
             pg_sys::BackgroundWorkerInitializeConnection(db as *mut i8, user as *mut i8);
 
-            #[cfg(any(feature = "pg11", feature = "pg12"))]
+            #[cfg(any(
+                feature = "pg11",
+                feature = "pg12",
+                feature = "pg13",
+                feature = "pg14",
+                feature = "pg15"
+            ))]
             pg_sys::BackgroundWorkerInitializeConnection(db, user, 0);
         };
     }
@@ -144,7 +232,11 @@ impl BackgroundWorker {
     /// use pgx::bgworkers::{BackgroundWorker, SignalWakeFlags};
     /// BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGHUP | SignalWakeFlags::SIGTERM);
     /// ```
+    ///
+    /// If the worker was registered with `BackgroundWorkerBuilder::enable_supervision()`, this is
+    /// also where it first reports itself as `Starting`
     pub fn attach_signal_handlers(wake: SignalWakeFlags) {
+        my_slot();
         unsafe {
             if wake.contains(SignalWakeFlags::SIGHUP) {
                 pg_sys::pqsignal(pg_sys::SIGHUP as i32, Some(worker_spi_sighup));
@@ -157,20 +249,238 @@ impl BackgroundWorker {
     }
 
     /// Once connected to SPI via `connect_worker_to_spi()`, begin a transaction to
-    /// use the `pgx::Spi` interface.
-    pub fn transaction<F: FnOnce() + std::panic::UnwindSafe + std::panic::RefUnwindSafe>(
+    /// use the `pgx::Spi` interface.  Returns whatever `transaction_body` returns, once the
+    /// transaction has committed
+    pub fn transaction<R, F: FnOnce() -> R + std::panic::UnwindSafe + std::panic::RefUnwindSafe>(
         transaction_body: F,
-    ) {
+    ) -> R {
         unsafe {
             pg_sys::SetCurrentStatementStartTimestamp();
             pg_sys::StartTransactionCommand();
             pg_sys::PushActiveSnapshot(pg_sys::GetTransactionSnapshot());
         }
-        pg_sys::guard(|| transaction_body());
+        let result = pg_sys::guard(|| transaction_body());
         unsafe {
             pg_sys::PopActiveSnapshot();
             pg_sys::CommitTransactionCommand();
         }
+        result
+    }
+}
+
+/// The lifecycle state of a supervised background worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundWorkerState {
+    Starting = 1,
+    Running = 2,
+    Waiting = 3,
+    Stopped = 4,
+}
+
+impl BackgroundWorkerState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackgroundWorkerState::Starting => "starting",
+            BackgroundWorkerState::Running => "running",
+            BackgroundWorkerState::Waiting => "waiting",
+            BackgroundWorkerState::Stopped => "stopped",
+        }
+    }
+}
+
+impl From<u32> for BackgroundWorkerState {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => BackgroundWorkerState::Starting,
+            2 => BackgroundWorkerState::Running,
+            3 => BackgroundWorkerState::Waiting,
+            _ => BackgroundWorkerState::Stopped,
+        }
+    }
+}
+
+/// A point-in-time snapshot of a supervised background worker, as returned by
+/// `BackgroundWorker::supervised_workers()`
+#[derive(Debug)]
+pub struct BackgroundWorkerStatus {
+    pub name: String,
+    pub pid: i32,
+    pub state: BackgroundWorkerState,
+    pub last_heartbeat: Option<TimestampWithTimeZone>,
+    pub restart_count: i64,
+}
+
+/// How a supervised worker was registered, to recognize it by once it's running.  Its index in
+/// `SUPERVISED_WORKERS` is the index of its slot in the `SupervisionTable`
+struct SupervisedWorker {
+    name: Vec<i8>,
+    library: Vec<i8>,
+    function: Vec<i8>,
+    main_arg: pg_sys::Datum,
+    extra: Vec<i8>,
+}
+
+impl SupervisedWorker {
+    fn new(bgw: &pg_sys::BackgroundWorker) -> Self {
+        SupervisedWorker {
+            name: bgw.bgw_name.to_vec(),
+            library: bgw.bgw_library_name.to_vec(),
+            function: bgw.bgw_function_name.to_vec(),
+            main_arg: bgw.bgw_main_arg,
+            extra: bgw.bgw_extra.to_vec(),
+        }
+    }
+
+    /// Was `entry`, the worker Postgres started, registered like this?
+    fn is(&self, entry: &pg_sys::BackgroundWorker) -> bool {
+        self.name[..] == entry.bgw_name[..]
+            && self.library[..] == entry.bgw_library_name[..]
+            && self.function[..] == entry.bgw_function_name[..]
+            && self.main_arg == entry.bgw_main_arg
+            && self.extra[..] == entry.bgw_extra[..]
+    }
+}
+
+/// A NUL-terminated `char` array from a `pg_sys::BackgroundWorker`, as a `String`
+fn chars_to_string(chars: &[i8]) -> String {
+    let bytes = chars
+        .iter()
+        .take_while(|c| **c != 0)
+        .map(|c| *c as u8)
+        .collect::<Vec<_>>();
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// One supervised worker's entry in shared memory.  Everything is atomic so that the table can be
+/// read by any backend without taking a lock.  `name` is only written when the table is created
+#[repr(C)]
+struct SupervisionSlot {
+    pid: AtomicI32,
+    state: AtomicU32,
+    last_heartbeat: AtomicI64,
+    restart_count: AtomicI64,
+    name: [AtomicU8; SUPERVISED_WORKER_NAME_LEN],
+}
+
+impl SupervisionSlot {
+    fn name_as_bytes(&self) -> Vec<u8> {
+        self.name
+            .iter()
+            .map(|b| b.load(Ordering::SeqCst))
+            .take_while(|b| *b != 0)
+            .collect()
+    }
+
+    fn name_as_string(&self) -> String {
+        String::from_utf8_lossy(&self.name_as_bytes()).to_string()
+    }
+
+    fn set_name(&self, name: &str) {
+        let mut bytes = name.as_bytes().iter().take(SUPERVISED_WORKER_NAME_LEN - 1);
+        for dest in self.name.iter() {
+            dest.store(*bytes.next().unwrap_or(&0), Ordering::SeqCst);
+        }
+    }
+}
+
+#[repr(C)]
+struct SupervisionTable {
+    slots: [SupervisionSlot; MAX_SUPERVISED_WORKERS],
+}
+
+unsafe extern "C" fn supervision_shmem_startup() {
+    if let Some(prev) = PREV_SUPERVISION_SHMEM_STARTUP_HOOK {
+        prev();
+    }
+
+    // slots are numbered by the order this extension registered its workers in, so every
+    // extension needs a table of its own.  Postgres only keeps the first 47 bytes of the name
+    let mut found = false;
+    let name = CString::new(format!(
+        "pgx workers {}",
+        chars_to_string(&SUPERVISED_WORKERS[0].library)
+    ))
+    .unwrap();
+
+    let addin_shmem_init_lock = crate::shmem::addin_shmem_init_lock();
+    pg_sys::LWLockAcquire(addin_shmem_init_lock, pg_sys::LWLockMode_LW_EXCLUSIVE);
+    let table = pg_sys::ShmemInitStruct(
+        name.as_ptr(),
+        std::mem::size_of::<SupervisionTable>(),
+        &mut found,
+    ) as *mut SupervisionTable;
+    if !found {
+        // shared memory is not zeroed for us, and all-zeros is a table of stopped workers that
+        // have never run
+        std::ptr::write_bytes(table, 0, 1);
+        for (slot, worker) in (*table).slots.iter().zip(SUPERVISED_WORKERS.iter()) {
+            slot.set_name(&chars_to_string(&worker.name));
+            slot.state
+                .store(BackgroundWorkerState::Stopped as u32, Ordering::SeqCst);
+        }
+    }
+    pg_sys::LWLockRelease(addin_shmem_init_lock);
+
+    SUPERVISION_TABLE = table;
+}
+
+/// Find (or claim) the supervision slot for the currently-running background worker, if it was
+/// registered with `BackgroundWorkerBuilder::enable_supervision()`
+fn my_slot() -> Option<&'static SupervisionSlot> {
+    let table = unsafe { SUPERVISION_TABLE.as_ref() }?;
+
+    if unsafe { LOOKED_FOR_SUPERVISION_SLOT } {
+        return unsafe { MY_SUPERVISION_SLOT }.map(|idx| &table.slots[idx]);
+    }
+    unsafe {
+        LOOKED_FOR_SUPERVISION_SLOT = true;
+    }
+
+    // `None` if we're not a background worker
+    let entry = unsafe { pg_sys::MyBgworkerEntry.as_ref() }?;
+    let pid = unsafe { pg_sys::MyProcPid };
+
+    // a worker registered more than once with the same settings takes whichever of its slots
+    // isn't in use
+    let idx = unsafe { SUPERVISED_WORKERS.iter() }
+        .enumerate()
+        .position(|(idx, worker)| {
+            worker.is(entry)
+                && table.slots[idx]
+                    .pid
+                    .compare_exchange(0, pid, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+        })?;
+
+    let slot = &table.slots[idx];
+    if slot.last_heartbeat.load(Ordering::SeqCst) != 0 {
+        // we've run before, so we're being restarted
+        slot.restart_count.fetch_add(1, Ordering::SeqCst);
+    }
+    slot.last_heartbeat
+        .store(unsafe { pg_sys::GetCurrentTimestamp() }, Ordering::SeqCst);
+    slot.state
+        .store(BackgroundWorkerState::Starting as u32, Ordering::SeqCst);
+
+    unsafe {
+        MY_SUPERVISION_SLOT = Some(idx);
+        pg_sys::before_shmem_exit(Some(supervision_on_exit), 0);
+    }
+
+    Some(slot)
+}
+
+fn set_my_state(state: BackgroundWorkerState) {
+    if let Some(slot) = my_slot() {
+        slot.state.store(state as u32, Ordering::SeqCst);
+    }
+}
+
+unsafe extern "C" fn supervision_on_exit(_code: i32, _arg: pg_sys::Datum) {
+    if let Some(slot) = my_slot() {
+        slot.state
+            .store(BackgroundWorkerState::Stopped as u32, Ordering::SeqCst);
+        slot.pid.store(0, Ordering::SeqCst);
     }
 }
 
@@ -224,6 +534,7 @@ pub struct BackgroundWorkerBuilder {
     bgw_extra: String,
     bgw_notify_pid: pg_sys::pid_t,
     shared_memory_startup_fn: Option<unsafe extern "C" fn()>,
+    supervised: bool,
 }
 
 impl BackgroundWorkerBuilder {
@@ -246,6 +557,7 @@ impl BackgroundWorkerBuilder {
             bgw_extra: "".to_string(),
             bgw_notify_pid: 0,
             shared_memory_startup_fn: None,
+            supervised: false,
         }
     }
 
@@ -274,6 +586,17 @@ impl BackgroundWorkerBuilder {
         self
     }
 
+    /// Should this BackgroundWorker report its pid, state, heartbeat, and restart count to
+    /// shared memory?
+    ///
+    /// Supervised workers are listed by `BackgroundWorker::supervised_workers()`.  Like all
+    /// shared memory, this requires that the extension be loaded via `shared_preload_libraries`
+    pub fn enable_supervision(mut self: Self) -> Self {
+        self.bgw_flags = self.bgw_flags | BGWflags::BGWORKER_SHMEM_ACCESS;
+        self.supervised = true;
+        self
+    }
+
     /// When should this BackgroundWorker be started by Postgres?
     pub fn set_start_time(mut self: Self, input: BgWorkerStartTime) -> Self {
         self.bgw_start_time = input;
@@ -390,7 +713,13 @@ impl BackgroundWorkerBuilder {
             bgw_notify_pid: self.bgw_notify_pid,
         };
 
-        #[cfg(any(feature = "pg11", feature = "pg12"))]
+        #[cfg(any(
+            feature = "pg11",
+            feature = "pg12",
+            feature = "pg13",
+            feature = "pg14",
+            feature = "pg15"
+        ))]
         let mut bgw = pg_sys::BackgroundWorker {
             bgw_name: RpgffiChar::from(&self.bgw_name[..]).0,
             bgw_type: RpgffiChar::from(&self.bgw_type[..]).0,
@@ -415,10 +744,34 @@ impl BackgroundWorkerBuilder {
                 PREV_SHMEM_STARTUP_HOOK = pg_sys::shmem_startup_hook;
                 pg_sys::shmem_startup_hook = self.shared_memory_startup_fn;
             }
+
+            if self.supervised {
+                supervise(&bgw, &self.bgw_name);
+            }
         };
     }
 }
 
+/// Give the worker `bgw` the next slot in the supervision table, and ask for the table itself when
+/// it's the first
+unsafe fn supervise(bgw: &pg_sys::BackgroundWorker, name: &str) {
+    if SUPERVISED_WORKERS.len() == MAX_SUPERVISED_WORKERS {
+        warning!(
+            "background worker \"{}\" won't be supervised, as the maximum is {}",
+            name,
+            MAX_SUPERVISED_WORKERS
+        );
+        return;
+    }
+
+    if SUPERVISED_WORKERS.is_empty() {
+        crate::shmem::request_shmem(std::mem::size_of::<SupervisionTable>(), None);
+        PREV_SUPERVISION_SHMEM_STARTUP_HOOK = pg_sys::shmem_startup_hook;
+        pg_sys::shmem_startup_hook = Some(supervision_shmem_startup);
+    }
+    SUPERVISED_WORKERS.push(SupervisedWorker::new(bgw));
+}
+
 fn wait_latch(timeout: i64, wakeup_flags: WLflags) -> i32 {
     unsafe {
         let latch = pg_sys::WaitLatch(
@@ -437,7 +790,13 @@ fn wait_latch(timeout: i64, wakeup_flags: WLflags) -> i32 {
 #[cfg(feature = "pg10")]
 type RpgffiChar = RpgffiChar64;
 
-#[cfg(any(feature = "pg11", feature = "pg12"))]
+#[cfg(any(
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15"
+))]
 type RpgffiChar = RpgffiChar96;
 
 struct RpgffiChar64([i8; 64]);
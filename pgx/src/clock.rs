@@ -0,0 +1,101 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! A clock that tests can stop and move, so time-dependent logic is testable deterministically
+//!
+//! Extension code that asks `pgx::clock::now()`, rather than `SystemTime::now()` or Postgres'
+//! `GetCurrentTimestamp()`, for the time gets the real time by default.  A test can instead pin
+//! the clock to a known time with `clock::set()`, and then `clock::advance()` it, to check that
+//! a TTL cache expires its entries or a scheduler fires at the right moment without sleeping.
+//! The real clock is restored when the `ClockOverride` that `set()` returns is dropped.
+//!
+//! The override only applies to the current thread, which for a backend is the whole session,
+//! and as each `#[pg_test]` runs in a session of its own, tests can't see each other's clocks.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use std::time::{Duration, SystemTime};
+//!
+//! fn is_expired(created: SystemTime, ttl: Duration) -> bool {
+//!     clock::now() >= created + ttl
+//! }
+//!
+//! #[pg_test]
+//! fn test_expiry() {
+//!     let _clock = clock::set(SystemTime::UNIX_EPOCH);
+//!     let created = clock::now();
+//!     assert!(!is_expired(created, Duration::from_secs(60)));
+//!
+//!     clock::advance(Duration::from_secs(60));
+//!     assert!(is_expired(created, Duration::from_secs(60)));
+//! }
+//! ```
+use crate::pg_sys;
+use std::cell::Cell;
+use std::time::{Duration, SystemTime};
+
+/// Seconds from the Unix epoch to Postgres' epoch, 2000-01-01 00:00:00 UTC
+const POSTGRES_EPOCH_UNIX_SECS: u64 = 946_684_800;
+
+thread_local! {
+    static OVERRIDE: Cell<Option<SystemTime>> = Cell::new(None);
+}
+
+/// The current time, or the time the clock was `set()` to
+pub fn now() -> SystemTime {
+    OVERRIDE
+        .with(|time| time.get())
+        .unwrap_or_else(SystemTime::now)
+}
+
+/// `now()` as a Postgres `timestamptz`, for passing to Postgres functions in place of
+/// `GetCurrentTimestamp()`
+pub fn current_timestamp() -> pg_sys::TimestampTz {
+    let postgres_epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(POSTGRES_EPOCH_UNIX_SECS);
+    match now().duration_since(postgres_epoch) {
+        Ok(since) => since.as_micros() as pg_sys::TimestampTz,
+        Err(e) => -(e.duration().as_micros() as pg_sys::TimestampTz),
+    }
+}
+
+/// Is the clock pinned by a `set()`?
+pub fn is_overridden() -> bool {
+    OVERRIDE.with(|time| time.get().is_some())
+}
+
+/// Pin the clock to `time` until the returned `ClockOverride` is dropped
+pub fn set(time: SystemTime) -> ClockOverride {
+    ClockOverride {
+        previous: OVERRIDE.with(|current| current.replace(Some(time))),
+    }
+}
+
+/// Pin the clock to the current time until the returned `ClockOverride` is dropped
+pub fn freeze() -> ClockOverride {
+    set(now())
+}
+
+/// Move a pinned clock forward by `by`.
+///
+/// Panics if the clock isn't pinned, as the real one can't be moved
+pub fn advance(by: Duration) {
+    OVERRIDE.with(|time| match time.get() {
+        Some(current) => time.set(Some(current + by)),
+        None => panic!("clock::advance() requires a clock pinned with clock::set()"),
+    })
+}
+
+/// Restores the clock to what it was before `set()` or `freeze()` when dropped
+#[must_use = "the clock is restored as soon as the ClockOverride is dropped"]
+pub struct ClockOverride {
+    previous: Option<SystemTime>,
+}
+
+impl Drop for ClockOverride {
+    fn drop(&mut self) {
+        let previous = self.previous;
+        OVERRIDE.with(|time| time.set(previous));
+    }
+}
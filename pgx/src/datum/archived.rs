@@ -0,0 +1,191 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Zero-copy custom types, stored in a varlena in `rkyv`'s archived format
+//!
+//! The default `#[derive(PostgresType)]` representation is CBOR, which has to be decoded into a new
+//! Rust value every time a function reads one.  Types that are mostly read can instead be
+//! `#[archived_inoutfuncs]`, and used as `PgArchived<T>`, which dereferences to `T`'s archived form
+//! directly inside the detoasted varlena, without decoding anything.
+//!
+//! This requires pgx's `rkyv` feature, and a dependency on the same version of `rkyv` for its
+//! derive macros.  The archive must also be `#[archive(check_bytes)]`:  a value read from a datum
+//! may have been written by another build of the extension, with a different layout of the type or
+//! version of `rkyv`, or be corrupt, so it's validated once before it's read, and an invalid one
+//! raises an ERROR.
+//!
+//! ## Examples
+//!
+//! ```rust,ignore
+//! use pgx::*;
+//! use rkyv::{Archive, Deserialize, Serialize};
+//! use std::ffi::CStr;
+//!
+//! #[derive(Archive, Serialize, Deserialize, PostgresType)]
+//! #[archive(check_bytes)]
+//! #[archived_inoutfuncs]
+//! pub struct Tags {
+//!     tags: Vec<String>,
+//! }
+//!
+//! impl ArchivedInOutFuncs for Tags {
+//!     fn input(input: &CStr) -> Self {
+//!         let tags = input.to_str().unwrap().split(',').map(|tag| tag.to_string());
+//!         Tags { tags: tags.collect() }
+//!     }
+//!
+//!     fn output(archived: &ArchivedTags, buffer: &mut StringInfo) {
+//!         let tags = archived.tags.iter().map(|tag| tag.as_str()).collect::<Vec<_>>();
+//!         buffer.push_str(&tags.join(","));
+//!     }
+//! }
+//!
+//! #[pg_extern]
+//! fn has_tag(tags: PgArchived<Tags>, tag: &str) -> bool {
+//!     // reads the strings right out of the varlena
+//!     tags.tags.iter().any(|t| t.as_str() == tag)
+//! }
+//! ```
+use crate::datum::postgres_type_oid;
+use crate::{
+    ereport, pg_sys, set_varsize, varsize, FromDatum, IntoDatum, PgLogLevel, PgMemoryContexts,
+    PgSqlErrorCode,
+};
+use rkyv::bytecheck::CheckBytes;
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+/// The archive starts this far into the varlena, right after its header and padding, so it's
+/// `MAXALIGN`ed just like the varlena itself is.  That's as aligned as archived data need be
+const ARCHIVE_OFFSET: usize = 8;
+
+/// A `T`, in `rkyv`'s archived format, inside a Postgres varlena
+pub struct PgArchived<T: rkyv::Archive> {
+    varlena: *mut pg_sys::varlena,
+    __marker: PhantomData<T>,
+}
+
+impl<T: rkyv::Archive> PgArchived<T> {
+    /// Archive `value` into a new varlena, allocated in the `CurrentMemoryContext`
+    pub fn new(value: &T) -> Self
+    where
+        T: rkyv::Serialize<AllocSerializer<256>>,
+    {
+        let bytes = rkyv::to_bytes::<_, 256>(value).expect("failed to archive value");
+        let size = ARCHIVE_OFFSET + bytes.len();
+        unsafe {
+            let varlena =
+                PgMemoryContexts::CurrentMemoryContext.palloc0(size) as *mut pg_sys::varlena;
+            set_varsize(varlena, size as i32);
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                (varlena as *mut u8).add(ARCHIVE_OFFSET),
+                bytes.len(),
+            );
+
+            PgArchived {
+                varlena,
+                __marker: PhantomData,
+            }
+        }
+    }
+
+    /// Wrap a known-to-be-non-null `pg_sys::Datum` of a `#[archived_inoutfuncs]` type, detoasting
+    /// it if necessary.  As `FromDatum` is implemented for `PgArchived<T>`, it's unlikely this
+    /// will need to be called directly.
+    ///
+    /// Raises an ERROR if the varlena doesn't hold a valid archived `T`.
+    ///
+    /// ## Safety
+    ///
+    /// This function is unsafe as it can't guarantee `datum` really is a varlena
+    pub unsafe fn from_datum(datum: pg_sys::Datum) -> Self
+    where
+        T::Archived: for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        let mut varlena = pg_sys::pg_detoast_datum(datum as *mut pg_sys::varlena);
+
+        // values are declared with `ALIGNMENT = double`, so this only happens if something
+        // other than Postgres handed us the datum
+        if (varlena as usize) % ARCHIVE_OFFSET != 0 {
+            let size = varsize(varlena);
+            varlena = PgMemoryContexts::CurrentMemoryContext.copy_ptr_into(varlena as *mut u8, size)
+                as *mut pg_sys::varlena;
+        }
+
+        let archived = PgArchived {
+            varlena,
+            __marker: PhantomData,
+        };
+
+        // validated once here, so that `deref()` can trust the bytes from then on
+        if let Err(e) = rkyv::check_archived_root::<T>(archived.as_bytes()) {
+            ereport(
+                PgLogLevel::ERROR,
+                PgSqlErrorCode::ERRCODE_DATA_CORRUPTED,
+                &format!("invalid archived {}: {}", std::any::type_name::<T>(), e),
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+        archived
+    }
+
+    /// The archived bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(
+                (self.varlena as *const u8).add(ARCHIVE_OFFSET),
+                varsize(self.varlena) - ARCHIVE_OFFSET,
+            )
+        }
+    }
+
+    /// Decode the archive into an owned `T`, for when it needs to be modified
+    pub fn deserialize(&self) -> T
+    where
+        T::Archived: rkyv::Deserialize<T, rkyv::Infallible>,
+    {
+        rkyv::Deserialize::deserialize(self.deref(), &mut rkyv::Infallible).unwrap()
+    }
+
+    /// The backing `*mut pg_sys::varlena`, for passing to a Postgres function
+    pub fn into_pg(self) -> *mut pg_sys::varlena {
+        self.varlena
+    }
+}
+
+impl<T: rkyv::Archive> Deref for PgArchived<T> {
+    type Target = T::Archived;
+
+    fn deref(&self) -> &Self::Target {
+        // either `new()` archived the bytes itself or `from_datum()` validated them
+        unsafe { rkyv::archived_root::<T>(self.as_bytes()) }
+    }
+}
+
+impl<T: rkyv::Archive> IntoDatum for PgArchived<T> {
+    fn into_datum(self) -> Option<pg_sys::Datum> {
+        Some(self.into_pg() as pg_sys::Datum)
+    }
+
+    fn type_oid() -> u32 {
+        postgres_type_oid::<T>()
+    }
+}
+
+impl<T: rkyv::Archive> FromDatum for PgArchived<T>
+where
+    T::Archived: for<'a> CheckBytes<DefaultValidator<'a>>,
+{
+    unsafe fn from_datum(datum: pg_sys::Datum, is_null: bool, _typoid: u32) -> Option<Self> {
+        if is_null {
+            None
+        } else {
+            Some(PgArchived::<T>::from_datum(datum))
+        }
+    }
+}
@@ -0,0 +1,36 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Conversions for `#[derive(PostgresType)]` types with the `#[passedbyvalue]` attribute, which are
+//! stored directly in a `Datum` rather than as a varlena
+//!
+//! The value's bytes are read as an unsigned integer of the same size, just like Postgres stores
+//! its own `int2`, `int4`, and `int8` values, so they're laid out on disk identically on both
+//! little- and big-endian machines.
+
+use crate::pg_sys;
+
+/// Pack `value` into a `Datum`.  `T` must be 1, 2, 4, or 8 bytes
+#[inline]
+pub unsafe fn byval_into_datum<T: Copy>(value: T) -> pg_sys::Datum {
+    let ptr = &value as *const T;
+    match std::mem::size_of::<T>() {
+        1 => std::ptr::read_unaligned(ptr as *const u8) as pg_sys::Datum,
+        2 => std::ptr::read_unaligned(ptr as *const u16) as pg_sys::Datum,
+        4 => std::ptr::read_unaligned(ptr as *const u32) as pg_sys::Datum,
+        8 => std::ptr::read_unaligned(ptr as *const u64) as pg_sys::Datum,
+        size => panic!("passed-by-value types can't be {} bytes", size),
+    }
+}
+
+/// Unpack a `T` from a `Datum` made by `byval_into_datum()`
+#[inline]
+pub unsafe fn byval_from_datum<T: Copy>(datum: pg_sys::Datum) -> T {
+    match std::mem::size_of::<T>() {
+        1 => std::ptr::read_unaligned(&(datum as u8) as *const u8 as *const T),
+        2 => std::ptr::read_unaligned(&(datum as u16) as *const u16 as *const T),
+        4 => std::ptr::read_unaligned(&(datum as u32) as *const u32 as *const T),
+        8 => std::ptr::read_unaligned(&(datum as u64) as *const u64 as *const T),
+        size => panic!("passed-by-value types can't be {} bytes", size),
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! for converting a whole column of Datums at once, rather than one value at a time
+//!
+//! Vectorized code wants a column as a dense `Vec` of values, plus a note of which rows are NULL,
+//! rather than a `Vec<Option<T>>`.  `FromDatumColumn` makes a `PgColumn<T>` of that shape from a
+//! `PgDatumColumn`, in one pass over its values and `is_null` flags, and `IntoDatumColumn` does
+//! the reverse.  `SpiTupleTable::get_column()` and `PgTuplestore::column()` read a
+//! `PgDatumColumn` out of query results and tuplestores, and `PgTuplestore::push_columns()`
+//! writes them back.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! let sum = Spi::connect(|client| {
+//!     let prices = client
+//!         .select("SELECT price FROM items", None, None)
+//!         .get_column::<f64>(1)
+//!         .expect("no price column");
+//!     Ok(Some(prices.values.iter().sum::<f64>()))
+//! });
+//! ```
+use crate::{pg_sys, FromDatum, IntoDatum};
+
+/// A column of raw `Datum`s, as Postgres stores a row's values:  a `Datum` and an `is_null` flag
+/// for each row, all of type `typoid`
+#[derive(Clone, Debug)]
+pub struct PgDatumColumn {
+    /// The value of each row.  The `Datum`s of NULL rows are meaningless
+    pub datums: Vec<pg_sys::Datum>,
+
+    /// Whether each row is NULL
+    pub nulls: Vec<bool>,
+
+    /// The type of the values
+    pub typoid: pg_sys::Oid,
+}
+
+impl PgDatumColumn {
+    /// An empty column of type `typoid`, with room for `capacity` rows
+    pub fn with_capacity(typoid: pg_sys::Oid, capacity: usize) -> Self {
+        PgDatumColumn {
+            datums: Vec::with_capacity(capacity),
+            nulls: Vec::with_capacity(capacity),
+            typoid,
+        }
+    }
+
+    /// Add a row's value, with `None` being NULL
+    pub fn push(&mut self, value: Option<pg_sys::Datum>) {
+        self.datums.push(value.unwrap_or(0));
+        self.nulls.push(value.is_none());
+    }
+
+    /// How many rows are in the column?
+    pub fn len(&self) -> usize {
+        self.datums.len()
+    }
+
+    /// Is the column empty?
+    pub fn is_empty(&self) -> bool {
+        self.datums.is_empty()
+    }
+
+    /// Convert the column into a `PgColumn<T>`.
+    ///
+    /// ## Safety
+    ///
+    /// The same as `FromDatum::from_datum()`:  the `Datum`s must really be of type `T`, and any
+    /// they point to must still be valid
+    pub unsafe fn to_column<T: FromDatumColumn>(&self) -> PgColumn<T> {
+        T::from_datum_column(self)
+    }
+}
+
+/// A column of values converted from `Datum`s, laid out for vectorized processing
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgColumn<T> {
+    /// The value of each row.  NULL rows hold `T::default()`
+    pub values: Vec<T>,
+
+    /// Whether each row is NULL, or `None` if none of them are
+    pub nulls: Option<Vec<bool>>,
+}
+
+impl<T> PgColumn<T> {
+    /// A column of `values`, none of which are NULL
+    pub fn new(values: Vec<T>) -> Self {
+        PgColumn {
+            values,
+            nulls: None,
+        }
+    }
+
+    /// How many rows are in the column?
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Is the column empty?
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Is row `i` NULL?
+    pub fn is_null(&self, i: usize) -> bool {
+        self.nulls.as_ref().map_or(false, |nulls| nulls[i])
+    }
+
+    /// How many rows are NULL?
+    pub fn null_count(&self) -> usize {
+        self.nulls
+            .as_ref()
+            .map_or(0, |nulls| nulls.iter().filter(|is_null| **is_null).count())
+    }
+
+    /// The value of row `i`, or `None` if it's NULL
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if self.is_null(i) {
+            None
+        } else {
+            self.values.get(i)
+        }
+    }
+
+    /// Iterate over the rows, with NULLs as `None`
+    pub fn iter(&self) -> impl Iterator<Item = Option<&T>> {
+        self.values
+            .iter()
+            .enumerate()
+            .map(move |(i, value)| if self.is_null(i) { None } else { Some(value) })
+    }
+
+    /// The rows as `Option`s, as `FromDatum` would have produced them one at a time
+    pub fn into_options(self) -> Vec<Option<T>> {
+        match self.nulls {
+            Some(nulls) => self
+                .values
+                .into_iter()
+                .zip(nulls)
+                .map(|(value, is_null)| if is_null { None } else { Some(value) })
+                .collect(),
+            None => self.values.into_iter().map(Some).collect(),
+        }
+    }
+}
+
+impl<T: Default> std::iter::FromIterator<Option<T>> for PgColumn<T> {
+    fn from_iter<I: IntoIterator<Item = Option<T>>>(iter: I) -> Self {
+        let mut values = Vec::new();
+        let mut nulls = Vec::new();
+        let mut has_nulls = false;
+        for value in iter {
+            nulls.push(value.is_none());
+            has_nulls |= value.is_none();
+            values.push(value.unwrap_or_default());
+        }
+
+        PgColumn {
+            values,
+            nulls: if has_nulls { Some(nulls) } else { None },
+        }
+    }
+}
+
+/// Convert a whole `PgDatumColumn` into a `PgColumn` in one pass.
+///
+/// The default implementation calls `FromDatum::from_datum()` for each row, so any `FromDatum`
+/// type with a `Default` can opt in with an empty `impl FromDatumColumn for MyType {}`.  The
+/// primitive types, which are passed by value, convert their `Datum`s directly instead.
+///
+/// If implementing this, also implement `IntoDatumColumn` for the reverse conversion
+pub trait FromDatumColumn: FromDatum + Default + Sized {
+    /// ## Safety
+    ///
+    /// The same as `FromDatum::from_datum()`, for every row of the column
+    unsafe fn from_datum_column(column: &PgDatumColumn) -> PgColumn<Self> {
+        let mut values = Vec::with_capacity(column.len());
+        let mut nulls = Vec::with_capacity(column.len());
+        let mut has_nulls = false;
+        for (datum, is_null) in column.datums.iter().zip(&column.nulls) {
+            // a value that isn't NULL can still convert to nothing, so go by the result
+            let value = Self::from_datum(*datum, *is_null, column.typoid);
+            has_nulls |= value.is_none();
+            nulls.push(value.is_none());
+            values.push(value.unwrap_or_default());
+        }
+
+        PgColumn {
+            values,
+            nulls: if has_nulls { Some(nulls) } else { None },
+        }
+    }
+}
+
+/// Convert a whole `PgColumn` into a `PgDatumColumn` in one pass.
+///
+/// The default implementation calls `IntoDatum::into_datum()` for each row, so any `IntoDatum`
+/// type can opt in with an empty `impl IntoDatumColumn for MyType {}`.  As with `IntoDatum`,
+/// values that need memory for their `Datum`s allocate it in the `CurrentMemoryContext`
+pub trait IntoDatumColumn: IntoDatum + Sized {
+    fn into_datum_column(column: PgColumn<Self>) -> PgDatumColumn {
+        let mut datums = PgDatumColumn::with_capacity(Self::type_oid(), column.len());
+        for value in column.into_options() {
+            datums.push(value.and_then(IntoDatum::into_datum));
+        }
+        datums
+    }
+}
+
+/// The primitive types are passed by value, so their `Datum`s convert without a function call or
+/// an `Option` per row, in loops the compiler can vectorize
+macro_rules! primitive_datum_column {
+    ($t:ty, $datum:ident => $from:expr, $value:ident => $into:expr) => {
+        impl FromDatumColumn for $t {
+            unsafe fn from_datum_column(column: &PgDatumColumn) -> PgColumn<Self> {
+                let mut values = Vec::with_capacity(column.len());
+                let mut has_nulls = false;
+                for (&$datum, &is_null) in column.datums.iter().zip(&column.nulls) {
+                    has_nulls |= is_null;
+                    values.push(if is_null { <$t>::default() } else { $from });
+                }
+
+                PgColumn {
+                    values,
+                    nulls: if has_nulls {
+                        Some(column.nulls.clone())
+                    } else {
+                        None
+                    },
+                }
+            }
+        }
+
+        impl IntoDatumColumn for $t {
+            fn into_datum_column(column: PgColumn<Self>) -> PgDatumColumn {
+                let datums = column
+                    .values
+                    .into_iter()
+                    .map(|$value| $into as pg_sys::Datum)
+                    .collect::<Vec<_>>();
+                let nulls = column.nulls.unwrap_or_else(|| vec![false; datums.len()]);
+
+                PgDatumColumn {
+                    datums,
+                    nulls,
+                    typoid: <$t as IntoDatum>::type_oid(),
+                }
+            }
+        }
+    };
+}
+
+primitive_datum_column!(bool, datum => datum != 0, value => value as u8);
+primitive_datum_column!(i8, datum => datum as i8, value => value);
+primitive_datum_column!(i16, datum => datum as i16, value => value);
+primitive_datum_column!(i32, datum => datum as i32, value => value);
+primitive_datum_column!(u32, datum => datum as u32, value => value);
+primitive_datum_column!(i64, datum => datum as i64, value => value);
+primitive_datum_column!(f32, datum => f32::from_bits(datum as u32), value => value.to_bits());
+primitive_datum_column!(f64, datum => f64::from_bits(datum as u64), value => value.to_bits());
+
+impl FromDatumColumn for String {}
+impl IntoDatumColumn for String {}
+
+impl FromDatumColumn for Vec<u8> {}
+impl IntoDatumColumn for Vec<u8> {}
@@ -5,7 +5,11 @@
 //! and converting Rust types into their corresponding Postgres types
 mod anyarray;
 mod anyelement;
+#[cfg(feature = "rkyv")]
+mod archived;
 mod array;
+mod byval;
+mod column;
 mod date;
 mod from;
 mod geo;
@@ -25,7 +29,11 @@ mod varlena;
 pub use self::time::*;
 pub use anyarray::*;
 pub use anyelement::*;
+#[cfg(feature = "rkyv")]
+pub use archived::*;
 pub use array::*;
+pub use byval::*;
+pub use column::*;
 pub use date::*;
 pub use from::*;
 pub use geo::*;
@@ -41,6 +49,20 @@ pub use time_with_timezone::*;
 pub use tuples::*;
 pub use varlena::*;
 
+use crate::{direct_function_call, pg_sys};
+
+/// The Oid of the SQL type `#[derive(PostgresType)]` created for `T`, which is named after `T`
+/// without its module path or generic arguments
+pub(crate) fn postgres_type_oid<T>() -> pg_sys::Oid {
+    let type_name = std::any::type_name::<T>();
+    let type_name = type_name.split('<').next().unwrap();
+    let type_name = type_name.rsplit("::").next().unwrap();
+    unsafe {
+        direct_function_call::<pg_sys::Oid>(pg_sys::regtypein, vec![type_name.into_datum()])
+            .expect("failed to lookup typeoid")
+    }
+}
+
 /// A tagging trait to indicate a user type is also meant to be used by Postgres
 /// Implemented automatically by `#[derive(PostgresType)]`
 pub trait PostgresType {}
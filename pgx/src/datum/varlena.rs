@@ -1,9 +1,9 @@
 //! Wrapper for Postgres 'varlena' type, over Rust types of a fixed size (ie, `impl Copy`)
+use crate::datum::postgres_type_oid;
 use crate::pg_sys::{VARATT_SHORT_MAX, VARHDRSZ_SHORT};
 use crate::{
-    direct_function_call, pg_sys, set_varsize, set_varsize_short, vardata_any, varsize_any,
-    varsize_any_exhdr, void_mut_ptr, FromDatum, IntoDatum, PgMemoryContexts, PostgresType,
-    StringInfo,
+    pg_sys, set_varsize, set_varsize_short, vardata_any, varsize_any, varsize_any_exhdr,
+    void_mut_ptr, FromDatum, IntoDatum, PgMemoryContexts, PostgresType, StringInfo,
 };
 use pgx_pg_sys::varlena;
 use serde::{Deserialize, Serialize};
@@ -259,13 +259,7 @@ where
     }
 
     fn type_oid() -> u32 {
-        unsafe {
-            direct_function_call::<pg_sys::Oid>(
-                pg_sys::regtypein,
-                vec![std::any::type_name::<T>().into_datum()],
-            )
-            .expect("failed to lookup typeoid")
-        }
+        postgres_type_oid::<T>()
     }
 }
 
@@ -315,13 +309,7 @@ where
     }
 
     fn type_oid() -> u32 {
-        unsafe {
-            direct_function_call::<pg_sys::Oid>(
-                pg_sys::regtypein,
-                vec![std::any::type_name::<T>().into_datum()],
-            )
-            .expect("failed to lookup typeoid")
-        }
+        postgres_type_oid::<T>()
     }
 }
 
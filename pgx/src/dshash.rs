@@ -0,0 +1,385 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! A typed wrapper around Postgres' `dshash`, a hash table in dynamic shared memory (Postgres 11+)
+//!
+//! Unlike `PgShmemCache`, whose size is fixed when the postmaster starts, a `PgDshash` is stored
+//! in a DSA area and so can grow for as long as there's memory available.  Only a small, fixed,
+//! control structure is reserved at startup.  The table itself is created by the first backend
+//! to use it, and every other backend attaches to it on first use.
+//!
+//! As with all shared memory, keys and values must be plain `Copy` data that doesn't point
+//! anywhere.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! static SEEN_USERS: PgDshash<pg_sys::Oid, i64> = PgDshash::new("my_extension_seen_users");
+//!
+//! #[pg_guard]
+//! pub extern "C" fn _PG_init() {
+//!     pg_shmem_init(&SEEN_USERS);
+//! }
+//!
+//! #[pg_extern]
+//! fn record_visit() -> i64 {
+//!     let user = unsafe { pg_sys::GetUserId() };
+//!     let visits = SEEN_USERS.get(&user).unwrap_or(0) + 1;
+//!     SEEN_USERS.insert(user, visits);
+//!     visits
+//! }
+//! ```
+use crate::pg_sys;
+use crate::shmem::{shmem_init_struct, PgSharedMemoryInitialization};
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::CString;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+/// The fixed-size control structure for a `PgDshash`, which lives in regular shared memory
+#[repr(C)]
+struct DshashControl {
+    initialized: bool,
+    area_handle: pg_sys::dsa_handle,
+    table_handle: pg_sys::dshash_table_handle,
+
+    /// an array of every key in the table, so we can iterate it.  `dshash` itself has no way to
+    /// do that until Postgres 15
+    keys: pg_sys::dsa_pointer,
+    nkeys: u32,
+    keys_capacity: u32,
+}
+
+/// What's actually stored in the `dshash`.  It requires the key come first
+#[repr(C)]
+struct DshashEntry<K, V> {
+    key: K,
+    key_index: u32,
+    value: V,
+}
+
+/// A typed, growable hash table in dynamic shared memory.  See the module documentation
+pub struct PgDshash<K, V> {
+    name: &'static str,
+    control: AtomicPtr<DshashControl>,
+    lock: AtomicPtr<pg_sys::LWLock>,
+    area: AtomicPtr<pg_sys::dsa_area>,
+    table: AtomicPtr<pg_sys::dshash_table>,
+    __marker: PhantomData<(K, V)>,
+}
+
+unsafe impl<K: Copy + Hash + Eq, V: Copy> Sync for PgDshash<K, V> {}
+
+// `const fn`s can't have trait bounds, so this is kept apart from the rest of the impl
+impl<K, V> PgDshash<K, V> {
+    /// Declare a new table.  It's registered with Postgres by passing it to `pg_shmem_init()`
+    /// from `_PG_init()`
+    pub const fn new(name: &'static str) -> Self {
+        PgDshash {
+            name,
+            control: AtomicPtr::new(std::ptr::null_mut()),
+            lock: AtomicPtr::new(std::ptr::null_mut()),
+            area: AtomicPtr::new(std::ptr::null_mut()),
+            table: AtomicPtr::new(std::ptr::null_mut()),
+            __marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Copy + Hash + Eq, V: Copy> PgDshash<K, V> {
+    /// Look up the value for `key`
+    pub fn get(&self, key: &K) -> Option<V> {
+        let table = self.attach();
+        unsafe {
+            let entry = pg_sys::dshash_find(table, key as *const K as *const _, false)
+                as *mut DshashEntry<K, V>;
+            if entry.is_null() {
+                return None;
+            }
+            let value = (*entry).value;
+            pg_sys::dshash_release_lock(table, entry as *mut _);
+            Some(value)
+        }
+    }
+
+    /// Is `key` in the table?
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Add or replace `key`, returning the value previously associated with it, if any
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let table = self.attach();
+        unsafe {
+            // the common case of replacing an existing value doesn't need the membership lock
+            let entry = pg_sys::dshash_find(table, &key as *const K as *const _, true)
+                as *mut DshashEntry<K, V>;
+            if !entry.is_null() {
+                let old = std::mem::replace(&mut (*entry).value, value);
+                pg_sys::dshash_release_lock(table, entry as *mut _);
+                return Some(old);
+            }
+
+            self.with_membership_lock(|control| {
+                let mut found = false;
+                let entry = pg_sys::dshash_find_or_insert(
+                    table,
+                    &key as *const K as *const _,
+                    &mut found,
+                ) as *mut DshashEntry<K, V>;
+
+                let old = if found {
+                    // another backend inserted it before we got the lock
+                    Some(std::mem::replace(&mut (*entry).value, value))
+                } else {
+                    (*entry).key_index = self.push_key(control, key);
+                    (*entry).value = value;
+                    None
+                };
+                pg_sys::dshash_release_lock(table, entry as *mut _);
+                old
+            })
+        }
+    }
+
+    /// Remove `key` from the table, returning its value if it was present
+    pub fn remove(&self, key: &K) -> Option<V> {
+        let table = self.attach();
+        self.with_membership_lock(|control| unsafe {
+            let entry = pg_sys::dshash_find(table, key as *const K as *const _, true)
+                as *mut DshashEntry<K, V>;
+            if entry.is_null() {
+                return None;
+            }
+
+            let value = (*entry).value;
+            let key_index = (*entry).key_index;
+            pg_sys::dshash_delete_entry(table, entry as *mut _);
+
+            // move the last key into the removed key's spot, and tell its entry where it went
+            if let Some(moved) = self.swap_remove_key(control, key_index) {
+                let entry = pg_sys::dshash_find(table, &moved as *const K as *const _, true)
+                    as *mut DshashEntry<K, V>;
+                (*entry).key_index = key_index;
+                pg_sys::dshash_release_lock(table, entry as *mut _);
+            }
+
+            Some(value)
+        })
+    }
+
+    /// How many entries are in the table?
+    pub fn len(&self) -> usize {
+        self.attach();
+        self.with_membership_lock(|control| control.nkeys as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A snapshot of every entry in the table.  Entries inserted or removed by other backends
+    /// while the snapshot is being taken block until it's finished
+    pub fn iter(&self) -> std::vec::IntoIter<(K, V)> {
+        let table = self.attach();
+        self.with_membership_lock(|control| unsafe {
+            self.keys(control)
+                .iter()
+                .filter_map(|key| {
+                    let entry = pg_sys::dshash_find(table, key as *const K as *const _, false)
+                        as *mut DshashEntry<K, V>;
+                    if entry.is_null() {
+                        return None;
+                    }
+                    let value = (*entry).value;
+                    pg_sys::dshash_release_lock(table, entry as *mut _);
+                    Some((*key, value))
+                })
+                .collect::<Vec<_>>()
+        })
+        .into_iter()
+    }
+
+    /// Remove every entry from the table
+    pub fn clear(&self) {
+        let table = self.attach();
+        self.with_membership_lock(|control| unsafe {
+            for key in self.keys(control) {
+                pg_sys::dshash_delete_key(table, key as *const K as *const _);
+            }
+            control.nkeys = 0;
+        })
+    }
+
+    fn params(&self) -> pg_sys::dshash_parameters {
+        pg_sys::dshash_parameters {
+            key_size: std::mem::size_of::<K>(),
+            entry_size: std::mem::size_of::<DshashEntry<K, V>>(),
+            compare_function: Some(dshash_compare::<K>),
+            hash_function: Some(dshash_hash::<K>),
+            tranche_id: unsafe { (*self.lock.load(Ordering::Acquire)).tranche as i32 },
+        }
+    }
+
+    /// Attach this backend to the table, creating it if we're the first backend to use it
+    fn attach(&self) -> *mut pg_sys::dshash_table {
+        let table = self.table.load(Ordering::Acquire);
+        if !table.is_null() {
+            return table;
+        }
+
+        if self.control.load(Ordering::Acquire).is_null() {
+            panic!(
+                "dshash table `{}` was not initialized.  Did you call `pg_shmem_init()` from `_PG_init()`?",
+                self.name
+            );
+        }
+
+        self.with_membership_lock(|control| unsafe {
+            let params = self.params();
+            let (area, table) = if control.initialized {
+                let area = pg_sys::dsa_attach(control.area_handle);
+                let table =
+                    pg_sys::dshash_attach(area, &params, control.table_handle, std::ptr::null_mut());
+                (area, table)
+            } else {
+                let area = pg_sys::dsa_create(params.tranche_id);
+                pg_sys::dsa_pin(area);
+                let table = pg_sys::dshash_create(area, &params, std::ptr::null_mut());
+
+                control.area_handle = pg_sys::dsa_get_handle(area);
+                control.table_handle = pg_sys::dshash_get_hash_table_handle(table);
+                control.keys = pg_sys::InvalidDsaPointer;
+                control.nkeys = 0;
+                control.keys_capacity = 0;
+                control.initialized = true;
+                (area, table)
+            };
+
+            // keep the area mapped for the life of this backend, not just the current resource owner
+            pg_sys::dsa_pin_mapping(area);
+            self.area.store(area, Ordering::Release);
+            self.table.store(table, Ordering::Release);
+            table
+        })
+    }
+
+    fn with_membership_lock<R, F: FnOnce(&mut DshashControl) -> R>(&self, f: F) -> R {
+        unsafe {
+            let lock = self.lock.load(Ordering::Acquire);
+            pg_sys::LWLockAcquire(lock, pg_sys::LWLockMode_LW_EXCLUSIVE);
+            let result = f(&mut *self.control.load(Ordering::Acquire));
+            pg_sys::LWLockRelease(lock);
+            result
+        }
+    }
+
+    /// The array of keys.  Only valid while the membership lock is held
+    unsafe fn keys(&self, control: &DshashControl) -> &[K] {
+        if control.nkeys == 0 {
+            return &[];
+        }
+        let area = self.area.load(Ordering::Acquire);
+        let keys = pg_sys::dsa_get_address(area, control.keys) as *const K;
+        std::slice::from_raw_parts(keys, control.nkeys as usize)
+    }
+
+    /// Append `key` to the key array, growing it if necessary, returning its index
+    unsafe fn push_key(&self, control: &mut DshashControl, key: K) -> u32 {
+        let area = self.area.load(Ordering::Acquire);
+
+        if control.nkeys == control.keys_capacity {
+            let new_capacity = (control.keys_capacity * 2).max(64);
+            let new_keys = pg_sys::dsa_allocate_extended(
+                area,
+                new_capacity as usize * std::mem::size_of::<K>(),
+                0,
+            );
+            if control.nkeys > 0 {
+                std::ptr::copy_nonoverlapping(
+                    pg_sys::dsa_get_address(area, control.keys) as *const K,
+                    pg_sys::dsa_get_address(area, new_keys) as *mut K,
+                    control.nkeys as usize,
+                );
+            }
+            if control.keys != pg_sys::InvalidDsaPointer {
+                pg_sys::dsa_free(area, control.keys);
+            }
+            control.keys = new_keys;
+            control.keys_capacity = new_capacity;
+        }
+
+        let keys = pg_sys::dsa_get_address(area, control.keys) as *mut K;
+        let index = control.nkeys;
+        *keys.add(index as usize) = key;
+        control.nkeys += 1;
+        index
+    }
+
+    /// Remove the key at `index` by moving the last key into its place.  Returns the key that was
+    /// moved, if any
+    unsafe fn swap_remove_key(&self, control: &mut DshashControl, index: u32) -> Option<K> {
+        let area = self.area.load(Ordering::Acquire);
+        let keys = pg_sys::dsa_get_address(area, control.keys) as *mut K;
+
+        control.nkeys -= 1;
+        if index == control.nkeys {
+            return None;
+        }
+
+        let moved = *keys.add(control.nkeys as usize);
+        *keys.add(index as usize) = moved;
+        Some(moved)
+    }
+}
+
+impl<K: Copy + Hash + Eq, V: Copy> PgSharedMemoryInitialization for PgDshash<K, V> {
+    fn shmem_size(&self) -> usize {
+        std::mem::size_of::<DshashControl>()
+    }
+
+    fn lwlock_tranche(&self) -> Option<(&'static str, usize)> {
+        Some((self.name, 1))
+    }
+
+    unsafe fn shmem_init(&self) {
+        let (control, found) = shmem_init_struct(self.name, self.shmem_size());
+        let control = control as *mut DshashControl;
+        if !found {
+            // the dsa area and table are created later, by the first backend that needs them
+            (*control).initialized = false;
+        }
+        self.control.store(control, Ordering::Release);
+
+        let tranche_name = CString::new(self.name).unwrap();
+        let locks = pg_sys::GetNamedLWLockTranche(tranche_name.as_ptr());
+        self.lock.store(&mut (*locks).lock, Ordering::Release);
+    }
+}
+
+unsafe extern "C" fn dshash_compare<K: Eq>(
+    a: *const std::os::raw::c_void,
+    b: *const std::os::raw::c_void,
+    _size: usize,
+    _arg: *mut std::os::raw::c_void,
+) -> std::os::raw::c_int {
+    if *(a as *const K) == *(b as *const K) {
+        0
+    } else {
+        1
+    }
+}
+
+unsafe extern "C" fn dshash_hash<K: Hash>(
+    v: *const std::os::raw::c_void,
+    _size: usize,
+    _arg: *mut std::os::raw::c_void,
+) -> pg_sys::dshash_hash {
+    // `DefaultHasher::new()` always uses the same keys, so every backend computes the same hash
+    let mut hasher = DefaultHasher::new();
+    (*(v as *const K)).hash(&mut hasher);
+    hasher.finish() as pg_sys::dshash_hash
+}
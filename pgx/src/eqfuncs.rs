@@ -0,0 +1,61 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Support for the `#[derive(PostgresEq)]` proc macro, which gives custom Postgres types the
+//! equality operators and hash operator class that arrays of them need.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PostgresType, PostgresEq, PartialEq, Eq, Hash)]
+//! pub struct Color {
+//!     name: String,
+//! }
+//!
+//! // now `'{"name": "red"}'::Color = ANY(palette)` and `palette @> ARRAY[...]::Color[]` work
+//! #[pg_extern]
+//! fn palette() -> Vec<Color> {
+//!     vec![
+//!         Color { name: "red".into() },
+//!         Color { name: "green".into() },
+//!     ]
+//! }
+//! ```
+use std::hash::{Hash, Hasher};
+
+/// Hash `value` for a `#[derive(PostgresEq)]` type's hash support function.
+///
+/// Hash indexes store these, so unlike `std::collections::hash_map::DefaultHasher`, the result
+/// only depends on `value`'s `Hash` implementation, and not on the version of Rust or pgx.
+pub fn hash_value<T: Hash + ?Sized>(value: &T) -> i32 {
+    let mut hasher = Fnv1aHasher::default();
+    value.hash(&mut hasher);
+
+    let hash = hasher.finish();
+    (hash ^ (hash >> 32)) as i32
+}
+
+/// 64-bit FNV-1a
+struct Fnv1aHasher(u64);
+
+impl Default for Fnv1aHasher {
+    fn default() -> Self {
+        Fnv1aHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1aHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Add properties to `EXPLAIN` output
+//!
+//! Custom scan providers (`ExplainCustomScan`), foreign data wrappers (`ExplainForeignScan`), and
+//! the `PgHooks::explain_one_query()` hook are all handed a `pg_sys::ExplainState`.  Wrapping it
+//! in a `PgExplainState` lets them add labeled properties that are rendered correctly in every
+//! `EXPLAIN` output format, including `FORMAT JSON`.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! #[pg_guard]
+//! unsafe extern "C" fn explain_my_scan(
+//!     _node: *mut pg_sys::CustomScanState,
+//!     _ancestors: *mut pg_sys::List,
+//!     es: *mut pg_sys::ExplainState,
+//! ) {
+//!     let es = PgExplainState::from_pg(es);
+//!     es.property_text("Index Name", "my_index");
+//!     if es.is_analyze() {
+//!         es.property_integer("Rows Fetched", None, 42);
+//!         es.property_float("Fetch Time", Some("ms"), 1.23, 3);
+//!     }
+//! }
+//! ```
+use crate::{pg_sys, PgList};
+use std::ffi::CString;
+
+/// The output format `EXPLAIN` was asked for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PgExplainFormat {
+    Text,
+    Xml,
+    Json,
+    Yaml,
+}
+
+/// A wrapper around a Postgres-provided `pg_sys::ExplainState`
+pub struct PgExplainState {
+    es: *mut pg_sys::ExplainState,
+}
+
+impl PgExplainState {
+    /// Wrap the `ExplainState` Postgres gave us
+    pub fn from_pg(es: *mut pg_sys::ExplainState) -> Self {
+        if es.is_null() {
+            panic!("ExplainState is NULL");
+        }
+        PgExplainState { es }
+    }
+
+    /// The underlying `pg_sys::ExplainState`
+    pub fn as_ptr(&self) -> *mut pg_sys::ExplainState {
+        self.es
+    }
+
+    /// The output format `EXPLAIN` was asked for
+    pub fn format(&self) -> PgExplainFormat {
+        match unsafe { (*self.es).format } {
+            pg_sys::ExplainFormat_EXPLAIN_FORMAT_XML => PgExplainFormat::Xml,
+            pg_sys::ExplainFormat_EXPLAIN_FORMAT_JSON => PgExplainFormat::Json,
+            pg_sys::ExplainFormat_EXPLAIN_FORMAT_YAML => PgExplainFormat::Yaml,
+            _ => PgExplainFormat::Text,
+        }
+    }
+
+    /// Was `EXPLAIN (VERBOSE)` requested?
+    pub fn is_verbose(&self) -> bool {
+        unsafe { (*self.es).verbose }
+    }
+
+    /// Was `EXPLAIN (ANALYZE)` requested?  If so, the query is actually executed and run-time
+    /// statistics are expected
+    pub fn is_analyze(&self) -> bool {
+        unsafe { (*self.es).analyze }
+    }
+
+    /// Should estimated costs be shown?
+    pub fn is_costs(&self) -> bool {
+        unsafe { (*self.es).costs }
+    }
+
+    /// Should timing information be shown?
+    pub fn is_timing(&self) -> bool {
+        unsafe { (*self.es).timing }
+    }
+
+    /// Add a text property
+    pub fn property_text(&self, label: &str, value: &str) {
+        let label = explain_cstring(label);
+        let value = explain_cstring(value);
+        unsafe { pg_sys::ExplainPropertyText(label.as_ptr(), value.as_ptr(), self.es) }
+    }
+
+    /// Add an integer property.  The `unit`, such as `"kB"`, is only shown in the text format
+    pub fn property_integer(&self, label: &str, unit: Option<&str>, value: i64) {
+        self.property_integer_impl(label, unit, value)
+    }
+
+    /// Add a floating-point property, rounded to `ndigits` decimal places.  The `unit`, such as
+    /// `"ms"`, is only shown in the text format
+    pub fn property_float(&self, label: &str, unit: Option<&str>, value: f64, ndigits: usize) {
+        self.property_float_impl(label, unit, value, ndigits)
+    }
+
+    /// Add a boolean property
+    pub fn property_bool(&self, label: &str, value: bool) {
+        let label = explain_cstring(label);
+        unsafe { pg_sys::ExplainPropertyBool(label.as_ptr(), value, self.es) }
+    }
+
+    /// Add a property that's a list of strings, such as the output columns of a node
+    pub fn property_list(&self, label: &str, values: &[&str]) {
+        let label = explain_cstring(label);
+
+        // Postgres only reads these strings, so they can stay owned by Rust
+        let values = values
+            .iter()
+            .map(|value| explain_cstring(value))
+            .collect::<Vec<_>>();
+        let mut list = PgList::<std::os::raw::c_char>::new();
+        for value in &values {
+            list.push(value.as_ptr() as *mut std::os::raw::c_char);
+        }
+
+        unsafe { pg_sys::ExplainPropertyList(label.as_ptr(), list.as_ptr(), self.es) }
+    }
+
+    /// Add a nested group of properties, such as `"Workers"` or `"Plans"`.  A `labeled` group is
+    /// rendered as a JSON object (or its equivalent in other formats), otherwise it's an array.
+    /// The `label` must be `None` when the group is itself an element of an unlabeled group.
+    ///
+    /// `object_type` is the XML element name used for the group (Postgres 11+)
+    #[cfg(any(
+        feature = "pg11",
+        feature = "pg12",
+        feature = "pg13",
+        feature = "pg14",
+        feature = "pg15"
+    ))]
+    pub fn group<F: FnOnce(&PgExplainState)>(
+        &self,
+        object_type: &str,
+        label: Option<&str>,
+        labeled: bool,
+        f: F,
+    ) {
+        let object_type = explain_cstring(object_type);
+        let label = label.map(explain_cstring);
+        let label_ptr = label.as_ref().map_or(std::ptr::null(), |label| label.as_ptr());
+        unsafe {
+            pg_sys::ExplainOpenGroup(object_type.as_ptr(), label_ptr, labeled, self.es);
+        }
+        f(self);
+        unsafe {
+            pg_sys::ExplainCloseGroup(object_type.as_ptr(), label_ptr, labeled, self.es);
+        }
+    }
+
+    #[cfg(any(
+        feature = "pg11",
+        feature = "pg12",
+        feature = "pg13",
+        feature = "pg14",
+        feature = "pg15"
+    ))]
+    fn property_integer_impl(&self, label: &str, unit: Option<&str>, value: i64) {
+        let label = explain_cstring(label);
+        let unit = unit.map(explain_cstring);
+        unsafe {
+            pg_sys::ExplainPropertyInteger(
+                label.as_ptr(),
+                unit.as_ref().map_or(std::ptr::null(), |unit| unit.as_ptr()),
+                value,
+                self.es,
+            )
+        }
+    }
+
+    #[cfg(feature = "pg10")]
+    fn property_integer_impl(&self, label: &str, unit: Option<&str>, value: i64) {
+        // Postgres 10 doesn't support units, so we render them ourselves for the text format
+        match unit {
+            Some(unit) if self.format() == PgExplainFormat::Text => {
+                self.property_text(label, &format!("{} {}", value, unit))
+            }
+            _ => {
+                let label = explain_cstring(label);
+                unsafe { pg_sys::ExplainPropertyLong(label.as_ptr(), value, self.es) }
+            }
+        }
+    }
+
+    #[cfg(any(
+        feature = "pg11",
+        feature = "pg12",
+        feature = "pg13",
+        feature = "pg14",
+        feature = "pg15"
+    ))]
+    fn property_float_impl(&self, label: &str, unit: Option<&str>, value: f64, ndigits: usize) {
+        let label = explain_cstring(label);
+        let unit = unit.map(explain_cstring);
+        unsafe {
+            pg_sys::ExplainPropertyFloat(
+                label.as_ptr(),
+                unit.as_ref().map_or(std::ptr::null(), |unit| unit.as_ptr()),
+                value,
+                ndigits as i32,
+                self.es,
+            )
+        }
+    }
+
+    #[cfg(feature = "pg10")]
+    fn property_float_impl(&self, label: &str, unit: Option<&str>, value: f64, ndigits: usize) {
+        // Postgres 10 doesn't support units, so we render them ourselves for the text format
+        match unit {
+            Some(unit) if self.format() == PgExplainFormat::Text => {
+                self.property_text(label, &format!("{:.*} {}", ndigits, value, unit))
+            }
+            _ => {
+                let label = explain_cstring(label);
+                unsafe {
+                    pg_sys::ExplainPropertyFloat(label.as_ptr(), value, ndigits as i32, self.es)
+                }
+            }
+        }
+    }
+}
+
+fn explain_cstring(s: &str) -> CString {
+    CString::new(s).expect("EXPLAIN property contains a null byte")
+}
@@ -0,0 +1,195 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Manage which objects belong to an extension, like `ALTER EXTENSION ... ADD/DROP`
+//!
+//! Objects created by an extension's script are members of it automatically, but those created
+//! later, at runtime, are not.  Adding them to the extension means they're dropped along with it
+//! by `DROP EXTENSION`, and that `pg_dump` leaves them to be recreated by `CREATE EXTENSION`
+//! instead of dumping them separately.
+//!
+//! These skip the ownership checks `ALTER EXTENSION` makes, so it's up to the extension to only
+//! add objects it created itself.
+//!
+//! Work that should happen once, when the extension is created in a database, rather than every
+//! time the library is loaded like `_PG_init()`, goes in a `#[pg_extern(on_create)]` function.
+//! The generated schema calls it after everything else in the extension's script is created, and
+//! it raises an ERROR if called any other time.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! #[pg_extern]
+//! fn create_partition(name: &str) {
+//!     Spi::run(&format!("CREATE TABLE {} (LIKE events)", quote_identifier(name)));
+//!
+//!     let relid = Spi::get_one::<pg_sys::Oid>(&format!(
+//!         "SELECT {}::regclass::oid",
+//!         quote_literal(name)
+//!     ))
+//!     .unwrap();
+//!     let extension = extension_oid("my_extension").expect("my_extension is not installed");
+//!     add_to_extension(extension, &relation_address(relid));
+//! }
+//!
+//! #[pg_extern(on_create)]
+//! fn seed_settings() {
+//!     Spi::run("INSERT INTO my_extension_settings VALUES ('enabled', 'true')");
+//! }
+//! ```
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use std::ffi::{CStr, CString};
+
+/// Is an extension's script being run by `CREATE EXTENSION` or `ALTER EXTENSION ... UPDATE`?
+pub fn creating_extension() -> bool {
+    unsafe { pg_sys::creating_extension }
+}
+
+/// The Oid of the extension whose script is being run, if any
+pub fn current_extension() -> Option<pg_sys::Oid> {
+    if creating_extension() {
+        Some(unsafe { pg_sys::CurrentExtensionObject })
+    } else {
+        None
+    }
+}
+
+/// Raise an ERROR unless an extension's script is being run.  `#[pg_extern(on_create)]` functions
+/// call this first, so they can't be run again by hand once the extension is created
+pub fn check_creating_extension(function: &str) {
+    if !creating_extension() {
+        ereport(
+            PgLogLevel::ERROR,
+            PgSqlErrorCode::ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE,
+            &format!("{}() can only be called by CREATE EXTENSION", function),
+            file!(),
+            line!(),
+            column!(),
+        );
+    }
+}
+
+/// The Oid of the installed extension named `name`, if it's installed
+pub fn extension_oid(name: &str) -> Option<pg_sys::Oid> {
+    let name = CString::new(name).expect("extension name contains a null byte");
+    match unsafe { pg_sys::get_extension_oid(name.as_ptr(), true) } {
+        pg_sys::InvalidOid => None,
+        oid => Some(oid),
+    }
+}
+
+/// The address of the table, index, sequence, view, or other relation `relid`
+pub fn relation_address(relid: pg_sys::Oid) -> pg_sys::ObjectAddress {
+    object_address(pg_sys::RelationRelationId, relid)
+}
+
+/// The address of the function `funcid`
+pub fn function_address(funcid: pg_sys::Oid) -> pg_sys::ObjectAddress {
+    object_address(pg_sys::ProcedureRelationId, funcid)
+}
+
+/// The address of the type `typid`
+pub fn type_address(typid: pg_sys::Oid) -> pg_sys::ObjectAddress {
+    object_address(pg_sys::TypeRelationId, typid)
+}
+
+/// The address of the object `object_id` in the catalog `class_id`, such as
+/// `pg_sys::RelationRelationId`
+pub fn object_address(class_id: pg_sys::Oid, object_id: pg_sys::Oid) -> pg_sys::ObjectAddress {
+    pg_sys::ObjectAddress {
+        classId: class_id,
+        objectId: object_id,
+        objectSubId: 0,
+    }
+}
+
+/// The Oid of the extension `object` is a member of, if any
+pub fn extension_of_object(object: &pg_sys::ObjectAddress) -> Option<pg_sys::Oid> {
+    match unsafe { pg_sys::getExtensionOfObject(object.classId, object.objectId) } {
+        pg_sys::InvalidOid => None,
+        oid => Some(oid),
+    }
+}
+
+/// Make `object` a member of the extension whose script is being run, just as if the script had
+/// created it.  Does nothing if no extension script is being run
+pub fn add_to_current_extension(object: &pg_sys::ObjectAddress) {
+    unsafe { pg_sys::recordDependencyOnCurrentExtension(object, false) }
+}
+
+/// Make `object` a member of the extension `extension`, like `ALTER EXTENSION ... ADD`.  Raises
+/// an ERROR if it's already a member of an extension
+pub fn add_to_extension(extension: pg_sys::Oid, object: &pg_sys::ObjectAddress) {
+    if let Some(existing) = extension_of_object(object) {
+        ereport(
+            PgLogLevel::ERROR,
+            PgSqlErrorCode::ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE,
+            &format!(
+                "{} is already a member of extension \"{}\"",
+                object_description(object),
+                extension_name(existing)
+            ),
+            file!(),
+            line!(),
+            column!(),
+        );
+    }
+
+    let referenced = object_address(pg_sys::ExtensionRelationId, extension);
+    unsafe {
+        pg_sys::recordDependencyOn(
+            object,
+            &referenced,
+            pg_sys::DependencyType_DEPENDENCY_EXTENSION,
+        );
+        pg_sys::CommandCounterIncrement();
+    }
+}
+
+/// Stop `object` from being a member of the extension `extension`, like
+/// `ALTER EXTENSION ... DROP`.  Raises an ERROR if it isn't a member
+pub fn remove_from_extension(extension: pg_sys::Oid, object: &pg_sys::ObjectAddress) {
+    if extension_of_object(object) != Some(extension) {
+        ereport(
+            PgLogLevel::ERROR,
+            PgSqlErrorCode::ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE,
+            &format!(
+                "{} is not a member of extension \"{}\"",
+                object_description(object),
+                extension_name(extension)
+            ),
+            file!(),
+            line!(),
+            column!(),
+        );
+    }
+
+    unsafe {
+        pg_sys::deleteDependencyRecordsForClass(
+            object.classId,
+            object.objectId,
+            pg_sys::ExtensionRelationId,
+            pg_sys::DependencyType_DEPENDENCY_EXTENSION as std::os::raw::c_char,
+        );
+        pg_sys::CommandCounterIncrement();
+    }
+}
+
+fn extension_name(extension: pg_sys::Oid) -> String {
+    let name = unsafe { pg_sys::get_extension_name(extension) };
+    if name.is_null() {
+        format!("OID {}", extension)
+    } else {
+        unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+fn object_description(object: &pg_sys::ObjectAddress) -> String {
+    unsafe { CStr::from_ptr(pg_sys::getObjectDescription(object)) }
+        .to_string_lossy()
+        .into_owned()
+}
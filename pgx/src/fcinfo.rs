@@ -112,7 +112,7 @@ mod pg_10_11 {
     }
 }
 
-#[cfg(feature = "pg12")]
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
 mod pg_12 {
     use crate::{pg_sys, FromDatum};
 
@@ -148,14 +148,11 @@ mod pg_12 {
     }
 
     #[inline]
-    fn get_nullable_datum(
-        fcinfo: pg_sys::FunctionCallInfo,
-        num: usize,
-    ) -> pg_sys::pg12_specific::NullableDatum {
+    fn get_nullable_datum(fcinfo: pg_sys::FunctionCallInfo, num: usize) -> pg_sys::NullableDatum {
         let fcinfo = unsafe { fcinfo.as_mut() }.unwrap();
         unsafe {
             let nargs = fcinfo.nargs;
-            let len = std::mem::size_of::<pg_sys::pg12_specific::NullableDatum>() * nargs as usize;
+            let len = std::mem::size_of::<pg_sys::NullableDatum>() * nargs as usize;
             fcinfo.args.as_slice(len)[num]
         }
     }
@@ -175,7 +172,7 @@ mod pg_12 {
 #[cfg(any(feature = "pg10", feature = "pg11"))]
 pub use pg_10_11::*;
 
-#[cfg(feature = "pg12")]
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
 pub use pg_12::*;
 use std::ops::DerefMut;
 
@@ -276,6 +273,19 @@ pub fn direct_function_call_as_datum(
     func: unsafe fn(pg_sys::FunctionCallInfo) -> pg_sys::Datum,
     args: Vec<Option<pg_sys::Datum>>,
 ) -> Option<pg_sys::Datum> {
+    let fcinfo = new_function_call_info(args);
+    let datum = unsafe { func(fcinfo) };
+
+    if unsafe { fcinfo.as_ref() }.unwrap().isnull {
+        None
+    } else {
+        Some(datum)
+    }
+}
+
+/// A `FunctionCallInfo`, allocated in the `CurrentMemoryContext`, for calling a function with
+/// `args`.  It has no `flinfo`
+pub(crate) fn new_function_call_info(args: Vec<Option<pg_sys::Datum>>) -> pg_sys::FunctionCallInfo {
     let mut null_array = [false; 100usize];
     let mut arg_array = [0 as pg_sys::Datum; 100usize];
     let nargs = args.len();
@@ -294,14 +304,7 @@ pub fn direct_function_call_as_datum(
         }
     }
 
-    let mut fcid = make_function_call_info(nargs, arg_array, null_array);
-    let datum = unsafe { func(fcid.deref_mut()) };
-
-    if fcid.isnull {
-        None
-    } else {
-        Some(datum)
-    }
+    make_function_call_info(nargs, arg_array, null_array).as_ptr()
 }
 
 #[cfg(feature = "pg10")]
@@ -336,27 +339,27 @@ fn make_function_call_info(
     fcinfo_boxed
 }
 
-#[cfg(feature = "pg12")]
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
 fn make_function_call_info(
     nargs: usize,
     arg_array: [usize; 100],
     null_array: [bool; 100],
-) -> PgBox<pg_sys::pg12_specific::FunctionCallInfoBaseData> {
-    let fcid: *mut pg_sys::pg12_specific::FunctionCallInfoBaseData = unsafe {
+) -> PgBox<pg_sys::FunctionCallInfoBaseData> {
+    let fcid: *mut pg_sys::FunctionCallInfoBaseData = unsafe {
         pg_sys::palloc0(
-            std::mem::size_of::<pg_sys::pg12_specific::FunctionCallInfoBaseData>()
-                + nargs * std::mem::size_of::<pg_sys::pg12_specific::NullableDatum>(),
-        ) as *mut pg_sys::pg12_specific::FunctionCallInfoBaseData
+            std::mem::size_of::<pg_sys::FunctionCallInfoBaseData>()
+                + nargs * std::mem::size_of::<pg_sys::NullableDatum>(),
+        ) as *mut pg_sys::FunctionCallInfoBaseData
     };
 
-    let mut fcinfo_boxed = PgBox::<pg_sys::pg12_specific::FunctionCallInfoBaseData>::from_pg(fcid);
+    let mut fcinfo_boxed = PgBox::<pg_sys::FunctionCallInfoBaseData>::from_pg(fcid);
     let fcinfo = fcinfo_boxed.deref_mut();
 
     fcinfo.nargs = nargs as i16;
 
     let slice = unsafe { fcinfo.args.as_mut_slice(nargs) };
     for i in 0..nargs {
-        slice[i] = pg_sys::pg12_specific::NullableDatum {
+        slice[i] = pg_sys::NullableDatum {
             value: arg_array[i],
             isnull: null_array[i],
         }
@@ -0,0 +1,186 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Helpers for handing Rust closures to C code (Postgres or otherwise) that calls back into Rust
+//!
+//! C APIs that accept callbacks typically take a function pointer and an opaque `void *` "arg"
+//! that is passed back to the function on every call.  `PgCallback` boxes a Rust closure so its
+//! address can be used as that "arg", and the various `*_trampoline` functions are the
+//! `extern "C"` function pointers that recover the closure and call it.
+//!
+//! Every trampoline runs its closure through `guard_callback()`, so a Rust `panic!()` (or a
+//! Postgres `ERROR` raised by the closure) is translated into a Postgres `ERROR` at the FFI
+//! boundary instead of unwinding into C.
+//!
+//! ## Examples
+//!
+//! Sort a slice with Postgres' `qsort_arg()`, using a closure that captures local state:
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! let mut comparisons = 0;
+//! let mut values = vec![3, 1, 2];
+//! pg_qsort_by(&mut values, |a: &i32, b: &i32| {
+//!     comparisons += 1;
+//!     a.cmp(b)
+//! });
+//! assert_eq!(values, vec![1, 2, 3]);
+//! ```
+//!
+//! Register a closure as a process-exit callback:
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! fn register_exit_callback<F: FnMut(i32) + 'static>(f: F) {
+//!     let callback = PgCallback::new(f);
+//!     unsafe {
+//!         pg_sys::before_shmem_exit(
+//!             Some(exit_trampoline::<F>),
+//!             callback.into_raw() as pg_sys::Datum,
+//!         );
+//!     }
+//! }
+//!
+//! register_exit_callback(|code| info!("exiting with code {}", code));
+//! ```
+
+use crate::{pg_sys, void_mut_ptr, void_ptr};
+use std::cmp::Ordering;
+use std::panic::AssertUnwindSafe;
+
+/// Run `f` such that a Rust `panic!()` or Postgres `ERROR` it raises is converted into a Postgres
+/// `ERROR`, rather than unwinding out of an `extern "C"` function.
+///
+/// Unlike `pg_sys::guard()`, the closure need not be `UnwindSafe`.  Any state it captures is
+/// never observed again after an error, as control is transferred back to Postgres.
+///
+/// Use this as the body of hand-written `extern "C"` callbacks that can't take a user "arg",
+/// such as dynahash's hash and match functions.
+pub fn guard_callback<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    pg_sys::guard(AssertUnwindSafe(f))
+}
+
+/// A boxed Rust closure whose address can be passed to C as a callback's opaque "arg" pointer.
+///
+/// The closure is called through one of the `*_trampoline` functions in this module, whichever
+/// matches the shape of the C callback.
+pub struct PgCallback<F> {
+    closure: Box<F>,
+}
+
+impl<F> PgCallback<F> {
+    /// Box the closure `f`
+    pub fn new(f: F) -> Self {
+        PgCallback {
+            closure: Box::new(f),
+        }
+    }
+
+    /// Returns the pointer to pass to C as the callback's "arg".
+    ///
+    /// The pointer is only valid for as long as this `PgCallback` is alive, so this is suitable
+    /// for callbacks that are only invoked during a single, synchronous C call (like `qsort_arg()`)
+    pub fn as_arg(&mut self) -> void_mut_ptr {
+        self.closure.as_mut() as *mut F as void_mut_ptr
+    }
+
+    /// Consumes this `PgCallback`, leaking the closure, and returns the pointer to pass to C as
+    /// the callback's "arg".
+    ///
+    /// This is for callbacks that outlive the current Rust stack frame, such as hooks and exit
+    /// callbacks.  The closure can be reclaimed with `PgCallback::from_raw()`.
+    pub fn into_raw(self) -> void_mut_ptr {
+        Box::into_raw(self.closure) as void_mut_ptr
+    }
+
+    /// Reclaim a `PgCallback` from a pointer previously returned by `PgCallback::into_raw()`
+    ///
+    /// ## Safety
+    ///
+    /// `arg` must have come from `PgCallback::<F>::into_raw()` and must no longer be reachable
+    /// by C code that might call back with it.
+    pub unsafe fn from_raw(arg: void_mut_ptr) -> Self {
+        PgCallback {
+            closure: Box::from_raw(arg as *mut F),
+        }
+    }
+}
+
+/// Recover the closure behind a callback "arg"
+unsafe fn closure_from_arg<'a, F>(arg: void_mut_ptr) -> &'a mut F {
+    assert!(!arg.is_null(), "PgCallback arg is NULL");
+    &mut *(arg as *mut F)
+}
+
+/// Trampoline for callbacks of the form `void (*)(void *arg)`, such as
+/// `MemoryContextCallbackFunction`
+pub unsafe extern "C" fn void_trampoline<F: FnMut()>(arg: void_mut_ptr) {
+    let f = closure_from_arg::<F>(arg);
+    guard_callback(|| f())
+}
+
+/// Trampoline for callbacks of the form `void (*)(int code, Datum arg)`, such as the
+/// `pg_on_exit_callback` used by `before_shmem_exit()` and `on_proc_exit()`.  The closure
+/// receives the exit code
+pub unsafe extern "C" fn exit_trampoline<F: FnMut(i32)>(
+    code: std::os::raw::c_int,
+    arg: pg_sys::Datum,
+) {
+    let f = closure_from_arg::<F>(arg as void_mut_ptr);
+    guard_callback(|| f(code as i32))
+}
+
+/// Trampoline for comparators of the form `int (*)(const void *a, const void *b, void *arg)`,
+/// such as `qsort_arg_comparator`.  The closure receives `a` and `b` as references to `T`
+pub unsafe extern "C" fn comparator_trampoline<T, F: FnMut(&T, &T) -> Ordering>(
+    a: void_ptr,
+    b: void_ptr,
+    arg: void_mut_ptr,
+) -> std::os::raw::c_int {
+    let f = closure_from_arg::<F>(arg);
+    guard_callback(|| match f(&*(a as *const T), &*(b as *const T)) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    })
+}
+
+/// Trampoline for hash functions of the form `uint32 (*)(const void *key, void *arg)`, as used by
+/// some third-party C libraries.  The closure receives `key` as a reference to `K`
+pub unsafe extern "C" fn hash_trampoline<K, F: FnMut(&K) -> u32>(
+    key: void_ptr,
+    arg: void_mut_ptr,
+) -> u32 {
+    let f = closure_from_arg::<F>(arg);
+    guard_callback(|| f(&*(key as *const K)))
+}
+
+/// Sort `slice` in place with Postgres' `qsort_arg()`, using the closure `compare` to order
+/// elements.
+///
+/// A `panic!()` in `compare` is raised as a Postgres `ERROR` directly from the comparator, which
+/// `qsort_arg()` is safe to be `longjmp`'d out of.  `slice` is left in an unspecified order.
+pub fn pg_qsort_by<T, F>(slice: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if slice.len() < 2 || std::mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    let mut callback = PgCallback::new(compare);
+    unsafe {
+        pg_sys::qsort_arg(
+            slice.as_mut_ptr() as void_mut_ptr,
+            slice.len(),
+            std::mem::size_of::<T>(),
+            Some(comparator_trampoline::<T, F>),
+            callback.as_arg(),
+        );
+    }
+}
@@ -0,0 +1,328 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Group statements by fingerprint, the way `pg_stat_statements` does
+//!
+//! Postgres 10-12 don't have query jumbling in core -- it lives in `pg_stat_statements`, which
+//! stores its result in `Query.queryId`.  `query_fingerprint()` uses that `queryId` when it's been
+//! computed, and otherwise falls back to hashing the query tree with its constants and source
+//! locations masked out, so two queries that differ only in their literal values share a
+//! fingerprint either way.
+//!
+//! For statements that are only available as text, `normalize_query_string()` replaces literal
+//! constants with `$n` parameters like `pg_stat_statements` shows them, and
+//! `query_string_fingerprint()` hashes that normalized form, ignoring whitespace, comments, and
+//! the case of keywords and unquoted identifiers.
+//!
+//! Fingerprints are stable across backends and restarts, but not across Postgres versions.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! assert_eq!(
+//!     normalize_query_string("SELECT * FROM users WHERE id = 42 AND name = 'bob'"),
+//!     "SELECT * FROM users WHERE id = $1 AND name = $2"
+//! );
+//! assert_eq!(
+//!     query_string_fingerprint("select * from users where id = 1"),
+//!     query_string_fingerprint("SELECT *\n  FROM users\n WHERE id = 2 -- find user 2"),
+//! );
+//! ```
+use crate::{nodes::node_to_string, pg_sys};
+
+/// The fingerprint of an analyzed `Query`.  This is `pg_stat_statements`' `queryId` if it has
+/// already been computed, and otherwise a hash of the query tree without its constants
+pub fn query_fingerprint(query: &pg_sys::Query) -> u64 {
+    if query.queryId != 0 {
+        return query.queryId as u64;
+    }
+
+    let tree = node_to_string(query as *const pg_sys::Query as *mut pg_sys::Node)
+        .expect("unable to convert Query to a string");
+    fnv1a(mask_query_tree(tree).as_bytes())
+}
+
+/// Replace the literal constants in `sql` with `$n` parameters, numbered after any parameters
+/// the statement already has.  Everything else, including whitespace and comments, is kept as-is
+pub fn normalize_query_string(sql: &str) -> String {
+    let tokens = tokenize(sql);
+    let mut next_param = tokens
+        .iter()
+        .filter_map(|token| match token.kind {
+            TokenKind::Param(n) => Some(n),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    let mut normalized = String::with_capacity(sql.len());
+    for token in &tokens {
+        match token.kind {
+            TokenKind::Literal => {
+                normalized.push_str(&format!("${}", next_param));
+                next_param += 1;
+            }
+            _ => normalized.push_str(token.text),
+        }
+    }
+    normalized
+}
+
+/// The fingerprint of a SQL statement's text.  Statements that differ only in their literal
+/// values, whitespace, comments, or the case of keywords and unquoted identifiers share a
+/// fingerprint
+pub fn query_string_fingerprint(sql: &str) -> u64 {
+    let mut canonical = String::with_capacity(sql.len());
+    for token in tokenize(sql) {
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Comment => continue,
+            // all literals and parameters are equivalent
+            TokenKind::Literal | TokenKind::Param(_) => canonical.push('?'),
+            TokenKind::QuotedIdentifier => canonical.push_str(token.text),
+            TokenKind::Word | TokenKind::Other => {
+                canonical.push_str(&token.text.to_lowercase())
+            }
+        }
+        canonical.push(' ');
+    }
+    fnv1a(canonical.as_bytes())
+}
+
+/// The `nodeToString()` fields that `pg_stat_statements` ignores when jumbling a query
+const MASKED_FIELDS: [&str; 6] = [
+    ":location",
+    ":stmt_location",
+    ":stmt_len",
+    ":queryId",
+    ":constisnull",
+    ":constvalue",
+];
+
+/// Remove the values of `MASKED_FIELDS` from a `nodeToString()` representation of a query tree,
+/// keeping any node-closing characters that follow them
+fn mask_query_tree(tree: &str) -> String {
+    let mut masked = String::with_capacity(tree.len());
+    let mut words = tree.split(' ');
+
+    while let Some(word) = words.next() {
+        if !MASKED_FIELDS.contains(&word) {
+            masked.push_str(word);
+            masked.push(' ');
+            continue;
+        }
+
+        let value = match words.next() {
+            Some(value) => value,
+            None => break,
+        };
+
+        let rest = if word == ":constvalue" && !value.starts_with("<>") {
+            // a non-null Const's value is written as `length [ byte byte ... ]`
+            let mut rest = "";
+            for word in &mut words {
+                if word.starts_with(']') {
+                    rest = &word[1..];
+                    break;
+                }
+            }
+            rest
+        } else if value.starts_with("<>") {
+            &value[2..]
+        } else {
+            value.trim_start_matches(|c: char| c.is_ascii_alphanumeric() || c == '-')
+        };
+
+        masked.push_str(rest);
+        masked.push(' ');
+    }
+    masked
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TokenKind {
+    Whitespace,
+    Comment,
+    Literal,
+    Param(usize),
+    QuotedIdentifier,
+    Word,
+    Other,
+}
+
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+}
+
+/// Split `sql` into tokens, following the lexical rules of Postgres' scanner closely enough to
+/// find literals, comments, and quoted identifiers
+fn tokenize(sql: &str) -> Vec<Token> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+
+    while start < bytes.len() {
+        let (kind, end) = scan_token(sql, start);
+        tokens.push(Token {
+            kind,
+            text: &sql[start..end],
+        });
+        start = end;
+    }
+    tokens
+}
+
+fn scan_token(sql: &str, start: usize) -> (TokenKind, usize) {
+    let bytes = sql.as_bytes();
+    let at = |i: usize| bytes.get(i).copied().unwrap_or(0);
+    let c = at(start);
+
+    if c.is_ascii_whitespace() {
+        let end = (start..bytes.len())
+            .find(|&i| !bytes[i].is_ascii_whitespace())
+            .unwrap_or_else(|| bytes.len());
+        return (TokenKind::Whitespace, end);
+    }
+
+    if c == b'-' && at(start + 1) == b'-' {
+        let end = (start..bytes.len())
+            .find(|&i| bytes[i] == b'\n')
+            .unwrap_or_else(|| bytes.len());
+        return (TokenKind::Comment, end);
+    }
+
+    if c == b'/' && at(start + 1) == b'*' {
+        // block comments nest
+        let mut depth = 0;
+        let mut i = start;
+        while i < bytes.len() {
+            if at(i) == b'/' && at(i + 1) == b'*' {
+                depth += 1;
+                i += 2;
+            } else if at(i) == b'*' && at(i + 1) == b'/' {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    break;
+                }
+            } else {
+                i += 1;
+            }
+        }
+        return (TokenKind::Comment, i.min(bytes.len()));
+    }
+
+    if c == b'\'' {
+        return (TokenKind::Literal, scan_quoted(bytes, start, b'\'', false));
+    }
+
+    // prefixed strings:  E'...', B'...', X'...', N'...', and U&'...'
+    if at(start + 1) == b'\'' && b"eEbBxXnN".contains(&c) {
+        let backslash_escapes = c == b'e' || c == b'E';
+        return (
+            TokenKind::Literal,
+            scan_quoted(bytes, start + 1, b'\'', backslash_escapes),
+        );
+    }
+    if (c == b'u' || c == b'U') && at(start + 1) == b'&' && at(start + 2) == b'\'' {
+        return (TokenKind::Literal, scan_quoted(bytes, start + 2, b'\'', false));
+    }
+
+    if c == b'"' {
+        return (
+            TokenKind::QuotedIdentifier,
+            scan_quoted(bytes, start, b'"', false),
+        );
+    }
+
+    if c == b'$' {
+        // a positional parameter
+        if at(start + 1).is_ascii_digit() {
+            let end = (start + 1..bytes.len())
+                .find(|&i| !bytes[i].is_ascii_digit())
+                .unwrap_or_else(|| bytes.len());
+            let n = sql[start + 1..end].parse().unwrap_or(0);
+            return (TokenKind::Param(n), end);
+        }
+
+        // a dollar-quoted string, like $$...$$ or $tag$...$tag$
+        if let Some(tag_len) = sql[start + 1..].find('$') {
+            let tag = &sql[start..start + tag_len + 2];
+            if tag[1..tag.len() - 1]
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80)
+            {
+                let body_start = start + tag.len();
+                let end = match sql[body_start..].find(tag) {
+                    Some(offset) => body_start + offset + tag.len(),
+                    None => bytes.len(),
+                };
+                return (TokenKind::Literal, end);
+            }
+        }
+    }
+
+    if c.is_ascii_digit() || (c == b'.' && at(start + 1).is_ascii_digit()) {
+        let mut i = start;
+        while at(i).is_ascii_digit() || at(i) == b'.' {
+            i += 1;
+        }
+        if (at(i) == b'e' || at(i) == b'E')
+            && (at(i + 1).is_ascii_digit()
+                || ((at(i + 1) == b'+' || at(i + 1) == b'-') && at(i + 2).is_ascii_digit()))
+        {
+            i += 2;
+            while at(i).is_ascii_digit() {
+                i += 1;
+            }
+        }
+        return (TokenKind::Literal, i);
+    }
+
+    if c.is_ascii_alphabetic() || c == b'_' || c >= 0x80 {
+        let end = (start..bytes.len())
+            .find(|&i| {
+                let b = bytes[i];
+                !(b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80)
+            })
+            .unwrap_or_else(|| bytes.len());
+        return (TokenKind::Word, end);
+    }
+
+    // an operator or punctuation.  Step over a whole UTF8 character
+    let len = sql[start..].chars().next().map_or(1, |c| c.len_utf8());
+    (TokenKind::Other, start + len)
+}
+
+/// Find the end of the string starting with the `quote` at `start`.  A doubled quote is an
+/// escaped quote, as is a backslash-escaped one in an `E'...'` string
+fn scan_quoted(bytes: &[u8], start: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if backslash_escapes && bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+            } else {
+                return i + 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// 64-bit FNV-1a, which unlike `std`'s hashers is guaranteed to be stable
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
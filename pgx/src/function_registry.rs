@@ -0,0 +1,190 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Runtime introspection of the functions an extension exports with `#[pg_extern]`
+//!
+//! Every `#[pg_extern]` function gets a companion `pgx_meta_<name>_wrapper` symbol, exported next
+//! to its `pg_finfo_<name>_wrapper`, that describes where the function lives in the extension's
+//! Rust source.  `exported_functions()` pairs that with what the system catalogs say about each
+//! function the installed extension created: its SQL signature and volatility.
+//!
+//! The `pg_function_registry!()` macro creates an `<extname>_functions()` SQL function that
+//! returns `(name, signature, rust_path, volatility)` for each of them, which is handy for
+//! diagnostics and admin UIs.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! pg_module_magic!();
+//! pg_function_registry!();
+//!
+//! #[pg_extern]
+//! fn volatile_functions() -> Vec<String> {
+//!     exported_functions()
+//!         .into_iter()
+//!         .filter(|function| function.volatility == PgVolatility::Volatile)
+//!         .map(|function| function.signature)
+//!         .collect()
+//! }
+//! ```
+//!
+//! ```sql
+//! SELECT * FROM my_extension_functions() ORDER BY rust_path;
+//! ```
+use crate::{pg_sys, IntoDatum, PgBuiltInOids, Spi};
+use std::ffi::CString;
+
+/// What `#[pg_extern]` records about a function at compile time.  One of these is exported as
+/// `pgx_meta_<name>_wrapper` for every `#[pg_extern]` function
+#[derive(Debug)]
+pub struct PgExternMetadata {
+    /// The Rust function's name
+    pub name: &'static str,
+
+    /// The path of the module the function is declared in, as `module_path!()` reports it
+    pub module_path: &'static str,
+
+    /// The source file the function is declared in
+    pub file: &'static str,
+
+    /// The line of `file` the function is declared on
+    pub line: u32,
+}
+
+impl PgExternMetadata {
+    /// The function's full Rust path, like `my_extension::strings::reverse`
+    pub fn rust_path(&self) -> String {
+        format!("{}::{}", self.module_path, self.name)
+    }
+}
+
+/// A function's volatility category, as given by `pg_proc.provolatile`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PgVolatility {
+    Immutable,
+    Stable,
+    Volatile,
+}
+
+impl PgVolatility {
+    fn from_provolatile(provolatile: &str) -> Self {
+        match provolatile {
+            "i" => PgVolatility::Immutable,
+            "s" => PgVolatility::Stable,
+            _ => PgVolatility::Volatile,
+        }
+    }
+
+    /// The keyword `CREATE FUNCTION` uses for this volatility
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PgVolatility::Immutable => "immutable",
+            PgVolatility::Stable => "stable",
+            PgVolatility::Volatile => "volatile",
+        }
+    }
+}
+
+/// A function created by the extension's SQL
+#[derive(Debug, Clone)]
+pub struct PgExportedFunction {
+    /// The function's Oid
+    pub oid: pg_sys::Oid,
+
+    /// The function's SQL name
+    pub name: String,
+
+    /// The function's SQL signature, such as `reverse(text) RETURNS text`.  The name is schema
+    /// qualified if its schema isn't on the `search_path`
+    pub signature: String,
+
+    /// The function's volatility
+    pub volatility: PgVolatility,
+
+    /// What `#[pg_extern]` recorded about the Rust function behind it.  `None` for functions not
+    /// implemented in this library, such as those written in SQL with `extension_sql!()`
+    pub metadata: Option<&'static PgExternMetadata>,
+}
+
+impl PgExportedFunction {
+    /// The full Rust path of the function behind this one, if it's a `#[pg_extern]` function
+    pub fn rust_path(&self) -> Option<String> {
+        self.metadata.map(PgExternMetadata::rust_path)
+    }
+}
+
+/// Every function the installed extension that this library belongs to has created, ordered by
+/// signature.
+///
+/// The extension is the one named by `pg_module_magic!()`, so this returns nothing if the library
+/// wasn't built by `cargo pgx` or the extension isn't installed in the current database.
+pub fn exported_functions() -> Vec<PgExportedFunction> {
+    let extname = match crate::get_magic_block().and_then(|block| block.extname) {
+        Some(extname) => extname,
+        None => return Vec::new(),
+    };
+    let extension = match crate::extension_oid(extname) {
+        Some(extension) => extension,
+        None => return Vec::new(),
+    };
+
+    let mut functions = Vec::new();
+    Spi::connect(|client| {
+        let table = client.select(
+            "SELECT p.oid, p.proname::text,
+                    p.oid::regprocedure::text || ' RETURNS ' || pg_catalog.pg_get_function_result(p.oid),
+                    p.provolatile::text, p.probin, p.prosrc
+               FROM pg_catalog.pg_proc p
+               JOIN pg_catalog.pg_depend d ON d.classid = 'pg_catalog.pg_proc'::regclass
+                                          AND d.objid = p.oid
+              WHERE d.refclassid = 'pg_catalog.pg_extension'::regclass
+                AND d.refobjid = $1
+                AND d.deptype = 'e'
+           ORDER BY 3",
+            None,
+            Some(vec![(PgBuiltInOids::OIDOID.oid(), extension.into_datum())]),
+        );
+
+        for row in table {
+            let probin = row.get_datum::<String>(5);
+            let prosrc = row.get_datum::<String>(6);
+
+            functions.push(PgExportedFunction {
+                oid: row.get_datum(1).expect("pg_proc.oid is NULL"),
+                name: row.get_datum(2).expect("pg_proc.proname is NULL"),
+                signature: row.get_datum(3).expect("function signature is NULL"),
+                volatility: PgVolatility::from_provolatile(
+                    &row.get_datum::<String>(4).unwrap_or_default(),
+                ),
+                metadata: match (probin, prosrc) {
+                    (Some(probin), Some(prosrc)) => lookup_metadata(&probin, &prosrc),
+                    _ => None,
+                },
+            });
+        }
+        Ok(Some(()))
+    });
+    functions
+}
+
+/// Find the `pgx_meta_` symbol for the C function `prosrc` in the library `probin`
+fn lookup_metadata(probin: &str, prosrc: &str) -> Option<&'static PgExternMetadata> {
+    let probin = CString::new(probin).ok()?;
+    let symbol = CString::new(format!("pgx_meta_{}", prosrc)).ok()?;
+
+    // Postgres already has the library open, so this just looks up the symbol
+    let function = unsafe {
+        pg_sys::load_external_function(
+            probin.as_ptr(),
+            symbol.as_ptr(),
+            false,
+            std::ptr::null_mut(),
+        )
+    }?;
+
+    let metadata =
+        unsafe { std::mem::transmute::<_, extern "C" fn() -> &'static PgExternMetadata>(function) };
+    Some(metadata())
+}
@@ -0,0 +1,283 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Per-function call counts and timings, collected in shared memory
+//!
+//! Functions declared with `#[pg_extern(track_stats)]` record how many times they've been called,
+//! their cumulative run time, and their "self" time -- the run time that wasn't spent in other
+//! tracked functions they called.  Statistics are shared by every backend in the cluster and
+//! survive until the server restarts or they are reset.
+//!
+//! Collection is opt-in.  The extension must be loaded via `shared_preload_libraries` and call
+//! `pg_function_stats_init()` from `_PG_init()`.  Without that, tracked functions run normally
+//! but nothing is recorded.
+//!
+//! The `pg_function_stats!()` macro creates two SQL functions for inspecting them:
+//!
+//! - `pgx_function_stats()`, which returns `(function_name, calls, total_time_ms, self_time_ms)`
+//! - `pgx_function_stats_reset()`, which zeroes every function's statistics
+//!
+//! A set-returning function's time only covers building its iterator, not producing its rows.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! pg_module_magic!();
+//! pg_function_stats!();
+//!
+//! #[pg_guard]
+//! pub extern "C" fn _PG_init() {
+//!     pg_function_stats_init();
+//! }
+//!
+//! #[pg_extern(track_stats)]
+//! fn expensive_lookup(key: &str) -> Option<String> {
+//!     Spi::get_one_with_args(
+//!         "SELECT value FROM lookup WHERE key = $1",
+//!         vec![(PgBuiltInOids::TEXTOID.oid(), key.into_datum())],
+//!     )
+//! }
+//! ```
+//!
+//! ```sql
+//! SELECT * FROM pgx_function_stats() ORDER BY self_time_ms DESC;
+//! ```
+use crate::shmem::{pg_shmem_init, shmem_init_struct, PgSharedMemoryInitialization};
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicPtr, AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// How many distinct functions statistics can be collected for
+const MAX_TRACKED_FUNCTIONS: usize = 1024;
+
+/// Function names are truncated to fit, like identifiers are
+const MAX_NAME_LEN: usize = pg_sys::NAMEDATALEN as usize;
+
+const SLOT_EMPTY: u32 = 0;
+const SLOT_CLAIMED: u32 = 1;
+const SLOT_READY: u32 = 2;
+
+/// The statistics collected for one function
+#[derive(Clone, Debug)]
+pub struct PgFunctionStats {
+    /// The function's name
+    pub name: String,
+
+    /// How many times it was called, including calls that raised an ERROR
+    pub calls: u64,
+
+    /// Its cumulative run time
+    pub total_time: Duration,
+
+    /// Its cumulative run time, not counting time spent in other tracked functions it called
+    pub self_time: Duration,
+}
+
+#[repr(C)]
+struct Slot {
+    state: AtomicU32,
+    name_len: AtomicU32,
+    name: UnsafeCell<[u8; MAX_NAME_LEN]>,
+    calls: AtomicU64,
+    total_ns: AtomicU64,
+    self_ns: AtomicU64,
+}
+
+struct FunctionStatsShmem {
+    slots: AtomicPtr<Slot>,
+}
+
+static FUNCTION_STATS: FunctionStatsShmem = FunctionStatsShmem {
+    slots: AtomicPtr::new(std::ptr::null_mut()),
+};
+
+impl PgSharedMemoryInitialization for FunctionStatsShmem {
+    fn shmem_size(&self) -> usize {
+        std::mem::size_of::<Slot>() * MAX_TRACKED_FUNCTIONS
+    }
+
+    unsafe fn shmem_init(&self) {
+        let (ptr, found) = shmem_init_struct("pgx_function_stats", self.shmem_size());
+        if !found {
+            // all zeros is an empty slot
+            std::ptr::write_bytes(ptr as *mut u8, 0, self.shmem_size());
+        }
+        self.slots.store(ptr as *mut Slot, Ordering::Release);
+    }
+}
+
+/// Start collecting statistics for `#[pg_extern(track_stats)]` functions.  Must be called from
+/// `_PG_init()` while the extension is being loaded via `shared_preload_libraries`
+pub fn pg_function_stats_init() {
+    pg_shmem_init(&FUNCTION_STATS);
+}
+
+/// Are statistics being collected for `#[pg_extern(track_stats)]` functions?
+pub fn function_stats_enabled() -> bool {
+    !FUNCTION_STATS.slots.load(Ordering::Acquire).is_null()
+}
+
+/// The statistics collected for every tracked function that's been called, in no particular order.
+/// Raises an ERROR if `pg_function_stats_init()` wasn't called when the extension was loaded
+pub fn function_stats() -> Vec<PgFunctionStats> {
+    let slots = check_enabled();
+    let mut stats = Vec::new();
+    for slot in slots {
+        if slot.state.load(Ordering::Acquire) != SLOT_READY {
+            continue;
+        }
+
+        let name_len = slot.name_len.load(Ordering::Relaxed) as usize;
+        stats.push(PgFunctionStats {
+            name: String::from_utf8_lossy(&slot.name()[..name_len]).to_string(),
+            calls: slot.calls.load(Ordering::Relaxed),
+            total_time: Duration::from_nanos(slot.total_ns.load(Ordering::Relaxed)),
+            self_time: Duration::from_nanos(slot.self_ns.load(Ordering::Relaxed)),
+        });
+    }
+    stats
+}
+
+/// Zero the statistics of every tracked function.  Raises an ERROR if `pg_function_stats_init()`
+/// wasn't called when the extension was loaded
+pub fn reset_function_stats() {
+    for slot in check_enabled() {
+        slot.calls.store(0, Ordering::Relaxed);
+        slot.total_ns.store(0, Ordering::Relaxed);
+        slot.self_ns.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Records one call of a tracked function when it's dropped.  `#[pg_extern(track_stats)]` creates
+/// one of these at the start of the function's body
+pub struct PgFunctionStatsGuard {
+    slot: Option<&'static Slot>,
+    start: Instant,
+}
+
+/// The time spent in tracked functions called by each tracked function that's currently running
+/// in this backend, innermost last
+static mut CHILD_TIME_STACK: Vec<Duration> = Vec::new();
+
+/// This backend's cache of which slot each function's statistics are stored in
+static mut SLOT_CACHE: Option<HashMap<&'static str, &'static Slot>> = None;
+
+impl PgFunctionStatsGuard {
+    /// Start timing a call to the function `name`
+    pub fn enter(name: &'static str) -> Self {
+        let slot = find_slot(name);
+        if slot.is_some() {
+            unsafe { CHILD_TIME_STACK.push(Duration::default()) }
+        }
+        PgFunctionStatsGuard {
+            slot,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for PgFunctionStatsGuard {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot {
+            let total = self.start.elapsed();
+            let children = unsafe { CHILD_TIME_STACK.pop() }.unwrap_or_default();
+            if let Some(parent) = unsafe { CHILD_TIME_STACK.last_mut() } {
+                *parent += total;
+            }
+
+            let self_time = total.checked_sub(children).unwrap_or_default();
+            slot.calls.fetch_add(1, Ordering::Relaxed);
+            slot.total_ns
+                .fetch_add(total.as_nanos() as u64, Ordering::Relaxed);
+            slot.self_ns
+                .fetch_add(self_time.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Slot {
+    /// The slot's name bytes.  Only valid once its state is `SLOT_READY`
+    fn name(&self) -> &[u8; MAX_NAME_LEN] {
+        unsafe { &*self.name.get() }
+    }
+}
+
+fn check_enabled() -> &'static [Slot] {
+    let slots = FUNCTION_STATS.slots.load(Ordering::Acquire);
+    if slots.is_null() {
+        ereport(
+            PgLogLevel::ERROR,
+            PgSqlErrorCode::ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE,
+            "function statistics are not being collected",
+            file!(),
+            line!(),
+            column!(),
+        );
+        unreachable!();
+    }
+    unsafe { std::slice::from_raw_parts(slots, MAX_TRACKED_FUNCTIONS) }
+}
+
+/// Find the slot for `name`'s statistics, claiming an empty one if it doesn't have one yet.
+/// Returns `None` if statistics aren't being collected, or if every slot is taken
+fn find_slot(name: &'static str) -> Option<&'static Slot> {
+    let slots = FUNCTION_STATS.slots.load(Ordering::Acquire);
+    if slots.is_null() {
+        return None;
+    }
+
+    let cache = unsafe { SLOT_CACHE.get_or_insert_with(HashMap::new) };
+    if let Some(slot) = cache.get(name) {
+        return Some(slot);
+    }
+
+    let slots: &'static [Slot] = unsafe { std::slice::from_raw_parts(slots, MAX_TRACKED_FUNCTIONS) };
+    let name_bytes = truncate_name(name);
+    let start = crate::fingerprint::fnv1a(name_bytes) as usize % MAX_TRACKED_FUNCTIONS;
+
+    // open addressing with linear probing.  Slots are never released, so a name's slot is
+    // always somewhere between its starting position and the first empty slot
+    for i in 0..MAX_TRACKED_FUNCTIONS {
+        let slot = &slots[(start + i) % MAX_TRACKED_FUNCTIONS];
+        loop {
+            match slot.state.compare_exchange(
+                SLOT_EMPTY,
+                SLOT_CLAIMED,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    // we own the slot until we mark it ready
+                    unsafe { (*slot.name.get())[..name_bytes.len()].copy_from_slice(name_bytes) };
+                    slot.name_len
+                        .store(name_bytes.len() as u32, Ordering::Relaxed);
+                    slot.state.store(SLOT_READY, Ordering::Release);
+                    break;
+                }
+
+                // another backend is writing this slot's name
+                Err(SLOT_CLAIMED) => std::thread::yield_now(),
+                Err(_) => break,
+            }
+        }
+
+        let name_len = slot.name_len.load(Ordering::Relaxed) as usize;
+        if &slot.name()[..name_len] == name_bytes {
+            cache.insert(name, slot);
+            return Some(slot);
+        }
+    }
+    None
+}
+
+fn truncate_name(name: &str) -> &[u8] {
+    let mut len = name.len().min(MAX_NAME_LEN - 1);
+    while !name.is_char_boundary(len) {
+        len -= 1;
+    }
+    &name.as_bytes()[..len]
+}
@@ -0,0 +1,258 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! A hash table allocated in a Postgres `MemoryContext`, wrapping Postgres' `dynahash`
+//!
+//! A `std::collections::HashMap` lives on the Rust heap, so it's only freed when it's dropped --
+//! which doesn't happen if a transaction aborts with an ERROR while it's in use.  A `PgHashMap`
+//! instead lives in its own child of the `MemoryContext` it's created in, and disappears with
+//! that context.  This makes it a good fit for per-query or per-transaction state.
+//!
+//! Because Postgres frees the entries without running any Rust code, keys and values must be
+//! `Copy`.  The table must not be used after its memory context has been reset or deleted.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! let mut counts = PgHashMap::<pg_sys::Oid, i64>::new_in(PgMemoryContexts::TopTransactionContext);
+//! for relid in vec![1259, 1247, 1259] {
+//!     let count = counts.get(&relid).copied().unwrap_or(0);
+//!     counts.insert(relid, count + 1);
+//! }
+//! assert_eq!(counts.get(&1259), Some(&2));
+//! assert_eq!(counts.len(), 2);
+//! ```
+use crate::{pg_sys, PgMemoryContexts};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// How many entries a new table is sized for when no capacity is given.  dynahash grows as needed
+const DEFAULT_CAPACITY: usize = 64;
+
+/// What's actually stored in the `HTAB`.  dynahash requires the key come first
+#[repr(C)]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+}
+
+/// A typed hash table in a Postgres `MemoryContext`.  See the module documentation
+pub struct PgHashMap<K, V> {
+    htab: *mut pg_sys::HTAB,
+    __marker: PhantomData<(K, V)>,
+}
+
+impl<K: Copy + Hash + Eq, V: Copy> PgHashMap<K, V> {
+    /// Create an empty table in the `CurrentMemoryContext`
+    pub fn new() -> Self {
+        PgHashMap::new_in(PgMemoryContexts::CurrentMemoryContext)
+    }
+
+    /// Create an empty table in the `context` memory context
+    pub fn new_in(context: PgMemoryContexts) -> Self {
+        PgHashMap::with_capacity_in(DEFAULT_CAPACITY, context)
+    }
+
+    /// Create an empty table in the `context` memory context, sized for about `capacity` entries
+    pub fn with_capacity_in(capacity: usize, context: PgMemoryContexts) -> Self {
+        if std::mem::size_of::<K>() == 0 {
+            panic!("PgHashMap keys cannot be zero-sized");
+        }
+        if std::mem::align_of::<Entry<K, V>>() > std::mem::align_of::<f64>() {
+            panic!("PgHashMap entries cannot be aligned more strictly than MAXALIGN");
+        }
+
+        let mut ctl: pg_sys::HASHCTL = unsafe { std::mem::zeroed() };
+        ctl.keysize = std::mem::size_of::<K>();
+        ctl.entrysize = std::mem::size_of::<Entry<K, V>>();
+        ctl.hash = Some(htab_hash::<K>);
+        ctl.match_ = Some(htab_compare::<K>);
+        ctl.hcxt = context.value();
+
+        let name = std::ffi::CString::new("pgx PgHashMap").unwrap();
+        let htab = unsafe {
+            pg_sys::hash_create(
+                name.as_ptr(),
+                capacity.max(1) as std::os::raw::c_long,
+                &mut ctl,
+                (pg_sys::HASH_ELEM
+                    | pg_sys::HASH_FUNCTION
+                    | pg_sys::HASH_COMPARE
+                    | pg_sys::HASH_CONTEXT) as i32,
+            )
+        };
+
+        PgHashMap {
+            htab,
+            __marker: PhantomData,
+        }
+    }
+
+    /// The value for `key`, if it's in the table
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|entry| unsafe { &(*entry).value })
+    }
+
+    /// A mutable reference to the value for `key`, if it's in the table
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.find(key).map(|entry| unsafe { &mut (*entry).value })
+    }
+
+    /// Is `key` in the table?
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Set the value for `key`, returning its previous value, if there was one
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut found = false;
+        unsafe {
+            // dynahash copies the key into a new entry for us, but leaves its value uninitialized
+            let entry = pg_sys::hash_search(
+                self.htab,
+                &key as *const K as *const std::os::raw::c_void,
+                pg_sys::HASHACTION_HASH_ENTER,
+                &mut found,
+            ) as *mut Entry<K, V>;
+
+            if found {
+                Some(std::mem::replace(&mut (*entry).value, value))
+            } else {
+                std::ptr::write(&mut (*entry).value, value);
+                None
+            }
+        }
+    }
+
+    /// Remove `key` from the table, returning its value, if it was there
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let mut found = false;
+        unsafe {
+            // the removed entry goes on dynahash's freelist, and is valid until the next insert
+            let entry = pg_sys::hash_search(
+                self.htab,
+                key as *const K as *const std::os::raw::c_void,
+                pg_sys::HASHACTION_HASH_REMOVE,
+                &mut found,
+            ) as *mut Entry<K, V>;
+
+            if found {
+                Some((*entry).value)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// How many entries are in the table?
+    pub fn len(&self) -> usize {
+        unsafe { pg_sys::hash_get_num_entries(self.htab) as usize }
+    }
+
+    /// Is the table empty?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the table's entries, in no particular order
+    pub fn iter(&self) -> PgHashMapIter<'_, K, V> {
+        let mut status: pg_sys::HASH_SEQ_STATUS = unsafe { std::mem::zeroed() };
+        unsafe { pg_sys::hash_seq_init(&mut status, self.htab) }
+        PgHashMapIter {
+            status,
+            done: false,
+            __marker: PhantomData,
+        }
+    }
+
+    /// Free the table now, rather than when its memory context is reset
+    pub fn destroy(self) {
+        unsafe { pg_sys::hash_destroy(self.htab) }
+    }
+
+    /// The underlying `pg_sys::HTAB`
+    pub fn as_ptr(&self) -> *mut pg_sys::HTAB {
+        self.htab
+    }
+
+    fn find(&self, key: &K) -> Option<*mut Entry<K, V>> {
+        let entry = unsafe {
+            pg_sys::hash_search(
+                self.htab,
+                key as *const K as *const std::os::raw::c_void,
+                pg_sys::HASHACTION_HASH_FIND,
+                std::ptr::null_mut(),
+            )
+        } as *mut Entry<K, V>;
+
+        if entry.is_null() {
+            None
+        } else {
+            Some(entry)
+        }
+    }
+}
+
+impl<K: Copy + Hash + Eq, V: Copy> Default for PgHashMap<K, V> {
+    fn default() -> Self {
+        PgHashMap::new()
+    }
+}
+
+/// An iterator over the entries of a `PgHashMap`
+pub struct PgHashMapIter<'a, K, V> {
+    status: pg_sys::HASH_SEQ_STATUS,
+    done: bool,
+    __marker: PhantomData<&'a PgHashMap<K, V>>,
+}
+
+impl<'a, K, V> Iterator for PgHashMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let entry = unsafe { pg_sys::hash_seq_search(&mut self.status) } as *const Entry<K, V>;
+        if entry.is_null() {
+            // dynahash ends the scan itself when it runs out of entries
+            self.done = true;
+            None
+        } else {
+            unsafe { Some((&(*entry).key, &(*entry).value)) }
+        }
+    }
+}
+
+impl<'a, K, V> Drop for PgHashMapIter<'a, K, V> {
+    fn drop(&mut self) {
+        if !self.done {
+            unsafe { pg_sys::hash_seq_term(&mut self.status) }
+        }
+    }
+}
+
+unsafe extern "C" fn htab_compare<K: Eq>(
+    a: *const std::os::raw::c_void,
+    b: *const std::os::raw::c_void,
+    _keysize: pg_sys::Size,
+) -> std::os::raw::c_int {
+    if *(a as *const K) == *(b as *const K) {
+        0
+    } else {
+        1
+    }
+}
+
+unsafe extern "C" fn htab_hash<K: Hash>(
+    key: *const std::os::raw::c_void,
+    _keysize: pg_sys::Size,
+) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    (*(key as *const K)).hash(&mut hasher);
+    hasher.finish() as u32
+}
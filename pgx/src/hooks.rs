@@ -24,6 +24,16 @@ impl<T> Deref for HookResult<T> {
     }
 }
 
+/// The `completionTag` argument to `ProcessUtility()`.  Postgres 13 replaced its string buffer with
+/// a `QueryCompletion`
+#[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
+pub type CompletionTag = *mut ::std::os::raw::c_char;
+
+/// The `completionTag` argument to `ProcessUtility()`.  Postgres 13 replaced its string buffer with
+/// a `QueryCompletion`
+#[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+pub type CompletionTag = *mut pg_sys::QueryCompletion;
+
 pub trait PgHooks {
     /// Hook for plugins to get control in ExecutorStart()
     fn executor_start(
@@ -92,7 +102,7 @@ pub trait PgHooks {
         params: PgBox<pg_sys::ParamListInfoData>,
         query_env: PgBox<pg_sys::QueryEnvironment>,
         dest: PgBox<pg_sys::DestReceiver>,
-        completion_tag: *mut ::std::os::raw::c_char,
+        completion_tag: CompletionTag,
         prev_hook: fn(
             pstmt: PgBox<pg_sys::PlannedStmt>,
             query_string: &std::ffi::CStr,
@@ -100,7 +110,7 @@ pub trait PgHooks {
             params: PgBox<pg_sys::ParamListInfoData>,
             query_env: PgBox<pg_sys::QueryEnvironment>,
             dest: PgBox<pg_sys::DestReceiver>,
-            completion_tag: *mut ::std::os::raw::c_char,
+            completion_tag: CompletionTag,
         ) -> HookResult<()>,
     ) -> HookResult<()> {
         prev_hook(
@@ -115,6 +125,7 @@ pub trait PgHooks {
     }
 
     /// Hook for plugins to get control of the planner
+    #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
     fn planner(
         &mut self,
         parse: PgBox<pg_sys::Query>,
@@ -129,6 +140,58 @@ pub trait PgHooks {
         prev_hook(parse, cursor_options, bound_params)
     }
 
+    /// Hook for plugins to get control of the planner.  Postgres 13 added the `query_string`,
+    /// which is `None` when the caller doesn't have one
+    #[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+    fn planner(
+        &mut self,
+        parse: PgBox<pg_sys::Query>,
+        query_string: Option<&std::ffi::CStr>,
+        cursor_options: i32,
+        bound_params: PgBox<pg_sys::ParamListInfoData>,
+        prev_hook: fn(
+            parse: PgBox<pg_sys::Query>,
+            query_string: Option<&std::ffi::CStr>,
+            cursor_options: i32,
+            bound_params: PgBox<pg_sys::ParamListInfoData>,
+        ) -> HookResult<*mut pg_sys::PlannedStmt>,
+    ) -> HookResult<*mut pg_sys::PlannedStmt> {
+        prev_hook(parse, query_string, cursor_options, bound_params)
+    }
+
+    /// Hook for plugins to get control of `EXPLAIN` for planned (non-utility) queries.  An
+    /// implementation typically calls `prev_hook` and then adds its own properties with
+    /// `PgExplainState`.  `query_env` is always NULL on Postgres 10
+    fn explain_one_query(
+        &mut self,
+        query: PgBox<pg_sys::Query>,
+        cursor_options: i32,
+        into: PgBox<pg_sys::IntoClause>,
+        es: PgBox<pg_sys::ExplainState>,
+        query_string: &std::ffi::CStr,
+        params: PgBox<pg_sys::ParamListInfoData>,
+        query_env: PgBox<pg_sys::QueryEnvironment>,
+        prev_hook: fn(
+            query: PgBox<pg_sys::Query>,
+            cursor_options: i32,
+            into: PgBox<pg_sys::IntoClause>,
+            es: PgBox<pg_sys::ExplainState>,
+            query_string: &std::ffi::CStr,
+            params: PgBox<pg_sys::ParamListInfoData>,
+            query_env: PgBox<pg_sys::QueryEnvironment>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(
+            query,
+            cursor_options,
+            into,
+            es,
+            query_string,
+            params,
+            query_env,
+        )
+    }
+
     /// Called when the transaction aborts
     fn abort(&mut self) {}
 
@@ -145,6 +208,7 @@ struct Hooks {
     prev_executor_check_perms_hook: pg_sys::ExecutorCheckPerms_hook_type,
     prev_process_utility_hook: pg_sys::ProcessUtility_hook_type,
     prev_planner_hook: pg_sys::planner_hook_type,
+    prev_explain_one_query_hook: pg_sys::ExplainOneQuery_hook_type,
 }
 
 static mut HOOKS: Option<Hooks> = None;
@@ -177,6 +241,9 @@ pub unsafe fn register_hook(hook: &'static mut (dyn PgHooks)) {
         prev_planner_hook: pg_sys::planner_hook
             .replace(pgx_planner)
             .or(Some(pgx_standard_planner_wrapper)),
+        prev_explain_one_query_hook: pg_sys::ExplainOneQuery_hook
+            .replace(pgx_explain_one_query)
+            .or(Some(pgx_standard_explain_one_query_wrapper)),
     });
 
     unsafe extern "C" fn xact_callback(event: pg_sys::XactEvent, _: void_mut_ptr) {
@@ -301,15 +368,67 @@ unsafe extern "C" fn pgx_executor_check_perms(
         .inner
 }
 
+#[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
+#[pg_guard]
+unsafe extern "C" fn pgx_process_utility(
+    pstmt: *mut pg_sys::PlannedStmt,
+    query_string: *const ::std::os::raw::c_char,
+    context: pg_sys::ProcessUtilityContext,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+    dest: *mut pg_sys::DestReceiver,
+    completion_tag: CompletionTag,
+) {
+    process_utility(
+        pstmt,
+        query_string,
+        context,
+        params,
+        query_env,
+        dest,
+        completion_tag,
+    )
+}
+
+#[cfg(any(feature = "pg14", feature = "pg15"))]
 #[pg_guard]
 unsafe extern "C" fn pgx_process_utility(
+    pstmt: *mut pg_sys::PlannedStmt,
+    query_string: *const ::std::os::raw::c_char,
+    read_only_tree: bool,
+    context: pg_sys::ProcessUtilityContext,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+    dest: *mut pg_sys::DestReceiver,
+    completion_tag: CompletionTag,
+) {
+    // `pstmt` may belong to a cached plan, which hooks must not scribble on, so give them their
+    // own copy.  Everything downstream of us then sees a tree it's free to modify
+    let pstmt = if read_only_tree {
+        pg_sys::copyObjectImpl(pstmt as crate::void_ptr) as *mut pg_sys::PlannedStmt
+    } else {
+        pstmt
+    };
+
+    process_utility(
+        pstmt,
+        query_string,
+        context,
+        params,
+        query_env,
+        dest,
+        completion_tag,
+    )
+}
+
+unsafe fn process_utility(
     pstmt: *mut pg_sys::PlannedStmt,
     query_string: *const ::std::os::raw::c_char,
     context: pg_sys::ProcessUtilityContext,
     params: pg_sys::ParamListInfo,
     query_env: *mut pg_sys::QueryEnvironment,
     dest: *mut pg_sys::DestReceiver,
-    completion_tag: *mut ::std::os::raw::c_char,
+    completion_tag: CompletionTag,
 ) {
     fn prev(
         pstmt: PgBox<pg_sys::PlannedStmt>,
@@ -318,15 +437,20 @@ unsafe extern "C" fn pgx_process_utility(
         params: PgBox<pg_sys::ParamListInfoData>,
         query_env: PgBox<pg_sys::QueryEnvironment>,
         dest: PgBox<pg_sys::DestReceiver>,
-        completion_tag: *mut ::std::os::raw::c_char,
+        completion_tag: CompletionTag,
     ) -> HookResult<()> {
-        HookResult::new(unsafe {
-            (HOOKS
+        let prev_hook = unsafe {
+            HOOKS
                 .as_mut()
                 .unwrap()
                 .prev_process_utility_hook
                 .as_ref()
-                .unwrap())(
+                .unwrap()
+        };
+
+        #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
+        unsafe {
+            prev_hook(
                 pstmt.into_pg(),
                 query_string.as_ptr(),
                 context,
@@ -335,7 +459,23 @@ unsafe extern "C" fn pgx_process_utility(
                 dest.into_pg(),
                 completion_tag,
             )
-        })
+        }
+
+        #[cfg(any(feature = "pg14", feature = "pg15"))]
+        unsafe {
+            prev_hook(
+                pstmt.into_pg(),
+                query_string.as_ptr(),
+                false,
+                context,
+                params.into_pg(),
+                query_env.into_pg(),
+                dest.into_pg(),
+                completion_tag,
+            )
+        }
+
+        HookResult::new(())
     }
 
     let hook = &mut HOOKS.as_mut().unwrap().current_hook;
@@ -352,6 +492,7 @@ unsafe extern "C" fn pgx_process_utility(
     .inner
 }
 
+#[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
 #[pg_guard]
 unsafe extern "C" fn pgx_planner(
     parse: *mut pg_sys::Query,
@@ -381,6 +522,157 @@ unsafe extern "C" fn pgx_planner(
     .inner
 }
 
+#[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+#[pg_guard]
+unsafe extern "C" fn pgx_planner(
+    parse: *mut pg_sys::Query,
+    query_string: *const ::std::os::raw::c_char,
+    cursor_options: i32,
+    bound_params: pg_sys::ParamListInfo,
+) -> *mut pg_sys::PlannedStmt {
+    fn prev(
+        parse: PgBox<pg_sys::Query>,
+        query_string: Option<&std::ffi::CStr>,
+        cursor_options: i32,
+        bound_params: PgBox<pg_sys::ParamListInfoData>,
+    ) -> HookResult<*mut pg_sys::PlannedStmt> {
+        HookResult::new(unsafe {
+            (HOOKS.as_mut().unwrap().prev_planner_hook.as_ref().unwrap())(
+                parse.into_pg(),
+                query_string.map_or(std::ptr::null(), |s| s.as_ptr()),
+                cursor_options,
+                bound_params.into_pg(),
+            )
+        })
+    }
+
+    let query_string = if query_string.is_null() {
+        None
+    } else {
+        Some(std::ffi::CStr::from_ptr(query_string))
+    };
+
+    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
+    hook.planner(
+        PgBox::from_pg(parse),
+        query_string,
+        cursor_options,
+        PgBox::from_pg(bound_params),
+        prev,
+    )
+    .inner
+}
+
+fn prev_explain_one_query(
+    query: PgBox<pg_sys::Query>,
+    cursor_options: i32,
+    into: PgBox<pg_sys::IntoClause>,
+    es: PgBox<pg_sys::ExplainState>,
+    query_string: &std::ffi::CStr,
+    params: PgBox<pg_sys::ParamListInfoData>,
+    query_env: PgBox<pg_sys::QueryEnvironment>,
+) -> HookResult<()> {
+    let prev_hook = unsafe {
+        HOOKS
+            .as_mut()
+            .unwrap()
+            .prev_explain_one_query_hook
+            .as_ref()
+            .unwrap()
+    };
+
+    #[cfg(feature = "pg10")]
+    {
+        let _ = query_env;
+        unsafe {
+            prev_hook(
+                query.into_pg(),
+                cursor_options,
+                into.into_pg(),
+                es.into_pg(),
+                query_string.as_ptr(),
+                params.into_pg(),
+            )
+        }
+    }
+
+    #[cfg(any(
+        feature = "pg11",
+        feature = "pg12",
+        feature = "pg13",
+        feature = "pg14",
+        feature = "pg15"
+    ))]
+    unsafe {
+        prev_hook(
+            query.into_pg(),
+            cursor_options,
+            into.into_pg(),
+            es.into_pg(),
+            query_string.as_ptr(),
+            params.into_pg(),
+            query_env.into_pg(),
+        )
+    }
+
+    HookResult::new(())
+}
+
+#[cfg(feature = "pg10")]
+#[pg_guard]
+unsafe extern "C" fn pgx_explain_one_query(
+    query: *mut pg_sys::Query,
+    cursor_options: i32,
+    into: *mut pg_sys::IntoClause,
+    es: *mut pg_sys::ExplainState,
+    query_string: *const ::std::os::raw::c_char,
+    params: pg_sys::ParamListInfo,
+) {
+    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
+    hook.explain_one_query(
+        PgBox::from_pg(query),
+        cursor_options,
+        PgBox::from_pg(into),
+        PgBox::from_pg(es),
+        std::ffi::CStr::from_ptr(query_string),
+        PgBox::from_pg(params),
+        PgBox::null(),
+        prev_explain_one_query,
+    )
+    .inner
+}
+
+#[cfg(any(
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15"
+))]
+#[pg_guard]
+unsafe extern "C" fn pgx_explain_one_query(
+    query: *mut pg_sys::Query,
+    cursor_options: i32,
+    into: *mut pg_sys::IntoClause,
+    es: *mut pg_sys::ExplainState,
+    query_string: *const ::std::os::raw::c_char,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+) {
+    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
+    hook.explain_one_query(
+        PgBox::from_pg(query),
+        cursor_options,
+        PgBox::from_pg(into),
+        PgBox::from_pg(es),
+        std::ffi::CStr::from_ptr(query_string),
+        PgBox::from_pg(params),
+        PgBox::from_pg(query_env),
+        prev_explain_one_query,
+    )
+    .inner
+}
+
 #[pg_guard]
 unsafe extern "C" fn pgx_standard_executor_start_wrapper(
     query_desc: *mut pg_sys::QueryDesc,
@@ -417,19 +709,44 @@ unsafe extern "C" fn pgx_standard_executor_check_perms_wrapper(
     true
 }
 
+#[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12", feature = "pg13"))]
+#[pg_guard]
+unsafe extern "C" fn pgx_standard_process_utility_wrapper(
+    pstmt: *mut pg_sys::PlannedStmt,
+    query_string: *const ::std::os::raw::c_char,
+    context: pg_sys::ProcessUtilityContext,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+    dest: *mut pg_sys::DestReceiver,
+    completion_tag: CompletionTag,
+) {
+    pg_sys::standard_ProcessUtility(
+        pstmt,
+        query_string,
+        context,
+        params,
+        query_env,
+        dest,
+        completion_tag,
+    )
+}
+
+#[cfg(any(feature = "pg14", feature = "pg15"))]
 #[pg_guard]
 unsafe extern "C" fn pgx_standard_process_utility_wrapper(
     pstmt: *mut pg_sys::PlannedStmt,
     query_string: *const ::std::os::raw::c_char,
+    read_only_tree: bool,
     context: pg_sys::ProcessUtilityContext,
     params: pg_sys::ParamListInfo,
     query_env: *mut pg_sys::QueryEnvironment,
     dest: *mut pg_sys::DestReceiver,
-    completion_tag: *mut ::std::os::raw::c_char,
+    completion_tag: CompletionTag,
 ) {
     pg_sys::standard_ProcessUtility(
         pstmt,
         query_string,
+        read_only_tree,
         context,
         params,
         query_env,
@@ -438,6 +755,7 @@ unsafe extern "C" fn pgx_standard_process_utility_wrapper(
     )
 }
 
+#[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
 #[pg_guard]
 unsafe extern "C" fn pgx_standard_planner_wrapper(
     parse: *mut pg_sys::Query,
@@ -446,3 +764,114 @@ unsafe extern "C" fn pgx_standard_planner_wrapper(
 ) -> *mut pg_sys::PlannedStmt {
     pg_sys::standard_planner(parse, cursor_options, bound_params)
 }
+
+#[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+#[pg_guard]
+unsafe extern "C" fn pgx_standard_planner_wrapper(
+    parse: *mut pg_sys::Query,
+    query_string: *const ::std::os::raw::c_char,
+    cursor_options: i32,
+    bound_params: pg_sys::ParamListInfo,
+) -> *mut pg_sys::PlannedStmt {
+    pg_sys::standard_planner(parse, query_string, cursor_options, bound_params)
+}
+
+/// Postgres doesn't export its default `ExplainOneQuery()` behavior, so this is a copy of it:
+/// plan the query, timing how long that took, and explain the plan
+unsafe fn standard_explain_one_query(
+    query: *mut pg_sys::Query,
+    cursor_options: i32,
+    into: *mut pg_sys::IntoClause,
+    es: *mut pg_sys::ExplainState,
+    query_string: *const ::std::os::raw::c_char,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+) {
+    let start = std::time::Instant::now();
+
+    #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
+    let plan = pg_sys::pg_plan_query(query, cursor_options, params);
+
+    #[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+    let plan = pg_sys::pg_plan_query(query, query_string, cursor_options, params);
+
+    let elapsed = start.elapsed();
+
+    let plan_duration = pg_sys::instr_time {
+        tv_sec: elapsed.as_secs() as _,
+        tv_nsec: elapsed.subsec_nanos() as _,
+    };
+
+    #[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
+    pg_sys::ExplainOnePlan(
+        plan,
+        into,
+        es,
+        query_string,
+        params,
+        query_env,
+        &plan_duration,
+    );
+
+    // we don't track the planner's buffer usage
+    #[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+    pg_sys::ExplainOnePlan(
+        plan,
+        into,
+        es,
+        query_string,
+        params,
+        query_env,
+        &plan_duration,
+        std::ptr::null(),
+    );
+}
+
+#[cfg(feature = "pg10")]
+#[pg_guard]
+unsafe extern "C" fn pgx_standard_explain_one_query_wrapper(
+    query: *mut pg_sys::Query,
+    cursor_options: i32,
+    into: *mut pg_sys::IntoClause,
+    es: *mut pg_sys::ExplainState,
+    query_string: *const ::std::os::raw::c_char,
+    params: pg_sys::ParamListInfo,
+) {
+    standard_explain_one_query(
+        query,
+        cursor_options,
+        into,
+        es,
+        query_string,
+        params,
+        std::ptr::null_mut(),
+    )
+}
+
+#[cfg(any(
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15"
+))]
+#[pg_guard]
+unsafe extern "C" fn pgx_standard_explain_one_query_wrapper(
+    query: *mut pg_sys::Query,
+    cursor_options: i32,
+    into: *mut pg_sys::IntoClause,
+    es: *mut pg_sys::ExplainState,
+    query_string: *const ::std::os::raw::c_char,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+) {
+    standard_explain_one_query(
+        query,
+        cursor_options,
+        into,
+        es,
+        query_string,
+        params,
+        query_env,
+    )
+}
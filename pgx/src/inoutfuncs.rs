@@ -24,7 +24,8 @@ pub trait PgVarlenaInOutFuncs {
 }
 
 /// `#[derive(Serialize, Deserialize, PostgresType)]` types may implement this trait if they prefer
-/// a textual representation that isn't JSON
+/// a textual representation that isn't JSON.  `#[passedbyvalue]` types, which aren't serialized,
+/// must implement it or `TextInOutFuncs`
 pub trait InOutFuncs {
     /// Given a string representation of `Self`, parse it into `Self`.
     ///
@@ -37,8 +38,40 @@ pub trait InOutFuncs {
     fn output(&self, buffer: &mut StringInfo);
 }
 
+/// `#[derive(Serialize, Deserialize, PostgresType)]` and `#[passedbyvalue]` types may implement this
+/// trait, along with the `#[text_inoutfuncs]` attribute macro, to parse and format their textual
+/// representation as plain Rust strings, such as `'1.5km'`
+pub trait TextInOutFuncs {
+    /// Given a string representation of `Self`, parse it into `Self`.
+    ///
+    /// It is expected that malformed input will raise an `error!()` or `panic!()`
+    fn input(input: &str) -> Self
+    where
+        Self: Sized;
+
+    /// Convert `Self` into text
+    fn output(&self) -> String;
+}
+
+/// `#[derive(rkyv::Archive, rkyv::Serialize, PostgresType)]` types with the `#[archived_inoutfuncs]`
+/// attribute macro implement this trait to provide their text input/output functions.  They're
+/// stored in `rkyv`'s archived format, and read without decoding through `PgArchived<Self>`
+#[cfg(feature = "rkyv")]
+pub trait ArchivedInOutFuncs: rkyv::Archive {
+    /// Given a string representation of `Self`, parse it into `Self`, which is then archived.
+    ///
+    /// It is expected that malformed input will raise an `error!()` or `panic!()`
+    fn input(input: &std::ffi::CStr) -> Self
+    where
+        Self: Sized;
+
+    /// Convert the archived form of `Self` into text by writing to the supplied `StringInfo` buffer
+    fn output(archived: &Self::Archived, buffer: &mut StringInfo);
+}
+
 /// Automatically implemented for `#[derive(Serialize, Deserialize, PostgresType)]` types that do
-/// **not** also have the `#[inoutfuncs]` attribute macro
+/// **not** also have the `#[inoutfuncs]`, `#[text_inoutfuncs]`, or `#[archived_inoutfuncs]` attribute
+/// macros
 pub trait JsonInOutFuncs<'de>: serde::de::Deserialize<'de> + serde::ser::Serialize {
     /// Uses `serde_json` to deserialize the input, which is assumed to be JSON
     fn input(input: &'de std::ffi::CStr) -> Self {
@@ -29,11 +29,30 @@ extern crate bitflags;
 // expose our various derive macros
 pub use pgx_macros::*;
 
+pub mod advisory_lock;
+pub mod backend;
 pub mod callbacks;
+pub mod clock;
 pub mod datum;
+#[cfg(any(
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15"
+))]
+pub mod dshash;
 pub mod enum_helper;
+pub mod eqfuncs;
+pub mod explain;
+pub mod extension;
 pub mod fcinfo;
+pub mod ffi_callback;
+pub mod fingerprint;
+pub mod function_registry;
+pub mod function_stats;
 pub mod guc;
+pub mod hash_table;
 pub mod hooks;
 pub mod htup;
 pub mod inoutfuncs;
@@ -41,39 +60,104 @@ pub mod itemptr;
 pub mod list;
 #[macro_use]
 pub mod log;
+pub mod lwlock;
+pub mod magic;
 pub mod bgworkers;
 pub mod memcxt;
+pub mod memory_budget;
+pub mod mock;
+pub mod name;
 pub mod namespace;
 pub mod nodes;
+pub mod parser;
 pub mod pgbox;
+pub mod portal;
+pub mod prepared_statement;
+pub mod queue;
+pub mod random;
+pub mod recovery;
+pub mod reload;
 pub mod rel;
+pub mod replication_origin;
+pub mod replication_slot;
+pub mod resowner;
+pub mod server_files;
+pub mod shmem;
+pub mod shmem_cache;
+pub mod snapshot;
 pub mod spi;
+pub mod stat_counters;
 pub mod stringinfo;
+pub mod subxact;
+pub mod tempfile;
 pub mod trigger_support;
 pub mod tupdesc;
+pub mod tuplesort;
+pub mod tuplestore;
+pub mod vacuum;
 pub mod varlena;
 pub mod xid;
 
+pub use advisory_lock::*;
 pub use callbacks::*;
 pub use datum::*;
+#[cfg(any(
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15"
+))]
+pub use dshash::*;
 pub use enum_helper::*;
+pub use eqfuncs::*;
+pub use explain::*;
+pub use extension::*;
 pub use fcinfo::*;
+pub use ffi_callback::*;
+pub use fingerprint::*;
+pub use function_registry::*;
+pub use function_stats::*;
 pub use guc::*;
+pub use hash_table::*;
 pub use hooks::*;
 pub use htup::*;
 pub use inoutfuncs::*;
 pub use itemptr::*;
 pub use list::*;
 pub use log::*;
+pub use lwlock::*;
+pub use magic::*;
 pub use memcxt::*;
+pub use memory_budget::*;
+pub use name::*;
 pub use namespace::*;
-pub use nodes::{is_a, PgNode, PgNodeFactory}; // be specific since we have multiple versions of these things behind feature gates
+pub use nodes::{
+    is_a, make_bool_expr, make_const, make_func_expr, make_null_const, make_target_entry, make_var,
+    make_var_for_attribute, PgExprNode, PgNode, PgNodeFactory,
+}; // be specific since we have multiple versions of these things behind feature gates
+pub use parser::*;
 pub use pgbox::*;
+pub use portal::*;
+pub use prepared_statement::*;
 pub use rel::*;
+pub use reload::*;
+pub use replication_origin::*;
+pub use replication_slot::*;
+pub use resowner::*;
+pub use server_files::*;
+pub use shmem::*;
+pub use shmem_cache::*;
 pub use spi::*;
+pub use stat_counters::*;
 pub use stringinfo::*;
+pub use subxact::*;
+pub use tempfile::*;
 pub use trigger_support::*;
 pub use tupdesc::*;
+pub use tuplesort::*;
+pub use tuplestore::*;
+pub use vacuum::*;
 pub use varlena::*;
 pub use xid::*;
 
@@ -111,9 +195,35 @@ macro_rules! pg_module_magic {
             // calls this function first
             pgx::initialize();
 
+            // remember what this library was built for, and refuse to load if that's not the
+            // extension schema that's installed.  Postgres calls this before `_PG_init()`
+            pgx::register_magic_block(&PGX_MAGIC_BLOCK);
+            pgx::pg_sys::guard(pgx::check_binary_compatibility);
+
             // return the magic
             &MY_MAGIC
         }
+
+        /// Describes the pgx, Postgres, and extension versions this library was built for, and the
+        /// git revision and cargo profile it was built from
+        #[no_mangle]
+        #[allow(unused)]
+        pub static PGX_MAGIC_BLOCK: pgx::PgxMagicBlock = pgx::PgxMagicBlock::new(
+            option_env!("PGX_EXTENSION_NAME"),
+            option_env!("PGX_EXTENSION_VERSION"),
+            option_env!("PGX_BUILD_GIT_REVISION"),
+            match option_env!("PGX_BUILD_PROFILE") {
+                Some(profile) => profile,
+                None if cfg!(debug_assertions) => "debug",
+                None => "release",
+            },
+        );
+
+        /// The `PG_VERSION_NUM` this library was built for, as a plain word so that tools like
+        /// `cargo pgx` can read it without loading the library
+        #[no_mangle]
+        #[allow(unused)]
+        pub static PGX_PG_VERSION_NUM: u32 = pgx::pg_sys::PG_VERSION_NUM;
     };
 }
 
@@ -65,16 +65,7 @@ impl<T> PgList<T> {
         if self.list.is_null() {
             None
         } else {
-            Some(unsafe {
-                self.list
-                    .as_ref()
-                    .unwrap()
-                    .head
-                    .as_ref()
-                    .unwrap()
-                    .data
-                    .ptr_value
-            } as *mut T)
+            Some(unsafe { pg_sys::list_nth(self.list, 0) } as *mut T)
         }
     }
 
@@ -83,16 +74,7 @@ impl<T> PgList<T> {
         if self.list.is_null() {
             None
         } else {
-            Some(unsafe {
-                self.list
-                    .as_ref()
-                    .unwrap()
-                    .tail
-                    .as_ref()
-                    .unwrap()
-                    .data
-                    .ptr_value
-            } as *mut T)
+            Some(unsafe { pg_sys::list_nth(self.list, (self.len() - 1) as i32) } as *mut T)
         }
     }
 
@@ -138,7 +120,7 @@ impl<T> PgList<T> {
     pub fn replace_ptr(&mut self, i: usize, with: *mut T) {
         unsafe {
             let cell = pg_sys::list_nth_cell(self.list, i as i32);
-            cell.as_mut().expect("cell is null").data.ptr_value = with as void_mut_ptr;
+            cell_data(cell).ptr_value = with as void_mut_ptr;
         }
     }
 
@@ -146,7 +128,7 @@ impl<T> PgList<T> {
     pub fn replace_int(&mut self, i: usize, with: i32) {
         unsafe {
             let cell = pg_sys::list_nth_cell(self.list, i as i32);
-            cell.as_mut().expect("cell is null").data.int_value = with;
+            cell_data(cell).int_value = with;
         }
     }
 
@@ -154,7 +136,7 @@ impl<T> PgList<T> {
     pub fn replace_oid(&mut self, i: usize, with: pg_sys::Oid) {
         unsafe {
             let cell = pg_sys::list_nth_cell(self.list, i as i32);
-            cell.as_mut().expect("cell is null").data.oid_value = with;
+            cell_data(cell).oid_value = with;
         }
     }
 
@@ -199,6 +181,19 @@ impl<T> PgList<T> {
     }
 }
 
+/// The union holding a `ListCell`'s value
+#[cfg(any(feature = "pg10", feature = "pg11", feature = "pg12"))]
+unsafe fn cell_data<'a>(cell: *mut pg_sys::ListCell) -> &'a mut pg_sys::ListCell__bindgen_ty_1 {
+    &mut cell.as_mut().expect("cell is null").data
+}
+
+/// The union holding a `ListCell`'s value.  Postgres 13 reimplemented `List` as an array, and
+/// its cells are now just that union
+#[cfg(any(feature = "pg13", feature = "pg14", feature = "pg15"))]
+unsafe fn cell_data<'a>(cell: *mut pg_sys::ListCell) -> &'a mut pg_sys::ListCell {
+    cell.as_mut().expect("cell is null")
+}
+
 struct PgListIteratorPtr<'a, T> {
     list: &'a PgList<T>,
     pos: usize,
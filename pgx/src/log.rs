@@ -2,6 +2,7 @@
 // governed by the MIT license that can be found in the LICENSE file.
 
 //! Access to Postgres' logging system
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Postgres' various logging levels
 #[allow(dead_code)]
@@ -51,9 +52,13 @@ pub enum PgLogLevel {
     PANIC = crate::pg_sys::PANIC as isize,
 }
 
-/// This list of SQL Error Codes is taken directly from Postgres 12's generated "utils/errcodes.h"
+/// This list of SQL Error Codes is taken directly from Postgres 12's generated "utils/errcodes.h",
+/// plus the few added since.
+///
+/// Use these both to raise errors, with `ereport()`, and to tell apart the errors caught with
+/// `PgTryResult::catch_error()`, with `from_sqlerrcode()` or `class()`
 #[allow(non_camel_case_types)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PgSqlErrorCode {
     /// Class 00 - Successful Completion
     ERRCODE_SUCCESSFUL_COMPLETION = MAKE_SQLSTATE('0', '0', '0', '0', '0') as isize,
@@ -368,6 +373,8 @@ pub enum PgSqlErrorCode {
     ERRCODE_CRASH_SHUTDOWN = MAKE_SQLSTATE('5', '7', 'P', '0', '2') as isize,
     ERRCODE_CANNOT_CONNECT_NOW = MAKE_SQLSTATE('5', '7', 'P', '0', '3') as isize,
     ERRCODE_DATABASE_DROPPED = MAKE_SQLSTATE('5', '7', 'P', '0', '4') as isize,
+    /// Postgres 14 and later
+    ERRCODE_IDLE_SESSION_TIMEOUT = MAKE_SQLSTATE('5', '7', 'P', '0', '5') as isize,
 
     /// Class 58 - System Error (errors external to PostgreSQL itself) as isize,
     ERRCODE_SYSTEM_ERROR = MAKE_SQLSTATE('5', '8', '0', '0', '0') as isize,
@@ -427,6 +434,503 @@ pub enum PgSqlErrorCode {
     ERRCODE_INDEX_CORRUPTED = MAKE_SQLSTATE('X', 'X', '0', '0', '2') as isize,
 }
 
+impl PgSqlErrorCode {
+    /// Every error code, in the order Postgres lists them
+    pub const ALL: &'static [PgSqlErrorCode] = &[
+        PgSqlErrorCode::ERRCODE_SUCCESSFUL_COMPLETION,
+        PgSqlErrorCode::ERRCODE_WARNING,
+        PgSqlErrorCode::ERRCODE_WARNING_DYNAMIC_RESULT_SETS_RETURNED,
+        PgSqlErrorCode::ERRCODE_WARNING_IMPLICIT_ZERO_BIT_PADDING,
+        PgSqlErrorCode::ERRCODE_WARNING_NULL_VALUE_ELIMINATED_IN_SET_FUNCTION,
+        PgSqlErrorCode::ERRCODE_WARNING_PRIVILEGE_NOT_GRANTED,
+        PgSqlErrorCode::ERRCODE_WARNING_PRIVILEGE_NOT_REVOKED,
+        PgSqlErrorCode::ERRCODE_WARNING_STRING_DATA_RIGHT_TRUNCATION,
+        PgSqlErrorCode::ERRCODE_WARNING_DEPRECATED_FEATURE,
+        PgSqlErrorCode::ERRCODE_NO_DATA,
+        PgSqlErrorCode::ERRCODE_NO_ADDITIONAL_DYNAMIC_RESULT_SETS_RETURNED,
+        PgSqlErrorCode::ERRCODE_SQL_STATEMENT_NOT_YET_COMPLETE,
+        PgSqlErrorCode::ERRCODE_CONNECTION_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_CONNECTION_DOES_NOT_EXIST,
+        PgSqlErrorCode::ERRCODE_CONNECTION_FAILURE,
+        PgSqlErrorCode::ERRCODE_SQLCLIENT_UNABLE_TO_ESTABLISH_SQLCONNECTION,
+        PgSqlErrorCode::ERRCODE_SQLSERVER_REJECTED_ESTABLISHMENT_OF_SQLCONNECTION,
+        PgSqlErrorCode::ERRCODE_TRANSACTION_RESOLUTION_UNKNOWN,
+        PgSqlErrorCode::ERRCODE_PROTOCOL_VIOLATION,
+        PgSqlErrorCode::ERRCODE_TRIGGERED_ACTION_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+        PgSqlErrorCode::ERRCODE_INVALID_TRANSACTION_INITIATION,
+        PgSqlErrorCode::ERRCODE_LOCATOR_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_L_E_INVALID_SPECIFICATION,
+        PgSqlErrorCode::ERRCODE_INVALID_GRANTOR,
+        PgSqlErrorCode::ERRCODE_INVALID_GRANT_OPERATION,
+        PgSqlErrorCode::ERRCODE_INVALID_ROLE_SPECIFICATION,
+        PgSqlErrorCode::ERRCODE_DIAGNOSTICS_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_STACKED_DIAGNOSTICS_ACCESSED_WITHOUT_ACTIVE_HANDLER,
+        PgSqlErrorCode::ERRCODE_CASE_NOT_FOUND,
+        PgSqlErrorCode::ERRCODE_CARDINALITY_VIOLATION,
+        PgSqlErrorCode::ERRCODE_DATA_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_ARRAY_ELEMENT_ERROR,
+        PgSqlErrorCode::ERRCODE_CHARACTER_NOT_IN_REPERTOIRE,
+        PgSqlErrorCode::ERRCODE_DATETIME_FIELD_OVERFLOW,
+        PgSqlErrorCode::ERRCODE_DIVISION_BY_ZERO,
+        PgSqlErrorCode::ERRCODE_ERROR_IN_ASSIGNMENT,
+        PgSqlErrorCode::ERRCODE_ESCAPE_CHARACTER_CONFLICT,
+        PgSqlErrorCode::ERRCODE_INDICATOR_OVERFLOW,
+        PgSqlErrorCode::ERRCODE_INTERVAL_FIELD_OVERFLOW,
+        PgSqlErrorCode::ERRCODE_INVALID_ARGUMENT_FOR_LOG,
+        PgSqlErrorCode::ERRCODE_INVALID_ARGUMENT_FOR_NTILE,
+        PgSqlErrorCode::ERRCODE_INVALID_ARGUMENT_FOR_NTH_VALUE,
+        PgSqlErrorCode::ERRCODE_INVALID_ARGUMENT_FOR_POWER_FUNCTION,
+        PgSqlErrorCode::ERRCODE_INVALID_ARGUMENT_FOR_WIDTH_BUCKET_FUNCTION,
+        PgSqlErrorCode::ERRCODE_INVALID_CHARACTER_VALUE_FOR_CAST,
+        PgSqlErrorCode::ERRCODE_INVALID_DATETIME_FORMAT,
+        PgSqlErrorCode::ERRCODE_INVALID_ESCAPE_CHARACTER,
+        PgSqlErrorCode::ERRCODE_INVALID_ESCAPE_OCTET,
+        PgSqlErrorCode::ERRCODE_INVALID_ESCAPE_SEQUENCE,
+        PgSqlErrorCode::ERRCODE_NONSTANDARD_USE_OF_ESCAPE_CHARACTER,
+        PgSqlErrorCode::ERRCODE_INVALID_INDICATOR_PARAMETER_VALUE,
+        PgSqlErrorCode::ERRCODE_INVALID_PARAMETER_VALUE,
+        PgSqlErrorCode::ERRCODE_INVALID_PRECEDING_OR_FOLLOWING_SIZE,
+        PgSqlErrorCode::ERRCODE_INVALID_REGULAR_EXPRESSION,
+        PgSqlErrorCode::ERRCODE_INVALID_ROW_COUNT_IN_LIMIT_CLAUSE,
+        PgSqlErrorCode::ERRCODE_INVALID_ROW_COUNT_IN_RESULT_OFFSET_CLAUSE,
+        PgSqlErrorCode::ERRCODE_INVALID_TABLESAMPLE_ARGUMENT,
+        PgSqlErrorCode::ERRCODE_INVALID_TABLESAMPLE_REPEAT,
+        PgSqlErrorCode::ERRCODE_INVALID_TIME_ZONE_DISPLACEMENT_VALUE,
+        PgSqlErrorCode::ERRCODE_INVALID_USE_OF_ESCAPE_CHARACTER,
+        PgSqlErrorCode::ERRCODE_MOST_SPECIFIC_TYPE_MISMATCH,
+        PgSqlErrorCode::ERRCODE_NULL_VALUE_NOT_ALLOWED,
+        PgSqlErrorCode::ERRCODE_NULL_VALUE_NO_INDICATOR_PARAMETER,
+        PgSqlErrorCode::ERRCODE_NUMERIC_VALUE_OUT_OF_RANGE,
+        PgSqlErrorCode::ERRCODE_SEQUENCE_GENERATOR_LIMIT_EXCEEDED,
+        PgSqlErrorCode::ERRCODE_STRING_DATA_LENGTH_MISMATCH,
+        PgSqlErrorCode::ERRCODE_STRING_DATA_RIGHT_TRUNCATION,
+        PgSqlErrorCode::ERRCODE_SUBSTRING_ERROR,
+        PgSqlErrorCode::ERRCODE_TRIM_ERROR,
+        PgSqlErrorCode::ERRCODE_UNTERMINATED_C_STRING,
+        PgSqlErrorCode::ERRCODE_ZERO_LENGTH_CHARACTER_STRING,
+        PgSqlErrorCode::ERRCODE_FLOATING_POINT_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_INVALID_TEXT_REPRESENTATION,
+        PgSqlErrorCode::ERRCODE_INVALID_BINARY_REPRESENTATION,
+        PgSqlErrorCode::ERRCODE_BAD_COPY_FILE_FORMAT,
+        PgSqlErrorCode::ERRCODE_UNTRANSLATABLE_CHARACTER,
+        PgSqlErrorCode::ERRCODE_NOT_AN_XML_DOCUMENT,
+        PgSqlErrorCode::ERRCODE_INVALID_XML_DOCUMENT,
+        PgSqlErrorCode::ERRCODE_INVALID_XML_CONTENT,
+        PgSqlErrorCode::ERRCODE_INVALID_XML_COMMENT,
+        PgSqlErrorCode::ERRCODE_INVALID_XML_PROCESSING_INSTRUCTION,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_JSON_OBJECT_KEY_VALUE,
+        PgSqlErrorCode::ERRCODE_INVALID_JSON_TEXT,
+        PgSqlErrorCode::ERRCODE_INVALID_SQL_JSON_SUBSCRIPT,
+        PgSqlErrorCode::ERRCODE_MORE_THAN_ONE_SQL_JSON_ITEM,
+        PgSqlErrorCode::ERRCODE_NO_SQL_JSON_ITEM,
+        PgSqlErrorCode::ERRCODE_NON_NUMERIC_SQL_JSON_ITEM,
+        PgSqlErrorCode::ERRCODE_NON_UNIQUE_KEYS_IN_A_JSON_OBJECT,
+        PgSqlErrorCode::ERRCODE_SINGLETON_SQL_JSON_ITEM_REQUIRED,
+        PgSqlErrorCode::ERRCODE_SQL_JSON_ARRAY_NOT_FOUND,
+        PgSqlErrorCode::ERRCODE_SQL_JSON_MEMBER_NOT_FOUND,
+        PgSqlErrorCode::ERRCODE_SQL_JSON_NUMBER_NOT_FOUND,
+        PgSqlErrorCode::ERRCODE_SQL_JSON_OBJECT_NOT_FOUND,
+        PgSqlErrorCode::ERRCODE_TOO_MANY_JSON_ARRAY_ELEMENTS,
+        PgSqlErrorCode::ERRCODE_TOO_MANY_JSON_OBJECT_MEMBERS,
+        PgSqlErrorCode::ERRCODE_SQL_JSON_SCALAR_REQUIRED,
+        PgSqlErrorCode::ERRCODE_INTEGRITY_CONSTRAINT_VIOLATION,
+        PgSqlErrorCode::ERRCODE_RESTRICT_VIOLATION,
+        PgSqlErrorCode::ERRCODE_NOT_NULL_VIOLATION,
+        PgSqlErrorCode::ERRCODE_FOREIGN_KEY_VIOLATION,
+        PgSqlErrorCode::ERRCODE_UNIQUE_VIOLATION,
+        PgSqlErrorCode::ERRCODE_CHECK_VIOLATION,
+        PgSqlErrorCode::ERRCODE_EXCLUSION_VIOLATION,
+        PgSqlErrorCode::ERRCODE_INVALID_CURSOR_STATE,
+        PgSqlErrorCode::ERRCODE_INVALID_TRANSACTION_STATE,
+        PgSqlErrorCode::ERRCODE_ACTIVE_SQL_TRANSACTION,
+        PgSqlErrorCode::ERRCODE_BRANCH_TRANSACTION_ALREADY_ACTIVE,
+        PgSqlErrorCode::ERRCODE_HELD_CURSOR_REQUIRES_SAME_ISOLATION_LEVEL,
+        PgSqlErrorCode::ERRCODE_INAPPROPRIATE_ACCESS_MODE_FOR_BRANCH_TRANSACTION,
+        PgSqlErrorCode::ERRCODE_INAPPROPRIATE_ISOLATION_LEVEL_FOR_BRANCH_TRANSACTION,
+        PgSqlErrorCode::ERRCODE_NO_ACTIVE_SQL_TRANSACTION_FOR_BRANCH_TRANSACTION,
+        PgSqlErrorCode::ERRCODE_READ_ONLY_SQL_TRANSACTION,
+        PgSqlErrorCode::ERRCODE_SCHEMA_AND_DATA_STATEMENT_MIXING_NOT_SUPPORTED,
+        PgSqlErrorCode::ERRCODE_NO_ACTIVE_SQL_TRANSACTION,
+        PgSqlErrorCode::ERRCODE_IN_FAILED_SQL_TRANSACTION,
+        PgSqlErrorCode::ERRCODE_IDLE_IN_TRANSACTION_SESSION_TIMEOUT,
+        PgSqlErrorCode::ERRCODE_INVALID_SQL_STATEMENT_NAME,
+        PgSqlErrorCode::ERRCODE_TRIGGERED_DATA_CHANGE_VIOLATION,
+        PgSqlErrorCode::ERRCODE_INVALID_AUTHORIZATION_SPECIFICATION,
+        PgSqlErrorCode::ERRCODE_INVALID_PASSWORD,
+        PgSqlErrorCode::ERRCODE_DEPENDENT_PRIVILEGE_DESCRIPTORS_STILL_EXIST,
+        PgSqlErrorCode::ERRCODE_DEPENDENT_OBJECTS_STILL_EXIST,
+        PgSqlErrorCode::ERRCODE_INVALID_TRANSACTION_TERMINATION,
+        PgSqlErrorCode::ERRCODE_SQL_ROUTINE_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_S_R_E_FUNCTION_EXECUTED_NO_RETURN_STATEMENT,
+        PgSqlErrorCode::ERRCODE_S_R_E_MODIFYING_SQL_DATA_NOT_PERMITTED,
+        PgSqlErrorCode::ERRCODE_S_R_E_PROHIBITED_SQL_STATEMENT_ATTEMPTED,
+        PgSqlErrorCode::ERRCODE_S_R_E_READING_SQL_DATA_NOT_PERMITTED,
+        PgSqlErrorCode::ERRCODE_INVALID_CURSOR_NAME,
+        PgSqlErrorCode::ERRCODE_EXTERNAL_ROUTINE_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_E_R_E_CONTAINING_SQL_NOT_PERMITTED,
+        PgSqlErrorCode::ERRCODE_E_R_E_MODIFYING_SQL_DATA_NOT_PERMITTED,
+        PgSqlErrorCode::ERRCODE_E_R_E_PROHIBITED_SQL_STATEMENT_ATTEMPTED,
+        PgSqlErrorCode::ERRCODE_E_R_E_READING_SQL_DATA_NOT_PERMITTED,
+        PgSqlErrorCode::ERRCODE_EXTERNAL_ROUTINE_INVOCATION_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_E_R_I_E_INVALID_SQLSTATE_RETURNED,
+        PgSqlErrorCode::ERRCODE_E_R_I_E_NULL_VALUE_NOT_ALLOWED,
+        PgSqlErrorCode::ERRCODE_E_R_I_E_TRIGGER_PROTOCOL_VIOLATED,
+        PgSqlErrorCode::ERRCODE_E_R_I_E_SRF_PROTOCOL_VIOLATED,
+        PgSqlErrorCode::ERRCODE_E_R_I_E_EVENT_TRIGGER_PROTOCOL_VIOLATED,
+        PgSqlErrorCode::ERRCODE_SAVEPOINT_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_S_E_INVALID_SPECIFICATION,
+        PgSqlErrorCode::ERRCODE_INVALID_CATALOG_NAME,
+        PgSqlErrorCode::ERRCODE_INVALID_SCHEMA_NAME,
+        PgSqlErrorCode::ERRCODE_TRANSACTION_ROLLBACK,
+        PgSqlErrorCode::ERRCODE_T_R_INTEGRITY_CONSTRAINT_VIOLATION,
+        PgSqlErrorCode::ERRCODE_T_R_SERIALIZATION_FAILURE,
+        PgSqlErrorCode::ERRCODE_T_R_STATEMENT_COMPLETION_UNKNOWN,
+        PgSqlErrorCode::ERRCODE_T_R_DEADLOCK_DETECTED,
+        PgSqlErrorCode::ERRCODE_SYNTAX_ERROR_OR_ACCESS_RULE_VIOLATION,
+        PgSqlErrorCode::ERRCODE_SYNTAX_ERROR,
+        PgSqlErrorCode::ERRCODE_INSUFFICIENT_PRIVILEGE,
+        PgSqlErrorCode::ERRCODE_CANNOT_COERCE,
+        PgSqlErrorCode::ERRCODE_GROUPING_ERROR,
+        PgSqlErrorCode::ERRCODE_WINDOWING_ERROR,
+        PgSqlErrorCode::ERRCODE_INVALID_RECURSION,
+        PgSqlErrorCode::ERRCODE_INVALID_FOREIGN_KEY,
+        PgSqlErrorCode::ERRCODE_INVALID_NAME,
+        PgSqlErrorCode::ERRCODE_NAME_TOO_LONG,
+        PgSqlErrorCode::ERRCODE_RESERVED_NAME,
+        PgSqlErrorCode::ERRCODE_DATATYPE_MISMATCH,
+        PgSqlErrorCode::ERRCODE_INDETERMINATE_DATATYPE,
+        PgSqlErrorCode::ERRCODE_COLLATION_MISMATCH,
+        PgSqlErrorCode::ERRCODE_INDETERMINATE_COLLATION,
+        PgSqlErrorCode::ERRCODE_WRONG_OBJECT_TYPE,
+        PgSqlErrorCode::ERRCODE_GENERATED_ALWAYS,
+        PgSqlErrorCode::ERRCODE_UNDEFINED_COLUMN,
+        PgSqlErrorCode::ERRCODE_UNDEFINED_FUNCTION,
+        PgSqlErrorCode::ERRCODE_UNDEFINED_TABLE,
+        PgSqlErrorCode::ERRCODE_UNDEFINED_PARAMETER,
+        PgSqlErrorCode::ERRCODE_UNDEFINED_OBJECT,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_COLUMN,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_CURSOR,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_DATABASE,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_FUNCTION,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_PSTATEMENT,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_SCHEMA,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_TABLE,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_ALIAS,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_OBJECT,
+        PgSqlErrorCode::ERRCODE_AMBIGUOUS_COLUMN,
+        PgSqlErrorCode::ERRCODE_AMBIGUOUS_FUNCTION,
+        PgSqlErrorCode::ERRCODE_AMBIGUOUS_PARAMETER,
+        PgSqlErrorCode::ERRCODE_AMBIGUOUS_ALIAS,
+        PgSqlErrorCode::ERRCODE_INVALID_COLUMN_REFERENCE,
+        PgSqlErrorCode::ERRCODE_INVALID_COLUMN_DEFINITION,
+        PgSqlErrorCode::ERRCODE_INVALID_CURSOR_DEFINITION,
+        PgSqlErrorCode::ERRCODE_INVALID_DATABASE_DEFINITION,
+        PgSqlErrorCode::ERRCODE_INVALID_FUNCTION_DEFINITION,
+        PgSqlErrorCode::ERRCODE_INVALID_PSTATEMENT_DEFINITION,
+        PgSqlErrorCode::ERRCODE_INVALID_SCHEMA_DEFINITION,
+        PgSqlErrorCode::ERRCODE_INVALID_TABLE_DEFINITION,
+        PgSqlErrorCode::ERRCODE_INVALID_OBJECT_DEFINITION,
+        PgSqlErrorCode::ERRCODE_WITH_CHECK_OPTION_VIOLATION,
+        PgSqlErrorCode::ERRCODE_INSUFFICIENT_RESOURCES,
+        PgSqlErrorCode::ERRCODE_DISK_FULL,
+        PgSqlErrorCode::ERRCODE_OUT_OF_MEMORY,
+        PgSqlErrorCode::ERRCODE_TOO_MANY_CONNECTIONS,
+        PgSqlErrorCode::ERRCODE_CONFIGURATION_LIMIT_EXCEEDED,
+        PgSqlErrorCode::ERRCODE_PROGRAM_LIMIT_EXCEEDED,
+        PgSqlErrorCode::ERRCODE_STATEMENT_TOO_COMPLEX,
+        PgSqlErrorCode::ERRCODE_TOO_MANY_COLUMNS,
+        PgSqlErrorCode::ERRCODE_TOO_MANY_ARGUMENTS,
+        PgSqlErrorCode::ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE,
+        PgSqlErrorCode::ERRCODE_OBJECT_IN_USE,
+        PgSqlErrorCode::ERRCODE_CANT_CHANGE_RUNTIME_PARAM,
+        PgSqlErrorCode::ERRCODE_LOCK_NOT_AVAILABLE,
+        PgSqlErrorCode::ERRCODE_UNSAFE_NEW_ENUM_VALUE_USAGE,
+        PgSqlErrorCode::ERRCODE_OPERATOR_INTERVENTION,
+        PgSqlErrorCode::ERRCODE_QUERY_CANCELED,
+        PgSqlErrorCode::ERRCODE_ADMIN_SHUTDOWN,
+        PgSqlErrorCode::ERRCODE_CRASH_SHUTDOWN,
+        PgSqlErrorCode::ERRCODE_CANNOT_CONNECT_NOW,
+        PgSqlErrorCode::ERRCODE_DATABASE_DROPPED,
+        PgSqlErrorCode::ERRCODE_IDLE_SESSION_TIMEOUT,
+        PgSqlErrorCode::ERRCODE_SYSTEM_ERROR,
+        PgSqlErrorCode::ERRCODE_IO_ERROR,
+        PgSqlErrorCode::ERRCODE_UNDEFINED_FILE,
+        PgSqlErrorCode::ERRCODE_DUPLICATE_FILE,
+        PgSqlErrorCode::ERRCODE_SNAPSHOT_TOO_OLD,
+        PgSqlErrorCode::ERRCODE_CONFIG_FILE_ERROR,
+        PgSqlErrorCode::ERRCODE_LOCK_FILE_EXISTS,
+        PgSqlErrorCode::ERRCODE_FDW_ERROR,
+        PgSqlErrorCode::ERRCODE_FDW_COLUMN_NAME_NOT_FOUND,
+        PgSqlErrorCode::ERRCODE_FDW_DYNAMIC_PARAMETER_VALUE_NEEDED,
+        PgSqlErrorCode::ERRCODE_FDW_FUNCTION_SEQUENCE_ERROR,
+        PgSqlErrorCode::ERRCODE_FDW_INCONSISTENT_DESCRIPTOR_INFORMATION,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_ATTRIBUTE_VALUE,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_COLUMN_NAME,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_COLUMN_NUMBER,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_DATA_TYPE,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_DATA_TYPE_DESCRIPTORS,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_DESCRIPTOR_FIELD_IDENTIFIER,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_HANDLE,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_OPTION_INDEX,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_OPTION_NAME,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_STRING_LENGTH_OR_BUFFER_LENGTH,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_STRING_FORMAT,
+        PgSqlErrorCode::ERRCODE_FDW_INVALID_USE_OF_NULL_POINTER,
+        PgSqlErrorCode::ERRCODE_FDW_TOO_MANY_HANDLES,
+        PgSqlErrorCode::ERRCODE_FDW_OUT_OF_MEMORY,
+        PgSqlErrorCode::ERRCODE_FDW_NO_SCHEMAS,
+        PgSqlErrorCode::ERRCODE_FDW_OPTION_NAME_NOT_FOUND,
+        PgSqlErrorCode::ERRCODE_FDW_REPLY_HANDLE,
+        PgSqlErrorCode::ERRCODE_FDW_SCHEMA_NOT_FOUND,
+        PgSqlErrorCode::ERRCODE_FDW_TABLE_NOT_FOUND,
+        PgSqlErrorCode::ERRCODE_FDW_UNABLE_TO_CREATE_EXECUTION,
+        PgSqlErrorCode::ERRCODE_FDW_UNABLE_TO_CREATE_REPLY,
+        PgSqlErrorCode::ERRCODE_FDW_UNABLE_TO_ESTABLISH_CONNECTION,
+        PgSqlErrorCode::ERRCODE_PLPGSQL_ERROR,
+        PgSqlErrorCode::ERRCODE_RAISE_EXCEPTION,
+        PgSqlErrorCode::ERRCODE_NO_DATA_FOUND,
+        PgSqlErrorCode::ERRCODE_TOO_MANY_ROWS,
+        PgSqlErrorCode::ERRCODE_ASSERT_FAILURE,
+        PgSqlErrorCode::ERRCODE_INTERNAL_ERROR,
+        PgSqlErrorCode::ERRCODE_DATA_CORRUPTED,
+        PgSqlErrorCode::ERRCODE_INDEX_CORRUPTED,
+    ];
+
+    /// The code packed into an integer, as it is in Postgres' `ErrorData.sqlerrcode` and
+    /// `CaughtError::sqlerrcode`
+    pub fn sqlerrcode(self) -> i32 {
+        self as i32
+    }
+
+    /// The five-character `SQLSTATE`, such as `23505` for `ERRCODE_UNIQUE_VIOLATION`
+    pub fn sqlstate(self) -> String {
+        unpack_sqlstate(self as i32)
+    }
+
+    /// The error code for a packed `sqlerrcode`, such as a `CaughtError`'s, or `None` if it's not
+    /// one Postgres defines
+    pub fn from_sqlerrcode(sqlerrcode: i32) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|code| *code as i32 == sqlerrcode)
+    }
+
+    /// The error code for a five-character `SQLSTATE`, such as `40001`
+    pub fn from_sqlstate(sqlstate: &str) -> Option<Self> {
+        pack_sqlstate(sqlstate).and_then(Self::from_sqlerrcode)
+    }
+
+    /// The class of error this is, named by the first two characters of its `SQLSTATE`
+    pub fn class(self) -> SqlStateClass {
+        SqlStateClass::from_sqlerrcode(self as i32).expect("error code has no class")
+    }
+}
+
+/// The classes `SQLSTATE`s are grouped into, named by their first two characters.  Matching on the
+/// class of a caught error handles all of its codes, including ones newer than this list
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SqlStateClass {
+    /// Class 00 - Successful Completion
+    SuccessfulCompletion = MAKE_SQLSTATE('0', '0', '0', '0', '0') as isize,
+
+    /// Class 01 - Warning
+    Warning = MAKE_SQLSTATE('0', '1', '0', '0', '0') as isize,
+
+    /// Class 02 - No Data
+    NoData = MAKE_SQLSTATE('0', '2', '0', '0', '0') as isize,
+
+    /// Class 03 - SQL Statement Not Yet Complete
+    SqlStatementNotYetComplete = MAKE_SQLSTATE('0', '3', '0', '0', '0') as isize,
+
+    /// Class 08 - Connection Exception
+    ConnectionException = MAKE_SQLSTATE('0', '8', '0', '0', '0') as isize,
+
+    /// Class 09 - Triggered Action Exception
+    TriggeredActionException = MAKE_SQLSTATE('0', '9', '0', '0', '0') as isize,
+
+    /// Class 0A - Feature Not Supported
+    FeatureNotSupported = MAKE_SQLSTATE('0', 'A', '0', '0', '0') as isize,
+
+    /// Class 0B - Invalid Transaction Initiation
+    InvalidTransactionInitiation = MAKE_SQLSTATE('0', 'B', '0', '0', '0') as isize,
+
+    /// Class 0F - Locator Exception
+    LocatorException = MAKE_SQLSTATE('0', 'F', '0', '0', '0') as isize,
+
+    /// Class 0L - Invalid Grantor
+    InvalidGrantor = MAKE_SQLSTATE('0', 'L', '0', '0', '0') as isize,
+
+    /// Class 0P - Invalid Role Specification
+    InvalidRoleSpecification = MAKE_SQLSTATE('0', 'P', '0', '0', '0') as isize,
+
+    /// Class 0Z - Diagnostics Exception
+    DiagnosticsException = MAKE_SQLSTATE('0', 'Z', '0', '0', '0') as isize,
+
+    /// Class 20 - Case Not Found
+    CaseNotFound = MAKE_SQLSTATE('2', '0', '0', '0', '0') as isize,
+
+    /// Class 21 - Cardinality Violation
+    CardinalityViolation = MAKE_SQLSTATE('2', '1', '0', '0', '0') as isize,
+
+    /// Class 22 - Data Exception
+    DataException = MAKE_SQLSTATE('2', '2', '0', '0', '0') as isize,
+
+    /// Class 23 - Integrity Constraint Violation
+    IntegrityConstraintViolation = MAKE_SQLSTATE('2', '3', '0', '0', '0') as isize,
+
+    /// Class 24 - Invalid Cursor State
+    InvalidCursorState = MAKE_SQLSTATE('2', '4', '0', '0', '0') as isize,
+
+    /// Class 25 - Invalid Transaction State
+    InvalidTransactionState = MAKE_SQLSTATE('2', '5', '0', '0', '0') as isize,
+
+    /// Class 26 - Invalid SQL Statement Name
+    InvalidSqlStatementName = MAKE_SQLSTATE('2', '6', '0', '0', '0') as isize,
+
+    /// Class 27 - Triggered Data Change Violation
+    TriggeredDataChangeViolation = MAKE_SQLSTATE('2', '7', '0', '0', '0') as isize,
+
+    /// Class 28 - Invalid Authorization Specification
+    InvalidAuthorizationSpecification = MAKE_SQLSTATE('2', '8', '0', '0', '0') as isize,
+
+    /// Class 2B - Dependent Privilege Descriptors Still Exist
+    DependentPrivilegeDescriptorsStillExist = MAKE_SQLSTATE('2', 'B', '0', '0', '0') as isize,
+
+    /// Class 2D - Invalid Transaction Termination
+    InvalidTransactionTermination = MAKE_SQLSTATE('2', 'D', '0', '0', '0') as isize,
+
+    /// Class 2F - SQL Routine Exception
+    SqlRoutineException = MAKE_SQLSTATE('2', 'F', '0', '0', '0') as isize,
+
+    /// Class 34 - Invalid Cursor Name
+    InvalidCursorName = MAKE_SQLSTATE('3', '4', '0', '0', '0') as isize,
+
+    /// Class 38 - External Routine Exception
+    ExternalRoutineException = MAKE_SQLSTATE('3', '8', '0', '0', '0') as isize,
+
+    /// Class 39 - External Routine Invocation Exception
+    ExternalRoutineInvocationException = MAKE_SQLSTATE('3', '9', '0', '0', '0') as isize,
+
+    /// Class 3B - Savepoint Exception
+    SavepointException = MAKE_SQLSTATE('3', 'B', '0', '0', '0') as isize,
+
+    /// Class 3D - Invalid Catalog Name
+    InvalidCatalogName = MAKE_SQLSTATE('3', 'D', '0', '0', '0') as isize,
+
+    /// Class 3F - Invalid Schema Name
+    InvalidSchemaName = MAKE_SQLSTATE('3', 'F', '0', '0', '0') as isize,
+
+    /// Class 40 - Transaction Rollback
+    TransactionRollback = MAKE_SQLSTATE('4', '0', '0', '0', '0') as isize,
+
+    /// Class 42 - Syntax Error or Access Rule Violation
+    SyntaxErrorOrAccessRuleViolation = MAKE_SQLSTATE('4', '2', '0', '0', '0') as isize,
+
+    /// Class 44 - WITH CHECK OPTION Violation
+    WithCheckOptionViolation = MAKE_SQLSTATE('4', '4', '0', '0', '0') as isize,
+
+    /// Class 53 - Insufficient Resources
+    InsufficientResources = MAKE_SQLSTATE('5', '3', '0', '0', '0') as isize,
+
+    /// Class 54 - Program Limit Exceeded
+    ProgramLimitExceeded = MAKE_SQLSTATE('5', '4', '0', '0', '0') as isize,
+
+    /// Class 55 - Object Not In Prerequisite State
+    ObjectNotInPrerequisiteState = MAKE_SQLSTATE('5', '5', '0', '0', '0') as isize,
+
+    /// Class 57 - Operator Intervention
+    OperatorIntervention = MAKE_SQLSTATE('5', '7', '0', '0', '0') as isize,
+
+    /// Class 58 - System Error
+    SystemError = MAKE_SQLSTATE('5', '8', '0', '0', '0') as isize,
+
+    /// Class 72 - Snapshot Failure
+    SnapshotFailure = MAKE_SQLSTATE('7', '2', '0', '0', '0') as isize,
+
+    /// Class F0 - Configuration File Error
+    ConfigFileError = MAKE_SQLSTATE('F', '0', '0', '0', '0') as isize,
+
+    /// Class HV - Foreign Data Wrapper Error
+    FdwError = MAKE_SQLSTATE('H', 'V', '0', '0', '0') as isize,
+
+    /// Class P0 - PL/pgSQL Error
+    PlpgsqlError = MAKE_SQLSTATE('P', '0', '0', '0', '0') as isize,
+
+    /// Class XX - Internal Error
+    InternalError = MAKE_SQLSTATE('X', 'X', '0', '0', '0') as isize,
+}
+
+impl SqlStateClass {
+    /// Every class, in the order Postgres lists them
+    pub const ALL: &'static [SqlStateClass] = &[
+        SqlStateClass::SuccessfulCompletion,
+        SqlStateClass::Warning,
+        SqlStateClass::NoData,
+        SqlStateClass::SqlStatementNotYetComplete,
+        SqlStateClass::ConnectionException,
+        SqlStateClass::TriggeredActionException,
+        SqlStateClass::FeatureNotSupported,
+        SqlStateClass::InvalidTransactionInitiation,
+        SqlStateClass::LocatorException,
+        SqlStateClass::InvalidGrantor,
+        SqlStateClass::InvalidRoleSpecification,
+        SqlStateClass::DiagnosticsException,
+        SqlStateClass::CaseNotFound,
+        SqlStateClass::CardinalityViolation,
+        SqlStateClass::DataException,
+        SqlStateClass::IntegrityConstraintViolation,
+        SqlStateClass::InvalidCursorState,
+        SqlStateClass::InvalidTransactionState,
+        SqlStateClass::InvalidSqlStatementName,
+        SqlStateClass::TriggeredDataChangeViolation,
+        SqlStateClass::InvalidAuthorizationSpecification,
+        SqlStateClass::DependentPrivilegeDescriptorsStillExist,
+        SqlStateClass::InvalidTransactionTermination,
+        SqlStateClass::SqlRoutineException,
+        SqlStateClass::InvalidCursorName,
+        SqlStateClass::ExternalRoutineException,
+        SqlStateClass::ExternalRoutineInvocationException,
+        SqlStateClass::SavepointException,
+        SqlStateClass::InvalidCatalogName,
+        SqlStateClass::InvalidSchemaName,
+        SqlStateClass::TransactionRollback,
+        SqlStateClass::SyntaxErrorOrAccessRuleViolation,
+        SqlStateClass::WithCheckOptionViolation,
+        SqlStateClass::InsufficientResources,
+        SqlStateClass::ProgramLimitExceeded,
+        SqlStateClass::ObjectNotInPrerequisiteState,
+        SqlStateClass::OperatorIntervention,
+        SqlStateClass::SystemError,
+        SqlStateClass::SnapshotFailure,
+        SqlStateClass::ConfigFileError,
+        SqlStateClass::FdwError,
+        SqlStateClass::PlpgsqlError,
+        SqlStateClass::InternalError,
+    ];
+
+    /// The two-character class code, such as `40` for `TransactionRollback`
+    pub fn code(self) -> String {
+        unpack_sqlstate(self as i32)[..2].to_string()
+    }
+
+    /// The class of a packed `sqlerrcode`, such as a `CaughtError`'s
+    pub fn from_sqlerrcode(sqlerrcode: i32) -> Option<Self> {
+        // as ERRCODE_TO_CATEGORY() does, keep the first two characters
+        let category = sqlerrcode & ((1 << 12) - 1);
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|class| *class as i32 == category)
+    }
+
+    /// The class of a five-character `SQLSTATE`, such as `40001`
+    pub fn from_sqlstate(sqlstate: &str) -> Option<Self> {
+        pack_sqlstate(sqlstate).and_then(Self::from_sqlerrcode)
+    }
+}
+
 #[allow(non_snake_case)]
 #[inline]
 const fn PGSIXBIT(ch: i32) -> i32 {
@@ -443,6 +947,86 @@ const fn MAKE_SQLSTATE(ch1: char, ch2: char, ch3: char, ch4: char, ch5: char) ->
         + (PGSIXBIT(ch5 as i32) << 24)) as i32
 }
 
+/// The reverse of `MAKE_SQLSTATE()`, as Postgres' `unpack_sql_state()`
+fn unpack_sqlstate(sqlerrcode: i32) -> String {
+    (0..5)
+        .map(|i| (((sqlerrcode >> (6 * i)) & 0x3F) as u8 + b'0') as char)
+        .collect()
+}
+
+/// `MAKE_SQLSTATE()` for a five-character `SQLSTATE` string, if it is one
+fn pack_sqlstate(sqlstate: &str) -> Option<i32> {
+    let chars = sqlstate.to_ascii_uppercase().chars().collect::<Vec<_>>();
+    let valid = |c: &char| c.is_ascii_digit() || c.is_ascii_uppercase();
+    if chars.len() != 5 || !chars.iter().all(valid) {
+        return None;
+    }
+    Some(MAKE_SQLSTATE(
+        chars[0], chars[1], chars[2], chars[3], chars[4],
+    ))
+}
+
+/// Would a message at `level` be sent to the server log or to the client?  That's the case if
+/// it's at or above `log_min_messages` or `client_min_messages`.
+///
+/// Use this to skip building expensive log messages that would only be thrown away.  The
+/// `debug1!()` through `debug5!()` macros already check it
+pub fn log_level_enabled(level: PgLogLevel) -> bool {
+    let elevel = level as i32;
+    let (log_min_messages, client_min_messages) = unsafe {
+        (
+            crate::pg_sys::log_min_messages,
+            crate::pg_sys::client_min_messages,
+        )
+    };
+
+    // LOG sorts below WARNING, but goes to the server log as if it were just below FATAL
+    let log_output = match (elevel, log_min_messages) {
+        (e, min) if e == PgLogLevel::LOG as i32 || e == PgLogLevel::LOG_SERVER_ONLY as i32 => {
+            min == PgLogLevel::LOG as i32 || min <= PgLogLevel::ERROR as i32
+        }
+        (e, min) if min == PgLogLevel::LOG as i32 => e >= PgLogLevel::FATAL as i32,
+        (e, min) => e >= min,
+    };
+
+    // and INFO always goes to the client
+    log_output || elevel == PgLogLevel::INFO as i32 || elevel >= client_min_messages
+}
+
+/// Per-call-site state for the rate-limited logging macros, `warn_once!()` and `log_every!()`
+pub struct PgLogSampler {
+    calls: AtomicU64,
+}
+
+impl PgLogSampler {
+    pub const fn new() -> Self {
+        PgLogSampler {
+            calls: AtomicU64::new(0),
+        }
+    }
+
+    /// Count a call, returning true if it should be logged:  the first one, and then every
+    /// `every`th one after that.  An `every` of zero logs only the first call
+    pub fn sample(&self, every: u64) -> bool {
+        let calls = self.calls.fetch_add(1, Ordering::Relaxed);
+        match every {
+            0 => calls == 0,
+            every => calls % every == 0,
+        }
+    }
+
+    /// How many times this call site has been reached
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for PgLogSampler {
+    fn default() -> Self {
+        PgLogSampler::new()
+    }
+}
+
 /// Emit a Postgres log message.
 ///
 /// Log messages of level `pg_sys::ERROR` will cause the current transaction to abort
@@ -525,78 +1109,411 @@ pub fn ereport(
     }
 }
 
-/// Log to Postgres' `debug5` log level
+/// Emit a Postgres `ereport` message with a `DETAIL` line.
+///
+/// Messages of level `pg_sys::ERROR` will cause the current transaction to abort
+pub fn ereport_with_detail(
+    level: PgLogLevel,
+    code: PgSqlErrorCode,
+    message: &str,
+    detail: &str,
+    file: &str,
+    lineno: u32,
+    colno: u32,
+) {
+    use std::ffi::CStr;
+    use std::ffi::CString;
+    use std::os::raw::c_char;
+
+    extern "C" {
+        fn pgx_ereport_detail(
+            level: i32,
+            code: i32,
+            message: *const c_char,
+            detail: *const c_char,
+            file: *const c_char,
+            lineno: i32,
+            colno: i32,
+        );
+    }
+
+    let message = match CString::new(message) {
+        Ok(s) => s,
+        Err(_) => CString::from(
+            CStr::from_bytes_with_nul(b"error message was null\0")
+                .expect("hardcoded error message failed"),
+        ),
+    };
+
+    let detail = match CString::new(detail) {
+        Ok(s) => s,
+        Err(_) => CString::from(
+            CStr::from_bytes_with_nul(b"error detail was null\0")
+                .expect("hardcoded error message failed"),
+        ),
+    };
+
+    let file = match CString::new(file) {
+        Ok(f) => f,
+        Err(_) => CString::from(
+            CStr::from_bytes_with_nul(b"filename was null\0")
+                .expect("hardcoded error message failed"),
+        ),
+    };
+
+    unsafe {
+        crate::guard(|| {
+            pgx_ereport_detail(
+                level as i32,
+                code as i32,
+                message.as_ptr(),
+                detail.as_ptr(),
+                file.as_ptr(),
+                lineno as i32,
+                colno as i32,
+            );
+        });
+    }
+}
+
+/// How structured key/value log fields are rendered into a message's `DETAIL`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PgLogFieldFormat {
+    /// One `key=value` line per field, quoting values that contain whitespace, `=`, or `"`
+    KeyValue,
+
+    /// A single JSON object, so that `jsonlog` consumers can parse the `detail` field directly
+    Json,
+}
+
+impl PgLogFieldFormat {
+    /// `Json` if the server's `log_destination` includes `jsonlog` (Postgres 15+), otherwise `KeyValue`
+    pub fn for_log_destination() -> Self {
+        let destination = unsafe {
+            crate::pg_sys::GetConfigOption(
+                b"log_destination\0".as_ptr() as *const std::os::raw::c_char,
+                true,
+                false,
+            )
+        };
+
+        if destination.is_null() {
+            return PgLogFieldFormat::KeyValue;
+        }
+
+        let destination = unsafe { std::ffi::CStr::from_ptr(destination) }.to_string_lossy();
+        if destination
+            .split(',')
+            .any(|dest| dest.trim().eq_ignore_ascii_case("jsonlog"))
+        {
+            PgLogFieldFormat::Json
+        } else {
+            PgLogFieldFormat::KeyValue
+        }
+    }
+
+    /// Render `fields` in this format
+    pub fn render(self, fields: &[(&str, String)]) -> String {
+        match self {
+            PgLogFieldFormat::KeyValue => fields
+                .iter()
+                .map(|(key, value)| {
+                    if value.is_empty()
+                        || value
+                            .chars()
+                            .any(|c| c.is_whitespace() || c == '=' || c == '"')
+                    {
+                        format!("{}={:?}", key, value)
+                    } else {
+                        format!("{}={}", key, value)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            PgLogFieldFormat::Json => {
+                let object = fields
+                    .iter()
+                    .map(|(key, value)| {
+                        (
+                            key.to_string(),
+                            serde_json::Value::String(value.to_string()),
+                        )
+                    })
+                    .collect::<serde_json::Map<_, _>>();
+                serde_json::Value::Object(object).to_string()
+            }
+        }
+    }
+}
+
+/// Log `message` with structured key/value `fields` attached as its `DETAIL`, rendered in the
+/// `PgLogFieldFormat` appropriate for the server's `log_destination`.
+///
+/// This is what the logging macros use when given fields, as in `info!("done"; rows = 42)`
+pub fn log_with_fields(
+    level: PgLogLevel,
+    message: &str,
+    fields: &[(&str, String)],
+    file: &str,
+    lineno: u32,
+    colno: u32,
+) {
+    // the same default codes `elog()` uses
+    let code = match level {
+        PgLogLevel::ERROR | PgLogLevel::FATAL | PgLogLevel::PANIC => {
+            PgSqlErrorCode::ERRCODE_INTERNAL_ERROR
+        }
+        PgLogLevel::WARNING => PgSqlErrorCode::ERRCODE_WARNING,
+        _ => PgSqlErrorCode::ERRCODE_SUCCESSFUL_COMPLETION,
+    };
+
+    if fields.is_empty() {
+        ereport(level, code, message, file, lineno, colno);
+    } else {
+        let detail = PgLogFieldFormat::for_log_destination().render(fields);
+        ereport_with_detail(level, code, message, &detail, file, lineno, colno);
+    }
+}
+
+/// Log to Postgres' `debug5` log level.  Nothing is formatted unless `log_min_messages` or
+/// `client_min_messages` would let the message through
+///
+/// Structured key/value fields may follow the message after a `;`, as in
+/// `debug5!("message {}", arg; key = value, other = value)`.  See `log_with_fields()`
 #[macro_export]
 macro_rules! debug5 {
+    ($($arg:expr),+ ; $($key:ident = $value:expr),+ $(,)?) => (
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG5) {
+            $crate::log::log_with_fields(
+                $crate::log::PgLogLevel::DEBUG5,
+                format!($($arg),+).as_str(),
+                &[$((stringify!($key), format!("{}", $value))),+],
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    );
     ($($arg:tt)*) => (
-        $crate::log::elog($crate::log::PgLogLevel::DEBUG5, format!($($arg)*).as_str());
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG5) {
+            $crate::log::elog($crate::log::PgLogLevel::DEBUG5, format!($($arg)*).as_str());
+        }
     )
 }
 
-/// Log to Postgres' `debug4` log level
+/// Log to Postgres' `debug4` log level.  Nothing is formatted unless `log_min_messages` or
+/// `client_min_messages` would let the message through
+///
+/// Structured key/value fields may follow the message after a `;`, as in
+/// `debug4!("message {}", arg; key = value, other = value)`.  See `log_with_fields()`
 #[macro_export]
 macro_rules! debug4 {
+    ($($arg:expr),+ ; $($key:ident = $value:expr),+ $(,)?) => (
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG4) {
+            $crate::log::log_with_fields(
+                $crate::log::PgLogLevel::DEBUG4,
+                format!($($arg),+).as_str(),
+                &[$((stringify!($key), format!("{}", $value))),+],
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    );
     ($($arg:tt)*) => (
-        $crate::log::elog($crate::log::PgLogLevel::DEBUG4, format!($($arg)*).as_str());
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG4) {
+            $crate::log::elog($crate::log::PgLogLevel::DEBUG4, format!($($arg)*).as_str());
+        }
     )
 }
 
-/// Log to Postgres' `debug3` log level
+/// Log to Postgres' `debug3` log level.  Nothing is formatted unless `log_min_messages` or
+/// `client_min_messages` would let the message through
+///
+/// Structured key/value fields may follow the message after a `;`, as in
+/// `debug3!("message {}", arg; key = value, other = value)`.  See `log_with_fields()`
 #[macro_export]
 macro_rules! debug3 {
+    ($($arg:expr),+ ; $($key:ident = $value:expr),+ $(,)?) => (
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG3) {
+            $crate::log::log_with_fields(
+                $crate::log::PgLogLevel::DEBUG3,
+                format!($($arg),+).as_str(),
+                &[$((stringify!($key), format!("{}", $value))),+],
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    );
     ($($arg:tt)*) => (
-        $crate::log::elog($crate::log::PgLogLevel::DEBUG3, format!($($arg)*).as_str());
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG3) {
+            $crate::log::elog($crate::log::PgLogLevel::DEBUG3, format!($($arg)*).as_str());
+        }
     )
 }
 
-/// Log to Postgres' `debug2` log level
+/// Log to Postgres' `debug2` log level.  Nothing is formatted unless `log_min_messages` or
+/// `client_min_messages` would let the message through
+///
+/// Structured key/value fields may follow the message after a `;`, as in
+/// `debug2!("message {}", arg; key = value, other = value)`.  See `log_with_fields()`
 #[macro_export]
 macro_rules! debug2 {
+    ($($arg:expr),+ ; $($key:ident = $value:expr),+ $(,)?) => (
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG2) {
+            $crate::log::log_with_fields(
+                $crate::log::PgLogLevel::DEBUG2,
+                format!($($arg),+).as_str(),
+                &[$((stringify!($key), format!("{}", $value))),+],
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    );
     ($($arg:tt)*) => (
-        $crate::log::elog($crate::log::PgLogLevel::DEBUG2, format!($($arg)*).as_str());
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG2) {
+            $crate::log::elog($crate::log::PgLogLevel::DEBUG2, format!($($arg)*).as_str());
+        }
     )
 }
 
-/// Log to Postgres' `debug1` log level
+/// Log to Postgres' `debug1` log level.  Nothing is formatted unless `log_min_messages` or
+/// `client_min_messages` would let the message through
+///
+/// Structured key/value fields may follow the message after a `;`, as in
+/// `debug1!("message {}", arg; key = value, other = value)`.  See `log_with_fields()`
 #[macro_export]
 macro_rules! debug1 {
+    ($($arg:expr),+ ; $($key:ident = $value:expr),+ $(,)?) => (
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG1) {
+            $crate::log::log_with_fields(
+                $crate::log::PgLogLevel::DEBUG1,
+                format!($($arg),+).as_str(),
+                &[$((stringify!($key), format!("{}", $value))),+],
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    );
     ($($arg:tt)*) => (
-        $crate::log::elog($crate::log::PgLogLevel::DEBUG1, format!($($arg)*).as_str());
+        if $crate::log::log_level_enabled($crate::log::PgLogLevel::DEBUG1) {
+            $crate::log::elog($crate::log::PgLogLevel::DEBUG1, format!($($arg)*).as_str());
+        }
     )
 }
 
 /// Log to Postgres' `log` log level
+///
+/// Structured key/value fields may follow the message after a `;`, as in
+/// `log!("message {}", arg; key = value, other = value)`.  See `log_with_fields()`
 #[macro_export]
 macro_rules! log {
+    ($($arg:expr),+ ; $($key:ident = $value:expr),+ $(,)?) => (
+        $crate::log::log_with_fields(
+            $crate::log::PgLogLevel::LOG,
+            format!($($arg),+).as_str(),
+            &[$((stringify!($key), format!("{}", $value))),+],
+            file!(),
+            line!(),
+            column!(),
+        );
+    );
     ($($arg:tt)*) => (
         $crate::log::elog($crate::log::PgLogLevel::LOG, format!($($arg)*).as_str());
     )
 }
 
 /// Log to Postgres' `info` log level
+///
+/// Structured key/value fields may follow the message after a `;`, as in
+/// `info!("message {}", arg; key = value, other = value)`.  See `log_with_fields()`
 #[macro_export]
 macro_rules! info {
+    ($($arg:expr),+ ; $($key:ident = $value:expr),+ $(,)?) => (
+        $crate::log::log_with_fields(
+            $crate::log::PgLogLevel::INFO,
+            format!($($arg),+).as_str(),
+            &[$((stringify!($key), format!("{}", $value))),+],
+            file!(),
+            line!(),
+            column!(),
+        );
+    );
     ($($arg:tt)*) => (
         $crate::log::elog($crate::log::PgLogLevel::INFO, format!($($arg)*).as_str());
     )
 }
 
 /// Log to Postgres' `notice` log level
+///
+/// Structured key/value fields may follow the message after a `;`, as in
+/// `notice!("message {}", arg; key = value, other = value)`.  See `log_with_fields()`
 #[macro_export]
 macro_rules! notice {
+    ($($arg:expr),+ ; $($key:ident = $value:expr),+ $(,)?) => (
+        $crate::log::log_with_fields(
+            $crate::log::PgLogLevel::NOTICE,
+            format!($($arg),+).as_str(),
+            &[$((stringify!($key), format!("{}", $value))),+],
+            file!(),
+            line!(),
+            column!(),
+        );
+    );
     ($($arg:tt)*) => (
         $crate::log::elog($crate::log::PgLogLevel::NOTICE, format!($($arg)*).as_str());
     )
 }
 
 /// Log to Postgres' `warning` log level
+///
+/// Structured key/value fields may follow the message after a `;`, as in
+/// `warning!("message {}", arg; key = value, other = value)`.  See `log_with_fields()`
 #[macro_export]
 macro_rules! warning {
+    ($($arg:expr),+ ; $($key:ident = $value:expr),+ $(,)?) => (
+        $crate::log::log_with_fields(
+            $crate::log::PgLogLevel::WARNING,
+            format!($($arg),+).as_str(),
+            &[$((stringify!($key), format!("{}", $value))),+],
+            file!(),
+            line!(),
+            column!(),
+        );
+    );
     ($($arg:tt)*) => (
         $crate::log::elog($crate::log::PgLogLevel::WARNING, format!($($arg)*).as_str());
     )
 }
 
+/// Log to Postgres' `warning` log level, but only the first time this call site is reached in
+/// the current backend
+#[macro_export]
+macro_rules! warn_once {
+    ($($arg:tt)*) => ({
+        static SAMPLER: $crate::log::PgLogSampler = $crate::log::PgLogSampler::new();
+        if SAMPLER.sample(0) {
+            $crate::warning!($($arg)*);
+        }
+    })
+}
+
+/// Log to Postgres' `log` log level, but only the first time and then every `$every`th time
+/// after that this call site is reached in the current backend, as in
+/// `log_every!(1000, "cache miss for {}", key)`
+#[macro_export]
+macro_rules! log_every {
+    ($every:expr, $($arg:tt)*) => ({
+        static SAMPLER: $crate::log::PgLogSampler = $crate::log::PgLogSampler::new();
+        if SAMPLER.sample($every) {
+            $crate::log!($($arg)*);
+        }
+    })
+}
+
 /// Log to Postgres' `error` log level.  This will abort the current Postgres transaction.
 #[macro_export]
 macro_rules! error {
@@ -643,7 +1560,7 @@ pub fn interrupt_pending() -> bool {
 }
 
 /// Is an interrupt pending?
-#[cfg(feature = "pg12")]
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
 #[inline]
 pub fn interrupt_pending() -> bool {
     unsafe { crate::pg_sys::InterruptPending != 0 }
@@ -662,7 +1579,7 @@ macro_rules! check_for_interrupts {
             }
         }
 
-        #[cfg(feature = "pg12")]
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
         #[allow(unused_unsafe)]
         unsafe {
             if $crate::pg_sys::InterruptPending != 0 {
@@ -0,0 +1,235 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Named LWLock tranches for extensions
+//!
+//! A `PgLwLockTranche` is a set of LWLocks requested with `RequestNamedLWLockTranche()`.  Because
+//! the tranche is named, backends waiting on one of its locks show that name as their
+//! `wait_event` in `pg_stat_activity`.
+//!
+//! Locks are held by a `PgLwLockGuard` and released when it's dropped, including while unwinding
+//! from a Rust `panic!` or an `elog(ERROR)`, so a lock is never left held.  Like
+//! `std::sync::Mutex`, a lock whose exclusive guard was dropped during a panic is "poisoned", as
+//! the data it protected may have been left half-modified.  Acquiring a poisoned lock raises an
+//! ERROR until `PgLwLockTranche::clear_poison()` is called.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! static LOCKS: PgLwLockTranche = PgLwLockTranche::new("my_extension_locks", 4);
+//!
+//! #[pg_guard]
+//! pub extern "C" fn _PG_init() {
+//!     pg_shmem_init(&LOCKS);
+//! }
+//!
+//! #[pg_extern]
+//! fn rebuild_bucket(bucket: i32) {
+//!     let _guard = LOCKS.exclusive(bucket as usize);
+//!     // ... rebuild it ...
+//! }
+//! ```
+use crate::{pg_sys, shmem_init_struct, PgSharedMemoryInitialization};
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+/// A named tranche of LWLocks in shared memory.  See the module documentation
+pub struct PgLwLockTranche {
+    name: &'static str,
+    num_locks: usize,
+    locks: AtomicPtr<pg_sys::LWLockPadded>,
+    poisoned: AtomicPtr<AtomicBool>,
+}
+
+/// A held LWLock from a `PgLwLockTranche`, released when dropped
+pub struct PgLwLockGuard<'a> {
+    tranche: &'a PgLwLockTranche,
+    index: usize,
+    exclusive: bool,
+}
+
+impl PgLwLockTranche {
+    /// Declare a tranche of `num_locks` LWLocks.  It's registered with Postgres by passing it to
+    /// `pg_shmem_init()` from `_PG_init()`
+    pub const fn new(name: &'static str, num_locks: usize) -> Self {
+        PgLwLockTranche {
+            name,
+            num_locks,
+            locks: AtomicPtr::new(std::ptr::null_mut()),
+            poisoned: AtomicPtr::new(std::ptr::null_mut()),
+        }
+    }
+
+    /// The tranche's name, as shown in `pg_stat_activity.wait_event`
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// How many locks are in this tranche?
+    pub fn len(&self) -> usize {
+        self.num_locks
+    }
+
+    /// Does this tranche have no locks at all?
+    pub fn is_empty(&self) -> bool {
+        self.num_locks == 0
+    }
+
+    /// Wait for exclusive access to lock number `index`
+    pub fn exclusive(&self, index: usize) -> PgLwLockGuard {
+        self.acquire(index, true)
+    }
+
+    /// Wait for shared access to lock number `index`
+    pub fn shared(&self, index: usize) -> PgLwLockGuard {
+        self.acquire(index, false)
+    }
+
+    /// Acquire exclusive access to lock number `index`, only if it's available immediately
+    pub fn try_exclusive(&self, index: usize) -> Option<PgLwLockGuard> {
+        self.try_acquire(index, true)
+    }
+
+    /// Acquire shared access to lock number `index`, only if it's available immediately
+    pub fn try_shared(&self, index: usize) -> Option<PgLwLockGuard> {
+        self.try_acquire(index, false)
+    }
+
+    /// Was an exclusive guard for lock number `index` dropped during a panic?
+    pub fn is_poisoned(&self, index: usize) -> bool {
+        self.poison_flag(index).load(Ordering::Acquire)
+    }
+
+    /// Allow lock number `index` to be acquired again after it was poisoned, presumably once the
+    /// data it protects has been repaired
+    pub fn clear_poison(&self, index: usize) {
+        self.poison_flag(index).store(false, Ordering::Release);
+    }
+
+    fn acquire(&self, index: usize, exclusive: bool) -> PgLwLockGuard {
+        unsafe {
+            pg_sys::LWLockAcquire(self.lwlock(index), lwlock_mode(exclusive));
+        }
+        self.guard(index, exclusive)
+    }
+
+    fn try_acquire(&self, index: usize, exclusive: bool) -> Option<PgLwLockGuard> {
+        if unsafe { pg_sys::LWLockConditionalAcquire(self.lwlock(index), lwlock_mode(exclusive)) } {
+            Some(self.guard(index, exclusive))
+        } else {
+            None
+        }
+    }
+
+    fn guard(&self, index: usize, exclusive: bool) -> PgLwLockGuard {
+        // create the guard first, so the lock is released if we raise an ERROR below
+        let guard = PgLwLockGuard {
+            tranche: self,
+            index,
+            exclusive,
+        };
+
+        if self.is_poisoned(index) {
+            // the guard's `Drop` will see that we're panicking and re-poison the lock, which is
+            // already poisoned anyways
+            panic!(
+                "LWLock {} of tranche `{}` is poisoned: a previous holder panicked while holding it",
+                index, self.name
+            );
+        }
+        guard
+    }
+
+    fn lwlock(&self, index: usize) -> *mut pg_sys::LWLock {
+        let locks = self.locks.load(Ordering::Acquire);
+        if locks.is_null() {
+            panic!(
+                "LWLock tranche `{}` was not initialized.  Did you call `pg_shmem_init()` from `_PG_init()`?",
+                self.name
+            );
+        }
+        if index >= self.num_locks {
+            panic!(
+                "LWLock {} is out of range for tranche `{}`, which has {} locks",
+                index, self.name, self.num_locks
+            );
+        }
+
+        unsafe { &mut (*locks.add(index)).lock as *mut pg_sys::LWLock }
+    }
+
+    fn poison_flag(&self, index: usize) -> &AtomicBool {
+        // `lwlock()` validates that we're initialized and that `index` is in range
+        self.lwlock(index);
+        unsafe { &*self.poisoned.load(Ordering::Acquire).add(index) }
+    }
+}
+
+impl PgSharedMemoryInitialization for PgLwLockTranche {
+    fn shmem_size(&self) -> usize {
+        std::mem::size_of::<AtomicBool>() * self.num_locks
+    }
+
+    fn lwlock_tranche(&self) -> Option<(&'static str, usize)> {
+        Some((self.name, self.num_locks))
+    }
+
+    unsafe fn shmem_init(&self) {
+        let (poisoned, found) = shmem_init_struct(self.name, self.shmem_size());
+        let poisoned = poisoned as *mut AtomicBool;
+        if !found {
+            for i in 0..self.num_locks {
+                poisoned.add(i).write(AtomicBool::new(false));
+            }
+        }
+        self.poisoned.store(poisoned, Ordering::Release);
+
+        let tranche_name = CString::new(self.name).unwrap();
+        self.locks.store(
+            pg_sys::GetNamedLWLockTranche(tranche_name.as_ptr()),
+            Ordering::Release,
+        );
+    }
+}
+
+impl<'a> PgLwLockGuard<'a> {
+    /// The index of the lock this guard holds, within its tranche
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Is the lock held exclusively?
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+}
+
+impl<'a> Drop for PgLwLockGuard<'a> {
+    fn drop(&mut self) {
+        let lock = self.tranche.lwlock(self.index);
+
+        if self.exclusive && std::thread::panicking() {
+            self.tranche
+                .poison_flag(self.index)
+                .store(true, Ordering::Release);
+        }
+
+        unsafe {
+            // if Postgres has already released all LWLocks during error recovery, there's nothing
+            // left for us to do
+            if pg_sys::LWLockHeldByMe(lock) {
+                pg_sys::LWLockRelease(lock);
+            }
+        }
+    }
+}
+
+fn lwlock_mode(exclusive: bool) -> pg_sys::LWLockMode {
+    if exclusive {
+        pg_sys::LWLockMode_LW_EXCLUSIVE
+    } else {
+        pg_sys::LWLockMode_LW_SHARED
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Binary compatibility checking between an extension's shared library and its installed SQL schema
+//!
+//! `pg_module_magic!()` embeds a `PgxMagicBlock` in every extension library describing the `pgx`
+//! version, the Postgres version, and the extension SQL version it was built for.  As Postgres
+//! loads the library, before `_PG_init()`, it's checked with `check_binary_compatibility()`, which
+//! turns a mismatched `.so` into a clear ERROR rather than obscure "could not find function" errors
+//! later on.
+//!
+//! The block also records the git revision and cargo profile `cargo pgx` built the library from,
+//! so a production binary can be traced back to its sources.  The `pg_build_info!()` macro
+//! creates an `<extname>_build_info()` SQL function that returns all of it:
+//!
+//! ```sql
+//! SELECT * FROM my_extension_build_info();
+//! ```
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode, Spi};
+
+/// The version of `pgx` an extension was compiled against
+pub const PGX_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+static mut MAGIC_BLOCK: Option<&'static PgxMagicBlock> = None;
+
+/// Describes what an extension library was built for.  One of these is generated for every
+/// extension by `pg_module_magic!()` and exported as the `PGX_MAGIC_BLOCK` symbol
+#[derive(Debug)]
+pub struct PgxMagicBlock {
+    /// The `pgx` version the library was compiled against
+    pub pgx_version: &'static str,
+
+    /// The `PG_VERSION_NUM` of the Postgres headers the library was compiled against
+    pub pg_version_num: u32,
+
+    /// The extension name, as determined by `cargo pgx` from the control file
+    pub extname: Option<&'static str>,
+
+    /// The extension's `default_version`, as determined by `cargo pgx` from the control file
+    pub extversion: Option<&'static str>,
+
+    /// The git commit the extension was built from, with `-dirty` appended if it had uncommitted
+    /// changes, as determined by `cargo pgx`
+    pub git_revision: Option<&'static str>,
+
+    /// The cargo profile the library was built with, `debug` or `release`
+    pub profile: &'static str,
+}
+
+impl PgxMagicBlock {
+    pub const fn new(
+        extname: Option<&'static str>,
+        extversion: Option<&'static str>,
+        git_revision: Option<&'static str>,
+        profile: &'static str,
+    ) -> Self {
+        PgxMagicBlock {
+            pgx_version: PGX_VERSION,
+            pg_version_num: pg_sys::PG_VERSION_NUM,
+            extname,
+            extversion,
+            git_revision,
+            profile,
+        }
+    }
+
+    /// The Postgres version the library was compiled against, like `13.2`
+    pub fn pg_version(&self) -> String {
+        format!(
+            "{}.{}",
+            self.pg_version_num / 10000,
+            self.pg_version_num % 10000
+        )
+    }
+}
+
+/// Remember the magic block for the library currently being loaded.  This is called automatically
+/// by the `pg_module_magic!()` macro and need not be called directly
+pub fn register_magic_block(block: &'static PgxMagicBlock) {
+    // Postgres backends are single-threaded, and a library is only ever loaded once per backend
+    unsafe {
+        MAGIC_BLOCK = Some(block);
+    }
+}
+
+/// Retrieve the magic block registered by `pg_module_magic!()`, if any
+pub fn get_magic_block() -> Option<&'static PgxMagicBlock> {
+    unsafe { MAGIC_BLOCK }
+}
+
+/// Verify that this shared library is compatible with the extension SQL currently installed in
+/// the database.  This is called automatically by the `Pg_magic_func()` that `pg_module_magic!()`
+/// generates, so a mismatched library fails to load, and need not be called directly.
+///
+/// If the library is being loaded outside of a transaction (ie, via `shared_preload_libraries`),
+/// or while `CREATE/ALTER EXTENSION` is running, there's no installed schema to compare against
+/// and this function does nothing.
+pub fn check_binary_compatibility() {
+    let block = match get_magic_block() {
+        Some(block) => block,
+        None => return,
+    };
+
+    let (extname, extversion) = match (block.extname, block.extversion) {
+        (Some(extname), Some(extversion)) => (extname, extversion),
+
+        // the library wasn't built by `cargo pgx`, so we don't know what to compare against
+        _ => return,
+    };
+
+    if !unsafe { pg_sys::IsTransactionState() } || unsafe { pg_sys::creating_extension } {
+        return;
+    }
+
+    let installed = Spi::get_one::<String>(&format!(
+        "SELECT extversion FROM pg_catalog.pg_extension WHERE extname = '{}'",
+        extname.replace("'", "''")
+    ));
+
+    if let Some(installed) = installed {
+        if installed != extversion {
+            ereport(
+                PgLogLevel::ERROR,
+                PgSqlErrorCode::ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE,
+                &format!(
+                    "extension \"{}\" library version {} (pgx {}, Postgres {}) does not match installed SQL version {}.  Run `ALTER EXTENSION {} UPDATE;`",
+                    extname,
+                    extversion,
+                    block.pgx_version,
+                    block.pg_version(),
+                    installed,
+                    extname
+                ),
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    }
+}
@@ -159,6 +159,29 @@ pub enum PgMemoryContexts {
     },
 }
 
+/// Memory usage totals for a `MemoryContext`, as `MemoryContextStats()` would report them
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PgMemoryUsage {
+    /// The number of blocks the context(s) have malloc'd
+    pub blocks: usize,
+
+    /// The number of chunks on the context(s) freelists
+    pub free_chunks: usize,
+
+    /// The total number of bytes malloc'd by the context(s)
+    pub total_bytes: usize,
+
+    /// The number of those bytes that aren't currently handed out
+    pub free_bytes: usize,
+}
+
+impl PgMemoryUsage {
+    /// The number of bytes currently in use
+    pub fn used_bytes(&self) -> usize {
+        self.total_bytes - self.free_bytes
+    }
+}
+
 impl PgMemoryContexts {
     /// Retrieve the underlying Postgres `*mut MemoryContextData`
     ///
@@ -319,6 +342,53 @@ impl PgMemoryContexts {
         leaked_ptr
     }
 
+    /// How much memory this context, and all of its children, are using.
+    ///
+    /// This is the same accounting `MemoryContextStats()` logs to the server's stderr
+    pub fn usage(&self) -> PgMemoryUsage {
+        let mut usage = PgMemoryUsage::default();
+        PgMemoryContexts::accumulate_usage(self.value(), true, &mut usage);
+        usage
+    }
+
+    /// How much memory this context alone is using, excluding its children
+    pub fn usage_without_children(&self) -> PgMemoryUsage {
+        let mut usage = PgMemoryUsage::default();
+        PgMemoryContexts::accumulate_usage(self.value(), false, &mut usage);
+        usage
+    }
+
+    fn accumulate_usage(context: pg_sys::MemoryContext, recurse: bool, usage: &mut PgMemoryUsage) {
+        let mut totals = pg_sys::MemoryContextCounters::default();
+        unsafe {
+            let stats = (*(*context).methods)
+                .stats
+                .expect("MemoryContext has no stats method");
+
+            #[cfg(feature = "pg10")]
+            stats(context, 0, false, &mut totals);
+
+            #[cfg(any(feature = "pg11", feature = "pg12", feature = "pg13"))]
+            stats(context, None, std::ptr::null_mut(), &mut totals);
+
+            #[cfg(any(feature = "pg14", feature = "pg15"))]
+            stats(context, None, std::ptr::null_mut(), &mut totals, false);
+        }
+
+        usage.blocks += totals.nblocks;
+        usage.free_chunks += totals.freechunks;
+        usage.total_bytes += totals.totalspace;
+        usage.free_bytes += totals.freespace;
+
+        if recurse {
+            let mut child = unsafe { (*context).firstchild };
+            while !child.is_null() {
+                PgMemoryContexts::accumulate_usage(child, true, usage);
+                child = unsafe { (*child).nextchild };
+            }
+        }
+    }
+
     /// helper function
     fn exec_in_context<
         R,
@@ -0,0 +1,237 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Memory contexts with a byte limit, for extensions that enforce their own memory budgets
+//!
+//! A `PgMemoryBudget` is a regular `AllocSet` memory context that keeps count of the bytes
+//! handed out from it.  An allocation that would take it over its limit raises a clean Postgres
+//! `ERROR` (`ERRCODE_PROGRAM_LIMIT_EXCEEDED`), rather than letting the backend grow until the
+//! operating system's OOM killer takes it, and with it the whole cluster, down.
+//!
+//! Only allocations made directly in the budget's context are counted.  Child contexts created
+//! beneath it have their own accounting, which `PgMemoryContexts::usage()` can report on.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! #[pg_extern]
+//! fn bounded_work(n: i32) -> i64 {
+//!     let mut budget = PgMemoryBudget::new("bounded_work", 64 * 1024 * 1024);
+//!     budget.switch_to(|_| {
+//!         // every palloc() in here counts against the 64MB budget
+//!         let values = PgMemoryContexts::CurrentMemoryContext.palloc_slice::<i64>(n as usize);
+//!         values.len() as i64
+//!     })
+//! }
+//! ```
+use crate::{
+    ereport_with_detail, pg_sys, PgLogLevel, PgMemoryContexts, PgMemoryUsage, PgSqlErrorCode,
+};
+use std::ffi::{CStr, CString};
+
+/// A memory context that raises an `ERROR` instead of allocating more than `limit` bytes
+///
+/// The context is a child of the `CurrentMemoryContext` at the time it was created, unless
+/// created with `PgMemoryBudget::new_in()`, and is deleted when the `PgMemoryBudget` is dropped.
+pub struct PgMemoryBudget {
+    context: pg_sys::MemoryContext,
+}
+
+/// The `MemoryContextMethods` we install on a budgeted context.  Postgres only ever sees the
+/// `methods` field, so it must be first
+#[repr(C)]
+struct BudgetMethods {
+    methods: pg_sys::MemoryContextMethods,
+    inner: *const pg_sys::MemoryContextMethods,
+    name: CString,
+    limit: usize,
+    used: usize,
+}
+
+impl PgMemoryBudget {
+    /// Create a new budgeted context, named `name`, as a child of the `CurrentMemoryContext`
+    pub fn new(name: &str, limit: usize) -> Self {
+        PgMemoryBudget::new_in(PgMemoryContexts::CurrentMemoryContext, name, limit)
+    }
+
+    /// Create a new budgeted context, named `name`, as a child of `parent`
+    pub fn new_in(parent: PgMemoryContexts, name: &str, limit: usize) -> Self {
+        let name = CString::new(name).expect("memory context name contains a null byte");
+        unsafe {
+            let context = pg_sys::AllocSetContextCreateExtended(
+                parent.value(),
+                name.as_ptr(),
+                pg_sys::ALLOCSET_DEFAULT_MINSIZE as usize,
+                pg_sys::ALLOCSET_DEFAULT_INITSIZE as usize,
+                pg_sys::ALLOCSET_DEFAULT_MAXSIZE as usize,
+            );
+
+            let inner = (*context).methods as *const pg_sys::MemoryContextMethods;
+            let mut methods = *inner;
+            methods.alloc = Some(budget_alloc);
+            methods.free_p = Some(budget_free);
+            methods.realloc = Some(budget_realloc);
+            methods.reset = Some(budget_reset);
+            methods.delete_context = Some(budget_delete);
+
+            let budget = Box::new(BudgetMethods {
+                methods,
+                inner,
+                name,
+                limit,
+                used: 0,
+            });
+            (*context).methods = Box::into_raw(budget) as *mut pg_sys::MemoryContextMethods as _;
+
+            PgMemoryBudget { context }
+        }
+    }
+
+    /// The underlying context, for passing to other `PgMemoryContexts` functions
+    pub fn context(&self) -> PgMemoryContexts {
+        PgMemoryContexts::For(self.context)
+    }
+
+    /// Run `f` with this budget's context as the `CurrentMemoryContext`
+    pub fn switch_to<
+        R,
+        F: Fn(&mut PgMemoryContexts) -> R + std::panic::UnwindSafe + std::panic::RefUnwindSafe,
+    >(
+        &mut self,
+        f: F,
+    ) -> R {
+        self.context().switch_to(f)
+    }
+
+    /// The number of bytes this context may allocate
+    pub fn limit(&self) -> usize {
+        unsafe { budget_of(self.context).limit }
+    }
+
+    /// Change the number of bytes this context may allocate.  Lowering it below what's already
+    /// in use doesn't free anything, but causes the next allocation to fail
+    pub fn set_limit(&mut self, limit: usize) {
+        unsafe { budget_of(self.context).limit = limit }
+    }
+
+    /// The number of bytes currently allocated from this context, counting each chunk's full
+    /// size as rounded up by the allocator
+    pub fn used(&self) -> usize {
+        unsafe { budget_of(self.context).used }
+    }
+
+    /// The number of bytes that can still be allocated before the limit is reached
+    pub fn remaining(&self) -> usize {
+        self.limit().saturating_sub(self.used())
+    }
+
+    /// Block-level usage of this context and its children, as `MemoryContextStats()` reports it
+    pub fn usage(&self) -> PgMemoryUsage {
+        self.context().usage()
+    }
+
+    /// Free everything allocated in this context, and delete its children
+    pub fn reset(&mut self) {
+        unsafe { pg_sys::MemoryContextReset(self.context) }
+    }
+}
+
+impl Drop for PgMemoryBudget {
+    fn drop(&mut self) {
+        unsafe { pg_sys::MemoryContextDelete(self.context) }
+    }
+}
+
+unsafe fn budget_of<'a>(context: pg_sys::MemoryContext) -> &'a mut BudgetMethods {
+    &mut *((*context).methods as *mut BudgetMethods)
+}
+
+unsafe fn chunk_space(
+    budget: &BudgetMethods,
+    context: pg_sys::MemoryContext,
+    pointer: *mut std::os::raw::c_void,
+) -> usize {
+    ((*budget.inner).get_chunk_space.unwrap())(context, pointer)
+}
+
+unsafe fn over_budget(budget: &BudgetMethods, size: usize) {
+    let name = CStr::from_ptr(budget.name.as_ptr()).to_string_lossy();
+    ereport_with_detail(
+        PgLogLevel::ERROR,
+        PgSqlErrorCode::ERRCODE_PROGRAM_LIMIT_EXCEEDED,
+        &format!(
+            "memory context \"{}\" exceeded its budget of {} bytes",
+            name, budget.limit
+        ),
+        &format!(
+            "{} bytes in use, failed on request of size {}.",
+            budget.used, size
+        ),
+        file!(),
+        line!(),
+        column!(),
+    );
+}
+
+unsafe extern "C" fn budget_alloc(
+    context: pg_sys::MemoryContext,
+    size: pg_sys::Size,
+) -> *mut std::os::raw::c_void {
+    let budget = budget_of(context);
+    if budget.used.saturating_add(size) > budget.limit {
+        over_budget(budget, size);
+    }
+
+    let pointer = ((*budget.inner).alloc.unwrap())(context, size);
+    if !pointer.is_null() {
+        budget.used += chunk_space(budget, context, pointer);
+    }
+    pointer
+}
+
+unsafe extern "C" fn budget_free(
+    context: pg_sys::MemoryContext,
+    pointer: *mut std::os::raw::c_void,
+) {
+    let budget = budget_of(context);
+    budget.used = budget
+        .used
+        .saturating_sub(chunk_space(budget, context, pointer));
+    ((*budget.inner).free_p.unwrap())(context, pointer)
+}
+
+unsafe extern "C" fn budget_realloc(
+    context: pg_sys::MemoryContext,
+    pointer: *mut std::os::raw::c_void,
+    size: pg_sys::Size,
+) -> *mut std::os::raw::c_void {
+    let budget = budget_of(context);
+    let old_space = chunk_space(budget, context, pointer);
+    if size > old_space && budget.used.saturating_add(size - old_space) > budget.limit {
+        over_budget(budget, size);
+    }
+
+    let new_pointer = ((*budget.inner).realloc.unwrap())(context, pointer, size);
+    if !new_pointer.is_null() {
+        budget.used =
+            budget.used.saturating_sub(old_space) + chunk_space(budget, context, new_pointer);
+    }
+    new_pointer
+}
+
+unsafe extern "C" fn budget_reset(context: pg_sys::MemoryContext) {
+    let budget = budget_of(context);
+    ((*budget.inner).reset.unwrap())(context);
+    budget.used = 0;
+}
+
+unsafe extern "C" fn budget_delete(context: pg_sys::MemoryContext) {
+    // put the allocator's own methods back before handing the context to it, as it may keep
+    // the context around on a freelist for reuse
+    let budget = Box::from_raw((*context).methods as *mut BudgetMethods);
+    (*context).methods = budget.inner as _;
+    ((*budget.inner).delete_context.unwrap())(context);
+    drop(budget);
+}
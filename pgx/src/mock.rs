@@ -0,0 +1,362 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Unit testing datum conversions and pure logic with plain `cargo test`, without a Postgres server
+//!
+//! `#[pg_test]` runs every test inside a real Postgres, which is what most tests want, but it's a
+//! slow loop when all you're working on is how a type converts to and from a `Datum`.  The
+//! `pg_mock!()` macro defines just enough of Postgres for that to work in an ordinary test
+//! binary: `palloc()` and friends, backed by the Rust allocator, and the handful of `text` and
+//! detoasting functions `FromDatum` and `IntoDatum` use.  `MockFunctionCall` builds a
+//! `FunctionCallInfo` for calling a `#[pg_extern]` function's `_wrapper` directly.
+//!
+//! Memory is never freed with its context, as there are no contexts, and anything else that needs
+//! a real Postgres (SPI, the catalogs, `ereport()`, toasted values) will fail to link or panic.
+//! Only use `pg_mock!()` under `#[cfg(test)]`, as the symbols it defines would replace Postgres'
+//! own if they were loaded into a server.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! #[pg_extern]
+//! fn repeat(s: &str, n: i32) -> String {
+//!     s.repeat(n as usize)
+//! }
+//!
+//! #[cfg(test)]
+//! mod unit_tests {
+//!     use pgx::*;
+//!
+//!     pg_mock!();
+//!
+//!     #[test]
+//!     fn text_round_trip() {
+//!         mock::run(|| {
+//!             let datum = "hello".into_datum();
+//!             let s = unsafe { String::from_datum(datum.unwrap(), false, pg_sys::TEXTOID) };
+//!             assert_eq!(s.as_deref(), Some("hello"));
+//!         })
+//!     }
+//!
+//!     #[test]
+//!     fn call_repeat() {
+//!         mock::run(|| {
+//!             let result = mock::MockFunctionCall::new()
+//!                 .arg("ab")
+//!                 .arg(3)
+//!                 .call::<String>(super::repeat_wrapper);
+//!             assert_eq!(result.as_deref(), Some("ababab"));
+//!         })
+//!     }
+//! }
+//! ```
+use crate::{pg_sys, FromDatum, IntoDatum};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+thread_local! {
+    /// The argument types of each `MockFunctionCall`, by the address of its `flinfo`
+    static ARG_TYPES: RefCell<HashMap<usize, Vec<pg_sys::Oid>>> = RefCell::new(HashMap::new());
+}
+
+/// Run `f` with the current thread allowed to call Postgres functions, as `cargo test` runs each
+/// test on a thread of its own.  `pg_mock!()` must have been used somewhere in the test binary
+pub fn run<R, F: FnOnce() -> R>(f: F) -> R {
+    pg_sys::guard::register_mock_main_thread();
+    f()
+}
+
+/// Builds a `FunctionCallInfo` for calling a `#[pg_extern]` function's `_wrapper` without
+/// Postgres, such as in a `pg_mock!()` unit test
+#[derive(Debug, Default)]
+pub struct MockFunctionCall {
+    args: Vec<Option<pg_sys::Datum>>,
+    types: Vec<pg_sys::Oid>,
+}
+
+impl MockFunctionCall {
+    pub fn new() -> Self {
+        MockFunctionCall::default()
+    }
+
+    /// Add an argument.  `None` passes `NULL`, as in `.arg(None::<i32>)`
+    pub fn arg<T: IntoDatum>(mut self, value: T) -> Self {
+        self.types.push(T::type_oid());
+        self.args.push(value.into_datum());
+        self
+    }
+
+    /// The `FunctionCallInfo`, with an `flinfo` that reports the arguments' types
+    pub fn build(self) -> pg_sys::FunctionCallInfo {
+        let fcinfo = crate::fcinfo::new_function_call_info(self.args);
+        unsafe {
+            let flinfo =
+                pg_sys::palloc0(std::mem::size_of::<pg_sys::FmgrInfo>()) as *mut pg_sys::FmgrInfo;
+            (*flinfo).fn_nargs = self.types.len() as i16;
+            (*flinfo).fn_mcxt = pg_sys::CurrentMemoryContext;
+            (*fcinfo).flinfo = flinfo;
+
+            ARG_TYPES.with(|types| types.borrow_mut().insert(flinfo as usize, self.types));
+        }
+        fcinfo
+    }
+
+    /// Call `func` with the arguments, converting what it returns to `R`
+    pub fn call<R: FromDatum>(
+        self,
+        func: unsafe fn(pg_sys::FunctionCallInfo) -> pg_sys::Datum,
+    ) -> Option<R> {
+        let fcinfo = self.build();
+        unsafe {
+            let datum = func(fcinfo);
+            R::from_datum(datum, (*fcinfo).isnull, pg_sys::InvalidOid)
+        }
+    }
+}
+
+/// Define the Postgres functions, and global variables, that `pgx::mock` provides, for unit tests
+/// that run without a Postgres server.  See the `mock` module documentation.
+///
+/// Only ever use this in a `#[cfg(test)]` module.
+#[macro_export]
+macro_rules! pg_mock {
+    () => {
+        #[allow(non_upper_case_globals, non_snake_case, clippy::missing_safety_doc)]
+        mod pgx_mock_symbols {
+            use std::os::raw::{c_char, c_int, c_void};
+            use $crate::mock::symbols;
+            use $crate::pg_sys;
+
+            #[no_mangle]
+            pub static mut CurrentMemoryContext: pg_sys::MemoryContext = std::ptr::null_mut();
+
+            #[no_mangle]
+            pub static mut PG_exception_stack: *mut pg_sys::sigjmp_buf = std::ptr::null_mut();
+
+            #[no_mangle]
+            pub static mut error_context_stack: *mut pg_sys::ErrorContextCallback =
+                std::ptr::null_mut();
+
+            #[no_mangle]
+            pub unsafe extern "C" fn palloc(size: pg_sys::Size) -> *mut c_void {
+                symbols::allocate(size, false)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn palloc0(size: pg_sys::Size) -> *mut c_void {
+                symbols::allocate(size, true)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn MemoryContextAlloc(
+                _context: pg_sys::MemoryContext,
+                size: pg_sys::Size,
+            ) -> *mut c_void {
+                symbols::allocate(size, false)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn MemoryContextAllocZero(
+                _context: pg_sys::MemoryContext,
+                size: pg_sys::Size,
+            ) -> *mut c_void {
+                symbols::allocate(size, true)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn repalloc(
+                pointer: *mut c_void,
+                size: pg_sys::Size,
+            ) -> *mut c_void {
+                symbols::reallocate(pointer, size)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn pfree(pointer: *mut c_void) {
+                symbols::free(pointer)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn pstrdup(s: *const c_char) -> *mut c_char {
+                symbols::strdup(s)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn MemoryContextStrdup(
+                _context: pg_sys::MemoryContext,
+                s: *const c_char,
+            ) -> *mut c_char {
+                symbols::strdup(s)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn cstring_to_text(s: *const c_char) -> *mut pg_sys::text {
+                let len = std::ffi::CStr::from_ptr(s).to_bytes().len();
+                symbols::cstring_to_text_with_len(s, len as c_int)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn cstring_to_text_with_len(
+                s: *const c_char,
+                len: c_int,
+            ) -> *mut pg_sys::text {
+                symbols::cstring_to_text_with_len(s, len)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn text_to_cstring(t: *const pg_sys::text) -> *mut c_char {
+                symbols::text_to_cstring(t)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn pg_detoast_datum(
+                datum: *mut pg_sys::varlena,
+            ) -> *mut pg_sys::varlena {
+                symbols::detoast(datum, false)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn pg_detoast_datum_copy(
+                datum: *mut pg_sys::varlena,
+            ) -> *mut pg_sys::varlena {
+                symbols::detoast(datum, true)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn pg_detoast_datum_packed(
+                datum: *mut pg_sys::varlena,
+            ) -> *mut pg_sys::varlena {
+                symbols::detoast_packed(datum)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn get_fn_expr_argtype(
+                flinfo: *mut pg_sys::FmgrInfo,
+                argnum: c_int,
+            ) -> pg_sys::Oid {
+                symbols::get_fn_expr_argtype(flinfo, argnum)
+            }
+        }
+    };
+}
+
+/// What `pg_mock!()`'s functions do.  They live here, rather than in the macro, so that only
+/// their `#[no_mangle]` declarations end up in the crate being tested
+#[doc(hidden)]
+pub mod symbols {
+    use super::ARG_TYPES;
+    use crate::{
+        pg_sys, set_varsize, varatt_is_1b, varatt_is_1b_e, varatt_is_4b_u, vardata_any,
+        varsize_any, varsize_any_exhdr,
+    };
+    use std::alloc::Layout;
+    use std::os::raw::{c_char, c_int, c_void};
+
+    /// Every allocation is preceded by this many bytes, holding its size, so that `pfree()` and
+    /// `repalloc()` can find its `Layout`.  It's also the alignment, which is at least `MAXALIGN`
+    const HEADER: usize = 16;
+
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(size + HEADER, HEADER).expect("palloc() request too large")
+    }
+
+    pub unsafe fn allocate(size: usize, zeroed: bool) -> *mut c_void {
+        let layout = layout(size);
+        let base = if zeroed {
+            std::alloc::alloc_zeroed(layout)
+        } else {
+            std::alloc::alloc(layout)
+        };
+        if base.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+
+        (base as *mut usize).write(size);
+        base.add(HEADER) as *mut c_void
+    }
+
+    pub unsafe fn reallocate(pointer: *mut c_void, size: usize) -> *mut c_void {
+        let base = (pointer as *mut u8).sub(HEADER);
+        let old_size = (base as *const usize).read();
+        let base = std::alloc::realloc(base, layout(old_size), size + HEADER);
+        if base.is_null() {
+            std::alloc::handle_alloc_error(layout(size));
+        }
+
+        (base as *mut usize).write(size);
+        base.add(HEADER) as *mut c_void
+    }
+
+    pub unsafe fn free(pointer: *mut c_void) {
+        let base = (pointer as *mut u8).sub(HEADER);
+        let size = (base as *const usize).read();
+        std::alloc::dealloc(base, layout(size));
+    }
+
+    pub unsafe fn strdup(s: *const c_char) -> *mut c_char {
+        let bytes = std::ffi::CStr::from_ptr(s).to_bytes_with_nul();
+        let copy = allocate(bytes.len(), false) as *mut c_char;
+        std::ptr::copy_nonoverlapping(s, copy, bytes.len());
+        copy
+    }
+
+    pub unsafe fn cstring_to_text_with_len(s: *const c_char, len: c_int) -> *mut pg_sys::text {
+        let len = len as usize;
+        let text = allocate(len + pg_sys::VARHDRSZ, false) as *mut pg_sys::text;
+        set_varsize(text, (len + pg_sys::VARHDRSZ) as i32);
+        std::ptr::copy_nonoverlapping(s, vardata_any(text) as *mut c_char, len);
+        text
+    }
+
+    pub unsafe fn text_to_cstring(t: *const pg_sys::text) -> *mut c_char {
+        let t = detoast_packed(t as *mut pg_sys::varlena);
+        let len = varsize_any_exhdr(t);
+        let s = allocate(len + 1, false) as *mut c_char;
+        std::ptr::copy_nonoverlapping(vardata_any(t), s, len);
+        *s.add(len) = 0;
+        s
+    }
+
+    /// What Postgres calls an "extended" value: toasted, compressed, or with a short header
+    unsafe fn is_extended(datum: *const pg_sys::varlena) -> bool {
+        !varatt_is_4b_u(datum)
+    }
+
+    unsafe fn check_not_toasted(datum: *const pg_sys::varlena) {
+        if is_extended(datum) && !(varatt_is_1b(datum) && !varatt_is_1b_e(datum)) {
+            panic!("pgx::mock can't detoast external or compressed values");
+        }
+    }
+
+    /// The value with a regular 4-byte header, copied if it had a short one or if `copy` is set
+    pub unsafe fn detoast(datum: *mut pg_sys::varlena, copy: bool) -> *mut pg_sys::varlena {
+        check_not_toasted(datum);
+        if !is_extended(datum) && !copy {
+            return datum;
+        }
+
+        let len = varsize_any_exhdr(datum);
+        let result = allocate(len + pg_sys::VARHDRSZ, false) as *mut pg_sys::varlena;
+        set_varsize(result, (len + pg_sys::VARHDRSZ) as i32);
+        std::ptr::copy_nonoverlapping(vardata_any(datum), vardata_any(result) as *mut c_char, len);
+        debug_assert_eq!(varsize_any(result), len + pg_sys::VARHDRSZ);
+        result
+    }
+
+    pub unsafe fn detoast_packed(datum: *mut pg_sys::varlena) -> *mut pg_sys::varlena {
+        check_not_toasted(datum);
+        datum
+    }
+
+    pub unsafe fn get_fn_expr_argtype(flinfo: *mut pg_sys::FmgrInfo, argnum: c_int) -> pg_sys::Oid {
+        ARG_TYPES.with(|types| {
+            types
+                .borrow()
+                .get(&(flinfo as usize))
+                .and_then(|types| types.get(argnum as usize).cloned())
+                .unwrap_or(pg_sys::InvalidOid)
+        })
+    }
+}
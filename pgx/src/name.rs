@@ -0,0 +1,115 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Helpers for identifiers, Postgres `Name`s, and quoting values into dynamic SQL
+//!
+//! Building SQL for `Spi` by pasting strings together is only safe when every identifier goes
+//! through `quote_identifier()` and every value through `quote_literal()` (or, better yet, is
+//! passed as an argument).  These call the same functions Postgres' own `format('%I %L')` does.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! fn count_matching(schema: &str, table: &str, column: &str, value: &str) -> Option<i64> {
+//!     Spi::get_one(&format!(
+//!         "SELECT count(*) FROM {} WHERE {} = {}",
+//!         quote_qualified_identifier(schema, table),
+//!         quote_identifier(column),
+//!         quote_literal(value)
+//!     ))
+//! }
+//! ```
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use std::ffi::{CStr, CString};
+
+/// The longest an identifier can be, in bytes.  Longer ones are truncated, like Postgres does
+pub const MAX_IDENTIFIER_LEN: usize = pg_sys::NAMEDATALEN as usize - 1;
+
+/// Convert `s` to a `CString`, raising an ERROR if it contains a null byte.  Postgres strings
+/// can't contain them, so this is the error Postgres itself would raise
+pub fn to_cstring(s: &str) -> CString {
+    match CString::new(s) {
+        Ok(cstring) => cstring,
+        Err(_) => {
+            ereport(
+                PgLogLevel::ERROR,
+                PgSqlErrorCode::ERRCODE_CHARACTER_NOT_IN_REPERTOIRE,
+                "invalid byte sequence for encoding \"UTF8\": 0x00",
+                file!(),
+                line!(),
+                column!(),
+            );
+            unreachable!()
+        }
+    }
+}
+
+/// `ident`, truncated to `MAX_IDENTIFIER_LEN` bytes without splitting a character
+pub fn truncate_identifier(ident: &str) -> &str {
+    let mut len = ident.len().min(MAX_IDENTIFIER_LEN);
+    while !ident.is_char_boundary(len) {
+        len -= 1;
+    }
+    &ident[..len]
+}
+
+/// Create a `pg_sys::NameData` holding `s`, truncated as `truncate_identifier()` does
+pub fn str_to_name(s: &str) -> pg_sys::NameData {
+    let cstring = to_cstring(truncate_identifier(s));
+    let mut name = pg_sys::NameData {
+        data: [0; pg_sys::NAMEDATALEN as usize],
+    };
+    for (dest, src) in name.data.iter_mut().zip(cstring.as_bytes()) {
+        *dest = *src as std::os::raw::c_char;
+    }
+    name
+}
+
+/// The identifier held in a `pg_sys::NameData`
+pub fn name_to_str(name: &pg_sys::NameData) -> &str {
+    let bytes =
+        unsafe { std::slice::from_raw_parts(name.data.as_ptr() as *const u8, name.data.len()) };
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..len]).expect("Name is not valid UTF8")
+}
+
+/// Quote `ident` for use as an identifier in SQL, if it needs quoting.  It needs quoting if it
+/// isn't all lowercase, contains special characters, or is a keyword
+pub fn quote_identifier(ident: &str) -> String {
+    let cstring = to_cstring(ident);
+    unsafe {
+        // returns its argument when no quoting is needed, and otherwise a palloc'd copy
+        let quoted = pg_sys::quote_identifier(cstring.as_ptr());
+        let result = CStr::from_ptr(quoted).to_string_lossy().into_owned();
+        if quoted != cstring.as_ptr() {
+            pg_sys::pfree(quoted as crate::void_mut_ptr);
+        }
+        result
+    }
+}
+
+/// Quote `ident`, qualified by `qualifier`, such as a table name and the schema it's in
+pub fn quote_qualified_identifier(qualifier: &str, ident: &str) -> String {
+    format!("{}.{}", quote_identifier(qualifier), quote_identifier(ident))
+}
+
+/// Quote `value` as a string literal for use in SQL, escaping any quotes and backslashes
+pub fn quote_literal(value: &str) -> String {
+    let cstring = to_cstring(value);
+    unsafe {
+        let quoted = pg_sys::quote_literal_cstr(cstring.as_ptr());
+        let result = CStr::from_ptr(quoted).to_string_lossy().into_owned();
+        pg_sys::pfree(quoted as crate::void_mut_ptr);
+        result
+    }
+}
+
+/// Like `quote_literal()`, but `None` becomes an unquoted `NULL`
+pub fn quote_nullable(value: Option<&str>) -> String {
+    match value {
+        Some(value) => quote_literal(value),
+        None => "NULL".to_string(),
+    }
+}
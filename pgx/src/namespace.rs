@@ -8,7 +8,7 @@ use crate::pg_sys;
 
 /// A helper struct for creating a Postgres `List` of `String`s to qualify an object name
 pub struct PgQualifiedNameBuilder {
-    list: PgList<pg_sys::Value>,
+    list: PgList<pg_sys::Node>,
 }
 
 impl Default for PgQualifiedNameBuilder {
@@ -20,13 +20,16 @@ impl Default for PgQualifiedNameBuilder {
 impl PgQualifiedNameBuilder {
     pub fn new() -> PgQualifiedNameBuilder {
         PgQualifiedNameBuilder {
-            list: PgList::<pg_sys::Value>::new(),
+            list: PgList::<pg_sys::Node>::new(),
         }
     }
 
     pub fn push(mut self, value: &str) -> PgQualifiedNameBuilder {
-        self.list
-            .push(unsafe { pg_sys::makeString(std::ffi::CString::new(value).unwrap().into_raw()) });
+        // `makeString()` returns a `Value` before Postgres 15, and a `String` node since
+        self.list.push(unsafe {
+            pg_sys::makeString(std::ffi::CString::new(value).unwrap().into_raw())
+                as *mut pg_sys::Node
+        });
         self
     }
 
@@ -0,0 +1,195 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Typed constructors for the expression nodes FDWs and custom scans most often have to build,
+//! wrapping Postgres' `nodes/makefuncs.h`
+//!
+//! Each fills in the type, typmod, and collation details Postgres would, so the resulting nodes
+//! are indistinguishable from the ones the parser makes.  Nodes are allocated in the
+//! `CurrentMemoryContext`, and belong to Postgres once they're part of a tree.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! // `abs(42)`, where 1397 is the Oid of `abs(integer)`
+//! let abs = make_func_expr(1397, vec![make_const(42i32).into_expr()]);
+//! let tle = make_target_entry(abs.into_expr(), 1, Some("abs"), false);
+//! ```
+use crate::{pg_sys, IntoDatum, PgBox, PgList, PgMemoryContexts};
+
+/// An expression node that can be the argument of another expression
+pub trait PgExprNode {
+    /// This node, as a plain `pg_sys::Node` pointer
+    fn into_expr(self) -> *mut pg_sys::Node;
+}
+
+impl PgExprNode for *mut pg_sys::Node {
+    fn into_expr(self) -> *mut pg_sys::Node {
+        self
+    }
+}
+
+macro_rules! impl_expr_node {
+    ($($ty:ty),*) => {
+        $(
+            impl PgExprNode for PgBox<$ty> {
+                fn into_expr(self) -> *mut pg_sys::Node {
+                    self.into_pg() as *mut pg_sys::Node
+                }
+            }
+        )*
+    };
+}
+
+impl_expr_node!(
+    pg_sys::Const,
+    pg_sys::Var,
+    pg_sys::Param,
+    pg_sys::FuncExpr,
+    pg_sys::OpExpr,
+    pg_sys::BoolExpr,
+    pg_sys::RelabelType,
+    pg_sys::Expr
+);
+
+/// A `Const` holding `value`, whose type is `T`'s Postgres type.  `None` is a NULL constant
+pub fn make_const<T: IntoDatum>(value: T) -> PgBox<pg_sys::Const> {
+    let type_oid = T::type_oid();
+    let mut typlen = 0;
+    let mut typbyval = false;
+    unsafe { pg_sys::get_typlenbyval(type_oid, &mut typlen, &mut typbyval) };
+
+    let datum = value.into_datum();
+    PgBox::from_pg(unsafe {
+        pg_sys::makeConst(
+            type_oid,
+            -1,
+            pg_sys::get_typcollation(type_oid),
+            typlen as i32,
+            datum.unwrap_or(0),
+            datum.is_none(),
+            typbyval,
+        )
+    })
+}
+
+/// A NULL `Const` of type `type_oid`
+pub fn make_null_const(type_oid: pg_sys::Oid) -> PgBox<pg_sys::Const> {
+    PgBox::from_pg(unsafe {
+        pg_sys::makeNullConst(type_oid, -1, pg_sys::get_typcollation(type_oid))
+    })
+}
+
+/// A `Var` referring to attribute `attno`, of type `type_oid`, of range table entry `varno`
+pub fn make_var(
+    varno: pg_sys::Index,
+    attno: pg_sys::AttrNumber,
+    type_oid: pg_sys::Oid,
+) -> PgBox<pg_sys::Var> {
+    PgBox::from_pg(unsafe {
+        pg_sys::makeVar(
+            varno,
+            attno,
+            type_oid,
+            -1,
+            pg_sys::get_typcollation(type_oid),
+            0,
+        )
+    })
+}
+
+/// A `Var` referring to `attribute` of range table entry `varno`, with its typmod and collation
+pub fn make_var_for_attribute(
+    varno: pg_sys::Index,
+    attribute: &pg_sys::FormData_pg_attribute,
+) -> PgBox<pg_sys::Var> {
+    PgBox::from_pg(unsafe {
+        pg_sys::makeVar(
+            varno,
+            attribute.attnum,
+            attribute.atttypid,
+            attribute.atttypmod,
+            attribute.attcollation,
+            0,
+        )
+    })
+}
+
+/// A call of the function `funcid` with `args`.  Raises an ERROR if there's no such function,
+/// and panics if it takes a different number of arguments
+pub fn make_func_expr(
+    funcid: pg_sys::Oid,
+    args: Vec<*mut pg_sys::Node>,
+) -> PgBox<pg_sys::FuncExpr> {
+    let rettype = unsafe { pg_sys::get_func_rettype(funcid) };
+    let nargs = unsafe { pg_sys::get_func_nargs(funcid) } as usize;
+    if nargs != args.len() {
+        panic!(
+            "function {} takes {} arguments, but {} were provided",
+            funcid,
+            nargs,
+            args.len()
+        );
+    }
+
+    // like the parser, the function uses the collation of its first collatable argument
+    let inputcollid = args
+        .iter()
+        .map(|arg| unsafe { pg_sys::exprCollation(*arg) })
+        .find(|collid| *collid != pg_sys::InvalidOid)
+        .unwrap_or(pg_sys::InvalidOid);
+    let funccollid = if unsafe { pg_sys::get_typcollation(rettype) } == pg_sys::InvalidOid {
+        pg_sys::InvalidOid
+    } else {
+        inputcollid
+    };
+
+    PgBox::from_pg(unsafe {
+        pg_sys::makeFuncExpr(
+            funcid,
+            rettype,
+            node_list(args),
+            funccollid,
+            inputcollid,
+            pg_sys::CoercionForm_COERCE_EXPLICIT_CALL,
+        )
+    })
+}
+
+/// An `AND`, `OR`, or `NOT` of `args`
+pub fn make_bool_expr(
+    op: pg_sys::BoolExprType,
+    args: Vec<*mut pg_sys::Node>,
+) -> PgBox<pg_sys::BoolExpr> {
+    if op == pg_sys::BoolExprType_NOT_EXPR && args.len() != 1 {
+        panic!("NOT takes exactly one argument");
+    }
+    let expr = unsafe { pg_sys::makeBoolExpr(op, node_list(args), -1) };
+    PgBox::from_pg(expr as *mut pg_sys::BoolExpr)
+}
+
+/// A `TargetEntry` producing `expr` as output column `resno`, which is 1-based.  A `resjunk`
+/// entry is only needed internally, and isn't part of the final result
+pub fn make_target_entry(
+    expr: *mut pg_sys::Node,
+    resno: pg_sys::AttrNumber,
+    name: Option<&str>,
+    resjunk: bool,
+) -> PgBox<pg_sys::TargetEntry> {
+    let resname = name.map_or(std::ptr::null_mut(), |name| {
+        PgMemoryContexts::CurrentMemoryContext.pstrdup(name)
+    });
+    PgBox::from_pg(unsafe {
+        pg_sys::makeTargetEntry(expr as *mut pg_sys::Expr, resno, resname, resjunk)
+    })
+}
+
+fn node_list(nodes: Vec<*mut pg_sys::Node>) -> *mut pg_sys::List {
+    let mut list = PgList::<pg_sys::Node>::new();
+    for node in nodes {
+        list.push(node);
+    }
+    list.into_pg()
+}
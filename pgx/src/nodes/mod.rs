@@ -3,6 +3,8 @@
 
 //! Helper functions and such for Postgres' various query tree `Node`s
 
+mod makefuncs;
+
 #[cfg(feature = "pg10")]
 mod pg10;
 
@@ -12,6 +14,17 @@ mod pg11;
 #[cfg(feature = "pg12")]
 mod pg12;
 
+#[cfg(feature = "pg13")]
+mod pg13;
+
+#[cfg(feature = "pg14")]
+mod pg14;
+
+#[cfg(feature = "pg15")]
+mod pg15;
+
+pub use makefuncs::*;
+
 #[cfg(feature = "pg10")]
 pub use pg10::*;
 
@@ -21,6 +34,15 @@ pub use pg11::*;
 #[cfg(feature = "pg12")]
 pub use pg12::*;
 
+#[cfg(feature = "pg13")]
+pub use pg13::*;
+
+#[cfg(feature = "pg14")]
+pub use pg14::*;
+
+#[cfg(feature = "pg15")]
+pub use pg15::*;
+
 use crate::{pg_sys, PgBox};
 
 /// #define IsA(nodeptr,_type_)            (nodeTag(nodeptr) == T_##_type_)
@@ -0,0 +1,179 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Parse SQL with the server's own parser, walk the resulting trees, and deparse expressions
+//!
+//! `raw_parse()` produces the grammar's raw parse trees, which need no catalog access and so work
+//! for statements that reference objects that don't exist.  `parse_analyze()` goes on to resolve
+//! names and types, producing the same `Query` trees the planner sees.  Either kind of tree can
+//! be walked with a `PgNodeVisitor`.
+//!
+//! Syntax errors, and unknown objects during analysis, are raised as regular Postgres ERRORs.
+//! Use `pg_try()` to handle them.
+//!
+//! Postgres only exports a deparser for expressions, not whole statements, so that's all
+//! `deparse_expression()` can do.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! // count the column references in a statement
+//! let mut columns = 0;
+//! for stmt in raw_parse("SELECT a, b + 1 FROM t WHERE c = 'x'") {
+//!     walk_raw_tree(stmt.as_ptr() as *mut pg_sys::Node, &mut |node: *mut pg_sys::Node| {
+//!         if is_a(node, PgNode::ColumnRef as u32) {
+//!             columns += 1;
+//!         }
+//!         PgWalk::Continue
+//!     });
+//! }
+//! assert_eq!(columns, 3);
+//! ```
+use crate::{is_a, pg_guard, pg_sys, PgBox, PgList, PgNode};
+use std::ffi::{CStr, CString};
+
+/// What a `PgNodeVisitor` wants to happen after it visits a node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PgWalk {
+    /// Go on to visit this node's children
+    Continue,
+
+    /// Don't visit this node's children, but go on with the rest of the tree
+    SkipChildren,
+
+    /// Stop walking the tree
+    Stop,
+}
+
+/// Visits every node of a parse or query tree, parents before their children
+pub trait PgNodeVisitor {
+    fn visit(&mut self, node: *mut pg_sys::Node) -> PgWalk;
+}
+
+impl<F: FnMut(*mut pg_sys::Node) -> PgWalk> PgNodeVisitor for F {
+    fn visit(&mut self, node: *mut pg_sys::Node) -> PgWalk {
+        self(node)
+    }
+}
+
+/// Parse `sql`, which may contain multiple statements, into raw parse trees.  Raises an ERROR if
+/// it has a syntax error
+pub fn raw_parse(sql: &str) -> Vec<PgBox<pg_sys::RawStmt>> {
+    let sql = CString::new(sql).expect("SQL contains a null byte");
+    let list = PgList::<pg_sys::RawStmt>::from_pg(unsafe { pg_sys::raw_parser(sql.as_ptr()) });
+    list.iter_ptr().map(PgBox::from_pg).collect()
+}
+
+/// Parse `sql`, and then analyze and rewrite each statement into `Query` trees, just as
+/// Postgres does before planning it.  Rules may rewrite one statement into several `Query`s.
+/// Raises an ERROR if `sql` has a syntax error or refers to objects that don't exist
+pub fn parse_analyze(sql: &str) -> Vec<PgBox<pg_sys::Query>> {
+    let sql_cstr = CString::new(sql).expect("SQL contains a null byte");
+    let mut queries = Vec::new();
+    for stmt in raw_parse(sql) {
+        let rewritten = PgList::<pg_sys::Query>::from_pg(unsafe {
+            pg_sys::pg_analyze_and_rewrite(
+                stmt.into_pg(),
+                sql_cstr.as_ptr(),
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+            )
+        });
+        queries.extend(rewritten.iter_ptr().map(PgBox::from_pg));
+    }
+    queries
+}
+
+/// Walk a raw parse tree, as returned by `raw_parse()`.  Returns `true` if the `visitor` stopped
+/// the walk
+pub fn walk_raw_tree(node: *mut pg_sys::Node, visitor: &mut dyn PgNodeVisitor) -> bool {
+    let mut visitor = visitor;
+    unsafe {
+        raw_walker(
+            node,
+            &mut visitor as *mut &mut dyn PgNodeVisitor as crate::void_mut_ptr,
+        )
+    }
+}
+
+/// Walk an analyzed `Query` tree, or an expression tree within one, including any subqueries.
+/// Returns `true` if the `visitor` stopped the walk
+pub fn walk_query_tree(node: *mut pg_sys::Node, visitor: &mut dyn PgNodeVisitor) -> bool {
+    let mut visitor = visitor;
+    unsafe {
+        query_walker(
+            node,
+            &mut visitor as *mut &mut dyn PgNodeVisitor as crate::void_mut_ptr,
+        )
+    }
+}
+
+/// Convert an expression from an analyzed `Query` back into SQL.  Any `Var`s must refer to
+/// `relation`, given as its alias and `Oid`
+pub fn deparse_expression(
+    expr: *mut pg_sys::Node,
+    relation: Option<(&str, pg_sys::Oid)>,
+) -> String {
+    let context = match relation {
+        Some((alias, relid)) => {
+            let alias = CString::new(alias).expect("alias contains a null byte");
+            unsafe { pg_sys::deparse_context_for(alias.as_ptr(), relid) }
+        }
+        None => std::ptr::null_mut(),
+    };
+
+    unsafe {
+        let sql = pg_sys::deparse_expression(expr, context, false, false);
+        let result = CStr::from_ptr(sql).to_string_lossy().into_owned();
+        pg_sys::pfree(sql as crate::void_mut_ptr);
+        result
+    }
+}
+
+/// Visit `node`, and then, unless told not to, its children
+unsafe fn visit_node(
+    node: *mut pg_sys::Node,
+    context: crate::void_mut_ptr,
+    walk_children: impl FnOnce() -> bool,
+) -> bool {
+    if node.is_null() {
+        return false;
+    }
+
+    let visitor = &mut *(context as *mut &mut dyn PgNodeVisitor);
+    match visitor.visit(node) {
+        PgWalk::Continue => walk_children(),
+        PgWalk::SkipChildren => false,
+        PgWalk::Stop => true,
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn raw_walker(node: *mut pg_sys::Node, context: crate::void_mut_ptr) -> bool {
+    visit_node(node, context, || {
+        if is_a(node, PgNode::RawStmt as u32) {
+            // `raw_expression_tree_walker()` doesn't know about the node that wraps each statement
+            raw_walker((*(node as *mut pg_sys::RawStmt)).stmt, context)
+        } else {
+            // bindgen doesn't know the walker's arguments, since it's declared as `bool (*walker) ()`
+            let walker: unsafe extern "C" fn() -> bool = std::mem::transmute(
+                raw_walker as unsafe extern "C" fn(*mut pg_sys::Node, crate::void_mut_ptr) -> bool,
+            );
+            pg_sys::raw_expression_tree_walker(node, Some(walker), context)
+        }
+    })
+}
+
+#[pg_guard]
+unsafe extern "C" fn query_walker(node: *mut pg_sys::Node, context: crate::void_mut_ptr) -> bool {
+    visit_node(node, context, || {
+        if is_a(node, PgNode::Query as u32) {
+            pg_sys::query_tree_walker(node as *mut pg_sys::Query, Some(query_walker), context, 0)
+        } else {
+            pg_sys::expression_tree_walker(node, Some(query_walker), context)
+        }
+    })
+}
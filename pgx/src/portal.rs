@@ -0,0 +1,180 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Inspect the portals, which includes every open cursor, of the current backend
+//!
+//! `PgPortal::all()` lists the same portals as the `pg_cursors` view, which are the cursors
+//! opened by `DECLARE`, the protocol-level portals of clients, and those opened through SPI.
+//! Rows can be fetched from any of them with `SpiClient::fetch_cursor()`.
+//!
+//! A `PgPortal` is only valid until its cursor is closed, so it shouldn't be held on to across
+//! statements.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! #[pg_extern]
+//! fn open_cursor_positions() -> impl std::iter::Iterator<Item = (name!(name, String), name!(position, i64))> {
+//!     PgPortal::all()
+//!         .into_iter()
+//!         .map(|portal| (portal.name().to_string(), portal.position() as i64))
+//!         .collect::<Vec<_>>()
+//!         .into_iter()
+//! }
+//! ```
+use crate::{pg_sys, Spi};
+use std::ffi::{CStr, CString};
+
+/// The lifecycle state of a portal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PgPortalStatus {
+    /// freshly created
+    New,
+
+    /// its query has been defined, but it hasn't been started
+    Defined,
+
+    /// ready to fetch from
+    Ready,
+
+    /// currently running a query
+    Active,
+
+    /// finished running, and can't be fetched from any longer
+    Done,
+
+    /// an ERROR was raised while it was running
+    Failed,
+}
+
+/// A wrapper around a Postgres `Portal`
+pub struct PgPortal {
+    portal: pg_sys::Portal,
+}
+
+impl PgPortal {
+    /// The portal, or cursor, named `name`, if it exists
+    pub fn find(name: &str) -> Option<Self> {
+        let name = CString::new(name).expect("portal name contains a null byte");
+        let portal = unsafe { pg_sys::GetPortalByName(name.as_ptr()) };
+        if portal.is_null() {
+            None
+        } else {
+            Some(PgPortal { portal })
+        }
+    }
+
+    /// Every portal listed in the `pg_cursors` view, in no particular order
+    pub fn all() -> Vec<Self> {
+        Spi::get_one::<Vec<Option<String>>>("SELECT array_agg(name) FROM pg_cursors")
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .filter_map(|name| PgPortal::find(&name))
+            .collect()
+    }
+
+    /// Wrap a `pg_sys::Portal` Postgres gave us
+    pub fn from_pg(portal: pg_sys::Portal) -> Self {
+        if portal.is_null() {
+            panic!("Portal is NULL");
+        }
+        PgPortal { portal }
+    }
+
+    /// The underlying `pg_sys::Portal`
+    pub fn as_ptr(&self) -> pg_sys::Portal {
+        self.portal
+    }
+
+    /// The portal's name, which is the cursor's name for cursors
+    pub fn name(&self) -> &str {
+        unsafe { self.c_str((*self.portal).name) }.unwrap_or_default()
+    }
+
+    /// The name of the prepared statement the portal is running, if any
+    pub fn prepared_statement_name(&self) -> Option<&str> {
+        unsafe { self.c_str((*self.portal).prepStmtName) }
+    }
+
+    /// The text of the query the portal is running, once it's been defined
+    pub fn source_text(&self) -> Option<&str> {
+        unsafe { self.c_str((*self.portal).sourceText) }
+    }
+
+    /// The command tag of the portal's query, such as `"SELECT"`, once it's been defined
+    pub fn command_tag(&self) -> Option<&str> {
+        unsafe { self.c_str((*self.portal).commandTag) }
+    }
+
+    /// The portal's lifecycle state
+    pub fn status(&self) -> PgPortalStatus {
+        match unsafe { (*self.portal).status } {
+            pg_sys::PortalStatus_PORTAL_NEW => PgPortalStatus::New,
+            pg_sys::PortalStatus_PORTAL_DEFINED => PgPortalStatus::Defined,
+            pg_sys::PortalStatus_PORTAL_READY => PgPortalStatus::Ready,
+            pg_sys::PortalStatus_PORTAL_ACTIVE => PgPortalStatus::Active,
+            pg_sys::PortalStatus_PORTAL_DONE => PgPortalStatus::Done,
+            _ => PgPortalStatus::Failed,
+        }
+    }
+
+    /// Was the cursor declared `WITH HOLD`, so it outlives its transaction?
+    pub fn is_holdable(&self) -> bool {
+        self.has_cursor_option(pg_sys::CURSOR_OPT_HOLD)
+    }
+
+    /// Was the cursor declared `BINARY`?
+    pub fn is_binary(&self) -> bool {
+        self.has_cursor_option(pg_sys::CURSOR_OPT_BINARY)
+    }
+
+    /// Was the cursor declared `SCROLL`, so it can be fetched from backwards?
+    pub fn is_scrollable(&self) -> bool {
+        self.has_cursor_option(pg_sys::CURSOR_OPT_SCROLL)
+    }
+
+    /// Is the portal shown in `pg_cursors`?  The unnamed portal of the statement currently being
+    /// run is not
+    pub fn is_visible(&self) -> bool {
+        unsafe { (*self.portal).visible }
+    }
+
+    /// Is the cursor positioned before its first row?
+    pub fn at_start(&self) -> bool {
+        unsafe { (*self.portal).atStart }
+    }
+
+    /// Is the cursor positioned after its last row?
+    pub fn at_end(&self) -> bool {
+        unsafe { (*self.portal).atEnd }
+    }
+
+    /// How many rows the cursor is past its start, counting the row it's positioned on
+    pub fn position(&self) -> u64 {
+        unsafe { (*self.portal).portalPos }
+    }
+
+    /// When the portal was created
+    pub fn creation_time(&self) -> pg_sys::TimestampTz {
+        unsafe { (*self.portal).creation_time }
+    }
+
+    fn has_cursor_option(&self, option: u32) -> bool {
+        unsafe { (*self.portal).cursorOptions as u32 & option != 0 }
+    }
+
+    unsafe fn c_str(&self, ptr: *const std::os::raw::c_char) -> Option<&str> {
+        if ptr.is_null() {
+            None
+        } else {
+            Some(
+                CStr::from_ptr(ptr)
+                    .to_str()
+                    .expect("portal string is not valid UTF8"),
+            )
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Inspect and deallocate the current backend's prepared statements
+//!
+//! `PgPreparedStatement::all()` lists the same statements as the `pg_prepared_statements` view,
+//! which are those created by `PREPARE` and by clients using the extended query protocol.
+//!
+//! A `PgPreparedStatement` is only valid until the statement is deallocated, so it shouldn't be
+//! held on to across statements.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! /// deallocate the statements that have never been worth a generic plan
+//! #[pg_extern]
+//! fn deallocate_custom_planned() -> i32 {
+//!     let mut count = 0;
+//!     for stmt in PgPreparedStatement::all() {
+//!         if stmt.num_custom_plans() > 100 {
+//!             stmt.deallocate();
+//!             count += 1;
+//!         }
+//!     }
+//!     count
+//! }
+//! ```
+use crate::{name_to_str, pg_sys, Spi};
+use std::ffi::{CStr, CString};
+
+/// A wrapper around one of the backend's prepared statements
+pub struct PgPreparedStatement {
+    stmt: *mut pg_sys::PreparedStatement,
+}
+
+impl PgPreparedStatement {
+    /// The prepared statement named `name`, if it exists
+    pub fn find(name: &str) -> Option<Self> {
+        let name = CString::new(name).expect("statement name contains a null byte");
+        let stmt = unsafe { pg_sys::FetchPreparedStatement(name.as_ptr(), false) };
+        if stmt.is_null() {
+            None
+        } else {
+            Some(PgPreparedStatement { stmt })
+        }
+    }
+
+    /// Every prepared statement listed in the `pg_prepared_statements` view, in no particular
+    /// order
+    pub fn all() -> Vec<Self> {
+        Spi::get_one::<Vec<Option<String>>>("SELECT array_agg(name) FROM pg_prepared_statements")
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .filter_map(|name| PgPreparedStatement::find(&name))
+            .collect()
+    }
+
+    /// Deallocate every prepared statement, like `DEALLOCATE ALL`
+    pub fn deallocate_all() {
+        unsafe { pg_sys::DropAllPreparedStatements() }
+    }
+
+    /// Discard every cached plan in the backend, including those of prepared statements, so
+    /// they're re-planned the next time they're run
+    pub fn invalidate_all_plans() {
+        unsafe { pg_sys::ResetPlanCache() }
+    }
+
+    /// The underlying `pg_sys::PreparedStatement`
+    pub fn as_ptr(&self) -> *mut pg_sys::PreparedStatement {
+        self.stmt
+    }
+
+    /// The statement's name
+    pub fn name(&self) -> &str {
+        // `stmt_name` is laid out exactly like a `NameData`
+        unsafe { name_to_str(&*((*self.stmt).stmt_name.as_ptr() as *const pg_sys::NameData)) }
+    }
+
+    /// The text of the statement's query
+    pub fn query_string(&self) -> &str {
+        unsafe { CStr::from_ptr((*self.plansource()).query_string) }
+            .to_str()
+            .expect("query string is not valid UTF8")
+    }
+
+    /// The types of the statement's parameters
+    pub fn param_types(&self) -> Vec<pg_sys::Oid> {
+        unsafe {
+            let plansource = self.plansource();
+            if (*plansource).num_params == 0 {
+                return Vec::new();
+            }
+            std::slice::from_raw_parts((*plansource).param_types, (*plansource).num_params as usize)
+                .to_vec()
+        }
+    }
+
+    /// Was the statement created by a SQL `PREPARE`, rather than by the client protocol?
+    pub fn from_sql(&self) -> bool {
+        unsafe { (*self.stmt).from_sql }
+    }
+
+    /// When the statement was prepared
+    pub fn prepare_time(&self) -> pg_sys::TimestampTz {
+        unsafe { (*self.stmt).prepare_time }
+    }
+
+    /// Is the statement's analyzed query still valid?  It's re-analyzed the next time it's run
+    /// if not, such as after a table it uses is altered
+    pub fn is_valid(&self) -> bool {
+        unsafe { (*self.plansource()).is_valid }
+    }
+
+    /// How many times the statement has been planned specifically for its parameter values,
+    /// rather than run with a generic plan
+    pub fn num_custom_plans(&self) -> i32 {
+        unsafe { (*self.plansource()).num_custom_plans }
+    }
+
+    /// Deallocate the statement, like `DEALLOCATE`
+    pub fn deallocate(self) {
+        let name = CString::new(self.name()).unwrap();
+        unsafe { pg_sys::DropPreparedStatement(name.as_ptr(), false) }
+    }
+
+    fn plansource(&self) -> *mut pg_sys::CachedPlanSource {
+        unsafe { (*self.stmt).plansource }
+    }
+}
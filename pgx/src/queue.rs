@@ -0,0 +1,293 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! A durable work queue backed by a regular table and `SELECT ... FOR UPDATE SKIP LOCKED`
+//!
+//! Messages are enqueued either from Rust, via `PgQueue::enqueue()`, or directly from SQL with a
+//! plain `INSERT INTO my_queue (payload) VALUES ('{...}')`.  Dequeuing a message hides it from
+//! other consumers for a "visibility timeout".  If the consumer doesn't `ack()` the message before
+//! the timeout expires (because it crashed, or its transaction aborted), the message becomes
+//! visible again and will be retried.
+//!
+//! `PgQueueWorkerPool` registers a set of background workers which drain a queue by calling a
+//! Rust function for each message.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use pgx::queue::{PgQueue, PgQueueWorkerPool, QueueMessage};
+//! use std::time::Duration;
+//!
+//! pg_module_magic!();
+//!
+//! extension_sql!(
+//!     r#"
+//! CREATE TABLE my_queue (
+//!     id bigserial PRIMARY KEY,
+//!     payload jsonb NOT NULL,
+//!     enqueued_at timestamptz NOT NULL DEFAULT now(),
+//!     visible_at timestamptz NOT NULL DEFAULT now(),
+//!     attempts integer NOT NULL DEFAULT 0
+//! );
+//! "#
+//! );
+//!
+//! #[pg_guard]
+//! pub extern "C" fn _PG_init() {
+//!     PgQueueWorkerPool::new("my_queue", "my_extension", "queue_worker_main")
+//!         .set_workers(4)
+//!         .load();
+//! }
+//!
+//! #[pg_guard]
+//! pub extern "C" fn queue_worker_main(_arg: pg_sys::Datum) {
+//!     PgQueue::run_worker(Some("postgres"), None, Duration::from_secs(30), |message: &QueueMessage| {
+//!         info!("processing message {}: {}", message.id, message.payload);
+//!         Ok(())
+//!     });
+//! }
+//! ```
+use crate::bgworkers::{BackgroundWorker, BackgroundWorkerBuilder, SignalWakeFlags};
+use crate::{subtransaction, IntoDatum, JsonB, PgBuiltInOids, Spi};
+use serde_json::Value;
+use std::time::Duration;
+
+/// A message dequeued from a `PgQueue`
+#[derive(Debug)]
+pub struct QueueMessage {
+    /// The message's unique id, used to `ack()` it
+    pub id: i64,
+
+    /// The message itself
+    pub payload: Value,
+
+    /// How many times this message has been dequeued, including this time
+    pub attempts: i32,
+}
+
+/// A work queue stored in the table `table_name`
+///
+/// `table_name` is interpolated directly into SQL, so it must be a trusted, properly-quoted
+/// (and optionally schema-qualified) identifier
+pub struct PgQueue {
+    table_name: String,
+}
+
+impl PgQueue {
+    pub fn new(table_name: &str) -> Self {
+        PgQueue {
+            table_name: table_name.to_string(),
+        }
+    }
+
+    /// The table name this queue is stored in
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// The DDL necessary to create this queue's table.  This is typically included in your
+    /// extension's schema via `extension_sql!()`, but can also be run directly with `Spi::run()`
+    pub fn create_table_sql(&self) -> String {
+        format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+                id bigserial PRIMARY KEY,
+                payload jsonb NOT NULL,
+                enqueued_at timestamptz NOT NULL DEFAULT now(),
+                visible_at timestamptz NOT NULL DEFAULT now(),
+                attempts integer NOT NULL DEFAULT 0
+            )",
+            table = self.table_name
+        )
+    }
+
+    /// Add a message to the queue, returning its id.  The message becomes visible to consumers
+    /// once the current transaction commits
+    pub fn enqueue(&self, payload: Value) -> i64 {
+        Spi::get_one_with_args(
+            &format!(
+                "INSERT INTO {} (payload) VALUES ($1) RETURNING id",
+                self.table_name
+            ),
+            vec![(PgBuiltInOids::JSONBOID.oid(), JsonB(payload).into_datum())],
+        )
+        .expect("INSERT into queue returned NULL")
+    }
+
+    /// Claim the oldest visible message, hiding it from other consumers for `visibility_timeout`.
+    ///
+    /// Messages locked by other, concurrent, transactions are skipped rather than waited on, so
+    /// any number of consumers can dequeue from the same queue at once.  Returns `None` if there
+    /// are no visible messages
+    pub fn dequeue(&self, visibility_timeout: Duration) -> Option<QueueMessage> {
+        let (id, payload, attempts) = Spi::get_three_with_args::<i64, JsonB, i32>(
+            &format!(
+                "UPDATE {table}
+                    SET visible_at = clock_timestamp() + $1 * interval '1 millisecond',
+                        attempts = attempts + 1
+                  WHERE id = (SELECT id
+                                FROM {table}
+                               WHERE visible_at <= clock_timestamp()
+                            ORDER BY id
+                               LIMIT 1
+                                 FOR UPDATE SKIP LOCKED)
+              RETURNING id, payload, attempts",
+                table = self.table_name
+            ),
+            vec![(
+                PgBuiltInOids::INT8OID.oid(),
+                (visibility_timeout.as_millis() as i64).into_datum(),
+            )],
+        );
+
+        Some(QueueMessage {
+            id: id?,
+            payload: payload?.0,
+            attempts: attempts.unwrap_or_default(),
+        })
+    }
+
+    /// Permanently remove a message from the queue once it has been processed.  Returns false if
+    /// the message no longer exists
+    pub fn ack(&self, id: i64) -> bool {
+        Spi::get_one_with_args::<i64>(
+            &format!("DELETE FROM {} WHERE id = $1 RETURNING id", self.table_name),
+            vec![(PgBuiltInOids::INT8OID.oid(), id.into_datum())],
+        )
+        .is_some()
+    }
+
+    /// Make a dequeued message immediately visible again, rather than waiting for its visibility
+    /// timeout to expire
+    pub fn release(&self, id: i64) -> bool {
+        Spi::get_one_with_args::<i64>(
+            &format!(
+                "UPDATE {} SET visible_at = clock_timestamp() WHERE id = $1 RETURNING id",
+                self.table_name
+            ),
+            vec![(PgBuiltInOids::INT8OID.oid(), id.into_datum())],
+        )
+        .is_some()
+    }
+
+    /// How many messages are in the queue, visible or not
+    pub fn len(&self) -> i64 {
+        Spi::get_one(&format!("SELECT count(*) FROM {}", self.table_name))
+            .expect("count(*) returned NULL")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The main loop for a queue background worker registered by `PgQueueWorkerPool`.
+    ///
+    /// Connects to `dbname` as `username`, then repeatedly dequeues messages from the queue named in
+    /// the worker's `extra` data and passes each to `handler`.  Each message is claimed in its own
+    /// transaction and then processed (and acknowledged) in a second one.  The handler runs in a
+    /// subtransaction, so an ERROR it raises is rolled back and logged like an `Err`, and the
+    /// worker carries on.  Either way, the message is retried after `visibility_timeout`.
+    ///
+    /// Returns once the worker receives a SIGTERM
+    pub fn run_worker<F>(
+        dbname: Option<&str>,
+        username: Option<&str>,
+        visibility_timeout: Duration,
+        handler: F,
+    ) where
+        F: Fn(&QueueMessage) -> Result<(), String> + std::panic::RefUnwindSafe,
+    {
+        BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGHUP | SignalWakeFlags::SIGTERM);
+        BackgroundWorker::connect_worker_to_spi(dbname, username);
+
+        let queue = PgQueue::new(BackgroundWorker::get_extra());
+        let poll_interval = Duration::from_secs(1);
+
+        while BackgroundWorker::wait_latch(Some(poll_interval)) {
+            // drain everything that's currently visible before waiting again
+            loop {
+                let dequeued = BackgroundWorker::transaction(|| queue.dequeue(visibility_timeout));
+                let message = match dequeued {
+                    Some(message) => message,
+                    None => break,
+                };
+                BackgroundWorker::transaction(|| {
+                    // an ERROR only rolls back the handler's subtransaction, not the worker
+                    let error = match subtransaction(|| handler(&message)) {
+                        Ok(Ok(())) => {
+                            queue.ack(message.id);
+                            return;
+                        }
+                        Ok(Err(e)) => e,
+                        Err(caught) => caught.message,
+                    };
+                    warning!(
+                        "{}: message {} failed (attempt {}): {}",
+                        BackgroundWorker::get_name(),
+                        message.id,
+                        message.attempts,
+                        error
+                    );
+                });
+
+                BackgroundWorker::heartbeat();
+                if BackgroundWorker::sigterm_received() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// A builder-style interface for registering a pool of background workers that drain a `PgQueue`
+///
+/// Like `BackgroundWorkerBuilder`, this must be used from within your extension's `_PG_init()`
+/// function, and the extension must be loaded via `shared_preload_libraries`.  The named
+/// `function` should call `PgQueue::run_worker()`
+pub struct PgQueueWorkerPool {
+    table_name: String,
+    library: String,
+    function: String,
+    workers: usize,
+    restart_time: Option<Duration>,
+}
+
+impl PgQueueWorkerPool {
+    /// By default, the pool has a single worker that is restarted 10 seconds after crashing
+    pub fn new(table_name: &str, library: &str, function: &str) -> Self {
+        PgQueueWorkerPool {
+            table_name: table_name.to_string(),
+            library: library.to_string(),
+            function: function.to_string(),
+            workers: 1,
+            restart_time: Some(Duration::from_secs(10)),
+        }
+    }
+
+    /// How many workers should consume from the queue concurrently?
+    pub fn set_workers(mut self: Self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// How long should Postgres wait before restarting a crashed worker?  `None` means never
+    pub fn set_restart_time(mut self: Self, restart_time: Option<Duration>) -> Self {
+        self.restart_time = restart_time;
+        self
+    }
+
+    /// Register each of the pool's workers with Postgres
+    pub fn load(self: Self) {
+        for i in 0..self.workers {
+            BackgroundWorkerBuilder::new(&format!("{} worker {}", self.table_name, i + 1))
+                .set_type(&format!("{} worker", self.table_name))
+                .set_library(&self.library)
+                .set_function(&self.function)
+                .set_extra(&self.table_name)
+                .set_restart_time(self.restart_time)
+                .enable_spi_access()
+                .enable_supervision()
+                .load();
+        }
+    }
+}
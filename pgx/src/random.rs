@@ -0,0 +1,117 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Random numbers that tests can make repeatable
+//!
+//! Extension code that draws its randomness from `pgx::random`, for jitter, sampling, or
+//! picking a victim to evict, gets a differently seeded sequence in every backend.  A test can
+//! `random::seed()` it so the same sequence comes out every run, and the code's choices can be
+//! asserted on.  The previous sequence resumes when the `RandomOverride` that `seed()` returns is
+//! dropped.
+//!
+//! The generator is SplitMix64: fast and statistically sound, but **not** cryptographically
+//! secure.  Use Postgres' `pg_strong_random()` for secrets.  Like `pgx::clock`, the seed only
+//! applies to the current thread, which for a backend is the whole session.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! fn pick_victim(candidates: &[&str]) -> String {
+//!     candidates[random::below(candidates.len() as u64) as usize].to_string()
+//! }
+//!
+//! #[pg_test]
+//! fn test_pick_victim() {
+//!     let _random = random::seed(42);
+//!     let first = pick_victim(&["a", "b", "c"]);
+//!
+//!     let _random = random::seed(42);
+//!     assert_eq!(pick_victim(&["a", "b", "c"]), first);
+//! }
+//! ```
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+thread_local! {
+    static STATE: Cell<u64> = Cell::new(initial_seed());
+}
+
+/// `RandomState` is seeded from the operating system's random source, once per thread
+fn initial_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    hasher.finish()
+}
+
+/// The next random `u64`
+pub fn next_u64() -> u64 {
+    STATE.with(|state| {
+        let mut z = state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+        state.set(z);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    })
+}
+
+/// A random `f64` in `[0, 1)`
+pub fn next_f64() -> f64 {
+    (next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// A random number in `[0, n)`, without the bias of `next_u64() % n`.
+///
+/// Panics if `n` is zero
+pub fn below(n: u64) -> u64 {
+    if n == 0 {
+        panic!("random::below() requires a non-zero bound");
+    }
+
+    // values from the incomplete last multiple of `n` would favor the smaller results
+    let limit = u64::MAX - u64::MAX % n;
+    loop {
+        let value = next_u64();
+        if value < limit {
+            return value % n;
+        }
+    }
+}
+
+/// Fill `bytes` with random bytes
+pub fn fill_bytes(bytes: &mut [u8]) {
+    for chunk in bytes.chunks_mut(8) {
+        let value = next_u64().to_le_bytes();
+        chunk.copy_from_slice(&value[..chunk.len()]);
+    }
+}
+
+/// Shuffle `values` into a random order
+pub fn shuffle<T>(values: &mut [T]) {
+    for i in (1..values.len()).rev() {
+        values.swap(i, below(i as u64 + 1) as usize);
+    }
+}
+
+/// Make the sequence repeatable, starting from `seed`, until the returned `RandomOverride` is
+/// dropped
+pub fn seed(seed: u64) -> RandomOverride {
+    RandomOverride {
+        previous: STATE.with(|state| state.replace(seed)),
+    }
+}
+
+/// Resumes the sequence that was interrupted by `seed()` when dropped
+#[must_use = "the seed is forgotten as soon as the RandomOverride is dropped"]
+pub struct RandomOverride {
+    previous: u64,
+}
+
+impl Drop for RandomOverride {
+    fn drop(&mut self) {
+        let previous = self.previous;
+        STATE.with(|state| state.set(previous));
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Helpers for extensions that may be installed on hot standby replicas
+//!
+//! Postgres itself refuses to write to tables during recovery, but it can't know about an
+//! extension's other side effects, such as writing files, talking to external services, or
+//! modifying shared memory that's expected to mirror table contents.  Functions with side effects
+//! like these can call `check_not_in_recovery()`, or be declared with
+//! `#[pg_extern(requires_primary)]`, to raise a clear ERROR when called on a standby.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use std::time::Duration;
+//!
+//! #[pg_extern(requires_primary)]
+//! fn publish_to_queue(message: &str) {
+//!     // ... talk to the external queue ...
+//! }
+//!
+//! #[pg_extern]
+//! fn read_your_writes(lsn: &str) -> bool {
+//!     // on a standby, wait for the primary's write to be replayed before reading it
+//!     let lsn = recovery::parse_lsn(lsn).expect("invalid LSN");
+//!     recovery::wait_for_lsn(lsn, Duration::from_secs(10))
+//! }
+//! ```
+use crate::advisory_lock::sleep_on_latch;
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use std::time::{Duration, Instant};
+
+/// Is this server a standby (or otherwise still replaying WAL)?  Like `pg_is_in_recovery()`
+pub fn in_recovery() -> bool {
+    unsafe { pg_sys::RecoveryInProgress() }
+}
+
+/// The furthest WAL location that's visible to queries on this server:  the last replayed
+/// location on a standby, or the last flushed location on a primary
+pub fn current_lsn() -> pg_sys::XLogRecPtr {
+    unsafe {
+        if pg_sys::RecoveryInProgress() {
+            pg_sys::GetXLogReplayRecPtr(std::ptr::null_mut())
+        } else {
+            pg_sys::GetFlushRecPtr()
+        }
+    }
+}
+
+/// Wait until `current_lsn()` reaches `lsn`, returning `false` if it didn't within `timeout`.
+///
+/// On a primary this returns immediately for any location that's already been flushed.  Interrupts,
+/// such as a query cancel, are honored while waiting
+pub fn wait_for_lsn(lsn: pg_sys::XLogRecPtr, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let mut delay = Duration::from_millis(1);
+
+    loop {
+        if current_lsn() >= lsn {
+            return true;
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+
+        let sleep = std::cmp::min(delay, deadline - now);
+        sleep_on_latch(sleep);
+        delay = std::cmp::min(delay * 2, Duration::from_millis(100));
+    }
+}
+
+/// Raise an ERROR if this server is in recovery, the same way Postgres rejects commands that
+/// can't run on a standby.  `action` names what was attempted, as in "cannot execute {action}
+/// during recovery"
+pub fn check_not_in_recovery(action: &str) {
+    if in_recovery() {
+        ereport(
+            PgLogLevel::ERROR,
+            PgSqlErrorCode::ERRCODE_READ_ONLY_SQL_TRANSACTION,
+            &format!("cannot execute {} during recovery", action),
+            file!(),
+            line!(),
+            column!(),
+        );
+    }
+}
+
+/// Parse a WAL location in Postgres' `pg_lsn` text format, such as `16/B374D848`
+pub fn parse_lsn(lsn: &str) -> Option<pg_sys::XLogRecPtr> {
+    let mut parts = lsn.trim().splitn(2, '/');
+    let hi = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let lo = u32::from_str_radix(parts.next()?, 16).ok()?;
+    Some((hi as u64) << 32 | lo as u64)
+}
+
+/// Format a WAL location in Postgres' `pg_lsn` text format
+pub fn format_lsn(lsn: pg_sys::XLogRecPtr) -> String {
+    format!("{:X}/{:X}", lsn >> 32, lsn as u32)
+}
@@ -0,0 +1,125 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Installing a new build of an extension's library, and per-backend state outside of it
+//!
+//! Postgres loads a library into a backend at most once, and never unloads it, so `_PG_fini()` is
+//! never called.  Loading it again, even after `cargo pgx install` replaced the file, gives back
+//! the library that's already loaded, as `dfmgr.c` recognizes it by its path (or its inode).  A
+//! new build is therefore only picked up by backends that start after it's installed:
+//!
+//!   - if the library is loaded on demand, by new sessions.  Sessions that already loaded it keep
+//!     running the old build until they reconnect
+//!   - if it's in `shared_preload_libraries`, only once Postgres is restarted, as every backend
+//!     starts out with the postmaster's copy of the old build
+//!
+//! What `cargo pgx install` does make safe is replacing the file under backends that are running
+//! the old build:  it copies the new one next to it and renames it into place, rather than
+//! rewriting the file those backends have mapped.
+//!
+//! `PgBackendState` keeps per-backend state outside of the library, where any library loaded into
+//! the backend finds the same value by name, instead of each having a `static` of its own.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use std::cell::Cell;
+//!
+//! static CALLS: PgBackendState<Cell<i64>> =
+//!     PgBackendState::new("my_extension calls", 1, || Cell::new(0));
+//!
+//! #[pg_extern]
+//! fn my_extension_calls() -> i64 {
+//!     let calls = CALLS.get();
+//!     calls.set(calls.get() + 1);
+//!     calls.get()
+//! }
+//! ```
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+/// A per-backend value that every library loaded into the backend shares by name.  Declare one as
+/// a `static`, and its value is created by `init` the first time any library asks for it.
+///
+/// The value is shared as-is, so all the libraries must agree on the layout of `T`.  `version` is
+/// checked on each library's first access, and must change whenever `T` does.  Like any
+/// per-backend state, `T` needs interior mutability, such as a `Cell` or `RefCell`, to change
+pub struct PgBackendState<T: 'static> {
+    name: &'static str,
+    version: u32,
+    init: fn() -> T,
+
+    /// The value, once this library has found or created it
+    slot: AtomicPtr<BackendStateSlot<T>>,
+}
+
+/// The `version` comes first, so any library can read it without knowing what `T` is in the
+/// library that created the value
+#[repr(C)]
+struct BackendStateSlot<T> {
+    version: u32,
+    value: T,
+}
+
+impl<T: 'static> PgBackendState<T> {
+    /// State named `name`, which must be unique across every extension loaded into the backend
+    pub const fn new(name: &'static str, version: u32, init: fn() -> T) -> Self {
+        PgBackendState {
+            name,
+            version,
+            init,
+            slot: AtomicPtr::new(std::ptr::null_mut()),
+        }
+    }
+
+    /// The backend's value, created now if no library has asked for it yet.  Raises an ERROR if it
+    /// was created with a different `version`
+    pub fn get(&self) -> &'static T {
+        let mut slot = self.slot.load(Ordering::Acquire);
+        if slot.is_null() {
+            slot = self.find_or_create();
+            self.slot.store(slot, Ordering::Release);
+        }
+        unsafe { &(*slot).value }
+    }
+
+    fn find_or_create(&self) -> *mut BackendStateSlot<T> {
+        let rendezvous = rendezvous(&format!("pgx state {}", self.name));
+        unsafe {
+            if (*rendezvous).is_null() {
+                *rendezvous = Box::into_raw(Box::new(BackendStateSlot {
+                    version: self.version,
+                    value: (self.init)(),
+                })) as *mut c_void;
+            }
+
+            let slot = *rendezvous as *mut BackendStateSlot<T>;
+            if (*slot).version != self.version {
+                ereport(
+                    PgLogLevel::ERROR,
+                    PgSqlErrorCode::ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE,
+                    &format!(
+                        "backend state `{}` has version {}, but this library expects version {}.  Reconnect to use it",
+                        self.name,
+                        (*slot).version,
+                        self.version
+                    ),
+                    file!(),
+                    line!(),
+                    column!(),
+                );
+            }
+            slot
+        }
+    }
+}
+
+/// Postgres' process-wide pointer named `name`, which every library loaded into the process can
+/// find, for as long as the process lives
+fn rendezvous(name: &str) -> *mut *mut c_void {
+    let name = CString::new(name).expect("invalid rendezvous variable name");
+    unsafe { pg_sys::find_rendezvous_variable(name.as_ptr()) }
+}
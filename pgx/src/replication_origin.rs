@@ -0,0 +1,233 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Replication origins, for extensions that apply changes from another system
+//!
+//! A replication origin durably tracks how far changes from some remote source have been applied.
+//! When a backend sets up a `PgReplicationOriginSession`, every transaction it commits is tagged
+//! with the origin, and the origin's progress is advanced atomically with the commit.  That makes
+//! replay idempotent -- after a crash, resume from `PgReplicationOrigin::progress()` -- and lets
+//! logical decoding output plugins filter out changes that came from the origin, so they aren't
+//! replicated back to where they came from.
+//!
+//! Unlike the SQL-level `pg_replication_origin_*()` functions, these don't check that the caller
+//! is a superuser.
+//!
+//! The session must outlive the commit of the transactions it applies, so it's typically set up
+//! by a background worker, around the loop that applies changes.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use pgx::bgworkers::BackgroundWorker;
+//!
+//! # fn next_remote_transaction() -> (pg_sys::XLogRecPtr, pg_sys::TimestampTz) { unimplemented!() }
+//! let origin = std::sync::Mutex::new(None);
+//! BackgroundWorker::transaction(|| {
+//!     *origin.lock().unwrap() = Some(PgReplicationOrigin::find_or_create("my_cdc_source"))
+//! });
+//! let origin = origin.into_inner().unwrap().unwrap();
+//! let session = origin.setup_session();
+//!
+//! while BackgroundWorker::wait_latch(None) {
+//!     let (remote_lsn, remote_commit_time) = next_remote_transaction();
+//!     if session.progress(false) >= remote_lsn {
+//!         // we've already applied this one
+//!         continue;
+//!     }
+//!
+//!     BackgroundWorker::transaction(|| {
+//!         session.set_xact_origin(remote_lsn, remote_commit_time);
+//!         // ... apply the changes.  They, and the origin's progress, commit together
+//!     });
+//! }
+//! ```
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use std::ffi::{CStr, CString};
+
+/// A replication origin, as listed in `pg_replication_origin`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgReplicationOrigin {
+    id: pg_sys::RepOriginId,
+    name: String,
+}
+
+/// This backend's replication origin session, which tags the transactions it commits with their
+/// origin.  The session is reset when this is dropped, so it must be kept alive until after any
+/// transaction it's meant to tag has committed
+pub struct PgReplicationOriginSession {
+    origin: PgReplicationOrigin,
+}
+
+impl PgReplicationOrigin {
+    /// Create a new replication origin, like `pg_replication_origin_create()`.  Raises an ERROR
+    /// if one with the same name already exists
+    pub fn create(name: &str) -> Self {
+        check_prerequisites();
+        let cname = origin_name(name);
+        let id = unsafe { pg_sys::replorigin_create(cname.as_ptr() as *mut std::os::raw::c_char) };
+        PgReplicationOrigin {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    /// Find the replication origin named `name`, like `pg_replication_origin_oid()`
+    pub fn find(name: &str) -> Option<Self> {
+        let cname = origin_name(name);
+        let id =
+            unsafe { pg_sys::replorigin_by_name(cname.as_ptr() as *mut std::os::raw::c_char, true) };
+        if id == pg_sys::InvalidRepOriginId {
+            None
+        } else {
+            Some(PgReplicationOrigin {
+                id,
+                name: name.to_string(),
+            })
+        }
+    }
+
+    /// Find the replication origin named `name`, creating it if it doesn't exist
+    pub fn find_or_create(name: &str) -> Self {
+        match PgReplicationOrigin::find(name) {
+            Some(origin) => origin,
+            None => PgReplicationOrigin::create(name),
+        }
+    }
+
+    /// Find the replication origin with the internal identifier `id`
+    pub fn from_id(id: pg_sys::RepOriginId) -> Option<Self> {
+        let mut name = std::ptr::null_mut();
+        if unsafe { pg_sys::replorigin_by_oid(id, true, &mut name) } {
+            Some(PgReplicationOrigin {
+                id,
+                name: unsafe { CStr::from_ptr(name) }.to_string_lossy().to_string(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The origin's internal identifier, as transactions are tagged with
+    pub fn id(&self) -> pg_sys::RepOriginId {
+        self.id
+    }
+
+    /// The origin's name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How far changes from this origin have been applied, like
+    /// `pg_replication_origin_progress()`.  If `flush` is true, only progress that's been durably
+    /// flushed to disk is reported
+    pub fn progress(&self, flush: bool) -> pg_sys::XLogRecPtr {
+        unsafe { pg_sys::replorigin_get_progress(self.id, flush) }
+    }
+
+    /// Set how far changes from this origin have been applied, like
+    /// `pg_replication_origin_advance()`.  This can move the origin's progress backwards, and
+    /// takes effect immediately rather than at commit, so it's mostly useful for initial setup
+    pub fn advance(&self, remote_lsn: pg_sys::XLogRecPtr) {
+        check_prerequisites();
+        unsafe {
+            // lock to prevent the origin from being dropped concurrently
+            pg_sys::LockRelationOid(
+                pg_sys::ReplicationOriginRelationId,
+                pg_sys::RowExclusiveLock as pg_sys::LOCKMODE,
+            );
+            pg_sys::replorigin_advance(
+                self.id,
+                remote_lsn,
+                pg_sys::InvalidXLogRecPtr as pg_sys::XLogRecPtr,
+                true,
+                true,
+            );
+            pg_sys::UnlockRelationOid(
+                pg_sys::ReplicationOriginRelationId,
+                pg_sys::RowExclusiveLock as pg_sys::LOCKMODE,
+            );
+        }
+    }
+
+    /// Make this origin this backend's session origin, like
+    /// `pg_replication_origin_session_setup()`.  Raises an ERROR if another backend is already
+    /// using it, or if this backend already has a session origin
+    pub fn setup_session(&self) -> PgReplicationOriginSession {
+        check_prerequisites();
+        unsafe {
+            pg_sys::replorigin_session_setup(self.id);
+            pg_sys::replorigin_session_origin = self.id;
+        }
+        PgReplicationOriginSession {
+            origin: self.clone(),
+        }
+    }
+
+    /// Drop the replication origin, like `pg_replication_origin_drop()`.  If `nowait` is false,
+    /// this waits for any backend using it as its session origin to stop doing so
+    pub fn drop_origin(self, nowait: bool) {
+        check_prerequisites();
+        unsafe {
+            pg_sys::replorigin_drop(self.id, nowait);
+        }
+    }
+}
+
+impl PgReplicationOriginSession {
+    /// The session's origin
+    pub fn origin(&self) -> &PgReplicationOrigin {
+        &self.origin
+    }
+
+    /// Record the remote location and commit time of the transaction being applied, like
+    /// `pg_replication_origin_xact_setup()`.  When the current transaction commits, the origin's
+    /// progress is advanced to `remote_lsn`
+    pub fn set_xact_origin(
+        &self,
+        remote_lsn: pg_sys::XLogRecPtr,
+        remote_commit_time: pg_sys::TimestampTz,
+    ) {
+        unsafe {
+            pg_sys::replorigin_session_origin_lsn = remote_lsn;
+            pg_sys::replorigin_session_origin_timestamp = remote_commit_time;
+        }
+    }
+
+    /// How far changes from the session's origin have been applied, like
+    /// `pg_replication_origin_session_progress()`
+    pub fn progress(&self, flush: bool) -> pg_sys::XLogRecPtr {
+        unsafe { pg_sys::replorigin_session_get_progress(flush) }
+    }
+}
+
+impl Drop for PgReplicationOriginSession {
+    /// Stop tagging this backend's transactions with the origin, like
+    /// `pg_replication_origin_session_reset()`
+    fn drop(&mut self) {
+        unsafe {
+            pg_sys::replorigin_session_reset();
+            pg_sys::replorigin_session_origin = pg_sys::InvalidRepOriginId;
+            pg_sys::replorigin_session_origin_lsn = pg_sys::InvalidXLogRecPtr as pg_sys::XLogRecPtr;
+            pg_sys::replorigin_session_origin_timestamp = 0;
+        }
+    }
+}
+
+fn origin_name(name: &str) -> CString {
+    CString::new(name).expect("replication origin name contains a null byte")
+}
+
+fn check_prerequisites() {
+    if crate::recovery::in_recovery() {
+        ereport(
+            PgLogLevel::ERROR,
+            PgSqlErrorCode::ERRCODE_READ_ONLY_SQL_TRANSACTION,
+            "cannot manipulate replication origins during recovery",
+            file!(),
+            line!(),
+            column!(),
+        );
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Create, drop, advance, and inspect replication slots
+//!
+//! These are thin wrappers around Postgres' own `pg_create_physical_replication_slot()` family of
+//! functions, so they perform the same permission and configuration checks (the caller must be a
+//! superuser or have the `REPLICATION` attribute, and `max_replication_slots` must be non-zero).
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! /// drop inactive slots that are holding back more than 1GB of WAL
+//! #[pg_extern]
+//! fn slot_janitor() -> i32 {
+//!     let mut dropped = 0;
+//!     for slot in replication_slots() {
+//!         if !slot.active && slot.retained_wal_bytes().unwrap_or(0) > 1024 * 1024 * 1024 {
+//!             drop_replication_slot(&slot.name);
+//!             dropped += 1;
+//!         }
+//!     }
+//!     dropped
+//! }
+//! ```
+use crate::{pg_sys, recovery, IntoDatum, PgBuiltInOids, Spi};
+
+/// Does a replication slot stream physical WAL, or logically decoded changes?
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplicationSlotKind {
+    Physical,
+    Logical,
+}
+
+/// A replication slot, as listed in `pg_replication_slots`
+#[derive(Clone, Debug)]
+pub struct ReplicationSlotStatus {
+    /// The slot's name
+    pub name: String,
+
+    /// Whether the slot is physical or logical
+    pub kind: ReplicationSlotKind,
+
+    /// The output plugin of a logical slot
+    pub plugin: Option<String>,
+
+    /// The database a logical slot is associated with
+    pub database: Option<String>,
+
+    /// Is the slot dropped automatically at the end of the session that created it?
+    pub temporary: bool,
+
+    /// Is the slot currently being used?
+    pub active: bool,
+
+    /// The process using the slot, if it's active
+    pub active_pid: Option<i32>,
+
+    /// The oldest WAL location the slot still needs
+    pub restart_lsn: Option<pg_sys::XLogRecPtr>,
+
+    /// The location up to which a logical slot's consumer has confirmed receiving changes
+    pub confirmed_flush_lsn: Option<pg_sys::XLogRecPtr>,
+}
+
+impl ReplicationSlotStatus {
+    /// How many bytes of WAL is this slot preventing from being removed?  This is what slot lag
+    /// monitors usually alert on
+    pub fn retained_wal_bytes(&self) -> Option<u64> {
+        self.restart_lsn
+            .map(|restart_lsn| recovery::current_lsn().saturating_sub(restart_lsn))
+    }
+}
+
+/// Create a physical replication slot, like `pg_create_physical_replication_slot()`.
+///
+/// If `immediately_reserve` is true, the slot starts retaining WAL now rather than when a client
+/// first connects to it, and the returned location is where it starts from
+pub fn create_physical_replication_slot(
+    name: &str,
+    immediately_reserve: bool,
+    temporary: bool,
+) -> Option<pg_sys::XLogRecPtr> {
+    let lsn = Spi::get_one_with_args::<String>(
+        "SELECT lsn::text FROM pg_create_physical_replication_slot($1, $2, $3)",
+        vec![
+            (PgBuiltInOids::TEXTOID.oid(), name.into_datum()),
+            (PgBuiltInOids::BOOLOID.oid(), immediately_reserve.into_datum()),
+            (PgBuiltInOids::BOOLOID.oid(), temporary.into_datum()),
+        ],
+    );
+    lsn.and_then(|lsn| recovery::parse_lsn(&lsn))
+}
+
+/// Create a logical replication slot that decodes changes with the output `plugin`, like
+/// `pg_create_logical_replication_slot()`.  Returns the location changes will be streamed from.
+///
+/// Postgres doesn't allow this in a transaction that has already written to the database
+pub fn create_logical_replication_slot(
+    name: &str,
+    plugin: &str,
+    temporary: bool,
+) -> pg_sys::XLogRecPtr {
+    let lsn = Spi::get_one_with_args::<String>(
+        "SELECT lsn::text FROM pg_create_logical_replication_slot($1, $2, $3)",
+        vec![
+            (PgBuiltInOids::TEXTOID.oid(), name.into_datum()),
+            (PgBuiltInOids::TEXTOID.oid(), plugin.into_datum()),
+            (PgBuiltInOids::BOOLOID.oid(), temporary.into_datum()),
+        ],
+    )
+    .expect("pg_create_logical_replication_slot() returned NULL");
+    recovery::parse_lsn(&lsn).expect("pg_create_logical_replication_slot() returned an invalid LSN")
+}
+
+/// Drop a replication slot, like `pg_drop_replication_slot()`.  Raises an ERROR if the slot
+/// doesn't exist or is active
+pub fn drop_replication_slot(name: &str) {
+    Spi::get_one_with_args::<()>(
+        "SELECT pg_drop_replication_slot($1)",
+        vec![(PgBuiltInOids::TEXTOID.oid(), name.into_datum())],
+    );
+}
+
+/// Advance a replication slot to `upto` without consuming its changes, like
+/// `pg_replication_slot_advance()` (Postgres 11+).  Returns the slot's new position
+#[cfg(any(
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15"
+))]
+pub fn advance_replication_slot(name: &str, upto: pg_sys::XLogRecPtr) -> pg_sys::XLogRecPtr {
+    let lsn = Spi::get_one_with_args::<String>(
+        "SELECT end_lsn::text FROM pg_replication_slot_advance($1, $2::pg_lsn)",
+        vec![
+            (PgBuiltInOids::TEXTOID.oid(), name.into_datum()),
+            (
+                PgBuiltInOids::TEXTOID.oid(),
+                recovery::format_lsn(upto).into_datum(),
+            ),
+        ],
+    )
+    .expect("pg_replication_slot_advance() returned NULL");
+    recovery::parse_lsn(&lsn).expect("pg_replication_slot_advance() returned an invalid LSN")
+}
+
+/// The status of every replication slot in the cluster
+pub fn replication_slots() -> Vec<ReplicationSlotStatus> {
+    let mut slots = Vec::new();
+    Spi::connect(|client| {
+        let table = client.select(
+            "SELECT slot_name::text, slot_type, plugin::text, database::text, temporary,
+                    active, active_pid, restart_lsn::text, confirmed_flush_lsn::text
+               FROM pg_replication_slots
+           ORDER BY slot_name",
+            None,
+            None,
+        );
+
+        for row in table {
+            let kind = match row.get_datum::<String>(2).as_deref() {
+                Some("logical") => ReplicationSlotKind::Logical,
+                _ => ReplicationSlotKind::Physical,
+            };
+
+            slots.push(ReplicationSlotStatus {
+                name: row.get_datum(1).expect("slot_name is NULL"),
+                kind,
+                plugin: row.get_datum(3),
+                database: row.get_datum(4),
+                temporary: row.get_datum(5).unwrap_or(false),
+                active: row.get_datum(6).unwrap_or(false),
+                active_pid: row.get_datum(7),
+                restart_lsn: row
+                    .get_datum::<String>(8)
+                    .and_then(|lsn| recovery::parse_lsn(&lsn)),
+                confirmed_flush_lsn: row
+                    .get_datum::<String>(9)
+                    .and_then(|lsn| recovery::parse_lsn(&lsn)),
+            });
+        }
+        Ok(Some(()))
+    });
+    slots
+}
+
+/// The status of the replication slot named `name`, if it exists
+pub fn replication_slot(name: &str) -> Option<ReplicationSlotStatus> {
+    replication_slots()
+        .into_iter()
+        .find(|slot| slot.name == name)
+}
@@ -0,0 +1,99 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! A guard around Postgres `ResourceOwner`s
+//!
+//! Postgres tracks buffer pins, relcache references, temporary files, and other resources with a
+//! `ResourceOwner`, releasing them automatically when the owner is released.  Normally this is the
+//! current transaction's owner, but a `PgResourceOwner` lets a chunk of work have its own, so
+//! that its resources can be released early -- or, if it's dropped without ever committing, as if
+//! it had aborted.
+use crate::pg_sys;
+use std::ffi::CString;
+
+/// A child of the current `ResourceOwner`, released and deleted when dropped
+pub struct PgResourceOwner {
+    owner: pg_sys::ResourceOwner,
+
+    // Postgres keeps a pointer to the name, so it must live as long as the owner
+    _name: CString,
+}
+
+impl PgResourceOwner {
+    /// Create a new `ResourceOwner` as a child of `CurrentResourceOwner`
+    pub fn new(name: &str) -> Self {
+        let name = CString::new(name).expect("resource owner name contains a null byte");
+        let owner = unsafe { pg_sys::ResourceOwnerCreate(pg_sys::CurrentResourceOwner, name.as_ptr()) };
+        PgResourceOwner { owner, _name: name }
+    }
+
+    /// The underlying `pg_sys::ResourceOwner`
+    pub fn as_ptr(&self) -> pg_sys::ResourceOwner {
+        self.owner
+    }
+
+    /// Run `f` with this as the `CurrentResourceOwner`, so that any resources it acquires are
+    /// owned by it.  The previous owner is restored afterwards, even if `f` panics
+    pub fn switch_to<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        struct Restore(pg_sys::ResourceOwner);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                unsafe {
+                    pg_sys::CurrentResourceOwner = self.0;
+                }
+            }
+        }
+
+        let _restore = Restore(unsafe { pg_sys::CurrentResourceOwner });
+        unsafe {
+            pg_sys::CurrentResourceOwner = self.owner;
+        }
+        f()
+    }
+
+    /// Release everything this owner holds as though its work completed successfully.  Locks are
+    /// transferred to the parent owner, and anything else left open (such as temporary files) is
+    /// closed with a "leak" WARNING
+    pub fn commit(self) {
+        self.release(true);
+        // `Drop` will now find nothing left to release
+    }
+
+    fn release(&self, is_commit: bool) {
+        unsafe {
+            pg_sys::ResourceOwnerRelease(
+                self.owner,
+                pg_sys::ResourceReleasePhase_RESOURCE_RELEASE_BEFORE_LOCKS,
+                is_commit,
+                false,
+            );
+            pg_sys::ResourceOwnerRelease(
+                self.owner,
+                pg_sys::ResourceReleasePhase_RESOURCE_RELEASE_LOCKS,
+                is_commit,
+                false,
+            );
+            pg_sys::ResourceOwnerRelease(
+                self.owner,
+                pg_sys::ResourceReleasePhase_RESOURCE_RELEASE_AFTER_LOCKS,
+                is_commit,
+                false,
+            );
+        }
+    }
+}
+
+impl Drop for PgResourceOwner {
+    /// Release everything this owner still holds, as if it were aborting, then delete it
+    fn drop(&mut self) {
+        unsafe {
+            if pg_sys::CurrentResourceOwner == self.owner {
+                pg_sys::CurrentResourceOwner = pg_sys::ResourceOwnerGetParent(self.owner);
+            }
+        }
+        self.release(false);
+        unsafe {
+            pg_sys::ResourceOwnerDelete(self.owner);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Read files on the database server with the same security rules as `pg_read_file()`
+//!
+//! Using `std::fs` directly from an extension lets any user who can call your function read any
+//! file the `postgres` OS user can.  These functions instead follow Postgres' own rules:
+//!
+//!   - relative paths are resolved against the data directory, and may not escape it with `..`
+//!   - absolute paths must be within the data directory or `log_directory`
+//!   - superusers, and (on Postgres 11+) members of `pg_read_server_files`, may read any path
+//!
+//! `check_read_server_files_privilege()` additionally restricts a function to those same roles,
+//! matching the default privileges of `pg_read_file()` itself.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! #[pg_extern]
+//! fn read_my_extension_log(filename: &str) -> Option<String> {
+//!     check_read_server_files_privilege();
+//!     read_server_file_text(filename, 0, None, true)
+//! }
+//! ```
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use std::ffi::CStr;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+/// Can the current user read arbitrary server files?  This is true for superusers and, on
+/// Postgres 11+, members of the `pg_read_server_files` role
+pub fn can_read_server_files() -> bool {
+    unsafe { pg_sys::superuser() } || is_member_of_read_server_files()
+}
+
+#[cfg(any(feature = "pg11", feature = "pg12", feature = "pg13"))]
+fn is_member_of_read_server_files() -> bool {
+    unsafe {
+        pg_sys::is_member_of_role(
+            pg_sys::GetUserId(),
+            pg_sys::DEFAULT_ROLE_READ_SERVER_FILES,
+        )
+    }
+}
+
+#[cfg(any(feature = "pg14", feature = "pg15"))]
+fn is_member_of_read_server_files() -> bool {
+    // Postgres 14 renamed the `DEFAULT_ROLE_*` OIDs to `ROLE_PG_*`
+    unsafe { pg_sys::is_member_of_role(pg_sys::GetUserId(), pg_sys::ROLE_PG_READ_SERVER_FILES) }
+}
+
+#[cfg(feature = "pg10")]
+fn is_member_of_read_server_files() -> bool {
+    // Postgres 10 doesn't have the `pg_read_server_files` role
+    false
+}
+
+/// Raise an ERROR unless the current user `can_read_server_files()`
+pub fn check_read_server_files_privilege() {
+    if !can_read_server_files() {
+        raise(
+            PgSqlErrorCode::ERRCODE_INSUFFICIENT_PRIVILEGE,
+            "must be superuser or a member of pg_read_server_files to read files".to_string(),
+        );
+    }
+}
+
+/// Resolve `filename` into an absolute path, raising an ERROR if the current user isn't allowed
+/// to read it.  This mirrors Postgres' own `convert_and_check_filename()`
+pub fn resolve_server_file_path(filename: &str) -> PathBuf {
+    let filename = canonicalize_path(Path::new(filename));
+    let data_dir = canonicalize_path(Path::new(&c_string(unsafe { pg_sys::DataDir })));
+
+    if can_read_server_files() {
+        return data_dir.join(filename);
+    }
+
+    if filename.is_absolute() {
+        let log_dir = canonicalize_path(Path::new(&c_string(unsafe { pg_sys::Log_directory })));
+
+        if filename.starts_with(&data_dir) || (log_dir.is_absolute() && filename.starts_with(&log_dir))
+        {
+            filename
+        } else {
+            raise(
+                PgSqlErrorCode::ERRCODE_INSUFFICIENT_PRIVILEGE,
+                "absolute path not allowed".to_string(),
+            )
+        }
+    } else if filename
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        raise(
+            PgSqlErrorCode::ERRCODE_INSUFFICIENT_PRIVILEGE,
+            "path must be in or below the current directory".to_string(),
+        )
+    } else {
+        data_dir.join(filename)
+    }
+}
+
+/// Read up to `length` bytes (or the whole file, if `None`) of `filename`, starting at `offset`.
+///
+/// Returns `None` if the file doesn't exist and `missing_ok` is true, like `pg_read_binary_file()`
+pub fn read_server_file(
+    filename: &str,
+    offset: u64,
+    length: Option<u64>,
+    missing_ok: bool,
+) -> Option<Vec<u8>> {
+    let path = resolve_server_file_path(filename);
+
+    let mut file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) if missing_ok && e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => raise_io(&format!("could not open file \"{}\" for reading", filename), e),
+    };
+
+    if let Err(e) = file.seek(SeekFrom::Start(offset)) {
+        raise_io(&format!("could not seek in file \"{}\"", filename), e)
+    }
+
+    let mut contents = Vec::new();
+    let result = match length {
+        Some(length) => file.take(length).read_to_end(&mut contents),
+        None => file.read_to_end(&mut contents),
+    };
+    if let Err(e) = result {
+        raise_io(&format!("could not read file \"{}\"", filename), e)
+    }
+
+    Some(contents)
+}
+
+/// Like `read_server_file()`, but the file's contents must be valid UTF8, like `pg_read_file()`
+pub fn read_server_file_text(
+    filename: &str,
+    offset: u64,
+    length: Option<u64>,
+    missing_ok: bool,
+) -> Option<String> {
+    let contents = read_server_file(filename, offset, length, missing_ok)?;
+    match String::from_utf8(contents) {
+        Ok(contents) => Some(contents),
+        Err(e) => raise(
+            PgSqlErrorCode::ERRCODE_CHARACTER_NOT_IN_REPERTOIRE,
+            format!("file \"{}\" is not valid UTF8: {}", filename, e),
+        ),
+    }
+}
+
+/// List the entries in the directory `dirname`, like `pg_ls_dir()`.
+///
+/// Returns `None` if the directory doesn't exist and `missing_ok` is true
+pub fn list_server_dir(dirname: &str, missing_ok: bool, include_dot_dirs: bool) -> Option<Vec<String>> {
+    let path = resolve_server_file_path(dirname);
+
+    let entries = match std::fs::read_dir(&path) {
+        Ok(entries) => entries,
+        Err(e) if missing_ok && e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => raise_io(&format!("could not open directory \"{}\"", dirname), e),
+    };
+
+    let mut names = Vec::new();
+    if include_dot_dirs {
+        names.push(".".to_string());
+        names.push("..".to_string());
+    }
+    for entry in entries {
+        match entry {
+            Ok(entry) => names.push(entry.file_name().to_string_lossy().to_string()),
+            Err(e) => raise_io(&format!("could not read directory \"{}\"", dirname), e),
+        }
+    }
+    Some(names)
+}
+
+/// Lexically clean up a path the way Postgres' `canonicalize_path()` does: drop `.` components
+/// and collapse `dir/..` pairs, without consulting the filesystem
+fn canonicalize_path(path: &Path) -> PathBuf {
+    let mut canonical = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let last = canonical.components().last();
+                let (at_root, after_dir) = (
+                    last == Some(Component::RootDir),
+                    matches!(last, Some(Component::Normal(_))),
+                );
+
+                if after_dir {
+                    canonical.pop();
+                } else if !at_root {
+                    // ".." at the start of a relative path can't be collapsed
+                    canonical.push("..");
+                }
+            }
+            other => canonical.push(other.as_os_str()),
+        }
+    }
+    canonical
+}
+
+fn c_string(ptr: *const std::os::raw::c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(ptr) }.to_string_lossy().to_string()
+    }
+}
+
+fn raise_io(message: &str, e: std::io::Error) -> ! {
+    let code = match e.kind() {
+        std::io::ErrorKind::NotFound => PgSqlErrorCode::ERRCODE_UNDEFINED_FILE,
+        std::io::ErrorKind::PermissionDenied => PgSqlErrorCode::ERRCODE_INSUFFICIENT_PRIVILEGE,
+        _ => PgSqlErrorCode::ERRCODE_IO_ERROR,
+    };
+    raise(code, format!("{}: {}", message, e))
+}
+
+fn raise(code: PgSqlErrorCode, message: String) -> ! {
+    ereport(PgLogLevel::ERROR, code, &message, file!(), line!(), column!());
+    unreachable!("ereport(ERROR) returned")
+}
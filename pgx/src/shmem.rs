@@ -0,0 +1,156 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Support for allocating extension data structures in Postgres' shared memory
+//!
+//! Shared memory can only be requested while the extension is being loaded via
+//! `shared_preload_libraries`.  Anything implementing `PgSharedMemoryInitialization` is given to
+//! `pg_shmem_init()` from `_PG_init()`, which requests its space and LWLocks from Postgres and
+//! then initializes it once the postmaster has created the shared memory segment.
+use crate::pg_sys;
+use std::ffi::CString;
+
+/// A data structure that lives in Postgres' shared memory
+pub trait PgSharedMemoryInitialization {
+    /// How many bytes of shared memory does this structure need?
+    fn shmem_size(&self) -> usize;
+
+    /// The name and number of LWLocks this structure needs, if any.  They're available from
+    /// `pg_sys::GetNamedLWLockTranche()` once `shmem_init()` is called
+    fn lwlock_tranche(&self) -> Option<(&'static str, usize)> {
+        None
+    }
+
+    /// Attach to (and, if necessary, initialize) this structure's shared memory.
+    ///
+    /// This is called from the `shmem_startup_hook`, with `AddinShmemInitLock` held
+    ///
+    /// ## Safety
+    ///
+    /// This is only called by `pgx` at the time Postgres sets up shared memory
+    unsafe fn shmem_init(&self);
+}
+
+static mut SHMEM_REGISTRY: Vec<&'static (dyn PgSharedMemoryInitialization + Sync)> = Vec::new();
+static mut PREV_SHMEM_STARTUP_HOOK: Option<unsafe extern "C" fn()> = None;
+
+#[cfg(feature = "pg15")]
+static mut SHMEM_REQUESTS: Vec<(usize, Option<(CString, usize)>)> = Vec::new();
+#[cfg(feature = "pg15")]
+static mut PREV_SHMEM_REQUEST_HOOK: Option<unsafe extern "C" fn()> = None;
+
+/// Request shared memory for the specified data structure.  Must be called from `_PG_init()`
+///
+/// ## Examples
+///
+/// ```rust,no_run
+/// use pgx::*;
+///
+/// static CACHE: PgShmemCache<i64, i64> = PgShmemCache::new("my_extension_cache", 10_000);
+///
+/// #[pg_guard]
+/// pub extern "C" fn _PG_init() {
+///     pg_shmem_init(&CACHE);
+/// }
+/// ```
+pub fn pg_shmem_init(object: &'static (dyn PgSharedMemoryInitialization + Sync)) {
+    unsafe {
+        if !pg_sys::process_shared_preload_libraries_in_progress {
+            panic!("shared memory can only be requested by extensions loaded via `shared_preload_libraries`");
+        }
+
+        request_shmem(
+            object.shmem_size(),
+            object.lwlock_tranche().map(|(tranche_name, num_lwlocks)| {
+                (
+                    CString::new(tranche_name).expect("invalid LWLock tranche name"),
+                    num_lwlocks,
+                )
+            }),
+        );
+
+        if SHMEM_REGISTRY.is_empty() {
+            PREV_SHMEM_STARTUP_HOOK = pg_sys::shmem_startup_hook;
+            pg_sys::shmem_startup_hook = Some(shmem_startup);
+        }
+        SHMEM_REGISTRY.push(object);
+    }
+}
+
+/// Ask Postgres to reserve `size` bytes of shared memory and, optionally, a named tranche of
+/// LWLocks
+#[cfg(any(
+    feature = "pg10",
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14"
+))]
+pub(crate) unsafe fn request_shmem(size: usize, lwlock_tranche: Option<(CString, usize)>) {
+    pg_sys::RequestAddinShmemSpace(size);
+    if let Some((tranche_name, num_lwlocks)) = lwlock_tranche {
+        pg_sys::RequestNamedLWLockTranche(tranche_name.as_ptr(), num_lwlocks as i32);
+    }
+}
+
+/// Ask Postgres to reserve `size` bytes of shared memory and, optionally, a named tranche of
+/// LWLocks.  Postgres 15 only accepts these requests from its `shmem_request_hook`, so they're
+/// held until it runs
+#[cfg(feature = "pg15")]
+pub(crate) unsafe fn request_shmem(size: usize, lwlock_tranche: Option<(CString, usize)>) {
+    if SHMEM_REQUESTS.is_empty() {
+        PREV_SHMEM_REQUEST_HOOK = pg_sys::shmem_request_hook;
+        pg_sys::shmem_request_hook = Some(shmem_request);
+    }
+    SHMEM_REQUESTS.push((size, lwlock_tranche));
+}
+
+#[cfg(feature = "pg15")]
+unsafe extern "C" fn shmem_request() {
+    if let Some(prev) = PREV_SHMEM_REQUEST_HOOK {
+        prev();
+    }
+
+    for (size, lwlock_tranche) in SHMEM_REQUESTS.iter() {
+        pg_sys::RequestAddinShmemSpace(*size);
+        if let Some((tranche_name, num_lwlocks)) = lwlock_tranche {
+            pg_sys::RequestNamedLWLockTranche(tranche_name.as_ptr(), *num_lwlocks as i32);
+        }
+    }
+}
+
+/// Find or create the named shared memory chunk, returning a pointer to it and whether it already
+/// existed.  This is a thin wrapper around `pg_sys::ShmemInitStruct()`
+///
+/// ## Safety
+///
+/// Must only be called from `PgSharedMemoryInitialization::shmem_init()`
+pub unsafe fn shmem_init_struct(name: &str, size: usize) -> (*mut std::os::raw::c_void, bool) {
+    let name = CString::new(name).expect("invalid shared memory name");
+    let mut found = false;
+    let ptr = pg_sys::ShmemInitStruct(name.as_ptr(), size, &mut found);
+    (ptr, found)
+}
+
+/// Round `offset` up to the next multiple of `align`
+pub(crate) fn align_up(offset: usize, align: usize) -> usize {
+    (offset + align - 1) / align * align
+}
+
+unsafe extern "C" fn shmem_startup() {
+    if let Some(prev) = PREV_SHMEM_STARTUP_HOOK {
+        prev();
+    }
+
+    let lock = addin_shmem_init_lock();
+    pg_sys::LWLockAcquire(lock, pg_sys::LWLockMode_LW_EXCLUSIVE);
+    for object in SHMEM_REGISTRY.iter() {
+        object.shmem_init();
+    }
+    pg_sys::LWLockRelease(lock);
+}
+
+/// `AddinShmemInitLock` is the 21st built-in LWLock (see `lwlocknames.h`)
+pub(crate) unsafe fn addin_shmem_init_lock() -> *mut pg_sys::LWLock {
+    &mut (*pg_sys::MainLWLockArray.add(21)).lock as *mut pg_sys::LWLock
+}
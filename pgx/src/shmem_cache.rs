@@ -0,0 +1,496 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! A fixed-size, partitioned, LRU hash table in shared memory
+//!
+//! `PgShmemCache<K, V>` is visible to every backend in the cluster.  Entries are spread across a
+//! number of partitions, each protected by its own LWLock, and each partition evicts its
+//! least-recently-used entry once it's full.
+//!
+//! Because the cache lives in shared memory, keys and values must be plain `Copy` data that
+//! doesn't point anywhere -- no `String`s, `Vec`s, or references.
+//!
+//! A cache can be cleared automatically on catalog invalidations, but those only come from
+//! changes to a relation's definition and the like.  Postgres doesn't send one when rows are
+//! INSERTed, UPDATEd, or DELETEd, so a cache of table data has to be cleared by hand, say from a
+//! trigger on the table, when it changes that way.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! static IP_TO_COUNTRY: PgShmemCache<u32, [u8; 2]> = PgShmemCache::new("ip_geo_cache", 100_000);
+//!
+//! #[pg_guard]
+//! pub extern "C" fn _PG_init() {
+//!     pg_shmem_init(&IP_TO_COUNTRY);
+//!
+//!     // forget everything whenever any table is ALTERed, TRUNCATEd, etc.  Changing rows doesn't
+//!     // count, so `ip_geo` is only ever reloaded with TRUNCATE and COPY
+//!     IP_TO_COUNTRY.invalidate_on(PgShmemCacheInvalidation::AnyRelation);
+//! }
+//!
+//! #[pg_extern]
+//! fn country_for_ip(ip: i64) -> Option<String> {
+//!     let ip = ip as u32;
+//!     let code = match IP_TO_COUNTRY.get(&ip) {
+//!         Some(code) => code,
+//!         None => {
+//!             let code: String = Spi::get_one(&format!("SELECT code FROM ip_geo WHERE {} <@ range", ip))?;
+//!             let code = [code.as_bytes()[0], code.as_bytes()[1]];
+//!             IP_TO_COUNTRY.insert(ip, code);
+//!             code
+//!         }
+//!     };
+//!     Some(String::from_utf8_lossy(&code).to_string())
+//! }
+//! ```
+use crate::shmem::{align_up, shmem_init_struct, PgSharedMemoryInitialization};
+use crate::pg_sys;
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::CString;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+const NIL: u32 = u32::MAX;
+const DEFAULT_PARTITIONS: usize = 16;
+
+/// When should a `PgShmemCache` be cleared?
+#[derive(Debug, Clone, Copy)]
+pub enum PgShmemCacheInvalidation {
+    /// When the relcache entry for the specified relation is invalidated, which happens when
+    /// it's ALTERed, TRUNCATEd, VACUUMed, etc, but not when its rows change
+    Relation(pg_sys::Oid),
+
+    /// When any relcache entry is invalidated, which, again, doesn't happen when rows change
+    AnyRelation,
+
+    /// When any entry in the specified syscache is invalidated, for example
+    /// `pg_sys::SysCacheIdentifier_PROCOID` whenever a function is changed
+    Syscache(pg_sys::SysCacheIdentifier),
+}
+
+/// A typed, fixed-capacity LRU cache in shared memory.  See the module documentation
+pub struct PgShmemCache<K, V> {
+    name: &'static str,
+    capacity: usize,
+    partitions: usize,
+    base: AtomicPtr<u8>,
+    locks: AtomicPtr<pg_sys::LWLockPadded>,
+    __marker: PhantomData<(K, V)>,
+}
+
+unsafe impl<K: Copy + Hash + Eq, V: Copy> Sync for PgShmemCache<K, V> {}
+
+#[repr(C)]
+struct PartitionHeader {
+    len: u32,
+    free_head: u32,
+    lru_head: u32,
+    lru_tail: u32,
+}
+
+#[repr(C)]
+struct Entry<K, V> {
+    hash: u64,
+    lru_prev: u32,
+    lru_next: u32,
+    chain_next: u32,
+    key: MaybeUninit<K>,
+    value: MaybeUninit<V>,
+}
+
+/// A view over one partition's memory.  Only valid while the partition's lock is held
+struct Partition<K, V> {
+    header: *mut PartitionHeader,
+    buckets: *mut u32,
+    entries: *mut Entry<K, V>,
+    nbuckets: usize,
+}
+
+// `const fn`s can't have trait bounds, so these are kept apart from the rest of the impl
+impl<K, V> PgShmemCache<K, V> {
+    /// Declare a cache that holds up to `capacity` entries, which must be at least one.  It's
+    /// registered with Postgres by passing it to `pg_shmem_init()` from `_PG_init()`
+    pub const fn new(name: &'static str, capacity: usize) -> Self {
+        PgShmemCache {
+            name,
+            capacity,
+            partitions: DEFAULT_PARTITIONS,
+            base: AtomicPtr::new(std::ptr::null_mut()),
+            locks: AtomicPtr::new(std::ptr::null_mut()),
+            __marker: PhantomData,
+        }
+    }
+
+    /// Declare a cache with a specific number of partitions, which must be at least one.  More
+    /// partitions means less lock contention, but each partition evicts independently, so LRU
+    /// order is only approximate
+    pub const fn with_partitions(name: &'static str, capacity: usize, partitions: usize) -> Self {
+        PgShmemCache {
+            name,
+            capacity,
+            partitions,
+            base: AtomicPtr::new(std::ptr::null_mut()),
+            locks: AtomicPtr::new(std::ptr::null_mut()),
+            __marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Copy + Hash + Eq, V: Copy> PgShmemCache<K, V> {
+    /// The maximum number of entries this cache can hold
+    pub fn capacity(&self) -> usize {
+        self.partition_capacity() * self.partitions
+    }
+
+    /// Look up `key`, marking it as most-recently-used
+    pub fn get(&self, key: &K) -> Option<V> {
+        let hash = hash_key(key);
+        self.with_partition(hash, |partition| unsafe {
+            let idx = partition.find(hash, key)?;
+            partition.lru_unlink(idx);
+            partition.lru_push_front(idx);
+            Some((*partition.entries.add(idx as usize)).value.assume_init())
+        })
+    }
+
+    /// Is `key` in the cache?  This doesn't affect its LRU position
+    pub fn contains_key(&self, key: &K) -> bool {
+        let hash = hash_key(key);
+        self.with_partition(hash, |partition| unsafe { partition.find(hash, key).is_some() })
+    }
+
+    /// Add or replace `key`, evicting the partition's least-recently-used entry if it's full.
+    /// Returns the value previously associated with `key`, if any
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let hash = hash_key(&key);
+        self.with_partition(hash, |partition| unsafe {
+            if let Some(idx) = partition.find(hash, &key) {
+                let entry = &mut *partition.entries.add(idx as usize);
+                let old = entry.value.assume_init();
+                entry.value = MaybeUninit::new(value);
+                partition.lru_unlink(idx);
+                partition.lru_push_front(idx);
+                return Some(old);
+            }
+
+            let idx = partition.allocate();
+            let entry = &mut *partition.entries.add(idx as usize);
+            entry.hash = hash;
+            entry.key = MaybeUninit::new(key);
+            entry.value = MaybeUninit::new(value);
+
+            let bucket = partition.buckets.add(partition.bucket_for(hash));
+            entry.chain_next = *bucket;
+            *bucket = idx;
+            partition.lru_push_front(idx);
+            (*partition.header).len += 1;
+            None
+        })
+    }
+
+    /// Remove `key` from the cache, returning its value if it was present
+    pub fn remove(&self, key: &K) -> Option<V> {
+        let hash = hash_key(key);
+        self.with_partition(hash, |partition| unsafe {
+            let idx = partition.find(hash, key)?;
+            let value = (*partition.entries.add(idx as usize)).value.assume_init();
+            partition.delete(idx);
+            Some(value)
+        })
+    }
+
+    /// How many entries are in the cache?
+    pub fn len(&self) -> usize {
+        (0..self.partitions)
+            .map(|i| self.lock_partition(i, |partition| unsafe { (*partition.header).len as usize }))
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove every entry from the cache
+    pub fn clear(&self) {
+        for i in 0..self.partitions {
+            self.lock_partition(i, |partition| unsafe { partition.reset(self.partition_capacity()) });
+        }
+    }
+
+    /// Automatically `clear()` the cache when the specified catalog change happens.  Must be called
+    /// from `_PG_init()`.
+    ///
+    /// Invalidations are processed by each backend individually, so a single catalog change may
+    /// clear the cache more than once
+    pub fn invalidate_on(&'static self, invalidation: PgShmemCacheInvalidation)
+    where
+        K: 'static,
+        V: 'static,
+    {
+        unsafe {
+            INVALIDATION_REGISTRY.push((self, invalidation));
+            let arg = (INVALIDATION_REGISTRY.len() - 1) as pg_sys::Datum;
+
+            match invalidation {
+                PgShmemCacheInvalidation::Relation(_) | PgShmemCacheInvalidation::AnyRelation => {
+                    pg_sys::CacheRegisterRelcacheCallback(Some(relcache_callback), arg)
+                }
+                PgShmemCacheInvalidation::Syscache(cacheid) => {
+                    pg_sys::CacheRegisterSyscacheCallback(
+                        cacheid as i32,
+                        Some(syscache_callback),
+                        arg,
+                    )
+                }
+            }
+        }
+    }
+
+    /// Panic, from `pg_shmem_init()`, if the cache can't hold anything.  A partition without a
+    /// single entry would have nothing to evict when it's full
+    fn check_dimensions(&self) {
+        if self.capacity == 0 || self.partitions == 0 {
+            panic!(
+                "shared memory cache `{}` must have a capacity and number of partitions of at least one",
+                self.name
+            );
+        }
+    }
+
+    fn partition_capacity(&self) -> usize {
+        (self.capacity + self.partitions - 1) / self.partitions
+    }
+
+    fn nbuckets(&self) -> usize {
+        self.partition_capacity().next_power_of_two()
+    }
+
+    fn buckets_offset(&self) -> usize {
+        align_up(std::mem::size_of::<PartitionHeader>(), std::mem::align_of::<u32>())
+    }
+
+    fn entries_offset(&self) -> usize {
+        align_up(
+            self.buckets_offset() + self.nbuckets() * std::mem::size_of::<u32>(),
+            std::mem::align_of::<Entry<K, V>>(),
+        )
+    }
+
+    fn partition_size(&self) -> usize {
+        align_up(
+            self.entries_offset() + self.partition_capacity() * std::mem::size_of::<Entry<K, V>>(),
+            std::mem::align_of::<Entry<K, V>>().max(std::mem::align_of::<PartitionHeader>()),
+        )
+    }
+
+    fn partition(&self, i: usize) -> Partition<K, V> {
+        let base = self.base.load(Ordering::Acquire);
+        if base.is_null() {
+            panic!(
+                "shared memory cache `{}` was not initialized.  Did you call `pg_shmem_init()` from `_PG_init()`?",
+                self.name
+            );
+        }
+
+        unsafe {
+            let start = base.add(i * self.partition_size());
+            Partition {
+                header: start as *mut PartitionHeader,
+                buckets: start.add(self.buckets_offset()) as *mut u32,
+                entries: start.add(self.entries_offset()) as *mut Entry<K, V>,
+                nbuckets: self.nbuckets(),
+            }
+        }
+    }
+
+    fn with_partition<R, F: FnOnce(&mut Partition<K, V>) -> R>(&self, hash: u64, f: F) -> R {
+        self.lock_partition((hash % self.partitions as u64) as usize, f)
+    }
+
+    fn lock_partition<R, F: FnOnce(&mut Partition<K, V>) -> R>(&self, i: usize, f: F) -> R {
+        let mut partition = self.partition(i);
+        unsafe {
+            let lock = &mut (*self.locks.load(Ordering::Acquire).add(i)).lock as *mut pg_sys::LWLock;
+            pg_sys::LWLockAcquire(lock, pg_sys::LWLockMode_LW_EXCLUSIVE);
+            let result = f(&mut partition);
+            pg_sys::LWLockRelease(lock);
+            result
+        }
+    }
+}
+
+impl<K: Copy + Hash + Eq, V: Copy> PgSharedMemoryInitialization for PgShmemCache<K, V> {
+    fn shmem_size(&self) -> usize {
+        self.check_dimensions();
+        self.partition_size() * self.partitions
+    }
+
+    fn lwlock_tranche(&self) -> Option<(&'static str, usize)> {
+        Some((self.name, self.partitions))
+    }
+
+    unsafe fn shmem_init(&self) {
+        let (base, found) = shmem_init_struct(self.name, self.shmem_size());
+        self.base.store(base as *mut u8, Ordering::Release);
+
+        let tranche_name = CString::new(self.name).unwrap();
+        self.locks.store(
+            pg_sys::GetNamedLWLockTranche(tranche_name.as_ptr()),
+            Ordering::Release,
+        );
+
+        if !found {
+            for i in 0..self.partitions {
+                self.partition(i).reset(self.partition_capacity());
+            }
+        }
+    }
+}
+
+impl<K: Copy + Eq, V: Copy> Partition<K, V> {
+    fn bucket_for(&self, hash: u64) -> usize {
+        ((hash >> 32) as usize) & (self.nbuckets - 1)
+    }
+
+    unsafe fn entry(&mut self, idx: u32) -> &mut Entry<K, V> {
+        &mut *self.entries.add(idx as usize)
+    }
+
+    /// Forget every entry, putting them all on the free list
+    unsafe fn reset(&mut self, capacity: usize) {
+        for b in 0..self.nbuckets {
+            *self.buckets.add(b) = NIL;
+        }
+        for idx in 0..capacity {
+            let entry = self.entry(idx as u32);
+            entry.chain_next = if idx + 1 < capacity {
+                (idx + 1) as u32
+            } else {
+                NIL
+            };
+            entry.lru_prev = NIL;
+            entry.lru_next = NIL;
+        }
+        *self.header = PartitionHeader {
+            len: 0,
+            free_head: if capacity > 0 { 0 } else { NIL },
+            lru_head: NIL,
+            lru_tail: NIL,
+        };
+    }
+
+    unsafe fn find(&self, hash: u64, key: &K) -> Option<u32> {
+        let mut idx = *self.buckets.add(self.bucket_for(hash));
+        while idx != NIL {
+            let entry = &*self.entries.add(idx as usize);
+            if entry.hash == hash && &*entry.key.as_ptr() == key {
+                return Some(idx);
+            }
+            idx = entry.chain_next;
+        }
+        None
+    }
+
+    /// Take an entry off the free list, evicting the least-recently-used entry if there are none
+    unsafe fn allocate(&mut self) -> u32 {
+        if (*self.header).free_head == NIL {
+            let victim = (*self.header).lru_tail;
+            self.delete(victim);
+        }
+
+        let idx = (*self.header).free_head;
+        (*self.header).free_head = self.entry(idx).chain_next;
+        idx
+    }
+
+    /// Unlink an entry from its bucket and the LRU list, and return it to the free list
+    unsafe fn delete(&mut self, idx: u32) {
+        let bucket = self.buckets.add(self.bucket_for(self.entry(idx).hash));
+        if *bucket == idx {
+            *bucket = self.entry(idx).chain_next;
+        } else {
+            let mut prev = *bucket;
+            while self.entry(prev).chain_next != idx {
+                prev = self.entry(prev).chain_next;
+            }
+            self.entry(prev).chain_next = self.entry(idx).chain_next;
+        }
+
+        self.lru_unlink(idx);
+        self.entry(idx).chain_next = (*self.header).free_head;
+        (*self.header).free_head = idx;
+        (*self.header).len -= 1;
+    }
+
+    unsafe fn lru_unlink(&mut self, idx: u32) {
+        let (prev, next) = (self.entry(idx).lru_prev, self.entry(idx).lru_next);
+        if prev == NIL {
+            (*self.header).lru_head = next;
+        } else {
+            self.entry(prev).lru_next = next;
+        }
+        if next == NIL {
+            (*self.header).lru_tail = prev;
+        } else {
+            self.entry(next).lru_prev = prev;
+        }
+        self.entry(idx).lru_prev = NIL;
+        self.entry(idx).lru_next = NIL;
+    }
+
+    unsafe fn lru_push_front(&mut self, idx: u32) {
+        let head = (*self.header).lru_head;
+        self.entry(idx).lru_prev = NIL;
+        self.entry(idx).lru_next = head;
+        if head == NIL {
+            (*self.header).lru_tail = idx;
+        } else {
+            self.entry(head).lru_prev = idx;
+        }
+        (*self.header).lru_head = idx;
+    }
+}
+
+fn hash_key<K: Hash>(key: &K) -> u64 {
+    // `DefaultHasher::new()` always uses the same keys, so every backend computes the same hash
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Something that can be cleared in response to a catalog invalidation
+trait ClearOnInvalidation {
+    fn clear_cache(&self);
+}
+
+impl<K: Copy + Hash + Eq, V: Copy> ClearOnInvalidation for PgShmemCache<K, V> {
+    fn clear_cache(&self) {
+        // the callback can fire in backends that have yet to touch shared memory
+        if !self.base.load(Ordering::Acquire).is_null() {
+            self.clear();
+        }
+    }
+}
+
+static mut INVALIDATION_REGISTRY: Vec<(&'static dyn ClearOnInvalidation, PgShmemCacheInvalidation)> =
+    Vec::new();
+
+unsafe extern "C" fn relcache_callback(arg: pg_sys::Datum, relid: pg_sys::Oid) {
+    let (cache, invalidation) = INVALIDATION_REGISTRY[arg];
+    match invalidation {
+        // an InvalidOid relid means the entire relcache is being reset
+        PgShmemCacheInvalidation::Relation(watched)
+            if relid != pg_sys::InvalidOid && relid != watched => {}
+        _ => cache.clear_cache(),
+    }
+}
+
+unsafe extern "C" fn syscache_callback(arg: pg_sys::Datum, _cacheid: i32, _hashvalue: u32) {
+    let (cache, _) = INVALIDATION_REGISTRY[arg];
+    cache.clear_cache();
+}
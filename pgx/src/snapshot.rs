@@ -0,0 +1,156 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Snapshot assertions, in the style of the `insta` crate, for `#[pg_test]`s
+//!
+//! Rather than asserting on every cell of a complex query's result, `assert_spi_snapshot!()`
+//! renders the whole result as a table (see `SpiTupleTable::to_table_string()`) and compares it
+//! with the one saved in `snapshots/<name>.snap`, under the crate's directory.
+//!
+//! When they differ, or there's no snapshot yet, the new result is written next to it as
+//! `<name>.snap.new` and the assertion fails with a line-by-line diff.  Review the `.snap.new`
+//! file and rename it over the `.snap` to accept it, or run the tests with
+//! `PGX_UPDATE_SNAPSHOTS=1 cargo pgx test` to accept every new result at once.  Snapshot files
+//! are meant to be committed with your tests.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! #[pg_test]
+//! fn test_top_customers() {
+//!     assert_spi_snapshot!(
+//!         "top_customers",
+//!         "SELECT name, total FROM top_customers(3) ORDER BY total DESC"
+//!     );
+//! }
+//! ```
+use std::path::PathBuf;
+
+/// The environment variable that, when set, makes mismatched snapshots be overwritten instead
+/// of failing the assertion
+pub const UPDATE_SNAPSHOTS_ENV: &str = "PGX_UPDATE_SNAPSHOTS";
+
+/// Assert that `actual` matches the snapshot named `name` in `manifest_dir`/snapshots/.  This is
+/// what `assert_spi_snapshot!()` calls, and can be used directly to snapshot any other string
+pub fn assert_snapshot(manifest_dir: &str, name: &str, actual: &str) {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        panic!(
+            "snapshot name `{}` must only contain ASCII letters, digits, `_`, and `-`",
+            name
+        );
+    }
+
+    let mut path = PathBuf::from(manifest_dir);
+    path.push("snapshots");
+    path.push(format!("{}.snap", name));
+    let mut new_path = path.clone();
+    new_path.set_extension("snap.new");
+
+    let actual = normalize(actual);
+    let expected = std::fs::read_to_string(&path).ok().map(|s| normalize(&s));
+    if expected.as_ref() == Some(&actual) {
+        // an earlier mismatch has been fixed
+        std::fs::remove_file(&new_path).ok();
+        return;
+    }
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+        write(&path, &actual);
+        std::fs::remove_file(&new_path).ok();
+        return;
+    }
+
+    write(&new_path, &actual);
+    match expected {
+        Some(expected) => panic!(
+            "snapshot `{}` doesn't match {}:\n\n{}\nthe new result was written to {}.  Rename it over the snapshot to accept it, or run with {}=1",
+            name,
+            path.display(),
+            diff(&expected, &actual),
+            new_path.display(),
+            UPDATE_SNAPSHOTS_ENV
+        ),
+        None => panic!(
+            "there's no snapshot `{}` yet.  The result:\n\n{}\nwas written to {}.  Rename it to {} to accept it, or run with {}=1",
+            name,
+            actual,
+            new_path.display(),
+            path.display(),
+            UPDATE_SNAPSHOTS_ENV
+        ),
+    }
+}
+
+/// Render `query`'s result with `Spi::render()` and assert that it matches the snapshot `name`
+/// saved in the crate's `snapshots/` directory.  See the `snapshot` module documentation
+#[macro_export]
+macro_rules! assert_spi_snapshot {
+    ($name:expr, $query:expr) => {
+        $crate::snapshot::assert_snapshot(
+            env!("CARGO_MANIFEST_DIR"),
+            $name,
+            &$crate::Spi::render($query),
+        )
+    };
+}
+
+/// Ignore line endings and trailing whitespace, which editors and git like to change
+fn normalize(s: &str) -> String {
+    let mut normalized = String::new();
+    for line in s.lines() {
+        normalized.push_str(line.trim_end());
+        normalized.push('\n');
+    }
+    normalized
+}
+
+fn write(path: &PathBuf, contents: &str) {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .unwrap_or_else(|e| panic!("failed to create {}: {}", dir.display(), e));
+    }
+    std::fs::write(path, contents)
+        .unwrap_or_else(|e| panic!("failed to write {}: {}", path.display(), e));
+}
+
+/// The lines of `expected` and `actual`, prefixed with `-` for those only in `expected`, `+` for
+/// those only in `actual`, and a space for those in both
+fn diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+
+    // the length of the longest common subsequence of expected[i..] and actual[j..]
+    let mut lcs = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            diff.push_str(&format!("  {}\n", expected[i]));
+            i += 1;
+            j += 1;
+        } else if j < actual.len() && (i == expected.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            diff.push_str(&format!("+ {}\n", actual[j]));
+            j += 1;
+        } else {
+            diff.push_str(&format!("- {}\n", expected[i]));
+            i += 1;
+        }
+    }
+    diff
+}
@@ -3,10 +3,15 @@
 
 //! Safe access to Postgres' *Server Programming Interface* (SPI).
 
-use crate::{pg_sys, FromDatum, IntoDatum, Json, PgMemoryContexts, PgOid};
+use crate::{
+    ereport, pg_sys, void_mut_ptr, FromDatum, FromDatumColumn, IntoDatum, Json, PgColumn,
+    PgDatumColumn, PgLogLevel, PgMemoryContexts, PgOid, PgSqlErrorCode,
+};
 use enum_primitive_derive::*;
 use num_traits::FromPrimitive;
+use std::ffi::{CStr, CString};
 use std::fmt::Debug;
+use std::marker::PhantomData;
 
 #[derive(Debug, Primitive)]
 pub enum SpiOk {
@@ -68,6 +73,44 @@ pub struct SpiHeapTupleData {
     tupdesc: pg_sys::TupleDesc,
 }
 
+/// Where to move an `SpiCursor` to, and how many rows to fetch (or skip) along the way
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpiFetchDirection {
+    /// The next `n` rows
+    Forward(i64),
+
+    /// The prior `n` rows, in reverse order
+    Backward(i64),
+
+    /// The single row at position `n`, counting from 1.  Negative positions count back from the
+    /// end, so `-1` is the last row
+    Absolute(i64),
+
+    /// The single row `n` rows away from the current position
+    Relative(i64),
+}
+
+impl SpiFetchDirection {
+    fn as_pg(self) -> (pg_sys::FetchDirection, std::os::raw::c_long) {
+        match self {
+            SpiFetchDirection::Forward(n) => (pg_sys::FetchDirection_FETCH_FORWARD, n as _),
+            SpiFetchDirection::Backward(n) => (pg_sys::FetchDirection_FETCH_BACKWARD, n as _),
+            SpiFetchDirection::Absolute(n) => (pg_sys::FetchDirection_FETCH_ABSOLUTE, n as _),
+            SpiFetchDirection::Relative(n) => (pg_sys::FetchDirection_FETCH_RELATIVE, n as _),
+        }
+    }
+}
+
+/// A cursor opened through SPI by `SpiClient::open_cursor()` or `SpiClient::open_scroll_cursor()`
+///
+/// The cursor is closed when this `SpiCursor` is dropped, unless it's first handed off with
+/// `SpiCursor::detach_into_name()` so it can be found again by a later statement in the same
+/// transaction.
+pub struct SpiCursor<'client> {
+    portal: pg_sys::Portal,
+    __marker: PhantomData<&'client SpiClient>,
+}
+
 impl Spi {
     pub fn get_one<A: FromDatum + IntoDatum>(query: &str) -> Option<A> {
         Spi::connect(|client| {
@@ -171,6 +214,13 @@ impl Spi {
         .unwrap()
     }
 
+    /// render a query's result as a table, like `psql` prints it, for comparing as a whole,
+    /// such as with `assert_spi_snapshot!()`.  See `SpiTupleTable::to_table_string()`
+    pub fn render(query: &str) -> String {
+        Spi::connect(|client| Ok(Some(client.select(query, None, None).to_table_string())))
+            .unwrap_or_default()
+    }
+
     /// execute SPI commands via the provided `SpiClient`
     pub fn execute<F: FnOnce(SpiClient) + std::panic::UnwindSafe>(f: F) {
         Spi::connect(|client| {
@@ -299,6 +349,132 @@ impl SpiClient {
         SpiClient::execute(query, false, limit, args)
     }
 
+    /// fetch up to `count` rows from the already-open cursor named `name`, which might have
+    /// been opened by `DECLARE` or by the client, moving it forward
+    pub fn fetch_cursor(&self, name: &str, count: i64) -> SpiTupleTable {
+        let cname = std::ffi::CString::new(name).expect("cursor name contained a null byte");
+        let portal = unsafe { pg_sys::SPI_cursor_find(cname.as_ptr()) };
+        if portal.is_null() {
+            ereport(
+                PgLogLevel::ERROR,
+                PgSqlErrorCode::ERRCODE_INVALID_CURSOR_NAME,
+                &format!("cursor \"{}\" does not exist", name),
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+
+        unsafe {
+            pg_sys::SPI_tuptable = std::ptr::null_mut();
+            pg_sys::SPI_cursor_fetch(portal, true, count as std::os::raw::c_long);
+        }
+
+        SpiTupleTable::from_spi(SpiOk::Fetch)
+    }
+
+    /// open a cursor over `query`, which can only be read forwards.
+    ///
+    /// Unlike `select()`, rows are only materialized as they're fetched from the returned
+    /// `SpiCursor`
+    pub fn open_cursor(
+        &self,
+        query: &str,
+        args: Option<Vec<(PgOid, Option<pg_sys::Datum>)>>,
+    ) -> SpiCursor {
+        self.open(query, args, pg_sys::CURSOR_OPT_NO_SCROLL)
+    }
+
+    /// open a cursor over `query` which, like `DECLARE ... SCROLL CURSOR`, can be moved
+    /// backwards and to absolute positions with `SpiCursor::fetch()` and `SpiCursor::move_to()`
+    pub fn open_scroll_cursor(
+        &self,
+        query: &str,
+        args: Option<Vec<(PgOid, Option<pg_sys::Datum>)>>,
+    ) -> SpiCursor {
+        self.open(query, args, pg_sys::CURSOR_OPT_SCROLL)
+    }
+
+    /// find a cursor previously opened through SPI and handed off with
+    /// `SpiCursor::detach_into_name()`, or opened with `DECLARE`.  As with any `SpiCursor`, it's
+    /// closed when dropped unless it's detached again
+    pub fn find_cursor(&self, name: &str) -> Option<SpiCursor> {
+        let cname = CString::new(name).expect("cursor name contained a null byte");
+        let portal = unsafe { pg_sys::SPI_cursor_find(cname.as_ptr()) };
+        if portal.is_null() {
+            None
+        } else {
+            Some(SpiCursor {
+                portal,
+                __marker: PhantomData,
+            })
+        }
+    }
+
+    fn open(
+        &self,
+        query: &str,
+        args: Option<Vec<(PgOid, Option<pg_sys::Datum>)>>,
+        cursor_options: u32,
+    ) -> SpiCursor {
+        let src = CString::new(query).expect("query contained a null byte");
+        let (mut argtypes, mut datums, nulls) = SpiClient::args_to_pg(args.unwrap_or_default());
+
+        let portal = unsafe {
+            pg_sys::SPI_cursor_open_with_args(
+                std::ptr::null(),
+                src.as_ptr(),
+                argtypes.len() as i32,
+                argtypes.as_mut_ptr(),
+                datums.as_mut_ptr(),
+                nulls.as_ptr(),
+                false,
+                cursor_options as i32,
+            )
+        };
+        if portal.is_null() {
+            panic!("SPI_cursor_open_with_args() failed: {:?}", unsafe {
+                SpiError::from_i32(-pg_sys::SPI_result)
+            });
+        }
+
+        SpiCursor {
+            portal,
+            __marker: PhantomData,
+        }
+    }
+
+    /// split `args` into the parallel type, value, and null arrays SPI wants
+    fn args_to_pg(
+        args: Vec<(PgOid, Option<pg_sys::Datum>)>,
+    ) -> (
+        Vec<pg_sys::Oid>,
+        Vec<pg_sys::Datum>,
+        Vec<std::os::raw::c_char>,
+    ) {
+        let mut argtypes = vec![];
+        let mut datums = vec![];
+        let mut nulls = vec![];
+
+        for (argtype, datum) in args {
+            argtypes.push(argtype.value());
+
+            match datum {
+                Some(datum) => {
+                    datums.push(datum);
+                    nulls.push(0 as std::os::raw::c_char);
+                }
+
+                None => {
+                    datums.push(0);
+                    nulls.push(1 as std::os::raw::c_char);
+                }
+            }
+        }
+
+        (argtypes, datums, nulls)
+    }
+
     fn execute(
         query: &str,
         read_only: bool,
@@ -313,25 +489,7 @@ impl SpiClient {
         let status_code = match args {
             Some(args) => {
                 let nargs = args.len();
-                let mut argtypes = vec![];
-                let mut datums = vec![];
-                let mut nulls = vec![];
-
-                for (argtype, datum) in args {
-                    argtypes.push(argtype.value());
-
-                    match datum {
-                        Some(datum) => {
-                            datums.push(datum);
-                            nulls.push(0 as std::os::raw::c_char);
-                        }
-
-                        None => {
-                            datums.push(0);
-                            nulls.push(1 as std::os::raw::c_char);
-                        }
-                    }
-                }
+                let (mut argtypes, mut datums, mut nulls) = SpiClient::args_to_pg(args);
 
                 unsafe {
                     pg_sys::SPI_execute_with_args(
@@ -348,8 +506,68 @@ impl SpiClient {
             None => unsafe { pg_sys::SPI_execute(src.as_ptr(), read_only, limit.unwrap_or(0)) },
         };
 
+        SpiTupleTable::from_spi(Spi::check_status(status_code))
+    }
+}
+
+impl<'client> SpiCursor<'client> {
+    /// The name of this cursor, as seen in `pg_cursors`
+    pub fn name(&self) -> &str {
+        unsafe { CStr::from_ptr((*self.portal).name) }
+            .to_str()
+            .expect("cursor name is not valid UTF8")
+    }
+
+    /// Fetch the next `count` rows
+    pub fn fetch_forward(&mut self, count: i64) -> SpiTupleTable {
+        self.fetch(SpiFetchDirection::Forward(count))
+    }
+
+    /// Fetch rows in the specified `direction`, leaving the cursor positioned on the last row
+    /// returned.
+    ///
+    /// Anything other than `SpiFetchDirection::Forward` requires the cursor to have been opened
+    /// with `SpiClient::open_scroll_cursor()`
+    pub fn fetch(&mut self, direction: SpiFetchDirection) -> SpiTupleTable {
+        let (direction, count) = direction.as_pg();
+        unsafe {
+            pg_sys::SPI_tuptable = std::ptr::null_mut();
+            pg_sys::SPI_scroll_cursor_fetch(self.portal, direction, count);
+        }
+
+        SpiTupleTable::from_spi(SpiOk::Fetch)
+    }
+
+    /// Reposition the cursor as `fetch()` would, without returning any rows, and return how many
+    /// rows were skipped over
+    pub fn move_to(&mut self, direction: SpiFetchDirection) -> usize {
+        let (direction, count) = direction.as_pg();
+        unsafe {
+            pg_sys::SPI_scroll_cursor_move(self.portal, direction, count);
+            pg_sys::SPI_processed as usize
+        }
+    }
+
+    /// Leave the cursor open, beyond this `SpiClient`, and return its name so that it can be
+    /// found again with `SpiClient::find_cursor()` until the end of the transaction
+    pub fn detach_into_name(self) -> String {
+        let name = self.name().to_string();
+        std::mem::forget(self);
+        name
+    }
+}
+
+impl<'client> Drop for SpiCursor<'client> {
+    fn drop(&mut self) {
+        unsafe { pg_sys::SPI_cursor_close(self.portal) }
+    }
+}
+
+impl SpiTupleTable {
+    /// wrap whatever the last SPI call left in `SPI_tuptable`
+    fn from_spi(status_code: SpiOk) -> Self {
         SpiTupleTable {
-            status_code: Spi::check_status(status_code),
+            status_code,
             table: unsafe { pg_sys::SPI_tuptable },
             size: unsafe { pg_sys::SPI_processed as usize },
             tupdesc: if unsafe { pg_sys::SPI_tuptable }.is_null() {
@@ -360,9 +578,7 @@ impl SpiClient {
             current: -1,
         }
     }
-}
 
-impl SpiTupleTable {
     /// `SpiTupleTable`s are positioned before the start, for iteration purposes.
     ///
     /// This method moves the position to the first row.  If there are no rows, this
@@ -400,6 +616,128 @@ impl SpiTupleTable {
         (a, b, c)
     }
 
+    /// Render every row as a table like `psql` prints, with each value formatted by its type's
+    /// output function:
+    ///
+    /// ```text
+    ///  id | name
+    /// ----+--------
+    ///  1  | alice
+    ///  2  | [NULL]
+    /// (2 rows)
+    /// ```
+    ///
+    /// `NULL`s are shown as `[NULL]`, and backslashes, newlines, and tabs within values are
+    /// escaped so that each row is a single line.  Columns are left-aligned and trailing
+    /// whitespace is trimmed, so the result is stable enough to compare against a snapshot.
+    /// This doesn't change the table's position
+    pub fn to_table_string(&self) -> String {
+        let footer = if self.size == 1 {
+            "(1 row)\n".to_string()
+        } else {
+            format!("({} rows)\n", self.size)
+        };
+        let tupdesc = match self.tupdesc {
+            Some(tupdesc) => tupdesc,
+            // a utility statement, which doesn't return rows
+            None => return footer,
+        };
+
+        let to_string = |s: *mut std::os::raw::c_char| unsafe {
+            let string = CStr::from_ptr(s).to_string_lossy().into_owned();
+            pg_sys::pfree(s as void_mut_ptr);
+            string
+        };
+
+        let natts = unsafe { (*tupdesc).natts };
+        let mut rows = vec![(1..=natts)
+            .map(|attno| to_string(unsafe { pg_sys::SPI_fname(tupdesc, attno) }))
+            .collect::<Vec<_>>()];
+        if self.size > 0 {
+            let tuples = unsafe { std::slice::from_raw_parts((*self.table).vals, self.size) };
+            for tuple in tuples {
+                rows.push(
+                    (1..=natts)
+                        .map(|attno| {
+                            let value = unsafe { pg_sys::SPI_getvalue(*tuple, tupdesc, attno) };
+                            if value.is_null() {
+                                "[NULL]".to_string()
+                            } else {
+                                to_string(value)
+                                    .replace('\\', "\\\\")
+                                    .replace('\n', "\\n")
+                                    .replace('\r', "\\r")
+                                    .replace('\t', "\\t")
+                            }
+                        })
+                        .collect(),
+                );
+            }
+        }
+
+        let mut widths = vec![0; natts as usize];
+        for row in &rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.chars().count());
+            }
+        }
+
+        let mut table = String::new();
+        for (i, row) in rows.iter().enumerate() {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(value, width)| format!(" {:width$} ", value, width = width))
+                .collect::<Vec<_>>()
+                .join("|");
+            table.push_str(line.trim_end());
+            table.push('\n');
+
+            if i == 0 {
+                let line = widths
+                    .iter()
+                    .map(|width| "-".repeat(width + 2))
+                    .collect::<Vec<_>>()
+                    .join("+");
+                table.push_str(&line);
+                table.push('\n');
+            }
+        }
+        table.push_str(&footer);
+        table
+    }
+
+    /// The values of the column at `ordinal` (counting from one) in every row, converted in one
+    /// pass, or `None` if there's no such column.  This doesn't change the table's position
+    pub fn get_column<T: FromDatumColumn>(&self, ordinal: i32) -> Option<PgColumn<T>> {
+        self.get_datum_column(ordinal)
+            .map(|column| unsafe { column.to_column() })
+    }
+
+    /// The raw `Datum`s of the column at `ordinal` (counting from one) in every row, or `None` if
+    /// there's no such column.  They point into the table's memory, so are only valid for as long
+    /// as it is
+    pub fn get_datum_column(&self, ordinal: i32) -> Option<PgDatumColumn> {
+        let tupdesc = self.tupdesc?;
+        unsafe {
+            if ordinal < 1 || ordinal > (*tupdesc).natts {
+                return None;
+            }
+
+            let mut column =
+                PgDatumColumn::with_capacity(pg_sys::SPI_gettypeid(tupdesc, ordinal), self.size);
+            if self.size > 0 {
+                let tuples = std::slice::from_raw_parts((*self.table).vals, self.size);
+                for tuple in tuples {
+                    let mut is_null = false;
+                    let datum = pg_sys::SPI_getbinval(*tuple, tupdesc, ordinal, &mut is_null);
+                    column.push(if is_null { None } else { Some(datum) });
+                }
+            }
+            Some(column)
+        }
+    }
+
     pub fn get_heap_tuple(&self) -> Option<SpiHeapTupleData> {
         if self.current < 0 {
             panic!("SpiTupleTable positioned before start")
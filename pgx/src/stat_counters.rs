@@ -0,0 +1,337 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Wait-free statistics counters for an extension, in shared memory
+//!
+//! A `PgStatCounters` is a named set of `u64` counters shared by every backend.  Adding to one is
+//! a single atomic instruction, so counting never waits on a lock, no matter how many backends
+//! are doing it.
+//!
+//! These are not Postgres' cumulative statistics.  Postgres 15 has no way for an extension to
+//! add a kind of statistics of its own (that arrives in later releases), so the counters are an
+//! array in the extension's own shared memory, and they don't appear in the `pg_stat_*` views or
+//! in `pg_stat_reset()`.  They're written to a file of their own, `pg_stat/{name}.stat`, when the
+//! server shuts down cleanly and read back when it starts, so they survive restarts, and like
+//! Postgres' statistics they're discarded if the server crashes.
+//!
+//! Under Postgres 15, `PgStatCounters::snapshot()` follows the `stats_fetch_consistency` setting:
+//! unless it's `none`, the values read in a transaction stay the same until it ends.  Earlier
+//! versions always read the current values.
+//!
+//! The extension must be loaded via `shared_preload_libraries` and call `pg_stat_counters_init()`
+//! for each set of counters from `_PG_init()`.  Without that, counting does nothing.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! static STATS: PgStatCounters = PgStatCounters::new("my_extension", &["lookups", "misses"]);
+//! const LOOKUPS: usize = 0;
+//! const MISSES: usize = 1;
+//!
+//! #[pg_guard]
+//! pub extern "C" fn _PG_init() {
+//!     pg_stat_counters_init(&STATS);
+//! }
+//!
+//! #[pg_extern]
+//! fn lookup(key: &str) -> Option<String> {
+//!     STATS.increment(LOOKUPS);
+//!     let value = Spi::get_one_with_args(
+//!         "SELECT value FROM lookup WHERE key = $1",
+//!         vec![(PgBuiltInOids::TEXTOID.oid(), key.into_datum())],
+//!     );
+//!     if value.is_none() {
+//!         STATS.increment(MISSES);
+//!     }
+//!     value
+//! }
+//!
+//! #[pg_extern]
+//! fn my_extension_stats(
+//! ) -> impl std::iter::Iterator<Item = (name!(counter, String), name!(value, i64))> {
+//!     STATS
+//!         .snapshot()
+//!         .into_iter()
+//!         .map(|(counter, value)| (counter.to_string(), value as i64))
+//! }
+//! ```
+use crate::shmem::{pg_shmem_init, shmem_init_struct, PgSharedMemoryInitialization};
+use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+
+/// A named set of counters in shared memory.  Declare one as a `static`, and give it to
+/// `pg_stat_counters_init()` from `_PG_init()`
+pub struct PgStatCounters {
+    name: &'static str,
+    counters: &'static [&'static str],
+
+    /// One value per counter, followed by when they were last reset
+    values: AtomicPtr<AtomicU64>,
+}
+
+impl PgStatCounters {
+    /// Counters named `counters`, which are referred to by their position in it.  `name` names
+    /// their shared memory, and the `pg_stat/{name}.stat` file they're saved in, so it must be
+    /// unique across every extension in the cluster
+    pub const fn new(name: &'static str, counters: &'static [&'static str]) -> Self {
+        PgStatCounters {
+            name,
+            counters,
+            values: AtomicPtr::new(std::ptr::null_mut()),
+        }
+    }
+
+    /// The names of the counters
+    pub fn counters(&self) -> &'static [&'static str] {
+        self.counters
+    }
+
+    /// Are these counters counting?  They aren't if `pg_stat_counters_init()` wasn't called for
+    /// them when the extension was loaded
+    pub fn enabled(&self) -> bool {
+        !self.values.load(Ordering::Acquire).is_null()
+    }
+
+    /// Add `n` to the counter at `index`.  Does nothing if the counters aren't enabled
+    pub fn add(&self, index: usize, n: u64) {
+        if let Some(values) = self.values() {
+            values[self.check_index(index)].fetch_add(n, Ordering::Relaxed);
+        }
+    }
+
+    /// Add one to the counter at `index`.  Does nothing if the counters aren't enabled
+    pub fn increment(&self, index: usize) {
+        self.add(index, 1)
+    }
+
+    /// The current value of the counter at `index`, regardless of `stats_fetch_consistency`.
+    /// Raises an ERROR if the counters aren't enabled
+    pub fn get(&self, index: usize) -> u64 {
+        self.check_enabled()[self.check_index(index)].load(Ordering::Relaxed)
+    }
+
+    /// Every counter's name and value.  Under Postgres 15, unless `stats_fetch_consistency` is
+    /// `none`, the values are read once per transaction, so they agree with each other and stay
+    /// the same until the transaction ends.  Raises an ERROR if the counters aren't enabled
+    pub fn snapshot(&self) -> Vec<(&'static str, u64)> {
+        let values = if fetch_consistency_caches() {
+            let snapshots = unsafe { SNAPSHOTS.get_or_insert_with(HashMap::new) };
+            if snapshots.is_empty() {
+                // forget them all when the transaction ends, however it ends
+                crate::register_xact_callback(crate::PgXactCallbackEvent::Commit, || unsafe {
+                    SNAPSHOTS = None
+                });
+                crate::register_xact_callback(crate::PgXactCallbackEvent::Abort, || unsafe {
+                    SNAPSHOTS = None
+                });
+            }
+            snapshots
+                .entry(self.name)
+                .or_insert_with(|| self.read())
+                .clone()
+        } else {
+            self.read()
+        };
+
+        self.counters.iter().cloned().zip(values).collect()
+    }
+
+    /// When the counters were last reset with `reset()`, or `None` if they never have been
+    pub fn stats_reset(&self) -> Option<pg_sys::TimestampTz> {
+        match self.check_enabled()[self.counters.len()].load(Ordering::Relaxed) as i64 {
+            0 => None,
+            timestamp => Some(timestamp),
+        }
+    }
+
+    /// Zero every counter.  Raises an ERROR if the counters aren't enabled
+    pub fn reset(&self) {
+        let values = self.check_enabled();
+        for value in &values[..self.counters.len()] {
+            value.store(0, Ordering::Relaxed);
+        }
+        values[self.counters.len()].store(
+            unsafe { pg_sys::GetCurrentTimestamp() } as u64,
+            Ordering::Relaxed,
+        );
+    }
+
+    fn values(&self) -> Option<&'static [AtomicU64]> {
+        let values = self.values.load(Ordering::Acquire);
+        if values.is_null() {
+            None
+        } else {
+            Some(unsafe { std::slice::from_raw_parts(values, self.counters.len() + 1) })
+        }
+    }
+
+    fn check_enabled(&self) -> &'static [AtomicU64] {
+        match self.values() {
+            Some(values) => values,
+            None => {
+                ereport(
+                    PgLogLevel::ERROR,
+                    PgSqlErrorCode::ERRCODE_OBJECT_NOT_IN_PREREQUISITE_STATE,
+                    &format!("statistics counters `{}` are not enabled", self.name),
+                    file!(),
+                    line!(),
+                    column!(),
+                );
+                unreachable!();
+            }
+        }
+    }
+
+    fn check_index(&self, index: usize) -> usize {
+        if index >= self.counters.len() {
+            panic!(
+                "statistics counters `{}` have {} counters, there's no counter {}",
+                self.name,
+                self.counters.len(),
+                index
+            );
+        }
+        index
+    }
+
+    fn read(&self) -> Vec<u64> {
+        self.check_enabled()[..self.counters.len()]
+            .iter()
+            .map(|value| value.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Where the counters are saved between restarts, relative to the data directory
+    fn stats_file(&self) -> PathBuf {
+        PathBuf::from(format!("pg_stat/{}.stat", self.name))
+    }
+
+    /// Read back the counters saved by the last clean shutdown, and remove the file so a crash
+    /// later on doesn't bring them back
+    fn load(&self, values: &[AtomicU64]) {
+        let path = self.stats_file();
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => {
+                warning!("could not read `{}`: {}", path.display(), e);
+                return;
+            }
+        };
+
+        // counters are saved by name, so ones that have been added or removed since are fine
+        for line in contents.lines() {
+            let mut parts = line.splitn(2, ' ');
+            let (name, value) = match (parts.next(), parts.next().map(str::parse::<u64>)) {
+                (Some(name), Some(Ok(value))) => (name, value),
+                _ => {
+                    warning!("ignoring corrupt statistics file `{}`", path.display());
+                    for value in values {
+                        value.store(0, Ordering::Relaxed);
+                    }
+                    break;
+                }
+            };
+
+            if name == "stats_reset" {
+                values[self.counters.len()].store(value, Ordering::Relaxed);
+            } else if let Some(index) = self.counters.iter().position(|counter| *counter == name) {
+                values[index].store(value, Ordering::Relaxed);
+            }
+        }
+
+        if let Err(e) = std::fs::remove_file(&path) {
+            warning!("could not remove `{}`: {}", path.display(), e);
+        }
+    }
+
+    /// Save the counters for the next time the server starts
+    fn save(&self) {
+        let values = match self.values() {
+            Some(values) => values,
+            None => return,
+        };
+
+        let mut contents = String::new();
+        for (name, value) in self.counters.iter().zip(values) {
+            contents.push_str(&format!("{} {}\n", name, value.load(Ordering::Relaxed)));
+        }
+        contents.push_str(&format!(
+            "stats_reset {}\n",
+            values[self.counters.len()].load(Ordering::Relaxed)
+        ));
+
+        // write it all or nothing, so a half-written file is never read back
+        let path = self.stats_file();
+        let tmp = path.with_extension("tmp");
+        if let Err(e) = std::fs::write(&tmp, contents).and_then(|_| std::fs::rename(&tmp, &path)) {
+            warning!("could not write `{}`: {}", path.display(), e);
+        }
+    }
+}
+
+impl PgSharedMemoryInitialization for PgStatCounters {
+    fn shmem_size(&self) -> usize {
+        std::mem::size_of::<AtomicU64>() * (self.counters.len() + 1)
+    }
+
+    unsafe fn shmem_init(&self) {
+        let (ptr, found) = shmem_init_struct(self.name, self.shmem_size());
+        let values = std::slice::from_raw_parts(ptr as *const AtomicU64, self.counters.len() + 1);
+        if !found {
+            // all zeros is counters that have never counted anything
+            std::ptr::write_bytes(ptr as *mut u8, 0, self.shmem_size());
+
+            // the postmaster creates shared memory when the server starts, and again after a
+            // crash, by which time the file is gone
+            if !pg_sys::IsUnderPostmaster {
+                self.load(values);
+
+                // `pg_stat_counters_init()` only takes `&'static PgStatCounters`
+                pg_sys::on_shmem_exit(Some(save_on_exit), self as *const Self as pg_sys::Datum);
+            }
+        }
+        self.values.store(ptr as *mut AtomicU64, Ordering::Release);
+    }
+}
+
+/// Start counting with `counters`.  Must be called from `_PG_init()` while the extension is being
+/// loaded via `shared_preload_libraries`
+pub fn pg_stat_counters_init(counters: &'static PgStatCounters) {
+    pg_shmem_init(counters);
+}
+
+/// This backend's snapshot of each set of counters, when `stats_fetch_consistency` asks for them
+/// to be kept until the transaction ends
+static mut SNAPSHOTS: Option<HashMap<&'static str, Vec<u64>>> = None;
+
+#[cfg(feature = "pg15")]
+fn fetch_consistency_caches() -> bool {
+    unsafe {
+        pg_sys::pgstat_fetch_consistency
+            != pg_sys::PgStat_FetchConsistency_PGSTAT_FETCH_CONSISTENCY_NONE as i32
+    }
+}
+
+#[cfg(any(
+    feature = "pg10",
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14"
+))]
+fn fetch_consistency_caches() -> bool {
+    false
+}
+
+/// The postmaster's `on_shmem_exit` callback.  Postgres only saves its own statistics when it
+/// shuts down cleanly, and so do we
+unsafe extern "C" fn save_on_exit(code: i32, arg: pg_sys::Datum) {
+    if code == 0 {
+        (*(arg as *const PgStatCounters)).save();
+    }
+}
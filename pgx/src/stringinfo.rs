@@ -2,6 +2,20 @@
 // governed by the MIT license that can be found in the LICENSE file.
 
 //! A safe wrapper around Postgres `StringInfo` structure
+//!
+//! `StringInfo` implements both `std::fmt::Write` and `std::io::Write`, so `write!()` can format
+//! directly into Postgres-allocated memory, without building an intermediate Rust `String`.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use std::fmt::Write;
+//!
+//! let mut buffer = PgStringInfo::new();
+//! write!(&mut buffer, "{}\t{:.2}", "widget", 9.5).unwrap();
+//! let line: *const std::os::raw::c_char = buffer.into_char_ptr();
+//! ```
 #![allow(dead_code, non_snake_case)]
 
 use crate::{pg_sys, void_mut_ptr};
@@ -14,6 +28,9 @@ pub struct StringInfo {
     needs_pfree: bool,
 }
 
+/// `StringInfo`, named like the rest of pgx's wrappers around Postgres types
+pub type PgStringInfo = StringInfo;
+
 impl Into<pg_sys::StringInfo> for StringInfo {
     fn into(self) -> pg_sys::StringInfo {
         self.sid
@@ -45,6 +62,13 @@ impl std::io::Write for StringInfo {
     }
 }
 
+impl std::fmt::Write for StringInfo {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
 impl ToString for StringInfo {
     fn to_string(&self) -> String {
         unsafe { std::str::from_utf8_unchecked(self.as_bytes()).to_owned() }
@@ -105,10 +129,11 @@ impl StringInfo {
     }
 
     /// Push a Rust character onto the end.  A Rust `char` could be 4 bytes in total, so it
-    /// is converted into a String first to ensure unicode correctness
+    /// is encoded as UTF8 first to ensure unicode correctness
     #[inline]
     pub fn push(&mut self, ch: char) {
-        self.push_str(&ch.to_string());
+        let mut buf = [0u8; 4];
+        self.push_str(ch.encode_utf8(&mut buf));
     }
 
     /// Push a String reference onto the end
@@ -0,0 +1,141 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Run code in a subtransaction, so an ERROR it raises can be handled without aborting the whole
+//! transaction
+//!
+//! `subtransaction()` is what a PL/pgSQL `BEGIN ... EXCEPTION` block does:  if the closure raises
+//! an ERROR (or panics), everything it did is rolled back, and the caught error is returned for
+//! the caller to inspect with `PgSqlErrorCode::from_sqlerrcode()`.
+//!
+//! `retry_on_serialization()` builds on it to retry work that failed with a serialization
+//! failure (`40001`) or a deadlock (`40P01`), backing off a little longer before each attempt.
+//! Note that a `REPEATABLE READ` or `SERIALIZABLE` transaction keeps its snapshot for its whole
+//! life, so a serialization failure caused by that snapshot will just happen again.  At those
+//! isolation levels retrying inside the transaction helps with deadlocks and lock conflicts, but
+//! only the client can retry the transaction itself.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! // the last ERROR is raised again if all the attempts fail
+//! let balance = retry_on_serialization(|| {
+//!     Spi::get_one::<i64>(
+//!         "UPDATE accounts SET balance = balance - 10 WHERE id = 1 RETURNING balance",
+//!     )
+//! });
+//! ```
+use crate::{pg_sys, pg_try, CaughtError, PgSqlErrorCode};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::time::Duration;
+
+/// Run `f` in a subtransaction, which is committed if it returns and rolled back if it raises
+/// an ERROR or panics, in which case the caught error is returned.  Either way, the caller's
+/// memory context and resource owner are current again afterwards
+pub fn subtransaction<R, F>(f: F) -> Result<R, CaughtError>
+where
+    F: FnOnce() -> R + UnwindSafe + RefUnwindSafe,
+{
+    unsafe {
+        let context = pg_sys::CurrentMemoryContext;
+        let owner = pg_sys::CurrentResourceOwner;
+
+        pg_sys::BeginInternalSubTransaction(std::ptr::null());
+
+        // run `f` in the caller's memory context, not the subtransaction's, so what it returns
+        // outlives the subtransaction
+        pg_sys::CurrentMemoryContext = context;
+
+        // rolling the subtransaction back after the error is what makes catching it safe
+        let result = pg_try(|| {
+            let result = f();
+            pg_sys::ReleaseCurrentSubTransaction();
+            Ok(result)
+        })
+        .catch_error(|error| {
+            pg_sys::RollbackAndReleaseCurrentSubTransaction();
+            Err(error)
+        });
+
+        pg_sys::CurrentMemoryContext = context;
+        pg_sys::CurrentResourceOwner = owner;
+        result
+    }
+}
+
+/// How many times, and how patiently, `retry_on_serialization_with()` retries
+#[derive(Clone, Copy, Debug)]
+pub struct PgRetryPolicy {
+    /// Give up, raising the last error, after this many attempts in total
+    pub max_attempts: u32,
+
+    /// How long to wait before the first retry.  The wait doubles for each one after that
+    pub initial_backoff: Duration,
+
+    /// The longest to wait between attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for PgRetryPolicy {
+    fn default() -> Self {
+        PgRetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Run `f` in a subtransaction, retrying it with the default `PgRetryPolicy` if it fails with a
+/// serialization failure or a deadlock.  Any other error, or the last one once the attempts run
+/// out, is raised again
+pub fn retry_on_serialization<R, F>(f: F) -> R
+where
+    F: Fn() -> R + RefUnwindSafe,
+{
+    retry_on_serialization_with(PgRetryPolicy::default(), f)
+}
+
+/// `retry_on_serialization()`, with a `PgRetryPolicy` of your own
+pub fn retry_on_serialization_with<R, F>(policy: PgRetryPolicy, f: F) -> R
+where
+    F: Fn() -> R + RefUnwindSafe,
+{
+    let mut backoff = policy.initial_backoff;
+    let mut attempt = 1;
+
+    loop {
+        match subtransaction(&f) {
+            Ok(result) => return result,
+            Err(error) if attempt < policy.max_attempts && is_serialization_failure(&error) => {
+                debug1!(
+                    "retrying after attempt {} of {} failed: {}",
+                    attempt,
+                    policy.max_attempts,
+                    error.message
+                );
+
+                // sleep somewhere between half and all of the backoff, so that the transactions
+                // that conflicted don't just retry in lockstep
+                let millis = backoff.as_millis() as u64;
+                let jitter = crate::random::below(millis / 2 + 1);
+                crate::advisory_lock::sleep_on_latch(Duration::from_millis(millis - jitter));
+
+                backoff = std::cmp::min(backoff * 2, policy.max_backoff);
+                attempt += 1;
+            }
+            Err(error) => error.rethrow(),
+        }
+    }
+}
+
+/// Is `error` one that trying again might not run into?
+fn is_serialization_failure(error: &CaughtError) -> bool {
+    matches!(
+        PgSqlErrorCode::from_sqlerrcode(error.sqlerrcode),
+        Some(PgSqlErrorCode::ERRCODE_T_R_SERIALIZATION_FAILURE)
+            | Some(PgSqlErrorCode::ERRCODE_T_R_DEADLOCK_DETECTED)
+    )
+}
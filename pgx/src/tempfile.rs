@@ -0,0 +1,202 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Temporary files in `pgsql_tmp`, tracked by Postgres' resource owners
+//!
+//! Files created with `PgTempFile::new()` belong to the `CurrentResourceOwner`, so if the
+//! transaction aborts (or the owning `PgResourceOwner` is dropped) Postgres closes and deletes them
+//! instead of leaking them on disk.  They count towards `temp_file_limit` and are reported by
+//! `log_temp_files` like any other Postgres temporary file.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//! use std::io::{Read, Seek, SeekFrom, Write};
+//!
+//! let mut spill = PgTempFile::new();
+//! spill.write_all(b"lots of data").unwrap();
+//! spill.seek(SeekFrom::Start(0)).unwrap();
+//!
+//! let mut contents = String::new();
+//! spill.read_to_string(&mut contents).unwrap();
+//! ```
+use crate::{pg_sys, PgResourceOwner};
+use std::ffi::CStr;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+
+/// A temporary file that Postgres deletes once it's closed
+///
+/// The lifetime ties a file created by `PgTempFile::new_in()` to its `PgResourceOwner`.  Files
+/// created with `PgTempFile::new()` belong to the current transaction and must not outlive it
+pub struct PgTempFile<'a> {
+    file: pg_sys::File,
+    position: u64,
+    __marker: PhantomData<&'a PgResourceOwner>,
+}
+
+impl PgTempFile<'static> {
+    /// Create a temporary file owned by the `CurrentResourceOwner`, which is closed no later than
+    /// the end of the current transaction
+    pub fn new() -> Self {
+        PgTempFile::open(false)
+    }
+
+    /// Create a temporary file that outlives the current transaction.  It's closed when dropped,
+    /// or when the backend exits
+    pub fn new_inter_xact() -> Self {
+        PgTempFile::open(true)
+    }
+
+    fn open(inter_xact: bool) -> Self {
+        PgTempFile {
+            file: unsafe { pg_sys::OpenTemporaryFile(inter_xact) },
+            position: 0,
+            __marker: PhantomData,
+        }
+    }
+}
+
+impl Default for PgTempFile<'static> {
+    fn default() -> Self {
+        PgTempFile::new()
+    }
+}
+
+impl<'a> PgTempFile<'a> {
+    /// Create a temporary file owned by `owner`, which is closed no later than when `owner` is
+    pub fn new_in(owner: &'a PgResourceOwner) -> Self {
+        let file = owner.switch_to(|| unsafe { pg_sys::OpenTemporaryFile(false) });
+        PgTempFile {
+            file,
+            position: 0,
+            __marker: PhantomData,
+        }
+    }
+
+    /// The file's path, relative to the data directory
+    pub fn path(&self) -> String {
+        unsafe { CStr::from_ptr(pg_sys::FilePathName(self.file)) }
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// The file's current size, in bytes
+    pub fn size(&self) -> std::io::Result<u64> {
+        #[cfg(any(feature = "pg10", feature = "pg11"))]
+        let size = unsafe { pg_sys::FileSeek(self.file, 0, pg_sys::SEEK_END as i32) };
+
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+        let size = unsafe { pg_sys::FileSize(self.file) };
+
+        if size < 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(size as u64)
+        }
+    }
+}
+
+impl<'a> Read for PgTempFile<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        #[cfg(any(feature = "pg10", feature = "pg11"))]
+        let nread = unsafe {
+            pg_sys::FileSeek(
+                self.file,
+                self.position as pg_sys::off_t,
+                pg_sys::SEEK_SET as i32,
+            );
+            pg_sys::FileRead(
+                self.file,
+                buf.as_mut_ptr() as *mut std::os::raw::c_char,
+                buf.len() as i32,
+                pg_sys::PG_WAIT_EXTENSION,
+            )
+        };
+
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+        let nread = unsafe {
+            pg_sys::FileRead(
+                self.file,
+                buf.as_mut_ptr() as *mut std::os::raw::c_char,
+                buf.len() as i32,
+                self.position as pg_sys::off_t,
+                pg_sys::PG_WAIT_EXTENSION,
+            )
+        };
+
+        if nread < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        self.position += nread as u64;
+        Ok(nread as usize)
+    }
+}
+
+impl<'a> Write for PgTempFile<'a> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        #[cfg(any(feature = "pg10", feature = "pg11"))]
+        let nwritten = unsafe {
+            pg_sys::FileSeek(
+                self.file,
+                self.position as pg_sys::off_t,
+                pg_sys::SEEK_SET as i32,
+            );
+            pg_sys::FileWrite(
+                self.file,
+                buf.as_ptr() as *mut std::os::raw::c_char,
+                buf.len() as i32,
+                pg_sys::PG_WAIT_EXTENSION,
+            )
+        };
+
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+        let nwritten = unsafe {
+            pg_sys::FileWrite(
+                self.file,
+                buf.as_ptr() as *mut std::os::raw::c_char,
+                buf.len() as i32,
+                self.position as pg_sys::off_t,
+                pg_sys::PG_WAIT_EXTENSION,
+            )
+        };
+
+        if nwritten < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        self.position += nwritten as u64;
+        Ok(nwritten as usize)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        // Postgres temporary files are never fsync'd
+        Ok(())
+    }
+}
+
+impl<'a> Seek for PgTempFile<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+            SeekFrom::End(offset) => self.size()? as i64 + offset,
+        };
+
+        if new_position < 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            ));
+        }
+        self.position = new_position as u64;
+        Ok(self.position)
+    }
+}
+
+impl<'a> Drop for PgTempFile<'a> {
+    fn drop(&mut self) {
+        // closing a temporary file also deletes it
+        unsafe { pg_sys::FileClose(self.file) }
+    }
+}
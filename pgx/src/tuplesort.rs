@@ -0,0 +1,381 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Sorting that spills to disk under `work_mem` pressure
+//!
+//! `PgDatumSort` wraps Postgres' own `tuplesort`, ordering `Datum`s with a btree sort operator
+//! exactly like `ORDER BY` would.
+//!
+//! `PgExternalSort` sorts arbitrary Rust values with a comparator closure.  Values are buffered in
+//! memory until they exceed `work_mem`, at which point the buffer is sorted and written to a
+//! Postgres temporary file as a "run".  Iterating the sorted result merges the runs.  Values must
+//! be `serde` serializable so they can be written to disk.
+//!
+//! Both are useful for aggregates and set-returning functions whose intermediate results might not
+//! fit in memory.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! // longest words first, using at most 1MB of memory
+//! let mut sort = PgExternalSort::new(|a: &String, b: &String| b.len().cmp(&a.len()))
+//!     .with_work_mem(1024)
+//!     .with_item_size(|word| std::mem::size_of::<String>() + word.len());
+//! for word in vec!["a", "abc", "ab"] {
+//!     sort.push(word.to_string());
+//! }
+//! let sorted = sort.sort().collect::<Vec<_>>();
+//! assert_eq!(sorted, vec!["abc", "ab", "a"]);
+//!
+//! // integers, sorted by Postgres
+//! let mut sort = PgDatumSort::by_default_order(pg_sys::INT4OID, false);
+//! sort.push(3i32.into_datum());
+//! sort.push(None);
+//! sort.push(1i32.into_datum());
+//! let sorted = sort
+//!     .sort()
+//!     .map(|datum| unsafe { i32::from_datum(datum.unwrap_or(0), datum.is_none(), pg_sys::INT4OID) })
+//!     .collect::<Vec<_>>();
+//! assert_eq!(sorted, vec![Some(1), Some(3), None]);
+//! ```
+use crate::{pg_sys, PgTempFile};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom, Write};
+
+/// A sort of `Datum`s of a single type, using Postgres' `tuplesort`
+pub struct PgDatumSort {
+    state: *mut pg_sys::Tuplesortstate,
+}
+
+impl PgDatumSort {
+    /// Sort values of type `type_oid` with the btree ordering operator `sort_operator` (such as a
+    /// type's `<` or `>`), using up to `work_mem` of memory
+    pub fn new(
+        type_oid: pg_sys::Oid,
+        sort_operator: pg_sys::Oid,
+        collation: pg_sys::Oid,
+        nulls_first: bool,
+    ) -> Self {
+        PgDatumSort {
+            state: begin_datum_sort(type_oid, sort_operator, collation, nulls_first),
+        }
+    }
+
+    /// Sort values of type `type_oid` in its default ascending (or `descending`) order, the way
+    /// `ORDER BY` does.  NULLs sort as though they're larger than any other value
+    pub fn by_default_order(type_oid: pg_sys::Oid, descending: bool) -> Self {
+        let flags = if descending {
+            pg_sys::TYPECACHE_GT_OPR
+        } else {
+            pg_sys::TYPECACHE_LT_OPR
+        };
+        let typcache = unsafe { &*pg_sys::lookup_type_cache(type_oid, flags as i32) };
+        let sort_operator = if descending {
+            typcache.gt_opr
+        } else {
+            typcache.lt_opr
+        };
+        if sort_operator == pg_sys::InvalidOid {
+            panic!("type {} has no default btree ordering", type_oid);
+        }
+
+        let collation = unsafe { pg_sys::get_typcollation(type_oid) };
+        PgDatumSort::new(type_oid, sort_operator, collation, descending)
+    }
+
+    /// Add a value, where `None` is NULL.  Pass-by-reference values are copied
+    pub fn push(&mut self, value: Option<pg_sys::Datum>) {
+        unsafe { pg_sys::tuplesort_putdatum(self.state, value.unwrap_or(0), value.is_none()) }
+    }
+
+    /// Sort the values, and iterate over them in order.  Pass-by-reference values are copied into
+    /// the `CurrentMemoryContext`
+    pub fn sort(self) -> PgDatumSortIter {
+        unsafe { pg_sys::tuplesort_performsort(self.state) }
+        PgDatumSortIter { sort: self }
+    }
+}
+
+impl Drop for PgDatumSort {
+    fn drop(&mut self) {
+        unsafe { pg_sys::tuplesort_end(self.state) }
+    }
+}
+
+/// The sorted values of a `PgDatumSort`
+pub struct PgDatumSortIter {
+    sort: PgDatumSort,
+}
+
+impl Iterator for PgDatumSortIter {
+    type Item = Option<pg_sys::Datum>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut value = 0;
+        let mut is_null = false;
+        let found = unsafe {
+            pg_sys::tuplesort_getdatum(
+                self.sort.state,
+                true,
+                &mut value,
+                &mut is_null,
+                std::ptr::null_mut(),
+            )
+        };
+
+        if !found {
+            None
+        } else if is_null {
+            Some(None)
+        } else {
+            Some(Some(value))
+        }
+    }
+}
+
+#[cfg(feature = "pg10")]
+fn begin_datum_sort(
+    type_oid: pg_sys::Oid,
+    sort_operator: pg_sys::Oid,
+    collation: pg_sys::Oid,
+    nulls_first: bool,
+) -> *mut pg_sys::Tuplesortstate {
+    unsafe {
+        pg_sys::tuplesort_begin_datum(
+            type_oid,
+            sort_operator,
+            collation,
+            nulls_first,
+            pg_sys::work_mem,
+            false,
+        )
+    }
+}
+
+#[cfg(any(feature = "pg11", feature = "pg12", feature = "pg13", feature = "pg14"))]
+fn begin_datum_sort(
+    type_oid: pg_sys::Oid,
+    sort_operator: pg_sys::Oid,
+    collation: pg_sys::Oid,
+    nulls_first: bool,
+) -> *mut pg_sys::Tuplesortstate {
+    unsafe {
+        pg_sys::tuplesort_begin_datum(
+            type_oid,
+            sort_operator,
+            collation,
+            nulls_first,
+            pg_sys::work_mem,
+            std::ptr::null_mut(),
+            false,
+        )
+    }
+}
+
+#[cfg(feature = "pg15")]
+fn begin_datum_sort(
+    type_oid: pg_sys::Oid,
+    sort_operator: pg_sys::Oid,
+    collation: pg_sys::Oid,
+    nulls_first: bool,
+) -> *mut pg_sys::Tuplesortstate {
+    // Postgres 15 replaced the `randomAccess` flag with a bitmask of `TUPLESORT_*` options
+    unsafe {
+        pg_sys::tuplesort_begin_datum(
+            type_oid,
+            sort_operator,
+            collation,
+            nulls_first,
+            pg_sys::work_mem,
+            std::ptr::null_mut(),
+            pg_sys::TUPLESORT_NONE as i32,
+        )
+    }
+}
+
+/// A sort of Rust values, ordered by a comparator closure, that spills to disk.  See the module
+/// documentation.
+///
+/// Spilled runs belong to the current transaction, so the sort must be finished before it ends
+pub struct PgExternalSort<T, F> {
+    compare: F,
+    item_size: fn(&T) -> usize,
+    max_bytes: usize,
+    buffer: Vec<T>,
+    buffer_bytes: usize,
+    runs: Vec<PgTempFile<'static>>,
+    len: usize,
+}
+
+fn size_of_item<T>(_: &T) -> usize {
+    std::mem::size_of::<T>()
+}
+
+impl<T, F> PgExternalSort<T, F>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    /// Sort values with `compare`, using up to `work_mem` of memory
+    pub fn new(compare: F) -> Self {
+        PgExternalSort {
+            compare,
+            item_size: size_of_item::<T>,
+            max_bytes: unsafe { pg_sys::work_mem } as usize * 1024,
+            buffer: Vec::new(),
+            buffer_bytes: 0,
+            runs: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Use up to `kbytes` of memory, rather than `work_mem`
+    pub fn with_work_mem(mut self, kbytes: usize) -> Self {
+        self.max_bytes = kbytes * 1024;
+        self
+    }
+
+    /// Estimate how much memory each value uses with `item_size`.  By default, that's
+    /// `std::mem::size_of::<T>()`, which doesn't account for any heap allocations a value owns
+    pub fn with_item_size(mut self, item_size: fn(&T) -> usize) -> Self {
+        self.item_size = item_size;
+        self
+    }
+
+    /// Add a value
+    pub fn push(&mut self, item: T) {
+        self.buffer_bytes += (self.item_size)(&item);
+        self.buffer.push(item);
+        self.len += 1;
+
+        if self.buffer_bytes > self.max_bytes {
+            self.spill();
+        }
+    }
+
+    /// How many values have been added?
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Have no values been added?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Have the values outgrown memory and spilled to disk?
+    pub fn is_spilled(&self) -> bool {
+        !self.runs.is_empty()
+    }
+
+    /// Sort the values, and iterate over them in order
+    pub fn sort(mut self) -> PgExternalSortIter<T, F> {
+        self.sort_buffer();
+
+        let mut sources = Vec::with_capacity(self.runs.len() + 1);
+        for mut run in self.runs.drain(..) {
+            run.seek(SeekFrom::Start(0))
+                .expect("failed to rewind sort run");
+            sources.push(Source::Run(
+                serde_cbor::Deserializer::from_reader(BufReader::new(run)).into_iter(),
+            ));
+        }
+        sources.push(Source::Memory(
+            std::mem::replace(&mut self.buffer, Vec::new()).into_iter(),
+        ));
+
+        let heads = sources.iter_mut().map(Source::next).collect();
+        PgExternalSortIter {
+            compare: self.compare,
+            sources,
+            heads,
+        }
+    }
+
+    fn sort_buffer(&mut self) {
+        let compare = &mut self.compare;
+        self.buffer.sort_by(|a, b| compare(a, b));
+    }
+
+    /// Write the sorted buffer to a temporary file
+    fn spill(&mut self) {
+        self.sort_buffer();
+
+        let mut writer = BufWriter::new(PgTempFile::new());
+        for item in self.buffer.drain(..) {
+            serde_cbor::to_writer(&mut writer, &item).expect("failed to write sort run");
+        }
+        writer.flush().expect("failed to flush sort run");
+
+        self.runs.push(
+            writer
+                .into_inner()
+                .unwrap_or_else(|_| panic!("failed to flush sort run")),
+        );
+        self.buffer_bytes = 0;
+    }
+}
+
+type RunReader<T> = serde_cbor::StreamDeserializer<
+    'static,
+    serde_cbor::de::IoRead<BufReader<PgTempFile<'static>>>,
+    T,
+>;
+
+enum Source<T> {
+    Run(RunReader<T>),
+    Memory(std::vec::IntoIter<T>),
+}
+
+impl<T: DeserializeOwned> Source<T> {
+    fn next(&mut self) -> Option<T> {
+        match self {
+            Source::Run(run) => run
+                .next()
+                .map(|item| item.expect("failed to read sort run")),
+            Source::Memory(items) => items.next(),
+        }
+    }
+}
+
+/// The sorted values of a `PgExternalSort`
+pub struct PgExternalSortIter<T, F> {
+    compare: F,
+    sources: Vec<Source<T>>,
+    heads: Vec<Option<T>>,
+}
+
+impl<T, F> Iterator for PgExternalSortIter<T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // each run is sorted, so the next value is the smallest of their heads.  There are rarely
+        // enough runs for a heap to be worthwhile
+        let mut smallest: Option<usize> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            if let Some(head) = head {
+                smallest = match smallest {
+                    Some(s)
+                        if (self.compare)(head, self.heads[s].as_ref().unwrap())
+                            != Ordering::Less =>
+                    {
+                        Some(s)
+                    }
+                    _ => Some(i),
+                };
+            }
+        }
+
+        let i = smallest?;
+        let next = self.sources[i].next();
+        std::mem::replace(&mut self.heads[i], next)
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! A buffer of rows that spills to disk, wrapping Postgres' `tuplestore`
+//!
+//! A `PgTuplestore` keeps rows in memory until they exceed `work_mem`, and then transparently
+//! moves them to a temporary file.  It's what Postgres itself uses to materialize the results of
+//! set-returning functions, CTEs, and window partitions.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! let typid = 42 as pg_sys::Oid; // a composite type of (bigint, text)
+//! let tupdesc = unsafe { PgTupleDesc::from_pg_is_copy(pg_sys::lookup_rowtype_tupdesc_copy(typid, -1)) };
+//! let mut store = PgTuplestore::new(&tupdesc, false);
+//! for i in 0..1_000_000i64 {
+//!     store.push_values(&[i.into_datum(), format!("row {}", i).into_datum()]);
+//! }
+//!
+//! let mut total = 0;
+//! for row in store.rows() {
+//!     total += unsafe { i64::from_datum(row[0].unwrap(), false, pg_sys::INT8OID) }.unwrap();
+//! }
+//! ```
+use crate::{pg_sys, PgDatumColumn, PgTupleDesc};
+
+/// A spill-to-disk buffer of rows that all have the same `PgTupleDesc`
+pub struct PgTuplestore<'a> {
+    state: *mut pg_sys::Tuplestorestate,
+    tupdesc: &'a PgTupleDesc<'a>,
+}
+
+impl<'a> PgTuplestore<'a> {
+    /// Create an empty tuplestore for rows described by `tupdesc`, which keeps up to `work_mem` of
+    /// rows in memory.  A `random_access` tuplestore can be read more than once
+    pub fn new(tupdesc: &'a PgTupleDesc<'a>, random_access: bool) -> Self {
+        PgTuplestore::with_work_mem(tupdesc, random_access, unsafe { pg_sys::work_mem } as usize)
+    }
+
+    /// Like `new()`, but keeps up to `max_kbytes` of rows in memory
+    pub fn with_work_mem(
+        tupdesc: &'a PgTupleDesc<'a>,
+        random_access: bool,
+        max_kbytes: usize,
+    ) -> Self {
+        PgTuplestore {
+            state: unsafe { pg_sys::tuplestore_begin_heap(random_access, false, max_kbytes as i32) },
+            tupdesc,
+        }
+    }
+
+    /// Add a row.  There must be one value per attribute in the tuplestore's `PgTupleDesc`, and
+    /// `None` is NULL
+    pub fn push_values(&mut self, values: &[Option<pg_sys::Datum>]) {
+        let natts = self.tupdesc.len();
+        if values.len() != natts {
+            panic!(
+                "tuplestore rows have {} attributes, but {} values were provided",
+                natts,
+                values.len()
+            );
+        }
+
+        let mut datums = values
+            .iter()
+            .map(|value| value.unwrap_or(0))
+            .collect::<Vec<_>>();
+        let mut nulls = values.iter().map(Option::is_none).collect::<Vec<_>>();
+        unsafe {
+            pg_sys::tuplestore_putvalues(
+                self.state,
+                self.tupdesc.as_ptr(),
+                datums.as_mut_ptr(),
+                nulls.as_mut_ptr(),
+            )
+        }
+    }
+
+    /// Add a row for each value in `columns`, one column per attribute in the tuplestore's
+    /// `PgTupleDesc`.  The columns must all have the same number of rows
+    pub fn push_columns(&mut self, columns: &[PgDatumColumn]) {
+        let natts = self.tupdesc.len();
+        if columns.len() != natts {
+            panic!(
+                "tuplestore rows have {} attributes, but {} columns were provided",
+                natts,
+                columns.len()
+            );
+        }
+        let nrows = columns.first().map_or(0, PgDatumColumn::len);
+        if columns.iter().any(|column| column.len() != nrows) {
+            panic!("the columns pushed into a tuplestore must all have the same number of rows");
+        }
+
+        let mut datums = vec![0; natts];
+        let mut nulls = vec![false; natts];
+        for row in 0..nrows {
+            for (i, column) in columns.iter().enumerate() {
+                datums[i] = column.datums[row];
+                nulls[i] = column.nulls[row];
+            }
+            unsafe {
+                pg_sys::tuplestore_putvalues(
+                    self.state,
+                    self.tupdesc.as_ptr(),
+                    datums.as_mut_ptr(),
+                    nulls.as_mut_ptr(),
+                )
+            }
+        }
+    }
+
+    /// Add a copy of an existing tuple, which must match the tuplestore's `PgTupleDesc`
+    pub fn push_heap_tuple(&mut self, tuple: pg_sys::HeapTuple) {
+        unsafe { pg_sys::tuplestore_puttuple(self.state, tuple) }
+    }
+
+    /// How many rows have been added?
+    pub fn len(&self) -> usize {
+        unsafe { pg_sys::tuplestore_tuple_count(self.state) as usize }
+    }
+
+    /// Have no rows been added?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Do all the rows still fit in memory, or has the tuplestore spilled to disk?
+    pub fn is_in_memory(&self) -> bool {
+        unsafe { pg_sys::tuplestore_in_memory(self.state) }
+    }
+
+    /// Iterate over the rows, in the order they were added.  Each row's values are copied into the
+    /// `CurrentMemoryContext`, so they remain valid after the iterator moves on.
+    ///
+    /// Unless the tuplestore was created with `random_access`, this can only be done once
+    pub fn rows(&mut self) -> PgTuplestoreRows<'_, 'a> {
+        unsafe { pg_sys::tuplestore_rescan(self.state) }
+        let slot = make_slot(self.tupdesc.as_ptr());
+        let attrs = self
+            .tupdesc
+            .iter()
+            .map(|att| (att.attbyval, att.attlen as i32))
+            .collect();
+        PgTuplestoreRows {
+            store: self,
+            slot,
+            attrs,
+        }
+    }
+
+    /// Read the values of the attribute at `index` (counting from zero) from every row, in the
+    /// order they were added, as a column.  Like `rows()`, the values are copied into the
+    /// `CurrentMemoryContext`, and unless the tuplestore was created with `random_access`, this
+    /// can only be done once
+    pub fn column(&mut self, index: usize) -> PgDatumColumn {
+        let natts = self.tupdesc.len();
+        if index >= natts {
+            panic!(
+                "tuplestore rows have {} attributes, there's no attribute {}",
+                natts, index
+            );
+        }
+
+        let att = self.tupdesc.get(index).unwrap();
+        let (typbyval, typlen) = (att.attbyval, att.attlen as i32);
+        let mut column = PgDatumColumn::with_capacity(att.atttypid, self.len());
+        unsafe {
+            pg_sys::tuplestore_rescan(self.state);
+            let slot = make_slot(self.tupdesc.as_ptr());
+            while pg_sys::tuplestore_gettupleslot(self.state, true, false, slot) {
+                slot_getallattrs(slot, index + 1);
+
+                if *(*slot).tts_isnull.add(index) {
+                    column.push(None);
+                } else {
+                    // the slot's values only last until the next row is read
+                    let datum = *(*slot).tts_values.add(index);
+                    column.push(Some(pg_sys::datumCopy(datum, typbyval, typlen)));
+                }
+            }
+            pg_sys::ExecDropSingleTupleTableSlot(slot);
+        }
+        column
+    }
+
+    /// Give the tuplestore to Postgres, typically as the result of a set-returning function using
+    /// `SFRM_Materialize` mode.  Postgres becomes responsible for freeing it
+    pub fn into_pg(mut self) -> *mut pg_sys::Tuplestorestate {
+        std::mem::replace(&mut self.state, std::ptr::null_mut())
+    }
+}
+
+impl<'a> Drop for PgTuplestore<'a> {
+    fn drop(&mut self) {
+        if !self.state.is_null() {
+            unsafe { pg_sys::tuplestore_end(self.state) }
+        }
+    }
+}
+
+/// An iterator over the rows of a `PgTuplestore`
+pub struct PgTuplestoreRows<'s, 'a> {
+    store: &'s mut PgTuplestore<'a>,
+    slot: *mut pg_sys::TupleTableSlot,
+    attrs: Vec<(bool, i32)>,
+}
+
+impl<'s, 'a> Iterator for PgTuplestoreRows<'s, 'a> {
+    type Item = Vec<Option<pg_sys::Datum>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            if !pg_sys::tuplestore_gettupleslot(self.store.state, true, false, self.slot) {
+                return None;
+            }
+
+            let natts = self.attrs.len();
+            slot_getallattrs(self.slot, natts);
+
+            let values = std::slice::from_raw_parts((*self.slot).tts_values, natts);
+            let nulls = std::slice::from_raw_parts((*self.slot).tts_isnull, natts);
+            let row = (0..natts)
+                .map(|i| {
+                    if nulls[i] {
+                        None
+                    } else {
+                        // the slot's values only last until the next row is read
+                        let (typbyval, typlen) = self.attrs[i];
+                        Some(pg_sys::datumCopy(values[i], typbyval, typlen))
+                    }
+                })
+                .collect();
+            Some(row)
+        }
+    }
+}
+
+impl<'s, 'a> Drop for PgTuplestoreRows<'s, 'a> {
+    fn drop(&mut self) {
+        unsafe { pg_sys::ExecDropSingleTupleTableSlot(self.slot) }
+    }
+}
+
+#[cfg(any(feature = "pg10", feature = "pg11"))]
+fn make_slot(tupdesc: pg_sys::TupleDesc) -> *mut pg_sys::TupleTableSlot {
+    unsafe { pg_sys::MakeSingleTupleTableSlot(tupdesc) }
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+fn make_slot(tupdesc: pg_sys::TupleDesc) -> *mut pg_sys::TupleTableSlot {
+    unsafe { pg_sys::MakeSingleTupleTableSlot(tupdesc, &pg_sys::TTSOpsMinimalTuple) }
+}
+
+#[cfg(any(feature = "pg10", feature = "pg11"))]
+unsafe fn slot_getallattrs(slot: *mut pg_sys::TupleTableSlot, _natts: usize) {
+    pg_sys::slot_getallattrs(slot)
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+unsafe fn slot_getallattrs(slot: *mut pg_sys::TupleTableSlot, natts: usize) {
+    // `slot_getallattrs()` is an inline function in Postgres 12
+    if ((*slot).tts_nvalid as usize) < natts {
+        pg_sys::slot_getsomeattrs_int(slot, natts as i32)
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright 2020 ZomboDB, LLC <zombodb@gmail.com>. All rights reserved. Use of this source code is
+// governed by the MIT license that can be found in the LICENSE file.
+
+//! Vacuum and analyze tables, and read their `pg_stat_all_tables` counters, without building SQL
+//!
+//! Extensions that keep their own internal tables can use these from a maintenance background
+//! worker to keep them healthy, rather than relying on autovacuum's thresholds alone.
+//!
+//! `analyze_relation()` runs in the current transaction, so it can be used anywhere, including
+//! from `#[pg_extern]` functions.  Like the `VACUUM` command, `vacuum_relation()` commits
+//! transactions of its own, so it can only be called when no transaction is in progress, such
+//! as from a background worker's main loop, outside of `BackgroundWorker::transaction()`.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! use pgx::*;
+//!
+//! fn analyze_if_stale(relid: pg_sys::Oid) -> bool {
+//!     match table_stats(relid) {
+//!         Some(stats) if stats.n_mod_since_analyze > 1000 => {
+//!             analyze_relation(relid);
+//!             true
+//!         }
+//!         _ => false,
+//!     }
+//! }
+//!
+//! // called from a background worker's main loop, outside of any transaction
+//! fn vacuum_internal_table(relid: pg_sys::Oid) {
+//!     vacuum_relation(
+//!         relid,
+//!         &PgVacuumOptions {
+//!             analyze: true,
+//!             ..Default::default()
+//!         },
+//!     );
+//! }
+//! ```
+use crate::{backend, ereport, pg_sys, PgLogLevel, PgMemoryContexts, PgSqlErrorCode};
+
+/// How `vacuum_relation()` should vacuum, like the options of the `VACUUM` command
+#[derive(Clone, Debug, Default)]
+pub struct PgVacuumOptions {
+    /// Rewrite the table to reclaim all of its free space, like `VACUUM FULL`
+    pub full: bool,
+
+    /// Aggressively freeze tuples, like `VACUUM FREEZE`
+    pub freeze: bool,
+
+    /// Also update the table's planner statistics, like `VACUUM ANALYZE`
+    pub analyze: bool,
+
+    /// Report progress at `INFO`, like `VACUUM VERBOSE`
+    pub verbose: bool,
+
+    /// Visit every page of the table, even those the visibility map says are all-visible
+    pub disable_page_skipping: bool,
+}
+
+/// A table's statistics, as they're shown in `pg_stat_all_tables`
+#[derive(Clone, Debug, Default)]
+pub struct PgTableStats {
+    /// Sequential scans of the table
+    pub seq_scan: i64,
+
+    /// Live rows returned by sequential scans
+    pub seq_tup_read: i64,
+
+    /// Rows inserted
+    pub n_tup_ins: i64,
+
+    /// Rows updated, including HOT updates
+    pub n_tup_upd: i64,
+
+    /// Rows deleted
+    pub n_tup_del: i64,
+
+    /// Rows updated without needing to update any indexes
+    pub n_tup_hot_upd: i64,
+
+    /// The estimated number of live rows
+    pub n_live_tup: i64,
+
+    /// The estimated number of dead rows, which vacuum will reclaim
+    pub n_dead_tup: i64,
+
+    /// The estimated number of rows modified since the table was last analyzed
+    pub n_mod_since_analyze: i64,
+
+    /// Blocks requested by reads of the table
+    pub blocks_fetched: i64,
+
+    /// Blocks requested by reads of the table that were found in shared buffers
+    pub blocks_hit: i64,
+
+    /// When the table was last vacuumed manually, not counting `VACUUM FULL`
+    pub last_vacuum: Option<pg_sys::TimestampTz>,
+
+    /// When the table was last vacuumed by autovacuum
+    pub last_autovacuum: Option<pg_sys::TimestampTz>,
+
+    /// When the table was last analyzed manually
+    pub last_analyze: Option<pg_sys::TimestampTz>,
+
+    /// When the table was last analyzed by autovacuum
+    pub last_autoanalyze: Option<pg_sys::TimestampTz>,
+
+    /// How many times the table has been vacuumed manually, not counting `VACUUM FULL`
+    pub vacuum_count: i64,
+
+    /// How many times the table has been vacuumed by autovacuum
+    pub autovacuum_count: i64,
+
+    /// How many times the table has been analyzed manually
+    pub analyze_count: i64,
+
+    /// How many times the table has been analyzed by autovacuum
+    pub autoanalyze_count: i64,
+}
+
+/// Vacuum the table `relid`, like the `VACUUM` command, with the same permission checks.
+///
+/// Raises an ERROR if a transaction is in progress, because vacuum runs in transactions of its own
+pub fn vacuum_relation(relid: pg_sys::Oid, options: &PgVacuumOptions) {
+    if backend::in_transaction() {
+        ereport(
+            PgLogLevel::ERROR,
+            PgSqlErrorCode::ERRCODE_ACTIVE_SQL_TRANSACTION,
+            "VACUUM cannot run inside a transaction",
+            file!(),
+            line!(),
+            column!(),
+        );
+    }
+
+    PgMemoryContexts::Transient {
+        parent: PgMemoryContexts::TopMemoryContext.value(),
+        name: "pgx vacuum",
+        min_context_size: pg_sys::ALLOCSET_DEFAULT_MINSIZE,
+        initial_block_size: pg_sys::ALLOCSET_DEFAULT_INITSIZE,
+        max_block_size: pg_sys::ALLOCSET_DEFAULT_MAXSIZE,
+    }
+    .switch_to(|context| unsafe {
+        // like autovacuum, give vacuum a long-lived context to keep its state in while it
+        // commits transactions, and build the statement there so it survives them too
+        let context = context.value();
+        let previous_portal_context = pg_sys::PortalContext;
+        pg_sys::PortalContext = context;
+
+        pg_sys::SetCurrentStatementStartTimestamp();
+        pg_sys::StartTransactionCommand();
+        pg_sys::CurrentMemoryContext = context;
+
+        exec_vacuum(vacuum_stmt(relid, options), true);
+
+        pg_sys::CommitTransactionCommand();
+        pg_sys::PortalContext = previous_portal_context;
+    });
+}
+
+/// Update the planner statistics of the table `relid`, like the `ANALYZE` command, with the same
+/// permission checks.  Runs in the current transaction
+pub fn analyze_relation(relid: pg_sys::Oid) {
+    unsafe {
+        // vacuum keeps its state in a child of the `PortalContext`, which background workers
+        // don't have
+        let previous_portal_context = pg_sys::PortalContext;
+        if previous_portal_context.is_null() {
+            pg_sys::PortalContext = pg_sys::CurrentMemoryContext;
+        }
+
+        exec_vacuum(analyze_stmt(relid), false);
+
+        pg_sys::PortalContext = previous_portal_context;
+    }
+}
+
+/// The statistics collected for the table `relid`, or `None` if none have been yet.
+///
+/// Like `pg_stat_all_tables`, these come from a snapshot taken the first time they're read in a
+/// transaction, and don't include the current transaction's own activity
+pub fn table_stats(relid: pg_sys::Oid) -> Option<PgTableStats> {
+    let entry = unsafe { pg_sys::pgstat_fetch_stat_tabentry(relid) };
+    if entry.is_null() {
+        return None;
+    }
+
+    let entry = unsafe { &*entry };
+    Some(PgTableStats {
+        seq_scan: entry.numscans,
+        seq_tup_read: entry.tuples_returned,
+        n_tup_ins: entry.tuples_inserted,
+        n_tup_upd: entry.tuples_updated,
+        n_tup_del: entry.tuples_deleted,
+        n_tup_hot_upd: entry.tuples_hot_updated,
+        n_live_tup: entry.n_live_tuples,
+        n_dead_tup: entry.n_dead_tuples,
+        n_mod_since_analyze: entry.changes_since_analyze,
+        blocks_fetched: entry.blocks_fetched,
+        blocks_hit: entry.blocks_hit,
+        last_vacuum: timestamp(entry.vacuum_timestamp),
+        last_autovacuum: timestamp(entry.autovac_vacuum_timestamp),
+        last_analyze: timestamp(entry.analyze_timestamp),
+        last_autoanalyze: timestamp(entry.autovac_analyze_timestamp),
+        vacuum_count: entry.vacuum_count,
+        autovacuum_count: entry.autovac_vacuum_count,
+        analyze_count: entry.analyze_count,
+        autoanalyze_count: entry.autovac_analyze_count,
+    })
+}
+
+/// Discard this transaction's snapshot of the statistics, so the next `table_stats()` reads
+/// fresh ones
+pub fn clear_table_stats_snapshot() {
+    unsafe { pg_sys::pgstat_clear_snapshot() }
+}
+
+fn timestamp(ts: pg_sys::TimestampTz) -> Option<pg_sys::TimestampTz> {
+    if ts == 0 {
+        None
+    } else {
+        Some(ts)
+    }
+}
+
+/// A `RangeVar` naming the table `relid`, so vacuum looks it up and checks its permissions just
+/// like it does for the SQL command
+fn range_var(relid: pg_sys::Oid) -> *mut pg_sys::RangeVar {
+    unsafe {
+        let relname = pg_sys::get_rel_name(relid);
+        if relname.is_null() {
+            ereport(
+                PgLogLevel::ERROR,
+                PgSqlErrorCode::ERRCODE_UNDEFINED_TABLE,
+                &format!("relation with OID {} does not exist", relid),
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+        let schemaname = pg_sys::get_namespace_name(pg_sys::get_rel_namespace(relid));
+        pg_sys::makeRangeVar(schemaname, relname, -1)
+    }
+}
+
+#[cfg(feature = "pg10")]
+fn vacuum_stmt(relid: pg_sys::Oid, options: &PgVacuumOptions) -> *mut pg_sys::VacuumStmt {
+    let mut stmt = crate::PgNodeFactory::makeVacuumStmt();
+    stmt.options = vacuum_flags(options);
+    stmt.relation = range_var(relid);
+    stmt.into_pg()
+}
+
+#[cfg(feature = "pg11")]
+fn vacuum_stmt(relid: pg_sys::Oid, options: &PgVacuumOptions) -> *mut pg_sys::VacuumStmt {
+    let mut stmt = crate::PgNodeFactory::makeVacuumStmt();
+    stmt.options = vacuum_flags(options);
+    stmt.rels = relation_list(relid);
+    stmt.into_pg()
+}
+
+#[cfg(any(feature = "pg10", feature = "pg11"))]
+fn vacuum_flags(options: &PgVacuumOptions) -> i32 {
+    let choices = [
+        (options.full, pg_sys::VacuumOption_VACOPT_FULL),
+        (options.freeze, pg_sys::VacuumOption_VACOPT_FREEZE),
+        (options.analyze, pg_sys::VacuumOption_VACOPT_ANALYZE),
+        (options.verbose, pg_sys::VacuumOption_VACOPT_VERBOSE),
+        (
+            options.disable_page_skipping,
+            pg_sys::VacuumOption_VACOPT_DISABLE_PAGE_SKIPPING,
+        ),
+    ];
+    let mut flags = pg_sys::VacuumOption_VACOPT_VACUUM;
+    for (chosen, flag) in choices.iter() {
+        if *chosen {
+            flags |= *flag;
+        }
+    }
+    flags as i32
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+fn vacuum_stmt(relid: pg_sys::Oid, options: &PgVacuumOptions) -> *mut pg_sys::VacuumStmt {
+    let choices = [
+        (options.full, "full"),
+        (options.freeze, "freeze"),
+        (options.analyze, "analyze"),
+        (options.verbose, "verbose"),
+        (options.disable_page_skipping, "disable_page_skipping"),
+    ];
+    let mut defelems = crate::PgList::<pg_sys::DefElem>::new();
+    for (chosen, name) in choices.iter() {
+        if *chosen {
+            // an option without an argument is true
+            let name = PgMemoryContexts::CurrentMemoryContext.pstrdup(name);
+            defelems.push(unsafe { pg_sys::makeDefElem(name, std::ptr::null_mut(), -1) });
+        }
+    }
+
+    let mut stmt = crate::PgNodeFactory::makeVacuumStmt();
+    stmt.options = defelems.into_pg();
+    stmt.rels = relation_list(relid);
+    stmt.is_vacuumcmd = true;
+    stmt.into_pg()
+}
+
+#[cfg(feature = "pg10")]
+fn analyze_stmt(relid: pg_sys::Oid) -> *mut pg_sys::VacuumStmt {
+    let mut stmt = crate::PgNodeFactory::makeVacuumStmt();
+    stmt.options = pg_sys::VacuumOption_VACOPT_ANALYZE as i32;
+    stmt.relation = range_var(relid);
+    stmt.into_pg()
+}
+
+#[cfg(feature = "pg11")]
+fn analyze_stmt(relid: pg_sys::Oid) -> *mut pg_sys::VacuumStmt {
+    let mut stmt = crate::PgNodeFactory::makeVacuumStmt();
+    stmt.options = pg_sys::VacuumOption_VACOPT_ANALYZE as i32;
+    stmt.rels = relation_list(relid);
+    stmt.into_pg()
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+fn analyze_stmt(relid: pg_sys::Oid) -> *mut pg_sys::VacuumStmt {
+    let mut stmt = crate::PgNodeFactory::makeVacuumStmt();
+    stmt.rels = relation_list(relid);
+    stmt.is_vacuumcmd = false;
+    stmt.into_pg()
+}
+
+#[cfg(any(
+    feature = "pg11",
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15"
+))]
+fn relation_list(relid: pg_sys::Oid) -> *mut pg_sys::List {
+    let mut rels = crate::PgList::<pg_sys::VacuumRelation>::new();
+    rels.push(unsafe {
+        pg_sys::makeVacuumRelation(range_var(relid), pg_sys::InvalidOid, std::ptr::null_mut())
+    });
+    rels.into_pg()
+}
+
+#[cfg(any(feature = "pg10", feature = "pg11"))]
+unsafe fn exec_vacuum(stmt: *mut pg_sys::VacuumStmt, is_top_level: bool) {
+    pg_sys::ExecVacuum(stmt, is_top_level);
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+unsafe fn exec_vacuum(stmt: *mut pg_sys::VacuumStmt, is_top_level: bool) {
+    pg_sys::ExecVacuum(
+        pg_sys::make_parsestate(std::ptr::null_mut()),
+        stmt,
+        is_top_level,
+    );
+}
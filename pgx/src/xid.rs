@@ -4,10 +4,10 @@
 
 use crate::pg_sys;
 
-#[cfg(feature = "pg12")]
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
 #[inline]
 pub fn xid_to_64bit(xid: pg_sys::TransactionId) -> u64 {
-    let full_xid = unsafe { pg_sys::pg12_specific::ReadNextFullTransactionId() };
+    let full_xid = unsafe { pg_sys::ReadNextFullTransactionId() };
 
     let last_xid = full_xid.value as u32;
     let epoch = (full_xid.value >> 32) as u32;